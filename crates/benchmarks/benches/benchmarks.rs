@@ -1,4 +1,5 @@
 mod evaluate_benchmark;
+mod jit_benchmark;
 mod primitive;
 
 use criterion::{criterion_group, criterion_main};
@@ -6,6 +7,7 @@ use criterion::{criterion_group, criterion_main};
 criterion_group!(
     benches,
     evaluate_benchmark::evaluate_benchmark,
+    jit_benchmark::jit_benchmark,
     primitive::integer_benchmark::integer_benchmark
 );
 criterion_main!(benches);
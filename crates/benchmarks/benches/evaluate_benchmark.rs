@@ -24,6 +24,10 @@ pub fn evaluate_benchmark(c: &mut Criterion) {
             "optimized".to_owned(),
             prepare(boo_evaluation_optimized::new()),
         ),
+        ("cek".to_owned(), prepare(boo_evaluation_cek::new())),
+        ("secd".to_owned(), prepare(boo_evaluation_secd::new())),
+        ("vm".to_owned(), prepare(boo_vm::new())),
+        ("jit".to_owned(), prepare(boo_jit::new())),
     ];
 
     let mut group = c.benchmark_group("evaluate");
@@ -38,7 +42,7 @@ pub fn evaluate_benchmark(c: &mut Criterion) {
 }
 
 fn prepare(mut context: impl EvaluationContext + 'static) -> Box<dyn Evaluator> {
-    builtins::prepare(&mut context).unwrap();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
     Box::new(context.evaluator())
 }
 
@@ -0,0 +1,42 @@
+use criterion::{black_box, BenchmarkId, Criterion};
+
+use boo_core::builtins;
+use boo_core::evaluation::{EvaluationContext, Evaluator};
+
+/// Programs that only work with small integers, and so can be compiled.
+const PROGRAMS: &[(&str, &str)] = &[
+    (
+        "factorial",
+        "let rec factorial = fn n -> match n { 0 -> 1; _ -> n * factorial (n - 1) } in factorial 20",
+    ),
+    (
+        "sum",
+        "let rec sum = fn total n -> match n { 0 -> total; _ -> sum (total + n) (n - 1) } in sum 0 1_000",
+    ),
+    (
+        "fibonacci",
+        "let rec fibonacci = fn n -> match n < 2 { true -> n; _ -> fibonacci (n - 1) + fibonacci (n - 2) } in fibonacci 15",
+    ),
+];
+
+pub fn jit_benchmark(c: &mut Criterion) {
+    let optimized = prepare(boo_evaluation_optimized::new());
+    let jit = prepare(boo_jit::new());
+    let evaluators: [(&str, &dyn Evaluator); 2] = [("optimized", &optimized), ("jit", &jit)];
+
+    let mut group = c.benchmark_group("jit");
+    for (name, program) in PROGRAMS {
+        let expr = boo_parser::parse_expr(program).unwrap().to_core().unwrap();
+        for (evaluator_name, evaluator) in evaluators {
+            group.bench_with_input(BenchmarkId::new(evaluator_name, name), &expr, |b, expr| {
+                b.iter(|| evaluator.evaluate(black_box(expr.clone())).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn prepare<Context: EvaluationContext>(mut context: Context) -> Context::Eval {
+    builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+    context.evaluator()
+}
@@ -0,0 +1,81 @@
+//! The intermediate representation of closure-converted programs.
+//!
+//! Functions do not refer to any variables from their surroundings. Instead,
+//! each function is lifted out to the top level of the program, and takes an
+//! environment record holding the variables it captures, alongside its
+//! parameter. Where the original program constructed a function, the
+//! converted program constructs a closure, pairing the function with the
+//! current values of those variables.
+
+use boo_core::ast::Pattern;
+use boo_core::identifier::Identifier;
+use boo_core::native::Native;
+use boo_core::primitive::Primitive;
+
+/// Refers to a function within a [`Program`].
+pub type FunctionId = usize;
+
+/// A closure-converted program.
+#[derive(Debug, Clone)]
+pub struct Program {
+    /// Every function in the program, which can be referred to by its index.
+    pub functions: Vec<Function>,
+    /// The expression evaluated by the program.
+    pub body: Term,
+}
+
+/// A function lifted to the top level of the program.
+///
+/// The body may only refer to the variables in the environment, the
+/// parameter, and the variables that are free in the whole program.
+#[derive(Debug, Clone)]
+pub struct Function {
+    /// The names of the fields of the environment record, which are bound
+    /// when the function is called.
+    pub environment: Vec<Identifier>,
+    /// The name of the function parameter.
+    pub parameter: Identifier,
+    /// The body of the function.
+    pub body: Term,
+}
+
+/// An expression in a closure-converted program.
+#[derive(Debug, Clone)]
+pub enum Term {
+    Primitive(Primitive),
+    Native(Native),
+    Variable(Identifier),
+    /// Constructs a closure, capturing the variables named by the function's
+    /// environment, in the same order.
+    Closure {
+        function: FunctionId,
+        environment: Vec<Identifier>,
+    },
+    Apply {
+        function: Box<Term>,
+        argument: Box<Term>,
+    },
+    Assign {
+        name: Identifier,
+        value: Box<Term>,
+        inner: Box<Term>,
+    },
+    AssignRecursive {
+        bindings: Vec<(Identifier, Term)>,
+        inner: Box<Term>,
+    },
+    Match {
+        value: Box<Term>,
+        patterns: Vec<(Pattern, Term)>,
+    },
+    Sequence {
+        first: Box<Term>,
+        second: Box<Term>,
+    },
+    Tuple(Vec<Term>),
+    EmptyList,
+    Cons {
+        head: Box<Term>,
+        tail: Box<Term>,
+    },
+}
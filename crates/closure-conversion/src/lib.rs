@@ -0,0 +1,396 @@
+//! Closure conversion, which rewrites each function so that it takes its
+//! environment explicitly, as a record, rather than referring to variables
+//! bound outside it.
+//!
+//! This is a step towards compilation: once converted, every function can be
+//! compiled on its own, and the environment record says exactly what must be
+//! stored alongside it.
+
+pub mod ir;
+
+use std::collections::HashSet;
+
+use boo_core::ast::*;
+use boo_core::expr::Expr;
+use boo_core::identifier::Identifier;
+
+use crate::ir::{Program, Term};
+
+/// Converts a core expression to a closure-converted program.
+///
+/// Variables that are free in the whole expression, such as built-ins, are
+/// treated as globals, and so are never captured.
+pub fn convert(expr: &Expr) -> Program {
+    let mut conversion = Conversion { functions: vec![] };
+    let body = conversion.term(expr, &[]);
+    Program {
+        functions: conversion
+            .functions
+            .into_iter()
+            .map(|function| function.expect("A function was never converted."))
+            .collect(),
+        body,
+    }
+}
+
+struct Conversion {
+    /// Functions are reserved before they are converted, so that nested
+    /// functions come after the functions that contain them.
+    functions: Vec<Option<ir::Function>>,
+}
+
+impl Conversion {
+    /// Converts an expression, given the variables bound within the program
+    /// that are in scope, in the order they were bound.
+    fn term(&mut self, expr: &Expr, locals: &[Identifier]) -> Term {
+        match expr.expression() {
+            Expression::Primitive(primitive) => Term::Primitive(primitive.clone()),
+            Expression::Native(native) => Term::Native(native.clone()),
+            Expression::Identifier(name) => Term::Variable(name.clone()),
+            Expression::Function(function) => self.closure(function, locals),
+            Expression::Apply(Apply { function, argument }) => Term::Apply {
+                function: Box::new(self.term(function, locals)),
+                argument: Box::new(self.term(argument, locals)),
+            },
+            Expression::Assign(Assign { name, value, inner }) => Term::Assign {
+                name: name.clone(),
+                value: Box::new(self.term(value, locals)),
+                inner: Box::new(self.term(inner, &bind(locals, [name]))),
+            },
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                let locals = bind(locals, bindings.iter().map(|(name, _)| name));
+                Term::AssignRecursive {
+                    bindings: bindings
+                        .iter()
+                        .map(|(name, value)| (name.clone(), self.term(value, &locals)))
+                        .collect(),
+                    inner: Box::new(self.term(inner, &locals)),
+                }
+            }
+            Expression::Match(Match { value, patterns }) => Term::Match {
+                value: Box::new(self.term(value, locals)),
+                patterns: patterns
+                    .iter()
+                    .map(
+                        |PatternMatch {
+                             pattern, result, ..
+                         }| {
+                            let locals = bind(locals, pattern.variables());
+                            (pattern.clone(), self.term(result, &locals))
+                        },
+                    )
+                    .collect(),
+            },
+            // type annotations have no effect on evaluation
+            Expression::Typed(Typed { expression, .. }) => self.term(expression, locals),
+            Expression::Sequence(Sequence { first, second }) => Term::Sequence {
+                first: Box::new(self.term(first, locals)),
+                second: Box::new(self.term(second, locals)),
+            },
+            Expression::Tuple(Tuple { elements }) => Term::Tuple(
+                elements
+                    .iter()
+                    .map(|element| self.term(element, locals))
+                    .collect(),
+            ),
+            Expression::List(List::Empty) => Term::EmptyList,
+            Expression::List(List::Cons { head, tail }) => Term::Cons {
+                head: Box::new(self.term(head, locals)),
+                tail: Box::new(self.term(tail, locals)),
+            },
+        }
+    }
+
+    /// Lifts a function out, capturing the local variables it refers to.
+    fn closure(&mut self, function: &Function<Expr>, locals: &[Identifier]) -> Term {
+        let id = self.functions.len();
+        self.functions.push(None);
+
+        let free = free_variables(&function.body);
+        let environment = locals
+            .iter()
+            .filter(|local| **local != function.parameter)
+            // natives look up their arguments by name, which cannot be seen
+            // from the outside, so everything is captured
+            .filter(|local| free.as_ref().map_or(true, |free| free.contains(*local)))
+            .cloned()
+            .collect::<Vec<_>>();
+        let body = self.term(&function.body, &bind(&environment, [&function.parameter]));
+
+        self.functions[id] = Some(ir::Function {
+            environment: environment.clone(),
+            parameter: function.parameter.clone(),
+            body,
+        });
+        Term::Closure {
+            function: id,
+            environment,
+        }
+    }
+}
+
+/// Adds names to the local variables, removing any that they shadow.
+fn bind<'a>(
+    locals: &[Identifier],
+    names: impl IntoIterator<Item = &'a Identifier>,
+) -> Vec<Identifier> {
+    let mut locals = locals.to_vec();
+    for name in names {
+        locals.retain(|local| local != name);
+        locals.push(name.clone());
+    }
+    locals
+}
+
+/// The names referred to by an expression but not bound within it, or `None`
+/// if it contains a native, which could refer to anything.
+fn free_variables(expr: &Expr) -> Option<HashSet<Identifier>> {
+    let union = |exprs: &[&Expr]| {
+        exprs.iter().try_fold(HashSet::new(), |mut names, expr| {
+            names.extend(free_variables(expr)?);
+            Some(names)
+        })
+    };
+    match expr.expression() {
+        Expression::Primitive(_) | Expression::List(List::Empty) => Some(HashSet::new()),
+        Expression::Native(_) => None,
+        Expression::Identifier(name) => Some([name.clone()].into()),
+        Expression::Function(Function {
+            parameter, body, ..
+        }) => {
+            let mut names = free_variables(body)?;
+            names.remove(parameter);
+            Some(names)
+        }
+        Expression::Apply(Apply { function, argument }) => union(&[function, argument]),
+        Expression::Assign(Assign { name, value, inner }) => {
+            let mut names = free_variables(inner)?;
+            names.remove(name);
+            names.extend(free_variables(value)?);
+            Some(names)
+        }
+        Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+            let mut exprs = bindings.iter().map(|(_, value)| value).collect::<Vec<_>>();
+            exprs.push(inner);
+            let mut names = union(&exprs)?;
+            for (name, _) in bindings {
+                names.remove(name);
+            }
+            Some(names)
+        }
+        Expression::Match(Match { value, patterns }) => {
+            let mut names = free_variables(value)?;
+            for PatternMatch {
+                pattern, result, ..
+            } in patterns
+            {
+                let mut result_names = free_variables(result)?;
+                for variable in pattern.variables() {
+                    result_names.remove(variable);
+                }
+                names.extend(result_names);
+            }
+            Some(names)
+        }
+        Expression::Typed(Typed { expression, .. }) => free_variables(expression),
+        Expression::Sequence(Sequence { first, second }) => union(&[first, second]),
+        Expression::Tuple(Tuple { elements }) => union(&elements.iter().collect::<Vec<_>>()),
+        Expression::List(List::Cons { head, tail }) => union(&[head, tail]),
+    }
+}
+
+impl Program {
+    /// Converts the program back to a core expression.
+    ///
+    /// Each function is bound at the top level, taking its environment as a
+    /// tuple before its parameter, and each closure is constructed by applying
+    /// the function to a tuple of the captured variables. As the functions
+    /// only refer to their environment, their parameter, and globals, the
+    /// result can be evaluated like the original expression.
+    pub fn to_core(&self) -> Expr {
+        let mut used = HashSet::new();
+        for function in self.functions.iter() {
+            used.extend(function.environment.iter().cloned());
+            used.insert(function.parameter.clone());
+            names_in(&function.body, &mut used);
+        }
+        names_in(&self.body, &mut used);
+        let function_names = (0..self.functions.len())
+            .map(|id| unused_name(format!("function_{id}"), &used))
+            .collect::<Vec<_>>();
+        let environment_name = unused_name("environment".to_string(), &used);
+
+        // nested functions come later, so they are bound further out
+        self.functions.iter().zip(function_names.iter()).fold(
+            term_to_core(&self.body, &function_names),
+            |inner, (function, name)| {
+                let body = Expr::new(
+                    None,
+                    Expression::Match(Match {
+                        value: identifier(&environment_name),
+                        patterns: [PatternMatch {
+                            pattern: Pattern::Tuple(
+                                function
+                                    .environment
+                                    .iter()
+                                    .cloned()
+                                    .map(Pattern::Variable)
+                                    .collect(),
+                            ),
+                            pattern_span: None,
+                            result: term_to_core(&function.body, &function_names),
+                        }]
+                        .into(),
+                    }),
+                );
+                let value = lambda(
+                    environment_name.clone(),
+                    lambda(function.parameter.clone(), body),
+                );
+                Expr::new(
+                    None,
+                    Expression::Assign(Assign {
+                        name: name.clone(),
+                        value,
+                        inner,
+                    }),
+                )
+            },
+        )
+    }
+}
+
+fn term_to_core(term: &Term, function_names: &[Identifier]) -> Expr {
+    let to_core = |term: &Term| term_to_core(term, function_names);
+    let expression = match term {
+        Term::Primitive(primitive) => Expression::Primitive(primitive.clone()),
+        Term::Native(native) => Expression::Native(native.clone()),
+        Term::Variable(name) => Expression::Identifier(name.clone()),
+        Term::Closure {
+            function,
+            environment,
+        } => Expression::Apply(Apply {
+            function: identifier(&function_names[*function]),
+            argument: Expr::new(
+                None,
+                Expression::Tuple(Tuple {
+                    elements: environment.iter().map(identifier).collect(),
+                }),
+            ),
+        }),
+        Term::Apply { function, argument } => Expression::Apply(Apply {
+            function: to_core(function),
+            argument: to_core(argument),
+        }),
+        Term::Assign { name, value, inner } => Expression::Assign(Assign {
+            name: name.clone(),
+            value: to_core(value),
+            inner: to_core(inner),
+        }),
+        Term::AssignRecursive { bindings, inner } => Expression::AssignRecursive(AssignRecursive {
+            bindings: bindings
+                .iter()
+                .map(|(name, value)| (name.clone(), to_core(value)))
+                .collect(),
+            inner: to_core(inner),
+        }),
+        Term::Match { value, patterns } => Expression::Match(Match {
+            value: to_core(value),
+            patterns: patterns
+                .iter()
+                .map(|(pattern, result)| PatternMatch {
+                    pattern: pattern.clone(),
+                    pattern_span: None,
+                    result: to_core(result),
+                })
+                .collect(),
+        }),
+        Term::Sequence { first, second } => Expression::Sequence(Sequence {
+            first: to_core(first),
+            second: to_core(second),
+        }),
+        Term::Tuple(elements) => Expression::Tuple(Tuple {
+            elements: elements.iter().map(to_core).collect(),
+        }),
+        Term::EmptyList => Expression::List(List::Empty),
+        Term::Cons { head, tail } => Expression::List(List::Cons {
+            head: to_core(head),
+            tail: to_core(tail),
+        }),
+    };
+    Expr::new(None, expression)
+}
+
+/// Collects every name that appears in a term.
+fn names_in(term: &Term, names: &mut HashSet<Identifier>) {
+    match term {
+        Term::Primitive(_) | Term::Native(_) | Term::EmptyList => (),
+        Term::Variable(name) => {
+            names.insert(name.clone());
+        }
+        Term::Closure { environment, .. } => names.extend(environment.iter().cloned()),
+        Term::Apply { function, argument } => {
+            names_in(function, names);
+            names_in(argument, names);
+        }
+        Term::Assign { name, value, inner } => {
+            names.insert(name.clone());
+            names_in(value, names);
+            names_in(inner, names);
+        }
+        Term::AssignRecursive { bindings, inner } => {
+            for (name, value) in bindings {
+                names.insert(name.clone());
+                names_in(value, names);
+            }
+            names_in(inner, names);
+        }
+        Term::Match { value, patterns } => {
+            names_in(value, names);
+            for (pattern, result) in patterns {
+                names.extend(pattern.variables().into_iter().cloned());
+                names_in(result, names);
+            }
+        }
+        Term::Sequence { first, second } => {
+            names_in(first, names);
+            names_in(second, names);
+        }
+        Term::Tuple(elements) => {
+            for element in elements {
+                names_in(element, names);
+            }
+        }
+        Term::Cons { head, tail } => {
+            names_in(head, names);
+            names_in(tail, names);
+        }
+    }
+}
+
+/// Chooses a name, based on the given one, which is not used in the program.
+fn unused_name(mut name: String, used: &HashSet<Identifier>) -> Identifier {
+    loop {
+        let identifier = Identifier::name_from_string(name.clone()).unwrap();
+        if !used.contains(&identifier) {
+            return identifier;
+        }
+        name.push('_');
+    }
+}
+
+fn identifier(name: &Identifier) -> Expr {
+    Expr::new(None, Expression::Identifier(name.clone()))
+}
+
+fn lambda(parameter: Identifier, body: Expr) -> Expr {
+    Expr::new(
+        None,
+        Expression::Function(Function {
+            parameter,
+            parameter_type: None,
+            parameter_type_span: None,
+            body,
+        }),
+    )
+}
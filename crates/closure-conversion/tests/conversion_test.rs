@@ -0,0 +1,120 @@
+use proptest::prelude::*;
+
+use boo_closure_conversion::ir::Term;
+use boo_core::builtins;
+use boo_core::error::Result;
+use boo_core::evaluation::*;
+use boo_core::identifier::Identifier;
+use boo_test_helpers::proptest::*;
+
+#[test]
+fn test_converting_does_not_change_the_result() {
+    let evaluator = {
+        let mut context = boo_evaluation_reduction::new();
+        builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+        context.evaluator()
+    };
+
+    check(&boo_generator::arbitrary(), |expr| {
+        let core_expr = expr.clone().to_core()?;
+        let converted = boo_closure_conversion::convert(&core_expr).to_core();
+        let expected = evaluator.evaluate(core_expr);
+        let actual = evaluator.evaluate(converted.clone());
+
+        match (expected, actual) {
+            (Ok(expected), Ok(actual)) => prop_assert!(
+                same_result(&expected, &actual),
+                "results differ\n  left:      `{}`,\n  right:     `{}`\n  input:     {}\n  converted: {}\n",
+                expected,
+                actual,
+                expr,
+                converted
+            ),
+            (expected, actual) => prop_assert_eq!(expected.map(|_| ()), actual.map(|_| ())),
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn test_functions_capture_the_local_variables_they_refer_to() -> Result<()> {
+    let program = convert("fn x -> fn y -> fn z -> x + z")?;
+
+    assert_eq!(environments(&program), vec![vec![], vec!["x"], vec!["x"]]);
+    Ok(())
+}
+
+#[test]
+fn test_functions_do_not_capture_shadowed_variables() -> Result<()> {
+    let program = convert("fn x -> fn x -> x")?;
+
+    assert_eq!(environments(&program), vec![Vec::<String>::new(); 2]);
+    Ok(())
+}
+
+#[test]
+fn test_recursive_functions_capture_themselves() -> Result<()> {
+    let program = convert("let rec f = fn n -> f n in f")?;
+
+    assert_eq!(environments(&program), vec![vec!["f"]]);
+    Ok(())
+}
+
+#[test]
+fn test_closures_capture_their_environment_where_they_are_constructed() -> Result<()> {
+    let program = convert("let a = 1 in let f = fn x -> a in f")?;
+
+    let Term::Assign { inner, .. } = &program.body else {
+        panic!("Expected an assignment, but got {:?}", program.body)
+    };
+    let Term::Assign { value, .. } = inner.as_ref() else {
+        panic!("Expected an assignment, but got {:?}", inner)
+    };
+    match value.as_ref() {
+        Term::Closure {
+            function,
+            environment,
+        } => {
+            assert_eq!(*function, 0);
+            assert_eq!(environment, &[Identifier::name_from_str("a").unwrap()]);
+        }
+        value => panic!("Expected a closure, but got {:?}", value),
+    }
+    Ok(())
+}
+
+fn convert(program: &str) -> Result<boo_closure_conversion::ir::Program> {
+    let expr = boo_parser::parse_expr(program)?.to_core()?;
+    Ok(boo_closure_conversion::convert(&expr))
+}
+
+fn environments(program: &boo_closure_conversion::ir::Program) -> Vec<Vec<String>> {
+    program
+        .functions
+        .iter()
+        .map(|function| {
+            function
+                .environment
+                .iter()
+                .map(|name| name.to_string())
+                .collect()
+        })
+        .collect()
+}
+
+/// Compares two results, considering any two functions to be the same, as a
+/// converted function takes its environment as an extra argument.
+fn same_result(expected: &Evaluated, actual: &Evaluated) -> bool {
+    match (expected, actual) {
+        (Evaluated::Function(_), Evaluated::Function(_)) => true,
+        (Evaluated::Tuple(expected), Evaluated::Tuple(actual))
+        | (Evaluated::List(expected), Evaluated::List(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual)
+                    .all(|(expected, actual)| same_result(expected, actual))
+        }
+        (expected, actual) => expected == actual,
+    }
+}
@@ -0,0 +1,133 @@
+//! Compiles Boo programs to standalone WebAssembly modules.
+//!
+//! The module exports a `main` function, which takes no arguments, evaluates
+//! the program, and returns its result as an `i64`. Booleans are returned as
+//! 0 or 1. The program must therefore evaluate to an integer or a boolean.
+//!
+//! Only integers, booleans and functions are supported; anything else is
+//! reported as [`UnsupportedByCompiler`][boo_core::error::Error::UnsupportedByCompiler].
+//! Unlike in the evaluators, integers are 64 bits wide, and wrap on overflow.
+//! Runtime errors, such as division by zero, trap.
+//!
+//! Evaluation is lazy, as in the evaluators, but tail calls are not
+//! eliminated, so deeply-recursive programs may exhaust the stack.
+
+mod lowering;
+mod runtime;
+
+use wasm_encoder::{
+    CodeSection, ConstExpr, ElementSection, Elements, ExportKind, ExportSection, FunctionSection,
+    GlobalSection, GlobalType, MemorySection, MemoryType, Module, RefType, TableSection, TableType,
+    TypeSection, ValType,
+};
+
+use boo_core::ast::*;
+use boo_core::builtins::{self, IoCapabilities};
+use boo_core::error::Result;
+use boo_core::expr::Expr;
+
+/// Compiles a type-checked program to a WebAssembly module.
+///
+/// Any built-ins used by the program are compiled along with it.
+pub fn compile(expr: Expr) -> Result<Vec<u8>> {
+    let program = with_builtins(expr)?;
+    let lowered = lowering::lower(&program)?;
+
+    let runtime_functions = runtime::functions();
+    let first_generated = runtime_functions.len() as u32;
+    let generated_count = lowered.functions.len() as u32;
+    let main = first_generated + generated_count;
+
+    let mut types = TypeSection::new();
+    for (parameters, results) in runtime::types() {
+        types.function(parameters, results);
+    }
+
+    let mut functions = FunctionSection::new();
+    let mut code = CodeSection::new();
+    let main_function = (
+        runtime::MAIN_TYPE,
+        runtime::main(first_generated + lowered.program),
+    );
+    for (function_type, function) in runtime_functions
+        .iter()
+        .chain(lowered.functions.iter())
+        .chain([main_function].iter())
+    {
+        functions.function(*function_type);
+        code.function(function);
+    }
+
+    let mut tables = TableSection::new();
+    tables.table(TableType {
+        element_type: RefType::FUNCREF,
+        minimum: generated_count,
+        maximum: Some(generated_count),
+    });
+
+    let mut memories = MemorySection::new();
+    memories.memory(MemoryType {
+        minimum: 1,
+        maximum: None,
+        memory64: false,
+        shared: false,
+    });
+
+    let mut globals = GlobalSection::new();
+    globals.global(
+        GlobalType {
+            val_type: ValType::I32,
+            mutable: true,
+        },
+        &ConstExpr::i32_const(runtime::HEAP_START),
+    );
+
+    let mut exports = ExportSection::new();
+    exports.export("main", ExportKind::Func, main);
+    exports.export("memory", ExportKind::Memory, 0);
+
+    let table_entries = (first_generated..main).collect::<Vec<_>>();
+    let mut elements = ElementSection::new();
+    elements.active(
+        None,
+        &ConstExpr::i32_const(0),
+        Elements::Functions(&table_entries),
+    );
+
+    let mut module = Module::new();
+    module
+        .section(&types)
+        .section(&functions)
+        .section(&tables)
+        .section(&memories)
+        .section(&globals)
+        .section(&exports)
+        .section(&elements)
+        .section(&code);
+    Ok(module.finish())
+}
+
+/// Assigns each built-in referred to by the program, including those referred
+/// to by other built-ins.
+fn with_builtins(expr: Expr) -> Result<Expr> {
+    let mut program = expr;
+    loop {
+        let free = lowering::free_variables(&program)?;
+        let used = builtins::implementations(IoCapabilities::default())
+            .filter(|(name, _)| free.contains(name))
+            .collect::<Vec<_>>();
+        if used.is_empty() {
+            return Ok(program);
+        }
+        for (name, implementation) in used {
+            program = Expr::new(
+                program.span(),
+                Expression::Assign(Assign {
+                    name: name.clone(),
+                    value: implementation,
+                    inner: program,
+                }),
+            );
+        }
+    }
+}
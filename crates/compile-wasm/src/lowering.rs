@@ -0,0 +1,533 @@
+//! Lowers core expressions to WebAssembly functions.
+//!
+//! Each function literal becomes a closure, and each argument or assigned
+//! value that is not already a value becomes a thunk. The code of both is
+//! lifted out into a separate WebAssembly function, which receives its free
+//! variables in an environment.
+
+use std::collections::HashMap;
+
+use wasm_encoder::{BlockType, Instruction, ValType};
+
+use boo_core::ast::*;
+use boo_core::error::{Error, Result};
+use boo_core::expr::Expr;
+use boo_core::identifier::Identifier;
+use boo_core::native::Native;
+use boo_core::primitive::Primitive;
+use boo_core::span::Span;
+
+use crate::runtime::{self, field};
+
+/// The generated code, in function table order.
+pub struct Lowered {
+    /// The type and body of each function.
+    pub functions: Vec<(u32, wasm_encoder::Function)>,
+    /// The index of the function evaluating the whole program, which takes an
+    /// empty environment.
+    pub program: u32,
+}
+
+/// Lowers a program with no free variables.
+pub fn lower(expr: &Expr) -> Result<Lowered> {
+    let mut lowering = Lowering { functions: vec![] };
+    let program = lowering.lift(None, &[], expr)?;
+    Ok(Lowered {
+        functions: lowering
+            .functions
+            .into_iter()
+            .map(|function| function.expect("A function was never generated."))
+            .collect(),
+        program,
+    })
+}
+
+/// Where a variable can be found in the current function.
+#[derive(Debug, Clone, Copy)]
+enum Location {
+    /// In a local.
+    Local(u32),
+    /// In the environment, which is always in local 0.
+    Captured(u32),
+}
+
+type Scope = HashMap<Identifier, Location>;
+
+struct Lowering {
+    /// Functions are reserved before they are generated, so that nested
+    /// functions come after the functions that contain them.
+    functions: Vec<Option<(u32, wasm_encoder::Function)>>,
+}
+
+/// The body of a function, as it is generated.
+struct Body {
+    parameters: u32,
+    locals: Vec<ValType>,
+    instructions: Vec<Instruction<'static>>,
+}
+
+impl Body {
+    fn local(&mut self, value_type: ValType) -> u32 {
+        self.locals.push(value_type);
+        self.parameters + self.locals.len() as u32 - 1
+    }
+
+    fn emit(&mut self, instruction: Instruction<'static>) {
+        self.instructions.push(instruction);
+    }
+}
+
+impl Lowering {
+    /// Generates a separate function for a closure or thunk, returning its
+    /// index in the function table.
+    ///
+    /// The closure parameter, if there is one, is in local 1, and the free
+    /// variables are in the environment, in the order given.
+    fn lift(
+        &mut self,
+        parameter: Option<&Identifier>,
+        free: &[Identifier],
+        expr: &Expr,
+    ) -> Result<u32> {
+        let index = self.functions.len();
+        self.functions.push(None);
+
+        let mut scope: Scope = free
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.clone(), Location::Captured(i as u32)))
+            .collect();
+        let function_type = match parameter {
+            Some(parameter) => {
+                scope.insert(parameter.clone(), Location::Local(1));
+                runtime::CLOSURE_TYPE
+            }
+            None => runtime::THUNK_TYPE,
+        };
+        let mut body = Body {
+            parameters: if parameter.is_some() { 2 } else { 1 },
+            locals: vec![],
+            instructions: vec![],
+        };
+        self.evaluate(expr, &scope, &mut body)?;
+
+        let mut function =
+            wasm_encoder::Function::new(body.locals.into_iter().map(|local| (1, local)));
+        for instruction in body.instructions {
+            function.instruction(&instruction);
+        }
+        function.instruction(&Instruction::End);
+        self.functions[index] = Some((function_type, function));
+        Ok(index as u32)
+    }
+
+    /// Generates code that evaluates the expression, leaving a pointer to the
+    /// value on the stack.
+    fn evaluate(&mut self, expr: &Expr, scope: &Scope, body: &mut Body) -> Result<()> {
+        match expr.expression() {
+            Expression::Primitive(primitive) => primitive_value(primitive, expr.span(), body),
+            Expression::Native(native) => {
+                let operation = operation(native, expr.span())?;
+                operation.emit(scope, body)
+            }
+            Expression::Identifier(name) => {
+                load(name, expr.span(), scope, body)?;
+                body.emit(Instruction::Call(runtime::FORCE));
+                Ok(())
+            }
+            Expression::Function(Function {
+                parameter,
+                body: function_body,
+                ..
+            }) => {
+                let free = free_variables(expr)?;
+                let index = self.lift(Some(parameter), &free, function_body)?;
+                body.emit(Instruction::I32Const(index as i32));
+                environment(&free, scope, body)?;
+                body.emit(Instruction::Call(runtime::MAKE_CLOSURE));
+                Ok(())
+            }
+            Expression::Apply(Apply { function, argument }) => {
+                self.evaluate(function, scope, body)?;
+                self.suspend(argument, scope, body)?;
+                body.emit(Instruction::Call(runtime::APPLY));
+                Ok(())
+            }
+            Expression::Assign(Assign { name, value, inner }) => {
+                self.suspend(value, scope, body)?;
+                let local = body.local(ValType::I32);
+                body.emit(Instruction::LocalSet(local));
+                let mut inner_scope = scope.clone();
+                inner_scope.insert(name.clone(), Location::Local(local));
+                self.evaluate(inner, &inner_scope, body)
+            }
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                let mut inner_scope = scope.clone();
+                let locals = bindings
+                    .iter()
+                    .map(|(name, _)| {
+                        let local = body.local(ValType::I32);
+                        inner_scope.insert(name.clone(), Location::Local(local));
+                        local
+                    })
+                    .collect::<Vec<_>>();
+                // allocate every thunk before filling in the environments, so
+                // that each can refer to the others
+                let mut environments = Vec::with_capacity(bindings.len());
+                for ((_, value), local) in bindings.iter().zip(locals.iter()) {
+                    let free = free_variables(value)?;
+                    let index = self.lift(None, &free, value)?;
+                    body.emit(Instruction::I32Const(index as i32));
+                    body.emit(Instruction::I32Const(0));
+                    body.emit(Instruction::Call(runtime::MAKE_THUNK));
+                    body.emit(Instruction::LocalSet(*local));
+                    environments.push(free);
+                }
+                for (free, local) in environments.iter().zip(locals.iter()) {
+                    body.emit(Instruction::LocalGet(*local));
+                    environment(free, &inner_scope, body)?;
+                    body.emit(Instruction::I32Store(field(8)));
+                }
+                self.evaluate(inner, &inner_scope, body)
+            }
+            Expression::Match(Match { value, patterns }) => {
+                self.suspend(value, scope, body)?;
+                let local = body.local(ValType::I32);
+                body.emit(Instruction::LocalSet(local));
+                body.emit(Instruction::Block(BlockType::Result(ValType::I32)));
+                for PatternMatch {
+                    pattern,
+                    pattern_span,
+                    result,
+                } in patterns
+                {
+                    body.emit(Instruction::Block(BlockType::Empty));
+                    let mut result_scope = scope.clone();
+                    match pattern {
+                        Pattern::Anything => {}
+                        Pattern::Variable(name) => {
+                            result_scope.insert(name.clone(), Location::Local(local));
+                        }
+                        Pattern::Primitive(primitive) => {
+                            let (_, expected) = primitive_bits(primitive, *pattern_span)?;
+                            body.emit(Instruction::LocalGet(local));
+                            body.emit(Instruction::Call(runtime::VALUE));
+                            body.emit(Instruction::I64Const(expected));
+                            body.emit(Instruction::I64Ne);
+                            body.emit(Instruction::BrIf(0));
+                        }
+                        Pattern::Tuple(_)
+                        | Pattern::EmptyList
+                        | Pattern::Cons { .. }
+                        | Pattern::Some(_)
+                        | Pattern::None
+                        | Pattern::Ok(_)
+                        | Pattern::Err(_) => {
+                            return Err(unsupported(
+                                "patterns on tuples, lists, options or results",
+                                *pattern_span,
+                            ));
+                        }
+                    }
+                    self.evaluate(result, &result_scope, body)?;
+                    body.emit(Instruction::Br(1));
+                    body.emit(Instruction::End);
+                }
+                // no pattern matched
+                body.emit(Instruction::Unreachable);
+                body.emit(Instruction::End);
+                Ok(())
+            }
+            Expression::Typed(Typed { expression, .. }) => self.evaluate(expression, scope, body),
+            Expression::Sequence(Sequence { first, second }) => {
+                self.evaluate(first, scope, body)?;
+                body.emit(Instruction::Drop);
+                self.evaluate(second, scope, body)
+            }
+            Expression::Tuple(_) => Err(unsupported("tuples", expr.span())),
+            Expression::List(_) => Err(unsupported("lists", expr.span())),
+        }
+    }
+
+    /// Generates code that suspends the expression, leaving a pointer to a
+    /// thunk or value on the stack.
+    ///
+    /// Variables are shared rather than suspended again, and values are
+    /// constructed immediately.
+    fn suspend(&mut self, expr: &Expr, scope: &Scope, body: &mut Body) -> Result<()> {
+        match expr.expression() {
+            Expression::Identifier(name) => load(name, expr.span(), scope, body),
+            Expression::Primitive(_) | Expression::Function(_) => self.evaluate(expr, scope, body),
+            Expression::Typed(Typed { expression, .. }) => self.suspend(expression, scope, body),
+            _ => {
+                let free = free_variables(expr)?;
+                let index = self.lift(None, &free, expr)?;
+                body.emit(Instruction::I32Const(index as i32));
+                environment(&free, scope, body)?;
+                body.emit(Instruction::Call(runtime::MAKE_THUNK));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Generates code that pushes a pointer to the variable, without evaluating
+/// it.
+fn load(name: &Identifier, span: Option<Span>, scope: &Scope, body: &mut Body) -> Result<()> {
+    match scope.get(name) {
+        Some(Location::Local(local)) => {
+            body.emit(Instruction::LocalGet(*local));
+        }
+        Some(Location::Captured(i)) => {
+            body.emit(Instruction::LocalGet(0));
+            body.emit(Instruction::I32Load(field(4 * *i as u64)));
+        }
+        None => {
+            return Err(Error::unknown_variable(span, name, scope.keys()));
+        }
+    }
+    Ok(())
+}
+
+/// Generates code that allocates an environment containing the given
+/// variables, or pushes 0 if there are none.
+fn environment(free: &[Identifier], scope: &Scope, body: &mut Body) -> Result<()> {
+    if free.is_empty() {
+        body.emit(Instruction::I32Const(0));
+        return Ok(());
+    }
+    let local = body.local(ValType::I32);
+    body.emit(Instruction::I32Const(4 * free.len() as i32));
+    body.emit(Instruction::Call(runtime::ALLOCATE));
+    body.emit(Instruction::LocalSet(local));
+    for (i, name) in free.iter().enumerate() {
+        body.emit(Instruction::LocalGet(local));
+        load(name, None, scope, body)?;
+        body.emit(Instruction::I32Store(field(4 * i as u64)));
+    }
+    body.emit(Instruction::LocalGet(local));
+    Ok(())
+}
+
+fn primitive_value(primitive: &Primitive, span: Option<Span>, body: &mut Body) -> Result<()> {
+    let (tag, bits) = primitive_bits(primitive, span)?;
+    body.emit(Instruction::I32Const(tag));
+    body.emit(Instruction::I64Const(bits));
+    body.emit(Instruction::Call(runtime::PRIMITIVE));
+    Ok(())
+}
+
+/// The tag and representation of a primitive value.
+fn primitive_bits(primitive: &Primitive, span: Option<Span>) -> Result<(i32, i64)> {
+    match primitive {
+        Primitive::Integer(integer) => i64::try_from(integer)
+            .map(|value| (runtime::INTEGER, value))
+            .map_err(|_| unsupported("integers larger than 64 bits", span)),
+        Primitive::Boolean(value) => Ok((runtime::BOOLEAN, (*value).into())),
+        Primitive::Float(_) => Err(unsupported("floats", span)),
+        Primitive::Char(_) => Err(unsupported("characters", span)),
+        Primitive::String(_) => Err(unsupported("strings", span)),
+    }
+}
+
+/// A native binary operation on the variables `left` and `right`.
+struct Operation {
+    result_tag: i32,
+    instructions: &'static [Instruction<'static>],
+    /// Whether to trap if the right operand is negative.
+    non_negative_right: bool,
+}
+
+impl Operation {
+    fn emit(&self, scope: &Scope, body: &mut Body) -> Result<()> {
+        let left = Identifier::name_from_str("left").unwrap();
+        let right = Identifier::name_from_str("right").unwrap();
+        let right_value = body.local(ValType::I64);
+        load(&right, None, scope, body)?;
+        body.emit(Instruction::Call(runtime::VALUE));
+        body.emit(Instruction::LocalSet(right_value));
+        if self.non_negative_right {
+            body.emit(Instruction::LocalGet(right_value));
+            body.emit(Instruction::I64Const(0));
+            body.emit(Instruction::I64LtS);
+            body.emit(Instruction::If(BlockType::Empty));
+            body.emit(Instruction::Unreachable);
+            body.emit(Instruction::End);
+        }
+        body.emit(Instruction::I32Const(self.result_tag));
+        load(&left, None, scope, body)?;
+        body.emit(Instruction::Call(runtime::VALUE));
+        body.emit(Instruction::LocalGet(right_value));
+        for instruction in self.instructions {
+            body.emit(instruction.clone());
+        }
+        body.emit(Instruction::Call(runtime::PRIMITIVE));
+        Ok(())
+    }
+}
+
+/// Looks up the operation implementing a native function.
+///
+/// Only operations on integers and booleans are supported.
+fn operation(native: &Native, span: Option<Span>) -> Result<Operation> {
+    let arithmetic = |instructions| Operation {
+        result_tag: runtime::INTEGER,
+        instructions,
+        non_negative_right: false,
+    };
+    let comparison = |instructions| Operation {
+        result_tag: runtime::BOOLEAN,
+        instructions,
+        non_negative_right: false,
+    };
+    let shift = |instructions| Operation {
+        result_tag: runtime::INTEGER,
+        instructions,
+        non_negative_right: true,
+    };
+    match native.unique_name.to_string().as_str() {
+        "+" => Ok(arithmetic(&[Instruction::I64Add])),
+        "-" => Ok(arithmetic(&[Instruction::I64Sub])),
+        "*" => Ok(arithmetic(&[Instruction::I64Mul])),
+        "/" => Ok(arithmetic(&[Instruction::I64DivS])),
+        "%" => Ok(arithmetic(&[Instruction::I64RemS])),
+        "&" => Ok(arithmetic(&[Instruction::I64And])),
+        "|" => Ok(arithmetic(&[Instruction::I64Or])),
+        "xor" => Ok(arithmetic(&[Instruction::I64Xor])),
+        "<<" => Ok(shift(&[Instruction::I64Shl])),
+        ">>" => Ok(shift(&[Instruction::I64ShrS])),
+        "==" => Ok(comparison(&[
+            Instruction::I64Eq,
+            Instruction::I64ExtendI32U,
+        ])),
+        "!=" => Ok(comparison(&[
+            Instruction::I64Ne,
+            Instruction::I64ExtendI32U,
+        ])),
+        "<" => Ok(comparison(&[
+            Instruction::I64LtS,
+            Instruction::I64ExtendI32U,
+        ])),
+        "<=" => Ok(comparison(&[
+            Instruction::I64LeS,
+            Instruction::I64ExtendI32U,
+        ])),
+        ">" => Ok(comparison(&[
+            Instruction::I64GtS,
+            Instruction::I64ExtendI32U,
+        ])),
+        ">=" => Ok(comparison(&[
+            Instruction::I64GeS,
+            Instruction::I64ExtendI32U,
+        ])),
+        name => Err(unsupported(&format!("the built-in {name:?}"), span)),
+    }
+}
+
+fn unsupported(feature: &str, span: Option<Span>) -> Error {
+    Error::UnsupportedByCompiler {
+        span,
+        feature: feature.to_string(),
+    }
+}
+
+/// Computes the free variables of an expression, in the order in which they
+/// first appear.
+///
+/// Native operations refer to `left` and `right`.
+pub fn free_variables(expr: &Expr) -> Result<Vec<Identifier>> {
+    let mut free = vec![];
+    collect_free_variables(expr, &mut vec![], &mut free)?;
+    Ok(free)
+}
+
+fn collect_free_variables(
+    expr: &Expr,
+    bound: &mut Vec<Identifier>,
+    free: &mut Vec<Identifier>,
+) -> Result<()> {
+    let mut add = |name: &Identifier, bound: &[Identifier]| {
+        if !bound.contains(name) && !free.contains(name) {
+            free.push(name.clone());
+        }
+    };
+    match expr.expression() {
+        Expression::Primitive(_) => Ok(()),
+        Expression::Native(native) => {
+            operation(native, expr.span())?;
+            add(&Identifier::name_from_str("left").unwrap(), bound);
+            add(&Identifier::name_from_str("right").unwrap(), bound);
+            Ok(())
+        }
+        Expression::Identifier(name) => {
+            add(name, bound);
+            Ok(())
+        }
+        Expression::Function(Function {
+            parameter, body, ..
+        }) => within(bound, [parameter], |bound| {
+            collect_free_variables(body, bound, free)
+        }),
+        Expression::Apply(Apply { function, argument }) => {
+            collect_free_variables(function, bound, free)?;
+            collect_free_variables(argument, bound, free)
+        }
+        Expression::Assign(Assign { name, value, inner }) => {
+            collect_free_variables(value, bound, free)?;
+            within(bound, [name], |bound| {
+                collect_free_variables(inner, bound, free)
+            })
+        }
+        Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+            within(bound, bindings.iter().map(|(name, _)| name), |bound| {
+                for (_, value) in bindings {
+                    collect_free_variables(value, bound, free)?;
+                }
+                collect_free_variables(inner, bound, free)
+            })
+        }
+        Expression::Match(Match { value, patterns }) => {
+            collect_free_variables(value, bound, free)?;
+            for PatternMatch {
+                pattern, result, ..
+            } in patterns
+            {
+                within(bound, pattern.variables(), |bound| {
+                    collect_free_variables(result, bound, free)
+                })?;
+            }
+            Ok(())
+        }
+        Expression::Typed(Typed { expression, .. }) => {
+            collect_free_variables(expression, bound, free)
+        }
+        Expression::Sequence(Sequence { first, second }) => {
+            collect_free_variables(first, bound, free)?;
+            collect_free_variables(second, bound, free)
+        }
+        Expression::Tuple(Tuple { elements }) => {
+            for element in elements {
+                collect_free_variables(element, bound, free)?;
+            }
+            Ok(())
+        }
+        Expression::List(List::Empty) => Ok(()),
+        Expression::List(List::Cons { head, tail }) => {
+            collect_free_variables(head, bound, free)?;
+            collect_free_variables(tail, bound, free)
+        }
+    }
+}
+
+/// Runs the given function with extra variables bound.
+fn within<'a, T>(
+    bound: &mut Vec<Identifier>,
+    names: impl IntoIterator<Item = &'a Identifier>,
+    f: impl FnOnce(&mut Vec<Identifier>) -> T,
+) -> T {
+    let previous = bound.len();
+    bound.extend(names.into_iter().cloned());
+    let result = f(bound);
+    bound.truncate(previous);
+    result
+}
@@ -0,0 +1,296 @@
+//! The runtime support included in every compiled module.
+//!
+//! Every value is a pointer to an object on the heap, which is never freed.
+//! Each object is 16 bytes, starting with a tag:
+//!
+//! | tag         | offset 4      | offset 8        | offset 12        |
+//! |-------------|---------------|-----------------|------------------|
+//! | `INTEGER`   |               | value (`i64`)   |                  |
+//! | `BOOLEAN`   |               | 0 or 1 (`i64`)  |                  |
+//! | `CLOSURE`   | code (`i32`)  | environment     |                  |
+//! | `THUNK`     | code (`i32`)  | environment     | value, once set  |
+//!
+//! Code is referred to by its index in the function table, and environments
+//! are arrays of pointers to the captured values.
+
+use wasm_encoder::{BlockType, Function, Instruction, MemArg, ValType};
+
+pub const INTEGER: i32 = 0;
+pub const BOOLEAN: i32 = 1;
+pub const CLOSURE: i32 = 2;
+pub const THUNK: i32 = 3;
+
+/// The size of every object, in bytes.
+const OBJECT_SIZE: i32 = 16;
+
+/// The heap starts after a null object, so that no object is at address 0.
+pub const HEAP_START: i32 = OBJECT_SIZE;
+
+/// The heap pointer, which is the only global.
+pub const HEAP: u32 = 0;
+
+/// The code of closures, which takes the environment and the argument.
+pub const CLOSURE_TYPE: u32 = 0;
+/// The code of thunks, which takes the environment.
+pub const THUNK_TYPE: u32 = 1;
+const PRIMITIVE_TYPE: u32 = 2;
+const VALUE_TYPE: u32 = 3;
+pub const MAIN_TYPE: u32 = 4;
+
+/// The types of all functions, in index order.
+pub fn types() -> Vec<(Vec<ValType>, Vec<ValType>)> {
+    vec![
+        (vec![ValType::I32, ValType::I32], vec![ValType::I32]),
+        (vec![ValType::I32], vec![ValType::I32]),
+        (vec![ValType::I32, ValType::I64], vec![ValType::I32]),
+        (vec![ValType::I32], vec![ValType::I64]),
+        (vec![], vec![ValType::I64]),
+    ]
+}
+
+/// `(size: i32) -> i32`: allocates memory, growing it if necessary.
+pub const ALLOCATE: u32 = 0;
+/// `(tag: i32, value: i64) -> i32`: allocates an integer or boolean.
+pub const PRIMITIVE: u32 = 1;
+/// `(code: i32, environment: i32) -> i32`: allocates a closure.
+pub const MAKE_CLOSURE: u32 = 2;
+/// `(code: i32, environment: i32) -> i32`: allocates a thunk.
+pub const MAKE_THUNK: u32 = 3;
+/// `(object: i32) -> i32`: evaluates a thunk, if necessary, and returns the
+/// value. The value is stored in the thunk so it is only evaluated once.
+pub const FORCE: u32 = 4;
+/// `(function: i32, argument: i32) -> i32`: applies a closure to an argument.
+pub const APPLY: u32 = 5;
+/// `(object: i32) -> i64`: evaluates an integer or boolean, and reads it.
+pub const VALUE: u32 = 6;
+
+/// The runtime functions, along with their types, in index order.
+pub fn functions() -> Vec<(u32, Function)> {
+    vec![
+        (THUNK_TYPE, allocate()),
+        (PRIMITIVE_TYPE, primitive()),
+        (CLOSURE_TYPE, object_with_code(CLOSURE)),
+        (CLOSURE_TYPE, object_with_code(THUNK)),
+        (THUNK_TYPE, force()),
+        (CLOSURE_TYPE, apply()),
+        (VALUE_TYPE, value()),
+    ]
+}
+
+/// Accesses a field of an object, or an element of an environment.
+pub fn field(offset: u64) -> MemArg {
+    MemArg {
+        offset,
+        align: 2,
+        memory_index: 0,
+    }
+}
+
+fn wide_field(offset: u64) -> MemArg {
+    MemArg {
+        offset,
+        align: 3,
+        memory_index: 0,
+    }
+}
+
+fn function(locals: Vec<(u32, ValType)>, instructions: &[Instruction]) -> Function {
+    let mut function = Function::new(locals);
+    for instruction in instructions {
+        function.instruction(instruction);
+    }
+    function.instruction(&Instruction::End);
+    function
+}
+
+fn allocate() -> Function {
+    const SIZE: u32 = 0;
+    const RESULT: u32 = 1;
+    const PAGE_BITS: i32 = 16;
+    function(
+        vec![(1, ValType::I32)],
+        &[
+            Instruction::GlobalGet(HEAP),
+            Instruction::LocalSet(RESULT),
+            // advance the heap pointer, keeping it aligned to 8 bytes
+            Instruction::GlobalGet(HEAP),
+            Instruction::LocalGet(SIZE),
+            Instruction::I32Add,
+            Instruction::I32Const(7),
+            Instruction::I32Add,
+            Instruction::I32Const(-8),
+            Instruction::I32And,
+            Instruction::GlobalSet(HEAP),
+            // grow the memory if the heap no longer fits
+            Instruction::GlobalGet(HEAP),
+            Instruction::MemorySize(0),
+            Instruction::I32Const(PAGE_BITS),
+            Instruction::I32Shl,
+            Instruction::I32GtU,
+            Instruction::If(BlockType::Empty),
+            Instruction::GlobalGet(HEAP),
+            Instruction::MemorySize(0),
+            Instruction::I32Const(PAGE_BITS),
+            Instruction::I32Shl,
+            Instruction::I32Sub,
+            Instruction::I32Const(PAGE_BITS),
+            Instruction::I32ShrU,
+            Instruction::I32Const(1),
+            Instruction::I32Add,
+            Instruction::MemoryGrow(0),
+            Instruction::I32Const(-1),
+            Instruction::I32Eq,
+            Instruction::If(BlockType::Empty),
+            Instruction::Unreachable,
+            Instruction::End,
+            Instruction::End,
+            Instruction::LocalGet(RESULT),
+        ],
+    )
+}
+
+fn primitive() -> Function {
+    const TAG: u32 = 0;
+    const VALUE: u32 = 1;
+    const RESULT: u32 = 2;
+    function(
+        vec![(1, ValType::I32)],
+        &[
+            Instruction::I32Const(OBJECT_SIZE),
+            Instruction::Call(ALLOCATE),
+            Instruction::LocalTee(RESULT),
+            Instruction::LocalGet(TAG),
+            Instruction::I32Store(field(0)),
+            Instruction::LocalGet(RESULT),
+            Instruction::LocalGet(VALUE),
+            Instruction::I64Store(wide_field(8)),
+            Instruction::LocalGet(RESULT),
+        ],
+    )
+}
+
+fn object_with_code(tag: i32) -> Function {
+    const CODE: u32 = 0;
+    const ENVIRONMENT: u32 = 1;
+    const RESULT: u32 = 2;
+    function(
+        vec![(1, ValType::I32)],
+        &[
+            Instruction::I32Const(OBJECT_SIZE),
+            Instruction::Call(ALLOCATE),
+            Instruction::LocalTee(RESULT),
+            Instruction::I32Const(tag),
+            Instruction::I32Store(field(0)),
+            Instruction::LocalGet(RESULT),
+            Instruction::LocalGet(CODE),
+            Instruction::I32Store(field(4)),
+            Instruction::LocalGet(RESULT),
+            Instruction::LocalGet(ENVIRONMENT),
+            Instruction::I32Store(field(8)),
+            Instruction::LocalGet(RESULT),
+            Instruction::I32Const(0),
+            Instruction::I32Store(field(12)),
+            Instruction::LocalGet(RESULT),
+        ],
+    )
+}
+
+fn force() -> Function {
+    const OBJECT: u32 = 0;
+    const RESULT: u32 = 1;
+    function(
+        vec![(1, ValType::I32)],
+        &[
+            // anything other than a thunk is already a value
+            Instruction::LocalGet(OBJECT),
+            Instruction::I32Load(field(0)),
+            Instruction::I32Const(THUNK),
+            Instruction::I32Ne,
+            Instruction::If(BlockType::Empty),
+            Instruction::LocalGet(OBJECT),
+            Instruction::Return,
+            Instruction::End,
+            // the thunk may have been evaluated already
+            Instruction::LocalGet(OBJECT),
+            Instruction::I32Load(field(12)),
+            Instruction::LocalTee(RESULT),
+            Instruction::If(BlockType::Empty),
+            Instruction::LocalGet(RESULT),
+            Instruction::Return,
+            Instruction::End,
+            Instruction::LocalGet(OBJECT),
+            Instruction::I32Load(field(8)),
+            Instruction::LocalGet(OBJECT),
+            Instruction::I32Load(field(4)),
+            Instruction::CallIndirect {
+                ty: THUNK_TYPE,
+                table: 0,
+            },
+            Instruction::LocalSet(RESULT),
+            Instruction::LocalGet(OBJECT),
+            Instruction::LocalGet(RESULT),
+            Instruction::I32Store(field(12)),
+            Instruction::LocalGet(RESULT),
+        ],
+    )
+}
+
+fn apply() -> Function {
+    const FUNCTION: u32 = 0;
+    const ARGUMENT: u32 = 1;
+    function(
+        vec![],
+        &[
+            Instruction::LocalGet(FUNCTION),
+            Instruction::I32Load(field(0)),
+            Instruction::I32Const(CLOSURE),
+            Instruction::I32Ne,
+            Instruction::If(BlockType::Empty),
+            Instruction::Unreachable,
+            Instruction::End,
+            Instruction::LocalGet(FUNCTION),
+            Instruction::I32Load(field(8)),
+            Instruction::LocalGet(ARGUMENT),
+            Instruction::LocalGet(FUNCTION),
+            Instruction::I32Load(field(4)),
+            Instruction::CallIndirect {
+                ty: CLOSURE_TYPE,
+                table: 0,
+            },
+        ],
+    )
+}
+
+fn value() -> Function {
+    const OBJECT: u32 = 0;
+    function(
+        vec![],
+        &[
+            Instruction::LocalGet(OBJECT),
+            Instruction::Call(FORCE),
+            Instruction::I64Load(wide_field(8)),
+        ],
+    )
+}
+
+/// The exported entry point, which evaluates the program and returns its
+/// value, which must be an integer or a boolean.
+pub fn main(program: u32) -> Function {
+    const RESULT: u32 = 0;
+    function(
+        vec![(1, ValType::I32)],
+        &[
+            Instruction::I32Const(0),
+            Instruction::Call(program),
+            Instruction::LocalTee(RESULT),
+            Instruction::I32Load(field(0)),
+            Instruction::I32Const(BOOLEAN),
+            Instruction::I32GtU,
+            Instruction::If(BlockType::Empty),
+            Instruction::Unreachable,
+            Instruction::End,
+            Instruction::LocalGet(RESULT),
+            Instruction::I64Load(wide_field(8)),
+        ],
+    )
+}
@@ -0,0 +1,119 @@
+use boo_core::error::Error;
+
+#[test]
+fn test_compiles_arithmetic() {
+    assert_eq!(run("1 + 2 * 3 - 4 / 2 % 3"), Ok(5));
+}
+
+#[test]
+fn test_compiles_bitwise_operations() {
+    assert_eq!(run("1 << 4 | 3"), Ok(19));
+    assert_eq!(run("-8 >> 1"), Ok(-4));
+    assert_eq!(run("12 & 10 xor 1"), Ok(9));
+}
+
+#[test]
+fn test_compiles_comparisons() {
+    assert_eq!(run("3 * 3 > 8"), Ok(1));
+    assert_eq!(run("2 == 3"), Ok(0));
+}
+
+#[test]
+fn test_compiles_closures() {
+    assert_eq!(
+        run("let add = fn x y -> x + y in let add_five = add 5 in add_five 2 + add_five 3"),
+        Ok(15)
+    );
+}
+
+#[test]
+fn test_compiles_pattern_matching() {
+    assert_eq!(
+        run("let f = fn n -> match n { 1 -> 9; 2 -> 7; m -> m * 10 } in f 2 + f 3"),
+        Ok(37)
+    );
+    assert_eq!(run("match 1 < 2 { false -> 0; _ -> 1 }"), Ok(1));
+}
+
+#[test]
+fn test_compiles_recursion() {
+    assert_eq!(
+        run("let rec factorial = fn n -> match n { 0 -> 1; _ -> n * factorial (n - 1) } in factorial 20"),
+        Ok(2_432_902_008_176_640_000)
+    );
+}
+
+#[test]
+fn test_compiles_mutual_recursion() {
+    assert_eq!(
+        run("let rec is_even = fn n -> match n { 0 -> true; _ -> is_odd (n - 1) } and is_odd = fn n -> match n { 0 -> false; _ -> is_even (n - 1) } in is_even 10"),
+        Ok(1)
+    );
+}
+
+#[test]
+fn test_compiles_top_level_definitions() {
+    assert_eq!(
+        run("def double = fn x -> x * 2; def quadruple = fn x -> double (double x); quadruple 3"),
+        Ok(12)
+    );
+}
+
+#[test]
+fn test_does_not_evaluate_unused_arguments() {
+    assert_eq!(run("let const = fn x y -> x in const 1 (1 / 0)"), Ok(1));
+}
+
+#[test]
+fn test_evaluates_shared_values_once() {
+    // `fib 30` would take far too long if `x` were evaluated twice per call
+    assert_eq!(
+        run("let rec fib = fn n -> match n { 0 -> 0; 1 -> 1; _ -> let x = fib (n - 1) in x + fib (n - 2) } in fib 25"),
+        Ok(75025)
+    );
+}
+
+#[test]
+fn test_traps_on_division_by_zero() {
+    let module = compile("1 / 0").unwrap();
+    assert!(execute(&module).is_err());
+}
+
+#[test]
+fn test_rejects_unsupported_values() {
+    let result = compile("(1, 2)");
+    assert!(
+        matches!(result, Err(Error::UnsupportedByCompiler { ref feature, .. }) if feature == "tuples"),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn test_rejects_unsupported_builtins() {
+    let result = compile("string_length \"hello\"");
+    assert!(
+        matches!(result, Err(Error::UnsupportedByCompiler { .. })),
+        "{result:?}"
+    );
+}
+
+fn run(program: &str) -> Result<i64, Error> {
+    let module = compile(program)?;
+    Ok(execute(&module).unwrap())
+}
+
+fn compile(program: &str) -> Result<Vec<u8>, Error> {
+    let expr = boo_parser::parse_program(program)?.to_core()?;
+    boo_compile_wasm::compile(expr)
+}
+
+fn execute(module: &[u8]) -> Result<i64, wasmi::Error> {
+    let engine = wasmi::Engine::default();
+    let module = wasmi::Module::new(&engine, module)?;
+    let mut store = wasmi::Store::new(&engine, ());
+    let instance = wasmi::Linker::<()>::new(&engine)
+        .instantiate(&mut store, &module)?
+        .start(&mut store)?;
+    let main = instance.get_typed_func::<(), i64>(&store, "main")?;
+    Ok(main.call(&mut store, ())?)
+}
@@ -0,0 +1,371 @@
+//! Checks that evaluators agree with each other.
+//!
+//! The reducing evaluator is the simplest, and so is used as the reference
+//! implementation. Any other evaluator can be checked against it on generated
+//! programs with [`check_against_reference`], and on generated functions with
+//! [`check_applications_against_reference`], or compared with any number of
+//! other evaluators on a single program with [`assert_equivalent`].
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use proptest::prelude::*;
+use proptest::test_runner::{TestCaseError, TestCaseResult};
+
+use boo_core::ast::*;
+use boo_core::builtins;
+use boo_core::error::Error;
+use boo_core::evaluation::*;
+use boo_core::expr::Expr;
+use boo_core::identifier::Identifier;
+use boo_core::native::Native;
+use boo_core::primitive::Primitive;
+use boo_core::types::{Monotype, Type};
+use boo_test_helpers::proptest::check;
+
+/// Prepares the builtins in an evaluation context, and produces its evaluator.
+pub fn prepared<Context: EvaluationContext>(mut context: Context) -> Context::Eval {
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())
+        .expect("Could not prepare the builtins.");
+    context.evaluator()
+}
+
+/// The reference evaluator, against which all others are compared.
+pub fn reference() -> impl Evaluator {
+    prepared(boo_evaluation_reduction::new())
+}
+
+/// A strategy for generating well-typed programs which evaluate to an integer.
+pub fn programs() -> impl Strategy<Value = boo_language::Expr> {
+    boo_generator::arbitrary()
+}
+
+/// Checks that the evaluator gets the same result as the reference evaluator
+/// for every generated program.
+///
+/// Each program is located in its rendered source first, so that the spans in
+/// any failure refer to the source, which is reported alongside.
+pub fn check_against_reference(evaluator: &dyn Evaluator) {
+    let reference = reference();
+    check(&programs(), |program| {
+        let (source, program) = boo_generator::locate(&program)?;
+        let expr = program.to_core()?;
+        assert_equivalent(&[&reference, evaluator], expr)
+            .map_err(|error| with_source(error, &source))
+    })
+}
+
+/// A strategy for generating well-typed programs which evaluate to a function,
+/// along with arguments to apply the function to, so that it evaluates to an
+/// integer.
+pub fn function_programs() -> impl Strategy<Value = boo_generator::FunctionProgram> {
+    let config = Rc::new(boo_generator::ExprGenConfig::default());
+    let integer = || Monotype::from(Type::Integer);
+    let signatures = vec![
+        (vec![integer()], integer()),
+        (vec![integer(), integer()], integer()),
+        (vec![Type::Boolean.into(), Type::String.into()], integer()),
+        (
+            vec![Type::Function {
+                parameter: integer(),
+                body: integer(),
+            }
+            .into()],
+            integer(),
+        ),
+    ];
+    proptest::sample::select(signatures).prop_flat_map(move |(parameter_types, result_type)| {
+        boo_generator::gen_function_program(config.clone(), parameter_types, result_type)
+    })
+}
+
+/// Checks that the evaluator agrees with the reference evaluator on generated
+/// programs which evaluate to functions, both on evaluating the function
+/// itself, and on applying it.
+///
+/// As with [`check_against_reference`], each program is located in the
+/// rendered source of the application.
+pub fn check_applications_against_reference(evaluator: &dyn Evaluator) {
+    let reference = reference();
+    check(&function_programs(), |program| {
+        let (source, program) = program.located()?;
+        let function = program.function.clone().to_core()?;
+        let applied = program.applied().to_core()?;
+        for evaluator in [&reference as &dyn Evaluator, evaluator] {
+            match evaluator.evaluate(function.clone()) {
+                Ok(Evaluated::Function(_)) | Err(Error::StepLimitExceeded { .. }) => {}
+                result => prop_assert!(
+                    false,
+                    "did not evaluate to a function\n  result: `{:?}`\n  input:  {}\n  source:\n{}\n",
+                    result,
+                    function,
+                    source
+                ),
+            }
+        }
+        assert_equivalent(&[&reference, evaluator], applied)
+            .map_err(|error| with_source(error, &source))
+    })
+}
+
+/// Adds the source of a generated program to a failure, so that any spans
+/// in the failure can be found.
+fn with_source(error: TestCaseError, source: &str) -> TestCaseError {
+    match error {
+        TestCaseError::Fail(reason) => {
+            TestCaseError::fail(format!("{reason}\n  source:\n{source}\n"))
+        }
+        reject => reject,
+    }
+}
+
+/// Asserts that every evaluator gets the same result as the first.
+///
+/// Programs are expected to evaluate to a primitive, or to fail with the same
+/// error, at the same location. An evaluator which gives up because the program took too many steps is not
+/// considered to disagree; evaluation strategies such as call-by-name can take
+/// exponentially longer than others.
+pub fn assert_equivalent(evaluators: &[&dyn Evaluator], expr: Expr) -> TestCaseResult {
+    let Some((first, rest)) = evaluators.split_first() else {
+        return Ok(());
+    };
+    let expected = first.evaluate(expr.clone());
+    if let Err(Error::StepLimitExceeded { .. }) = expected {
+        return Ok(());
+    }
+    for evaluator in rest {
+        let actual = evaluator.evaluate(expr.clone());
+        match (&expected, actual) {
+            (_, Err(Error::StepLimitExceeded { .. })) => {}
+            (Ok(Evaluated::Primitive(expected)), Ok(Evaluated::Primitive(actual))) => {
+                prop_assert_eq!(expected, &actual, "input: {}", expr);
+            }
+            // a failure must be reported at the same location
+            (Err(expected), Err(actual)) => {
+                prop_assert_eq!(expected, &actual, "input: {}", expr);
+            }
+            (Ok(expected), Ok(actual)) => prop_assert!(
+                false,
+                "did not finish evaluation\n  left:   `{}`,\n  right:  `{}`\n  input:  {}\n",
+                expected,
+                actual,
+                expr
+            ),
+            (expected, actual) => prop_assert!(
+                false,
+                "evaluation failed\n  left:   `{:?}`,\n  right:  `{:?}`\n  input:  {}\n",
+                expected,
+                actual,
+                expr
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the evaluator fails in the same way as the reference evaluator
+/// on programs which fail in a native, such as by dividing by zero.
+///
+/// Natives do not know where they were called from, so this checks that the
+/// evaluator locates their failures at the same call site.
+pub fn check_failures_against_reference(evaluator: &dyn Evaluator) {
+    let reference = reference();
+    let sources = [
+        "let zero = 0 in 1 / zero",
+        "let zero = 0 in 1 % zero",
+        "let amount = 0 - 1 in 1 << amount",
+        "let exponent = 0 - 1 in pow 2 exponent",
+        "let x = 0 in (assert (x > 0); x)",
+        "int_to_char 55296",
+        "head [] + 1",
+        "let zero = 0 in (1 / zero; 2)",
+        "let divide = fn x y -> x / y in 1 + divide 3 0",
+        "let call = fn f -> f 0 in call (fn x -> 10 / x)",
+        "match [1 / 0] { [x] -> x + 1; _ -> 0 }",
+        "let check = fn x -> match x { 0 -> 1 / x; _ -> x } in check 0",
+    ];
+    for source in sources {
+        let expr = boo_parser::parse_expr(source)
+            .and_then(|expr| expr.to_core())
+            .expect("Could not parse a failing program.");
+        assert!(
+            reference.evaluate(expr.clone()).is_err(),
+            "The reference evaluator did not fail on:\n{source}"
+        );
+        if let Err(error) = assert_equivalent(&[&reference, evaluator], expr) {
+            panic!("{error}\n  source:\n{source}\n");
+        }
+    }
+}
+
+/// Checks that an evaluator which fails on integer overflow does so in the
+/// same way as the reference evaluator, and only when a result does not fit.
+///
+/// The evaluator must have been [`prepared`] with
+/// [`IntegerOverflow::Fail`].
+pub fn check_integer_overflow_fails(evaluator: &dyn Evaluator) {
+    let reference = prepared(
+        boo_evaluation_reduction::ReducingEvaluator::new()
+            .with_integer_overflow(IntegerOverflow::Fail),
+    );
+    let sources = [
+        "let x = 65536 in 1 + x * x",
+        "2147483647 + 1",
+        "let square = fn x -> x * x in square 65536 / 65536",
+        "let shift = fn x -> x << 31 in shift 1",
+        "2147483646 + 1",
+        "0 - 2147483647 - 1",
+    ];
+    for source in sources {
+        let expr = boo_parser::parse_expr(source)
+            .and_then(|expr| expr.to_core())
+            .expect("Could not parse an overflowing program.");
+        if let Err(error) = assert_equivalent(&[&reference, evaluator], expr) {
+            panic!("{error}\n  source:\n{source}\n");
+        }
+    }
+}
+
+/// Checks that a native can apply a function supplied by the program.
+///
+/// The evaluator must have been [`prepared`] with the builtins.
+pub fn check_natives_can_apply_functions(evaluator: &dyn Evaluator) {
+    let f = Identifier::name_from_str("f").unwrap();
+    let x = Identifier::name_from_str("x").unwrap();
+    let y = Identifier::name_from_str("y").unwrap();
+    let multiply = Identifier::operator_from_str("*").unwrap();
+
+    // equivalent to `(fn f x -> f x) (fn y -> y * 2) 20`, but the inner
+    // application is performed by a native
+    let native = expr(Expression::Native(Native {
+        unique_name: Identifier::name_from_str("apply").unwrap(),
+        implementation: Arc::new({
+            let f = f.clone();
+            let x = x.clone();
+            move |context| context.apply(&f, context.lookup_value(&x)?)
+        }),
+    }));
+    let apply = function(f, function(x, native));
+    let double = function(
+        y.clone(),
+        application(
+            application(expr(Expression::Identifier(multiply)), identifier(y)),
+            integer(2),
+        ),
+    );
+    let program = application(application(apply, double), integer(20));
+
+    let result = evaluator.evaluate(program);
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(40.into())))
+    );
+}
+
+/// Checks that the evaluator can evaluate an expression nested 10,000 deep,
+/// on a thread with a stack far too small to hold one frame per level.
+///
+/// The context is created on that thread, as evaluators need not be [`Send`].
+pub fn check_deeply_nested_expressions_in_a_small_stack<Context: EvaluationContext + 'static>(
+    new_context: fn() -> Context,
+) {
+    let result = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || {
+            let evaluator = new_context().evaluator();
+            let x = Identifier::name_from_str("x").unwrap();
+            // `(fn x -> x) ((fn x -> x) (... 1))`
+            let program = (0..10_000).fold(integer(1), |inner, _| {
+                application(function(x.clone(), identifier(x.clone())), inner)
+            });
+            evaluator.evaluate(program)
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(1.into())))
+    );
+}
+
+/// Checks that the evaluator can match against patterns whose parts are
+/// themselves matches, 10,000 deep, on a thread with a stack far too small to
+/// hold one frame per level.
+///
+/// The context is created on that thread, as evaluators need not be [`Send`].
+pub fn check_deeply_nested_matches_in_a_small_stack<Context: EvaluationContext + 'static>(
+    new_context: fn() -> Context,
+) {
+    let result = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || {
+            let evaluator = prepared(new_context());
+            // matching `(_, 0)` needs the second element, which is another match
+            let program = boo_parser::parse_expr(
+                "let rec f = fn n -> match n { 0 -> (0, 0); _ -> (0, match f (n - 1) { (_, 0) -> 1; _ -> 0 }) } in match f 10000 { (_, x) -> x }",
+            )
+            .and_then(|expr| expr.to_core())
+            .expect("Could not parse a deeply-nested match.");
+            evaluator.evaluate(program)
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(0.into())))
+    );
+}
+
+/// Checks that a context lists the identifiers bound in it, each once, even
+/// when one is bound more than once.
+pub fn check_bound_identifiers_are_listed<Context: EvaluationContext>(mut context: Context) {
+    let x = Identifier::name_from_str("x").unwrap();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())
+        .expect("Could not prepare the builtins.");
+    context.bind(x.clone(), integer(1)).unwrap();
+    context.bind(x.clone(), integer(2)).unwrap();
+
+    let identifiers = context.identifiers();
+
+    let expected = builtins::types(builtins::IoCapabilities::default())
+        .map(|(name, _)| name.clone())
+        .chain([x])
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(identifiers.len(), expected.len());
+    assert_eq!(
+        identifiers
+            .into_iter()
+            .collect::<std::collections::HashSet<_>>(),
+        expected
+    );
+}
+
+fn expr(expression: Expression<Expr>) -> Expr {
+    Expr::new(None, expression)
+}
+
+fn identifier(name: Identifier) -> Expr {
+    expr(Expression::Identifier(name))
+}
+
+fn integer(value: i32) -> Expr {
+    expr(Expression::Primitive(Primitive::Integer(value.into())))
+}
+
+fn function(parameter: Identifier, body: Expr) -> Expr {
+    expr(Expression::Function(Function {
+        parameter,
+        parameter_type: None,
+        parameter_type_span: None,
+        body,
+    }))
+}
+
+fn application(function: Expr, argument: Expr) -> Expr {
+    expr(Expression::Apply(Apply { function, argument }))
+}
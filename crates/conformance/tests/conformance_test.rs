@@ -0,0 +1,62 @@
+use boo_core::error::{Error, Result};
+use boo_core::evaluation::*;
+use boo_core::expr::Expr;
+use boo_core::primitive::Primitive;
+
+use boo_conformance::*;
+
+/// Evaluates every program to the same result.
+struct Constant(Result<Evaluated>);
+
+impl Evaluator for Constant {
+    fn evaluate(&self, _expr: Expr) -> Result<Evaluated> {
+        self.0.clone()
+    }
+}
+
+#[test]
+fn test_the_reference_evaluator_is_equivalent_to_itself() {
+    let reference = reference();
+    check_against_reference(&reference);
+}
+
+#[test]
+fn test_the_reference_evaluator_applies_functions_equivalently_to_itself() {
+    let reference = reference();
+    check_applications_against_reference(&reference);
+}
+
+#[test]
+fn test_natives_can_apply_functions_in_the_reference_evaluator() {
+    let reference = reference();
+    check_natives_can_apply_functions(&reference);
+}
+
+#[test]
+fn test_the_reference_evaluator_lists_its_bound_identifiers() {
+    check_bound_identifiers_are_listed(boo_evaluation_reduction::new());
+}
+
+#[test]
+fn test_evaluators_with_different_results_are_not_equivalent() {
+    let reference = reference();
+    let wrong = Constant(Ok(Evaluated::Primitive(Primitive::Integer(0.into()))));
+
+    let result = assert_equivalent(&[&reference, &wrong], parse("1 + 2"));
+
+    assert!(result.is_err(), "expected a failure, got {:?}", result);
+}
+
+#[test]
+fn test_evaluators_which_exceed_their_step_limit_are_skipped() {
+    let reference = reference();
+    let impatient = Constant(Err(Error::StepLimitExceeded { limit: 1 }));
+
+    let result = assert_equivalent(&[&reference, &impatient], parse("1 + 2"));
+
+    assert!(result.is_ok(), "expected success, got {:?}", result);
+}
+
+fn parse(program: &str) -> Expr {
+    boo_parser::parse_expr(program).unwrap().to_core().unwrap()
+}
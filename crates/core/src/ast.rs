@@ -5,7 +5,9 @@ use std::fmt::Display;
 
 use crate::identifier::Identifier;
 use crate::native::Native;
+use crate::pretty::{self, Doc, Pretty};
 use crate::primitive::Primitive;
+use crate::span::Span;
 use crate::types::Monotype;
 
 /// A Boo expression. These can be nested arbitrarily.
@@ -31,8 +33,12 @@ pub enum Expression<Outer> {
     Function(Function<Outer>),
     Apply(Apply<Outer>),
     Assign(Assign<Outer>),
+    AssignRecursive(AssignRecursive<Outer>),
     Match(Match<Outer>),
     Typed(Typed<Outer>),
+    Sequence(Sequence<Outer>),
+    Tuple(Tuple<Outer>),
+    List(List<Outer>),
 }
 
 impl<Outer> AsRef<Expression<Outer>> for Expression<Outer> {
@@ -41,11 +47,126 @@ impl<Outer> AsRef<Expression<Outer>> for Expression<Outer> {
     }
 }
 
+impl<Outer> Expression<Outer> {
+    /// Replaces each direct subexpression with the result of applying the
+    /// function to it, in the order given by [`Expression::children`].
+    ///
+    /// This allows a pass over the AST to take an expression apart and put it
+    /// back together without recursing, by working through an explicit stack.
+    pub fn map<Other>(self, mut f: impl FnMut(Outer) -> Other) -> Expression<Other> {
+        match self {
+            Expression::Primitive(primitive) => Expression::Primitive(primitive),
+            Expression::Native(native) => Expression::Native(native),
+            Expression::Identifier(identifier) => Expression::Identifier(identifier),
+            Expression::Function(Function {
+                parameter,
+                parameter_type,
+                parameter_type_span,
+                body,
+            }) => Expression::Function(Function {
+                parameter,
+                parameter_type,
+                parameter_type_span,
+                body: f(body),
+            }),
+            Expression::Apply(Apply { function, argument }) => {
+                let function = f(function);
+                let argument = f(argument);
+                Expression::Apply(Apply { function, argument })
+            }
+            Expression::Assign(Assign { name, value, inner }) => {
+                let value = f(value);
+                let inner = f(inner);
+                Expression::Assign(Assign { name, value, inner })
+            }
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                let bindings = bindings
+                    .into_iter()
+                    .map(|(name, value)| (name, f(value)))
+                    .collect();
+                let inner = f(inner);
+                Expression::AssignRecursive(AssignRecursive { bindings, inner })
+            }
+            Expression::Match(Match { value, patterns }) => {
+                let value = f(value);
+                let patterns = patterns
+                    .into_iter()
+                    .map(
+                        |PatternMatch {
+                             pattern,
+                             pattern_span,
+                             result,
+                         }| PatternMatch {
+                            pattern,
+                            pattern_span,
+                            result: f(result),
+                        },
+                    )
+                    .collect();
+                Expression::Match(Match { value, patterns })
+            }
+            Expression::Typed(Typed {
+                expression,
+                typ,
+                typ_span,
+            }) => Expression::Typed(Typed {
+                expression: f(expression),
+                typ,
+                typ_span,
+            }),
+            Expression::Sequence(Sequence { first, second }) => {
+                let first = f(first);
+                let second = f(second);
+                Expression::Sequence(Sequence { first, second })
+            }
+            Expression::Tuple(Tuple { elements }) => Expression::Tuple(Tuple {
+                elements: elements.into_iter().map(f).collect(),
+            }),
+            Expression::List(List::Empty) => Expression::List(List::Empty),
+            Expression::List(List::Cons { head, tail }) => {
+                let head = f(head);
+                let tail = f(tail);
+                Expression::List(List::Cons { head, tail })
+            }
+        }
+    }
+
+    /// The direct subexpressions, in the order in which they are written.
+    pub fn children(&self) -> Vec<&Outer> {
+        match self {
+            Expression::Primitive(_)
+            | Expression::Native(_)
+            | Expression::Identifier(_)
+            | Expression::List(List::Empty) => vec![],
+            Expression::Function(Function { body, .. }) => vec![body],
+            Expression::Apply(Apply { function, argument }) => vec![function, argument],
+            Expression::Assign(Assign { value, inner, .. }) => vec![value, inner],
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => bindings
+                .iter()
+                .map(|(_, value)| value)
+                .chain([inner])
+                .collect(),
+            Expression::Match(Match { value, patterns }) => [value]
+                .into_iter()
+                .chain(patterns.iter().map(|PatternMatch { result, .. }| result))
+                .collect(),
+            Expression::Typed(Typed { expression, .. }) => vec![expression],
+            Expression::Sequence(Sequence { first, second }) => vec![first, second],
+            Expression::Tuple(Tuple { elements }) => elements.iter().collect(),
+            Expression::List(List::Cons { head, tail }) => vec![head, tail],
+        }
+    }
+}
+
 /// Represents a function definition.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Function<Outer> {
     /// The name of the function parameter.
     pub parameter: Identifier,
+    /// The stated type of the function parameter, if any.
+    pub parameter_type: Option<Monotype>,
+    /// The location of the stated type in the source, if known.
+    pub parameter_type_span: Option<Span>,
     /// The body of the function.
     pub body: Outer,
 }
@@ -70,6 +191,17 @@ pub struct Assign<Outer> {
     pub inner: Outer,
 }
 
+/// Represents a group of mutually-recursive assignments.
+///
+/// Each value may refer to any of the names in the group, including its own.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AssignRecursive<Outer> {
+    /// The names and values of the assigned variables.
+    pub bindings: Vec<(Identifier, Outer)>,
+    /// The rest of the expression.
+    pub inner: Outer,
+}
+
 /// A set of patterns matched against a value.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Match<Outer> {
@@ -84,6 +216,8 @@ pub struct Match<Outer> {
 pub struct PatternMatch<Outer> {
     /// The pattern to be matched.
     pub pattern: Pattern,
+    /// The location of the pattern in the source, if known.
+    pub pattern_span: Option<Span>,
     /// The result of matching against the pattern.
     pub result: Outer,
 }
@@ -95,6 +229,35 @@ pub struct Typed<Outer> {
     pub expression: Outer,
     /// The stated type of the expression.
     pub typ: Monotype,
+    /// The location of the stated type in the source, if known.
+    pub typ_span: Option<Span>,
+}
+
+/// Evaluates one expression, discarding the result, and then another.
+///
+/// The first expression is evaluated as far as its outermost constructor, in
+/// the same way a `match` would evaluate it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sequence<Outer> {
+    /// The expression evaluated first, for its effects.
+    pub first: Outer,
+    /// The expression providing the result.
+    pub second: Outer,
+}
+
+/// A fixed-size collection of values, which may be of different types.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tuple<Outer> {
+    /// The elements of the tuple.
+    pub elements: Vec<Outer>,
+}
+
+/// A list, which is either empty, or an element followed by the rest of the
+/// list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum List<Outer> {
+    Empty,
+    Cons { head: Outer, tail: Outer },
 }
 
 /// A single pattern.
@@ -102,60 +265,254 @@ pub struct Typed<Outer> {
 pub enum Pattern {
     Anything,
     Primitive(Primitive),
+    Variable(Identifier),
+    Tuple(Vec<Pattern>),
+    EmptyList,
+    Cons {
+        head: Box<Pattern>,
+        tail: Box<Pattern>,
+    },
+    /// An option holding a value, which matches the inner pattern.
+    Some(Box<Pattern>),
+    /// An empty option.
+    None,
+    /// A successful result, whose value matches the inner pattern.
+    Ok(Box<Pattern>),
+    /// A failed result, whose error matches the inner pattern.
+    Err(Box<Pattern>),
 }
 
-impl<Outer: Display> Display for Expression<Outer> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Pattern {
+    /// Returns `true` if the pattern will match any value of the correct type.
+    pub fn is_irrefutable(&self) -> bool {
+        match self {
+            Pattern::Anything | Pattern::Variable(_) => true,
+            Pattern::Primitive(_)
+            | Pattern::EmptyList
+            | Pattern::Cons { .. }
+            | Pattern::Some(_)
+            | Pattern::None
+            | Pattern::Ok(_)
+            | Pattern::Err(_) => false,
+            Pattern::Tuple(elements) => elements.iter().all(|element| element.is_irrefutable()),
+        }
+    }
+
+    /// Returns `true` if this pattern matches every value that the other
+    /// pattern matches, making the other pattern redundant if it comes later.
+    pub fn subsumes(&self, other: &Pattern) -> bool {
+        match (self, other) {
+            _ if self.is_irrefutable() => true,
+            (Pattern::Primitive(expected), Pattern::Primitive(actual)) => expected == actual,
+            (Pattern::EmptyList, Pattern::EmptyList) => true,
+            (Pattern::None, Pattern::None) => true,
+            (Pattern::Some(expected), Pattern::Some(actual))
+            | (Pattern::Ok(expected), Pattern::Ok(actual))
+            | (Pattern::Err(expected), Pattern::Err(actual)) => expected.subsumes(actual),
+            (Pattern::Tuple(expected), Pattern::Tuple(actual)) => {
+                expected.len() == actual.len()
+                    && expected
+                        .iter()
+                        .zip(actual.iter())
+                        .all(|(expected, actual)| expected.subsumes(actual))
+            }
+            (
+                Pattern::Cons {
+                    head: expected_head,
+                    tail: expected_tail,
+                },
+                Pattern::Cons {
+                    head: actual_head,
+                    tail: actual_tail,
+                },
+            ) => expected_head.subsumes(actual_head) && expected_tail.subsumes(actual_tail),
+            _ => false,
+        }
+    }
+
+    /// The variables bound by the pattern, in order.
+    pub fn variables(&self) -> Vec<&Identifier> {
         match self {
-            Expression::Primitive(x) => x.fmt(f),
-            Expression::Native(x) => x.fmt(f),
-            Expression::Identifier(x) => x.fmt(f),
-            Expression::Function(x) => x.fmt(f),
-            Expression::Apply(x) => x.fmt(f),
-            Expression::Assign(x) => x.fmt(f),
-            Expression::Match(x) => x.fmt(f),
-            Expression::Typed(x) => x.fmt(f),
+            Pattern::Anything | Pattern::Primitive(_) | Pattern::EmptyList | Pattern::None => {
+                vec![]
+            }
+            Pattern::Variable(name) => vec![name],
+            Pattern::Tuple(elements) => elements.iter().flat_map(|e| e.variables()).collect(),
+            Pattern::Cons { head, tail } => {
+                let mut variables = head.variables();
+                variables.extend(tail.variables());
+                variables
+            }
+            Pattern::Some(inner) | Pattern::Ok(inner) | Pattern::Err(inner) => inner.variables(),
+        }
+    }
+
+    /// Options and results are not represented separately once evaluated.
+    /// Options are lists of at most one element, and results are tuples of a
+    /// boolean tag, which is `true` on success, and the value or error. This
+    /// rewrites an option or result pattern to match that representation,
+    /// leaving any other pattern as it is.
+    pub fn encoded(&self) -> Pattern {
+        match self {
+            Pattern::Some(inner) => Pattern::Cons {
+                head: inner.clone(),
+                tail: Pattern::EmptyList.into(),
+            },
+            Pattern::None => Pattern::EmptyList,
+            Pattern::Ok(inner) => Pattern::Tuple(vec![
+                Pattern::Primitive(Primitive::Boolean(true)),
+                (**inner).clone(),
+            ]),
+            Pattern::Err(inner) => Pattern::Tuple(vec![
+                Pattern::Primitive(Primitive::Boolean(false)),
+                (**inner).clone(),
+            ]),
+            pattern => pattern.clone(),
         }
     }
 }
 
-impl<Outer: Display> Display for Function<Outer> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "fn {} -> ({})", self.parameter, self.body)
+impl<Outer: Pretty> Pretty for Expression<Outer> {
+    fn to_doc(&self) -> Doc {
+        match self {
+            Expression::Primitive(x) => Doc::text(x.to_string()),
+            Expression::Native(x) => Doc::text(x.to_string()),
+            Expression::Identifier(x) => Doc::text(x.to_string()),
+            Expression::Function(x) => x.to_doc(),
+            Expression::Apply(x) => x.to_doc(),
+            Expression::Assign(x) => x.to_doc(),
+            Expression::AssignRecursive(x) => x.to_doc(),
+            Expression::Match(x) => x.to_doc(),
+            Expression::Typed(x) => x.to_doc(),
+            Expression::Sequence(x) => x.to_doc(),
+            Expression::Tuple(x) => x.to_doc(),
+            Expression::List(x) => x.to_doc(),
+        }
     }
 }
 
-impl<Outer: Display> Display for Apply<Outer> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}) ({})", self.function, self.argument)
+impl<Outer: Pretty> Pretty for Function<Outer> {
+    fn to_doc(&self) -> Doc {
+        let parameter = match &self.parameter_type {
+            None => self.parameter.to_string(),
+            Some(parameter_type) => format!("({}: {})", self.parameter, parameter_type),
+        };
+        Doc::concat([
+            Doc::text(format!("fn {} -> ", parameter)),
+            Doc::parenthesize(self.body.to_doc()),
+        ])
     }
 }
 
-impl<Outer: Display> Display for Assign<Outer> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "let {} = ({}) in ({})",
-            self.name, self.value, self.inner
-        )
+impl<Outer: Pretty> Pretty for Apply<Outer> {
+    fn to_doc(&self) -> Doc {
+        Doc::concat([
+            Doc::parenthesize(self.function.to_doc()),
+            Doc::concat([Doc::line(), Doc::parenthesize(self.argument.to_doc())]).nest(),
+        ])
+        .group()
     }
 }
 
-impl<Outer: Display> Display for Match<Outer> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "match {} {{", self.value)?;
-        let mut pattern_iter = self.patterns.iter();
-        if let Some(PatternMatch {
-            pattern: first_pattern,
-            result: first_result,
-        }) = pattern_iter.next()
-        {
-            write!(f, "{} -> ({})", first_pattern, first_result)?;
-            for PatternMatch { pattern, result } in pattern_iter {
-                write!(f, "; {} -> ({})", pattern, result)?;
-            }
+impl<Outer: Pretty> Pretty for Assign<Outer> {
+    fn to_doc(&self) -> Doc {
+        Doc::concat([
+            Doc::text(format!("let {} = ", self.name)),
+            Doc::parenthesize(self.value.to_doc()),
+            Doc::text(" in"),
+            Doc::line(),
+            Doc::parenthesize(self.inner.to_doc()),
+        ])
+        .group()
+    }
+}
+
+impl<Outer: Pretty> Pretty for AssignRecursive<Outer> {
+    fn to_doc(&self) -> Doc {
+        let bindings = self.bindings.iter().map(|(name, value)| {
+            Doc::concat([
+                Doc::text(format!("{} = ", name)),
+                Doc::parenthesize(value.to_doc()),
+            ])
+        });
+        Doc::concat([
+            Doc::text("let rec "),
+            Doc::join(bindings, Doc::concat([Doc::line(), Doc::text("and ")])),
+            Doc::text(" in"),
+            Doc::line(),
+            Doc::parenthesize(self.inner.to_doc()),
+        ])
+        .group()
+    }
+}
+
+impl<Outer: Pretty> Pretty for Match<Outer> {
+    fn to_doc(&self) -> Doc {
+        let patterns = self.patterns.iter().map(
+            |PatternMatch {
+                 pattern, result, ..
+             }| {
+                Doc::concat([
+                    Doc::text(format!("{} -> ", pattern)),
+                    Doc::parenthesize(result.to_doc()),
+                ])
+            },
+        );
+        Doc::concat([
+            Doc::text("match "),
+            self.value.to_doc(),
+            Doc::text(" "),
+            Doc::bracket(
+                "{",
+                Doc::join(patterns, Doc::concat([Doc::text(";"), Doc::line()])),
+                "}",
+            ),
+        ])
+    }
+}
+
+impl<Outer: Pretty> Pretty for Typed<Outer> {
+    fn to_doc(&self) -> Doc {
+        Doc::concat([
+            Doc::parenthesize(self.expression.to_doc()),
+            Doc::text(format!(": {}", self.typ)),
+        ])
+    }
+}
+
+impl<Outer: Pretty> Pretty for Sequence<Outer> {
+    fn to_doc(&self) -> Doc {
+        Doc::parenthesize(Doc::concat([
+            Doc::parenthesize(self.first.to_doc()),
+            Doc::text(";"),
+            Doc::line(),
+            Doc::parenthesize(self.second.to_doc()),
+        ]))
+    }
+}
+
+impl<Outer: Pretty> Pretty for Tuple<Outer> {
+    fn to_doc(&self) -> Doc {
+        Doc::parenthesize(Doc::join(
+            self.elements
+                .iter()
+                .map(|element| Doc::parenthesize(element.to_doc())),
+            Doc::concat([Doc::text(","), Doc::line()]),
+        ))
+    }
+}
+
+impl<Outer: Pretty> Pretty for List<Outer> {
+    fn to_doc(&self) -> Doc {
+        match self {
+            List::Empty => Doc::text("[]"),
+            List::Cons { head, tail } => Doc::concat([
+                Doc::parenthesize(head.to_doc()),
+                Doc::text(" ::"),
+                Doc::concat([Doc::line(), Doc::parenthesize(tail.to_doc())]).nest(),
+            ])
+            .group(),
         }
-        write!(f, "}}")
     }
 }
 
@@ -164,12 +521,84 @@ impl Display for Pattern {
         match self {
             Pattern::Primitive(x) => x.fmt(f),
             Pattern::Anything => write!(f, "_"),
+            Pattern::Variable(x) => x.fmt(f),
+            Pattern::Tuple(elements) => {
+                write!(f, "(")?;
+                let mut element_iter = elements.iter();
+                if let Some(first) = element_iter.next() {
+                    write!(f, "{}", first)?;
+                    for element in element_iter {
+                        write!(f, ", {}", element)?;
+                    }
+                }
+                write!(f, ")")
+            }
+            Pattern::EmptyList => write!(f, "[]"),
+            Pattern::Cons { head, tail } => write!(f, "({}) :: ({})", head, tail),
+            Pattern::Some(inner) => write!(f, "some ({})", inner),
+            Pattern::None => write!(f, "none"),
+            Pattern::Ok(inner) => write!(f, "ok ({})", inner),
+            Pattern::Err(inner) => write!(f, "err ({})", inner),
         }
     }
 }
 
-impl<Outer: Display> Display for Typed<Outer> {
+impl<Outer: Pretty> Display for Expression<Outer> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl<Outer: Pretty> Display for Function<Outer> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl<Outer: Pretty> Display for Apply<Outer> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl<Outer: Pretty> Display for Assign<Outer> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl<Outer: Pretty> Display for AssignRecursive<Outer> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl<Outer: Pretty> Display for Match<Outer> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl<Outer: Pretty> Display for Typed<Outer> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl<Outer: Pretty> Display for Sequence<Outer> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl<Outer: Pretty> Display for Tuple<Outer> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl<Outer: Pretty> Display for List<Outer> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}): {}", self.expression, self.typ)
+        pretty::display(self, f)
     }
 }
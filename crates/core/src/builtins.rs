@@ -1,96 +1,1024 @@
 //! Built-in native functionality, required for evaluation of anything useful.
 
-use std::rc::Rc;
+use std::cmp::Ordering;
+use std::sync::Arc;
 
 use lazy_static::lazy_static;
 
 use crate::ast::*;
-use crate::error::Result;
-use crate::evaluation::EvaluationContext;
+use crate::error::{Error, Result};
+use crate::evaluation::{EvaluationContext, IntegerOverflow};
 use crate::expr::Expr;
 use crate::identifier::Identifier;
-use crate::native::Native;
-use crate::primitive::{Integer, Primitive};
+use crate::native::{Native, NativeContext};
+use crate::primitive::{Float, Integer, Primitive};
 use crate::types::{Monotype, Polytype, Type, TypeVariable};
 
 lazy_static! {
     static ref NAME_ADD: Identifier = Identifier::operator_from_str("+").unwrap();
     static ref NAME_SUBTRACT: Identifier = Identifier::operator_from_str("-").unwrap();
     static ref NAME_MULTIPLY: Identifier = Identifier::operator_from_str("*").unwrap();
+    static ref NAME_DIVIDE: Identifier = Identifier::operator_from_str("/").unwrap();
+    static ref NAME_MODULO: Identifier = Identifier::operator_from_str("%").unwrap();
+    static ref NAME_BITWISE_AND: Identifier = Identifier::operator_from_str("&").unwrap();
+    static ref NAME_BITWISE_OR: Identifier = Identifier::operator_from_str("|").unwrap();
+    static ref NAME_BITWISE_XOR: Identifier = Identifier::operator_from_str("xor").unwrap();
+    static ref NAME_SHIFT_LEFT: Identifier = Identifier::operator_from_str("<<").unwrap();
+    static ref NAME_SHIFT_RIGHT: Identifier = Identifier::operator_from_str(">>").unwrap();
+    static ref NAME_ADD_FLOAT: Identifier = Identifier::operator_from_str("+.").unwrap();
+    static ref NAME_SUBTRACT_FLOAT: Identifier = Identifier::operator_from_str("-.").unwrap();
+    static ref NAME_MULTIPLY_FLOAT: Identifier = Identifier::operator_from_str("*.").unwrap();
+    static ref NAME_DIVIDE_FLOAT: Identifier = Identifier::operator_from_str("/.").unwrap();
+    static ref NAME_CONCATENATE: Identifier = Identifier::operator_from_str("++").unwrap();
+    static ref NAME_EQUAL: Identifier = Identifier::operator_from_str("==").unwrap();
+    static ref NAME_NOT_EQUAL: Identifier = Identifier::operator_from_str("!=").unwrap();
+    static ref NAME_LESS_THAN: Identifier = Identifier::operator_from_str("<").unwrap();
+    static ref NAME_LESS_THAN_OR_EQUAL: Identifier = Identifier::operator_from_str("<=").unwrap();
+    static ref NAME_GREATER_THAN: Identifier = Identifier::operator_from_str(">").unwrap();
+    static ref NAME_GREATER_THAN_OR_EQUAL: Identifier =
+        Identifier::operator_from_str(">=").unwrap();
+    static ref NAME_ABS: Identifier = Identifier::name_from_str("abs").unwrap();
+    static ref NAME_SIGN: Identifier = Identifier::name_from_str("sign").unwrap();
+    static ref NAME_MIN: Identifier = Identifier::name_from_str("min").unwrap();
+    static ref NAME_MAX: Identifier = Identifier::name_from_str("max").unwrap();
+    static ref NAME_POW: Identifier = Identifier::name_from_str("pow").unwrap();
+    static ref NAME_GCD: Identifier = Identifier::name_from_str("gcd").unwrap();
+    static ref NAME_STRING_LENGTH: Identifier = Identifier::name_from_str("string_length").unwrap();
+    static ref NAME_SUBSTRING: Identifier = Identifier::name_from_str("substring").unwrap();
+    static ref NAME_LENGTH: Identifier = Identifier::name_from_str("length").unwrap();
+    static ref NAME_CONCAT: Identifier = Identifier::name_from_str("concat").unwrap();
+    static ref NAME_SPLIT: Identifier = Identifier::name_from_str("split").unwrap();
+    static ref NAME_TO_UPPER: Identifier = Identifier::name_from_str("to_upper").unwrap();
+    static ref NAME_TO_LOWER: Identifier = Identifier::name_from_str("to_lower").unwrap();
+    static ref NAME_PARSE_INT: Identifier = Identifier::name_from_str("parse_int").unwrap();
+    static ref NAME_CHAR_TO_INT: Identifier = Identifier::name_from_str("char_to_int").unwrap();
+    static ref NAME_INT_TO_CHAR: Identifier = Identifier::name_from_str("int_to_char").unwrap();
+    static ref NAME_CONS: Identifier = Identifier::operator_from_str("::").unwrap();
+    static ref NAME_HEAD: Identifier = Identifier::name_from_str("head").unwrap();
+    static ref NAME_TAIL: Identifier = Identifier::name_from_str("tail").unwrap();
+    static ref NAME_IS_EMPTY: Identifier = Identifier::name_from_str("is_empty").unwrap();
+    static ref NAME_LIST_LENGTH: Identifier = Identifier::name_from_str("list_length").unwrap();
+    static ref NAME_MAP: Identifier = Identifier::name_from_str("map").unwrap();
+    static ref NAME_FILTER: Identifier = Identifier::name_from_str("filter").unwrap();
+    static ref NAME_FOLD: Identifier = Identifier::name_from_str("fold").unwrap();
+    static ref NAME_RANGE: Identifier = Identifier::name_from_str("range").unwrap();
+    static ref NAME_SOME: Identifier = Identifier::name_from_str("some").unwrap();
+    static ref NAME_NONE: Identifier = Identifier::name_from_str("none").unwrap();
+    static ref NAME_MAP_OPTION: Identifier = Identifier::name_from_str("map_option").unwrap();
+    static ref NAME_UNWRAP_OR: Identifier = Identifier::name_from_str("unwrap_or").unwrap();
+    static ref NAME_OK: Identifier = Identifier::name_from_str("ok").unwrap();
+    static ref NAME_ERR: Identifier = Identifier::name_from_str("err").unwrap();
+    static ref NAME_AND_THEN: Identifier = Identifier::name_from_str("and_then").unwrap();
+    static ref NAME_MAP_RESULT: Identifier = Identifier::name_from_str("map_result").unwrap();
+    static ref NAME_UNWRAP_OR_ELSE: Identifier =
+        Identifier::name_from_str("unwrap_or_else").unwrap();
+    static ref NAME_CHECKED_DIVIDE: Identifier =
+        Identifier::name_from_str("checked_divide").unwrap();
+    static ref NAME_ASSERT: Identifier = Identifier::name_from_str("assert").unwrap();
     static ref NAME_TRACE: Identifier = Identifier::name_from_str("trace").unwrap();
+    static ref NAME_PRINT: Identifier = Identifier::name_from_str("print").unwrap();
+    static ref NAME_RANDOM: Identifier = Identifier::name_from_str("random").unwrap();
+    static ref NAME_SEED: Identifier = Identifier::name_from_str("seed").unwrap();
 }
 
-/// Prepares an [EvaluationContext] by assigning all built-ins.
-pub fn prepare(context: &mut impl EvaluationContext) -> Result<()> {
-    for builtin in all().into_iter().rev() {
-        context.bind(builtin.name.clone(), builtin.implementation)?;
-    }
-    Ok(())
+/// The kinds of input and output that built-ins are permitted to perform.
+///
+/// By default, nothing is permitted, which keeps evaluation deterministic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoCapabilities {
+    /// Enables `print`, which writes to stdout.
+    pub print: bool,
+    /// Enables `random`, which generates pseudo-random numbers, and `seed`,
+    /// which is this value, and from which the program starts generating.
+    pub random_seed: Option<u64>,
+}
+
+/// Prepares an [EvaluationContext] by assigning all built-ins permitted by
+/// the given capabilities.
+///
+/// To choose which built-ins are available, use a [BuiltinsBuilder] instead.
+pub fn prepare(context: &mut impl EvaluationContext, capabilities: IoCapabilities) -> Result<()> {
+    Globals::new(capabilities).prepare(context)
 }
 
-/// Prepares an evaluator by assigning all built-ins.
-pub fn types() -> impl Iterator<Item = (&'static Identifier, Polytype)> {
-    all()
+/// The types of all built-ins permitted by the given capabilities.
+pub fn types(
+    capabilities: IoCapabilities,
+) -> impl Iterator<Item = (&'static Identifier, Polytype)> {
+    all(capabilities)
         .into_iter()
         .map(|builtin| (builtin.name, builtin.assumed_type))
 }
 
+/// The implementations of all built-ins permitted by the given capabilities.
+pub fn implementations(
+    capabilities: IoCapabilities,
+) -> impl Iterator<Item = (&'static Identifier, Expr)> {
+    all(capabilities)
+        .into_iter()
+        .map(|builtin| (builtin.name, builtin.implementation))
+}
+
+/// A group of related built-ins, which are made available together.
+///
+/// None of these perform input or output, except that `trace` writes to
+/// stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltinGroup {
+    /// Arithmetic, bitwise operations, and other functions on integers.
+    Integers,
+    /// Arithmetic on floats.
+    Floats,
+    /// Equality and ordering.
+    Comparison,
+    /// Functions on strings and characters.
+    Strings,
+    /// Constructing, inspecting, and transforming lists.
+    Lists,
+    /// Constructing and using options.
+    Options,
+    /// Constructing and using results.
+    Results,
+    /// `assert` and `trace`.
+    Debugging,
+}
+
+impl BuiltinGroup {
+    /// Every group.
+    pub const ALL: [BuiltinGroup; 8] = [
+        BuiltinGroup::Integers,
+        BuiltinGroup::Floats,
+        BuiltinGroup::Comparison,
+        BuiltinGroup::Strings,
+        BuiltinGroup::Lists,
+        BuiltinGroup::Options,
+        BuiltinGroup::Results,
+        BuiltinGroup::Debugging,
+    ];
+}
+
+/// Chooses exactly which built-ins are available to a program, so that, for
+/// example, an untrusted program can be restricted to pure arithmetic.
+///
+/// Nothing is available until it is added.
+#[derive(Debug, Clone, Default)]
+pub struct BuiltinsBuilder {
+    groups: Vec<BuiltinGroup>,
+    capabilities: IoCapabilities,
+}
+
+impl BuiltinsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the built-ins in the group available.
+    pub fn with(mut self, group: BuiltinGroup) -> Self {
+        if !self.groups.contains(&group) {
+            self.groups.push(group);
+        }
+        self
+    }
+
+    /// Makes the built-ins in every group available. This does not permit
+    /// input, output, or randomness.
+    pub fn with_all_groups(self) -> Self {
+        BuiltinGroup::ALL
+            .into_iter()
+            .fold(self, |builder, group| builder.with(group))
+    }
+
+    /// Makes `print` available, which writes to stdout.
+    pub fn with_print(mut self) -> Self {
+        self.capabilities.print = true;
+        self
+    }
+
+    /// Makes `random` available, along with the given seed, from which
+    /// numbers are generated.
+    pub fn with_random(mut self, seed: u64) -> Self {
+        self.capabilities.random_seed = Some(seed);
+        self
+    }
+
+    /// Produces the globals, to which natives can then be added.
+    pub fn build(self) -> Globals {
+        Globals {
+            groups: self.groups,
+            capabilities: self.capabilities,
+            natives: vec![],
+        }
+    }
+}
+
+/// The values available to a program before it is evaluated: the chosen
+/// built-ins, along with any natives registered by the embedder.
+///
+/// The same globals should be used both to prepare the [EvaluationContext]
+/// and to type-check the program, so that the type checker knows the type of
+/// each native.
+#[derive(Debug, Clone)]
+pub struct Globals {
+    groups: Vec<BuiltinGroup>,
+    capabilities: IoCapabilities,
+    /// Natives and definitions, in the order they were registered.
+    natives: Vec<(Identifier, Polytype, Expr)>,
+}
+
+impl Globals {
+    /// Every built-in permitted by the capabilities.
+    pub fn new(capabilities: IoCapabilities) -> Self {
+        Self {
+            groups: BuiltinGroup::ALL.to_vec(),
+            capabilities,
+            natives: vec![],
+        }
+    }
+
+    /// Registers a native function, implemented in Rust, under the given
+    /// name.
+    ///
+    /// The implementation receives one primitive argument for each parameter
+    /// in its type, in order, along with the context in which it is
+    /// evaluated. A native with no parameters is a value.
+    ///
+    /// A native with the same name as a built-in or an earlier native replaces
+    /// it.
+    ///
+    /// Panics if the type does not have exactly `N` parameters.
+    pub fn with_native<Op, const N: usize>(
+        mut self,
+        name: Identifier,
+        typ: Polytype,
+        implementation: Op,
+    ) -> Self
+    where
+        Op: Fn(&dyn NativeContext, [Primitive; N]) -> Result<Primitive> + Send + Sync + 'static,
+    {
+        let mut arity = 0;
+        let mut result = &typ.mono;
+        while let Type::Function { body, .. } = result.as_ref() {
+            arity += 1;
+            result = body;
+        }
+        assert_eq!(
+            arity, N,
+            "The native `{name}` has {arity} parameter(s) in its type, {typ}, but its implementation takes {N} argument(s)"
+        );
+        let parameter_names: [String; N] = std::array::from_fn(|i| format!("argument_{i}"));
+        let parameter_names: [&str; N] = std::array::from_fn(|i| parameter_names[i].as_str());
+        let implementation =
+            builtin_native_in_context(name.clone(), parameter_names, implementation);
+        self.natives.push((name, typ, implementation));
+        self
+    }
+
+    /// Registers a curried native function of two arguments, with the type
+    /// `left -> right -> result`.
+    pub fn with_binary_native<Op>(
+        self,
+        name: Identifier,
+        left: Monotype,
+        right: Monotype,
+        result: Monotype,
+        operate: Op,
+    ) -> Self
+    where
+        Op: Fn(Primitive, Primitive) -> Result<Primitive> + Send + Sync + 'static,
+    {
+        self.with_native(
+            name,
+            Polytype::unquantified(binary_function_type(left, right, result)),
+            move |_, [left, right]| operate(left, right),
+        )
+    }
+
+    /// Registers a definition written in Boo under the given name, such as one
+    /// made by an earlier program in an interactive session.
+    ///
+    /// As with natives, the type must be provided, and a definition replaces
+    /// any earlier global with the same name. Definitions and natives are
+    /// bound in the order they were registered, so each can refer to the
+    /// ones before it.
+    pub fn with_definition(mut self, name: Identifier, typ: Polytype, value: Expr) -> Self {
+        self.natives.push((name, typ, value));
+        self
+    }
+
+    /// Prepares an [EvaluationContext] by assigning the chosen built-ins,
+    /// followed by the registered natives and definitions.
+    pub fn prepare(&self, context: &mut impl EvaluationContext) -> Result<()> {
+        for builtin in self.builtins().rev() {
+            context.bind(builtin.name.clone(), builtin.implementation)?;
+        }
+        for (name, _, implementation) in self.natives.iter() {
+            context.bind(name.clone(), implementation.clone())?;
+        }
+        Ok(())
+    }
+
+    /// The types of the chosen built-ins, followed by the types of the
+    /// registered natives and definitions.
+    pub fn types(&self) -> impl Iterator<Item = (Identifier, Polytype)> + '_ {
+        self.builtins()
+            .map(|builtin| (builtin.name.clone(), builtin.assumed_type))
+            .chain(
+                self.natives
+                    .iter()
+                    .map(|(name, typ, _)| (name.clone(), typ.clone())),
+            )
+    }
+
+    fn builtins(&self) -> impl DoubleEndedIterator<Item = Builtin> + '_ {
+        all(self.capabilities)
+            .into_iter()
+            .filter(|builtin| match builtin.group {
+                Some(group) => self.groups.contains(&group),
+                None => true,
+            })
+    }
+}
+
+impl Default for Globals {
+    fn default() -> Self {
+        Self::new(IoCapabilities::default())
+    }
+}
+
+impl From<IoCapabilities> for Globals {
+    fn from(capabilities: IoCapabilities) -> Self {
+        Self::new(capabilities)
+    }
+}
+
 struct Builtin {
     name: &'static Identifier,
+    /// The group of built-ins this belongs to, or `None` if it is only
+    /// governed by the [IoCapabilities].
+    group: Option<BuiltinGroup>,
     assumed_type: Polytype,
     implementation: Expr,
 }
 
-/// All the built-in expressions.
-fn all() -> Vec<Builtin> {
-    vec![
+/// All the built-in expressions permitted by the given capabilities.
+fn all(capabilities: IoCapabilities) -> Vec<Builtin> {
+    let mut builtins = vec![
         Builtin {
             name: &NAME_ADD,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_add(),
+        },
+        Builtin {
+            name: &NAME_SUBTRACT,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_subtract(),
+        },
+        Builtin {
+            name: &NAME_MULTIPLY,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_multiply(),
+        },
+        Builtin {
+            name: &NAME_DIVIDE,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_divide(),
+        },
+        Builtin {
+            name: &NAME_MODULO,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_modulo(),
+        },
+        Builtin {
+            name: &NAME_BITWISE_AND,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_infix_math("&", |x, y| x & y),
+        },
+        Builtin {
+            name: &NAME_BITWISE_OR,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_infix_math("|", |x, y| x | y),
+        },
+        Builtin {
+            name: &NAME_BITWISE_XOR,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_infix_math("xor", |x, y| x ^ y),
+        },
+        Builtin {
+            name: &NAME_SHIFT_LEFT,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_shift("<<", Integer::checked_shl),
+        },
+        Builtin {
+            name: &NAME_SHIFT_RIGHT,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_shift(">>", Integer::checked_shr),
+        },
+        Builtin {
+            name: &NAME_ABS,
+            group: Some(BuiltinGroup::Integers),
             assumed_type: Polytype::unquantified(
                 Type::Function {
                     parameter: Type::Integer.into(),
-                    body: Type::Function {
-                        parameter: Type::Integer.into(),
-                        body: Type::Integer.into(),
-                    }
-                    .into(),
+                    body: Type::Integer.into(),
                 }
                 .into(),
             ),
-            implementation: builtin_add(),
+            implementation: builtin_abs(),
         },
         Builtin {
-            name: &NAME_SUBTRACT,
+            name: &NAME_SIGN,
+            group: Some(BuiltinGroup::Integers),
             assumed_type: Polytype::unquantified(
                 Type::Function {
                     parameter: Type::Integer.into(),
-                    body: Type::Function {
-                        parameter: Type::Integer.into(),
-                        body: Type::Integer.into(),
-                    }
-                    .into(),
+                    body: Type::Integer.into(),
                 }
                 .into(),
             ),
-            implementation: builtin_subtract(),
+            implementation: builtin_sign(),
         },
         Builtin {
-            name: &NAME_MULTIPLY,
+            name: &NAME_MIN,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_min_max(NAME_MIN.clone(), Ordering::Less),
+        },
+        Builtin {
+            name: &NAME_MAX,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_min_max(NAME_MAX.clone(), Ordering::Greater),
+        },
+        Builtin {
+            name: &NAME_POW,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_pow(),
+        },
+        Builtin {
+            name: &NAME_GCD,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Integer.into(),
+            )),
+            implementation: builtin_gcd(),
+        },
+        Builtin {
+            name: &NAME_ADD_FLOAT,
+            group: Some(BuiltinGroup::Floats),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Float.into(),
+                Type::Float.into(),
+            )),
+            implementation: builtin_infix_float_math("+.", |x, y| x + y),
+        },
+        Builtin {
+            name: &NAME_SUBTRACT_FLOAT,
+            group: Some(BuiltinGroup::Floats),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Float.into(),
+                Type::Float.into(),
+            )),
+            implementation: builtin_infix_float_math("-.", |x, y| x - y),
+        },
+        Builtin {
+            name: &NAME_MULTIPLY_FLOAT,
+            group: Some(BuiltinGroup::Floats),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Float.into(),
+                Type::Float.into(),
+            )),
+            implementation: builtin_infix_float_math("*.", |x, y| x * y),
+        },
+        Builtin {
+            name: &NAME_DIVIDE_FLOAT,
+            group: Some(BuiltinGroup::Floats),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Float.into(),
+                Type::Float.into(),
+            )),
+            implementation: builtin_infix_float_math("/.", |x, y| x / y),
+        },
+        Builtin {
+            name: &NAME_CONCATENATE,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::String.into(),
+                Type::String.into(),
+            )),
+            implementation: builtin_concatenate(),
+        },
+        Builtin {
+            name: &NAME_EQUAL,
+            group: Some(BuiltinGroup::Comparison),
+            assumed_type: comparison_type(),
+            implementation: builtin_comparison("==", |ordering| ordering.is_eq()),
+        },
+        Builtin {
+            name: &NAME_NOT_EQUAL,
+            group: Some(BuiltinGroup::Comparison),
+            assumed_type: comparison_type(),
+            implementation: builtin_comparison("!=", |ordering| ordering.is_ne()),
+        },
+        Builtin {
+            name: &NAME_LESS_THAN,
+            group: Some(BuiltinGroup::Comparison),
+            assumed_type: comparison_type(),
+            implementation: builtin_comparison("<", |ordering| ordering.is_lt()),
+        },
+        Builtin {
+            name: &NAME_LESS_THAN_OR_EQUAL,
+            group: Some(BuiltinGroup::Comparison),
+            assumed_type: comparison_type(),
+            implementation: builtin_comparison("<=", |ordering| ordering.is_le()),
+        },
+        Builtin {
+            name: &NAME_GREATER_THAN,
+            group: Some(BuiltinGroup::Comparison),
+            assumed_type: comparison_type(),
+            implementation: builtin_comparison(">", |ordering| ordering.is_gt()),
+        },
+        Builtin {
+            name: &NAME_GREATER_THAN_OR_EQUAL,
+            group: Some(BuiltinGroup::Comparison),
+            assumed_type: comparison_type(),
+            implementation: builtin_comparison(">=", |ordering| ordering.is_ge()),
+        },
+        Builtin {
+            name: &NAME_STRING_LENGTH,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(
+                Type::Function {
+                    parameter: Type::String.into(),
+                    body: Type::Integer.into(),
+                }
+                .into(),
+            ),
+            implementation: builtin_string_length(),
+        },
+        Builtin {
+            name: &NAME_SUBSTRING,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(
+                Type::Function {
+                    parameter: Type::String.into(),
+                    body: binary_operation_type(Type::Integer.into(), Type::String.into()),
+                }
+                .into(),
+            ),
+            implementation: builtin_substring(),
+        },
+        Builtin {
+            name: &NAME_LENGTH,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(
+                Type::Function {
+                    parameter: Type::String.into(),
+                    body: Type::Integer.into(),
+                }
+                .into(),
+            ),
+            implementation: builtin_string_length(),
+        },
+        Builtin {
+            name: &NAME_CONCAT,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::String.into(),
+                Type::String.into(),
+            )),
+            implementation: builtin_concatenate(),
+        },
+        Builtin {
+            name: &NAME_SPLIT,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::String.into(),
+                Type::List(Type::String.into()).into(),
+            )),
+            implementation: builtin_split(),
+        },
+        Builtin {
+            name: &NAME_TO_UPPER,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(
+                Type::Function {
+                    parameter: Type::String.into(),
+                    body: Type::String.into(),
+                }
+                .into(),
+            ),
+            implementation: builtin_map_string(NAME_TO_UPPER.clone(), str::to_uppercase),
+        },
+        Builtin {
+            name: &NAME_TO_LOWER,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(
+                Type::Function {
+                    parameter: Type::String.into(),
+                    body: Type::String.into(),
+                }
+                .into(),
+            ),
+            implementation: builtin_map_string(NAME_TO_LOWER.clone(), str::to_lowercase),
+        },
+        Builtin {
+            name: &NAME_PARSE_INT,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(
+                Type::Function {
+                    parameter: Type::String.into(),
+                    body: Type::Option(Type::Integer.into()).into(),
+                }
+                .into(),
+            ),
+            implementation: builtin_parse_int(),
+        },
+        Builtin {
+            name: &NAME_CHAR_TO_INT,
+            group: Some(BuiltinGroup::Strings),
+            assumed_type: Polytype::unquantified(
+                Type::Function {
+                    parameter: Type::Char.into(),
+                    body: Type::Integer.into(),
+                }
+                .into(),
+            ),
+            implementation: builtin_char_to_int(),
+        },
+        Builtin {
+            name: &NAME_INT_TO_CHAR,
+            group: Some(BuiltinGroup::Strings),
             assumed_type: Polytype::unquantified(
                 Type::Function {
                     parameter: Type::Integer.into(),
+                    body: Type::Char.into(),
+                }
+                .into(),
+            ),
+            implementation: builtin_int_to_char(),
+        },
+        Builtin {
+            name: &NAME_CONS,
+            group: Some(BuiltinGroup::Lists),
+            assumed_type: list_type(|element, list| {
+                Type::Function {
+                    parameter: element,
+                    body: Type::Function {
+                        parameter: list.clone(),
+                        body: list,
+                    }
+                    .into(),
+                }
+                .into()
+            }),
+            implementation: builtin_cons(),
+        },
+        Builtin {
+            name: &NAME_HEAD,
+            group: Some(BuiltinGroup::Lists),
+            assumed_type: list_type(|element, list| {
+                Type::Function {
+                    parameter: list,
+                    body: element,
+                }
+                .into()
+            }),
+            implementation: builtin_match_list(empty_list_error(NAME_HEAD.clone()), |head, _| head),
+        },
+        Builtin {
+            name: &NAME_TAIL,
+            group: Some(BuiltinGroup::Lists),
+            assumed_type: list_type(|_, list| {
+                Type::Function {
+                    parameter: list.clone(),
+                    body: list,
+                }
+                .into()
+            }),
+            implementation: builtin_match_list(empty_list_error(NAME_TAIL.clone()), |_, tail| tail),
+        },
+        Builtin {
+            name: &NAME_IS_EMPTY,
+            group: Some(BuiltinGroup::Lists),
+            assumed_type: list_type(|_, list| {
+                Type::Function {
+                    parameter: list,
+                    body: Type::Boolean.into(),
+                }
+                .into()
+            }),
+            implementation: builtin_match_list(
+                Expr::new(None, Expression::Primitive(Primitive::Boolean(true))),
+                |_, _| Expr::new(None, Expression::Primitive(Primitive::Boolean(false))),
+            ),
+        },
+        Builtin {
+            name: &NAME_LIST_LENGTH,
+            group: Some(BuiltinGroup::Lists),
+            assumed_type: list_type(|_, list| {
+                Type::Function {
+                    parameter: list,
+                    body: Type::Integer.into(),
+                }
+                .into()
+            }),
+            implementation: builtin_list_length(),
+        },
+        Builtin {
+            name: &NAME_MAP,
+            group: Some(BuiltinGroup::Lists),
+            assumed_type: {
+                let input_variable = TypeVariable::new_from_str("a");
+                let output_variable = TypeVariable::new_from_str("b");
+                let input: Monotype = Type::Variable(input_variable.clone()).into();
+                let output: Monotype = Type::Variable(output_variable.clone()).into();
+                Polytype {
+                    quantifiers: vec![input_variable, output_variable],
+                    mono: binary_function_type(
+                        Type::Function {
+                            parameter: input.clone(),
+                            body: output.clone(),
+                        }
+                        .into(),
+                        Type::List(input).into(),
+                        Type::List(output).into(),
+                    ),
+                }
+            },
+            implementation: builtin_map(),
+        },
+        Builtin {
+            name: &NAME_FILTER,
+            group: Some(BuiltinGroup::Lists),
+            assumed_type: list_type(|element, list| {
+                binary_function_type(
+                    Type::Function {
+                        parameter: element,
+                        body: Type::Boolean.into(),
+                    }
+                    .into(),
+                    list.clone(),
+                    list,
+                )
+            }),
+            implementation: builtin_filter(),
+        },
+        Builtin {
+            name: &NAME_FOLD,
+            group: Some(BuiltinGroup::Lists),
+            assumed_type: {
+                let accumulator_variable = TypeVariable::new_from_str("b");
+                let accumulator: Monotype = Type::Variable(accumulator_variable.clone()).into();
+                let mut polytype = list_type(|element, list| {
+                    binary_function_type(
+                        binary_function_type(accumulator.clone(), element, accumulator.clone()),
+                        accumulator.clone(),
+                        Type::Function {
+                            parameter: list,
+                            body: accumulator,
+                        }
+                        .into(),
+                    )
+                });
+                polytype.quantifiers.push(accumulator_variable);
+                polytype
+            },
+            implementation: builtin_fold(),
+        },
+        Builtin {
+            name: &NAME_RANGE,
+            group: Some(BuiltinGroup::Lists),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::List(Type::Integer.into()).into(),
+            )),
+            implementation: builtin_range(),
+        },
+        Builtin {
+            name: &NAME_SOME,
+            group: Some(BuiltinGroup::Options),
+            assumed_type: option_type(|element, option| {
+                Type::Function {
+                    parameter: element,
+                    body: option,
+                }
+                .into()
+            }),
+            implementation: builtin_some(),
+        },
+        Builtin {
+            name: &NAME_NONE,
+            group: Some(BuiltinGroup::Options),
+            assumed_type: option_type(|_, option| option),
+            implementation: option_none(),
+        },
+        Builtin {
+            name: &NAME_MAP_OPTION,
+            group: Some(BuiltinGroup::Options),
+            assumed_type: {
+                let input_variable = TypeVariable::new_from_str("a");
+                let output_variable = TypeVariable::new_from_str("b");
+                let input: Monotype = Type::Variable(input_variable.clone()).into();
+                let output: Monotype = Type::Variable(output_variable.clone()).into();
+                Polytype {
+                    quantifiers: vec![input_variable, output_variable],
+                    mono: Type::Function {
+                        parameter: Type::Function {
+                            parameter: input.clone(),
+                            body: output.clone(),
+                        }
+                        .into(),
+                        body: Type::Function {
+                            parameter: Type::Option(input).into(),
+                            body: Type::Option(output).into(),
+                        }
+                        .into(),
+                    }
+                    .into(),
+                }
+            },
+            implementation: builtin_map_option(),
+        },
+        Builtin {
+            name: &NAME_UNWRAP_OR,
+            group: Some(BuiltinGroup::Options),
+            assumed_type: option_type(|element, option| {
+                Type::Function {
+                    parameter: element.clone(),
                     body: Type::Function {
-                        parameter: Type::Integer.into(),
-                        body: Type::Integer.into(),
+                        parameter: option,
+                        body: element,
                     }
                     .into(),
                 }
+                .into()
+            }),
+            implementation: builtin_unwrap_or(),
+        },
+        Builtin {
+            name: &NAME_OK,
+            group: Some(BuiltinGroup::Results),
+            assumed_type: result_type(|ok, _, result| {
+                Type::Function {
+                    parameter: ok,
+                    body: result,
+                }
+                .into()
+            }),
+            implementation: builtin_result_constructor(true),
+        },
+        Builtin {
+            name: &NAME_ERR,
+            group: Some(BuiltinGroup::Results),
+            assumed_type: result_type(|_, error, result| {
+                Type::Function {
+                    parameter: error,
+                    body: result,
+                }
+                .into()
+            }),
+            implementation: builtin_result_constructor(false),
+        },
+        Builtin {
+            name: &NAME_AND_THEN,
+            group: Some(BuiltinGroup::Results),
+            assumed_type: {
+                let next_variable = TypeVariable::new_from_str("b");
+                let next_ok: Monotype = Type::Variable(next_variable.clone()).into();
+                let mut polytype = result_type(|ok, error, result| {
+                    let next_result: Monotype = Type::Result { ok: next_ok, error }.into();
+                    binary_function_type(
+                        result,
+                        Type::Function {
+                            parameter: ok,
+                            body: next_result.clone(),
+                        }
+                        .into(),
+                        next_result,
+                    )
+                });
+                polytype.quantifiers.push(next_variable);
+                polytype
+            },
+            implementation: builtin_and_then(),
+        },
+        Builtin {
+            name: &NAME_MAP_RESULT,
+            group: Some(BuiltinGroup::Results),
+            assumed_type: {
+                let next_variable = TypeVariable::new_from_str("b");
+                let next_ok: Monotype = Type::Variable(next_variable.clone()).into();
+                let mut polytype = result_type(|ok, error, result| {
+                    binary_function_type(
+                        Type::Function {
+                            parameter: ok,
+                            body: next_ok.clone(),
+                        }
+                        .into(),
+                        result,
+                        Type::Result { ok: next_ok, error }.into(),
+                    )
+                });
+                polytype.quantifiers.push(next_variable);
+                polytype
+            },
+            implementation: builtin_map_result(),
+        },
+        Builtin {
+            name: &NAME_UNWRAP_OR_ELSE,
+            group: Some(BuiltinGroup::Results),
+            assumed_type: result_type(|ok, error, result| {
+                binary_function_type(
+                    Type::Function {
+                        parameter: error,
+                        body: ok.clone(),
+                    }
+                    .into(),
+                    result,
+                    ok,
+                )
+            }),
+            implementation: builtin_unwrap_or_else(),
+        },
+        Builtin {
+            name: &NAME_CHECKED_DIVIDE,
+            group: Some(BuiltinGroup::Integers),
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Result {
+                    ok: Type::Integer.into(),
+                    error: Type::String.into(),
+                }
+                .into(),
+            )),
+            implementation: builtin_checked_divide(),
+        },
+        Builtin {
+            name: &NAME_ASSERT,
+            group: Some(BuiltinGroup::Debugging),
+            assumed_type: Polytype::unquantified(
+                Type::Function {
+                    parameter: Type::Boolean.into(),
+                    body: Type::Tuple(vec![]).into(),
+                }
                 .into(),
             ),
-            implementation: builtin_multiply(),
+            implementation: builtin_assert(),
         },
         Builtin {
             name: &NAME_TRACE,
+            group: Some(BuiltinGroup::Debugging),
             assumed_type: {
                 let variable = TypeVariable::new_from_str("a");
                 let variable_ref: Monotype = Type::Variable(variable.clone()).into();
@@ -105,52 +1033,475 @@ fn all() -> Vec<Builtin> {
             },
             implementation: builtin_trace(),
         },
-    ]
+    ];
+    if capabilities.print {
+        builtins.push(Builtin {
+            name: &NAME_PRINT,
+            group: None,
+            assumed_type: Polytype::unquantified(
+                Type::Function {
+                    parameter: Type::String.into(),
+                    body: Type::String.into(),
+                }
+                .into(),
+            ),
+            implementation: builtin_print(),
+        });
+    }
+    if let Some(seed) = capabilities.random_seed {
+        builtins.push(Builtin {
+            name: &NAME_RANDOM,
+            group: None,
+            assumed_type: Polytype::unquantified(binary_operation_type(
+                Type::Integer.into(),
+                Type::Tuple(vec![Type::Integer.into(), Type::Integer.into()]).into(),
+            )),
+            implementation: builtin_random(),
+        });
+        builtins.push(Builtin {
+            name: &NAME_SEED,
+            group: None,
+            assumed_type: Polytype::unquantified(Type::Integer.into()),
+            implementation: Expr::new(
+                None,
+                Expression::Primitive(Primitive::Integer((seed as i64).into())),
+            ),
+        });
+    }
+    builtins
+}
+
+/// The type of a curried function with two operands of the same type.
+fn binary_operation_type(operand: Monotype, result: Monotype) -> Monotype {
+    Type::Function {
+        parameter: operand.clone(),
+        body: Type::Function {
+            parameter: operand,
+            body: result,
+        }
+        .into(),
+    }
+    .into()
+}
+
+/// The type of a curried function with two operands of different types.
+fn binary_function_type(left: Monotype, right: Monotype, result: Monotype) -> Monotype {
+    Type::Function {
+        parameter: left,
+        body: Type::Function {
+            parameter: right,
+            body: result,
+        }
+        .into(),
+    }
+    .into()
+}
+
+/// The type of a comparison, which accepts two values of any (matching) type.
+fn comparison_type() -> Polytype {
+    let variable = TypeVariable::new_comparable("a");
+    let variable_ref: Monotype = Type::Variable(variable.clone()).into();
+    Polytype {
+        quantifiers: vec![variable],
+        mono: binary_operation_type(variable_ref, Type::Boolean.into()),
+    }
+}
+
+/// A type involving a list of any element type.
+///
+/// The type is constructed from the element type and the list type.
+fn list_type(construct: impl FnOnce(Monotype, Monotype) -> Monotype) -> Polytype {
+    let variable = TypeVariable::new_from_str("a");
+    let element: Monotype = Type::Variable(variable.clone()).into();
+    let list: Monotype = Type::List(element.clone()).into();
+    Polytype {
+        quantifiers: vec![variable],
+        mono: construct(element, list),
+    }
+}
+
+/// A type involving an optional value of any type.
+///
+/// The type is constructed from the value type and the option type.
+fn option_type(construct: impl FnOnce(Monotype, Monotype) -> Monotype) -> Polytype {
+    let variable = TypeVariable::new_from_str("a");
+    let element: Monotype = Type::Variable(variable.clone()).into();
+    let option: Monotype = Type::Option(element.clone()).into();
+    Polytype {
+        quantifiers: vec![variable],
+        mono: construct(element, option),
+    }
+}
+
+/// A type involving a result, which may succeed or fail, of any types.
+///
+/// The type is constructed from the success type, the error type, and the
+/// result type.
+fn result_type(construct: impl FnOnce(Monotype, Monotype, Monotype) -> Monotype) -> Polytype {
+    let ok_variable = TypeVariable::new_from_str("a");
+    let error_variable = TypeVariable::new_from_str("e");
+    let ok: Monotype = Type::Variable(ok_variable.clone()).into();
+    let error: Monotype = Type::Variable(error_variable.clone()).into();
+    let result: Monotype = Type::Result {
+        ok: ok.clone(),
+        error: error.clone(),
+    }
+    .into();
+    Polytype {
+        quantifiers: vec![ok_variable, error_variable],
+        mono: construct(ok, error, result),
+    }
 }
 
 /// Implements addition, with the `+` operator.
 fn builtin_add() -> Expr {
-    builtin_infix_math("+", |x, y| x + y)
+    builtin_infix_arithmetic("+", Integer::checked_add, |x, y| x + y)
 }
 
 /// Implements subtraction, with the `-` operator.
 fn builtin_subtract() -> Expr {
-    builtin_infix_math("-", |x, y| x - y)
+    builtin_infix_arithmetic("-", Integer::checked_sub, |x, y| x - y)
 }
 
 /// Implements multiplication, with the `*` operator.
 fn builtin_multiply() -> Expr {
-    builtin_infix_math("*", |x, y| x * y)
+    builtin_infix_arithmetic("*", Integer::checked_mul, |x, y| x * y)
 }
 
-/// Generic implementation of infix mathematical operations.
-fn builtin_infix_math<Op>(name: &str, operate: Op) -> Expr
-where
-    Op: Fn(Integer, Integer) -> Integer + 'static,
-{
-    let parameter_left = Identifier::name_from_str("left").unwrap();
-    let parameter_right = Identifier::name_from_str("right").unwrap();
+/// Implements integer division, with the `/` operator.
+///
+/// Fails if the divisor is zero.
+fn builtin_divide() -> Expr {
+    builtin_infix("/", |left, right| match (left, right) {
+        (Primitive::Integer(left), Primitive::Integer(right)) => left
+            .checked_div(&right)
+            .map(Primitive::Integer)
+            .ok_or(Error::DivisionByZero { span: None }),
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Implements the remainder of integer division, with the `%` operator.
+///
+/// Fails if the divisor is zero.
+fn builtin_modulo() -> Expr {
+    builtin_infix("%", |left, right| match (left, right) {
+        (Primitive::Integer(left), Primitive::Integer(right)) => left
+            .checked_rem(&right)
+            .map(Primitive::Integer)
+            .ok_or(Error::DivisionByZero { span: None }),
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Implements bit shifts, with the `<<` and `>>` operators.
+///
+/// Fails if the shift amount is negative.
+fn builtin_shift(name: &str, shift: fn(&Integer, &Integer) -> Option<Integer>) -> Expr {
+    builtin_infix(name, move |left, right| match (left, right) {
+        (Primitive::Integer(left), Primitive::Integer(right)) => shift(&left, &right)
+            .map(Primitive::Integer)
+            .ok_or(Error::InvalidShiftAmount {
+                span: None,
+                amount: right,
+            }),
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Computes the absolute value of an integer.
+///
+/// As with other arithmetic, this can overflow.
+fn builtin_abs() -> Expr {
+    builtin_native_in_context(NAME_ABS.clone(), ["x"], |context, [x]| match x {
+        Primitive::Integer(x) => overflowing(context, || x.checked_abs(), || x.abs()),
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Computes the sign of an integer: `-1` if it is negative, `0` if it is zero,
+/// and `1` if it is positive.
+fn builtin_sign() -> Expr {
+    builtin_native(NAME_SIGN.clone(), ["x"], |[x]| match x {
+        Primitive::Integer(x) => Ok(Primitive::Integer(x.signum())),
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Picks the lesser or greater of two integers, preferring the left if they
+/// are equal.
+fn builtin_min_max(unique_name: Identifier, preferred: Ordering) -> Expr {
+    builtin_native(
+        unique_name,
+        ["left", "right"],
+        move |[left, right]| match (left, right) {
+            (Primitive::Integer(left), Primitive::Integer(right)) => {
+                if right.cmp(&left) == preferred {
+                    Ok(Primitive::Integer(right))
+                } else {
+                    Ok(Primitive::Integer(left))
+                }
+            }
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// Raises an integer to the power of another.
+///
+/// Fails if the exponent is negative. As with other arithmetic, this can
+/// overflow.
+fn builtin_pow() -> Expr {
+    builtin_native_in_context(
+        NAME_POW.clone(),
+        ["base", "exponent"],
+        |context, [base, exponent]| match (base, exponent) {
+            (Primitive::Integer(base), Primitive::Integer(exponent)) => {
+                match u32::try_from(&exponent) {
+                    Ok(exponent) => overflowing(
+                        context,
+                        || base.checked_pow(exponent),
+                        || base.pow(exponent),
+                    ),
+                    Err(()) => Err(Error::InvalidExponent {
+                        span: None,
+                        exponent,
+                    }),
+                }
+            }
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// Computes the greatest common divisor of two integers.
+fn builtin_gcd() -> Expr {
+    builtin_native(
+        NAME_GCD.clone(),
+        ["left", "right"],
+        |[left, right]| match (left, right) {
+            (Primitive::Integer(left), Primitive::Integer(right)) => {
+                Ok(Primitive::Integer(left.gcd(&right)))
+            }
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// Implements string concatenation, with the `++` operator.
+fn builtin_concatenate() -> Expr {
+    builtin_infix("++", |left, right| match (left, right) {
+        (Primitive::String(left), Primitive::String(right)) => {
+            Ok(Primitive::String(Arc::new(format!("{}{}", left, right))))
+        }
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Computes the length of a string, in characters.
+fn builtin_string_length() -> Expr {
+    builtin_native(
+        NAME_STRING_LENGTH.clone(),
+        ["string"],
+        |[string]| match string {
+            Primitive::String(string) => Ok(Primitive::Integer(string.chars().count().into())),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// Extracts the characters of a string from the start index (inclusive) to
+/// the end index (exclusive).
+///
+/// Indices are clamped to the bounds of the string, so this never fails.
+fn builtin_substring() -> Expr {
+    builtin_native(
+        NAME_SUBSTRING.clone(),
+        ["string", "start", "end"],
+        |[string, start, end]| match (string, start, end) {
+            (Primitive::String(string), Primitive::Integer(start), Primitive::Integer(end)) => {
+                let length = string.chars().count();
+                let clamp = |index: Integer| {
+                    if index < Integer::from(0) {
+                        0
+                    } else {
+                        usize::try_from(&index).map_or(length, |index| index.min(length))
+                    }
+                };
+                let start = clamp(start);
+                let end = clamp(end).max(start);
+                Ok(Primitive::String(Arc::new(
+                    string.chars().skip(start).take(end - start).collect(),
+                )))
+            }
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// Splits a string into the pieces between each occurrence of the separator.
+///
+/// There is always at least one piece. If the separator is empty, the string
+/// is not split at all.
+fn builtin_split() -> Expr {
+    let go = Identifier::name_from_str("go").unwrap();
+    builtin_function(["separator", "string"], |[separator, string]| {
+        let step = builtin_function(["remaining"], |[remaining]| {
+            let split_with =
+                |native: Expr| apply(apply(native, separator.clone()), remaining.clone());
+            match_boolean(
+                split_with(builtin_split_found()),
+                cons(
+                    split_with(builtin_split_first()),
+                    apply(reference(&go), split_with(builtin_split_rest())),
+                ),
+                cons(remaining.clone(), empty_list()),
+            )
+        });
+        Expr::new(
+            None,
+            Expression::AssignRecursive(AssignRecursive {
+                bindings: vec![(go.clone(), step)],
+                inner: apply(reference(&go), string),
+            }),
+        )
+    })
+}
+
+/// Determines whether a string can be split by a non-empty separator.
+fn builtin_split_found() -> Expr {
+    builtin_native(
+        Identifier::name_from_str("split_found").unwrap(),
+        ["separator", "string"],
+        |[separator, string]| match (separator, string) {
+            (Primitive::String(separator), Primitive::String(string)) => Ok(Primitive::Boolean(
+                !separator.is_empty() && string.contains(separator.as_str()),
+            )),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// The part of a string before the first occurrence of the separator.
+fn builtin_split_first() -> Expr {
+    builtin_native(
+        Identifier::name_from_str("split_first").unwrap(),
+        ["separator", "string"],
+        |[separator, string]| match (separator, string) {
+            (Primitive::String(separator), Primitive::String(string)) => {
+                let first = string.split(separator.as_str()).next().unwrap_or_default();
+                Ok(Primitive::String(Arc::new(first.to_owned())))
+            }
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// The part of a string after the first occurrence of the separator.
+fn builtin_split_rest() -> Expr {
+    builtin_native(
+        Identifier::name_from_str("split_rest").unwrap(),
+        ["separator", "string"],
+        |[separator, string]| match (separator, string) {
+            (Primitive::String(separator), Primitive::String(string)) => {
+                let rest = string
+                    .split_once(separator.as_str())
+                    .map_or("", |(_, rest)| rest);
+                Ok(Primitive::String(Arc::new(rest.to_owned())))
+            }
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// Generic implementation of a transformation from one string to another.
+fn builtin_map_string(unique_name: Identifier, transform: fn(&str) -> String) -> Expr {
+    builtin_native(unique_name, ["string"], move |[string]| match string {
+        Primitive::String(string) => Ok(Primitive::String(Arc::new(transform(&string)))),
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Parses a string as a decimal integer, producing an empty option if it is
+/// not one.
+fn builtin_parse_int() -> Expr {
+    builtin_function(["string"], |[string]| {
+        let parse_with = |native: Expr| apply(native, string.clone());
+        match_boolean(
+            parse_with(builtin_parse_int_native("is_int", |parsed| {
+                Ok(Primitive::Boolean(parsed.is_some()))
+            })),
+            option_some(parse_with(builtin_parse_int_native(
+                "parse_int",
+                |parsed| {
+                    parsed
+                        .map(Primitive::Integer)
+                        .ok_or(Error::InvalidPrimitive { span: None })
+                },
+            ))),
+            option_none(),
+        )
+    })
+}
+
+/// Parses a string as an integer, and then describes the outcome.
+fn builtin_parse_int_native(
+    name: &str,
+    describe: fn(Option<Integer>) -> Result<Primitive>,
+) -> Expr {
+    builtin_native(
+        Identifier::name_from_str(name).unwrap(),
+        ["string"],
+        move |[string]| match string {
+            Primitive::String(string) => describe(string.parse().ok()),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// Converts a character to its Unicode code point.
+fn builtin_char_to_int() -> Expr {
+    builtin_native(NAME_CHAR_TO_INT.clone(), ["char"], |[c]| match c {
+        Primitive::Char(c) => Ok(Primitive::Integer(u32::from(c).into())),
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Converts a Unicode code point to the corresponding character.
+///
+/// Fails if the integer is not a valid Unicode scalar value.
+fn builtin_int_to_char() -> Expr {
+    builtin_native(NAME_INT_TO_CHAR.clone(), ["code"], |[code]| match code {
+        Primitive::Integer(code) => u32::try_from(&code)
+            .ok()
+            .and_then(char::from_u32)
+            .map(Primitive::Char)
+            .ok_or(Error::InvalidCharacterCode { span: None, code }),
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Constructs a list from a head and a tail, with the `::` operator.
+fn builtin_cons() -> Expr {
+    let head = Identifier::name_from_str("head").unwrap();
+    let tail = Identifier::name_from_str("tail").unwrap();
     Expr::new(
         None,
         Expression::Function(Function {
-            parameter: parameter_left.clone(),
+            parameter: head.clone(),
+            parameter_type: None,
+            parameter_type_span: None,
             body: Expr::new(
                 None,
                 Expression::Function(Function {
-                    parameter: parameter_right.clone(),
+                    parameter: tail.clone(),
+                    parameter_type: None,
+                    parameter_type_span: None,
                     body: Expr::new(
                         None,
-                        Expression::Native(Native {
-                            unique_name: Identifier::operator_from_str(name).unwrap(),
-                            implementation: Rc::new(move |context| {
-                                let left = context.lookup_value(&parameter_left)?;
-                                let right = context.lookup_value(&parameter_right)?;
-                                match (left, right) {
-                                    (Primitive::Integer(left), Primitive::Integer(right)) => {
-                                        Ok(Primitive::Integer(operate(left, right)))
-                                    }
-                                }
-                            }),
+                        Expression::List(List::Cons {
+                            head: Expr::new(None, Expression::Identifier(head)),
+                            tail: Expr::new(None, Expression::Identifier(tail)),
                         }),
                     ),
                 }),
@@ -159,6 +1510,635 @@ where
     )
 }
 
+/// Generic implementation of a function that accepts a list and matches on it.
+///
+/// If the list is non-empty, the result is constructed from references to its
+/// head and tail.
+fn builtin_match_list<Construct>(if_empty: Expr, if_non_empty: Construct) -> Expr
+where
+    Construct: FnOnce(Expr, Expr) -> Expr,
+{
+    let list = Identifier::name_from_str("list").unwrap();
+    let head = Identifier::name_from_str("head").unwrap();
+    let tail = Identifier::name_from_str("tail").unwrap();
+    Expr::new(
+        None,
+        Expression::Function(Function {
+            parameter: list.clone(),
+            parameter_type: None,
+            parameter_type_span: None,
+            body: Expr::new(
+                None,
+                Expression::Match(Match {
+                    value: Expr::new(None, Expression::Identifier(list)),
+                    patterns: [
+                        PatternMatch {
+                            pattern: Pattern::Cons {
+                                head: Pattern::Variable(head.clone()).into(),
+                                tail: Pattern::Variable(tail.clone()).into(),
+                            },
+                            pattern_span: None,
+                            result: if_non_empty(
+                                Expr::new(None, Expression::Identifier(head)),
+                                Expr::new(None, Expression::Identifier(tail)),
+                            ),
+                        },
+                        PatternMatch {
+                            pattern: Pattern::Anything,
+                            pattern_span: None,
+                            result: if_empty,
+                        },
+                    ]
+                    .into(),
+                }),
+            ),
+        }),
+    )
+}
+
+/// Fails, because the list is empty.
+fn empty_list_error(unique_name: Identifier) -> Expr {
+    Expr::new(
+        None,
+        Expression::Native(Native {
+            unique_name,
+            implementation: Arc::new(|_| Err(Error::EmptyList { span: None })),
+        }),
+    )
+}
+
+/// Counts the elements of a list.
+fn builtin_list_length() -> Expr {
+    builtin_function(["list"], |[list]| {
+        let count = builtin_function(["count", "element"], |[count, _]| {
+            apply(apply(builtin_add(), count), integer(1))
+        });
+        apply(apply(apply(builtin_fold(), count), integer(0)), list)
+    })
+}
+
+/// Applies a function to each element of a list.
+fn builtin_map() -> Expr {
+    let go = Identifier::name_from_str("go").unwrap();
+    builtin_function(["f"], |[f]| {
+        recursive(
+            go.clone(),
+            builtin_match_list(empty_list(), |head, tail| {
+                cons(apply(f, head), apply(reference(&go), tail))
+            }),
+        )
+    })
+}
+
+/// Keeps the elements of a list which satisfy the predicate.
+fn builtin_filter() -> Expr {
+    let go = Identifier::name_from_str("go").unwrap();
+    builtin_function(["predicate"], |[predicate]| {
+        recursive(
+            go.clone(),
+            builtin_match_list(empty_list(), |head, tail| {
+                let rest = apply(reference(&go), tail);
+                match_boolean(
+                    apply(predicate, head.clone()),
+                    cons(head, rest.clone()),
+                    rest,
+                )
+            }),
+        )
+    })
+}
+
+/// Combines the elements of a list from left to right, starting with the
+/// initial value.
+///
+/// The accumulated value is evaluated at each step, so that folding over a
+/// long list does not build up a long chain of unevaluated computations.
+fn builtin_fold() -> Expr {
+    let go = Identifier::name_from_str("go").unwrap();
+    let next = Identifier::name_from_str("next").unwrap();
+    builtin_function(["f", "initial", "elements"], |[f, initial, elements]| {
+        let step = builtin_function(["accumulator"], |[accumulator]| {
+            builtin_match_list(accumulator.clone(), |head, tail| {
+                Expr::new(
+                    None,
+                    Expression::Assign(Assign {
+                        name: next.clone(),
+                        value: apply(apply(f, accumulator), head),
+                        inner: Expr::new(
+                            None,
+                            Expression::Sequence(Sequence {
+                                first: reference(&next),
+                                second: apply(apply(reference(&go), reference(&next)), tail),
+                            }),
+                        ),
+                    }),
+                )
+            })
+        });
+        Expr::new(
+            None,
+            Expression::AssignRecursive(AssignRecursive {
+                bindings: vec![(go.clone(), step)],
+                inner: apply(apply(reference(&go), initial), elements),
+            }),
+        )
+    })
+}
+
+/// Constructs the list of integers from the start (inclusive) to the end
+/// (exclusive).
+fn builtin_range() -> Expr {
+    let go = Identifier::name_from_str("go").unwrap();
+    builtin_function(["start", "end"], |[start, end]| {
+        let step = builtin_function(["n"], |[n]| {
+            match_boolean(
+                apply(
+                    apply(
+                        builtin_comparison("<", |ordering| ordering.is_lt()),
+                        n.clone(),
+                    ),
+                    end,
+                ),
+                cons(
+                    n.clone(),
+                    apply(reference(&go), apply(apply(builtin_add(), n), integer(1))),
+                ),
+                empty_list(),
+            )
+        });
+        Expr::new(
+            None,
+            Expression::AssignRecursive(AssignRecursive {
+                bindings: vec![(go.clone(), step)],
+                inner: apply(reference(&go), start),
+            }),
+        )
+    })
+}
+
+/// Constructs an option with a value.
+///
+/// Options are represented as lists of at most one element, so they can share
+/// the list machinery in every evaluator. Only the type differs, and
+/// [`Pattern::encoded`] matches option patterns against this representation.
+fn option_some(value: Expr) -> Expr {
+    Expr::new(
+        None,
+        Expression::List(List::Cons {
+            head: value,
+            tail: option_none(),
+        }),
+    )
+}
+
+/// Constructs an option without a value.
+fn option_none() -> Expr {
+    Expr::new(None, Expression::List(List::Empty))
+}
+
+/// Wraps a value in an option.
+fn builtin_some() -> Expr {
+    let value = Identifier::name_from_str("value").unwrap();
+    Expr::new(
+        None,
+        Expression::Function(Function {
+            parameter: value.clone(),
+            parameter_type: None,
+            parameter_type_span: None,
+            body: option_some(Expr::new(None, Expression::Identifier(value))),
+        }),
+    )
+}
+
+/// Applies a function to the value inside an option, if there is one.
+fn builtin_map_option() -> Expr {
+    let function = Identifier::name_from_str("function").unwrap();
+    Expr::new(
+        None,
+        Expression::Function(Function {
+            parameter: function.clone(),
+            parameter_type: None,
+            parameter_type_span: None,
+            body: builtin_match_list(option_none(), |value, _| {
+                option_some(Expr::new(
+                    None,
+                    Expression::Apply(Apply {
+                        function: Expr::new(None, Expression::Identifier(function)),
+                        argument: value,
+                    }),
+                ))
+            }),
+        }),
+    )
+}
+
+/// Extracts the value inside an option, or returns the default if there is
+/// none.
+fn builtin_unwrap_or() -> Expr {
+    let default = Identifier::name_from_str("default").unwrap();
+    Expr::new(
+        None,
+        Expression::Function(Function {
+            parameter: default.clone(),
+            parameter_type: None,
+            parameter_type_span: None,
+            body: builtin_match_list(
+                Expr::new(None, Expression::Identifier(default)),
+                |value, _| value,
+            ),
+        }),
+    )
+}
+
+/// Constructs a result, which is either a success or a failure.
+///
+/// Results are represented as a tuple of a boolean tag, which is `true` on
+/// success, and the value or error. Only the type tracks what the value is,
+/// and [`Pattern::encoded`] matches result patterns against this
+/// representation.
+fn result_of(success: bool, value: Expr) -> Expr {
+    Expr::new(
+        None,
+        Expression::Tuple(Tuple {
+            elements: vec![
+                Expr::new(None, Expression::Primitive(Primitive::Boolean(success))),
+                value,
+            ],
+        }),
+    )
+}
+
+/// Implements `ok` (for success) or `err` (for failure), which wrap a value
+/// in a result.
+fn builtin_result_constructor(success: bool) -> Expr {
+    builtin_function(["value"], |[value]| result_of(success, value))
+}
+
+/// Matches on a result.
+///
+/// The corresponding case is constructed from a reference to the value (on
+/// success) or error (on failure), and a reference to the result itself.
+fn match_result(
+    result: Expr,
+    on_success: impl FnOnce(Expr) -> Expr,
+    on_failure: impl FnOnce(Expr, Expr) -> Expr,
+) -> Expr {
+    let value = Identifier::name_from_str("value").unwrap();
+    let value_ref = || Expr::new(None, Expression::Identifier(value.clone()));
+    Expr::new(
+        None,
+        Expression::Match(Match {
+            value: result.clone(),
+            patterns: [
+                PatternMatch {
+                    pattern: Pattern::Tuple(vec![
+                        Pattern::Primitive(Primitive::Boolean(true)),
+                        Pattern::Variable(value.clone()),
+                    ]),
+                    pattern_span: None,
+                    result: on_success(value_ref()),
+                },
+                PatternMatch {
+                    pattern: Pattern::Tuple(vec![
+                        Pattern::Anything,
+                        Pattern::Variable(value.clone()),
+                    ]),
+                    pattern_span: None,
+                    result: on_failure(value_ref(), result),
+                },
+            ]
+            .into(),
+        }),
+    )
+}
+
+/// Passes the value of a successful result to the next function, or
+/// short-circuits with the error of a failed result.
+///
+/// The result is accepted first, so that `try` expressions can be desugared
+/// into a call to this function.
+fn builtin_and_then() -> Expr {
+    builtin_function(["result", "next"], |[result, next]| {
+        match_result(result, |value| apply(next, value), |_, result| result)
+    })
+}
+
+/// Applies a function to the value of a successful result.
+fn builtin_map_result() -> Expr {
+    builtin_function(["function", "result"], |[function, result]| {
+        match_result(
+            result,
+            |value| result_of(true, apply(function, value)),
+            |_, result| result,
+        )
+    })
+}
+
+/// Extracts the value of a successful result, or computes a value from the
+/// error of a failed result.
+fn builtin_unwrap_or_else() -> Expr {
+    builtin_function(["handler", "result"], |[handler, result]| {
+        match_result(result, |value| value, |error, _| apply(handler, error))
+    })
+}
+
+/// Implements integer division, failing with an error result instead of
+/// aborting if the divisor is zero.
+fn builtin_checked_divide() -> Expr {
+    builtin_function(["left", "right"], |[left, right]| {
+        let quotient = apply(apply(builtin_divide(), left), right.clone());
+        Expr::new(
+            None,
+            Expression::Match(Match {
+                value: right,
+                patterns: [
+                    PatternMatch {
+                        pattern: Pattern::Primitive(Primitive::Integer(0.into())),
+                        pattern_span: None,
+                        result: result_of(
+                            false,
+                            Expr::new(
+                                None,
+                                Expression::Primitive(Primitive::String(Arc::new(
+                                    "division by zero".to_owned(),
+                                ))),
+                            ),
+                        ),
+                    },
+                    PatternMatch {
+                        pattern: Pattern::Anything,
+                        pattern_span: None,
+                        result: result_of(true, quotient),
+                    },
+                ]
+                .into(),
+            }),
+        )
+    })
+}
+
+/// Refers to a bound name.
+fn reference(name: &Identifier) -> Expr {
+    Expr::new(None, Expression::Identifier(name.clone()))
+}
+
+/// An integer literal.
+fn integer(value: i32) -> Expr {
+    Expr::new(
+        None,
+        Expression::Primitive(Primitive::Integer(value.into())),
+    )
+}
+
+/// The empty list.
+fn empty_list() -> Expr {
+    Expr::new(None, Expression::List(List::Empty))
+}
+
+/// Constructs a list from a head and a tail.
+fn cons(head: Expr, tail: Expr) -> Expr {
+    Expr::new(None, Expression::List(List::Cons { head, tail }))
+}
+
+/// Binds a recursive value to a name, and then refers to it.
+fn recursive(name: Identifier, value: Expr) -> Expr {
+    Expr::new(
+        None,
+        Expression::AssignRecursive(AssignRecursive {
+            bindings: vec![(name.clone(), value)],
+            inner: reference(&name),
+        }),
+    )
+}
+
+/// Chooses between two expressions, depending on a boolean.
+fn match_boolean(value: Expr, if_true: Expr, if_false: Expr) -> Expr {
+    Expr::new(
+        None,
+        Expression::Match(Match {
+            value,
+            patterns: [
+                PatternMatch {
+                    pattern: Pattern::Primitive(Primitive::Boolean(true)),
+                    pattern_span: None,
+                    result: if_true,
+                },
+                PatternMatch {
+                    pattern: Pattern::Anything,
+                    pattern_span: None,
+                    result: if_false,
+                },
+            ]
+            .into(),
+        }),
+    )
+}
+
+/// Applies a function to an argument.
+fn apply(function: Expr, argument: Expr) -> Expr {
+    Expr::new(None, Expression::Apply(Apply { function, argument }))
+}
+
+/// Generic implementation of a function with one or more parameters, curried
+/// so that each parameter is bound by its own function.
+///
+/// The body is constructed from references to the parameters.
+fn builtin_function<const N: usize>(
+    parameter_names: [&str; N],
+    construct_body: impl FnOnce([Expr; N]) -> Expr,
+) -> Expr {
+    let parameters =
+        parameter_names.map(|parameter_name| Identifier::name_from_str(parameter_name).unwrap());
+    let body = construct_body(
+        parameters
+            .clone()
+            .map(|parameter| Expr::new(None, Expression::Identifier(parameter))),
+    );
+    parameters.into_iter().rev().fold(body, |body, parameter| {
+        Expr::new(
+            None,
+            Expression::Function(Function {
+                parameter,
+                parameter_type: None,
+                parameter_type_span: None,
+                body,
+            }),
+        )
+    })
+}
+
+/// Generic implementation of infix mathematical operations.
+fn builtin_infix_math<Op>(name: &str, operate: Op) -> Expr
+where
+    Op: Fn(Integer, Integer) -> Integer + Send + Sync + 'static,
+{
+    builtin_infix(name, move |left, right| match (left, right) {
+        (Primitive::Integer(left), Primitive::Integer(right)) => {
+            Ok(Primitive::Integer(operate(left, right)))
+        }
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Generic implementation of infix arithmetic on integers, which can overflow.
+///
+/// If operating on two small integers produces a result that is too large to
+/// be small, the context decides whether to promote it or to fail.
+fn builtin_infix_arithmetic(
+    name: &str,
+    checked: fn(&Integer, &Integer) -> Option<Integer>,
+    promoting: fn(&Integer, &Integer) -> Integer,
+) -> Expr {
+    builtin_native_in_context(
+        Identifier::operator_from_str(name).unwrap(),
+        ["left", "right"],
+        move |context, [left, right]| match (left, right) {
+            (Primitive::Integer(left), Primitive::Integer(right)) => overflowing(
+                context,
+                || checked(&left, &right),
+                || promoting(&left, &right),
+            ),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        },
+    )
+}
+
+/// Computes the result of an operation which can overflow, either promoting
+/// the result or failing, as the context decides.
+fn overflowing(
+    context: &dyn NativeContext,
+    checked: impl FnOnce() -> Option<Integer>,
+    promoting: impl FnOnce() -> Integer,
+) -> Result<Primitive> {
+    match context.integer_overflow() {
+        IntegerOverflow::Promote => Ok(Primitive::Integer(promoting())),
+        IntegerOverflow::Fail => checked()
+            .map(Primitive::Integer)
+            .ok_or(Error::IntegerOverflow { span: None }),
+    }
+}
+
+/// Generic implementation of infix mathematical operations on floats.
+fn builtin_infix_float_math<Op>(name: &str, operate: Op) -> Expr
+where
+    Op: Fn(Float, Float) -> Float + Send + Sync + 'static,
+{
+    builtin_infix(name, move |left, right| match (left, right) {
+        (Primitive::Float(left), Primitive::Float(right)) => {
+            Ok(Primitive::Float(operate(left, right)))
+        }
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Generic implementation of comparisons.
+///
+/// The predicate is applied to the ordering of the left operand with respect
+/// to the right.
+fn builtin_comparison<Predicate>(name: &str, predicate: Predicate) -> Expr
+where
+    Predicate: Fn(Ordering) -> bool + Send + Sync + 'static,
+{
+    builtin_infix(name, move |left, right| match left.partial_cmp(&right) {
+        Some(ordering) => Ok(Primitive::Boolean(predicate(ordering))),
+        None => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Generic implementation of infix operations on two primitive values.
+fn builtin_infix<Op>(name: &str, operate: Op) -> Expr
+where
+    Op: Fn(Primitive, Primitive) -> Result<Primitive> + Send + Sync + 'static,
+{
+    builtin_native(
+        Identifier::operator_from_str(name).unwrap(),
+        ["left", "right"],
+        move |[left, right]| operate(left, right),
+    )
+}
+
+/// Generic implementation of a native function with one or more primitive
+/// parameters, curried so that each parameter is bound by its own function.
+fn builtin_native<Op, const N: usize>(
+    unique_name: Identifier,
+    parameter_names: [&str; N],
+    operate: Op,
+) -> Expr
+where
+    Op: Fn([Primitive; N]) -> Result<Primitive> + Send + Sync + 'static,
+{
+    builtin_native_in_context(unique_name, parameter_names, move |_, values| {
+        operate(values)
+    })
+}
+
+/// As [`builtin_native`], but the implementation can also consult the context
+/// in which it is evaluated.
+fn builtin_native_in_context<Op, const N: usize>(
+    unique_name: Identifier,
+    parameter_names: [&str; N],
+    operate: Op,
+) -> Expr
+where
+    Op: Fn(&dyn NativeContext, [Primitive; N]) -> Result<Primitive> + Send + Sync + 'static,
+{
+    let parameters =
+        parameter_names.map(|parameter_name| Identifier::name_from_str(parameter_name).unwrap());
+    let native = Expr::new(
+        None,
+        Expression::Native(Native {
+            unique_name,
+            implementation: Arc::new({
+                let parameters = parameters.clone();
+                move |context| {
+                    let values = parameters
+                        .iter()
+                        .map(|parameter| context.lookup_value(parameter))
+                        .collect::<Result<Vec<_>>>()?;
+                    match values.try_into() {
+                        Ok(values) => operate(context, values),
+                        Err(_) => unreachable!("the number of values must match the parameters"),
+                    }
+                }
+            }),
+        }),
+    );
+    parameters
+        .into_iter()
+        .rev()
+        .fold(native, |body, parameter| {
+            Expr::new(
+                None,
+                Expression::Function(Function {
+                    parameter,
+                    parameter_type: None,
+                    parameter_type_span: None,
+                    body,
+                }),
+            )
+        })
+}
+
+/// Does nothing if the condition is true, and fails otherwise.
+///
+/// The result is the empty tuple, so assertions are usually sequenced before
+/// the expression they guard, e.g. `(assert (x > 0); x)`.
+fn builtin_assert() -> Expr {
+    builtin_function(["condition"], |[condition]| {
+        match_boolean(
+            condition,
+            Expr::new(None, Expression::Tuple(Tuple { elements: vec![] })),
+            Expr::new(
+                None,
+                Expression::Native(Native {
+                    unique_name: NAME_ASSERT.clone(),
+                    implementation: Arc::new(|_| Err(Error::AssertionFailed { span: None })),
+                }),
+            ),
+        )
+    })
+}
+
 /// A "trace" function, which prints the computed value.
 fn builtin_trace() -> Expr {
     let parameter = Identifier::name_from_str("param").unwrap();
@@ -166,11 +2146,13 @@ fn builtin_trace() -> Expr {
         None,
         Expression::Function(Function {
             parameter: parameter.clone(),
+            parameter_type: None,
+            parameter_type_span: None,
             body: Expr::new(
                 None,
                 Expression::Native(Native {
                     unique_name: Identifier::name_from_str("trace").unwrap(),
-                    implementation: Rc::new(move |context| {
+                    implementation: Arc::new(move |context| {
                         let value = context.lookup_value(&parameter)?;
                         eprintln!("trace: {}", value);
                         Ok(value)
@@ -180,3 +2162,94 @@ fn builtin_trace() -> Expr {
         }),
     )
 }
+
+/// A "print" function, which writes a string to stdout, followed by a newline,
+/// and then returns it.
+fn builtin_print() -> Expr {
+    builtin_native(NAME_PRINT.clone(), ["string"], |[string]| match string {
+        Primitive::String(ref contents) => {
+            println!("{}", contents);
+            Ok(string)
+        }
+        _ => Err(Error::InvalidPrimitive { span: None }),
+    })
+}
+
+/// Generates a pseudo-random integer between 0 (inclusive) and the bound
+/// (exclusive) from the state of the generator, along with the next state.
+///
+/// The state is a value like any other, so the same state always produces
+/// the same number, however the program is evaluated.
+fn builtin_random() -> Expr {
+    let value = builtin_native(
+        Identifier::name_from_str("random_value").unwrap(),
+        ["bound", "state"],
+        |[bound, state]| {
+            let mut generator = SplitMix64::from_state(state)?;
+            match bound {
+                Primitive::Integer(bound) => match i64::try_from(&bound) {
+                    Ok(limit) if limit > 0 => {
+                        let next = generator.next();
+                        // scales the 64-bit value down to the bound, rather
+                        // than taking the remainder, which would favour the
+                        // lowest values
+                        let value = (u128::from(next) * limit as u128) >> 64;
+                        Ok(Primitive::Integer((value as i64).into()))
+                    }
+                    _ => Err(Error::InvalidRandomBound { span: None, bound }),
+                },
+                _ => Err(Error::InvalidPrimitive { span: None }),
+            }
+        },
+    );
+    let next_state = builtin_native(
+        Identifier::name_from_str("random_next_state").unwrap(),
+        ["state"],
+        |[state]| {
+            let mut generator = SplitMix64::from_state(state)?;
+            generator.next();
+            Ok(generator.into_state())
+        },
+    );
+    builtin_function(["bound", "state"], |[bound, state]| {
+        Expr::new(
+            None,
+            Expression::Tuple(Tuple {
+                elements: vec![
+                    apply(apply(value, bound), state.clone()),
+                    apply(next_state, state),
+                ],
+            }),
+        )
+    })
+}
+
+/// The SplitMix64 pseudo-random number generator, which is small and fast,
+/// but not suitable for cryptography.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    /// Reads the state of the generator from a 64-bit integer.
+    fn from_state(state: Primitive) -> Result<Self> {
+        match state {
+            Primitive::Integer(state) => match i64::try_from(&state) {
+                Ok(value) => Ok(Self(value as u64)),
+                Err(()) => Err(Error::InvalidRandomState { span: None, state }),
+            },
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        }
+    }
+
+    /// Writes the state of the generator as a 64-bit integer.
+    fn into_state(self) -> Primitive {
+        Primitive::Integer((self.0 as i64).into())
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
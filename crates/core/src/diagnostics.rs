@@ -0,0 +1,44 @@
+//! Accumulates non-fatal diagnostics from each pass over a program.
+
+use crate::warning::Warning;
+
+/// A collection of warnings, gathered by the various passes over a program.
+///
+/// Unlike an [`Error`][crate::error::Error], a warning does not stop
+/// processing, so each pass pushes warnings here and carries on. The caller
+/// decides when to report them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diagnostics {
+    warnings: Vec<Warning>,
+}
+
+impl Diagnostics {
+    /// Constructs an empty collection of diagnostics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a warning.
+    pub fn warn(&mut self, warning: Warning) {
+        self.warnings.push(warning);
+    }
+
+    /// The warnings recorded so far, in the order they were recorded.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Returns `true` if nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Warning;
+    type IntoIter = std::vec::IntoIter<Warning>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.warnings.into_iter()
+    }
+}
@@ -1,5 +1,7 @@
 //! The set of possible interpretation errors.
 
+use crate::identifier::Identifier;
+use crate::primitive::Integer;
 use crate::span::Span;
 use crate::types;
 
@@ -13,30 +15,82 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Debug, Clone, PartialEq, thiserror::Error, miette::Diagnostic)]
 pub enum Error {
     #[error("Unexpected token: {token}")]
-    #[diagnostic(code(boo::lexer::unexpected_token))]
+    #[diagnostic(
+        code(boo::lexer::unexpected_token),
+        help("check for a typo, or for a string or character that is not closed")
+    )]
     UnexpectedToken {
         #[label("unexpected token")]
         span: Span,
         token: String,
     },
 
-    #[error("Parse error: expected one of {expected_tokens:?}")]
+    #[error("Invalid escape sequence: {sequence}")]
+    #[diagnostic(
+        code(boo::lexer::invalid_escape_sequence),
+        help(
+            r#"the escape sequences are `\"`, `\'`, `\\`, `\n`, `\r`, `\t`, and `\u{{...}}`, with between one and six hexadecimal digits"#
+        )
+    )]
+    InvalidEscapeSequence {
+        #[label("invalid escape sequence")]
+        span: Span,
+        sequence: String,
+    },
+
+    #[error("Invalid digit in a base-{radix} integer literal: {digit}")]
+    #[diagnostic(
+        code(boo::lexer::invalid_digit),
+        help(
+            "binary (`0b`) literals use the digits 0 and 1, octal (`0o`) literals use 0 to 7, and hexadecimal (`0x`) literals use 0 to 9 and a to f; underscores may only separate digits"
+        )
+    )]
+    InvalidDigit {
+        #[label("invalid digit")]
+        span: Span,
+        digit: String,
+        radix: u32,
+    },
+
+    #[error("Parse error: {}", parse_error_message(expected_tokens, context))]
     #[diagnostic(code(boo::parser::error))]
     ParseError {
-        #[label("{}", expected_one_of(expected_tokens))]
+        #[label("{}", expected_in_context(expected_tokens, context))]
         span: Span,
         expected_tokens: Vec<&'static str>,
+        context: Option<ParseContext>,
+        #[label("while parsing this")]
+        context_span: Option<Span>,
+        #[help]
+        hint: Option<ParseHint>,
+    },
+
+    #[error("`{keyword}` is a keyword and cannot be used as a variable name")]
+    #[diagnostic(
+        code(boo::parser::keyword_as_identifier),
+        help("try another name, such as `{keyword}_`")
+    )]
+    KeywordAsIdentifier {
+        #[label("this is a keyword")]
+        span: Span,
+        keyword: String,
     },
 
     #[error("Match expression without a base case")]
-    #[diagnostic(code(boo::verifier::match_without_base_case))]
+    #[diagnostic(
+        code(boo::verifier::match_without_base_case),
+        help("add a final pattern of `_`, which matches anything")
+    )]
     MatchWithoutBaseCase {
         #[label("match expression requires a base case")]
         span: Option<Span>,
     },
 
     #[error("Could not unify types")]
-    #[diagnostic(code(boo::type_checker::type_mismatch))]
+    #[diagnostic(
+        code(boo::type_checker::type_mismatch),
+        help("annotating expressions with the types you intend, such as `(x: Integer)`, can help to narrow down the mistake")
+    )]
     TypeMismatch {
         #[label("expected {expected_type} but got {actual_type}")]
         span: Option<Span>,
@@ -45,37 +99,447 @@ pub enum Error {
     },
 
     #[error("Could not unify types")]
-    #[diagnostic(code(boo::type_checker::type_unification_error))]
+    #[diagnostic(
+        code(boo::type_checker::type_unification_error),
+        help("these must have the same type; annotating expressions with the types you intend, such as `(x: Integer)`, can help to narrow down the mistake")
+    )]
     TypeUnificationError {
-        #[label("{left_type}")]
+        #[label("this is {left_type}")]
         left_span: Option<Span>,
         left_type: types::Monotype,
-        #[label("{right_type}")]
+        #[label("but this is {right_type}")]
         right_span: Option<Span>,
         right_type: types::Monotype,
     },
 
+    #[error("Could not unify types")]
+    #[diagnostic(
+        code(boo::type_checker::float_in_integer_operation),
+        help("`{operator}` only works on integers; floats have their own operators, `+.`, `-.`, `*.`, and `/.`, so try `{float_operator}` instead")
+    )]
+    FloatInIntegerOperation {
+        #[label("this is {left_type}")]
+        left_span: Option<Span>,
+        left_type: types::Monotype,
+        #[label("but this is {right_type}")]
+        right_span: Option<Span>,
+        right_type: types::Monotype,
+        operator: String,
+        float_operator: String,
+    },
+
+    #[error("Values of type {typ} cannot be compared")]
+    #[diagnostic(
+        code(boo::type_checker::not_comparable),
+        help("only integers, floats, booleans, characters, and strings can be compared")
+    )]
+    NotComparable {
+        #[label("this is {typ}")]
+        span: Option<Span>,
+        typ: types::Monotype,
+    },
+
+    #[error("Infinite type: {variable} would have to be equal to {typ}")]
+    #[diagnostic(
+        code(boo::type_checker::infinite_type),
+        help("{variable} would have to contain itself; this often means that a function is applied to itself")
+    )]
+    InfiniteType {
+        #[label("this is {left_type}")]
+        left_span: Option<Span>,
+        left_type: types::Monotype,
+        #[label("but this is {right_type}")]
+        right_span: Option<Span>,
+        right_type: types::Monotype,
+        variable: types::TypeVariable,
+        typ: types::Monotype,
+    },
+
+    #[error("Unknown type: {name}")]
+    #[diagnostic(
+        code(boo::type_checker::unknown_type),
+        help("the types are Integer, Float, Boolean, Char, String, Option, and Result, along with lists, tuples, and functions")
+    )]
+    UnknownType {
+        #[label("unknown type")]
+        span: Option<Span>,
+        name: String,
+    },
+
+    #[error("`{name}` takes {}, but was given {arguments}", type_arguments(*expected))]
+    #[diagnostic(
+        code(boo::type_checker::kind_mismatch),
+        help("{name} has kind {kind}, so it takes one argument for each `*` before the last")
+    )]
+    KindMismatch {
+        #[label("incorrect number of type arguments")]
+        span: Option<Span>,
+        name: String,
+        kind: String,
+        expected: usize,
+        arguments: usize,
+    },
+
+    #[error("No expression at offset {offset}")]
+    #[diagnostic(
+        code(boo::type_checker::no_expression_at_offset),
+        help("offsets are counted in bytes from the start of the input, and must fall within it")
+    )]
+    NoExpressionAtOffset { offset: usize },
+
     #[error("Could not apply the function")]
-    #[diagnostic(code(boo::evaluator::invalid_function_application))]
+    #[diagnostic(
+        code(boo::evaluator::invalid_function_application),
+        help("only functions can be applied to arguments; type checking should prevent this")
+    )]
     InvalidFunctionApplication {
         #[label("invalid function")]
         span: Option<Span>,
     },
 
     #[error("Invalid primitive")]
-    #[diagnostic(code(boo::evaluator::type_error))]
+    #[diagnostic(
+        code(boo::evaluator::type_error),
+        help(
+            "the operation was given a value of the wrong type; type checking should prevent this"
+        )
+    )]
     InvalidPrimitive {
         #[label("invalid primitive")]
         span: Option<Span>,
     },
 
+    #[error("Division by zero")]
+    #[diagnostic(
+        code(boo::evaluator::division_by_zero),
+        help("check the divisor first, or use `checked_divide`, which results in an error value instead")
+    )]
+    DivisionByZero {
+        #[label("division by zero")]
+        span: Option<Span>,
+    },
+
+    #[error("Integer overflow")]
+    #[diagnostic(
+        code(boo::evaluator::integer_overflow),
+        help(
+            "integers can be promoted to arbitrary precision instead of failing when they overflow"
+        )
+    )]
+    IntegerOverflow {
+        #[label("the result is too large")]
+        span: Option<Span>,
+    },
+
+    #[error("Invalid shift amount: {amount}")]
+    #[diagnostic(
+        code(boo::evaluator::invalid_shift_amount),
+        help("to shift in the other direction, use the other operator")
+    )]
+    InvalidShiftAmount {
+        #[label("shift amounts must be non-negative")]
+        span: Option<Span>,
+        amount: Integer,
+    },
+
+    #[error("Invalid exponent: {exponent}")]
+    #[diagnostic(
+        code(boo::evaluator::invalid_exponent),
+        help(
+            "integers cannot be raised to negative powers, as the result would not be an integer"
+        )
+    )]
+    InvalidExponent {
+        #[label("exponents must be non-negative")]
+        span: Option<Span>,
+        exponent: Integer,
+    },
+
+    #[error("Invalid random bound: {bound}")]
+    #[diagnostic(
+        code(boo::evaluator::invalid_random_bound),
+        help("`random n` produces an integer from 0 up to, but not including, n")
+    )]
+    InvalidRandomBound {
+        #[label("the bound must be positive, and fit in 64 bits")]
+        span: Option<Span>,
+        bound: Integer,
+    },
+
+    #[error("Invalid random state: {state}")]
+    #[diagnostic(
+        code(boo::evaluator::invalid_random_state),
+        help("`random n state` takes its state from `seed`, or from an earlier `random`")
+    )]
+    InvalidRandomState {
+        #[label("the state must fit in 64 bits")]
+        span: Option<Span>,
+        state: Integer,
+    },
+
+    #[error("Invalid character code: {code}")]
+    #[diagnostic(
+        code(boo::evaluator::invalid_character_code),
+        help("Unicode scalar values range from 0 to 1114111, excluding the surrogates, from 55296 to 57343")
+    )]
+    InvalidCharacterCode {
+        #[label("not a valid Unicode scalar value")]
+        span: Option<Span>,
+        code: Integer,
+    },
+
+    #[error("Empty list")]
+    #[diagnostic(
+        code(boo::evaluator::empty_list),
+        help("check whether the list is empty first, with `is_empty`")
+    )]
+    EmptyList {
+        #[label("the list is empty")]
+        span: Option<Span>,
+    },
+
+    #[error("Assertion failed")]
+    #[diagnostic(
+        code(boo::evaluator::assertion_failed),
+        help("the program assumed that this condition would be true")
+    )]
+    AssertionFailed {
+        #[label("the condition is false")]
+        span: Option<Span>,
+    },
+
     #[error("Unknown variable: {name:?}")]
     #[diagnostic(code(boo::evaluator::unknown_variable))]
     UnknownVariable {
         #[label("unknown variable")]
         span: Option<Span>,
         name: String,
+        #[help]
+        suggestions: Option<Suggestions>,
     },
+
+    #[error("Evaluation did not finish within {limit} steps")]
+    #[diagnostic(
+        code(boo::evaluator::step_limit_exceeded),
+        help("the program may not terminate; check that each recursive call makes progress towards a base case")
+    )]
+    StepLimitExceeded { limit: usize },
+
+    #[error("Evaluation ran out of memory, using more than {limit} bytes")]
+    #[diagnostic(
+        code(boo::evaluator::out_of_memory),
+        help(
+            "the program may hold on to more data than it needs, or build a structure without end"
+        )
+    )]
+    OutOfMemory { limit: usize },
+
+    #[error("Evaluation was interrupted")]
+    #[diagnostic(
+        code(boo::evaluator::interrupted),
+        help("evaluation was stopped before it produced a result")
+    )]
+    Interrupted,
+
+    #[error("Cannot compile {feature} to WebAssembly")]
+    #[diagnostic(
+        code(boo::compiler::unsupported),
+        help("run the program with an evaluator instead")
+    )]
+    UnsupportedByCompiler {
+        #[label("not supported")]
+        span: Option<Span>,
+        feature: String,
+    },
+}
+
+impl Error {
+    /// Constructs an [`Error::UnknownVariable`], suggesting any similarly-named
+    /// variables which are in scope.
+    pub fn unknown_variable<'a>(
+        span: Option<Span>,
+        name: &Identifier,
+        in_scope: impl IntoIterator<Item = &'a Identifier>,
+    ) -> Self {
+        Error::UnknownVariable {
+            span,
+            name: name.to_string(),
+            suggestions: Suggestions::for_name(name, in_scope),
+        }
+    }
+
+    /// Attaches a location to an evaluation error that does not have one yet.
+    ///
+    /// Natives do not know where they were called from, so the evaluator uses
+    /// this to point their errors at the call site.
+    pub fn located_at(mut self, location: Option<Span>) -> Self {
+        match &mut self {
+            Error::MatchWithoutBaseCase { span }
+            | Error::InvalidFunctionApplication { span }
+            | Error::InvalidPrimitive { span }
+            | Error::DivisionByZero { span }
+            | Error::IntegerOverflow { span }
+            | Error::InvalidShiftAmount { span, .. }
+            | Error::InvalidExponent { span, .. }
+            | Error::InvalidRandomBound { span, .. }
+            | Error::InvalidRandomState { span, .. }
+            | Error::InvalidCharacterCode { span, .. }
+            | Error::EmptyList { span }
+            | Error::AssertionFailed { span }
+            | Error::UnknownVariable { span, .. } => {
+                if span.is_none() {
+                    *span = location;
+                }
+            }
+            _ => {}
+        }
+        self
+    }
+}
+
+/// Variables in scope with names similar to an unknown variable, which may have
+/// been what was meant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestions(pub Vec<String>);
+
+impl Suggestions {
+    /// The most suggestions offered at once.
+    const LIMIT: usize = 3;
+
+    /// Finds the closest names in scope to the given name, if any are close
+    /// enough to be plausible typos.
+    pub fn for_name<'a>(
+        name: &Identifier,
+        in_scope: impl IntoIterator<Item = &'a Identifier>,
+    ) -> Option<Self> {
+        let Identifier::Name(name) = name else {
+            return None;
+        };
+        let threshold = (name.chars().count() / 3).max(1);
+        let mut candidates = in_scope
+            .into_iter()
+            .filter_map(|candidate| match candidate {
+                Identifier::Name(candidate) if candidate != name => {
+                    let distance = edit_distance(name, candidate);
+                    (distance <= threshold).then(|| (distance, candidate.to_string()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        candidates.sort();
+        candidates.dedup();
+        let suggestions = candidates
+            .into_iter()
+            .take(Self::LIMIT)
+            .map(|(_, candidate)| candidate)
+            .collect::<Vec<_>>();
+        (!suggestions.is_empty()).then_some(Self(suggestions))
+    }
+}
+
+impl std::fmt::Display for Suggestions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let quoted = self
+            .0
+            .iter()
+            .map(|name| format!("`{}`", name))
+            .collect::<Vec<_>>();
+        match quoted.as_slice() {
+            [] => Ok(()),
+            [a] => write!(f, "did you mean {}?", a),
+            [init @ .., last] => write!(f, "did you mean {}, or {}?", init.join(", "), last),
+        }
+    }
+}
+
+/// The edit distance between two strings, counting characters. Swapping two
+/// adjacent characters counts as a single edit, as it is a common typo.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    // distances[i][j] is the distance between the first i characters of `a`
+    // and the first j characters of `b`
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// The innermost construct that was being parsed when a parse error occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseContext {
+    /// Describes the construct, e.g. "the body of `let x = ...`".
+    pub description: String,
+    /// The token which would finish the construct, as it appears in the
+    /// expected tokens.
+    pub closing_token: &'static str,
+}
+
+/// An explanation of a likely cause of a parse error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseHint {
+    /// An infix operator directly follows a function application, as in
+    /// `f x -`, which may have been intended to be part of the argument.
+    ApplicationBeforeOperator { operator: &'static str },
+}
+
+impl std::fmt::Display for ParseHint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseHint::ApplicationBeforeOperator { operator } => write!(
+                f,
+                "function application binds more tightly than `{operator}`, so the application \
+                 is its left operand; to use `{operator}` within an argument, wrap the argument \
+                 in parentheses"
+            ),
+        }
+    }
+}
+
+fn type_arguments(count: usize) -> String {
+    match count {
+        0 => "no type arguments".to_string(),
+        1 => "1 type argument".to_string(),
+        _ => format!("{count} type arguments"),
+    }
+}
+
+fn parse_error_message(expected_tokens: &[&str], context: &Option<ParseContext>) -> String {
+    match context {
+        None => format!("expected one of {:?}", expected_tokens),
+        Some(ParseContext { description, .. }) => format!(
+            "while parsing {}, {}",
+            description,
+            expected_in_context(expected_tokens, context)
+        ),
+    }
+}
+
+/// If the construct could have been finished here, that is probably what was
+/// intended, so only its closing token is mentioned.
+fn expected_in_context(expected_tokens: &[&str], context: &Option<ParseContext>) -> String {
+    match context {
+        Some(ParseContext { closing_token, .. }) if expected_tokens.contains(closing_token) => {
+            format!("expected `{}`", closing_token.trim_matches('\''))
+        }
+        _ => expected_one_of(expected_tokens),
+    }
 }
 
 fn expected_one_of(strings: &[&str]) -> String {
@@ -90,3 +554,153 @@ fn expected_one_of(strings: &[&str]) -> String {
         ),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use miette::Diagnostic;
+
+    use crate::types::{Type, TypeVariable};
+
+    use super::*;
+
+    #[test]
+    fn test_every_error_has_a_code_and_help() {
+        let span = Span::from(0..1);
+        let integer = types::Monotype::from(Type::Integer);
+        let variable = TypeVariable::new_from_str("a");
+        // the help for parse errors and unknown variables depends on what
+        // went wrong, so they are left out
+        let errors = [
+            Error::UnexpectedToken {
+                span,
+                token: "$".to_string(),
+            },
+            Error::InvalidEscapeSequence {
+                span,
+                sequence: "\\q".to_string(),
+            },
+            Error::InvalidDigit {
+                span,
+                digit: "2".to_string(),
+                radix: 2,
+            },
+            Error::KeywordAsIdentifier {
+                span,
+                keyword: "let".to_string(),
+            },
+            Error::MatchWithoutBaseCase { span: Some(span) },
+            Error::TypeMismatch {
+                span: Some(span),
+                expected_type: integer.clone(),
+                actual_type: Type::Boolean.into(),
+            },
+            Error::TypeUnificationError {
+                left_span: Some(span),
+                left_type: integer.clone(),
+                right_span: Some(span),
+                right_type: Type::Boolean.into(),
+            },
+            Error::FloatInIntegerOperation {
+                left_span: Some(span),
+                left_type: integer.clone(),
+                right_span: Some(span),
+                right_type: Type::Float.into(),
+                operator: "+".to_string(),
+                float_operator: "+.".to_string(),
+            },
+            Error::NotComparable {
+                span: Some(span),
+                typ: Type::List(integer.clone()).into(),
+            },
+            Error::InfiniteType {
+                left_span: Some(span),
+                left_type: integer.clone(),
+                right_span: None,
+                right_type: Type::Variable(variable.clone()).into(),
+                variable,
+                typ: integer.clone(),
+            },
+            Error::UnknownType {
+                span: Some(span),
+                name: "Pair".to_string(),
+            },
+            Error::KindMismatch {
+                span: Some(span),
+                name: "Option".to_string(),
+                kind: "* -> *".to_string(),
+                expected: 1,
+                arguments: 0,
+            },
+            Error::NoExpressionAtOffset { offset: 1 },
+            Error::InvalidFunctionApplication { span: Some(span) },
+            Error::InvalidPrimitive { span: Some(span) },
+            Error::DivisionByZero { span: Some(span) },
+            Error::IntegerOverflow { span: Some(span) },
+            Error::InvalidShiftAmount {
+                span: Some(span),
+                amount: (-1).into(),
+            },
+            Error::InvalidExponent {
+                span: Some(span),
+                exponent: (-1).into(),
+            },
+            Error::InvalidRandomBound {
+                span: Some(span),
+                bound: 0.into(),
+            },
+            Error::InvalidRandomState {
+                span: Some(span),
+                state: Integer::from(i128::MAX),
+            },
+            Error::InvalidCharacterCode {
+                span: Some(span),
+                code: (-1).into(),
+            },
+            Error::EmptyList { span: Some(span) },
+            Error::AssertionFailed { span: Some(span) },
+            Error::StepLimitExceeded { limit: 1 },
+            Error::OutOfMemory { limit: 1 },
+            Error::Interrupted,
+            Error::UnsupportedByCompiler {
+                span: Some(span),
+                feature: "floats".to_string(),
+            },
+        ];
+
+        for error in errors {
+            assert!(error.code().is_some(), "{error:?} has no code");
+            assert!(error.help().is_some(), "{error:?} has no help");
+        }
+    }
+
+    #[test]
+    fn test_labels_both_sides_of_a_type_error() {
+        let error = Error::TypeUnificationError {
+            left_span: Some((0..1).into()),
+            left_type: Type::Integer.into(),
+            right_span: Some((4..8).into()),
+            right_type: Type::Boolean.into(),
+        };
+
+        let labels = error
+            .labels()
+            .into_iter()
+            .flatten()
+            .map(|label| {
+                (
+                    label.offset(),
+                    label.len(),
+                    label.label().map(str::to_string),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            labels,
+            vec![
+                (0, 1, Some("this is Integer".to_string())),
+                (4, 4, Some("but this is Boolean".to_string())),
+            ]
+        );
+    }
+}
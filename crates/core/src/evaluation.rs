@@ -2,8 +2,10 @@ use crate::ast;
 use crate::error::Result;
 use crate::expr::Expr;
 use crate::identifier::Identifier;
+use crate::pretty::Pretty;
 use crate::primitive::Primitive;
 use crate::span::Spanned;
+use crate::types::{Monotype, Type};
 
 /// A context in which expressions can be evaluated.
 ///
@@ -14,6 +16,9 @@ pub trait EvaluationContext<Ex = Expr> {
     /// Bind a new top-level expression.
     fn bind(&mut self, identifier: Identifier, expr: Ex) -> Result<()>;
 
+    /// Lists the identifiers bound so far, each once, in no particular order.
+    fn identifiers(&self) -> Vec<Identifier>;
+
     /// Consume the context to produce an [Evaluator].
     fn evaluator(self) -> Self::Eval;
 }
@@ -24,32 +29,173 @@ pub trait Evaluator<Ex = Expr> {
     fn evaluate(&self, expr: Ex) -> Result<Evaluated<Ex>>;
 }
 
-/// An evaluation result. This can be either a primitive value or a closure.
+/// What to do when arithmetic on small integers overflows.
+///
+/// Integers are stored as 32 bits where possible, and otherwise in an
+/// arbitrary-precision representation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntegerOverflow {
+    /// Promotes the result to an arbitrary-precision integer.
+    #[default]
+    Promote,
+    /// Fails with [`Error::IntegerOverflow`][crate::error::Error::IntegerOverflow].
+    Fail,
+}
+
+/// An evaluation result. This can be a primitive value, a closure, or a tuple
+/// or list of evaluation results.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Evaluated<Ex = Expr> {
     Primitive(Primitive),
     Function(ast::Function<Ex>),
+    Tuple(Vec<Evaluated<Ex>>),
+    List(Vec<Evaluated<Ex>>),
 }
 
 impl<Ex: Clone> Evaluated<Ex> {
     pub fn to_core(self, reader: impl ExpressionReader<Expr = Ex>) -> Evaluated<Expr> {
         match self {
             Evaluated::Primitive(primitive) => Evaluated::Primitive(primitive),
-            Evaluated::Function(ast::Function { parameter, body }) => {
-                Evaluated::Function(ast::Function {
-                    parameter,
-                    body: reader.to_core(body),
-                })
-            }
+            Evaluated::Function(ast::Function {
+                parameter,
+                parameter_type,
+                parameter_type_span,
+                body,
+            }) => Evaluated::Function(ast::Function {
+                parameter,
+                parameter_type,
+                parameter_type_span,
+                body: reader.to_core(body),
+            }),
+            Evaluated::Tuple(elements) => Evaluated::Tuple(
+                elements
+                    .into_iter()
+                    .map(|element| element.to_core(reader))
+                    .collect(),
+            ),
+            Evaluated::List(elements) => Evaluated::List(
+                elements
+                    .into_iter()
+                    .map(|element| element.to_core(reader))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<Ex: Pretty> Evaluated<Ex> {
+    /// Displays the value as a value of the given type.
+    ///
+    /// Options and results are represented as lists and tuples once evaluated,
+    /// and so can only be displayed as such when the type is known.
+    pub fn display_as<'a>(&'a self, typ: &'a Monotype) -> impl std::fmt::Display + 'a {
+        DisplayAs {
+            value: self,
+            typ: Some(typ),
         }
     }
 }
 
-impl<Ex: std::fmt::Display> std::fmt::Display for Evaluated<Ex> {
+impl<Ex: Pretty> std::fmt::Display for Evaluated<Ex> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Evaluated::Primitive(x) => x.fmt(f),
-            Evaluated::Function(x) => x.fmt(f),
+        DisplayAs {
+            value: self,
+            typ: None,
+        }
+        .fmt(f)
+    }
+}
+
+/// Displays a value, with its type if known.
+struct DisplayAs<'a, Ex> {
+    value: &'a Evaluated<Ex>,
+    typ: Option<&'a Monotype>,
+}
+
+impl<'a, Ex: Pretty> DisplayAs<'a, Ex> {
+    /// Displays a nested value, whose type is only known if the type of the
+    /// outer value is known.
+    fn nested(value: &'a Evaluated<Ex>, typ: Option<&'a Monotype>) -> Self {
+        Self { value, typ }
+    }
+
+    /// Displays a value given as an argument to a constructor such as `some`
+    /// or `ok`,
+    /// which must be parenthesized if it is more than a single term.
+    fn argument(&self) -> String {
+        let argument = self.to_string();
+        if argument.starts_with('-')
+            || (argument.contains(' ') && !argument.starts_with(['(', '[', '"', '\'']))
+        {
+            format!("({argument})")
+        } else {
+            argument
+        }
+    }
+}
+
+impl<'a, Ex: Pretty> std::fmt::Display for DisplayAs<'a, Ex> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let typ = self.typ.map(|typ| typ.as_ref());
+        match (self.value, typ) {
+            (Evaluated::Primitive(x), _) => x.fmt(f),
+            (Evaluated::Function(x), _) => x.fmt(f),
+            (Evaluated::List(elements), Some(Type::Option(element_type))) => {
+                match elements.as_slice() {
+                    [] => write!(f, "none"),
+                    [element] => write!(
+                        f,
+                        "some {}",
+                        DisplayAs::nested(element, Some(element_type)).argument()
+                    ),
+                    _ => unreachable!("An option has more than one element."),
+                }
+            }
+            (Evaluated::Tuple(elements), Some(Type::Result { ok, error })) => {
+                match elements.as_slice() {
+                    [Evaluated::Primitive(Primitive::Boolean(true)), value] => {
+                        write!(f, "ok {}", DisplayAs::nested(value, Some(ok)).argument())
+                    }
+                    [Evaluated::Primitive(Primitive::Boolean(false)), value] => write!(
+                        f,
+                        "err {}",
+                        DisplayAs::nested(value, Some(error)).argument()
+                    ),
+                    _ => unreachable!("A result is not tagged."),
+                }
+            }
+            (Evaluated::Tuple(elements), _) => {
+                let element_types = match typ {
+                    Some(Type::Tuple(element_types)) if element_types.len() == elements.len() => {
+                        element_types.iter().map(Some).collect()
+                    }
+                    _ => vec![None; elements.len()],
+                };
+                write!(f, "(")?;
+                let mut element_iter = elements.iter().zip(element_types);
+                if let Some((first, first_type)) = element_iter.next() {
+                    write!(f, "{}", DisplayAs::nested(first, first_type))?;
+                    for (element, element_type) in element_iter {
+                        write!(f, ", {}", DisplayAs::nested(element, element_type))?;
+                    }
+                }
+                write!(f, ")")
+            }
+            (Evaluated::List(elements), _) => {
+                let element_type = match typ {
+                    Some(Type::List(element_type)) => Some(element_type),
+                    _ => None,
+                };
+                write!(f, "[")?;
+                let mut element_iter = elements.iter();
+                if let Some(first) = element_iter.next() {
+                    write!(f, "{}", DisplayAs::nested(first, element_type))?;
+                    for element in element_iter {
+                        write!(f, ", {}", DisplayAs::nested(element, element_type))?;
+                    }
+                }
+                write!(f, "]")
+            }
         }
     }
 }
@@ -80,12 +226,17 @@ pub trait ExpressionReader: Copy {
                 ast::Expression::Identifier(identifier) => {
                     ast::Expression::Identifier(identifier.clone())
                 }
-                ast::Expression::Function(ast::Function { parameter, body }) => {
-                    ast::Expression::Function(ast::Function {
-                        parameter: parameter.clone(),
-                        body: self.to_core(body.clone()),
-                    })
-                }
+                ast::Expression::Function(ast::Function {
+                    parameter,
+                    parameter_type,
+                    parameter_type_span,
+                    body,
+                }) => ast::Expression::Function(ast::Function {
+                    parameter: parameter.clone(),
+                    parameter_type: parameter_type.clone(),
+                    parameter_type_span: *parameter_type_span,
+                    body: self.to_core(body.clone()),
+                }),
                 ast::Expression::Apply(ast::Apply { function, argument }) => {
                     ast::Expression::Apply(ast::Apply {
                         function: self.to_core(function.clone()),
@@ -99,22 +250,62 @@ pub trait ExpressionReader: Copy {
                         inner: self.to_core(inner.clone()),
                     })
                 }
+                ast::Expression::AssignRecursive(ast::AssignRecursive { bindings, inner }) => {
+                    ast::Expression::AssignRecursive(ast::AssignRecursive {
+                        bindings: bindings
+                            .iter()
+                            .map(|(name, value)| (name.clone(), self.to_core(value.clone())))
+                            .collect(),
+                        inner: self.to_core(inner.clone()),
+                    })
+                }
                 ast::Expression::Match(ast::Match { value, patterns }) => {
                     ast::Expression::Match(ast::Match {
                         value: self.to_core(value.clone()),
                         patterns: patterns
                             .iter()
-                            .map(|ast::PatternMatch { pattern, result }| ast::PatternMatch {
-                                pattern: pattern.clone(),
-                                result: self.to_core(result.clone()),
-                            })
+                            .map(
+                                |ast::PatternMatch {
+                                     pattern,
+                                     pattern_span,
+                                     result,
+                                 }| ast::PatternMatch {
+                                    pattern: pattern.clone(),
+                                    pattern_span: *pattern_span,
+                                    result: self.to_core(result.clone()),
+                                },
+                            )
                             .collect(),
                     })
                 }
-                ast::Expression::Typed(ast::Typed { expression, typ }) => {
-                    ast::Expression::Typed(ast::Typed {
-                        expression: self.to_core(expression.clone()),
-                        typ: typ.clone(),
+                ast::Expression::Typed(ast::Typed {
+                    expression,
+                    typ,
+                    typ_span,
+                }) => ast::Expression::Typed(ast::Typed {
+                    expression: self.to_core(expression.clone()),
+                    typ: typ.clone(),
+                    typ_span: *typ_span,
+                }),
+                ast::Expression::Sequence(ast::Sequence { first, second }) => {
+                    ast::Expression::Sequence(ast::Sequence {
+                        first: self.to_core(first.clone()),
+                        second: self.to_core(second.clone()),
+                    })
+                }
+                ast::Expression::Tuple(ast::Tuple { elements }) => {
+                    ast::Expression::Tuple(ast::Tuple {
+                        elements: elements
+                            .iter()
+                            .map(|element| self.to_core(element.clone()))
+                            .collect(),
+                    })
+                }
+                ast::Expression::List(ast::List::Empty) => ast::Expression::List(ast::List::Empty),
+                ast::Expression::List(ast::List::Cons { head, tail }) => {
+                    ast::Expression::List(ast::List::Cons {
+                        head: self.to_core(head.clone()),
+                        tail: self.to_core(tail.clone()),
                     })
                 }
             },
@@ -130,3 +321,73 @@ impl<'a, T: ExpressionReader> ExpressionReader for &'a T {
         <T as ExpressionReader>::read(self, expr)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn integer(value: i32) -> Evaluated {
+        Evaluated::Primitive(Primitive::Integer(value.into()))
+    }
+
+    fn option_type(element_type: Monotype) -> Monotype {
+        Type::Option(element_type).into()
+    }
+
+    #[test]
+    fn test_displaying_options_with_their_type() {
+        let typ = Type::List(option_type(Type::Integer.into())).into();
+        let value = Evaluated::List(vec![
+            Evaluated::List(vec![integer(3)]),
+            Evaluated::List(vec![]),
+        ]);
+
+        assert_eq!(value.display_as(&typ).to_string(), "[some 3, none]");
+        assert_eq!(value.to_string(), "[[3], []]");
+    }
+
+    #[test]
+    fn test_parenthesizing_the_values_of_options() {
+        let typ = Type::Tuple(vec![
+            option_type(option_type(Type::Integer.into())),
+            option_type(Type::Integer.into()),
+        ])
+        .into();
+        let value = Evaluated::Tuple(vec![
+            Evaluated::List(vec![Evaluated::List(vec![integer(1)])]),
+            Evaluated::List(vec![integer(-2)]),
+        ]);
+
+        assert_eq!(
+            value.display_as(&typ).to_string(),
+            "(some (some 1), some (-2))"
+        );
+    }
+
+    #[test]
+    fn test_displaying_results_with_their_type() {
+        let result_type: Monotype = Type::Result {
+            ok: option_type(Type::Integer.into()),
+            error: Type::String.into(),
+        }
+        .into();
+        let typ = Type::List(result_type).into();
+        let value = Evaluated::List(vec![
+            Evaluated::Tuple(vec![
+                Evaluated::Primitive(Primitive::Boolean(true)),
+                Evaluated::List(vec![integer(4)]),
+            ]),
+            Evaluated::Tuple(vec![
+                Evaluated::Primitive(Primitive::Boolean(false)),
+                Evaluated::Primitive(Primitive::String(Arc::new("oh no".to_owned()))),
+            ]),
+        ]);
+
+        assert_eq!(
+            value.display_as(&typ).to_string(),
+            r#"[ok (some 4), err "oh no"]"#
+        );
+    }
+}
@@ -1,32 +1,130 @@
 //! The core Boo AST, represented as a wrapped [`Expression`].
 
+use std::sync::Arc;
+
 pub use crate::ast::*;
 use crate::evaluation::ExpressionReader;
+use crate::pretty::{self, Doc, Pretty};
 use crate::span::*;
 
 /// Wraps an expression with a span.
+///
+/// The expression is shared, so that copying it, e.g. to evaluate it later,
+/// takes the same time however large it is. Expressions can be nested very
+/// deeply, so they are also dropped without recursing; see [`Drop`].
 #[derive(Clone, PartialEq, Eq, Hash)]
-pub struct Expr(Spanned<Box<Expression<Expr>>>);
+pub struct Expr(
+    /// This is only `None` once the expression has been taken apart.
+    Spanned<Option<Arc<Expression<Expr>>>>,
+);
 
 impl Expr {
     pub fn new(span: Option<Span>, expression: Expression<Self>) -> Self {
         Self(Spanned {
             span,
-            value: expression.into(),
+            value: Some(Arc::new(expression)),
         })
     }
 
     pub fn expression(&self) -> &Expression<Expr> {
-        self.0.value.as_ref()
+        self.0.value.as_ref().expect("Expression was taken.")
     }
 
-    pub fn take(self) -> Expression<Expr> {
-        *self.0.value
+    /// Takes the expression out, copying it if it is shared.
+    ///
+    /// Only the outermost expression is copied; the subexpressions are shared.
+    pub fn take(mut self) -> Expression<Expr> {
+        let shared = self.0.value.take().expect("Expression was taken.");
+        Arc::try_unwrap(shared).unwrap_or_else(|shared| (*shared).clone())
     }
 
     pub fn span(&self) -> Option<Span> {
         self.0.span
     }
+
+    /// Builds a value from the expression, starting with its leaves, and
+    /// without recursing, so that the depth of the expression is not limited
+    /// by the size of the stack.
+    ///
+    /// `build` is given each expression, with its direct subexpressions
+    /// replaced by the values built from them.
+    pub fn fold<T>(self, mut build: impl FnMut(Option<Span>, Expression<T>) -> T) -> T {
+        enum Work {
+            TakeApart(Expr),
+            Build(Option<Span>, Expression<()>),
+        }
+
+        let mut work = vec![Work::TakeApart(self)];
+        let mut built: Vec<T> = vec![];
+        while let Some(next) = work.pop() {
+            match next {
+                Work::TakeApart(expr) => {
+                    let span = expr.span();
+                    let mut children = vec![];
+                    let shell = expr.take().map(|child| children.push(child));
+                    work.push(Work::Build(span, shell));
+                    work.extend(children.into_iter().rev().map(Work::TakeApart));
+                }
+                Work::Build(span, shell) => {
+                    let start = built.len() - shell.children().len();
+                    let mut children = built.split_off(start).into_iter();
+                    let expression = shell.map(|()| children.next().unwrap());
+                    built.push(build(span, expression));
+                }
+            }
+        }
+        built.pop().unwrap()
+    }
+
+    /// Removes the location of the expression and all of its subexpressions.
+    ///
+    /// This is useful when the expression outlives the source it was parsed
+    /// from, as locations in one source are meaningless in another.
+    pub fn without_spans(self) -> Self {
+        self.fold(|_, expression| {
+            let expression = match expression {
+                Expression::Function(function) => Expression::Function(Function {
+                    parameter_type_span: None,
+                    ..function
+                }),
+                Expression::Match(Match { value, patterns }) => Expression::Match(Match {
+                    value,
+                    patterns: patterns
+                        .into_iter()
+                        .map(|pattern_match| PatternMatch {
+                            pattern_span: None,
+                            ..pattern_match
+                        })
+                        .collect(),
+                }),
+                Expression::Typed(typed) => Expression::Typed(Typed {
+                    typ_span: None,
+                    ..typed
+                }),
+                expression => expression,
+            };
+            Self::new(None, expression)
+        })
+    }
+}
+
+impl Drop for Expr {
+    /// Drops the expression if this is the last copy of it.
+    ///
+    /// Rather than dropping each subexpression in turn, which would recurse as
+    /// deeply as the expression is nested, the subexpressions are set aside,
+    /// and dropped one at a time.
+    fn drop(&mut self) {
+        let Some(shared) = self.0.value.take() else {
+            return;
+        };
+        let mut pending = vec![shared];
+        while let Some(shared) = pending.pop() {
+            if let Ok(expression) = Arc::try_unwrap(shared) {
+                expression.map(|mut child| pending.extend(child.0.value.take()));
+            }
+        }
+    }
 }
 
 // We use this for testing, and the default implementation is a bit ugly.
@@ -34,14 +132,20 @@ impl std::fmt::Debug for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Expr")
             .field("span", &self.0.span)
-            .field("expression", self.0.value.as_ref())
+            .field("expression", self.expression())
             .finish()
     }
 }
 
+impl Pretty for Expr {
+    fn to_doc(&self) -> Doc {
+        self.expression().to_doc()
+    }
+}
+
 impl std::fmt::Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.value.fmt(f)
+        pretty::display(self, f)
     }
 }
 
@@ -50,10 +154,13 @@ pub struct ExprReader;
 
 impl ExpressionReader for ExprReader {
     type Expr = self::Expr;
-    type Target = Box<Expression<Self::Expr>>;
+    type Target = Arc<Expression<Self::Expr>>;
 
-    fn read(&self, expr: Self::Expr) -> Spanned<Self::Target> {
-        expr.0
+    fn read(&self, mut expr: Self::Expr) -> Spanned<Self::Target> {
+        Spanned {
+            span: expr.span(),
+            value: expr.0.value.take().expect("Expression was taken."),
+        }
     }
 
     fn to_core(&self, expr: Self::Expr) -> Expr {
@@ -16,10 +16,9 @@ use regex::Regex;
 pub enum Identifier {
     Name(Arc<String>),
     Operator(Arc<String>),
-    AvoidingCapture {
-        original: Box<Identifier>,
-        suffix: u32,
-    },
+    /// Refers to a value shared by an evaluator, rather than a name in the
+    /// program.
+    Label(usize),
 }
 
 /// Errors that can happen when dealing with identifiers.
@@ -44,10 +43,16 @@ lazy_static! {
     static ref VALID_IDENTIFIER_NAME_CHARACTER_REGEX: &'static str =
         r"[_\p{Letter}\p{Number}]";
 
-    static ref VALID_OPERATORS: HashSet<&'static str> = ["+", "-", "*"].into();
+    static ref VALID_OPERATORS: HashSet<&'static str> = [
+        "+", "-", "*", "/", "%", "++",
+        "&", "|", "xor", "<<", ">>",
+        "+.", "-.", "*.", "/.",
+        "==", "!=", "<", "<=", ">", ">=",
+        "::",
+    ].into();
 
     // ensure that the set of keywords matches the keywords defined in lexer.rs
-    static ref KEYWORDS: HashSet<&'static str> = ["fn", "in", "let", "match"].into();
+    static ref KEYWORDS: HashSet<&'static str> = ["and", "def", "false", "fn", "in", "let", "match", "rec", "true", "try", "xor"].into();
 }
 
 impl Identifier {
@@ -85,10 +90,7 @@ impl Identifier {
         match self {
             Identifier::Name(name) => name.to_string(),
             Identifier::Operator(operator) => format!("({operator})"),
-            Identifier::AvoidingCapture {
-                original,
-                suffix: _,
-            } => original.name(),
+            Identifier::Label(label) => format!("#{label}"),
         }
     }
 
@@ -108,7 +110,7 @@ impl std::fmt::Display for Identifier {
         match self {
             Identifier::Name(name) => name.fmt(f),
             Identifier::Operator(operator) => operator.fmt(f),
-            Identifier::AvoidingCapture { original, .. } => original.fmt(f),
+            Identifier::Label(label) => write!(f, "#{label}"),
         }
     }
 }
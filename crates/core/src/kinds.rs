@@ -0,0 +1,199 @@
+//! Kinds, which describe the type arguments a type constructor expects.
+
+use std::fmt::Display;
+
+use crate::error::{Error, Result};
+use crate::span::Span;
+use crate::types::{Monotype, Type, TypeName};
+
+/// The kind of a type.
+///
+/// Types with values, such as `Integer`, have the kind `*`. Type constructors,
+/// such as `Option`, have a kind such as `* -> *`, describing the types they
+/// take as arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kind {
+    Type,
+    Constructor {
+        parameter: Box<Kind>,
+        body: Box<Kind>,
+    },
+}
+
+impl Kind {
+    /// The kind of a type constructor that takes the given number of types,
+    /// each of which has the kind `*`.
+    pub fn of_arity(arity: usize) -> Self {
+        (0..arity).fold(Kind::Type, |body, _| Kind::Constructor {
+            parameter: Kind::Type.into(),
+            body: body.into(),
+        })
+    }
+
+    /// The number of types that must be applied to get a type of kind `*`.
+    pub fn arity(&self) -> usize {
+        match self {
+            Kind::Type => 0,
+            Kind::Constructor { body, .. } => 1 + body.arity(),
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Kind::Type => write!(f, "*"),
+            Kind::Constructor { parameter, body } => match parameter.as_ref() {
+                Kind::Type => write!(f, "* -> {body}"),
+                _ => write!(f, "({parameter}) -> {body}"),
+            },
+        }
+    }
+}
+
+/// The kind of each named type.
+fn kind_of(name: &TypeName) -> Option<Kind> {
+    match name.0.as_str() {
+        "Integer" | "Float" | "Boolean" | "Char" | "String" => Some(Kind::of_arity(0)),
+        "Option" => Some(Kind::of_arity(1)),
+        "Result" => Some(Kind::of_arity(2)),
+        _ => None,
+    }
+}
+
+/// Checks that every type constructor in the type is known, and is given the
+/// number of arguments its kind requires.
+///
+/// The span is that of the type, and is used to report errors.
+pub fn check(typ: &Monotype, span: Option<Span>) -> Result<()> {
+    match typ.as_ref() {
+        Type::Integer
+        | Type::Float
+        | Type::Boolean
+        | Type::Char
+        | Type::String
+        | Type::Variable(_) => Ok(()),
+        Type::Function { parameter, body } => {
+            check(parameter, span)?;
+            check(body, span)
+        }
+        Type::Tuple(elements) => elements.iter().try_for_each(|element| check(element, span)),
+        Type::List(element) | Type::Option(element) => check(element, span),
+        Type::Result { ok, error } => {
+            check(ok, span)?;
+            check(error, span)
+        }
+        Type::Constructor { name, arguments } => {
+            let kind = kind_of(name).ok_or_else(|| Error::UnknownType {
+                span,
+                name: name.to_string(),
+            })?;
+            if kind.arity() != arguments.len() {
+                return Err(Error::KindMismatch {
+                    span,
+                    name: name.to_string(),
+                    kind: kind.to_string(),
+                    expected: kind.arity(),
+                    arguments: arguments.len(),
+                });
+            }
+            arguments
+                .iter()
+                .try_for_each(|argument| check(argument, span))
+        }
+        Type::Forall { body, .. } | Type::Recursive { body, .. } => check(body, span),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_displays_kinds() {
+        assert_eq!(Kind::of_arity(0).to_string(), "*");
+        assert_eq!(Kind::of_arity(2).to_string(), "* -> * -> *");
+    }
+
+    #[test]
+    fn test_rejects_types_applied_to_too_many_arguments() {
+        let typ = constructor("Integer", vec![Type::Integer.into()]);
+
+        let result = check(&typ, Some((0..15).into()));
+
+        assert_eq!(
+            result,
+            Err(Error::KindMismatch {
+                span: Some((0..15).into()),
+                name: "Integer".to_string(),
+                kind: "*".to_string(),
+                expected: 0,
+                arguments: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_describes_the_expected_number_of_arguments() {
+        let messages = [
+            (
+                constructor("Integer", vec![Type::Integer.into()]),
+                "`Integer` takes no type arguments, but was given 1",
+            ),
+            (
+                constructor("Option", vec![]),
+                "`Option` takes 1 type argument, but was given 0",
+            ),
+            (
+                constructor("Result", vec![Type::Integer.into()]),
+                "`Result` takes 2 type arguments, but was given 1",
+            ),
+        ];
+        for (typ, expected_message) in messages {
+            let message = check(&typ, None).unwrap_err().to_string();
+
+            assert_eq!(message, expected_message);
+        }
+    }
+
+    #[test]
+    fn test_rejects_type_constructors_without_arguments() {
+        let typ = Type::List(constructor("Option", vec![])).into();
+
+        let result = check(&typ, Some((0..8).into()));
+
+        assert_eq!(
+            result,
+            Err(Error::KindMismatch {
+                span: Some((0..8).into()),
+                name: "Option".to_string(),
+                kind: "* -> *".to_string(),
+                expected: 1,
+                arguments: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_types() {
+        let typ = constructor("Pair", vec![Type::Integer.into(), Type::Boolean.into()]);
+
+        let result = check(&typ, Some((0..20).into()));
+
+        assert_eq!(
+            result,
+            Err(Error::UnknownType {
+                span: Some((0..20).into()),
+                name: "Pair".to_string(),
+            })
+        );
+    }
+
+    fn constructor(name: &str, arguments: Vec<Monotype>) -> Monotype {
+        Type::Constructor {
+            name: TypeName::new_from_str(name),
+            arguments,
+        }
+        .into()
+    }
+}
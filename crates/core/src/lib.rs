@@ -2,12 +2,20 @@
 
 pub mod ast;
 pub mod builtins;
+pub mod diagnostics;
 pub mod error;
 pub mod evaluation;
 pub mod expr;
 pub mod identifier;
+pub mod kinds;
+pub mod lint;
+pub mod matching;
 pub mod native;
+pub mod optimization;
+pub mod pretty;
 pub mod primitive;
 pub mod span;
+pub mod specialization;
 pub mod types;
 pub mod verification;
+pub mod warning;
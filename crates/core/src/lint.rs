@@ -0,0 +1,257 @@
+//! Lints over the core AST, which find code that is valid but suspicious.
+
+use std::collections::HashSet;
+
+use crate::diagnostics::Diagnostics;
+use crate::expr;
+use crate::identifier::Identifier;
+use crate::warning::Warning;
+
+/// Finds `let` bindings and function parameters that are never referenced,
+/// and records them as warnings.
+///
+/// Names starting with an underscore are assumed to be unused on purpose, and
+/// are not reported.
+pub fn lint(expr: &expr::Expr, diagnostics: &mut Diagnostics) {
+    free_variables(expr, diagnostics);
+}
+
+/// Computes the free variables of an expression, recording any bound
+/// variables that are not used along the way.
+fn free_variables(expr: &expr::Expr, diagnostics: &mut Diagnostics) -> HashSet<Identifier> {
+    match expr.expression() {
+        expr::Expression::Primitive(_) | expr::Expression::Native(_) => HashSet::new(),
+        expr::Expression::Identifier(name) => [name.clone()].into(),
+        expr::Expression::Function(expr::Function {
+            ref parameter,
+            ref body,
+            ..
+        }) => {
+            let mut free = free_variables(body, diagnostics);
+            check_usage(expr, parameter, &mut free, diagnostics);
+            free
+        }
+        expr::Expression::Apply(expr::Apply {
+            ref function,
+            ref argument,
+        }) => {
+            let mut free = free_variables(function, diagnostics);
+            free.extend(free_variables(argument, diagnostics));
+            free
+        }
+        expr::Expression::Assign(expr::Assign {
+            ref name,
+            ref value,
+            ref inner,
+        }) => {
+            let value_free = free_variables(value, diagnostics);
+            let mut free = free_variables(inner, diagnostics);
+            check_usage(expr, name, &mut free, diagnostics);
+            free.extend(value_free);
+            free
+        }
+        expr::Expression::AssignRecursive(expr::AssignRecursive {
+            ref bindings,
+            ref inner,
+        }) => {
+            let values_free = bindings
+                .iter()
+                .map(|(name, value)| (name, free_variables(value, diagnostics)))
+                .collect::<Vec<_>>();
+            let mut free = free_variables(inner, diagnostics);
+            // a binding referred to only by itself is still unused
+            for (name, _) in bindings {
+                let mut used = free.clone();
+                for (other, value_free) in values_free.iter() {
+                    if *other != name {
+                        used.extend(value_free.iter().cloned());
+                    }
+                }
+                check_usage(expr, name, &mut used, diagnostics);
+            }
+            for (_, value_free) in values_free {
+                free.extend(value_free);
+            }
+            for (name, _) in bindings {
+                free.remove(name);
+            }
+            free
+        }
+        expr::Expression::Match(expr::Match {
+            ref value,
+            ref patterns,
+        }) => {
+            let mut free = free_variables(value, diagnostics);
+            for expr::PatternMatch {
+                pattern, result, ..
+            } in patterns
+            {
+                let mut result_free = free_variables(result, diagnostics);
+                for variable in pattern.variables() {
+                    result_free.remove(variable);
+                }
+                free.extend(result_free);
+            }
+            free
+        }
+        expr::Expression::Typed(expr::Typed { ref expression, .. }) => {
+            free_variables(expression, diagnostics)
+        }
+        expr::Expression::Sequence(expr::Sequence {
+            ref first,
+            ref second,
+        }) => {
+            let mut free = free_variables(first, diagnostics);
+            free.extend(free_variables(second, diagnostics));
+            free
+        }
+        expr::Expression::Tuple(expr::Tuple { ref elements }) => elements
+            .iter()
+            .flat_map(|element| free_variables(element, diagnostics))
+            .collect(),
+        expr::Expression::List(expr::List::Empty) => HashSet::new(),
+        expr::Expression::List(expr::List::Cons { ref head, ref tail }) => {
+            let mut free = free_variables(head, diagnostics);
+            free.extend(free_variables(tail, diagnostics));
+            free
+        }
+    }
+}
+
+/// Removes the bound name from the set of free variables, reporting it if it
+/// was not there.
+fn check_usage(
+    binder: &expr::Expr,
+    name: &Identifier,
+    free: &mut HashSet<Identifier>,
+    diagnostics: &mut Diagnostics,
+) {
+    if !free.remove(name) && !name.name().starts_with('_') {
+        diagnostics.warn(Warning::UnusedBinding {
+            span: binder.span(),
+            name: name.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::primitive::Primitive;
+
+    use super::*;
+
+    #[test]
+    fn test_warns_about_an_unused_assignment() {
+        let expr = assign("x", integer(1), integer(2));
+
+        let mut diagnostics = Diagnostics::new();
+        lint(&expr, &mut diagnostics);
+
+        assert_eq!(
+            diagnostics.warnings(),
+            [Warning::UnusedBinding {
+                span: Some((0..10).into()),
+                name: "x".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_warns_about_an_unused_parameter() {
+        let expr = function("x", function("y", identifier("x")));
+
+        let mut diagnostics = Diagnostics::new();
+        lint(&expr, &mut diagnostics);
+
+        assert_eq!(
+            diagnostics.warnings(),
+            [Warning::UnusedBinding {
+                span: Some((0..10).into()),
+                name: "y".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_does_not_warn_about_used_bindings() {
+        let expr = assign(
+            "x",
+            integer(1),
+            expr::Expr::new(
+                None,
+                expr::Expression::Apply(expr::Apply {
+                    function: function("y", identifier("y")),
+                    argument: identifier("x"),
+                }),
+            ),
+        );
+
+        let mut diagnostics = Diagnostics::new();
+        lint(&expr, &mut diagnostics);
+
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_does_not_warn_about_names_starting_with_an_underscore() {
+        let expr = assign("_x", integer(1), integer(2));
+
+        let mut diagnostics = Diagnostics::new();
+        lint(&expr, &mut diagnostics);
+
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_does_not_count_a_reference_to_a_variable_in_its_own_value() {
+        let expr = assign("x", integer(1), assign("x", identifier("x"), integer(2)));
+
+        let mut diagnostics = Diagnostics::new();
+        lint(&expr, &mut diagnostics);
+
+        assert_eq!(
+            diagnostics.warnings(),
+            [Warning::UnusedBinding {
+                span: Some((0..10).into()),
+                name: "x".to_string(),
+            }]
+        );
+    }
+
+    fn assign(name: &str, value: expr::Expr, inner: expr::Expr) -> expr::Expr {
+        expr::Expr::new(
+            Some((0..10).into()),
+            expr::Expression::Assign(expr::Assign {
+                name: Identifier::name_from_str(name).unwrap(),
+                value,
+                inner,
+            }),
+        )
+    }
+
+    fn function(parameter: &str, body: expr::Expr) -> expr::Expr {
+        expr::Expr::new(
+            Some((0..10).into()),
+            expr::Expression::Function(expr::Function {
+                parameter: Identifier::name_from_str(parameter).unwrap(),
+                parameter_type: None,
+                parameter_type_span: None,
+                body,
+            }),
+        )
+    }
+
+    fn identifier(name: &str) -> expr::Expr {
+        expr::Expr::new(
+            None,
+            expr::Expression::Identifier(Identifier::name_from_str(name).unwrap()),
+        )
+    }
+
+    fn integer(value: i64) -> expr::Expr {
+        expr::Expr::new(
+            None,
+            expr::Expression::Primitive(Primitive::Integer(value.into())),
+        )
+    }
+}
@@ -0,0 +1,266 @@
+//! Matches values against patterns, one step at a time.
+//!
+//! Evaluators only evaluate as much of a value as the patterns inspect. Rather
+//! than evaluating each part of the value as it is needed, which would mean
+//! running the evaluator within itself, a [`Selection`] asks for the part it
+//! needs, and waits to be resumed once the evaluator has evaluated it as part
+//! of its own work.
+
+use std::collections::VecDeque;
+
+use crate::ast::{List, Pattern};
+use crate::identifier::Identifier;
+use crate::primitive::Primitive;
+
+/// A value, evaluated as far as its outermost constructor, with its parts left
+/// as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shape<Value> {
+    Primitive(Primitive),
+    Function,
+    Tuple(Vec<Value>),
+    List(List<Value>),
+}
+
+/// What a [`Selection`] needs next.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Selected<Value, Arm> {
+    /// The pattern for this result matched, binding these variables.
+    Matched {
+        result: Arm,
+        variables: Vec<(Identifier, Value)>,
+    },
+    /// No pattern matched.
+    NoMatch,
+    /// The value must be evaluated, and its shape passed to
+    /// [`Selection::resume`], before matching can continue.
+    Evaluate(Value),
+}
+
+/// Finds the first pattern that matches a value, evaluating only as much of the
+/// value as is required.
+///
+/// The value as a whole is evaluated at most once. Its parts are asked for
+/// again by each pattern that inspects them, so evaluators which do not share
+/// evaluated values will evaluate them again.
+#[derive(Debug)]
+pub struct Selection<Value, Arm> {
+    value: Value,
+    /// The shape of the value, once it has been evaluated.
+    shape: Option<Shape<Value>>,
+    /// The patterns after the current one.
+    patterns: VecDeque<(Pattern, Arm)>,
+    /// The result of the current pattern, if there is one.
+    result: Option<Arm>,
+    /// The parts of the value still to be matched against the current pattern,
+    /// with the next at the end. A part of `None` is the value as a whole.
+    pending: Vec<(Pattern, Option<Value>)>,
+    /// The variables bound so far by the current pattern.
+    variables: Vec<(Identifier, Value)>,
+    /// The pattern waiting on its part of the value to be evaluated.
+    waiting: Option<(Pattern, Option<Value>)>,
+}
+
+impl<Value: Clone, Arm> Selection<Value, Arm> {
+    pub fn new(value: Value, patterns: impl IntoIterator<Item = (Pattern, Arm)>) -> Self {
+        Self {
+            value,
+            shape: None,
+            patterns: patterns.into_iter().collect(),
+            result: None,
+            pending: vec![],
+            variables: vec![],
+            waiting: None,
+        }
+    }
+
+    /// The value being matched.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Matches as far as possible without evaluating anything further.
+    pub fn advance(&mut self) -> Selected<Value, Arm> {
+        loop {
+            let Some((pattern, part)) = self.pending.pop() else {
+                if let Some(result) = self.result.take() {
+                    return Selected::Matched {
+                        result,
+                        variables: std::mem::take(&mut self.variables),
+                    };
+                }
+                let Some((pattern, result)) = self.patterns.pop_front() else {
+                    return Selected::NoMatch;
+                };
+                self.result = Some(result);
+                self.pending.push((pattern, None));
+                continue;
+            };
+            match pattern {
+                Pattern::Anything => {}
+                Pattern::Variable(name) => {
+                    let value = part.unwrap_or_else(|| self.value.clone());
+                    self.variables.push((name, value));
+                }
+                Pattern::Some(_) | Pattern::None | Pattern::Ok(_) | Pattern::Err(_) => {
+                    self.pending.push((pattern.encoded(), part));
+                }
+                pattern => match (part, &self.shape) {
+                    (None, Some(shape)) => {
+                        let shape = shape.clone();
+                        self.inspect(pattern, shape);
+                    }
+                    (part, _) => {
+                        let value = part.clone().unwrap_or_else(|| self.value.clone());
+                        self.waiting = Some((pattern, part));
+                        return Selected::Evaluate(value);
+                    }
+                },
+            }
+        }
+    }
+
+    /// Continues matching, given the shape of the value asked for by
+    /// [`Selected::Evaluate`].
+    pub fn resume(&mut self, shape: Shape<Value>) -> Selected<Value, Arm> {
+        let (pattern, part) = self
+            .waiting
+            .take()
+            .expect("Resumed a selection which was not waiting for a value.");
+        if part.is_none() {
+            self.shape = Some(shape.clone());
+        }
+        self.inspect(pattern, shape);
+        self.advance()
+    }
+
+    /// Matches the outermost constructor of a pattern, moving on to the next
+    /// pattern if it does not match.
+    fn inspect(&mut self, pattern: Pattern, shape: Shape<Value>) {
+        let matches = match (pattern, shape) {
+            (Pattern::Primitive(expected), Shape::Primitive(actual)) => expected == actual,
+            (Pattern::Tuple(patterns), Shape::Tuple(elements))
+                if patterns.len() == elements.len() =>
+            {
+                self.pending.extend(
+                    patterns
+                        .into_iter()
+                        .zip(elements.into_iter().map(Some))
+                        .rev(),
+                );
+                true
+            }
+            (Pattern::EmptyList, Shape::List(List::Empty)) => true,
+            (Pattern::Cons { head, tail }, Shape::List(List::Cons { head: h, tail: t })) => {
+                self.pending.push((*tail, Some(t)));
+                self.pending.push((*head, Some(h)));
+                true
+            }
+            _ => false,
+        };
+        if !matches {
+            self.result = None;
+            self.pending.clear();
+            self.variables.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(name: &str) -> Identifier {
+        Identifier::name_from_str(name).unwrap()
+    }
+
+    #[test]
+    fn test_binds_variables_without_evaluating() {
+        let mut selection = Selection::new("value", [(Pattern::Variable(name("x")), 1)]);
+
+        assert_eq!(
+            selection.advance(),
+            Selected::Matched {
+                result: 1,
+                variables: vec![(name("x"), "value")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_evaluates_the_value_once_for_every_pattern() {
+        let mut selection = Selection::new(
+            "value",
+            [
+                (Pattern::Primitive(Primitive::Integer(1.into())), 1),
+                (Pattern::Primitive(Primitive::Integer(2.into())), 2),
+                (Pattern::Anything, 3),
+            ],
+        );
+
+        assert_eq!(selection.advance(), Selected::Evaluate("value"));
+        assert_eq!(
+            selection.resume(Shape::Primitive(Primitive::Integer(2.into()))),
+            Selected::Matched {
+                result: 2,
+                variables: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_evaluates_parts_of_the_value_in_order() {
+        let pattern = Pattern::Cons {
+            head: Pattern::Tuple(vec![
+                Pattern::Variable(name("a")),
+                Pattern::Primitive(Primitive::Boolean(true)),
+            ])
+            .into(),
+            tail: Pattern::EmptyList.into(),
+        };
+        let mut selection = Selection::new("list", [(pattern, 1), (Pattern::Anything, 2)]);
+
+        assert_eq!(selection.advance(), Selected::Evaluate("list"));
+        assert_eq!(
+            selection.resume(Shape::List(List::Cons {
+                head: "head",
+                tail: "tail",
+            })),
+            Selected::Evaluate("head")
+        );
+        assert_eq!(
+            selection.resume(Shape::Tuple(vec!["first", "second"])),
+            Selected::Evaluate("second")
+        );
+        assert_eq!(
+            selection.resume(Shape::Primitive(Primitive::Boolean(true))),
+            Selected::Evaluate("tail")
+        );
+        assert_eq!(
+            selection.resume(Shape::List(List::Empty)),
+            Selected::Matched {
+                result: 1,
+                variables: vec![(name("a"), "first")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_forgets_variables_bound_by_patterns_that_do_not_match() {
+        let pattern = Pattern::Tuple(vec![
+            Pattern::Variable(name("a")),
+            Pattern::Primitive(Primitive::Integer(0.into())),
+        ]);
+        let mut selection = Selection::new("pair", [(pattern, 1)]);
+
+        assert_eq!(selection.advance(), Selected::Evaluate("pair"));
+        assert_eq!(
+            selection.resume(Shape::Tuple(vec!["first", "second"])),
+            Selected::Evaluate("second")
+        );
+        assert_eq!(
+            selection.resume(Shape::Primitive(Primitive::Integer(1.into()))),
+            Selected::NoMatch
+        );
+    }
+}
@@ -1,14 +1,28 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::error::Result;
+use crate::evaluation::IntegerOverflow;
 use crate::identifier::Identifier;
 use crate::primitive::Primitive;
 
 pub trait NativeContext {
     fn lookup_value(&self, identifier: &Identifier) -> Result<Primitive>;
+
+    /// Applies the function bound to the given name, such as one of the
+    /// native's parameters, to the argument, and evaluates the result, which
+    /// must be a primitive.
+    ///
+    /// This allows a native to call back into a function supplied by the
+    /// program.
+    fn apply(&self, function: &Identifier, argument: Primitive) -> Result<Primitive>;
+
+    /// How arithmetic on small integers should handle overflow.
+    fn integer_overflow(&self) -> IntegerOverflow {
+        IntegerOverflow::default()
+    }
 }
 
-type Implementation = Rc<dyn Fn(&dyn NativeContext) -> Result<Primitive>>;
+type Implementation = Arc<dyn Fn(&dyn NativeContext) -> Result<Primitive> + Send + Sync>;
 
 #[derive(Clone)]
 pub struct Native {
@@ -0,0 +1,701 @@
+//! Optimizations over the core AST, which simplify an expression without
+//! changing its result.
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::expr::*;
+use crate::identifier::Identifier;
+use crate::span::Span;
+
+/// Functions larger than this, counted in expression nodes, are not inlined.
+const INLINING_SIZE_LIMIT: usize = 16;
+
+/// Applies all optimizations to the expression.
+pub fn optimize(expr: Expr) -> Expr {
+    Optimizations::ALL.apply(expr)
+}
+
+/// A selection of optimizations, which can be switched on and off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Optimizations {
+    /// Whether to apply [`inline`].
+    pub inline: bool,
+    /// Whether to apply [`eliminate_dead_code`].
+    pub eliminate_dead_code: bool,
+}
+
+impl Optimizations {
+    pub const ALL: Self = Self {
+        inline: true,
+        eliminate_dead_code: true,
+    };
+
+    pub const NONE: Self = Self {
+        inline: false,
+        eliminate_dead_code: false,
+    };
+
+    /// Applies the selected optimizations to the expression.
+    pub fn apply(self, mut expr: Expr) -> Expr {
+        if self.inline {
+            expr = inline(expr);
+        }
+        // inlining can leave bindings unused, so we eliminate them afterwards
+        if self.eliminate_dead_code {
+            expr = eliminate_dead_code(expr);
+        }
+        expr
+    }
+}
+
+impl Default for Optimizations {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Inlines small, non-recursive functions bound with `let` at each of their
+/// call sites.
+///
+/// A call, `f x`, is replaced with the function body, with the argument bound
+/// to the parameter: `let parameter = x in body`. Calls are only replaced where
+/// none of the names the function refers to have been shadowed.
+pub fn inline(expr: Expr) -> Expr {
+    inline_with(expr, Rc::new(HashMap::new()))
+}
+
+/// Removes `let` bindings whose names are never referenced.
+///
+/// As evaluation is lazy, the value of such a binding would never be
+/// evaluated, so removing it cannot change the result.
+///
+/// Natives look up their arguments by name, which cannot be seen from the
+/// outside, so any bindings around a native are kept.
+pub fn eliminate_dead_code(expr: Expr) -> Expr {
+    eliminate(expr).0
+}
+
+/// The names referred to by an expression.
+#[derive(Default)]
+pub(crate) struct References {
+    pub(crate) names: HashSet<Identifier>,
+    /// Set if the expression contains a native, which could refer to anything.
+    unknown: bool,
+}
+
+impl References {
+    fn contains(&self, name: &Identifier) -> bool {
+        self.unknown || self.names.contains(name)
+    }
+
+    fn remove(&mut self, name: &Identifier) {
+        self.names.remove(name);
+    }
+
+    fn extend(&mut self, other: References) {
+        self.names.extend(other.names);
+        self.unknown |= other.unknown;
+    }
+}
+
+/// Removes unreferenced bindings from the expression, returning the result
+/// along with the names it refers to.
+pub(crate) fn eliminate(expr: Expr) -> (Expr, References) {
+    expr.fold(|span, expression| match expression {
+        Expression::Primitive(primitive) => (
+            Expr::new(span, Expression::Primitive(primitive)),
+            References::default(),
+        ),
+        Expression::Native(native) => (
+            Expr::new(span, Expression::Native(native)),
+            References {
+                names: HashSet::new(),
+                unknown: true,
+            },
+        ),
+        Expression::Identifier(name) => {
+            let references = References {
+                names: [name.clone()].into(),
+                unknown: false,
+            };
+            (Expr::new(span, Expression::Identifier(name)), references)
+        }
+        Expression::Function(Function {
+            parameter,
+            parameter_type,
+            parameter_type_span,
+            body: (body, mut references),
+        }) => {
+            references.remove(&parameter);
+            let function = Function {
+                parameter,
+                parameter_type,
+                parameter_type_span,
+                body,
+            };
+            (Expr::new(span, Expression::Function(function)), references)
+        }
+        Expression::Apply(Apply {
+            function: (function, mut references),
+            argument: (argument, argument_references),
+        }) => {
+            references.extend(argument_references);
+            let apply = Apply { function, argument };
+            (Expr::new(span, Expression::Apply(apply)), references)
+        }
+        Expression::Assign(Assign {
+            name,
+            value: (value, value_references),
+            inner: (inner, mut references),
+        }) => {
+            if !references.contains(&name) {
+                return (inner, references);
+            }
+            references.remove(&name);
+            references.extend(value_references);
+            let assign = Assign { name, value, inner };
+            (Expr::new(span, Expression::Assign(assign)), references)
+        }
+        Expression::AssignRecursive(AssignRecursive {
+            bindings,
+            inner: (inner, mut references),
+        }) => {
+            let mut values = bindings
+                .into_iter()
+                .map(|(name, value)| (name, Some(value)))
+                .collect::<Vec<_>>();
+            // keep each binding referred to by the inner expression, or by
+            // another binding that is kept
+            let mut kept = vec![];
+            while let Some(index) = values
+                .iter()
+                .position(|(name, value)| value.is_some() && references.contains(name))
+            {
+                let (name, value) = &mut values[index];
+                let (value, value_references) = value.take().unwrap();
+                references.extend(value_references);
+                kept.push((index, (name.clone(), value)));
+            }
+            if kept.is_empty() {
+                return (inner, references);
+            }
+            for (name, _) in values.iter() {
+                references.remove(name);
+            }
+            // retain the original order of the bindings
+            kept.sort_by_key(|(index, _)| *index);
+            let assign = AssignRecursive {
+                bindings: kept.into_iter().map(|(_, binding)| binding).collect(),
+                inner,
+            };
+            (
+                Expr::new(span, Expression::AssignRecursive(assign)),
+                references,
+            )
+        }
+        Expression::Match(Match {
+            value: (value, mut references),
+            patterns,
+        }) => {
+            let patterns = patterns
+                .into_iter()
+                .map(
+                    |PatternMatch {
+                         pattern,
+                         pattern_span,
+                         result: (result, mut result_references),
+                     }| {
+                        for variable in pattern.variables() {
+                            result_references.remove(variable);
+                        }
+                        references.extend(result_references);
+                        PatternMatch {
+                            pattern,
+                            pattern_span,
+                            result,
+                        }
+                    },
+                )
+                .collect();
+            let match_ = Match { value, patterns };
+            (Expr::new(span, Expression::Match(match_)), references)
+        }
+        Expression::Typed(Typed {
+            expression: (expression, references),
+            typ,
+            typ_span,
+        }) => {
+            let typed = Typed {
+                expression,
+                typ,
+                typ_span,
+            };
+            (Expr::new(span, Expression::Typed(typed)), references)
+        }
+        Expression::Sequence(Sequence {
+            first: (first, mut references),
+            second: (second, second_references),
+        }) => {
+            references.extend(second_references);
+            let sequence = Sequence { first, second };
+            (Expr::new(span, Expression::Sequence(sequence)), references)
+        }
+        Expression::Tuple(Tuple { elements }) => {
+            let mut references = References::default();
+            let elements = elements
+                .into_iter()
+                .map(|(element, element_references)| {
+                    references.extend(element_references);
+                    element
+                })
+                .collect();
+            (
+                Expr::new(span, Expression::Tuple(Tuple { elements })),
+                references,
+            )
+        }
+        Expression::List(List::Empty) => (
+            Expr::new(span, Expression::List(List::Empty)),
+            References::default(),
+        ),
+        Expression::List(List::Cons {
+            head: (head, mut references),
+            tail: (tail, tail_references),
+        }) => {
+            references.extend(tail_references);
+            let list = List::Cons { head, tail };
+            (Expr::new(span, Expression::List(list)), references)
+        }
+    })
+}
+
+/// A function that can be inlined.
+#[derive(Clone)]
+struct Inlinable {
+    parameter: Identifier,
+    parameter_type: Option<crate::types::Monotype>,
+    parameter_type_span: Option<crate::span::Span>,
+    body: Expr,
+    references: HashSet<Identifier>,
+}
+
+type Inlinables = HashMap<Identifier, Inlinable>;
+
+impl Inlinable {
+    /// Decides whether a function bound to the given name can be inlined.
+    fn new(name: &Identifier, value: &Expr) -> Option<Self> {
+        let Expression::Function(function) = value.expression() else {
+            return None;
+        };
+        if size(value) > INLINING_SIZE_LIMIT {
+            return None;
+        }
+        let (body, mut references) = eliminate(function.body.clone());
+        references.remove(&function.parameter);
+        // a function that refers to a name it shadows cannot be moved into
+        // the scope of that name
+        if references.unknown || references.names.contains(name) {
+            return None;
+        }
+        Some(Self {
+            parameter: function.parameter.clone(),
+            parameter_type: function.parameter_type.clone(),
+            parameter_type_span: function.parameter_type_span,
+            body,
+            references: references.names,
+        })
+    }
+
+    /// Replaces a call to the function with its body.
+    fn apply(self, argument: Expr, span: Option<crate::span::Span>) -> Expr {
+        let value = match self.parameter_type {
+            Some(typ) => Expr::new(
+                argument.span(),
+                Expression::Typed(Typed {
+                    expression: argument,
+                    typ,
+                    typ_span: self.parameter_type_span,
+                }),
+            ),
+            None => argument,
+        };
+        Expr::new(
+            span,
+            Expression::Assign(Assign {
+                name: self.parameter,
+                value,
+                inner: self.body,
+            }),
+        )
+    }
+}
+
+/// Removes the functions which are shadowed by the given names, or which refer
+/// to them, so that they are not inlined where those names mean something
+/// else.
+fn shadow<'a>(
+    inlinables: &Rc<Inlinables>,
+    names: impl IntoIterator<Item = &'a Identifier>,
+) -> Rc<Inlinables> {
+    let names = names.into_iter().collect::<HashSet<_>>();
+    if names.is_empty() {
+        return Rc::clone(inlinables);
+    }
+    Rc::new(
+        inlinables
+            .iter()
+            .filter(|(name, inlinable)| {
+                !names.contains(name) && inlinable.references.iter().all(|r| !names.contains(r))
+            })
+            .map(|(name, inlinable)| (name.clone(), inlinable.clone()))
+            .collect(),
+    )
+}
+
+/// Inlines the given functions throughout the expression.
+///
+/// This works through an explicit stack, rather than recursing, so that the
+/// depth of the expression is not limited by the size of the stack.
+fn inline_with(expr: Expr, inlinables: Rc<Inlinables>) -> Expr {
+    enum Work {
+        /// Inlines functions within the expression.
+        Inline(Expr, Rc<Inlinables>),
+        /// Replaces a call with the body of the function, once the argument
+        /// is done.
+        Call(Inlinable, Option<Span>),
+        /// Continues with the rest of an assignment once the value is done,
+        /// as the value decides what can be inlined within the rest.
+        AssignInner {
+            span: Option<Span>,
+            name: Identifier,
+            inner: Expr,
+            inlinables: Rc<Inlinables>,
+        },
+        /// Puts an expression back together from its subexpressions.
+        Build(Option<Span>, Expression<()>),
+    }
+
+    let mut work = vec![Work::Inline(expr, inlinables)];
+    let mut done: Vec<Expr> = vec![];
+    while let Some(next) = work.pop() {
+        match next {
+            Work::Inline(expr, inlinables) => {
+                let span = expr.span();
+                let expression = match expr.take() {
+                    Expression::Apply(Apply { function, argument }) => {
+                        let inlinable = match function.expression() {
+                            Expression::Identifier(name) => inlinables.get(name),
+                            _ => None,
+                        };
+                        if let Some(inlinable) = inlinable {
+                            work.push(Work::Call(inlinable.clone(), span));
+                            work.push(Work::Inline(argument, inlinables));
+                            continue;
+                        }
+                        Expression::Apply(Apply { function, argument })
+                    }
+                    Expression::Assign(Assign { name, value, inner }) => {
+                        work.push(Work::AssignInner {
+                            span,
+                            name,
+                            inner,
+                            inlinables: Rc::clone(&inlinables),
+                        });
+                        work.push(Work::Inline(value, inlinables));
+                        continue;
+                    }
+                    expression => expression,
+                };
+                // each subexpression is inlined within the scope it is in
+                let scopes = match &expression {
+                    Expression::Function(Function { parameter, .. }) => {
+                        vec![shadow(&inlinables, [parameter])]
+                    }
+                    Expression::AssignRecursive(AssignRecursive { bindings, .. }) => {
+                        let group_inlinables =
+                            shadow(&inlinables, bindings.iter().map(|(name, _)| name));
+                        vec![group_inlinables; bindings.len() + 1]
+                    }
+                    Expression::Match(Match { patterns, .. }) => [Rc::clone(&inlinables)]
+                        .into_iter()
+                        .chain(patterns.iter().map(|PatternMatch { pattern, .. }| {
+                            shadow(&inlinables, pattern.variables())
+                        }))
+                        .collect(),
+                    expression => vec![Rc::clone(&inlinables); expression.children().len()],
+                };
+                let mut children = vec![];
+                let shell = expression.map(|child| children.push(child));
+                work.push(Work::Build(span, shell));
+                work.extend(
+                    children
+                        .into_iter()
+                        .zip(scopes)
+                        .rev()
+                        .map(|(child, scope)| Work::Inline(child, scope)),
+                );
+            }
+            Work::Call(inlinable, span) => {
+                let argument = done.pop().unwrap();
+                done.push(inlinable.apply(argument, span));
+            }
+            Work::AssignInner {
+                span,
+                name,
+                inner,
+                inlinables,
+            } => {
+                let value = done.last().unwrap();
+                let mut inner_inlinables = shadow(&inlinables, [&name]);
+                if let Some(inlinable) = Inlinable::new(&name, value) {
+                    Rc::make_mut(&mut inner_inlinables).insert(name.clone(), inlinable);
+                }
+                let shell = Expression::Assign(Assign {
+                    name,
+                    value: (),
+                    inner: (),
+                });
+                work.push(Work::Build(span, shell));
+                work.push(Work::Inline(inner, inner_inlinables));
+            }
+            Work::Build(span, shell) => {
+                let start = done.len() - shell.children().len();
+                let mut children = done.split_off(start).into_iter();
+                done.push(Expr::new(span, shell.map(|()| children.next().unwrap())));
+            }
+        }
+    }
+    done.pop().unwrap()
+}
+
+/// The number of nodes in an expression.
+fn size(expr: &Expr) -> usize {
+    let mut size = 0;
+    let mut pending = vec![expr];
+    while let Some(next) = pending.pop() {
+        size += 1;
+        pending.extend(next.expression().children());
+    }
+    size
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::primitive::Primitive;
+
+    use super::*;
+
+    #[test]
+    fn test_removes_an_unused_assignment() {
+        let expr = assign("x", integer(1), integer(2));
+
+        let result = eliminate_dead_code(expr);
+
+        assert_eq!(result, integer(2));
+    }
+
+    #[test]
+    fn test_keeps_a_used_assignment() {
+        let expr = assign("x", integer(1), identifier("x"));
+
+        let result = eliminate_dead_code(expr.clone());
+
+        assert_eq!(result, expr);
+    }
+
+    #[test]
+    fn test_respects_shadowing() {
+        let expr = assign("x", integer(1), assign("x", integer(2), identifier("x")));
+
+        let result = eliminate_dead_code(expr);
+
+        assert_eq!(result, assign("x", integer(2), identifier("x")));
+    }
+
+    #[test]
+    fn test_removes_assignments_only_used_by_removed_assignments() {
+        let expr = assign("x", integer(1), assign("y", identifier("x"), integer(2)));
+
+        let result = eliminate_dead_code(expr);
+
+        assert_eq!(result, integer(2));
+    }
+
+    #[test]
+    fn test_removes_unused_recursive_bindings() {
+        let expr = assign_recursive(
+            &[
+                ("f", identifier("g")),
+                ("g", identifier("f")),
+                ("h", identifier("h")),
+            ],
+            identifier("h"),
+        );
+
+        let result = eliminate_dead_code(expr);
+
+        assert_eq!(
+            result,
+            assign_recursive(&[("h", identifier("h"))], identifier("h"))
+        );
+    }
+
+    #[test]
+    fn test_keeps_assignments_around_natives() {
+        let expr = assign(
+            "x",
+            integer(1),
+            Expr::new(
+                None,
+                Expression::Native(crate::native::Native {
+                    unique_name: Identifier::name_from_str("native").unwrap(),
+                    implementation: std::sync::Arc::new(|context| {
+                        context.lookup_value(&Identifier::name_from_str("x").unwrap())
+                    }),
+                }),
+            ),
+        );
+
+        let result = eliminate_dead_code(expr.clone());
+
+        assert_eq!(result, expr);
+    }
+
+    #[test]
+    fn test_inlines_a_small_function() {
+        let expr = assign(
+            "f",
+            function("x", identifier("x")),
+            apply(identifier("f"), integer(1)),
+        );
+
+        let result = optimize(expr);
+
+        assert_eq!(result, assign("x", integer(1), identifier("x")));
+    }
+
+    #[test]
+    fn test_keeps_a_function_that_is_not_only_called() {
+        let expr = assign(
+            "f",
+            function("x", identifier("x")),
+            apply(identifier("g"), identifier("f")),
+        );
+
+        let result = optimize(expr.clone());
+
+        assert_eq!(result, expr);
+    }
+
+    #[test]
+    fn test_does_not_inline_recursive_functions() {
+        let expr = assign_recursive(
+            &[("f", function("x", apply(identifier("f"), identifier("x"))))],
+            apply(identifier("f"), integer(1)),
+        );
+
+        let result = inline(expr.clone());
+
+        assert_eq!(result, expr);
+    }
+
+    #[test]
+    fn test_does_not_inline_large_functions() {
+        let body =
+            (0..INLINING_SIZE_LIMIT).fold(identifier("x"), |body, _| apply(identifier("g"), body));
+        let expr = assign("f", function("x", body), apply(identifier("f"), integer(1)));
+
+        let result = inline(expr.clone());
+
+        assert_eq!(result, expr);
+    }
+
+    #[test]
+    fn test_does_not_inline_where_a_referenced_name_is_shadowed() {
+        let expr = assign(
+            "y",
+            integer(1),
+            assign(
+                "f",
+                function("x", identifier("y")),
+                assign("y", integer(2), apply(identifier("f"), integer(3))),
+            ),
+        );
+
+        let result = inline(expr.clone());
+
+        assert_eq!(result, expr);
+    }
+
+    #[test]
+    fn test_does_not_inline_a_function_referring_to_the_name_it_shadows() {
+        let expr = assign(
+            "f",
+            function("x", identifier("x")),
+            assign(
+                "f",
+                function("y", apply(identifier("g"), identifier("f"))),
+                apply(identifier("f"), integer(1)),
+            ),
+        );
+
+        let result = inline(expr.clone());
+
+        assert_eq!(result, expr);
+    }
+
+    fn assign(name: &str, value: Expr, inner: Expr) -> Expr {
+        Expr::new(
+            None,
+            Expression::Assign(Assign {
+                name: Identifier::name_from_str(name).unwrap(),
+                value,
+                inner,
+            }),
+        )
+    }
+
+    fn assign_recursive(bindings: &[(&str, Expr)], inner: Expr) -> Expr {
+        Expr::new(
+            None,
+            Expression::AssignRecursive(AssignRecursive {
+                bindings: bindings
+                    .iter()
+                    .map(|(name, value)| (Identifier::name_from_str(name).unwrap(), value.clone()))
+                    .collect(),
+                inner,
+            }),
+        )
+    }
+
+    fn function(parameter: &str, body: Expr) -> Expr {
+        Expr::new(
+            None,
+            Expression::Function(Function {
+                parameter: Identifier::name_from_str(parameter).unwrap(),
+                parameter_type: None,
+                parameter_type_span: None,
+                body,
+            }),
+        )
+    }
+
+    fn apply(function: Expr, argument: Expr) -> Expr {
+        Expr::new(None, Expression::Apply(Apply { function, argument }))
+    }
+
+    fn identifier(name: &str) -> Expr {
+        Expr::new(
+            None,
+            Expression::Identifier(Identifier::name_from_str(name).unwrap()),
+        )
+    }
+
+    fn integer(value: i64) -> Expr {
+        Expr::new(
+            None,
+            Expression::Primitive(Primitive::Integer(value.into())),
+        )
+    }
+}
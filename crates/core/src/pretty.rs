@@ -0,0 +1,239 @@
+//! A Wadler-style pretty-printer.
+//!
+//! Expressions are converted to a [`Doc`], which describes the text along
+//! with the places where it may be broken across lines. Each [group][Doc::group]
+//! is printed on a single line if it fits within the line width, and otherwise
+//! every line break directly within it is taken, with nested text indented.
+
+use std::fmt::Write;
+
+/// The line width used when none is specified.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// The indentation for each level of nesting.
+const INDENT: usize = 2;
+
+/// A document to be pretty-printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Doc {
+    /// Literal text, which should not contain line breaks.
+    Text(String),
+    /// A line break, or the given text if the enclosing group fits on a line.
+    Line(&'static str),
+    /// A sequence of documents.
+    Concat(Vec<Doc>),
+    /// Indents any line breaks within the document by one more level.
+    Nest(Box<Doc>),
+    /// Lays out the document on a single line if it fits.
+    Group(Box<Doc>),
+}
+
+impl Doc {
+    /// Literal text.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+
+    /// A line break, or a space when flattened.
+    pub fn line() -> Self {
+        Self::Line(" ")
+    }
+
+    /// A line break, or nothing when flattened.
+    pub fn softline() -> Self {
+        Self::Line("")
+    }
+
+    /// A sequence of documents.
+    pub fn concat(docs: impl IntoIterator<Item = Doc>) -> Self {
+        Self::Concat(docs.into_iter().collect())
+    }
+
+    /// Indents the line breaks within the document.
+    pub fn nest(self) -> Self {
+        Self::Nest(self.into())
+    }
+
+    /// Lays out the document on a single line if it fits.
+    pub fn group(self) -> Self {
+        Self::Group(self.into())
+    }
+
+    /// Joins the documents, separating them with the separator.
+    pub fn join(docs: impl IntoIterator<Item = Doc>, separator: Doc) -> Self {
+        let mut result = Vec::new();
+        for (index, doc) in docs.into_iter().enumerate() {
+            if index > 0 {
+                result.push(separator.clone());
+            }
+            result.push(doc);
+        }
+        Self::Concat(result)
+    }
+
+    /// Surrounds the document with the given brackets. If it does not fit on a
+    /// line, the contents are indented on their own lines.
+    pub fn bracket(open: &'static str, doc: Doc, close: &'static str) -> Self {
+        Self::concat([
+            Self::text(open),
+            Self::concat([Self::softline(), doc]).nest(),
+            Self::softline(),
+            Self::text(close),
+        ])
+        .group()
+    }
+
+    /// Surrounds the document with parentheses.
+    pub fn parenthesize(doc: Doc) -> Self {
+        Self::bracket("(", doc, ")")
+    }
+
+    /// Renders the document, breaking lines so that they fit within the width
+    /// where possible.
+    pub fn render(&self, width: usize) -> String {
+        let mut output = String::new();
+        self.render_to(width, &mut output)
+            .expect("Writing to a string cannot fail.");
+        output
+    }
+
+    /// Renders the document to the writer.
+    pub fn render_to(&self, width: usize, output: &mut impl Write) -> std::fmt::Result {
+        let mut column = 0;
+        let mut stack = vec![(0, Mode::Break, self)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Text(text) => {
+                    output.write_str(text)?;
+                    column += text.chars().count();
+                }
+                Doc::Line(flat) => match mode {
+                    Mode::Flat => {
+                        output.write_str(flat)?;
+                        column += flat.len();
+                    }
+                    Mode::Break => {
+                        writeln!(output)?;
+                        write!(output, "{:indent$}", "")?;
+                        column = indent;
+                    }
+                },
+                Doc::Concat(docs) => {
+                    stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+                }
+                Doc::Nest(doc) => stack.push((indent + INDENT, mode, doc)),
+                Doc::Group(doc) => {
+                    let mode =
+                        if mode == Mode::Flat || fits(width.saturating_sub(column), doc, &stack) {
+                            Mode::Flat
+                        } else {
+                            Mode::Break
+                        };
+                    stack.push((indent, mode, doc));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Whether the document fits in the remaining width when flattened, along
+/// with whatever follows it up to the next line break.
+fn fits(mut remaining: usize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut stack = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev();
+    loop {
+        let (mode, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some((_, mode, doc)) => (*mode, *doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(text) => match remaining.checked_sub(text.chars().count()) {
+                Some(left) => remaining = left,
+                None => return false,
+            },
+            Doc::Line(flat) => match mode {
+                Mode::Flat => match remaining.checked_sub(flat.len()) {
+                    Some(left) => remaining = left,
+                    None => return false,
+                },
+                Mode::Break => return true,
+            },
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (mode, doc))),
+            Doc::Nest(doc) | Doc::Group(doc) => stack.push((mode, doc)),
+        }
+    }
+}
+
+/// Values which can be pretty-printed.
+pub trait Pretty {
+    /// Describes the layout of the value.
+    fn to_doc(&self) -> Doc;
+
+    /// Renders the value, breaking lines to fit within the width.
+    fn pretty(&self, width: usize) -> String {
+        self.to_doc().render(width)
+    }
+}
+
+impl<T: Pretty + ?Sized> Pretty for Box<T> {
+    fn to_doc(&self) -> Doc {
+        self.as_ref().to_doc()
+    }
+}
+
+/// Implements [`Display`][std::fmt::Display] using the pretty-printer.
+///
+/// The line width can be set with the formatter's width, e.g. `{:40}`, and
+/// defaults to [`DEFAULT_WIDTH`].
+pub fn display(value: &impl Pretty, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    value
+        .to_doc()
+        .render_to(f.width().unwrap_or(DEFAULT_WIDTH), f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Doc {
+        Doc::concat([
+            Doc::text("let x = "),
+            Doc::parenthesize(Doc::join(
+                ["one", "two", "three"].map(Doc::text),
+                Doc::concat([Doc::text(","), Doc::line()]),
+            )),
+            Doc::text(" in"),
+            Doc::line(),
+            Doc::text("x"),
+        ])
+        .group()
+    }
+
+    #[test]
+    fn test_rendering_on_one_line() {
+        assert_eq!(example().render(80), "let x = (one, two, three) in x");
+    }
+
+    #[test]
+    fn test_breaking_the_outer_group_first() {
+        assert_eq!(example().render(28), "let x = (one, two, three) in\nx");
+    }
+
+    #[test]
+    fn test_breaking_nested_groups() {
+        assert_eq!(
+            example().render(16),
+            "let x = (\n  one,\n  two,\n  three\n) in\nx"
+        );
+    }
+}
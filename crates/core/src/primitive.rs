@@ -1,11 +1,16 @@
 //! Primitive values.
 
+pub mod float;
 pub mod integer;
 
+use std::sync::Arc;
+
+use proptest::prelude::*;
 use proptest::strategy::{BoxedStrategy, Strategy};
 
 use crate::types::{Type, TypeRef};
 
+pub use float::*;
 pub use integer::*;
 
 /// The set of valid primitive values.
@@ -13,12 +18,61 @@ pub use integer::*;
 pub enum Primitive {
     /// An [`Integer`] value.
     Integer(Integer),
+    /// A [`Float`] value.
+    Float(Float),
+    /// A boolean value, either `true` or `false`.
+    Boolean(bool),
+    /// A single Unicode character.
+    Char(char),
+    /// A string of Unicode characters.
+    String(Arc<String>),
 }
 
 impl std::fmt::Display for Primitive {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Primitive::Integer(value) => write!(f, "{}", value),
+            Primitive::Float(value) => write!(f, "{}", value),
+            Primitive::Boolean(value) => write!(f, "{}", value),
+            Primitive::Char(value) => {
+                write!(f, "'")?;
+                write_escaped(f, *value, '\'')?;
+                write!(f, "'")
+            }
+            Primitive::String(value) => {
+                write!(f, "\"")?;
+                for c in value.chars() {
+                    write_escaped(f, c, '"')?;
+                }
+                write!(f, "\"")
+            }
+        }
+    }
+}
+
+/// Writes a character inside a quoted literal, escaping it if necessary.
+fn write_escaped(f: &mut std::fmt::Formatter<'_>, c: char, quote: char) -> std::fmt::Result {
+    match c {
+        '\\' => write!(f, "\\\\"),
+        '\n' => write!(f, "\\n"),
+        '\r' => write!(f, "\\r"),
+        '\t' => write!(f, "\\t"),
+        _ if c == quote => write!(f, "\\{}", c),
+        _ => write!(f, "{}", c),
+    }
+}
+
+impl PartialOrd for Primitive {
+    /// Primitives of the same kind are ordered by value. Primitives of
+    /// different kinds cannot be compared.
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Primitive::Integer(left), Primitive::Integer(right)) => left.partial_cmp(right),
+            (Primitive::Float(left), Primitive::Float(right)) => left.partial_cmp(right),
+            (Primitive::Boolean(left), Primitive::Boolean(right)) => left.partial_cmp(right),
+            (Primitive::Char(left), Primitive::Char(right)) => left.partial_cmp(right),
+            (Primitive::String(left), Primitive::String(right)) => left.partial_cmp(right),
+            _ => None,
         }
     }
 }
@@ -28,12 +82,22 @@ impl Primitive {
     pub fn get_type<Outer: TypeRef>(&self) -> Outer {
         match self {
             Self::Integer(_) => Type::Integer.into(),
+            Self::Float(_) => Type::Float.into(),
+            Self::Boolean(_) => Type::Boolean.into(),
+            Self::Char(_) => Type::Char.into(),
+            Self::String(_) => Type::String.into(),
         }
     }
 
     /// A proptest strategy for an arbitrary primitive value.
     pub fn arbitrary() -> impl Strategy<Value = Primitive> {
-        Integer::arbitrary().prop_map(Primitive::Integer)
+        prop_oneof![
+            Integer::arbitrary().prop_map(Primitive::Integer),
+            Float::arbitrary().prop_map(Primitive::Float),
+            any::<bool>().prop_map(Primitive::Boolean),
+            any::<char>().prop_map(Primitive::Char),
+            any::<String>().prop_map(|s| Primitive::String(Arc::new(s))),
+        ]
     }
 
     /// A proptest strategy for an arbitrary primitive value of the given type.
@@ -42,6 +106,14 @@ impl Primitive {
     pub fn arbitrary_of_type(target_type: &Type<impl TypeRef>) -> Option<BoxedStrategy<Primitive>> {
         match target_type {
             Type::Integer => Some(Integer::arbitrary().prop_map(Primitive::Integer).boxed()),
+            Type::Float => Some(Float::arbitrary().prop_map(Primitive::Float).boxed()),
+            Type::Boolean => Some(any::<bool>().prop_map(Primitive::Boolean).boxed()),
+            Type::Char => Some(any::<char>().prop_map(Primitive::Char).boxed()),
+            Type::String => Some(
+                any::<String>()
+                    .prop_map(|s| Primitive::String(Arc::new(s)))
+                    .boxed(),
+            ),
             _ => None,
         }
     }
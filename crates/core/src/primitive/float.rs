@@ -0,0 +1,175 @@
+//! Primitive floating-point values.
+
+use proptest::strategy::Strategy;
+
+/// A 64-bit floating-point value.
+///
+/// Unlike [`f64`], floats are totally ordered, so that they can be compared,
+/// hashed, and matched upon. Positive and negative zero are considered equal,
+/// and all NaN values are equal to each other and greater than any other value.
+#[derive(Debug, Clone, Copy)]
+pub struct Float(f64);
+
+impl Float {
+    /// Normalizes the value so that equal values have the same representation.
+    fn normalized(self) -> f64 {
+        if self.0.is_nan() {
+            f64::NAN
+        } else if self.0 == 0.0 {
+            0.0
+        } else {
+            self.0
+        }
+    }
+
+    /// A proptest strategy for an arbitrary finite float.
+    pub fn arbitrary() -> impl Strategy<Value = Float> {
+        (proptest::num::f64::NORMAL
+            | proptest::num::f64::SUBNORMAL
+            | proptest::num::f64::ZERO
+            | proptest::num::f64::POSITIVE
+            | proptest::num::f64::NEGATIVE)
+            .prop_map(Float)
+    }
+}
+
+impl From<f64> for Float {
+    fn from(value: f64) -> Self {
+        Float(value)
+    }
+}
+
+impl From<Float> for f64 {
+    fn from(value: Float) -> Self {
+        value.0
+    }
+}
+
+impl std::str::FromStr for Float {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        f64::from_str(s).map(Float).map_err(|_| ())
+    }
+}
+
+impl std::fmt::Display for Float {
+    // The debug representation always includes a decimal point or exponent,
+    // so that it cannot be confused with an integer.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Float {}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.normalized().total_cmp(&other.normalized())
+    }
+}
+
+impl std::hash::Hash for Float {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.normalized().to_bits().hash(state)
+    }
+}
+
+impl std::ops::Add for Float {
+    type Output = Float;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Float(self.0 + rhs.0)
+    }
+}
+
+impl std::ops::Sub for Float {
+    type Output = Float;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Float(self.0 - rhs.0)
+    }
+}
+
+impl std::ops::Mul for Float {
+    type Output = Float;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Float(self.0 * rhs.0)
+    }
+}
+
+impl std::ops::Div for Float {
+    type Output = Float;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Float(self.0 / rhs.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use boo_test_helpers::proptest::*;
+
+    use super::*;
+
+    #[test]
+    fn test_from_string() {
+        check(&Float::arbitrary(), |value| {
+            let input = format!("{}", value);
+            prop_assert_eq!(input.parse::<Float>(), Ok(value));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_rendering_is_distinct_from_integers() {
+        check(&Float::arbitrary(), |value| {
+            let rendered = format!("{}", value);
+            prop_assert!(
+                rendered.contains('.') || rendered.contains('e'),
+                "rendered as {}",
+                rendered
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_zeros_are_equal() {
+        assert_eq!(Float::from(0.0), Float::from(-0.0));
+    }
+
+    #[test]
+    fn test_nan_is_equal_to_itself() {
+        assert_eq!(Float::from(f64::NAN), Float::from(-f64::NAN));
+    }
+
+    #[test]
+    fn test_comparison() {
+        check(
+            &(Float::arbitrary(), Float::arbitrary()),
+            |(left, right)| {
+                prop_assert_eq!(
+                    left.cmp(&right),
+                    f64::from(left).partial_cmp(&f64::from(right)).unwrap()
+                );
+                Ok(())
+            },
+        )
+    }
+}
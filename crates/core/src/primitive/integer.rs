@@ -1,6 +1,6 @@
 //! Primitive integer values.
 
-use num_bigint::BigInt;
+use num_bigint::{BigInt, Sign};
 use proptest::strategy::Strategy;
 
 type Small = i32;
@@ -44,6 +44,57 @@ impl From<i128> for Integer {
     }
 }
 
+impl From<u32> for Integer {
+    fn from(value: u32) -> Self {
+        match Small::try_from(value) {
+            Ok(value) => Integer::Small(value),
+            Err(_) => Integer::Large(value.into()),
+        }
+    }
+}
+
+impl From<usize> for Integer {
+    fn from(value: usize) -> Self {
+        match Small::try_from(value) {
+            Ok(value) => Integer::Small(value),
+            Err(_) => Integer::Large(value.into()),
+        }
+    }
+}
+
+impl TryFrom<&Integer> for u32 {
+    type Error = ();
+
+    fn try_from(value: &Integer) -> Result<Self, Self::Error> {
+        match value {
+            Integer::Small(value) => u32::try_from(*value).map_err(|_| ()),
+            Integer::Large(value) => u32::try_from(value).map_err(|_| ()),
+        }
+    }
+}
+
+impl TryFrom<&Integer> for i64 {
+    type Error = ();
+
+    fn try_from(value: &Integer) -> Result<Self, Self::Error> {
+        match value {
+            Integer::Small(value) => Ok((*value).into()),
+            Integer::Large(value) => i64::try_from(value).map_err(|_| ()),
+        }
+    }
+}
+
+impl TryFrom<&Integer> for usize {
+    type Error = ();
+
+    fn try_from(value: &Integer) -> Result<Self, Self::Error> {
+        match value {
+            Integer::Small(value) => usize::try_from(*value).map_err(|_| ()),
+            Integer::Large(value) => usize::try_from(value).map_err(|_| ()),
+        }
+    }
+}
+
 impl std::str::FromStr for Integer {
     type Err = ();
 
@@ -55,6 +106,18 @@ impl std::str::FromStr for Integer {
     }
 }
 
+impl Integer {
+    /// Parses an integer in the given radix, which must be between 2 and 36.
+    ///
+    /// The digits may be preceded by a sign.
+    pub fn from_str_radix(s: &str, radix: u32) -> Option<Self> {
+        Small::from_str_radix(s, radix)
+            .ok()
+            .map(Integer::Small)
+            .or_else(|| Large::parse_bytes(s.as_bytes(), radix).map(Integer::Large))
+    }
+}
+
 impl std::fmt::Display for Integer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -77,6 +140,23 @@ impl PartialEq for Integer {
 
 impl Eq for Integer {}
 
+impl PartialOrd for Integer {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Integer {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (Integer::Small(l), Integer::Small(r)) => l.cmp(r),
+            (Integer::Small(l), Integer::Large(r)) => Large::from(*l).cmp(r),
+            (Integer::Large(l), Integer::Small(r)) => l.cmp(&Large::from(*r)),
+            (Integer::Large(l), Integer::Large(r)) => l.cmp(r),
+        }
+    }
+}
+
 impl std::hash::Hash for Integer {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match self {
@@ -158,7 +238,236 @@ impl std::ops::Mul for Integer {
     }
 }
 
+impl std::ops::BitAnd for &Integer {
+    type Output = Integer;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Integer::Small(l), Integer::Small(r)) => Integer::Small(l & r),
+            (Integer::Small(l), Integer::Large(r)) => Integer::Large(Large::from(*l) & r),
+            (Integer::Large(l), Integer::Small(r)) => Integer::Large(l & Large::from(*r)),
+            (Integer::Large(l), Integer::Large(r)) => Integer::Large(l & r),
+        }
+    }
+}
+
+impl std::ops::BitAnd for Integer {
+    type Output = Integer;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        &self & &rhs
+    }
+}
+
+impl std::ops::BitOr for &Integer {
+    type Output = Integer;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Integer::Small(l), Integer::Small(r)) => Integer::Small(l | r),
+            (Integer::Small(l), Integer::Large(r)) => Integer::Large(Large::from(*l) | r),
+            (Integer::Large(l), Integer::Small(r)) => Integer::Large(l | Large::from(*r)),
+            (Integer::Large(l), Integer::Large(r)) => Integer::Large(l | r),
+        }
+    }
+}
+
+impl std::ops::BitOr for Integer {
+    type Output = Integer;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        &self | &rhs
+    }
+}
+
+impl std::ops::BitXor for &Integer {
+    type Output = Integer;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Integer::Small(l), Integer::Small(r)) => Integer::Small(l ^ r),
+            (Integer::Small(l), Integer::Large(r)) => Integer::Large(Large::from(*l) ^ r),
+            (Integer::Large(l), Integer::Small(r)) => Integer::Large(l ^ Large::from(*r)),
+            (Integer::Large(l), Integer::Large(r)) => Integer::Large(l ^ r),
+        }
+    }
+}
+
+impl std::ops::BitXor for Integer {
+    type Output = Integer;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        &self ^ &rhs
+    }
+}
+
 impl Integer {
+    /// Adds two integers, returning `None` if both are small and the result
+    /// is not.
+    pub fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        match (self, rhs) {
+            (Integer::Small(l), Integer::Small(r)) => l.checked_add(*r).map(Integer::Small),
+            _ => Some(self + rhs),
+        }
+    }
+
+    /// Subtracts one integer from another, returning `None` if both are small
+    /// and the result is not.
+    pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        match (self, rhs) {
+            (Integer::Small(l), Integer::Small(r)) => l.checked_sub(*r).map(Integer::Small),
+            _ => Some(self - rhs),
+        }
+    }
+
+    /// Multiplies two integers, returning `None` if both are small and the
+    /// result is not.
+    pub fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        match (self, rhs) {
+            (Integer::Small(l), Integer::Small(r)) => l.checked_mul(*r).map(Integer::Small),
+            _ => Some(self * rhs),
+        }
+    }
+
+    /// Divides this integer by another, rounding towards zero.
+    ///
+    /// Returns `None` if the divisor is zero.
+    pub fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        match (self, rhs) {
+            (_, Integer::Small(0)) => None,
+            (_, Integer::Large(r)) if *r == Large::from(0) => None,
+            (Integer::Small(l), Integer::Small(r)) => match (*l).checked_div(*r) {
+                Some(result) => Some(Integer::Small(result)),
+                None => Some(Integer::Large(Large::from(*l) / Large::from(*r))),
+            },
+            (Integer::Small(l), Integer::Large(r)) => Some(Integer::Large(Large::from(*l) / r)),
+            (Integer::Large(l), Integer::Small(r)) => Some(Integer::Large(l / Large::from(*r))),
+            (Integer::Large(l), Integer::Large(r)) => Some(Integer::Large(l / r)),
+        }
+    }
+
+    /// Computes the remainder of dividing this integer by another. The result
+    /// has the same sign as this integer.
+    ///
+    /// Returns `None` if the divisor is zero.
+    pub fn checked_rem(&self, rhs: &Self) -> Option<Self> {
+        match (self, rhs) {
+            (_, Integer::Small(0)) => None,
+            (_, Integer::Large(r)) if *r == Large::from(0) => None,
+            (Integer::Small(l), Integer::Small(r)) => match (*l).checked_rem(*r) {
+                Some(result) => Some(Integer::Small(result)),
+                None => Some(Integer::Large(Large::from(*l) % Large::from(*r))),
+            },
+            (Integer::Small(l), Integer::Large(r)) => Some(Integer::Large(Large::from(*l) % r)),
+            (Integer::Large(l), Integer::Small(r)) => Some(Integer::Large(l % Large::from(*r))),
+            (Integer::Large(l), Integer::Large(r)) => Some(Integer::Large(l % r)),
+        }
+    }
+
+    /// Shifts this integer left by the given number of bits.
+    ///
+    /// Returns `None` if the shift amount is negative or too large.
+    pub fn checked_shl(&self, rhs: &Self) -> Option<Self> {
+        let amount = u32::try_from(rhs).ok()?;
+        match self {
+            Integer::Small(l) if amount < Small::BITS => {
+                Some(Integer::from(i64::from(*l) << amount))
+            }
+            Integer::Small(l) => Some(Integer::Large(Large::from(*l) << amount)),
+            Integer::Large(l) => Some(Integer::Large(l << amount)),
+        }
+    }
+
+    /// Shifts this integer right by the given number of bits, rounding towards
+    /// negative infinity.
+    ///
+    /// Returns `None` if the shift amount is negative or too large.
+    pub fn checked_shr(&self, rhs: &Self) -> Option<Self> {
+        let amount = u32::try_from(rhs).ok()?;
+        match self {
+            Integer::Small(l) => Some(Integer::Small(l >> amount.min(Small::BITS - 1))),
+            Integer::Large(l) => Some(Integer::Large(l >> amount)),
+        }
+    }
+
+    /// The absolute value of this integer, returning `None` if it is small
+    /// and the result is not.
+    pub fn checked_abs(&self) -> Option<Self> {
+        match self {
+            Integer::Small(value) => value.checked_abs().map(Integer::Small),
+            Integer::Large(_) => Some(self.abs()),
+        }
+    }
+
+    /// The absolute value of this integer.
+    pub fn abs(&self) -> Self {
+        match self {
+            Integer::Small(value) => Integer::from(i64::from(*value).abs()),
+            Integer::Large(value) => Integer::Large(Large::from(value.magnitude().clone())),
+        }
+    }
+
+    /// `-1`, `0` or `1`, depending on whether this integer is negative, zero
+    /// or positive.
+    pub fn signum(&self) -> Self {
+        match self {
+            Integer::Small(value) => Integer::Small(value.signum()),
+            Integer::Large(value) => Integer::Small(match value.sign() {
+                Sign::Minus => -1,
+                Sign::NoSign => 0,
+                Sign::Plus => 1,
+            }),
+        }
+    }
+
+    /// Raises this integer to the given power, returning `None` if it is small
+    /// and the result is not.
+    pub fn checked_pow(&self, exponent: u32) -> Option<Self> {
+        match self {
+            Integer::Small(value) => value.checked_pow(exponent).map(Integer::Small),
+            Integer::Large(_) => Some(self.pow(exponent)),
+        }
+    }
+
+    /// Raises this integer to the given power.
+    pub fn pow(&self, exponent: u32) -> Self {
+        match self {
+            Integer::Small(value) => match value.checked_pow(exponent) {
+                Some(result) => Integer::Small(result),
+                None => Integer::Large(Large::from(*value).pow(exponent)),
+            },
+            Integer::Large(value) => Integer::Large(value.pow(exponent)),
+        }
+    }
+
+    /// The greatest common divisor of two integers, which is never negative.
+    ///
+    /// The greatest common divisor of zero and zero is zero.
+    pub fn gcd(&self, rhs: &Self) -> Self {
+        match (self, rhs) {
+            (Integer::Small(l), Integer::Small(r)) => {
+                let (mut a, mut b) = (i64::from(*l).abs(), i64::from(*r).abs());
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                Integer::from(a)
+            }
+            _ => {
+                let large = |value: &Integer| match value {
+                    Integer::Small(value) => Large::from(*value),
+                    Integer::Large(value) => value.clone(),
+                };
+                let (mut a, mut b) = (large(self), large(rhs));
+                while b.sign() != Sign::NoSign {
+                    let remainder = &a % &b;
+                    a = b;
+                    b = remainder;
+                }
+                Integer::Large(Large::from(a.magnitude().clone()))
+            }
+        }
+    }
+
     pub fn arbitrary() -> impl Strategy<Value = Integer> {
         proptest::num::i128::ANY.prop_map(|n| n.into())
     }
@@ -181,6 +490,51 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_from_string_with_radix() {
+        check(&(Integer::arbitrary(), 2u32..=36), |(value, radix)| {
+            let input = match &value {
+                Integer::Small(value) => Large::from(*value).to_str_radix(radix),
+                Integer::Large(value) => value.to_str_radix(radix),
+            };
+            prop_assert_eq!(Integer::from_str_radix(&input, radix), Some(value));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_conversion_to_usize() {
+        check(&any::<i128>(), |value| {
+            prop_assert_eq!(
+                usize::try_from(&Integer::from(value)),
+                usize::try_from(value).map_err(|_| ())
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_conversion_to_u32() {
+        check(&any::<i128>(), |value| {
+            prop_assert_eq!(
+                u32::try_from(&Integer::from(value)),
+                u32::try_from(value).map_err(|_| ())
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_comparison() {
+        check(&(any::<i128>(), any::<i128>()), |(left, right)| {
+            prop_assert_eq!(
+                Integer::from(left).cmp(&Integer::from(right)),
+                Large::from(left).cmp(&Large::from(right))
+            );
+            Ok(())
+        })
+    }
+
     #[test]
     fn test_addition() {
         check(&(any::<i128>(), any::<i128>()), |(left, right)| {
@@ -213,4 +567,231 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn test_division() {
+        check(
+            &(
+                any::<i128>(),
+                any::<i128>().prop_filter("non-zero", |n| *n != 0),
+            ),
+            |(left, right)| {
+                prop_assert_eq!(
+                    Integer::from(left).checked_div(&Integer::from(right)),
+                    Some(Integer::Large(Large::from(left) / Large::from(right)))
+                );
+                Ok(())
+            },
+        )
+    }
+
+    #[test]
+    fn test_division_by_zero() {
+        check(&any::<i128>(), |value| {
+            prop_assert_eq!(Integer::from(value).checked_div(&Integer::from(0)), None);
+            prop_assert_eq!(
+                Integer::from(value).checked_div(&Integer::Large(Large::from(0))),
+                None
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_remainder() {
+        check(
+            &(
+                any::<i128>(),
+                any::<i128>().prop_filter("non-zero", |n| *n != 0),
+            ),
+            |(left, right)| {
+                prop_assert_eq!(
+                    Integer::from(left).checked_rem(&Integer::from(right)),
+                    Some(Integer::Large(Large::from(left) % Large::from(right)))
+                );
+                Ok(())
+            },
+        )
+    }
+
+    #[test]
+    fn test_remainder_by_zero() {
+        check(&any::<i128>(), |value| {
+            prop_assert_eq!(Integer::from(value).checked_rem(&Integer::from(0)), None);
+            prop_assert_eq!(
+                Integer::from(value).checked_rem(&Integer::Large(Large::from(0))),
+                None
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_bitwise_and() {
+        check(&(any::<i128>(), any::<i128>()), |(left, right)| {
+            prop_assert_eq!(
+                Integer::from(left) & Integer::from(right),
+                Integer::Large(Large::from(left) & Large::from(right))
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_bitwise_or() {
+        check(&(any::<i128>(), any::<i128>()), |(left, right)| {
+            prop_assert_eq!(
+                Integer::from(left) | Integer::from(right),
+                Integer::Large(Large::from(left) | Large::from(right))
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_bitwise_xor() {
+        check(&(any::<i128>(), any::<i128>()), |(left, right)| {
+            prop_assert_eq!(
+                Integer::from(left) ^ Integer::from(right),
+                Integer::Large(Large::from(left) ^ Large::from(right))
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_shift_left() {
+        check(&(any::<i128>(), 0u32..200), |(value, amount)| {
+            prop_assert_eq!(
+                Integer::from(value).checked_shl(&Integer::from(amount)),
+                Some(Integer::Large(Large::from(value) << amount))
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_shift_right() {
+        check(&(any::<i128>(), 0u32..200), |(value, amount)| {
+            prop_assert_eq!(
+                Integer::from(value).checked_shr(&Integer::from(amount)),
+                Some(Integer::Large(Large::from(value) >> amount))
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_shift_by_a_negative_amount() {
+        check(&(any::<i128>(), i128::MIN..0), |(value, amount)| {
+            prop_assert_eq!(
+                Integer::from(value).checked_shl(&Integer::from(amount)),
+                None
+            );
+            prop_assert_eq!(
+                Integer::from(value).checked_shr(&Integer::from(amount)),
+                None
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_checked_arithmetic_on_small_integers() {
+        check(&(any::<i32>(), any::<i32>()), |(left, right)| {
+            let (l, r) = (Integer::from(left), Integer::from(right));
+            prop_assert_eq!(
+                l.checked_add(&r),
+                left.checked_add(right).map(Integer::from)
+            );
+            prop_assert_eq!(
+                l.checked_sub(&r),
+                left.checked_sub(right).map(Integer::from)
+            );
+            prop_assert_eq!(
+                l.checked_mul(&r),
+                left.checked_mul(right).map(Integer::from)
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_checked_arithmetic_on_large_integers_never_fails() {
+        let large = Integer::from(i64::MAX);
+        let small = Integer::from(2);
+        assert_eq!(large.checked_add(&small), Some(&large + &small));
+        assert_eq!(small.checked_sub(&large), Some(&small - &large));
+        assert_eq!(large.checked_mul(&large), Some(&large * &large));
+    }
+
+    #[test]
+    fn test_absolute_value() {
+        check(&any::<i128>(), |value| {
+            prop_assert_eq!(
+                Integer::from(value).abs(),
+                Integer::Large(Large::from(value).magnitude().clone().into())
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_sign() {
+        check(&any::<i128>(), |value| {
+            prop_assert_eq!(Integer::from(value).signum(), Integer::from(value.signum()));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_power() {
+        check(&(any::<i128>(), 0u32..10), |(value, exponent)| {
+            prop_assert_eq!(
+                Integer::from(value).pow(exponent),
+                Integer::Large(Large::from(value).pow(exponent))
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_checked_power_on_small_integers() {
+        check(&(any::<i32>(), 0u32..40), |(value, exponent)| {
+            prop_assert_eq!(
+                Integer::from(value).checked_pow(exponent),
+                value.checked_pow(exponent).map(Integer::from)
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_greatest_common_divisor() {
+        check(&(any::<i128>(), any::<i128>()), |(left, right)| {
+            let (l, r) = (Integer::from(left), Integer::from(right));
+            let gcd = l.gcd(&r);
+            prop_assert!(gcd >= Integer::from(0), "negative: {}", gcd);
+            if gcd != Integer::from(0) {
+                prop_assert_eq!(l.checked_rem(&gcd), Some(Integer::from(0)));
+                prop_assert_eq!(r.checked_rem(&gcd), Some(Integer::from(0)));
+                let l_reduced = l.checked_div(&gcd).unwrap();
+                let r_reduced = r.checked_div(&gcd).unwrap();
+                prop_assert_eq!(l_reduced.gcd(&r_reduced), Integer::from(1));
+            } else {
+                prop_assert_eq!((left, right), (0, 0));
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_greatest_common_divisor_of_small_integers() {
+        assert_eq!(Integer::from(12).gcd(&Integer::from(-18)), Integer::from(6));
+        assert_eq!(Integer::from(0).gcd(&Integer::from(-5)), Integer::from(5));
+        assert_eq!(
+            Integer::from(i32::MIN).gcd(&Integer::from(0)),
+            Integer::from(1i64 << 31)
+        );
+    }
 }
@@ -68,3 +68,121 @@ impl<Value> Spanned<Value> {
         }
     }
 }
+
+/// A position in the source, as a line and column, both starting at 1.
+///
+/// The column counts characters, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Converts between byte offsets, as used by [`Span`], and [`Location`]s.
+///
+/// This records where each line starts, so that it only needs to scan the
+/// source once.
+#[derive(Debug, Clone)]
+pub struct SourceIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The location of the given offset. Offsets past the end of the source
+    /// are treated as the end.
+    pub fn location(&self, offset: usize) -> Location {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self.source[line_start..]
+            .char_indices()
+            .take_while(|(index, _)| line_start + index < offset)
+            .count()
+            + 1;
+        Location { line, column }
+    }
+
+    /// The offset of the given location, or `None` if it is not in the source.
+    ///
+    /// The column may be just past the last character of the line.
+    pub fn offset(&self, location: Location) -> Option<usize> {
+        let line_start = *self.line_starts.get(location.line.checked_sub(1)?)?;
+        let line_end = self
+            .line_starts
+            .get(location.line)
+            .map_or(self.source.len(), |next_line_start| next_line_start - 1);
+        self.source[line_start..line_end]
+            .char_indices()
+            .map(|(index, _)| line_start + index)
+            .chain(std::iter::once(line_end))
+            .nth(location.column.checked_sub(1)?)
+    }
+
+    /// The locations of the start and end of the span.
+    pub fn span_locations(&self, span: Span) -> (Location, Location) {
+        (self.location(span.start), self.location(span.end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locations_of_offsets() {
+        let index = SourceIndex::new("let x = 1 in\nx + é\n\nx");
+
+        assert_eq!(index.location(0), Location { line: 1, column: 1 });
+        assert_eq!(index.location(4), Location { line: 1, column: 5 });
+        assert_eq!(
+            index.location(12),
+            Location {
+                line: 1,
+                column: 13
+            }
+        );
+        assert_eq!(index.location(13), Location { line: 2, column: 1 });
+        assert_eq!(index.location(17), Location { line: 2, column: 5 });
+        assert_eq!(index.location(19), Location { line: 2, column: 6 });
+        assert_eq!(index.location(20), Location { line: 3, column: 1 });
+        assert_eq!(index.location(21), Location { line: 4, column: 1 });
+        assert_eq!(index.location(100), Location { line: 4, column: 2 });
+    }
+
+    #[test]
+    fn test_offsets_of_locations() {
+        let source = "let x = 1 in\nx + é\n\nx";
+        let index = SourceIndex::new(source);
+
+        for offset in (0..=source.len()).filter(|offset| source.is_char_boundary(*offset)) {
+            assert_eq!(index.offset(index.location(offset)), Some(offset));
+        }
+        assert_eq!(index.offset(Location { line: 0, column: 1 }), None);
+        assert_eq!(index.offset(Location { line: 1, column: 0 }), None);
+        assert_eq!(
+            index.offset(Location {
+                line: 1,
+                column: 14
+            }),
+            None
+        );
+        assert_eq!(index.offset(Location { line: 5, column: 1 }), None);
+    }
+}
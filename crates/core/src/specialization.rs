@@ -0,0 +1,597 @@
+//! Partial evaluation, which specializes an expression with respect to the
+//! values that are already known, leaving behind a residual expression.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::expr::*;
+use crate::identifier::Identifier;
+use crate::native::NativeContext;
+use crate::optimization::{eliminate, eliminate_dead_code};
+use crate::primitive::Primitive;
+use crate::span::Span;
+
+/// Functions are unfolded at most this many times in total, so that
+/// specialization finishes even when evaluation would not.
+const UNFOLDING_LIMIT: usize = 256;
+
+/// Specializes an expression with respect to the given bindings, producing a
+/// residual expression which has the same result.
+///
+/// Anything that can be computed from the bindings and the literals in the
+/// expression is computed ahead of time: functions are called, natives are run
+/// on known primitives, and `match` expressions on known primitives choose
+/// their branch. Everything else is left for evaluation. To specialize a
+/// function with respect to some of its arguments, apply it to them; for
+/// example, specializing `power 2` produces a function which squares its
+/// argument.
+///
+/// Each binding must not refer to anything else. The residual expression may
+/// still refer to the bindings by name, and so must be evaluated in a context
+/// that includes them. As natives are run during specialization, the bindings
+/// should not include any with side effects, such as `trace`.
+///
+/// Recursive functions are only called with known primitive arguments, and
+/// calls stop being unfolded after a limit, so that specialization finishes
+/// even if evaluation would not.
+pub fn specialize(expr: Expr, bindings: impl IntoIterator<Item = (Identifier, Expr)>) -> Expr {
+    let (expr, references) = eliminate(expr);
+    let mut specializer = Specializer {
+        unfoldings: UNFOLDING_LIMIT,
+    };
+    let bindings = bindings.into_iter().collect::<Vec<_>>();
+    let mut scope = references.names;
+    scope.extend(bindings.iter().map(|(name, _)| name.clone()));
+
+    let mut values = Values::new();
+    for (name, value) in bindings {
+        let value = match specializer.value(value, &Values::new(), &scope) {
+            Some(value @ Value::Primitive(..)) => value,
+            Some(Value::Closure(closure)) => Value::Closure(Arc::new(Closure {
+                reference: Some(name.clone()),
+                ..(*closure).clone()
+            })),
+            Some(Value::Dynamic(_)) | None => Value::Dynamic(identifier(None, &name)),
+        };
+        values.insert(name, Binding::Value(value));
+    }
+
+    let residual = specializer
+        .value(expr.clone(), &values, &scope)
+        .and_then(|value| specializer.residualize(value, &scope));
+    match residual {
+        Some(residual) => eliminate_dead_code(residual),
+        // the expression contains a native which cannot be run yet
+        None => expr,
+    }
+}
+
+/// The names in scope in the residual expression.
+type Scope = HashSet<Identifier>;
+
+/// The values bound to each name in the original expression.
+type Values = HashMap<Identifier, Binding>;
+
+#[derive(Clone)]
+enum Binding {
+    Value(Value),
+    /// One of a group of recursive bindings. This is looked up lazily, as the
+    /// values of the group must refer to the group itself.
+    Recursive(Arc<Group>),
+}
+
+struct Group {
+    bindings: Vec<(Identifier, Expr)>,
+    values: Values,
+    /// The name of each binding in the residual expression.
+    names: HashMap<Identifier, Identifier>,
+}
+
+/// The result of specializing an expression.
+///
+/// Only primitives are passed out of the scope of a residual binding; anything
+/// else is made dynamic, so that it cannot be used where that binding is out
+/// of scope.
+#[derive(Clone)]
+enum Value {
+    /// A known primitive.
+    Primitive(Primitive, Option<Span>),
+    /// A known function, which can be called during specialization.
+    Closure(Arc<Closure>),
+    /// A residual expression, which will only be known during evaluation.
+    Dynamic(Expr),
+}
+
+#[derive(Clone)]
+struct Closure {
+    function: Function<Expr>,
+    span: Option<Span>,
+    values: Values,
+    /// Set if the function is bound recursively, in which case it is only
+    /// called with known primitive arguments.
+    recursive: bool,
+    /// The name of the function in the residual expression, if it has one.
+    reference: Option<Identifier>,
+    /// The call that produced the function, which is used in the residual
+    /// expression if the function cannot be specialized on its own.
+    fallback: Option<(Value, Value)>,
+}
+
+struct Specializer {
+    unfoldings: usize,
+}
+
+impl Specializer {
+    /// Specializes an expression, returning `None` if it runs a native which
+    /// cannot be run yet.
+    fn value(&mut self, expr: Expr, values: &Values, scope: &Scope) -> Option<Value> {
+        let span = expr.span();
+        match expr.take() {
+            Expression::Primitive(primitive) => Some(Value::Primitive(primitive, span)),
+            Expression::Native(native) => (native.implementation)(&Known(values))
+                .ok()
+                .map(|primitive| Value::Primitive(primitive, span)),
+            Expression::Identifier(name) => Some(match values.get(&name) {
+                Some(Binding::Value(value)) => value.clone(),
+                Some(Binding::Recursive(group)) => lookup_recursive(group, &name),
+                None => Value::Dynamic(identifier(span, &name)),
+            }),
+            Expression::Function(function) => Some(Value::Closure(Arc::new(Closure {
+                function,
+                span,
+                values: values.clone(),
+                recursive: false,
+                reference: None,
+                fallback: None,
+            }))),
+            Expression::Apply(Apply { function, argument }) => {
+                let function = self.value(function, values, scope)?;
+                let argument = self.value(argument, values, scope)?;
+                self.apply(function, argument, span, scope)
+            }
+            Expression::Assign(Assign { name, value, inner }) => {
+                let value = self.value(value, values, scope)?;
+                let mut inner_values = values.clone();
+                if is_trivial(&value) {
+                    inner_values.insert(name, Binding::Value(value));
+                    return self.value(inner, &inner_values, scope);
+                }
+                let (residual_name, inner_scope) = fresh(&name, scope);
+                let binding = match &value {
+                    Value::Closure(closure) => Value::Closure(Arc::new(Closure {
+                        reference: Some(residual_name.clone()),
+                        ..(**closure).clone()
+                    })),
+                    _ => Value::Dynamic(identifier(None, &residual_name)),
+                };
+                inner_values.insert(name, Binding::Value(binding));
+                let inner = self.value(inner, &inner_values, &inner_scope)?;
+                self.close(inner, &inner_scope, |specializer, inner| {
+                    let value = specializer.residualize(value, scope)?;
+                    Some(Expr::new(
+                        span,
+                        Expression::Assign(Assign {
+                            name: residual_name,
+                            value,
+                            inner,
+                        }),
+                    ))
+                })
+            }
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                let mut inner_scope = scope.clone();
+                let mut names = HashMap::new();
+                for (name, _) in bindings.iter() {
+                    let (residual_name, next_scope) = fresh(name, &inner_scope);
+                    inner_scope = next_scope;
+                    names.insert(name.clone(), residual_name);
+                }
+                let group = Arc::new(Group {
+                    bindings,
+                    values: values.clone(),
+                    names,
+                });
+                let inner = self.value(inner, &recursive_values(&group), &inner_scope)?;
+                self.close(inner, &inner_scope, |specializer, inner| {
+                    // the functions are not unfolded within their own
+                    // definitions, as that may never finish
+                    let mut definition_values = values.clone();
+                    for (name, residual_name) in group.names.iter() {
+                        let value = Value::Dynamic(identifier(None, residual_name));
+                        definition_values.insert(name.clone(), Binding::Value(value));
+                    }
+                    let bindings = group
+                        .bindings
+                        .iter()
+                        .map(|(name, value)| {
+                            let value = specializer.value(
+                                value.clone(),
+                                &definition_values,
+                                &inner_scope,
+                            )?;
+                            let value = specializer.residualize(value, &inner_scope)?;
+                            Some((group.names[name].clone(), value))
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(Expr::new(
+                        span,
+                        Expression::AssignRecursive(AssignRecursive { bindings, inner }),
+                    ))
+                })
+            }
+            Expression::Match(Match { value, patterns }) => {
+                let value = self.value(value, values, scope)?;
+                if let Value::Primitive(primitive, _) = &value {
+                    if let Some(index) = select(primitive, &patterns) {
+                        let PatternMatch {
+                            pattern, result, ..
+                        } = patterns.into_iter().nth(index).unwrap();
+                        let mut result_values = values.clone();
+                        if let Pattern::Variable(name) = pattern {
+                            result_values.insert(name, Binding::Value(value));
+                        }
+                        return self.value(result, &result_values, scope);
+                    }
+                }
+                let value = self.residualize(value, scope)?;
+                let patterns = patterns
+                    .into_iter()
+                    .map(
+                        |PatternMatch {
+                             pattern,
+                             pattern_span,
+                             result,
+                         }| {
+                            let mut result_scope = scope.clone();
+                            let mut result_values = values.clone();
+                            let pattern = rename(pattern, &mut result_scope, &mut result_values);
+                            let result = self.value(result, &result_values, &result_scope)?;
+                            let result = self.residualize(result, &result_scope)?;
+                            Some(PatternMatch {
+                                pattern,
+                                pattern_span,
+                                result,
+                            })
+                        },
+                    )
+                    .collect::<Option<VecDeque<_>>>()?;
+                Some(Value::Dynamic(Expr::new(
+                    span,
+                    Expression::Match(Match { value, patterns }),
+                )))
+            }
+            Expression::Typed(Typed {
+                expression,
+                typ,
+                typ_span,
+            }) => match self.value(expression, values, scope)? {
+                Value::Dynamic(expression) => Some(Value::Dynamic(Expr::new(
+                    span,
+                    Expression::Typed(Typed {
+                        expression,
+                        typ,
+                        typ_span,
+                    }),
+                ))),
+                value => Some(value),
+            },
+            Expression::Sequence(Sequence { first, second }) => {
+                let first = self.value(first, values, scope)?;
+                let second = self.value(second, values, scope)?;
+                if let Value::Primitive(..) = first {
+                    return Some(second);
+                }
+                let first = self.residualize(first, scope)?;
+                let second = self.residualize(second, scope)?;
+                Some(Value::Dynamic(Expr::new(
+                    span,
+                    Expression::Sequence(Sequence { first, second }),
+                )))
+            }
+            Expression::Tuple(Tuple { elements }) => {
+                let elements = elements
+                    .into_iter()
+                    .map(|element| {
+                        let element = self.value(element, values, scope)?;
+                        self.residualize(element, scope)
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Value::Dynamic(Expr::new(
+                    span,
+                    Expression::Tuple(Tuple { elements }),
+                )))
+            }
+            expression @ Expression::List(List::Empty) => {
+                Some(Value::Dynamic(Expr::new(span, expression)))
+            }
+            Expression::List(List::Cons { head, tail }) => {
+                let head = self.value(head, values, scope)?;
+                let head = self.residualize(head, scope)?;
+                let tail = self.value(tail, values, scope)?;
+                let tail = self.residualize(tail, scope)?;
+                Some(Value::Dynamic(Expr::new(
+                    span,
+                    Expression::List(List::Cons { head, tail }),
+                )))
+            }
+        }
+    }
+
+    /// Calls the function if it is known, and otherwise leaves the call in the
+    /// residual expression.
+    fn apply(
+        &mut self,
+        function: Value,
+        argument: Value,
+        span: Option<Span>,
+        scope: &Scope,
+    ) -> Option<Value> {
+        if let Value::Closure(closure) = &function {
+            let unfold = self.unfoldings > 0
+                && (!closure.recursive || matches!(argument, Value::Primitive(..)));
+            if unfold {
+                self.unfoldings -= 1;
+                if let Some(result) = self.unfold(closure, argument.clone(), scope) {
+                    return Some(match result {
+                        Value::Closure(closure)
+                            if closure.reference.is_none() && closure.fallback.is_none() =>
+                        {
+                            Value::Closure(Arc::new(Closure {
+                                fallback: Some((function, argument)),
+                                ..(*closure).clone()
+                            }))
+                        }
+                        result => result,
+                    });
+                }
+            }
+        }
+        let function = self.residualize(function, scope)?;
+        let argument = self.residualize(argument, scope)?;
+        Some(Value::Dynamic(Expr::new(
+            span,
+            Expression::Apply(Apply { function, argument }),
+        )))
+    }
+
+    /// Specializes the body of a function with its parameter bound to the
+    /// argument.
+    ///
+    /// A dynamic argument is bound with `let`, so that it is still only
+    /// evaluated once.
+    fn unfold(&mut self, closure: &Closure, argument: Value, scope: &Scope) -> Option<Value> {
+        let Function {
+            parameter, body, ..
+        } = &closure.function;
+        let mut values = closure.values.clone();
+        if is_trivial(&argument) {
+            values.insert(parameter.clone(), Binding::Value(argument));
+            return self.value(body.clone(), &values, scope);
+        }
+        let argument = self.residualize(argument, scope)?;
+        let (residual_name, body_scope) = fresh(parameter, scope);
+        let binding = Value::Dynamic(identifier(None, &residual_name));
+        values.insert(parameter.clone(), Binding::Value(binding));
+        let body = self.value(body.clone(), &values, &body_scope)?;
+        self.close(body, &body_scope, |_, body| {
+            Some(Expr::new(
+                argument.span(),
+                Expression::Assign(Assign {
+                    name: residual_name,
+                    value: argument,
+                    inner: body,
+                }),
+            ))
+        })
+    }
+
+    /// Leaves the scope of a residual binding, wrapping the residual
+    /// expression in that binding unless the value is a known primitive.
+    fn close(
+        &mut self,
+        value: Value,
+        scope: &Scope,
+        wrap: impl FnOnce(&mut Self, Expr) -> Option<Expr>,
+    ) -> Option<Value> {
+        if let Value::Primitive(..) = value {
+            return Some(value);
+        }
+        let residual = self.residualize(value, scope)?;
+        wrap(self, residual).map(Value::Dynamic)
+    }
+
+    /// Converts a value to a residual expression.
+    fn residualize(&mut self, value: Value, scope: &Scope) -> Option<Expr> {
+        match value {
+            Value::Primitive(primitive, span) => {
+                Some(Expr::new(span, Expression::Primitive(primitive)))
+            }
+            Value::Dynamic(expr) => Some(expr),
+            Value::Closure(closure) => {
+                if let Some(reference) = &closure.reference {
+                    return Some(identifier(closure.span, reference));
+                }
+                if let Some(function) = self.residualize_function(&closure, scope) {
+                    return Some(function);
+                }
+                let (function, argument) = closure.fallback.clone()?;
+                let function = self.residualize(function, scope)?;
+                let argument = self.residualize(argument, scope)?;
+                Some(Expr::new(
+                    closure.span,
+                    Expression::Apply(Apply { function, argument }),
+                ))
+            }
+        }
+    }
+
+    /// Specializes the body of a function with an unknown argument.
+    fn residualize_function(&mut self, closure: &Closure, scope: &Scope) -> Option<Expr> {
+        let Function {
+            parameter,
+            parameter_type,
+            parameter_type_span,
+            body,
+        } = &closure.function;
+        let (residual_parameter, body_scope) = fresh(parameter, scope);
+        let mut values = closure.values.clone();
+        let binding = Value::Dynamic(identifier(None, &residual_parameter));
+        values.insert(parameter.clone(), Binding::Value(binding));
+        let body = self.value(body.clone(), &values, &body_scope)?;
+        let body = self.residualize(body, &body_scope)?;
+        Some(Expr::new(
+            closure.span,
+            Expression::Function(Function {
+                parameter: residual_parameter,
+                parameter_type: parameter_type.clone(),
+                parameter_type_span: *parameter_type_span,
+                body,
+            }),
+        ))
+    }
+}
+
+/// Provides the known primitives to natives.
+struct Known<'a>(&'a Values);
+
+impl NativeContext for Known<'_> {
+    fn lookup_value(&self, identifier: &Identifier) -> Result<Primitive> {
+        match self.0.get(identifier) {
+            Some(Binding::Value(Value::Primitive(primitive, _))) => Ok(primitive.clone()),
+            _ => Err(Error::UnknownVariable {
+                span: None,
+                name: identifier.name(),
+                suggestions: None,
+            }),
+        }
+    }
+
+    /// Functions are not applied during specialization, so a native which
+    /// applies one is left to be run during evaluation.
+    fn apply(&self, function: &Identifier, _argument: Primitive) -> Result<Primitive> {
+        Err(Error::UnknownVariable {
+            span: None,
+            name: function.name(),
+            suggestions: None,
+        })
+    }
+}
+
+/// Values that can be substituted wherever they are referred to, without
+/// duplicating any work.
+fn is_trivial(value: &Value) -> bool {
+    match value {
+        Value::Primitive(..) => true,
+        Value::Closure(_) => false,
+        Value::Dynamic(expr) => matches!(expr.expression(), Expression::Identifier(_)),
+    }
+}
+
+fn recursive_values(group: &Arc<Group>) -> Values {
+    let mut values = group.values.clone();
+    for name in group.names.keys() {
+        values.insert(name.clone(), Binding::Recursive(group.clone()));
+    }
+    values
+}
+
+fn lookup_recursive(group: &Arc<Group>, name: &Identifier) -> Value {
+    let residual_name = &group.names[name];
+    let (_, value) = group
+        .bindings
+        .iter()
+        .find(|(binding_name, _)| binding_name == name)
+        .unwrap();
+    match value.expression() {
+        Expression::Function(function) => Value::Closure(Arc::new(Closure {
+            function: function.clone(),
+            span: value.span(),
+            values: recursive_values(group),
+            recursive: true,
+            reference: Some(residual_name.clone()),
+            fallback: None,
+        })),
+        _ => Value::Dynamic(identifier(None, residual_name)),
+    }
+}
+
+/// Finds the pattern that matches a known primitive, if it can be decided.
+fn select<'a>(
+    primitive: &Primitive,
+    patterns: impl IntoIterator<Item = &'a PatternMatch<Expr>>,
+) -> Option<usize> {
+    for (index, PatternMatch { pattern, .. }) in patterns.into_iter().enumerate() {
+        match pattern {
+            Pattern::Anything | Pattern::Variable(_) => return Some(index),
+            Pattern::Primitive(expected) if expected == primitive => return Some(index),
+            Pattern::Primitive(_) => (),
+            // a type error, which is left for evaluation to report
+            Pattern::Tuple(_)
+            | Pattern::EmptyList
+            | Pattern::Cons { .. }
+            | Pattern::Some(_)
+            | Pattern::None
+            | Pattern::Ok(_)
+            | Pattern::Err(_) => return None,
+        }
+    }
+    None
+}
+
+/// Renames the variables in a pattern so that they do not shadow anything in
+/// the residual expression.
+fn rename(pattern: Pattern, scope: &mut Scope, values: &mut Values) -> Pattern {
+    match pattern {
+        Pattern::Variable(name) => {
+            let (residual_name, next_scope) = fresh(&name, scope);
+            *scope = next_scope;
+            let binding = Value::Dynamic(identifier(None, &residual_name));
+            values.insert(name, Binding::Value(binding));
+            Pattern::Variable(residual_name)
+        }
+        Pattern::Tuple(elements) => Pattern::Tuple(
+            elements
+                .into_iter()
+                .map(|element| rename(element, scope, values))
+                .collect(),
+        ),
+        Pattern::Cons { head, tail } => Pattern::Cons {
+            head: Box::new(rename(*head, scope, values)),
+            tail: Box::new(rename(*tail, scope, values)),
+        },
+        Pattern::Some(inner) => Pattern::Some(Box::new(rename(*inner, scope, values))),
+        Pattern::Ok(inner) => Pattern::Ok(Box::new(rename(*inner, scope, values))),
+        Pattern::Err(inner) => Pattern::Err(Box::new(rename(*inner, scope, values))),
+        pattern @ (Pattern::Anything
+        | Pattern::Primitive(_)
+        | Pattern::EmptyList
+        | Pattern::None) => pattern,
+    }
+}
+
+/// Chooses a name for a residual binding which is not already in scope, and
+/// returns it along with the new scope.
+fn fresh(name: &Identifier, scope: &Scope) -> (Identifier, Scope) {
+    let residual_name = if !scope.contains(name) {
+        name.clone()
+    } else {
+        match name {
+            Identifier::Name(base) => (1..)
+                .map(|suffix| Identifier::name_from_string(format!("{base}_{suffix}")).unwrap())
+                .find(|candidate| !scope.contains(candidate))
+                .unwrap(),
+            Identifier::Operator(_) | Identifier::Label(_) => (0..)
+                .map(Identifier::Label)
+                .find(|candidate| !scope.contains(candidate))
+                .unwrap(),
+        }
+    };
+    let mut scope = scope.clone();
+    scope.insert(residual_name.clone());
+    (residual_name, scope)
+}
+
+fn identifier(span: Option<Span>, name: &Identifier) -> Expr {
+    Expr::new(span, Expression::Identifier(name.clone()))
+}
@@ -68,19 +68,77 @@ impl Display for Polytype {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Type<Outer: TypeRef> {
     Integer,
-    Function { parameter: Outer, body: Outer },
+    Float,
+    Boolean,
+    Char,
+    String,
+    Function {
+        parameter: Outer,
+        body: Outer,
+    },
+    Tuple(Vec<Outer>),
+    List(Outer),
+    Option(Outer),
+    Result {
+        ok: Outer,
+        error: Outer,
+    },
+    /// A named type constructor applied to zero or more arguments, such as
+    /// `Maybe a`.
+    Constructor {
+        name: TypeName,
+        arguments: Vec<Outer>,
+    },
     Variable(TypeVariable),
+    /// A polymorphic type nested inside another type, such as the parameter
+    /// of `(forall a. a -> a) -> Integer`.
+    Forall {
+        quantifiers: Vec<TypeVariable>,
+        body: Outer,
+    },
+    /// A recursive type, such as `mu t. (Integer, Option t)`, in which the
+    /// variable stands for the whole type.
+    ///
+    /// Recursive types are equal to their unfolding, so the above is the same
+    /// type as `(Integer, Option (mu t. (Integer, Option t)))`.
+    Recursive {
+        variable: TypeVariable,
+        body: Outer,
+    },
 }
 
 impl<Outer: TypeRef> Type<Outer> {
     pub fn transform<NewOuter: TypeRef>(self, f: impl Fn(Outer) -> NewOuter) -> Type<NewOuter> {
         match self {
             Type::Integer => Type::Integer,
+            Type::Float => Type::Float,
+            Type::Boolean => Type::Boolean,
+            Type::Char => Type::Char,
+            Type::String => Type::String,
             Type::Function { parameter, body } => Type::Function {
                 parameter: f(parameter),
                 body: f(body),
             },
+            Type::Tuple(elements) => Type::Tuple(elements.into_iter().map(f).collect()),
+            Type::List(element) => Type::List(f(element)),
+            Type::Option(element) => Type::Option(f(element)),
+            Type::Result { ok, error } => Type::Result {
+                ok: f(ok),
+                error: f(error),
+            },
+            Type::Constructor { name, arguments } => Type::Constructor {
+                name,
+                arguments: arguments.into_iter().map(f).collect(),
+            },
             Type::Variable(variable) => Type::Variable(variable),
+            Type::Forall { quantifiers, body } => Type::Forall {
+                quantifiers,
+                body: f(body),
+            },
+            Type::Recursive { variable, body } => Type::Recursive {
+                variable,
+                body: f(body),
+            },
         }
     }
 }
@@ -89,12 +147,70 @@ impl<Outer: TypeRef> Display for Type<Outer> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Type::Integer => write!(f, "Integer"),
+            Type::Float => write!(f, "Float"),
+            Type::Boolean => write!(f, "Boolean"),
+            Type::Char => write!(f, "Char"),
+            Type::String => write!(f, "String"),
             Type::Function { parameter, body } => write!(f, "({parameter} -> {body})"),
+            Type::Tuple(elements) => {
+                write!(f, "(")?;
+                let mut element_iter = elements.iter();
+                if let Some(first) = element_iter.next() {
+                    write!(f, "{first}")?;
+                    for element in element_iter {
+                        write!(f, ", {element}")?;
+                    }
+                }
+                write!(f, ")")
+            }
+            Type::List(element) => write!(f, "[{element}]"),
+            Type::Option(element) => write!(f, "Option {element}"),
+            Type::Result { ok, error } => write!(f, "Result {ok} {error}"),
+            Type::Constructor { name, arguments } => {
+                write!(f, "{name}")?;
+                for argument in arguments {
+                    let rendered = argument.to_string();
+                    // arguments which are themselves applied need parentheses
+                    if rendered.contains(' ') && !rendered.starts_with(['(', '[']) {
+                        write!(f, " ({rendered})")?;
+                    } else {
+                        write!(f, " {rendered}")?;
+                    }
+                }
+                Ok(())
+            }
             Type::Variable(variable) => write!(f, "{variable}"),
+            Type::Forall { quantifiers, body } => {
+                write!(f, "(forall")?;
+                for quantifier in quantifiers {
+                    write!(f, " {quantifier}")?;
+                }
+                write!(f, ". {body})")
+            }
+            Type::Recursive { variable, body } => write!(f, "(mu {variable}. {body})"),
         }
     }
 }
 
+/// The name of a type constructor.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypeName(pub Arc<String>);
+
+impl TypeName {
+    pub fn new(value: String) -> Self {
+        Self(Arc::new(value))
+    }
+    pub fn new_from_str(value: &str) -> Self {
+        Self::new(value.to_owned())
+    }
+}
+
+impl Display for TypeName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TypeVariable(pub Arc<String>);
 
@@ -105,6 +221,21 @@ impl TypeVariable {
     pub fn new_from_str(value: &str) -> Self {
         Self::new(value.to_owned())
     }
+
+    /// A type variable which can only stand for a type whose values can be
+    /// compared, i.e. a primitive type.
+    ///
+    /// As with Standard ML's equality type variables, these are written with a
+    /// leading `''`.
+    pub fn new_comparable(value: &str) -> Self {
+        Self::new(format!("''{value}"))
+    }
+
+    /// Whether the variable can only stand for a type whose values can be
+    /// compared.
+    pub fn is_comparable(&self) -> bool {
+        self.0.starts_with("''")
+    }
 }
 
 impl Borrow<str> for TypeVariable {
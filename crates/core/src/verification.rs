@@ -1,5 +1,8 @@
+use crate::diagnostics::Diagnostics;
 use crate::error::{Error, Result};
 use crate::expr;
+use crate::kinds;
+use crate::warning::Warning;
 
 pub fn verify(expr: &expr::Expr) -> Result<()> {
     match expr.expression() {
@@ -8,8 +11,13 @@ pub fn verify(expr: &expr::Expr) -> Result<()> {
         | expr::Expression::Identifier(_) => (),
         expr::Expression::Function(expr::Function {
             parameter: _,
+            ref parameter_type,
+            parameter_type_span,
             ref body,
         }) => {
+            if let Some(parameter_type) = parameter_type {
+                kinds::check(parameter_type, *parameter_type_span)?;
+            }
             verify(body)?;
         }
         expr::Expression::Apply(expr::Apply {
@@ -27,32 +35,138 @@ pub fn verify(expr: &expr::Expr) -> Result<()> {
             verify(value)?;
             verify(inner)?;
         }
+        expr::Expression::AssignRecursive(expr::AssignRecursive {
+            ref bindings,
+            ref inner,
+        }) => {
+            for (_, value) in bindings {
+                verify(value)?;
+            }
+            verify(inner)?;
+        }
         expr::Expression::Match(expr::Match {
             ref value,
             ref patterns,
         }) => {
             match patterns.back().map(|p| &p.pattern) {
-                Some(expr::Pattern::Anything) => Ok(()),
+                Some(pattern) if pattern.is_irrefutable() => Ok(()),
                 _ => Err(Error::MatchWithoutBaseCase { span: expr.span() }),
             }?;
             verify(value)?;
-            for expr::PatternMatch { pattern: _, result } in patterns {
+            for expr::PatternMatch { result, .. } in patterns {
                 verify(result)?;
             }
         }
         expr::Expression::Typed(expr::Typed {
             ref expression,
-            typ: _,
+            ref typ,
+            typ_span,
         }) => {
+            kinds::check(typ, *typ_span)?;
             verify(expression)?;
         }
+        expr::Expression::Sequence(expr::Sequence {
+            ref first,
+            ref second,
+        }) => {
+            verify(first)?;
+            verify(second)?;
+        }
+        expr::Expression::Tuple(expr::Tuple { ref elements }) => {
+            for element in elements {
+                verify(element)?;
+            }
+        }
+        expr::Expression::List(expr::List::Empty) => (),
+        expr::Expression::List(expr::List::Cons { ref head, ref tail }) => {
+            verify(head)?;
+            verify(tail)?;
+        }
     };
     Ok(())
 }
 
+/// Finds problems that do not prevent evaluation, such as match arms that can
+/// never be reached, and records them as warnings.
+pub fn warnings(expr: &expr::Expr, diagnostics: &mut Diagnostics) {
+    match expr.expression() {
+        expr::Expression::Primitive(_)
+        | expr::Expression::Native(_)
+        | expr::Expression::Identifier(_) => (),
+        expr::Expression::Function(expr::Function { ref body, .. }) => {
+            warnings(body, diagnostics);
+        }
+        expr::Expression::Apply(expr::Apply {
+            ref function,
+            ref argument,
+        }) => {
+            warnings(function, diagnostics);
+            warnings(argument, diagnostics);
+        }
+        expr::Expression::Assign(expr::Assign {
+            ref value,
+            ref inner,
+            ..
+        }) => {
+            warnings(value, diagnostics);
+            warnings(inner, diagnostics);
+        }
+        expr::Expression::AssignRecursive(expr::AssignRecursive {
+            ref bindings,
+            ref inner,
+        }) => {
+            for (_, value) in bindings {
+                warnings(value, diagnostics);
+            }
+            warnings(inner, diagnostics);
+        }
+        expr::Expression::Match(expr::Match {
+            ref value,
+            ref patterns,
+        }) => {
+            warnings(value, diagnostics);
+            for (index, pattern_match) in patterns.iter().enumerate() {
+                let earlier = patterns
+                    .iter()
+                    .take(index)
+                    .find(|earlier| earlier.pattern.subsumes(&pattern_match.pattern));
+                if let Some(earlier) = earlier {
+                    diagnostics.warn(Warning::UnreachablePattern {
+                        span: pattern_match.pattern_span,
+                        earlier_span: earlier.pattern_span,
+                    });
+                }
+                warnings(&pattern_match.result, diagnostics);
+            }
+        }
+        expr::Expression::Typed(expr::Typed { ref expression, .. }) => {
+            warnings(expression, diagnostics);
+        }
+        expr::Expression::Sequence(expr::Sequence {
+            ref first,
+            ref second,
+        }) => {
+            warnings(first, diagnostics);
+            warnings(second, diagnostics);
+        }
+        expr::Expression::Tuple(expr::Tuple { ref elements }) => {
+            for element in elements {
+                warnings(element, diagnostics);
+            }
+        }
+        expr::Expression::List(expr::List::Empty) => (),
+        expr::Expression::List(expr::List::Cons { ref head, ref tail }) => {
+            warnings(head, diagnostics);
+            warnings(tail, diagnostics);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::primitive::Primitive;
+    use crate::span::Span;
+    use crate::types::{Type, TypeName};
 
     use super::*;
 
@@ -67,6 +181,7 @@ mod tests {
                 ),
                 patterns: [expr::PatternMatch {
                     pattern: expr::Pattern::Primitive(Primitive::Integer(1.into())),
+                    pattern_span: Some((5..6).into()),
                     result: expr::Expr::new(
                         Some((7..8).into()),
                         expr::Expression::Primitive(Primitive::Integer(2.into())),
@@ -85,4 +200,128 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_rejects_annotations_with_the_wrong_kind() {
+        let expr = expr::Expr::new(
+            Some((0..18).into()),
+            expr::Expression::Typed(expr::Typed {
+                expression: expr::Expr::new(
+                    Some((0..1).into()),
+                    expr::Expression::Primitive(Primitive::Integer(1.into())),
+                ),
+                typ: Type::Constructor {
+                    name: TypeName::new_from_str("Integer"),
+                    arguments: vec![Type::Integer.into()],
+                }
+                .into(),
+                typ_span: Some((3..18).into()),
+            }),
+        );
+
+        let result = verify(&expr);
+
+        assert_eq!(
+            result,
+            Err(Error::KindMismatch {
+                span: Some((3..18).into()),
+                name: "Integer".to_string(),
+                kind: "*".to_string(),
+                expected: 0,
+                arguments: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_warns_about_a_pattern_after_a_base_case() {
+        let expr = match_on_integer(vec![
+            (expr::Pattern::Anything, (10..11).into()),
+            (
+                expr::Pattern::Primitive(Primitive::Integer(1.into())),
+                (18..19).into(),
+            ),
+        ]);
+
+        let mut diagnostics = Diagnostics::new();
+        warnings(&expr, &mut diagnostics);
+
+        assert_eq!(
+            diagnostics.warnings(),
+            [Warning::UnreachablePattern {
+                span: Some((18..19).into()),
+                earlier_span: Some((10..11).into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_warns_about_a_duplicate_pattern() {
+        let expr = match_on_integer(vec![
+            (
+                expr::Pattern::Primitive(Primitive::Integer(1.into())),
+                (10..11).into(),
+            ),
+            (
+                expr::Pattern::Primitive(Primitive::Integer(1.into())),
+                (18..19).into(),
+            ),
+            (expr::Pattern::Anything, (26..27).into()),
+        ]);
+
+        let mut diagnostics = Diagnostics::new();
+        warnings(&expr, &mut diagnostics);
+
+        assert_eq!(
+            diagnostics.warnings(),
+            [Warning::UnreachablePattern {
+                span: Some((18..19).into()),
+                earlier_span: Some((10..11).into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_does_not_warn_about_reachable_patterns() {
+        let expr = match_on_integer(vec![
+            (
+                expr::Pattern::Primitive(Primitive::Integer(1.into())),
+                (10..11).into(),
+            ),
+            (
+                expr::Pattern::Primitive(Primitive::Integer(2.into())),
+                (18..19).into(),
+            ),
+            (expr::Pattern::Anything, (26..27).into()),
+        ]);
+
+        let mut diagnostics = Diagnostics::new();
+        warnings(&expr, &mut diagnostics);
+
+        assert!(diagnostics.is_empty());
+    }
+
+    /// Constructs a match on an integer, where each pattern results in `0`.
+    fn match_on_integer(patterns: Vec<(expr::Pattern, Span)>) -> expr::Expr {
+        expr::Expr::new(
+            None,
+            expr::Expression::Match(expr::Match {
+                value: expr::Expr::new(
+                    Some((6..7).into()),
+                    expr::Expression::Primitive(Primitive::Integer(1.into())),
+                ),
+                patterns: patterns
+                    .into_iter()
+                    .map(|(pattern, span)| expr::PatternMatch {
+                        pattern,
+                        pattern_span: Some(span),
+                        result: expr::Expr::new(
+                            None,
+                            expr::Expression::Primitive(Primitive::Integer(0.into())),
+                        ),
+                    })
+                    .collect(),
+            }),
+        )
+    }
 }
@@ -0,0 +1,32 @@
+//! The set of possible warnings, which are reported without preventing
+//! evaluation.
+
+use crate::span::Span;
+
+/// The set of possible warnings.
+///
+/// Like [`Error`][crate::error::Error], this can be used with [`thiserror`]
+/// and [`miette`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error, miette::Diagnostic)]
+pub enum Warning {
+    #[error("Unreachable pattern")]
+    #[diagnostic(code(boo::verifier::unreachable_pattern), severity(Warning))]
+    UnreachablePattern {
+        #[label("this pattern will never match")]
+        span: Option<Span>,
+        #[label("because this pattern matches first")]
+        earlier_span: Option<Span>,
+    },
+
+    #[error("Unused binding: {name}")]
+    #[diagnostic(
+        code(boo::linter::unused_binding),
+        severity(Warning),
+        help("if this is intentional, start the name with an underscore")
+    )]
+    UnusedBinding {
+        #[label("`{name}` is never used")]
+        span: Option<Span>,
+        name: String,
+    },
+}
@@ -0,0 +1,86 @@
+use proptest::prelude::*;
+
+use boo_core::builtins;
+use boo_core::evaluation::*;
+use boo_core::expr::Expr;
+use boo_core::optimization::*;
+use boo_core::specialization::specialize;
+use boo_test_helpers::proptest::*;
+
+#[test]
+fn test_eliminating_dead_code_does_not_change_the_result() {
+    check_optimization_does_not_change_the_result(eliminate_dead_code);
+}
+
+#[test]
+fn test_inlining_does_not_change_the_result() {
+    check_optimization_does_not_change_the_result(inline);
+}
+
+#[test]
+fn test_optimizing_does_not_change_the_result() {
+    check_optimization_does_not_change_the_result(optimize);
+}
+
+#[test]
+fn test_applying_no_optimizations_leaves_the_expression_as_it_is() {
+    check(&boo_generator::arbitrary(), |expr| {
+        let core_expr = expr.to_core()?;
+        prop_assert_eq!(Optimizations::NONE.apply(core_expr.clone()), core_expr);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_specializing_does_not_change_the_result() {
+    check_optimization_does_not_change_the_result(|expr| {
+        let builtins = builtins::implementations(builtins::IoCapabilities::default())
+            .map(|(name, implementation)| (name.clone(), implementation));
+        specialize(expr, builtins)
+    });
+}
+
+fn check_optimization_does_not_change_the_result(optimization: impl Fn(Expr) -> Expr) {
+    let evaluator = {
+        let mut context = boo_evaluation_reduction::new();
+        builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+        context.evaluator()
+    };
+
+    check(&boo_generator::arbitrary(), |expr| {
+        let core_expr = expr.clone().to_core()?;
+        let optimized_expr = optimization(core_expr.clone());
+        let expected = evaluator.evaluate(core_expr);
+        let actual = evaluator.evaluate(optimized_expr.clone());
+
+        match (expected, actual) {
+            (Ok(expected), Ok(actual)) => prop_assert!(
+                same_result(&expected, &actual),
+                "results differ\n  left:      `{}`,\n  right:     `{}`\n  input:     {}\n  optimized: {}\n",
+                expected,
+                actual,
+                expr,
+                optimized_expr
+            ),
+            (expected, actual) => prop_assert_eq!(expected.map(|_| ()), actual.map(|_| ())),
+        }
+        Ok(())
+    })
+}
+
+/// Compares two results, considering any two functions to be the same, as they
+/// may have had dead code removed from their bodies.
+fn same_result(expected: &Evaluated, actual: &Evaluated) -> bool {
+    match (expected, actual) {
+        (Evaluated::Function(_), Evaluated::Function(_)) => true,
+        (Evaluated::Tuple(expected), Evaluated::Tuple(actual))
+        | (Evaluated::List(expected), Evaluated::List(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual)
+                    .all(|(expected, actual)| same_result(expected, actual))
+        }
+        (expected, actual) => expected == actual,
+    }
+}
@@ -0,0 +1,102 @@
+use boo_core::builtins;
+use boo_core::error::Result;
+use boo_core::identifier::Identifier;
+use boo_core::specialization::*;
+
+#[test]
+fn test_computes_known_values() -> Result<()> {
+    let residual = specialize_program("let x = 3 in x * (x + 1)", [])?;
+
+    assert_eq!(residual, "12");
+    Ok(())
+}
+
+#[test]
+fn test_specializes_a_function_with_respect_to_its_first_argument() -> Result<()> {
+    let residual = specialize_program("(fn x -> fn y -> x * y) 3", [])?;
+
+    assert_eq!(residual, "fn y -> (((*) (3)) (y))");
+    Ok(())
+}
+
+#[test]
+fn test_unfolds_recursive_functions_with_known_arguments() -> Result<()> {
+    let residual = specialize_program(
+        "let rec power = fn n -> fn x -> match n { 0 -> 1; _ -> x * power (n - 1) x } in power 2",
+        [],
+    )?;
+
+    assert_eq!(residual, "fn x -> (((*) (x)) (((*) (x)) (1)))");
+    Ok(())
+}
+
+#[test]
+fn test_keeps_recursive_functions_with_unknown_arguments() -> Result<()> {
+    let program = "let rec loop = fn n -> match n { 0 -> 0; _ -> loop (n - 1) } in fn m -> loop m";
+    let residual = specialize_program(program, [])?;
+
+    assert_eq!(
+        residual,
+        "let rec loop = (fn n -> (match n {0 -> (0); _ -> ((loop) (((-) (n)) (1)))})) in\n(fn m -> ((loop) (m)))"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_uses_the_given_bindings() -> Result<()> {
+    let residual = specialize_program("fn x -> x + n", [("n", "2")])?;
+
+    assert_eq!(residual, "fn x -> (((+) (x)) (2))");
+    Ok(())
+}
+
+#[test]
+fn test_binds_unknown_arguments_so_they_are_only_evaluated_once() -> Result<()> {
+    let residual = specialize_program("let f = fn x -> x + x in f (y * 2)", [])?;
+
+    assert_eq!(residual, "let x = (((*) (y)) (2)) in (((+) (x)) (x))");
+    Ok(())
+}
+
+#[test]
+fn test_avoids_capturing_names() -> Result<()> {
+    let residual = specialize_program("fn y -> let f = fn x -> y in fn y -> f 1", [])?;
+
+    assert_eq!(residual, "fn y -> (fn y_1 -> (y))");
+    Ok(())
+}
+
+#[test]
+fn test_leaves_errors_for_evaluation() -> Result<()> {
+    let residual = specialize_program("let x = 1 / 0 in 2", [])?;
+    assert_eq!(residual, "2");
+
+    let residual = specialize_program("1 / 0", [])?;
+    assert_eq!(residual, "((/) (1)) (0)");
+    Ok(())
+}
+
+#[test]
+fn test_finishes_even_when_evaluation_would_not() -> Result<()> {
+    let residual = specialize_program("let rec loop = fn n -> loop (n + 1) in loop 0", [])?;
+
+    assert!(residual.contains("loop"), "residual: {}", residual);
+    Ok(())
+}
+
+fn specialize_program<const N: usize>(
+    program: &str,
+    bindings: [(&str, &str); N],
+) -> Result<String> {
+    let expr = boo_parser::parse_expr(program)?.to_core()?;
+    let mut all_bindings = builtins::implementations(builtins::IoCapabilities::default())
+        .map(|(name, implementation)| (name.clone(), implementation))
+        .collect::<Vec<_>>();
+    for (name, value) in bindings {
+        all_bindings.push((
+            Identifier::name_from_str(name).unwrap(),
+            boo_parser::parse_expr(value)?.to_core()?,
+        ));
+    }
+    Ok(specialize(expr, all_bindings).to_string())
+}
@@ -1,5 +1,6 @@
-use boo::error::{Error, Result};
+use boo::error::{Error, Result, Suggestions};
 use boo::evaluation::{EvaluationContext, Evaluator};
+use boo::types::{Monotype, Type};
 use boo::*;
 
 #[test]
@@ -10,10 +11,45 @@ fn test_unknown_variable() -> Result<()> {
         Error::UnknownVariable {
             span: Some((6..9).into()),
             name: "xyz".to_string(),
+            suggestions: None,
         },
     )
 }
 
+#[test]
+fn test_unknown_variable_suggests_similar_names() -> Result<()> {
+    let program = "let cat = 1 in let bat = 2 in let hat = 3 in let mat = 4 in xat + cat";
+    let expected_error = Error::UnknownVariable {
+        span: Some((60..63).into()),
+        name: "xat".to_string(),
+        suggestions: Some(Suggestions(vec![
+            "bat".to_string(),
+            "cat".to_string(),
+            "hat".to_string(),
+        ])),
+    };
+    let ast = parse_expr(program)?.to_core()?;
+    let type_check_result =
+        boo_types_hindley_milner::type_of(&ast, builtins::IoCapabilities::default());
+    assert_eq!(type_check_result, Err(expected_error));
+
+    // the evaluator only keeps the bindings which are referenced
+    let program = "let value = 1 in valeu + value";
+    let ast = parse_expr(program)?.to_core()?;
+    let mut context = boo_evaluation_optimized::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let actual_result = context.evaluator().evaluate(ast);
+    assert_eq!(
+        actual_result,
+        Err(Error::UnknownVariable {
+            span: Some((17..22).into()),
+            name: "valeu".to_string(),
+            suggestions: Some(Suggestions(vec!["value".to_string()])),
+        })
+    );
+    Ok(())
+}
+
 #[test]
 fn test_does_not_close_over_variables_out_of_scope() -> Result<()> {
     expect_error(
@@ -22,22 +58,154 @@ fn test_does_not_close_over_variables_out_of_scope() -> Result<()> {
         Error::UnknownVariable {
             span: Some((52..60).into()),
             name: "external".to_string(),
+            suggestions: None,
+        },
+    )
+}
+
+#[test]
+fn test_division_by_zero() -> Result<()> {
+    expect_evaluation_error(
+        "division_by_zero",
+        "let zero = 0 in 1 / zero",
+        Type::Integer.into(),
+        Error::DivisionByZero {
+            span: Some((16..24).into()),
+        },
+    )
+}
+
+#[test]
+fn test_modulo_by_zero() -> Result<()> {
+    expect_evaluation_error(
+        "modulo_by_zero",
+        "let zero = 0 in 1 % zero",
+        Type::Integer.into(),
+        Error::DivisionByZero {
+            span: Some((16..24).into()),
+        },
+    )
+}
+
+#[test]
+fn test_shift_by_a_negative_amount() -> Result<()> {
+    expect_evaluation_error(
+        "shift_by_a_negative_amount",
+        "let amount = 0 - 1 in 1 << amount",
+        Type::Integer.into(),
+        Error::InvalidShiftAmount {
+            span: Some((22..33).into()),
+            amount: (-1).into(),
+        },
+    )
+}
+
+#[test]
+fn test_raising_to_a_negative_power() -> Result<()> {
+    expect_evaluation_error(
+        "raising_to_a_negative_power",
+        "let exponent = 0 - 1 in pow 2 exponent",
+        Type::Integer.into(),
+        Error::InvalidExponent {
+            span: Some((24..38).into()),
+            exponent: (-1).into(),
+        },
+    )
+}
+
+#[test]
+fn test_failed_assertion() -> Result<()> {
+    expect_evaluation_error(
+        "failed_assertion",
+        "let x = 0 in (assert (x > 0); x)",
+        Type::Integer.into(),
+        Error::AssertionFailed {
+            span: Some((14..27).into()),
+        },
+    )
+}
+
+#[test]
+fn test_invalid_character_code() -> Result<()> {
+    expect_evaluation_error(
+        "invalid_character_code",
+        "int_to_char 55296",
+        Type::Char.into(),
+        Error::InvalidCharacterCode {
+            span: Some((0..17).into()),
+            code: 55296.into(),
+        },
+    )
+}
+
+#[test]
+fn test_head_of_empty_list() -> Result<()> {
+    expect_evaluation_error(
+        "head_of_empty_list",
+        "head [] + 1",
+        Type::Integer.into(),
+        Error::EmptyList {
+            span: Some((0..7).into()),
+        },
+    )
+}
+
+#[test]
+fn test_sequencing_evaluates_the_first_expression() -> Result<()> {
+    expect_evaluation_error(
+        "sequencing_evaluates_the_first_expression",
+        "let zero = 0 in (1 / zero; 2)",
+        Type::Integer.into(),
+        Error::DivisionByZero {
+            span: Some((17..25).into()),
+        },
+    )
+}
+
+#[test]
+fn test_print_requires_the_io_capability() -> Result<()> {
+    expect_error(
+        "print_requires_the_io_capability",
+        "print \"hello\"",
+        Error::UnknownVariable {
+            span: Some((0..5).into()),
+            name: "print".to_string(),
+            suggestions: None,
         },
     )
 }
 
 fn expect_error(name: &str, program: &str, expected_error: Error) -> Result<()> {
-    let ast = parse(program)?.to_core()?;
+    check_errors(name, program, Err(expected_error.clone()), expected_error)
+}
+
+fn expect_evaluation_error(
+    name: &str,
+    program: &str,
+    expected_type: Monotype,
+    expected_error: Error,
+) -> Result<()> {
+    check_errors(name, program, Ok(expected_type), expected_error)
+}
+
+fn check_errors(
+    name: &str,
+    program: &str,
+    expected_type_check_result: Result<Monotype>,
+    expected_error: Error,
+) -> Result<()> {
+    let ast = parse_expr(program)?.to_core()?;
     insta::with_settings!({ description => program }, {
         insta::assert_debug_snapshot!(name.to_string() + "__parse", ast);
     });
 
-    let type_check_result = boo_types_hindley_milner::type_of(&ast);
-    assert_eq!(type_check_result, Err(expected_error.clone()));
+    let type_check_result =
+        boo_types_hindley_milner::type_of(&ast, builtins::IoCapabilities::default());
+    assert_eq!(type_check_result, expected_type_check_result);
 
     {
         let mut context = boo_evaluation_reduction::new();
-        builtins::prepare(&mut context)?;
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
         let evaluator = context.evaluator();
         let actual_result = evaluator.evaluate(ast.clone());
         assert_eq!(actual_result, Err(expected_error.clone()));
@@ -45,7 +213,7 @@ fn expect_error(name: &str, program: &str, expected_error: Error) -> Result<()>
 
     {
         let mut context = boo_evaluation_optimized::new();
-        builtins::prepare(&mut context)?;
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
         let evaluator = context.evaluator();
         let actual_result = evaluator.evaluate(ast);
         assert_eq!(actual_result, Err(expected_error));
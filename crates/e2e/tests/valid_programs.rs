@@ -1,5 +1,5 @@
 use boo::error::Result;
-use boo::evaluation::{EvaluationContext, Evaluator};
+use boo::evaluation::{Evaluated, EvaluationContext, Evaluator};
 use boo::types::{Monotype, Type};
 use boo::*;
 
@@ -8,6 +8,121 @@ fn test_integer() -> Result<()> {
     check_program("integer", "123", Type::Integer.into(), "123")
 }
 
+#[test]
+fn test_negation() -> Result<()> {
+    check_program(
+        "negation",
+        "let x = 4 in -(x * 2) + -x",
+        Type::Integer.into(),
+        "-12",
+    )
+}
+
+#[test]
+fn test_bitwise_operators() -> Result<()> {
+    check_program(
+        "bitwise_operators",
+        "(12 & 10) | (12 xor 10) << 4 | 255 >> 6",
+        Type::Integer.into(),
+        "107",
+    )
+}
+
+#[test]
+fn test_float_operators() -> Result<()> {
+    check_program(
+        "float_operators",
+        "1.5 +. 2.0 *. 3e2 -. 0.25 /. 0.5",
+        Type::Float.into(),
+        "601.0",
+    )
+}
+
+#[test]
+fn test_boolean() -> Result<()> {
+    check_program("boolean", "false", Type::Boolean.into(), "false")
+}
+
+#[test]
+fn test_character_conversion() -> Result<()> {
+    check_program(
+        "character_conversion",
+        "int_to_char (char_to_int 'a' + 1)",
+        Type::Char.into(),
+        "'b'",
+    )
+}
+
+#[test]
+fn test_pattern_matching_on_characters() -> Result<()> {
+    check_program(
+        "pattern_matching_on_characters",
+        r"let c = '\n' in match c { ' ' -> 0; '\n' -> 1; _ -> 2 }",
+        Type::Integer.into(),
+        "1",
+    )
+}
+
+#[test]
+fn test_string() -> Result<()> {
+    check_program(
+        "string",
+        r#""Hello, \"world\"!""#,
+        Type::String.into(),
+        r#""Hello, \"world\"!""#,
+    )
+}
+
+#[test]
+fn test_string_functions() -> Result<()> {
+    check_program(
+        "string_functions",
+        r#"let greeting = "Hello" ++ ", " ++ "world!" in substring greeting 7 (string_length greeting - 1)"#,
+        Type::String.into(),
+        r#""world""#,
+    )
+}
+
+#[test]
+fn test_splitting_strings() -> Result<()> {
+    check_program(
+        "splitting_strings",
+        r#"match split ", " (to_upper "a, b") { [first, second] -> concat second (to_lower first); _ -> "" }"#,
+        Type::String.into(),
+        r#""Ba""#,
+    )
+}
+
+#[test]
+fn test_parsing_integers() -> Result<()> {
+    check_program(
+        "parsing_integers",
+        r#"unwrap_or 0 (parse_int "-12") + unwrap_or 100 (parse_int "twelve") + length "abc""#,
+        Type::Integer.into(),
+        "91",
+    )
+}
+
+#[test]
+fn test_pattern_matching_on_strings() -> Result<()> {
+    check_program(
+        "pattern_matching_on_strings",
+        r#"let greeting = "hello" in match greeting { "goodbye" -> 0; "hello" -> 1; _ -> 2 }"#,
+        Type::Integer.into(),
+        "1",
+    )
+}
+
+#[test]
+fn test_comparison_operators() -> Result<()> {
+    check_program(
+        "comparison_operators",
+        "3 * 3 > 8",
+        Type::Boolean.into(),
+        "true",
+    )
+}
+
 #[test]
 fn test_mathematical_operators() -> Result<()> {
     check_program(
@@ -18,6 +133,21 @@ fn test_mathematical_operators() -> Result<()> {
     )
 }
 
+#[test]
+fn test_modulo() -> Result<()> {
+    check_program("modulo", "17 % 5 + -17 % 5", Type::Integer.into(), "0")
+}
+
+#[test]
+fn test_division_rounds_towards_zero() -> Result<()> {
+    check_program(
+        "division_rounds_towards_zero",
+        "-7 / 2",
+        Type::Integer.into(),
+        "-3",
+    )
+}
+
 #[test]
 fn test_overriding_precedence() -> Result<()> {
     check_program(
@@ -118,6 +248,26 @@ fn test_closing_over_a_variable() -> Result<()> {
     )
 }
 
+#[test]
+fn test_annotated_assignment() -> Result<()> {
+    check_program(
+        "annotated_assignment",
+        "let double: Integer -> Integer = fn x -> x * 2 in double 21",
+        Type::Integer.into(),
+        "42",
+    )
+}
+
+#[test]
+fn test_annotated_function_parameters() -> Result<()> {
+    check_program(
+        "annotated_function_parameters",
+        "let multiply = fn (x: Integer) (y: Integer) -> x * y in multiply 6 7",
+        Type::Integer.into(),
+        "42",
+    )
+}
+
 #[test]
 fn test_polymorphic_let() -> Result<()> {
     check_program(
@@ -128,6 +278,26 @@ fn test_polymorphic_let() -> Result<()> {
     )
 }
 
+#[test]
+fn test_mutual_recursion() -> Result<()> {
+    check_program(
+        "mutual_recursion",
+        "let rec is_even = fn n -> match n { 0 -> true; _ -> is_odd (n - 1) } and is_odd = fn n -> match n { 0 -> false; _ -> is_even (n - 1) } in is_even 10",
+        Type::Boolean.into(),
+        "true",
+    )
+}
+
+#[test]
+fn test_top_level_definitions() -> Result<()> {
+    check_program(
+        "top_level_definitions",
+        "def double = fn x -> x * 2;\nlet quadruple: Integer -> Integer = fn x -> double (double x);\ndef three = 3;\nquadruple three + 1",
+        Type::Integer.into(),
+        "13",
+    )
+}
+
 #[test]
 fn test_pattern_matching_on_integers() -> Result<()> {
     check_program(
@@ -138,6 +308,16 @@ fn test_pattern_matching_on_integers() -> Result<()> {
     )
 }
 
+#[test]
+fn test_pattern_matching_on_booleans() -> Result<()> {
+    check_program(
+        "pattern_matching_on_booleans",
+        "let yes = true in match yes { false -> 0; true -> 1; _ -> 2 }",
+        Type::Integer.into(),
+        "1",
+    )
+}
+
 #[test]
 fn test_pattern_matching_on_functions() -> Result<()> {
     check_program(
@@ -148,6 +328,185 @@ fn test_pattern_matching_on_functions() -> Result<()> {
     )
 }
 
+#[test]
+fn test_tuple() -> Result<()> {
+    check_program(
+        "tuple",
+        r#"(1 + 1, "two", 3.0)"#,
+        Type::Tuple(vec![
+            Type::Integer.into(),
+            Type::String.into(),
+            Type::Float.into(),
+        ])
+        .into(),
+        r#"(2, "two", 3.0)"#,
+    )
+}
+
+#[test]
+fn test_pattern_matching_on_tuples() -> Result<()> {
+    check_program(
+        "pattern_matching_on_tuples",
+        "let swap = fn pair -> match pair { (0, _) -> (false, 0); (x, y) -> (y, x) } in swap (5, true)",
+        Type::Tuple(vec![Type::Boolean.into(), Type::Integer.into()]).into(),
+        "(true, 5)",
+    )
+}
+
+#[test]
+fn test_list() -> Result<()> {
+    check_program(
+        "list",
+        "[1, 1 + 1, 3]",
+        Type::List(Type::Integer.into()).into(),
+        "[1, 2, 3]",
+    )
+}
+
+#[test]
+fn test_list_functions() -> Result<()> {
+    check_program(
+        "list_functions",
+        "let list = 1 :: [2, 3] in match is_empty list { true -> 0; _ -> head (tail list) }",
+        Type::Integer.into(),
+        "2",
+    )
+}
+
+#[test]
+fn test_list_library() -> Result<()> {
+    check_program(
+        "list_library",
+        "let evens = filter (fn x -> x % 2 == 0) (range 1 11) in fold (fn total -> fn x -> total + x) (list_length evens) (map (fn x -> x * x) evens)",
+        Type::Integer.into(),
+        "225",
+    )
+}
+
+#[test]
+fn test_math_functions() -> Result<()> {
+    check_program(
+        "math_functions",
+        "abs (0 - 7) + sign (0 - 3) + min 4 9 + max 4 9 + pow 2 10 + gcd 12 18",
+        Type::Integer.into(),
+        "1049",
+    )
+}
+
+#[test]
+fn test_options() -> Result<()> {
+    check_program(
+        "options",
+        "let double = map_option (fn x -> x * 2) in unwrap_or 0 (double (some 21)) + unwrap_or 1 (double none)",
+        Type::Integer.into(),
+        "43",
+    )
+}
+
+#[test]
+fn test_option_values() -> Result<()> {
+    check_program(
+        "option_values",
+        "let o: Option Boolean = some true in map_option (fn x -> match x { true -> 'y'; _ -> 'n' }) o",
+        Type::Option(Type::Char.into()).into(),
+        "['y']",
+    )
+}
+
+#[test]
+fn test_results() -> Result<()> {
+    check_program(
+        "results",
+        "let recover = unwrap_or_else (fn e -> string_length e) in recover (map_result (fn x -> x + 1) (checked_divide 7 2)) + recover (checked_divide 7 0)",
+        Type::Integer.into(),
+        "20",
+    )
+}
+
+#[test]
+fn test_try_expressions() -> Result<()> {
+    check_program(
+        "try_expressions",
+        "try x = checked_divide 12 3 in try y: Integer = checked_divide x 0 in ok (x + y)",
+        Type::Result {
+            ok: Type::Integer.into(),
+            error: Type::String.into(),
+        }
+        .into(),
+        r#"(false, "division by zero")"#,
+    )
+}
+
+#[test]
+fn test_sequencing() -> Result<()> {
+    check_program(
+        "sequencing",
+        "let x = 3 in (x + 1; x * 2)",
+        Type::Integer.into(),
+        "6",
+    )
+}
+
+#[test]
+fn test_assertions() -> Result<()> {
+    check_program(
+        "assertions",
+        "let x = 3 in (assert (x > 0); assert (x * x == 9); x)",
+        Type::Integer.into(),
+        "3",
+    )
+}
+
+#[test]
+fn test_higher_rank_annotations() -> Result<()> {
+    check_program(
+        "higher_rank_annotations",
+        "let both: (forall a. a -> a) -> (Integer, Boolean) = fn f -> (f 1, f true) in both (fn x -> x)",
+        Type::Tuple(vec![Type::Integer.into(), Type::Boolean.into()]).into(),
+        "(1, true)",
+    )
+}
+
+#[test]
+fn test_recursive_types() -> Result<()> {
+    check_program(
+        "recursive_types",
+        "let rec sum: (mu t. (Integer, Option t)) -> Integer = fn xs -> match xs { (x, rest) -> x + unwrap_or 0 (map_option sum rest) } in sum (1, some (2, some (3, none)))",
+        Type::Integer.into(),
+        "6",
+    )
+}
+
+#[test]
+fn test_pattern_matching_on_lists() -> Result<()> {
+    check_program(
+        "pattern_matching_on_lists",
+        "let second = fn list -> match list { [] -> 0; [x] -> x; _ :: x :: _ -> x; _ -> 0 } in second [4, 5, 6]",
+        Type::Integer.into(),
+        "5",
+    )
+}
+
+#[test]
+fn test_pattern_matching_on_options() -> Result<()> {
+    check_program(
+        "pattern_matching_on_options",
+        "let describe = fn o -> match o { some (some x) -> x; some none -> 1; none -> 0; _ -> 0 - 1 } in (describe (some (some 7)), describe (some none), describe none)",
+        Type::Tuple(vec![Type::Integer.into(), Type::Integer.into(), Type::Integer.into()]).into(),
+        "(7, 1, 0)",
+    )
+}
+
+#[test]
+fn test_pattern_matching_on_results() -> Result<()> {
+    check_program(
+        "pattern_matching_on_results",
+        "let describe = fn r -> match r { ok (some y) -> y; ok none -> 0; err e -> string_length e; _ -> 0 } in describe (ok (some 12)) + describe (ok none) + describe (err \"no\")",
+        Type::Integer.into(),
+        "14",
+    )
+}
+
 #[test]
 fn test_expression_type_annotations() -> Result<()> {
     check_program(
@@ -158,38 +517,213 @@ fn test_expression_type_annotations() -> Result<()> {
     )
 }
 
+#[test]
+fn test_natives_registered_by_the_embedder() -> Result<()> {
+    let globals = builtins::Globals::new(builtins::IoCapabilities::default())
+        .with_native(
+            identifier::Identifier::name_from_str("answer").unwrap(),
+            types::Polytype::unquantified(Type::Integer.into()),
+            |_, []| Ok(primitive::Primitive::Integer(42.into())),
+        )
+        .with_binary_native(
+            identifier::Identifier::name_from_str("repeat").unwrap(),
+            Type::String.into(),
+            Type::Integer.into(),
+            Type::String.into(),
+            |string, count| match (string, count) {
+                (primitive::Primitive::String(string), primitive::Primitive::Integer(count)) => {
+                    let count = usize::try_from(&count)
+                        .map_err(|_| error::Error::InvalidPrimitive { span: None })?;
+                    Ok(primitive::Primitive::String(string.repeat(count).into()))
+                }
+                _ => Err(error::Error::InvalidPrimitive { span: None }),
+            },
+        );
+    let program = parse_program(r#"repeat "ab" (answer / 14)"#)?;
+    let expected_result =
+        Evaluated::Primitive(primitive::Primitive::String("ababab".to_string().into()));
+
+    let actual_type =
+        boo_types_hindley_milner::type_of(&program.clone().to_core()?, globals.clone())?;
+    assert_eq!(actual_type, Type::String.into());
+
+    {
+        let mut context = boo_evaluation_reduction::new();
+        globals.prepare(&mut context)?;
+        let main = program.clone().bind(&mut context)?;
+        let actual_result = context.evaluator().evaluate(main)?;
+        assert_eq!(actual_result, expected_result);
+    }
+
+    {
+        let mut context = boo_evaluation_optimized::new();
+        globals.prepare(&mut context)?;
+        let main = program.bind(&mut context)?;
+        let actual_result = context.evaluator().evaluate(main)?;
+        assert_eq!(actual_result, expected_result);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "has 1 parameter(s) in its type")]
+fn test_natives_must_take_one_argument_per_parameter() {
+    builtins::Globals::new(builtins::IoCapabilities::default()).with_native(
+        identifier::Identifier::name_from_str("add").unwrap(),
+        types::Polytype::unquantified(
+            Type::Function {
+                parameter: Type::Integer.into(),
+                body: Type::Integer.into(),
+            }
+            .into(),
+        ),
+        |_, [left, right]| match (left, right) {
+            (primitive::Primitive::Integer(left), primitive::Primitive::Integer(right)) => {
+                Ok(primitive::Primitive::Integer(left + right))
+            }
+            _ => Err(error::Error::InvalidPrimitive { span: None }),
+        },
+    );
+}
+
+#[test]
+fn test_restricting_the_available_builtins() -> Result<()> {
+    let globals = builtins::BuiltinsBuilder::new()
+        .with(builtins::BuiltinGroup::Integers)
+        .build();
+
+    let program = parse_program("(3 + 4) * 6")?;
+    let actual_type =
+        boo_types_hindley_milner::type_of(&program.clone().to_core()?, globals.clone())?;
+    assert_eq!(actual_type, Type::Integer.into());
+    let mut context = boo_evaluation_optimized::new();
+    globals.prepare(&mut context)?;
+    let main = program.bind(&mut context)?;
+    let actual_result = context.evaluator().evaluate(main)?;
+    assert_eq!(
+        actual_result,
+        Evaluated::Primitive(primitive::Primitive::Integer(42.into()))
+    );
+
+    for forbidden in [r#"concat "a" "b""#, "1 < 2", r#"print "hello""#] {
+        let program = parse_program(forbidden)?;
+        let result =
+            boo_types_hindley_milner::type_of(&program.clone().to_core()?, globals.clone());
+        assert!(result.is_err(), "{forbidden} should not type-check");
+        let mut context = boo_evaluation_optimized::new();
+        globals.prepare(&mut context)?;
+        let result = program
+            .bind(&mut context)
+            .and_then(|main| context.evaluator().evaluate(main));
+        assert!(result.is_err(), "{forbidden} should not evaluate");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_random_numbers_are_reproducible() -> Result<()> {
+    let capabilities = builtins::IoCapabilities {
+        random_seed: Some(1234),
+        ..Default::default()
+    };
+    let program = parse_program("match random 1000000 seed { (value, _) -> value }")?;
+
+    let actual_type = boo_types_hindley_milner::type_of(&program.clone().to_core()?, capabilities)?;
+    assert_eq!(actual_type, Type::Integer.into());
+
+    let evaluate_with_reduction = || -> Result<Evaluated> {
+        let mut context = boo_evaluation_reduction::new();
+        builtins::prepare(&mut context, capabilities)?;
+        let main = program.clone().bind(&mut context)?;
+        context.evaluator().evaluate(main)
+    };
+    let evaluate_with_optimized = || -> Result<Evaluated> {
+        let mut context = boo_evaluation_optimized::new();
+        builtins::prepare(&mut context, capabilities)?;
+        let main = program.clone().bind(&mut context)?;
+        context.evaluator().evaluate(main)
+    };
+    let first = evaluate_with_reduction()?;
+
+    let Evaluated::Primitive(primitive::Primitive::Integer(value)) = &first else {
+        panic!("expected an integer, got {first:?}");
+    };
+    assert!(
+        (0.into()..1_000_000.into()).contains(value),
+        "{value} is out of range"
+    );
+    assert_eq!(evaluate_with_reduction()?, first);
+    assert_eq!(evaluate_with_optimized()?, first);
+    assert_eq!(evaluate_with_optimized()?, first);
+    Ok(())
+}
+
+#[test]
+fn test_random_numbers_do_not_depend_on_evaluation_order() -> Result<()> {
+    let capabilities = builtins::IoCapabilities {
+        random_seed: Some(1234),
+        ..Default::default()
+    };
+    let program = parse_program(
+        "let r = random 1000000 seed in match (r, r) { ((a, _), (b, _)) -> a == b }",
+    )?;
+    let expected_result = Evaluated::Primitive(primitive::Primitive::Boolean(true));
+
+    {
+        let mut context = boo_evaluation_reduction::new();
+        builtins::prepare(&mut context, capabilities)?;
+        let main = program.clone().bind(&mut context)?;
+        assert_eq!(context.evaluator().evaluate(main)?, expected_result);
+    }
+
+    {
+        let mut context = boo_evaluation_optimized::new();
+        builtins::prepare(&mut context, capabilities)?;
+        let main = program.bind(&mut context)?;
+        assert_eq!(context.evaluator().evaluate(main)?, expected_result);
+    }
+
+    Ok(())
+}
+
 fn check_program(
     name: &str,
     program: &str,
     expected_type: Monotype,
     expected_result_str: &str,
 ) -> Result<()> {
-    let ast = parse(program)?.to_core()?;
+    let parsed = parse_program(program)?;
+    let ast = parsed.clone().to_core()?;
     insta::with_settings!({ description => program }, {
         insta::assert_debug_snapshot!(name.to_string() + "__parse", ast);
     });
 
-    let expected_result = match parse(expected_result_str)?.to_core()?.take() {
-        ast::Expression::Primitive(primitive) => evaluation::Evaluated::Primitive(primitive),
-        expression => panic!("Expected result that is not a primitive: {:?}", expression),
-    };
+    // The expected result is a literal, so evaluating it without any builtins
+    // is trivial.
+    let expected_result = boo_evaluation_reduction::new()
+        .evaluator()
+        .evaluate(parse_expr(expected_result_str)?.to_core()?)?;
 
-    let actual_type = boo_types_hindley_milner::type_of(&ast)?;
+    let actual_type = boo_types_hindley_milner::type_of(&ast, builtins::IoCapabilities::default())?;
     assert_eq!(actual_type, expected_type);
 
     {
         let mut context = boo_evaluation_reduction::new();
-        builtins::prepare(&mut context)?;
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+        let main = parsed.clone().bind(&mut context)?;
         let evaluator = context.evaluator();
-        let actual_result = evaluator.evaluate(ast.clone())?;
+        let actual_result = evaluator.evaluate(main)?;
         assert_eq!(actual_result, expected_result.clone());
     }
 
     {
         let mut context = boo_evaluation_optimized::new();
-        builtins::prepare(&mut context)?;
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+        let main = parsed.bind(&mut context)?;
         let evaluator = context.evaluator();
-        let actual_result = evaluator.evaluate(ast)?;
+        let actual_result = evaluator.evaluate(main)?;
         assert_eq!(actual_result, expected_result);
     }
 
@@ -0,0 +1,435 @@
+//! Evaluates a core AST using a CEK machine.
+//!
+//! The state of the machine consists of the expression under evaluation (the
+//! control), the bindings in scope (the environment), and the work remaining
+//! once the expression has been evaluated (the continuation). Each step either
+//! breaks the control down, pushing a frame onto the continuation, or returns a
+//! value to the topmost frame.
+//!
+//! As with the other evaluators, values are bound lazily, and each binding is
+//! only evaluated once.
+
+use boo_core::ast::*;
+use boo_core::error::*;
+use boo_core::evaluation::*;
+use boo_core::expr::Expr;
+use boo_core::identifier::*;
+use boo_core::matching::{Selected, Selection};
+use boo_core::native::*;
+use boo_core::primitive::*;
+use boo_core::span::Span;
+use boo_evaluation_lazy::{
+    resolved, select, store, Binding, Bindings, CompletedEvaluation, EvaluatedBinding,
+};
+
+pub fn new() -> impl EvaluationContext {
+    CekEvaluator::new()
+}
+
+/// Evaluates an AST using a CEK machine.
+pub struct CekEvaluator {
+    bindings: Bindings<Expr>,
+    integer_overflow: IntegerOverflow,
+}
+
+impl CekEvaluator {
+    pub fn new() -> Self {
+        Self {
+            bindings: Bindings::new(),
+            integer_overflow: IntegerOverflow::default(),
+        }
+    }
+
+    /// Decides how arithmetic on small integers handles overflow.
+    pub fn with_integer_overflow(self, integer_overflow: IntegerOverflow) -> Self {
+        Self {
+            integer_overflow,
+            ..self
+        }
+    }
+}
+
+impl Default for CekEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EvaluationContext for CekEvaluator {
+    type Eval = Self;
+
+    fn bind(&mut self, identifier: Identifier, expr: Expr) -> Result<()> {
+        self.bindings = self.bindings.with(identifier, expr, self.bindings.clone());
+        Ok(())
+    }
+
+    fn identifiers(&self) -> Vec<Identifier> {
+        self.bindings.identifiers().cloned().collect()
+    }
+
+    fn evaluator(self) -> Self::Eval {
+        self
+    }
+}
+
+impl Evaluator for CekEvaluator {
+    fn evaluate(&self, expr: Expr) -> Result<Evaluated> {
+        self.run(
+            State::Evaluate {
+                control: expr,
+                environment: self.bindings.clone(),
+            },
+            vec![],
+        )?
+        .finish(&|binding| self.force(binding))
+    }
+}
+
+/// The control and environment of the machine.
+enum State {
+    /// Evaluates an expression in an environment.
+    Evaluate {
+        control: Expr,
+        environment: Bindings<Expr>,
+    },
+    /// Returns the result of an evaluation to the continuation.
+    Return(EvaluatedBinding<Expr>),
+}
+
+/// A single frame of the continuation.
+enum Frame {
+    /// Applies the returned function to the argument.
+    Apply {
+        argument: Expr,
+        environment: Bindings<Expr>,
+        span: Option<Span>,
+    },
+    /// Discards the returned value, and evaluates the next expression.
+    Sequence {
+        next: Expr,
+        environment: Bindings<Expr>,
+        call_site: Option<Span>,
+    },
+    /// Stores the returned value in a binding, so it is only evaluated once.
+    Update(Binding<Expr>),
+    /// Continues matching, now that the part of the value it asked for has
+    /// been evaluated.
+    Match {
+        selection: Selection<Binding<Expr>, Expr>,
+        environment: Bindings<Expr>,
+        span: Option<Span>,
+        call_site: Option<Span>,
+    },
+}
+
+impl CekEvaluator {
+    /// Runs the machine until it returns a value with nothing left to do.
+    fn run(&self, mut state: State, mut continuation: Vec<Frame>) -> EvaluatedBinding<Expr> {
+        // the most recent application of a function, blamed for errors in natives
+        let mut call_site = None;
+        loop {
+            state = match state {
+                State::Return(result) if continuation.is_empty() => return result,
+                State::Evaluate {
+                    control,
+                    environment,
+                } => self.evaluate_step(control, environment, call_site, &mut continuation),
+                State::Return(result) => {
+                    let frame = continuation.pop().unwrap();
+                    self.return_step(result, frame, &mut call_site, &mut continuation)
+                }
+            }
+        }
+    }
+
+    /// Breaks down the expression under evaluation.
+    fn evaluate_step(
+        &self,
+        control: Expr,
+        mut environment: Bindings<Expr>,
+        call_site: Option<Span>,
+        continuation: &mut Vec<Frame>,
+    ) -> State {
+        let span = control.span();
+        match control.take() {
+            Expression::Primitive(value) => {
+                State::Return(Ok(CompletedEvaluation::Primitive(value)))
+            }
+            Expression::Native(Native { implementation, .. }) => State::Return(
+                implementation(&self.switch(environment))
+                    .map(CompletedEvaluation::Primitive)
+                    .map_err(|error| error.located_at(call_site)),
+            ),
+            Expression::Identifier(name) => match environment.read(&name).cloned() {
+                Some(binding) => match binding.unresolved_value() {
+                    Some((control, environment)) => {
+                        continuation.push(Frame::Update(binding));
+                        State::Evaluate {
+                            control,
+                            environment,
+                        }
+                    }
+                    None => State::Return(resolved(
+                        binding.value().expect("Binding was not resolved."),
+                    )),
+                },
+                None => State::Return(Err(Error::unknown_variable(
+                    span,
+                    &name,
+                    environment.identifiers(),
+                ))),
+            },
+            Expression::Function(Function {
+                parameter,
+                parameter_type,
+                body,
+                ..
+            }) => State::Return(Ok(CompletedEvaluation::Closure {
+                parameter,
+                parameter_type,
+                body,
+                bindings: environment,
+            })),
+            Expression::Apply(Apply { function, argument }) => {
+                continuation.push(Frame::Apply {
+                    argument,
+                    environment: environment.clone(),
+                    span,
+                });
+                State::Evaluate {
+                    control: function,
+                    environment,
+                }
+            }
+            Expression::Assign(Assign { name, value, inner }) => State::Evaluate {
+                control: inner,
+                environment: environment.with(name, value, environment.clone()),
+            },
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => State::Evaluate {
+                control: inner,
+                environment: environment.with_recursive(bindings),
+            },
+            Expression::Match(Match { value, patterns }) => {
+                // Ensure we only evaluate the value once.
+                let value = Binding::unresolved((value, environment.clone()));
+                let mut selection = Selection::new(
+                    value,
+                    patterns.into_iter().map(
+                        |PatternMatch {
+                             pattern, result, ..
+                         }| (pattern, result),
+                    ),
+                );
+                let selected = select(&mut selection, None);
+                self.continue_selection(
+                    selection,
+                    selected,
+                    environment,
+                    span,
+                    call_site,
+                    continuation,
+                )
+            }
+            Expression::Typed(Typed { expression, .. }) => State::Evaluate {
+                control: expression,
+                environment,
+            },
+            Expression::Sequence(Sequence { first, second }) => {
+                continuation.push(Frame::Sequence {
+                    next: second,
+                    environment: environment.clone(),
+                    call_site,
+                });
+                State::Evaluate {
+                    control: first,
+                    environment,
+                }
+            }
+            Expression::Tuple(Tuple { elements }) => State::Return(Ok(CompletedEvaluation::Tuple(
+                elements
+                    .into_iter()
+                    .map(|element| Binding::unresolved((element, environment.clone())))
+                    .collect(),
+            ))),
+            Expression::List(List::Empty) => {
+                State::Return(Ok(CompletedEvaluation::List(List::Empty)))
+            }
+            Expression::List(List::Cons { head, tail }) => {
+                State::Return(Ok(CompletedEvaluation::List(List::Cons {
+                    head: Binding::unresolved((head, environment.clone())),
+                    tail: Binding::unresolved((tail, environment)),
+                })))
+            }
+        }
+    }
+
+    /// Returns a result to the topmost frame of the continuation.
+    ///
+    /// Applying a function moves the call site to that application, and it is
+    /// restored by frames that resume evaluation where they were pushed.
+    fn return_step(
+        &self,
+        result: EvaluatedBinding<Expr>,
+        frame: Frame,
+        call_site: &mut Option<Span>,
+        continuation: &mut Vec<Frame>,
+    ) -> State {
+        match frame {
+            Frame::Update(binding) => State::Return(store(&binding, result)),
+            // errors skip over every other frame
+            _ if result.is_err() => State::Return(result),
+            Frame::Apply {
+                argument,
+                environment,
+                span,
+            } => match result {
+                Ok(CompletedEvaluation::Closure {
+                    parameter,
+                    body,
+                    bindings,
+                    ..
+                }) => {
+                    *call_site = span.or(*call_site);
+                    State::Evaluate {
+                        control: body,
+                        // the argument is evaluated in the environment of the call
+                        environment: bindings.with(parameter, argument, environment),
+                    }
+                }
+                _ => State::Return(Err(Error::InvalidFunctionApplication { span })),
+            },
+            Frame::Sequence {
+                next,
+                environment,
+                call_site: next_call_site,
+            } => {
+                *call_site = next_call_site;
+                State::Evaluate {
+                    control: next,
+                    environment,
+                }
+            }
+            Frame::Match {
+                mut selection,
+                environment,
+                span,
+                call_site: match_call_site,
+            } => {
+                *call_site = match_call_site;
+                let selected = select(&mut selection, result.ok());
+                self.continue_selection(
+                    selection,
+                    selected,
+                    environment,
+                    span,
+                    *call_site,
+                    continuation,
+                )
+            }
+        }
+    }
+
+    /// Evaluates the result of the pattern that matched, or, if the selection
+    /// needs part of the value, evaluates that first, and then continues.
+    fn continue_selection(
+        &self,
+        selection: Selection<Binding<Expr>, Expr>,
+        selected: Result<Selected<Binding<Expr>, Expr>>,
+        environment: Bindings<Expr>,
+        span: Option<Span>,
+        call_site: Option<Span>,
+        continuation: &mut Vec<Frame>,
+    ) -> State {
+        match selected {
+            Ok(Selected::Matched { result, variables }) => State::Evaluate {
+                control: result,
+                environment: environment.with_bindings(variables),
+            },
+            Ok(Selected::NoMatch) => State::Return(Err(Error::MatchWithoutBaseCase { span })),
+            Ok(Selected::Evaluate(binding)) => {
+                let (control, value_environment) = binding
+                    .unresolved_value()
+                    .expect("Binding was already evaluated.");
+                continuation.push(Frame::Match {
+                    selection,
+                    environment,
+                    span,
+                    call_site,
+                });
+                continuation.push(Frame::Update(binding));
+                State::Evaluate {
+                    control,
+                    environment: value_environment,
+                }
+            }
+            Err(error) => State::Return(Err(error)),
+        }
+    }
+
+    /// Evaluates a binding immediately; see [`boo_evaluation_lazy::force`].
+    fn force(&self, binding: &mut Binding<Expr>) -> EvaluatedBinding<Expr> {
+        boo_evaluation_lazy::force(binding, |(control, environment)| {
+            self.run(
+                State::Evaluate {
+                    control,
+                    environment,
+                },
+                vec![],
+            )
+        })
+    }
+
+    fn switch(&self, new_bindings: Bindings<Expr>) -> Self {
+        Self {
+            bindings: new_bindings,
+            integer_overflow: self.integer_overflow,
+        }
+    }
+}
+
+impl NativeContext for CekEvaluator {
+    fn lookup_value(&self, identifier: &Identifier) -> Result<Primitive> {
+        let result = match self.bindings.clone().read(identifier) {
+            Some(binding) => self.force(binding)?,
+            None => {
+                return Err(Error::UnknownVariable {
+                    span: None,
+                    name: identifier.to_string(),
+                    suggestions: None,
+                })
+            }
+        };
+        match result {
+            CompletedEvaluation::Primitive(primitive) => Ok(primitive),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        }
+    }
+
+    fn apply(&self, function: &Identifier, argument: Primitive) -> Result<Primitive> {
+        let function = match self.bindings.clone().read(function) {
+            Some(binding) => self.force(binding)?,
+            None => {
+                return Err(Error::UnknownVariable {
+                    span: None,
+                    name: function.to_string(),
+                    suggestions: None,
+                })
+            }
+        };
+        let (body, bindings) = function.apply_to(argument)?;
+        match self.run(
+            State::Evaluate {
+                control: body,
+                environment: bindings,
+            },
+            vec![],
+        )? {
+            CompletedEvaluation::Primitive(primitive) => Ok(primitive),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        }
+    }
+
+    fn integer_overflow(&self) -> IntegerOverflow {
+        self.integer_overflow
+    }
+}
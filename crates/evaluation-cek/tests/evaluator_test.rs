@@ -0,0 +1,48 @@
+use boo_core::evaluation::IntegerOverflow;
+
+#[test]
+fn test_evaluation_gets_the_same_result_as_reducing_evaluation() {
+    let cek_evaluator = boo_conformance::prepared(boo_evaluation_cek::new());
+    boo_conformance::check_against_reference(&cek_evaluator);
+}
+
+#[test]
+fn test_applying_functions_gets_the_same_result_as_reducing_evaluation() {
+    let cek_evaluator = boo_conformance::prepared(boo_evaluation_cek::new());
+    boo_conformance::check_applications_against_reference(&cek_evaluator);
+}
+
+#[test]
+fn test_failing_gets_the_same_error_as_reducing_evaluation() {
+    let cek_evaluator = boo_conformance::prepared(boo_evaluation_cek::new());
+    boo_conformance::check_failures_against_reference(&cek_evaluator);
+}
+
+#[test]
+fn test_integer_overflow_fails_in_the_same_way_as_reducing_evaluation() {
+    let cek_evaluator = boo_conformance::prepared(
+        boo_evaluation_cek::CekEvaluator::new().with_integer_overflow(IntegerOverflow::Fail),
+    );
+    boo_conformance::check_integer_overflow_fails(&cek_evaluator);
+}
+
+#[test]
+fn test_natives_can_apply_functions() {
+    let cek_evaluator = boo_conformance::prepared(boo_evaluation_cek::new());
+    boo_conformance::check_natives_can_apply_functions(&cek_evaluator);
+}
+
+#[test]
+fn test_lists_bound_identifiers() {
+    boo_conformance::check_bound_identifiers_are_listed(boo_evaluation_cek::new());
+}
+
+#[test]
+fn test_evaluates_deeply_nested_expressions() {
+    boo_conformance::check_deeply_nested_expressions_in_a_small_stack(boo_evaluation_cek::new);
+}
+
+#[test]
+fn test_evaluates_deeply_nested_matches() {
+    boo_conformance::check_deeply_nested_matches_in_a_small_stack(boo_evaluation_cek::new);
+}
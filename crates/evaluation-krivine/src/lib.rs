@@ -0,0 +1,612 @@
+//! Evaluates a core AST using a Krivine machine.
+//!
+//! Unlike the other evaluators, this one uses call-by-name: a binding is
+//! evaluated every time it is used, rather than once, with the result shared.
+//! This is often much slower, but it is simpler, and so it serves as a point of
+//! comparison for the lazy evaluators.
+//!
+//! The state of the machine consists of the expression under evaluation, the
+//! environment in which it is evaluated, and a stack, which holds the arguments
+//! to the expression, followed by any other work remaining.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use boo_core::ast::*;
+use boo_core::error::*;
+use boo_core::evaluation::*;
+use boo_core::expr::Expr;
+use boo_core::identifier::*;
+use boo_core::matching::{Selected, Selection, Shape};
+use boo_core::native::*;
+use boo_core::primitive::*;
+use boo_core::span::Span;
+use boo_core::types::Monotype;
+use boo_evaluation_lazy::drop_iteratively;
+
+pub fn new() -> impl EvaluationContext {
+    KrivineEvaluator::new()
+}
+
+/// Evaluates an AST using a Krivine machine.
+pub struct KrivineEvaluator {
+    environment: Environment,
+    step_limit: Option<usize>,
+    steps: Rc<Cell<usize>>,
+    integer_overflow: IntegerOverflow,
+}
+
+impl KrivineEvaluator {
+    pub fn new() -> Self {
+        Self {
+            environment: Environment::new(),
+            step_limit: None,
+            steps: Rc::new(Cell::new(0)),
+            integer_overflow: IntegerOverflow::default(),
+        }
+    }
+
+    /// Stops evaluation with an error once it has taken the given number of
+    /// steps.
+    ///
+    /// As bindings are not shared, some programs take exponentially longer to
+    /// evaluate with this evaluator than with the others.
+    pub fn with_step_limit(self, limit: usize) -> Self {
+        Self {
+            step_limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Decides how arithmetic on small integers handles overflow.
+    pub fn with_integer_overflow(self, integer_overflow: IntegerOverflow) -> Self {
+        Self {
+            integer_overflow,
+            ..self
+        }
+    }
+}
+
+impl Default for KrivineEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EvaluationContext for KrivineEvaluator {
+    type Eval = Self;
+
+    fn bind(&mut self, identifier: Identifier, expr: Expr) -> Result<()> {
+        self.environment = self.environment.update(
+            identifier,
+            Closure::Suspended {
+                expr,
+                environment: self.environment.clone(),
+            },
+        );
+        Ok(())
+    }
+
+    fn identifiers(&self) -> Vec<Identifier> {
+        self.environment.keys().cloned().collect()
+    }
+
+    fn evaluator(self) -> Self::Eval {
+        self
+    }
+}
+
+impl Evaluator for KrivineEvaluator {
+    fn evaluate(&self, expr: Expr) -> Result<Evaluated> {
+        self.steps.set(0);
+        let value = self.run(expr, self.environment.clone())?;
+        self.finish(value)
+    }
+}
+
+/// The bindings in scope.
+type Environment = im::HashMap<Identifier, Closure>;
+
+/// An unevaluated expression, along with its environment.
+#[derive(Clone)]
+enum Closure {
+    Suspended {
+        expr: Expr,
+        environment: Environment,
+    },
+    /// One of a group of mutually-recursive bindings. These are evaluated in
+    /// an environment containing the whole group, which is rebuilt each time.
+    Recursive {
+        name: Identifier,
+        group: Rc<Vec<(Identifier, Expr)>>,
+        environment: Environment,
+    },
+}
+
+impl Closure {
+    /// Retrieves the expression and the environment in which to evaluate it.
+    fn open(&self) -> (Expr, Environment) {
+        match self {
+            Closure::Suspended { expr, environment } => (expr.clone(), environment.clone()),
+            Closure::Recursive {
+                name,
+                group,
+                environment,
+            } => {
+                let expr = group
+                    .iter()
+                    .find(|(candidate, _)| candidate == name)
+                    .map(|(_, expr)| expr.clone())
+                    .expect("Recursive binding is not in its group.");
+                (expr, with_recursive(environment, group))
+            }
+        }
+    }
+}
+
+impl Drop for Closure {
+    /// Closures refer to environments, which refer to other closures, often in
+    /// long chains, e.g. when a counter is passed along without being
+    /// evaluated. To avoid overflowing the stack, the environment is dropped
+    /// with [`drop_iteratively`].
+    fn drop(&mut self) {
+        let environment = match self {
+            Closure::Suspended { environment, .. } | Closure::Recursive { environment, .. } => {
+                std::mem::take(environment)
+            }
+        };
+        if !environment.is_empty() {
+            drop_iteratively(Box::new(environment));
+        }
+    }
+}
+
+/// Adds a group of mutually-recursive bindings to the environment.
+fn with_recursive(environment: &Environment, group: &Rc<Vec<(Identifier, Expr)>>) -> Environment {
+    group.iter().fold(environment.clone(), |result, (name, _)| {
+        result.update(
+            name.clone(),
+            Closure::Recursive {
+                name: name.clone(),
+                group: Rc::clone(group),
+                environment: environment.clone(),
+            },
+        )
+    })
+}
+
+/// An expression, evaluated as far as its outermost constructor.
+enum Value {
+    Primitive(Primitive),
+    Function {
+        parameter: Identifier,
+        parameter_type: Option<Monotype>,
+        body: Expr,
+    },
+    Tuple(Vec<Closure>),
+    List(List<Closure>),
+}
+
+impl Value {
+    /// The outermost constructor of the value, as far as matching is concerned.
+    fn shape(self) -> Shape<Closure> {
+        match self {
+            Value::Primitive(primitive) => Shape::Primitive(primitive),
+            Value::Function { .. } => Shape::Function,
+            Value::Tuple(elements) => Shape::Tuple(elements),
+            Value::List(list) => Shape::List(list),
+        }
+    }
+}
+
+/// An entry on the stack.
+enum Frame {
+    /// An argument to the function under evaluation.
+    Argument {
+        argument: Closure,
+        span: Option<Span>,
+    },
+    /// Discards the value, and evaluates the next expression.
+    Sequence {
+        next: Expr,
+        environment: Environment,
+        call_site: Option<Span>,
+    },
+    /// Continues matching, now that the part of the value it asked for has
+    /// been evaluated.
+    Match {
+        selection: Selection<Closure, Expr>,
+        environment: Environment,
+        span: Option<Span>,
+        call_site: Option<Span>,
+    },
+    /// Runs the native again, now that the value it asked for has been
+    /// evaluated.
+    Native {
+        native: Native,
+        environment: Environment,
+        evaluated: Vec<(Request, Primitive)>,
+        request: Request,
+        call_site: Option<Span>,
+    },
+}
+
+impl KrivineEvaluator {
+    /// Evaluates an expression as far as its outermost constructor.
+    fn run(&self, mut expr: Expr, mut environment: Environment) -> Result<Value> {
+        let mut stack: Vec<Frame> = vec![];
+        // the most recent application of a function, blamed for errors in natives
+        let mut call_site = None;
+        loop {
+            self.count_step()?;
+            let span = expr.span();
+            let value = match expr.take() {
+                Expression::Primitive(value) => Value::Primitive(value),
+                Expression::Native(native) => {
+                    (expr, environment) =
+                        self.run_native(native, environment, vec![], call_site, &mut stack)?;
+                    continue;
+                }
+                Expression::Identifier(name) => {
+                    let closure = environment
+                        .get(&name)
+                        .ok_or_else(|| Error::unknown_variable(span, &name, environment.keys()))?;
+                    (expr, environment) = closure.open();
+                    continue;
+                }
+                Expression::Function(Function {
+                    parameter,
+                    parameter_type,
+                    body,
+                    ..
+                }) => match stack.pop() {
+                    Some(Frame::Argument { argument, span }) => {
+                        call_site = span.or(call_site);
+                        environment = environment.update(parameter, argument);
+                        expr = body;
+                        continue;
+                    }
+                    frame => {
+                        stack.extend(frame);
+                        Value::Function {
+                            parameter,
+                            parameter_type,
+                            body,
+                        }
+                    }
+                },
+                Expression::Apply(Apply { function, argument }) => {
+                    stack.push(Frame::Argument {
+                        argument: Closure::Suspended {
+                            expr: argument,
+                            environment: environment.clone(),
+                        },
+                        span,
+                    });
+                    expr = function;
+                    continue;
+                }
+                Expression::Assign(Assign { name, value, inner }) => {
+                    environment = environment.update(
+                        name,
+                        Closure::Suspended {
+                            expr: value,
+                            environment: environment.clone(),
+                        },
+                    );
+                    expr = inner;
+                    continue;
+                }
+                Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                    environment = with_recursive(&environment, &Rc::new(bindings));
+                    expr = inner;
+                    continue;
+                }
+                Expression::Match(Match { value, patterns }) => {
+                    let closure = Closure::Suspended {
+                        expr: value,
+                        environment: environment.clone(),
+                    };
+                    let mut selection = Selection::new(
+                        closure,
+                        patterns.into_iter().map(
+                            |PatternMatch {
+                                 pattern, result, ..
+                             }| (pattern, result),
+                        ),
+                    );
+                    let selected = selection.advance();
+                    (expr, environment) = self.continue_selection(
+                        selection,
+                        selected,
+                        environment,
+                        span,
+                        call_site,
+                        &mut stack,
+                    )?;
+                    continue;
+                }
+                Expression::Typed(Typed { expression, .. }) => {
+                    expr = expression;
+                    continue;
+                }
+                Expression::Sequence(Sequence { first, second }) => {
+                    stack.push(Frame::Sequence {
+                        next: second,
+                        environment: environment.clone(),
+                        call_site,
+                    });
+                    expr = first;
+                    continue;
+                }
+                Expression::Tuple(Tuple { elements }) => Value::Tuple(
+                    elements
+                        .into_iter()
+                        .map(|element| Closure::Suspended {
+                            expr: element,
+                            environment: environment.clone(),
+                        })
+                        .collect(),
+                ),
+                Expression::List(List::Empty) => Value::List(List::Empty),
+                Expression::List(List::Cons { head, tail }) => Value::List(List::Cons {
+                    head: Closure::Suspended {
+                        expr: head,
+                        environment: environment.clone(),
+                    },
+                    tail: Closure::Suspended {
+                        expr: tail,
+                        environment,
+                    },
+                }),
+            };
+            match stack.pop() {
+                None => {
+                    return Ok(value);
+                }
+                // functions are handled above, so this must be something else
+                Some(Frame::Argument { span, .. }) => {
+                    return Err(Error::InvalidFunctionApplication { span });
+                }
+                Some(Frame::Sequence {
+                    next,
+                    environment: next_environment,
+                    call_site: next_call_site,
+                }) => {
+                    call_site = next_call_site;
+                    expr = next;
+                    environment = next_environment;
+                }
+                Some(Frame::Native {
+                    native,
+                    environment: native_environment,
+                    mut evaluated,
+                    request,
+                    call_site: native_call_site,
+                }) => {
+                    call_site = native_call_site;
+                    let Value::Primitive(value) = value else {
+                        return Err(Error::InvalidPrimitive { span: None });
+                    };
+                    evaluated.push((request, value));
+                    (expr, environment) = self.run_native(
+                        native,
+                        native_environment,
+                        evaluated,
+                        call_site,
+                        &mut stack,
+                    )?;
+                }
+                Some(Frame::Match {
+                    mut selection,
+                    environment: match_environment,
+                    span,
+                    call_site: match_call_site,
+                }) => {
+                    call_site = match_call_site;
+                    let selected = selection.resume(value.shape());
+                    (expr, environment) = self.continue_selection(
+                        selection,
+                        selected,
+                        match_environment,
+                        span,
+                        call_site,
+                        &mut stack,
+                    )?;
+                }
+            }
+        }
+    }
+
+    /// Finds the expression to evaluate next, and the environment in which to
+    /// evaluate it: either the result of the pattern that matched, or, if the
+    /// selection needs part of the value, that part, after which the selection
+    /// continues.
+    fn continue_selection(
+        &self,
+        selection: Selection<Closure, Expr>,
+        selected: Selected<Closure, Expr>,
+        environment: Environment,
+        span: Option<Span>,
+        call_site: Option<Span>,
+        stack: &mut Vec<Frame>,
+    ) -> Result<(Expr, Environment)> {
+        match selected {
+            Selected::Matched { result, variables } => Ok((
+                result,
+                variables
+                    .into_iter()
+                    .fold(environment, |result, (name, closure)| {
+                        result.update(name, closure)
+                    }),
+            )),
+            Selected::NoMatch => Err(Error::MatchWithoutBaseCase { span }),
+            Selected::Evaluate(closure) => {
+                stack.push(Frame::Match {
+                    selection,
+                    environment,
+                    span,
+                    call_site,
+                });
+                Ok(closure.open())
+            }
+        }
+    }
+
+    /// Runs a native, returning its result as an expression to evaluate.
+    ///
+    /// If the native asks for a value which has not been evaluated yet, this
+    /// pushes a frame to run the native again, and returns the expression to
+    /// evaluate for that value instead.
+    fn run_native(
+        &self,
+        native: Native,
+        environment: Environment,
+        evaluated: Vec<(Request, Primitive)>,
+        call_site: Option<Span>,
+        stack: &mut Vec<Frame>,
+    ) -> Result<(Expr, Environment)> {
+        let context = NativeEnvironment {
+            environment: &environment,
+            evaluated: &evaluated,
+            missing: RefCell::new(None),
+            integer_overflow: self.integer_overflow,
+        };
+        let result = (native.implementation)(&context);
+        let Some(request) = context.missing.into_inner() else {
+            let value = result.map_err(|error| error.located_at(call_site))?;
+            return Ok((Expr::new(None, Expression::Primitive(value)), environment));
+        };
+        let next = match &request {
+            Request::Lookup(name) => environment
+                .get(name)
+                .expect("A native asked for a variable which is not in scope.")
+                .open(),
+            Request::Apply(function, argument) => (
+                Expr::new(
+                    None,
+                    Expression::Apply(Apply {
+                        function: Expr::new(None, Expression::Identifier(function.clone())),
+                        argument: Expr::new(None, Expression::Primitive(argument.clone())),
+                    }),
+                ),
+                environment.clone(),
+            ),
+        };
+        stack.push(Frame::Native {
+            native,
+            environment,
+            evaluated,
+            request,
+            call_site,
+        });
+        Ok(next)
+    }
+
+    fn evaluate_closure(&self, closure: &Closure) -> Result<Value> {
+        let (expr, environment) = closure.open();
+        self.run(expr, environment)
+    }
+
+    /// Concludes evaluation, evaluating the elements of tuples and lists.
+    fn finish(&self, value: Value) -> Result<Evaluated> {
+        match value {
+            Value::Primitive(primitive) => Ok(Evaluated::Primitive(primitive)),
+            Value::Function {
+                parameter,
+                parameter_type,
+                body,
+            } => Ok(Evaluated::Function(Function {
+                parameter,
+                parameter_type,
+                parameter_type_span: None,
+                body,
+            })),
+            Value::Tuple(elements) => elements
+                .iter()
+                .map(|element| self.finish(self.evaluate_closure(element)?))
+                .collect::<Result<_>>()
+                .map(Evaluated::Tuple),
+            Value::List(list) => {
+                let mut elements = vec![];
+                let mut next = list;
+                while let List::Cons { head, tail } = next {
+                    elements.push(self.finish(self.evaluate_closure(&head)?)?);
+                    next = match self.evaluate_closure(&tail)? {
+                        Value::List(list) => list,
+                        _ => return Err(Error::InvalidPrimitive { span: None }),
+                    };
+                }
+                Ok(Evaluated::List(elements))
+            }
+        }
+    }
+
+    fn count_step(&self) -> Result<()> {
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+        match self.step_limit {
+            Some(limit) if steps > limit => Err(Error::StepLimitExceeded { limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A value asked for by a native.
+#[derive(Clone, PartialEq)]
+enum Request {
+    Lookup(Identifier),
+    Apply(Identifier, Primitive),
+}
+
+/// The environment in which a native runs.
+///
+/// Evaluating the values a native asks for here would run the machine within
+/// itself. Instead, the first value which has not been evaluated yet is noted,
+/// and the machine evaluates it on its own stack, and then runs the native
+/// again. Natives ask for their values before doing anything else, so running
+/// one again is safe.
+struct NativeEnvironment<'a> {
+    environment: &'a Environment,
+    evaluated: &'a [(Request, Primitive)],
+    missing: RefCell<Option<Request>>,
+    integer_overflow: IntegerOverflow,
+}
+
+impl<'a> NativeEnvironment<'a> {
+    fn request(&self, request: Request) -> Result<Primitive> {
+        match self
+            .evaluated
+            .iter()
+            .find(|(candidate, _)| *candidate == request)
+        {
+            Some((_, value)) => Ok(value.clone()),
+            None => {
+                self.missing.borrow_mut().get_or_insert(request);
+                // the result is discarded, as the native will be run again
+                Err(Error::Interrupted)
+            }
+        }
+    }
+}
+
+impl<'a> NativeContext for NativeEnvironment<'a> {
+    fn lookup_value(&self, identifier: &Identifier) -> Result<Primitive> {
+        if !self.environment.contains_key(identifier) {
+            return Err(Error::UnknownVariable {
+                span: None,
+                name: identifier.to_string(),
+                suggestions: None,
+            });
+        }
+        self.request(Request::Lookup(identifier.clone()))
+    }
+
+    fn apply(&self, function: &Identifier, argument: Primitive) -> Result<Primitive> {
+        self.request(Request::Apply(function.clone(), argument))
+    }
+
+    fn integer_overflow(&self) -> IntegerOverflow {
+        self.integer_overflow
+    }
+}
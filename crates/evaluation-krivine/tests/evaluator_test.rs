@@ -0,0 +1,105 @@
+use boo_core::builtins;
+use boo_core::error::Error;
+use boo_core::evaluation::*;
+use boo_core::primitive::Primitive;
+use boo_evaluation_krivine::KrivineEvaluator;
+
+// Call-by-name evaluation can take exponentially longer than call-by-need, so
+// we give up on programs that take too long, rather than failing.
+const STEP_LIMIT: usize = 100_000;
+
+#[test]
+fn test_evaluation_gets_the_same_result_as_reducing_evaluation() {
+    let krivine_evaluator =
+        boo_conformance::prepared(KrivineEvaluator::new().with_step_limit(STEP_LIMIT));
+    boo_conformance::check_against_reference(&krivine_evaluator);
+}
+
+#[test]
+fn test_applying_functions_gets_the_same_result_as_reducing_evaluation() {
+    let krivine_evaluator =
+        boo_conformance::prepared(KrivineEvaluator::new().with_step_limit(STEP_LIMIT));
+    boo_conformance::check_applications_against_reference(&krivine_evaluator);
+}
+
+#[test]
+fn test_failing_gets_the_same_error_as_reducing_evaluation() {
+    let krivine_evaluator =
+        boo_conformance::prepared(KrivineEvaluator::new().with_step_limit(STEP_LIMIT));
+    boo_conformance::check_failures_against_reference(&krivine_evaluator);
+}
+
+#[test]
+fn test_integer_overflow_fails_in_the_same_way_as_reducing_evaluation() {
+    let krivine_evaluator = boo_conformance::prepared(
+        KrivineEvaluator::new()
+            .with_step_limit(STEP_LIMIT)
+            .with_integer_overflow(IntegerOverflow::Fail),
+    );
+    boo_conformance::check_integer_overflow_fails(&krivine_evaluator);
+}
+
+#[test]
+fn test_natives_can_apply_functions() {
+    let krivine_evaluator =
+        boo_conformance::prepared(KrivineEvaluator::new().with_step_limit(STEP_LIMIT));
+    boo_conformance::check_natives_can_apply_functions(&krivine_evaluator);
+}
+
+#[test]
+fn test_lists_bound_identifiers() {
+    boo_conformance::check_bound_identifiers_are_listed(KrivineEvaluator::new());
+}
+
+#[test]
+fn test_evaluates_deeply_nested_expressions() {
+    boo_conformance::check_deeply_nested_expressions_in_a_small_stack(KrivineEvaluator::new);
+}
+
+#[test]
+fn test_natives_evaluate_their_arguments_in_a_small_stack() {
+    check_in_a_small_stack(
+        "let rec f = fn n -> match n { 0 -> 0; _ -> f (n - 1) } in f 200",
+        Primitive::Integer(0.into()),
+    );
+    check_in_a_small_stack(
+        "fold (fn a -> fn b -> a + b) 0 (range 0 40)",
+        Primitive::Integer(780.into()),
+    );
+}
+
+#[test]
+fn test_stops_after_the_step_limit() {
+    let evaluator = {
+        let mut context = KrivineEvaluator::new().with_step_limit(1_000);
+        builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+        context.evaluator()
+    };
+    let expr = boo_parser::parse_expr("let rec loop = fn x -> loop x in loop 1")
+        .unwrap()
+        .to_core()
+        .unwrap();
+
+    let result = evaluator.evaluate(expr);
+
+    assert_eq!(
+        result.map(|_| ()),
+        Err(Error::StepLimitExceeded { limit: 1_000 })
+    );
+}
+
+/// Evaluates the program on a thread with a stack far too small to hold one
+/// frame per binding that is evaluated.
+fn check_in_a_small_stack(program: &'static str, expected: Primitive) {
+    let result = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || {
+            let evaluator = boo_conformance::prepared(KrivineEvaluator::new());
+            evaluator.evaluate(boo_parser::parse_expr(program)?.to_core()?)
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(result, Ok(Evaluated::Primitive(expected)));
+}
@@ -1,8 +1,10 @@
 //! Core data structures used by the evaluator.
 
+use std::sync::Arc;
+
 use im::HashMap;
 
-use boo_core::error::Result;
+use boo_core::error::{Error, Result};
 use boo_core::identifier::Identifier;
 
 use crate::completed::CompletedEvaluation;
@@ -18,14 +20,29 @@ pub type Binding<Expr> = Thunk<UnevaluatedBinding<Expr>, EvaluatedBinding<Expr>>
 /// the underlying expression. This expression is evaluated lazily, but only
 /// once, using [`Thunk`].
 #[derive(Debug, Clone)]
-pub struct Bindings<Expr: Clone>(HashMap<Identifier, Binding<Expr>>);
+pub struct Bindings<Expr: Clone + 'static>(HashMap<Identifier, Binding<Expr>>);
 
-impl<Expr: Clone> Bindings<Expr> {
+impl<Expr: Clone + 'static> Bindings<Expr> {
     /// Constructs an empty set of bindings.
     pub fn new() -> Self {
         Self(HashMap::new())
     }
 
+    /// Looks up a binding without evaluating it.
+    pub fn get(&self, identifier: &Identifier) -> Option<&Binding<Expr>> {
+        self.0.get(identifier)
+    }
+
+    /// Lists the identifiers in scope, in no particular order.
+    pub fn identifiers(&self) -> impl Iterator<Item = &Identifier> {
+        self.0.keys()
+    }
+
+    /// Lists each binding in scope, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &Binding<Expr>)> {
+        self.0.iter()
+    }
+
     pub fn read(
         &mut self,
         identifier: &Identifier,
@@ -45,10 +62,100 @@ impl<Expr: Clone> Bindings<Expr> {
             Thunk::unresolved((expression, expression_bindings)),
         ))
     }
+
+    /// Adds a group of mutually-recursive bindings to the set.
+    ///
+    /// Each expression is evaluated in the context of the resulting set, so
+    /// that it can refer to any binding in the group, including its own. Note
+    /// that this creates a reference cycle, and so the bindings in the group
+    /// are never freed.
+    pub fn with_recursive(&self, group: impl IntoIterator<Item = (Identifier, Expr)>) -> Self {
+        self.with_recursive_using(group, Thunk::unresolved)
+    }
+
+    /// Adds a group of mutually-recursive bindings to the set, as with
+    /// [`Bindings::with_recursive`], constructing each binding with the given
+    /// function.
+    pub fn with_recursive_using(
+        &self,
+        group: impl IntoIterator<Item = (Identifier, Expr)>,
+        mut new_binding: impl FnMut(UnevaluatedBinding<Expr>) -> Binding<Expr>,
+    ) -> Self {
+        let group_bindings = group
+            .into_iter()
+            .map(|(identifier, expression)| (identifier, new_binding((expression, Self::new()))))
+            .collect::<Vec<(Identifier, Binding<Expr>)>>();
+        let result = group_bindings
+            .iter()
+            .fold(self.clone(), |bindings, (identifier, binding)| {
+                bindings.with_binding(identifier.clone(), binding.clone())
+            });
+        for (_, binding) in group_bindings {
+            binding.modify_unresolved(|(_, expression_bindings)| {
+                *expression_bindings = result.clone();
+            });
+        }
+        result
+    }
+
+    /// Adds an existing binding to the set, sharing its evaluation.
+    pub fn with_binding(&self, identifier: Identifier, binding: Binding<Expr>) -> Self {
+        Self(self.0.update(identifier, binding))
+    }
+
+    /// Adds existing bindings to the set, such as the variables bound by a
+    /// pattern, sharing their evaluation.
+    pub fn with_bindings(
+        &self,
+        bindings: impl IntoIterator<Item = (Identifier, Binding<Expr>)>,
+    ) -> Self {
+        bindings
+            .into_iter()
+            .fold(self.clone(), |result, (identifier, binding)| {
+                result.with_binding(identifier, binding)
+            })
+    }
 }
 
-impl<Expr: Clone> Default for Bindings<Expr> {
+impl<Expr: Clone + 'static> Default for Bindings<Expr> {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// Evaluates a binding with the given function, unless it has been evaluated
+/// already.
+///
+/// Evaluators evaluate bindings as part of their own work wherever they can.
+/// This is for when a value is needed immediately, e.g. by a native.
+pub fn force<Expr: Clone + 'static>(
+    binding: &Binding<Expr>,
+    evaluate: impl FnOnce(UnevaluatedBinding<Expr>) -> EvaluatedBinding<Expr>,
+) -> EvaluatedBinding<Expr> {
+    match binding.unresolved_value() {
+        Some(unevaluated) => store(binding, evaluate(unevaluated)),
+        None => resolved(binding.value().expect("Binding was not resolved.")),
+    }
+}
+
+/// Stores the result of evaluating a binding, so that it is only evaluated
+/// once, and returns it.
+///
+/// Running out of memory or being interrupted says nothing about the value, so
+/// such errors are not stored.
+pub fn store<Expr: Clone + 'static>(
+    binding: &Binding<Expr>,
+    result: EvaluatedBinding<Expr>,
+) -> EvaluatedBinding<Expr> {
+    match result {
+        Err(Error::OutOfMemory { .. } | Error::Interrupted) => result,
+        result => resolved(binding.resolve_with(result)),
+    }
+}
+
+/// Takes the result out of a resolved binding, copying it if it is shared.
+pub fn resolved<Expr: Clone + 'static>(
+    result: Arc<EvaluatedBinding<Expr>>,
+) -> EvaluatedBinding<Expr> {
+    Arc::try_unwrap(result).unwrap_or_else(|arc| (*arc).clone())
+}
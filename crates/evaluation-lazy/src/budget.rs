@@ -0,0 +1,152 @@
+//! Tracks the approximate amount of memory used by an evaluation.
+
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use boo_core::error::{Error, Result};
+use boo_core::identifier::Identifier;
+
+use crate::bindings::*;
+use crate::thunk::ThunkCell;
+
+/// A limit on the memory used by an evaluation.
+///
+/// Memory is counted when it is allocated, and released when the allocation is
+/// dropped, so the budget approximates the memory that is currently in use,
+/// rather than the total allocated over time. Only the larger structures
+/// (thunks, binding maps, and the like) are counted.
+///
+/// Copies of a budget share the same usage.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBudget(Option<Arc<Usage>>);
+
+#[derive(Debug)]
+struct Usage {
+    limit: usize,
+    used: AtomicUsize,
+}
+
+impl MemoryBudget {
+    /// Constructs a budget with no limit, which does not count anything.
+    pub fn unlimited() -> Self {
+        Self(None)
+    }
+
+    /// Constructs a budget which is exceeded once more than `limit` bytes are
+    /// in use.
+    pub fn limited(limit: usize) -> Self {
+        Self(Some(Arc::new(Usage {
+            limit,
+            used: AtomicUsize::new(0),
+        })))
+    }
+
+    /// Counts the given number of bytes against the budget, until the
+    /// allocation is dropped.
+    ///
+    /// This does not fail when the budget is exceeded; use
+    /// [`MemoryBudget::check`] for that.
+    pub fn allocate(&self, size: usize) -> Allocation {
+        let mut allocation = Allocation {
+            usage: self.0.clone(),
+            size: 0,
+        };
+        allocation.resize(size);
+        allocation
+    }
+
+    /// Constructs an unresolved binding, which is counted against the budget
+    /// for as long as it is alive.
+    pub fn binding<Expr: Clone + 'static>(&self, value: UnevaluatedBinding<Expr>) -> Binding<Expr> {
+        let binding = Binding::unresolved(value);
+        match self.0 {
+            None => binding,
+            Some(_) => binding.with_allocation(self.allocate(binding_size::<Expr>())),
+        }
+    }
+
+    /// Fails if more memory is in use than the budget allows.
+    pub fn check(&self) -> Result<()> {
+        match &self.0 {
+            Some(usage) if usage.used.load(Ordering::Relaxed) > usage.limit => {
+                Err(Error::OutOfMemory { limit: usage.limit })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Memory counted against a budget, which is released when dropped.
+#[derive(Debug)]
+pub struct Allocation {
+    usage: Option<Arc<Usage>>,
+    size: usize,
+}
+
+impl Allocation {
+    /// Changes the number of bytes counted by this allocation, e.g. when a
+    /// stack grows or shrinks.
+    pub fn resize(&mut self, size: usize) {
+        if let Some(usage) = &self.usage {
+            if size > self.size {
+                usage.used.fetch_add(size - self.size, Ordering::Relaxed);
+            } else {
+                usage.used.fetch_sub(self.size - size, Ordering::Relaxed);
+            }
+        }
+        self.size = size;
+    }
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        self.resize(0);
+    }
+}
+
+/// Adding an entry to a binding map copies a node of the underlying trie,
+/// which holds up to this many entries.
+const MAP_NODE_WIDTH: usize = 32;
+
+/// The approximate size of a binding: the thunk, its eventual value, and the
+/// binding map node copied to hold it.
+fn binding_size<Expr: Clone + 'static>() -> usize {
+    size_of::<ThunkCell<UnevaluatedBinding<Expr>, EvaluatedBinding<Expr>>>()
+        + size_of::<EvaluatedBinding<Expr>>()
+        + size_of::<(Identifier, Binding<Expr>)>() * MAP_NODE_WIDTH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_allocations_until_they_are_dropped() {
+        let budget = MemoryBudget::limited(100);
+        let first = budget.allocate(60);
+        assert_eq!(budget.check(), Ok(()));
+        let second = budget.allocate(60);
+        assert_eq!(budget.check(), Err(Error::OutOfMemory { limit: 100 }));
+        drop(first);
+        assert_eq!(budget.check(), Ok(()));
+        drop(second);
+    }
+
+    #[test]
+    fn test_resizes_allocations() {
+        let budget = MemoryBudget::limited(100);
+        let mut allocation = budget.allocate(0);
+        allocation.resize(200);
+        assert_eq!(budget.check(), Err(Error::OutOfMemory { limit: 100 }));
+        allocation.resize(50);
+        assert_eq!(budget.check(), Ok(()));
+    }
+
+    #[test]
+    fn test_never_exceeds_an_unlimited_budget() {
+        let budget = MemoryBudget::unlimited();
+        let _allocation = budget.allocate(usize::MAX);
+        assert_eq!(budget.check(), Ok(()));
+    }
+}
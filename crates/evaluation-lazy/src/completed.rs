@@ -1,33 +1,86 @@
 //! Represents the result of evaluating an expression.
 
+use boo_core::error::Error;
+use boo_core::error::Result;
 use boo_core::evaluation::Evaluated;
-use boo_core::expr::Function;
+use boo_core::expr::{Function, List};
 use boo_core::identifier::Identifier;
 use boo_core::primitive::Primitive;
+use boo_core::types::Monotype;
 
-use crate::bindings::Bindings;
+use crate::bindings::{Binding, Bindings, EvaluatedBinding, UnevaluatedBinding};
 
 /// An interim evaluation result.
 #[derive(Debug, Clone)]
-pub enum CompletedEvaluation<Expr: Clone> {
+pub enum CompletedEvaluation<Expr: Clone + 'static> {
     Primitive(Primitive),
     Closure {
         parameter: Identifier,
+        parameter_type: Option<Monotype>,
         body: Expr,
         bindings: Bindings<Expr>,
     },
+    Tuple(Vec<Binding<Expr>>),
+    List(List<Binding<Expr>>),
 }
 
-impl<Expr: Clone> CompletedEvaluation<Expr> {
+impl<Expr: Clone + 'static> CompletedEvaluation<Expr> {
+    /// Applies a closure to an argument which has already been evaluated, as
+    /// natives do, producing the body along with the bindings in which to
+    /// evaluate it.
+    pub fn apply_to(self, argument: Primitive) -> Result<UnevaluatedBinding<Expr>> {
+        match self {
+            Self::Closure {
+                parameter,
+                body,
+                bindings,
+                ..
+            } => {
+                let argument = Binding::resolved(Ok(Self::Primitive(argument)));
+                Ok((body, bindings.with_binding(parameter, argument)))
+            }
+            _ => Err(Error::InvalidFunctionApplication { span: None }),
+        }
+    }
+
     /// Concludes evaluation.
-    pub fn finish(self) -> Evaluated<Expr> {
+    ///
+    /// As the elements of a tuple or list are evaluated lazily, they are
+    /// resolved here using the provided function.
+    pub fn finish(
+        self,
+        resolve: &impl Fn(&mut Binding<Expr>) -> EvaluatedBinding<Expr>,
+    ) -> Result<Evaluated<Expr>> {
         match self {
-            Self::Primitive(primitive) => Evaluated::Primitive(primitive),
+            Self::Primitive(primitive) => Ok(Evaluated::Primitive(primitive)),
             Self::Closure {
                 parameter,
+                parameter_type,
                 body,
                 bindings: _,
-            } => Evaluated::Function(Function { parameter, body }),
+            } => Ok(Evaluated::Function(Function {
+                parameter,
+                parameter_type,
+                parameter_type_span: None,
+                body,
+            })),
+            Self::Tuple(elements) => elements
+                .into_iter()
+                .map(|mut element| resolve(&mut element)?.finish(resolve))
+                .collect::<Result<_>>()
+                .map(Evaluated::Tuple),
+            Self::List(list) => {
+                let mut elements = vec![];
+                let mut next = list;
+                while let List::Cons { mut head, mut tail } = next {
+                    elements.push(resolve(&mut head)?.finish(resolve)?);
+                    next = match resolve(&mut tail)? {
+                        Self::List(list) => list,
+                        _ => return Err(Error::InvalidPrimitive { span: None }),
+                    };
+                }
+                Ok(Evaluated::List(elements))
+            }
         }
     }
 }
@@ -1,7 +1,13 @@
 pub mod bindings;
+pub mod budget;
 pub mod completed;
+pub mod observer;
+pub mod selection;
 pub mod thunk;
 
 pub use bindings::*;
+pub use budget::*;
 pub use completed::*;
+pub use observer::*;
+pub use selection::*;
 pub use thunk::*;
@@ -0,0 +1,16 @@
+//! Allows evaluation to be observed, one step at a time.
+
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use crate::bindings::Bindings;
+
+/// Called each time an evaluator enters an expression, with the bindings in
+/// scope, and whether the value of the expression will be the result of the
+/// whole evaluation.
+///
+/// Evaluation is paused while the observer runs. If it returns
+/// [`ControlFlow::Break`], evaluation stops with
+/// [`Error::Interrupted`][boo_core::error::Error::Interrupted].
+pub type Observer<Expr> =
+    Arc<dyn Fn(&Expr, &Bindings<Expr>, bool) -> ControlFlow<()> + Send + Sync>;
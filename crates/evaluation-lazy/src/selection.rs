@@ -0,0 +1,47 @@
+//! Matches lazily-evaluated values against patterns.
+
+use boo_core::ast::List;
+use boo_core::error::Result;
+use boo_core::matching::{Selected, Selection, Shape};
+
+use crate::bindings::{resolved, Binding};
+use crate::completed::CompletedEvaluation;
+
+impl<Expr: Clone + 'static> CompletedEvaluation<Expr> {
+    /// The outermost constructor of the value, as far as matching is concerned.
+    pub fn shape(self) -> Shape<Binding<Expr>> {
+        match self {
+            Self::Primitive(primitive) => Shape::Primitive(primitive),
+            Self::Closure { .. } => Shape::Function,
+            Self::Tuple(elements) => Shape::Tuple(elements),
+            Self::List(List::Empty) => Shape::List(List::Empty),
+            Self::List(List::Cons { head, tail }) => Shape::List(List::Cons { head, tail }),
+        }
+    }
+}
+
+/// Continues a selection, given the value it asked to be evaluated, if any.
+///
+/// Bindings which have already been evaluated are used as they are, so this
+/// only asks for a binding to be evaluated if it has not been yet. The
+/// evaluator is expected to evaluate it, store the result in the binding, and
+/// pass the result back here.
+pub fn select<Expr: Clone + 'static, Arm>(
+    selection: &mut Selection<Binding<Expr>, Arm>,
+    evaluated: Option<CompletedEvaluation<Expr>>,
+) -> Result<Selected<Binding<Expr>, Arm>> {
+    let mut selected = match evaluated {
+        None => selection.advance(),
+        Some(value) => selection.resume(value.shape()),
+    };
+    while let Selected::Evaluate(binding) = &selected {
+        match binding.value() {
+            Some(result) => {
+                let value = resolved(result)?;
+                selected = selection.resume(value.shape());
+            }
+            None => break,
+        }
+    }
+    Ok(selected)
+}
@@ -1,14 +1,33 @@
 //! Provides infrastructure for thread-safe thunks.
 
+use std::any::Any;
+use std::cell::RefCell;
 use std::sync::{Arc, RwLock};
 
+use crate::budget::Allocation;
+
 /// A thunk is a value that is left as unresolved until needed, and then
 /// resolved a single time when required. Subsequent accesses will get the same
 /// value that was resolved the first time.
 ///
 /// Thunks are thread-safe.
+///
+/// A thunk may also hold an allocation from a
+/// [`MemoryBudget`][crate::budget::MemoryBudget], which is released once every
+/// copy of the thunk has been dropped.
+///
+/// Thunks often refer to other thunks, sometimes in very long chains, e.g. when
+/// a value is built up over many iterations without being evaluated. To avoid
+/// overflowing the stack, they are not dropped recursively; see [`Drop`].
 #[derive(Debug, Clone)]
-pub struct Thunk<Unresolved, Resolved>(Arc<RwLock<ThunkValue<Unresolved, Arc<Resolved>>>>);
+pub struct Thunk<Unresolved: 'static, Resolved: 'static>(
+    /// This is only `None` once the thunk has been dropped.
+    Option<Arc<ThunkCell<Unresolved, Resolved>>>,
+    Option<Arc<Allocation>>,
+);
+
+/// The shared, mutable contents of a thunk.
+pub(crate) type ThunkCell<Unresolved, Resolved> = RwLock<ThunkValue<Unresolved, Arc<Resolved>>>;
 
 /// A thunk can be either unresolved or resolved.
 #[derive(Debug)]
@@ -17,16 +36,39 @@ pub enum ThunkValue<Unresolved, Resolved> {
     Resolved(Resolved),
 }
 
-impl<Unresolved, Resolved> From<Unresolved> for Thunk<Unresolved, Resolved> {
+impl<Unresolved: 'static, Resolved: 'static> From<Unresolved> for Thunk<Unresolved, Resolved> {
     fn from(value: Unresolved) -> Self {
         Self::unresolved(value)
     }
 }
 
-impl<Unresolved, Resolved> Thunk<Unresolved, Resolved> {
+impl<Unresolved: 'static, Resolved: 'static> Thunk<Unresolved, Resolved> {
     /// Constructs a new unresolved thunk.
     pub fn unresolved(value: Unresolved) -> Self {
-        Self(Arc::new(RwLock::new(ThunkValue::Unresolved(value))))
+        Self(
+            Some(Arc::new(RwLock::new(ThunkValue::Unresolved(value)))),
+            None,
+        )
+    }
+
+    /// Constructs a new thunk which has already been resolved.
+    pub fn resolved(value: Resolved) -> Self {
+        Self(
+            Some(Arc::new(RwLock::new(ThunkValue::Resolved(Arc::new(value))))),
+            None,
+        )
+    }
+
+    /// Identifies the thunk, so that copies of the same thunk can be
+    /// recognized.
+    pub fn as_ptr(&self) -> *const () {
+        self.0.as_ref().map_or(std::ptr::null(), Arc::as_ptr).cast()
+    }
+
+    /// Attaches an allocation to the thunk, which lives as long as the thunk.
+    pub fn with_allocation(mut self, allocation: Allocation) -> Self {
+        self.1 = Some(Arc::new(allocation));
+        self
     }
 
     /// Resolves a thunk by computing something over the unresolved value.
@@ -43,7 +85,7 @@ impl<Unresolved, Resolved> Thunk<Unresolved, Resolved> {
                 }
             }
 
-            match (*self.0).write() {
+            match self.cell().write() {
                 Ok(mut inner) => match *inner {
                     ThunkValue::Unresolved(ref mut input) => {
                         let value = Arc::new(compute(input));
@@ -57,10 +99,62 @@ impl<Unresolved, Resolved> Thunk<Unresolved, Resolved> {
         }
     }
 
+    /// Resolves a thunk with a value computed elsewhere.
+    ///
+    /// This allows the caller to compute the value without holding a lock on
+    /// the thunk. If the thunk was resolved in the meantime, the existing value
+    /// is kept.
+    pub fn resolve_with(&self, value: Resolved) -> Arc<Resolved> {
+        match self.cell().write() {
+            Ok(mut inner) => match *inner {
+                ThunkValue::Unresolved(_) => {
+                    let value = Arc::new(value);
+                    *inner = ThunkValue::Resolved(Arc::clone(&value));
+                    value
+                }
+                ThunkValue::Resolved(ref value) => Arc::clone(value),
+            },
+            Err(err) => panic!("Poisoned mutex in thunk: {}", err),
+        }
+    }
+
+    /// Returns a copy of the unresolved value, or `None` if the thunk has
+    /// already been resolved.
+    pub fn unresolved_value(&self) -> Option<Unresolved>
+    where
+        Unresolved: Clone,
+    {
+        match self.cell().read() {
+            Ok(inner) => match *inner {
+                ThunkValue::Unresolved(ref value) => Some(value.clone()),
+                ThunkValue::Resolved(_) => None,
+            },
+            Err(err) => panic!("Poisoned mutex in thunk: {}", err),
+        }
+    }
+
+    /// Modifies the unresolved value, if the thunk has not yet been resolved.
+    ///
+    /// This affects all copies of the thunk.
+    pub fn modify_unresolved(&self, modify: impl FnOnce(&mut Unresolved)) {
+        match self.cell().write() {
+            Ok(mut inner) => {
+                if let ThunkValue::Unresolved(ref mut input) = *inner {
+                    modify(input);
+                }
+            }
+            Err(err) => panic!("Poisoned mutex in thunk: {}", err),
+        }
+    }
+
+    fn cell(&self) -> &ThunkCell<Unresolved, Resolved> {
+        self.0.as_ref().expect("Thunk was dropped.")
+    }
+
     /// Returns the resolve value if it has already been computed, or `None`
     /// otherwise.
     pub fn value(&self) -> Option<Arc<Resolved>> {
-        match (*self.0).read() {
+        match self.cell().read() {
             Ok(inner) => match *inner {
                 ThunkValue::Unresolved(_) => None,
                 ThunkValue::Resolved(ref value) => Some(Arc::clone(value)),
@@ -70,6 +164,58 @@ impl<Unresolved, Resolved> Thunk<Unresolved, Resolved> {
     }
 }
 
+impl<Unresolved: 'static, Resolved: 'static> Drop for Thunk<Unresolved, Resolved> {
+    /// Drops the value if this is the last copy of the thunk.
+    ///
+    /// If another thunk is already being dropped, the value is set aside, and
+    /// dropped afterwards, so that the depth of the stack does not depend on
+    /// the length of a chain of thunks.
+    fn drop(&mut self) {
+        if let Some(cell) = self.0.take() {
+            if Arc::strong_count(&cell) == 1 {
+                drop_iteratively(Box::new(cell));
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// The values waiting to be dropped, or `None` if no thunk is being dropped
+    /// on this thread.
+    static PENDING_DROPS: RefCell<Option<Vec<Box<dyn Any>>>> = const { RefCell::new(None) };
+}
+
+/// Drops a value, or, if another value is already being dropped this way on
+/// this thread, sets it aside to be dropped afterwards.
+///
+/// Values which refer to other values of the same kind can form very long
+/// chains. Dropping each of them this way means that dropping the chain does
+/// not recurse once per link.
+pub fn drop_iteratively(value: Box<dyn Any>) {
+    let outermost = PENDING_DROPS.with(|pending| {
+        let mut pending = pending.borrow_mut();
+        match pending.as_mut() {
+            Some(values) => {
+                values.push(value);
+                None
+            }
+            None => {
+                *pending = Some(vec![]);
+                Some(value)
+            }
+        }
+    });
+    if let Some(value) = outermost {
+        drop(value);
+        while let Some(next) =
+            PENDING_DROPS.with(|pending| pending.borrow_mut().as_mut().and_then(Vec::pop))
+        {
+            drop(next);
+        }
+        PENDING_DROPS.with(|pending| *pending.borrow_mut() = None);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::atomic::{AtomicU32, Ordering};
@@ -98,6 +244,27 @@ mod tests {
         assert_eq!(thunk.value(), Some(5.into()));
     }
 
+    #[test]
+    fn test_resolve_a_thunk_with_a_value() {
+        let thunk = Thunk::<i32, i32>::unresolved(3);
+        assert_eq!(thunk.unresolved_value(), Some(3));
+        thunk.resolve_with(4);
+        thunk.resolve_with(5);
+        assert_eq!(thunk.unresolved_value(), None);
+        assert_eq!(thunk.value(), Some(4.into()));
+    }
+
+    #[test]
+    fn test_long_chains_of_thunks_can_be_dropped() {
+        struct Link(Option<Thunk<Link, ()>>);
+
+        let mut chain = Thunk::unresolved(Link(None));
+        for _ in 0..1_000_000 {
+            chain = Thunk::unresolved(Link(Some(chain)));
+        }
+        drop(chain);
+    }
+
     #[test]
     fn test_thunks_can_be_shared_across_threads() {
         let thunk = Thunk::<_, i32>::unresolved((7, 6));
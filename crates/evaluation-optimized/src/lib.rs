@@ -1,17 +1,44 @@
-use boo_core::evaluation::EvaluationContext;
-use boo_evaluation_lazy::Bindings;
+use boo_core::evaluation::IntegerOverflow;
+use boo_evaluation_lazy::{Bindings, MemoryBudget, Observer};
 use boo_evaluation_pooling::{ast, NewInnerEvaluator, PoolingEvaluationContext};
 
-pub fn new() -> impl EvaluationContext {
+pub use boo_evaluation_pooling::{
+    Breakpoint, Profile, ProfileEntry, Provenance, Resumed, Suspended,
+};
+
+/// Constructs an evaluation context.
+///
+/// The resulting evaluator is [`Send`] and [`Sync`], so evaluation can happen
+/// on any thread, and one evaluator can be shared between several.
+pub fn new() -> PoolingEvaluationContext<NewRecursiveEvaluator> {
     PoolingEvaluationContext::<NewRecursiveEvaluator>::new()
 }
 
+/// Constructs an evaluation context which fails with
+/// [`OutOfMemory`][boo_core::error::Error::OutOfMemory] if an evaluation uses
+/// more than approximately `limit` bytes.
+pub fn with_memory_limit(limit: usize) -> PoolingEvaluationContext<NewRecursiveEvaluator> {
+    PoolingEvaluationContext::<NewRecursiveEvaluator>::new().with_memory_limit(limit)
+}
+
 pub struct NewRecursiveEvaluator {}
 
 impl<'pool> NewInnerEvaluator<'pool> for NewRecursiveEvaluator {
     type Inner = boo_evaluation_recursive::RecursiveEvaluator<ast::Expr, &'pool ast::ExprPool>;
 
-    fn new(pool: &'pool ast::ExprPool, bindings: Bindings<ast::Expr>) -> Self::Inner {
-        boo_evaluation_recursive::RecursiveEvaluator::new(pool, bindings)
+    fn new(
+        pool: &'pool ast::ExprPool,
+        bindings: Bindings<ast::Expr>,
+        budget: MemoryBudget,
+        observer: Option<Observer<ast::Expr>>,
+        integer_overflow: IntegerOverflow,
+    ) -> Self::Inner {
+        let evaluator = boo_evaluation_recursive::RecursiveEvaluator::new(pool, bindings)
+            .with_memory_budget(budget)
+            .with_integer_overflow(integer_overflow);
+        match observer {
+            Some(observer) => evaluator.with_observer(observer),
+            None => evaluator,
+        }
     }
 }
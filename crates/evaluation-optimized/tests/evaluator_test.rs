@@ -1,46 +1,574 @@
-use proptest::prelude::*;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
 
 use boo_core::builtins;
+use boo_core::error::Error;
 use boo_core::evaluation::*;
-use boo_test_helpers::proptest::*;
+use boo_core::identifier::Identifier;
+use boo_core::optimization::Optimizations;
+use boo_core::primitive::Primitive;
+use boo_core::span::Span;
+use boo_evaluation_optimized::{Breakpoint, Resumed};
 
 #[test]
 fn test_evaluation_gets_the_same_result_as_reducing_evaluation() {
-    let reducing_evaluator = {
-        let mut context = boo_evaluation_reduction::new();
-        builtins::prepare(&mut context).unwrap();
+    let optimized_evaluator = boo_conformance::prepared(boo_evaluation_optimized::new());
+    boo_conformance::check_against_reference(&optimized_evaluator);
+}
+
+#[test]
+fn test_applying_functions_gets_the_same_result_as_reducing_evaluation() {
+    let optimized_evaluator = boo_conformance::prepared(boo_evaluation_optimized::new());
+    boo_conformance::check_applications_against_reference(&optimized_evaluator);
+}
+
+#[test]
+fn test_failing_gets_the_same_error_as_reducing_evaluation() {
+    let optimized_evaluator = boo_conformance::prepared(boo_evaluation_optimized::new());
+    boo_conformance::check_failures_against_reference(&optimized_evaluator);
+}
+
+#[test]
+fn test_integer_overflow_fails_in_the_same_way_as_reducing_evaluation() {
+    let optimized_evaluator = boo_conformance::prepared(
+        boo_evaluation_optimized::new().with_integer_overflow(IntegerOverflow::Fail),
+    );
+    boo_conformance::check_integer_overflow_fails(&optimized_evaluator);
+}
+
+#[test]
+fn test_natives_can_apply_functions() {
+    let optimized_evaluator = boo_conformance::prepared(boo_evaluation_optimized::new());
+    boo_conformance::check_natives_can_apply_functions(&optimized_evaluator);
+}
+
+#[test]
+fn test_lists_bound_identifiers() {
+    boo_conformance::check_bound_identifiers_are_listed(boo_evaluation_optimized::new());
+}
+
+#[test]
+fn test_evaluates_deeply_nested_expressions() {
+    boo_conformance::check_deeply_nested_expressions_in_a_small_stack(
+        boo_evaluation_optimized::new,
+    );
+}
+
+#[test]
+fn test_evaluates_deeply_nested_matches() {
+    boo_conformance::check_deeply_nested_matches_in_a_small_stack(boo_evaluation_optimized::new);
+}
+
+#[test]
+fn test_tail_calls_run_in_constant_stack_space() {
+    check_in_a_small_stack(
+        "let rec loop = fn n -> match n { 0 -> 0; _ -> loop (n - 1) } in loop 10000",
+        Primitive::Integer(0.into()),
+    );
+}
+
+#[test]
+fn test_mutually_recursive_tail_calls_run_in_constant_stack_space() {
+    check_in_a_small_stack(
+        "let rec is_even = fn n -> match n { 0 -> true; _ -> is_odd (n - 1) } and is_odd = fn n -> match n { 0 -> false; _ -> is_even (n - 1) } in is_even 10000",
+        Primitive::Boolean(true),
+    );
+}
+
+#[test]
+fn test_tail_calls_can_pass_values_along() {
+    check_in_a_small_stack(
+        "let rec loop = fn n -> fn result -> match n { 0 -> result; _ -> loop (n - 1) result } in loop 10000 7",
+        Primitive::Integer(7.into()),
+    );
+}
+
+#[test]
+fn test_stops_when_a_program_leaks_memory() {
+    // each call adds one to a value that is never evaluated
+    let result = evaluate_with_a_memory_limit("let rec loop = fn n -> loop (n + 1) in loop 0");
+
+    assert_eq!(
+        result,
+        Err(Error::OutOfMemory {
+            limit: MEMORY_LIMIT
+        })
+    );
+}
+
+#[test]
+fn test_long_running_programs_in_constant_space_do_not_run_out_of_memory() {
+    let result = evaluate_with_a_memory_limit(
+        "let rec loop = fn n -> match n { 0 -> 0; _ -> loop (n - 1) } in loop 100_000",
+    );
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(0.into())))
+    );
+}
+
+#[test]
+fn test_stops_after_the_step_limit() -> boo_core::error::Result<()> {
+    let evaluator = {
+        let mut context = boo_evaluation_optimized::new().with_step_limit(1_000);
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
         context.evaluator()
     };
-    let optimized_evaluator = {
+    let finite = boo_parser::parse_expr("let x = 1 + 2 in x * x")?.to_core()?;
+    let infinite = boo_parser::parse_expr("let rec loop = fn x -> loop x in loop 1")?.to_core()?;
+
+    // each evaluation has its own limit
+    assert_eq!(
+        evaluator.evaluate(finite.clone()),
+        Ok(Evaluated::Primitive(Primitive::Integer(9.into())))
+    );
+    assert_eq!(
+        evaluator.evaluate(infinite).map(|_| ()),
+        Err(Error::StepLimitExceeded { limit: 1_000 })
+    );
+    assert_eq!(
+        evaluator.evaluate(finite),
+        Ok(Evaluated::Primitive(Primitive::Integer(9.into())))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_evaluation_without_optimizations_gets_the_same_result_as_reducing_evaluation() {
+    let unoptimized_evaluator = boo_conformance::prepared(
+        boo_evaluation_optimized::new().with_optimizations(Optimizations::NONE),
+    );
+    boo_conformance::check_against_reference(&unoptimized_evaluator);
+}
+
+#[test]
+fn test_evaluators_can_be_shared_between_threads() -> boo_core::error::Result<()> {
+    let evaluator = {
         let mut context = boo_evaluation_optimized::new();
-        builtins::prepare(&mut context).unwrap();
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+        // each thread reads the same binding
+        context.bind(
+            Identifier::name_from_str("base").unwrap(),
+            boo_parser::parse_expr("1 + 2")?.to_core()?,
+        )?;
         context.evaluator()
     };
+    let programs = (0..4)
+        .map(|n| boo_parser::parse_expr(&format!("base * {n}"))?.to_core())
+        .collect::<boo_core::error::Result<Vec<_>>>()?;
+
+    let results = std::thread::scope(|scope| {
+        let threads = programs
+            .into_iter()
+            .map(|program| scope.spawn(|| evaluator.evaluate(program)))
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect::<Vec<_>>()
+    });
 
-    check(&boo_generator::arbitrary(), |expr| {
-        let core_expr = expr.clone().to_core()?;
-        let expected = reducing_evaluator.evaluate(core_expr.clone());
-        let actual = optimized_evaluator.evaluate(core_expr);
+    assert_eq!(
+        results,
+        (0..4)
+            .map(|n| Ok(Evaluated::Primitive(Primitive::Integer((3 * n).into()))))
+            .collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_compaction_frees_shadowed_bindings() -> boo_core::error::Result<()> {
+    let mut context = boo_evaluation_optimized::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let name = Identifier::name_from_str("value").unwrap();
+    let bind_value = |context: &mut boo_evaluation_pooling::PoolingEvaluationContext<
+        boo_evaluation_optimized::NewRecursiveEvaluator,
+    >,
+                      n: i32| {
+        let expr = boo_parser::parse_expr(&format!("({n} + 1) * ({n} - 1)"))?.to_core()?;
+        context.bind(name.clone(), expr)
+    };
 
-        match (expected, actual) {
-            (Ok(Evaluated::Primitive(expected)), Ok(Evaluated::Primitive(actual))) => {
-                prop_assert_eq!(expected, actual);
+    bind_value(&mut context, 0)?;
+    context.compact();
+    let initial_size = context.pool_size();
+    for n in 1..=100 {
+        bind_value(&mut context, n)?;
+        context.compact();
+        assert_eq!(context.pool_size(), initial_size);
+    }
+
+    let evaluator = context.evaluator();
+    assert_eq!(
+        evaluator.evaluate(boo_parser::parse_expr("value / 3")?.to_core()?),
+        Ok(Evaluated::Primitive(Primitive::Integer(3333.into())))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_compaction_keeps_shadowed_bindings_which_are_still_referenced(
+) -> boo_core::error::Result<()> {
+    let mut context = boo_evaluation_optimized::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let counter = Identifier::name_from_str("counter").unwrap();
+    let total = Identifier::name_from_str("total").unwrap();
+
+    context.bind(counter.clone(), boo_parser::parse_expr("1")?.to_core()?)?;
+    context.bind(
+        total.clone(),
+        boo_parser::parse_expr("counter * 10")?.to_core()?,
+    )?;
+    for _ in 0..10 {
+        context.bind(
+            counter.clone(),
+            boo_parser::parse_expr("counter + 1")?.to_core()?,
+        )?;
+        context.compact();
+    }
+
+    let evaluator = context.evaluator();
+    assert_eq!(
+        evaluator.evaluate(boo_parser::parse_expr("(counter, total)")?.to_core()?),
+        Ok(Evaluated::Tuple(vec![
+            Evaluated::Primitive(Primitive::Integer(11.into())),
+            Evaluated::Primitive(Primitive::Integer(10.into())),
+        ]))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_stepwise_evaluation_observes_each_expression_as_it_is_entered() {
+    let steps = Arc::new(Mutex::new(Vec::new()));
+    let result = {
+        let steps = steps.clone();
+        evaluate_stepwise("let x = 1 in (fn y -> x) 2", move |step| {
+            steps.lock().unwrap().push(step.to_core().to_string());
+            ControlFlow::Continue(())
+        })
+    };
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(1.into())))
+    );
+    // the argument is never used, and so is never evaluated
+    assert_eq!(
+        steps.lock().unwrap().as_slice(),
+        &[
+            "let x = (1) in ((fn y -> (x)) (2))",
+            "(fn y -> (x)) (2)",
+            "fn y -> (x)",
+            "x",
+            "1",
+        ]
+    );
+}
+
+#[test]
+fn test_stepwise_evaluation_exposes_the_bindings_in_scope() {
+    let names = Arc::new(Mutex::new(None));
+    let result = {
+        let names = names.clone();
+        evaluate_stepwise("let x = 1 in let y = x + 1 in y", move |step| {
+            if let boo_core::ast::Expression::Identifier(_) = step.expression().value {
+                let mut identifiers = step
+                    .bindings()
+                    .identifiers()
+                    .map(|identifier| identifier.to_string())
+                    .filter(|name| name == "x" || name == "y")
+                    .collect::<Vec<_>>();
+                identifiers.sort();
+                names.lock().unwrap().get_or_insert(identifiers);
+            }
+            ControlFlow::Continue(())
+        })
+    };
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(2.into())))
+    );
+    assert_eq!(
+        names.lock().unwrap().clone(),
+        Some(vec!["x".to_string(), "y".to_string()])
+    );
+}
+
+#[test]
+fn test_stepwise_evaluation_can_be_interrupted() {
+    let mut remaining = 1000;
+    let result = evaluate_stepwise("let rec loop = fn n -> loop n in loop 0", move |_| {
+        remaining -= 1;
+        if remaining == 0 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    assert_eq!(result, Err(Error::Interrupted));
+}
+
+#[test]
+fn test_profiling_counts_how_often_each_expression_is_entered() -> boo_core::error::Result<()> {
+    let program = "let rec loop = fn n -> match n { 0 -> 0; _ -> loop (n - 1) } in loop 3";
+    let mut context = boo_evaluation_optimized::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let evaluator = context.evaluator();
+
+    let (result, profile) =
+        evaluator.evaluate_profiled(boo_parser::parse_expr(program)?.to_core()?);
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(0.into())))
+    );
+    let match_start = program.find("match").unwrap();
+    let match_span = Span::from(match_start..program.rfind('}').unwrap() + 1);
+    assert_eq!(
+        profile.get(Some(match_span)).map(|entry| entry.count),
+        Some(4)
+    );
+    assert_eq!(
+        profile
+            .get(Some(Span::from(0..program.len())))
+            .map(|entry| entry.count),
+        Some(1)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_resumable_evaluation_suspends_after_each_step() -> boo_core::error::Result<()> {
+    let program = "let f = fn x -> x * 2 in f 3 + f 4";
+    let steps = Arc::new(Mutex::new(0));
+    let expected = {
+        let steps = steps.clone();
+        evaluate_stepwise(program, move |_| {
+            *steps.lock().unwrap() += 1;
+            ControlFlow::Continue(())
+        })
+    };
+    let evaluator = {
+        let mut context = boo_evaluation_optimized::new();
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+        context.evaluator()
+    };
+
+    let mut suspensions = 0;
+    let mut resumed = evaluator.evaluate_resumably(
+        boo_parser::parse_expr(program)?.to_core()?,
+        Breakpoint::Steps(1),
+    );
+    let actual = loop {
+        match resumed {
+            Resumed::Finished(result) => break result,
+            Resumed::Suspended(suspended) => {
+                suspensions += 1;
+                resumed = suspended.resume(Breakpoint::Steps(1));
             }
-            (Ok(expected), Ok(actual)) => prop_assert!(
-                false,
-                "did not finish evaluation\n  left:   `{}`,\n  right:  `{}`\n  input:  {}\n",
-                expected,
-                actual,
-                expr
-            ),
-            (expected, actual) => prop_assert!(
-                false,
-                "evaluation failed\n  left:   `{:?}`,\n  right:  `{:?}`\n  input:  {}\n",
-                expected,
-                actual,
-                expr
-            ),
         }
-        Ok(())
-    })
+    };
+
+    assert_eq!(actual, expected);
+    assert_eq!(suspensions, *steps.lock().unwrap());
+    Ok(())
+}
+
+#[test]
+fn test_resumable_evaluation_suspends_at_breakpoints() -> boo_core::error::Result<()> {
+    let program = "let f = fn x -> x * 2 in f 3 + f 4";
+    let body_start = program.find("x *").unwrap();
+    let body = Span::from(body_start..body_start + 5);
+    let evaluator = {
+        let mut context = boo_evaluation_optimized::new();
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+        context.evaluator()
+    };
+    let breakpoint = || Breakpoint::at(move |span| span == Some(body));
+
+    let mut suspended_at = Vec::new();
+    let mut resumed =
+        evaluator.evaluate_resumably(boo_parser::parse_expr(program)?.to_core()?, breakpoint());
+    let result = loop {
+        match resumed {
+            Resumed::Finished(result) => break result,
+            Resumed::Suspended(suspended) => {
+                suspended_at.push((suspended.span(), suspended.expression().to_string()));
+                resumed = suspended.resume(breakpoint());
+            }
+        }
+    };
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(14.into())))
+    );
+    // the operator and its partial application share the span of the body
+    assert!(suspended_at.iter().all(|(span, _)| *span == Some(body)));
+    assert_eq!(
+        suspended_at
+            .iter()
+            .filter(|(_, expression)| expression == "((*) (x)) (2)")
+            .count(),
+        2
+    );
+    Ok(())
+}
+
+#[test]
+fn test_suspended_evaluation_can_be_abandoned() -> boo_core::error::Result<()> {
+    let evaluator = {
+        let mut context = boo_evaluation_optimized::new();
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+        context.evaluator()
+    };
+
+    let resumed = evaluator.evaluate_resumably(
+        boo_parser::parse_expr("let rec loop = fn n -> loop n in loop 0")?.to_core()?,
+        Breakpoint::Steps(100),
+    );
+
+    let Resumed::Suspended(suspended) = resumed else {
+        panic!("Expected evaluation to be suspended.");
+    };
+    drop(suspended);
+    Ok(())
+}
+
+#[test]
+fn test_provenance_follows_the_result_through_lets_and_applications() -> boo_core::error::Result<()>
+{
+    let program = "let double = fn n -> n * 2 in let x = 3 in double x";
+    let mut context = boo_evaluation_optimized::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let evaluator = context.evaluator();
+
+    let (result, provenance) =
+        evaluator.evaluate_with_provenance(boo_parser::parse_expr(program)?.to_core()?);
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(6.into())))
+    );
+    let sources = provenance
+        .chain()
+        .iter()
+        .map(|span| &program[span.range()])
+        .collect::<Vec<_>>();
+    // `double` is inlined, which removes the outermost `let`
+    assert_eq!(sources, vec!["let x = 3 in double x", "double x", "n * 2"]);
+    assert_eq!(
+        provenance.origin(),
+        program
+            .find("n * 2")
+            .map(|start| Span::from(start..start + 5))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_errors_in_natives_point_at_the_call_site() -> boo_core::error::Result<()> {
+    let program = "let x = 0 in 1 + 6 / x";
+    let mut context = boo_evaluation_optimized::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let evaluator = context.evaluator();
+
+    let result = evaluator.evaluate(boo_parser::parse_expr(program)?.to_core()?);
+
+    let division_start = program.find("6 / x").unwrap();
+    assert_eq!(
+        result,
+        Err(Error::DivisionByZero {
+            span: Some(Span::from(division_start..division_start + 5))
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_integer_overflow_promotes_by_default() -> boo_core::error::Result<()> {
+    let mut context = boo_evaluation_optimized::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let evaluator = context.evaluator();
+
+    let result = evaluator.evaluate(boo_parser::parse_expr("2147483647 + 1")?.to_core()?);
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(
+            2147483648i64.into()
+        )))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_integer_overflow_can_fail_instead() -> boo_core::error::Result<()> {
+    let program = "let x = 65536 in 1 + x * x";
+    let mut context = boo_evaluation_optimized::new().with_integer_overflow(IntegerOverflow::Fail);
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let evaluator = context.evaluator();
+
+    let overflowing = evaluator.evaluate(boo_parser::parse_expr(program)?.to_core()?);
+    let fitting = evaluator.evaluate(boo_parser::parse_expr("2147483646 + 1")?.to_core()?);
+
+    let multiplication_start = program.find("x * x").unwrap();
+    assert_eq!(
+        overflowing,
+        Err(Error::IntegerOverflow {
+            span: Some(Span::from(multiplication_start..multiplication_start + 5))
+        })
+    );
+    assert_eq!(
+        fitting,
+        Ok(Evaluated::Primitive(Primitive::Integer(2147483647.into())))
+    );
+    Ok(())
+}
+
+fn evaluate_stepwise(
+    program: &str,
+    observe: impl FnMut(boo_evaluation_pooling::Step) -> ControlFlow<()> + Send + 'static,
+) -> boo_core::error::Result<Evaluated> {
+    let mut context = boo_evaluation_optimized::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let evaluator = context.evaluator();
+    evaluator.evaluate_stepwise(boo_parser::parse_expr(program)?.to_core()?, observe)
+}
+
+const MEMORY_LIMIT: usize = 1024 * 1024;
+
+fn evaluate_with_a_memory_limit(program: &str) -> boo_core::error::Result<Evaluated> {
+    let mut context = boo_evaluation_optimized::with_memory_limit(MEMORY_LIMIT);
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let evaluator = context.evaluator();
+    evaluator.evaluate(boo_parser::parse_expr(program)?.to_core()?)
+}
+
+/// Evaluates the program on a thread with a stack far too small to hold one
+/// frame per call.
+fn check_in_a_small_stack(program: &'static str, expected: Primitive) {
+    let result = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || {
+            let mut context = boo_evaluation_optimized::new();
+            builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+            let evaluator = context.evaluator();
+            let expr = boo_parser::parse_expr(program)?.to_core()?;
+            let result = evaluator.evaluate(expr)?;
+            match result {
+                Evaluated::Primitive(primitive) => Ok(primitive),
+                _ => Err(Error::InvalidPrimitive { span: None }),
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(result, Ok(expected));
 }
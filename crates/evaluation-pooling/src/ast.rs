@@ -2,6 +2,7 @@
 
 use boo_core::ast::Expression;
 use boo_core::evaluation::ExpressionReader;
+use boo_core::pretty::{Doc, Pretty};
 use boo_core::span::{Span, Spanned};
 
 use super::pool::*;
@@ -33,6 +34,19 @@ impl Expr {
 
 impl Copy for Expr {}
 
+// Child expressions are rendered as references into the pool.
+impl Pretty for Expr {
+    fn to_doc(&self) -> Doc {
+        Doc::text(self.0.to_string())
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 /// A builder for [ExprPool][].
 pub type ExprPoolBuilder = PoolBuilder<Inner>;
 
@@ -0,0 +1,304 @@
+//! Compacts a [`Pool`][crate::pool::Pool] of expressions, discarding the ones
+//! that can no longer be reached.
+//!
+//! Pools only ever grow, so anything that keeps a pool around for a long time,
+//! binding more and more expressions into it, needs to be able to free the ones
+//! it no longer refers to. Compaction marks every expression reachable from a
+//! set of roots, and copies them into a new pool, remapping the references
+//! between them.
+
+use std::collections::{HashMap, HashSet};
+
+use boo_core::ast::*;
+use boo_core::identifier::Identifier;
+
+use crate::ast::*;
+
+/// Maps each expression in the old pool to its copy in the compacted pool.
+pub type Remapping = HashMap<Expr, Expr>;
+
+/// Copies every expression reachable from the given roots into a new pool.
+///
+/// Returns the new pool, along with the new location of each copied expression.
+/// Expressions reachable from more than one root are only copied once.
+pub fn compact(
+    pool: &ExprPool,
+    roots: impl IntoIterator<Item = Expr>,
+) -> (ExprPoolBuilder, Remapping) {
+    let mut compactor = Compactor::new(pool);
+    for root in roots {
+        compactor.copy(root);
+    }
+    compactor.finish()
+}
+
+/// Copies expressions from one pool to another, one at a time.
+pub(crate) struct Compactor<'a> {
+    pool: &'a ExprPool,
+    builder: ExprPoolBuilder,
+    remapping: Remapping,
+}
+
+impl<'a> Compactor<'a> {
+    pub fn new(pool: &'a ExprPool) -> Self {
+        Self {
+            pool,
+            builder: ExprPoolBuilder::new(),
+            remapping: Remapping::new(),
+        }
+    }
+
+    /// Copies an expression and all its children, unless it has already been
+    /// copied, and returns the new reference.
+    ///
+    /// As with [`add_expr`][crate::add_expr], children are added before their
+    /// parents.
+    pub fn copy(&mut self, expr: Expr) -> Expr {
+        if let Some(copied) = self.remapping.get(&expr) {
+            return *copied;
+        }
+        let inner = expr.read_from(self.pool);
+        let expression = match &inner.value {
+            Expression::Primitive(x) => Expression::Primitive(x.clone()),
+            Expression::Native(x) => Expression::Native(x.clone()),
+            Expression::Identifier(x) => Expression::Identifier(x.clone()),
+            Expression::Function(Function {
+                parameter,
+                parameter_type,
+                parameter_type_span,
+                body,
+            }) => Expression::Function(Function {
+                parameter: parameter.clone(),
+                parameter_type: parameter_type.clone(),
+                parameter_type_span: *parameter_type_span,
+                body: self.copy(*body),
+            }),
+            Expression::Apply(Apply { function, argument }) => Expression::Apply(Apply {
+                function: self.copy(*function),
+                argument: self.copy(*argument),
+            }),
+            Expression::Assign(Assign { name, value, inner }) => Expression::Assign(Assign {
+                name: name.clone(),
+                value: self.copy(*value),
+                inner: self.copy(*inner),
+            }),
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                Expression::AssignRecursive(AssignRecursive {
+                    bindings: bindings
+                        .iter()
+                        .map(|(name, value)| (name.clone(), self.copy(*value)))
+                        .collect(),
+                    inner: self.copy(*inner),
+                })
+            }
+            Expression::Match(Match { value, patterns }) => Expression::Match(Match {
+                value: self.copy(*value),
+                patterns: patterns
+                    .iter()
+                    .map(
+                        |PatternMatch {
+                             pattern,
+                             pattern_span,
+                             result,
+                         }| PatternMatch {
+                            pattern: pattern.clone(),
+                            pattern_span: *pattern_span,
+                            result: self.copy(*result),
+                        },
+                    )
+                    .collect(),
+            }),
+            Expression::Typed(Typed {
+                expression,
+                typ,
+                typ_span,
+            }) => Expression::Typed(Typed {
+                expression: self.copy(*expression),
+                typ: typ.clone(),
+                typ_span: *typ_span,
+            }),
+            Expression::Sequence(Sequence { first, second }) => Expression::Sequence(Sequence {
+                first: self.copy(*first),
+                second: self.copy(*second),
+            }),
+            Expression::Tuple(Tuple { elements }) => Expression::Tuple(Tuple {
+                elements: elements.iter().map(|element| self.copy(*element)).collect(),
+            }),
+            Expression::List(List::Empty) => Expression::List(List::Empty),
+            Expression::List(List::Cons { head, tail }) => Expression::List(List::Cons {
+                head: self.copy(*head),
+                tail: self.copy(*tail),
+            }),
+        };
+        let copied = Expr::insert(&mut self.builder, inner.span, expression);
+        self.remapping.insert(expr, copied);
+        copied
+    }
+
+    pub fn finish(self) -> (ExprPoolBuilder, Remapping) {
+        (self.builder, self.remapping)
+    }
+}
+
+/// The names referred to by an expression but not bound within it, or `None`
+/// if it contains a native, which could refer to anything.
+pub(crate) fn free_variables(pool: &ExprPool, expr: Expr) -> Option<HashSet<Identifier>> {
+    let union = |exprs: &[Expr]| {
+        exprs.iter().try_fold(HashSet::new(), |mut names, expr| {
+            names.extend(free_variables(pool, *expr)?);
+            Some(names)
+        })
+    };
+    match &expr.read_from(pool).value {
+        Expression::Primitive(_) | Expression::List(List::Empty) => Some(HashSet::new()),
+        Expression::Native(_) => None,
+        Expression::Identifier(name) => Some([name.clone()].into()),
+        Expression::Function(Function {
+            parameter, body, ..
+        }) => {
+            let mut names = free_variables(pool, *body)?;
+            names.remove(parameter);
+            Some(names)
+        }
+        Expression::Apply(Apply { function, argument }) => union(&[*function, *argument]),
+        Expression::Assign(Assign { name, value, inner }) => {
+            let mut names = free_variables(pool, *inner)?;
+            names.remove(name);
+            names.extend(free_variables(pool, *value)?);
+            Some(names)
+        }
+        Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+            let mut exprs = bindings.iter().map(|(_, value)| *value).collect::<Vec<_>>();
+            exprs.push(*inner);
+            let mut names = union(&exprs)?;
+            for (name, _) in bindings {
+                names.remove(name);
+            }
+            Some(names)
+        }
+        Expression::Match(Match { value, patterns }) => {
+            let mut names = free_variables(pool, *value)?;
+            for PatternMatch {
+                pattern, result, ..
+            } in patterns
+            {
+                let mut result_names = free_variables(pool, *result)?;
+                for variable in pattern.variables() {
+                    result_names.remove(variable);
+                }
+                names.extend(result_names);
+            }
+            Some(names)
+        }
+        Expression::Typed(Typed { expression, .. }) => free_variables(pool, *expression),
+        Expression::Sequence(Sequence { first, second }) => union(&[*first, *second]),
+        Expression::Tuple(Tuple { elements }) => union(elements),
+        Expression::List(List::Cons { head, tail }) => union(&[*head, *tail]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boo_core::primitive::Primitive;
+
+    use super::*;
+
+    fn identifier(name: &str) -> Expression<Expr> {
+        Expression::Identifier(Identifier::name_from_str(name).unwrap())
+    }
+
+    #[test]
+    fn test_compaction_discards_unreachable_expressions() {
+        let (pool, root) = {
+            let mut builder = ExprPoolBuilder::new();
+            let _ = Expr::insert(&mut builder, None, identifier("unused"));
+            let function = Expr::insert(&mut builder, None, identifier("f"));
+            let argument = Expr::insert(
+                &mut builder,
+                None,
+                Expression::Primitive(Primitive::Integer(1.into())),
+            );
+            let _ = Expr::insert(&mut builder, None, identifier("also_unused"));
+            let root = Expr::insert(
+                &mut builder,
+                None,
+                Expression::Apply(Apply { function, argument }),
+            );
+            (builder.build(), root)
+        };
+
+        let (builder, remapping) = compact(&pool, [root]);
+
+        assert_eq!(builder.added(), 3);
+        let compacted = builder.build();
+        let Expression::Apply(Apply { function, argument }) =
+            &remapping[&root].read_from(&compacted).value
+        else {
+            panic!("Expected an application.");
+        };
+        assert_eq!(function.read_from(&compacted).value, identifier("f"));
+        assert_eq!(
+            argument.read_from(&compacted).value,
+            Expression::Primitive(Primitive::Integer(1.into()))
+        );
+    }
+
+    #[test]
+    fn test_compaction_copies_shared_expressions_once() {
+        let (pool, a, b) = {
+            let mut builder = ExprPoolBuilder::new();
+            let shared = Expr::insert(&mut builder, None, identifier("x"));
+            let a = Expr::insert(
+                &mut builder,
+                None,
+                Expression::Tuple(Tuple {
+                    elements: vec![shared, shared],
+                }),
+            );
+            let b = Expr::insert(
+                &mut builder,
+                None,
+                Expression::Sequence(Sequence {
+                    first: shared,
+                    second: a,
+                }),
+            );
+            (builder.build(), a, b)
+        };
+
+        let (builder, remapping) = compact(&pool, [a, b, a]);
+
+        assert_eq!(builder.added(), 3);
+        assert_eq!(remapping.len(), 3);
+    }
+
+    #[test]
+    fn test_compacting_a_forked_pool() {
+        let (pool, root) = {
+            let mut builder = ExprPoolBuilder::new();
+            let _ = Expr::insert(&mut builder, None, identifier("unused"));
+            let inherited = Expr::insert(&mut builder, None, identifier("y"));
+            let mut forked = builder.build().fork();
+            let root = Expr::insert(
+                &mut forked,
+                None,
+                Expression::Function(Function {
+                    parameter: Identifier::name_from_str("x").unwrap(),
+                    parameter_type: None,
+                    parameter_type_span: None,
+                    body: inherited,
+                }),
+            );
+            (forked.build(), root)
+        };
+
+        let (builder, remapping) = compact(&pool, [root]);
+
+        assert_eq!(builder.added(), 2);
+        let compacted = builder.build();
+        assert_eq!(
+            free_variables(&compacted, remapping[&root]),
+            Some([Identifier::name_from_str("y").unwrap()].into())
+        );
+    }
+}
@@ -1,20 +1,35 @@
 //! Pools [`Expr`][super::pooler::ast::Expr] values and evaluates them.
 
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use boo_core::error::*;
 use boo_core::evaluation::*;
 use boo_core::expr::Expr;
 use boo_core::identifier::*;
-use boo_evaluation_lazy::Bindings;
+use boo_core::optimization::Optimizations;
+use boo_evaluation_lazy::{Binding, Bindings, MemoryBudget, Observer, Thunk};
 
 use crate::ast;
+use crate::compaction::{free_variables, Compactor};
 use crate::pooler::add_expr;
+use crate::profile::{Profile, Profiler};
+use crate::provenance::Provenance;
+use crate::step::Step;
+use crate::suspension::{self, Breakpoint, Resumed};
 
 /// An expression pool together with its bound context.
 pub struct PoolingEvaluationContext<NewInner: for<'pool> NewInnerEvaluator<'pool>> {
     pool_builder: ast::ExprPoolBuilder,
     bindings: Bindings<ast::Expr>,
+    memory_limit: Option<usize>,
+    step_limit: Option<usize>,
+    integer_overflow: IntegerOverflow,
+    optimizations: Optimizations,
     new_inner_marker: PhantomData<NewInner>,
 }
 
@@ -23,9 +38,121 @@ impl<NewInner: for<'pool> NewInnerEvaluator<'pool>> PoolingEvaluationContext<New
         Self {
             pool_builder: ast::ExprPoolBuilder::new(),
             bindings: Bindings::new(),
+            memory_limit: None,
+            step_limit: None,
+            integer_overflow: IntegerOverflow::default(),
+            optimizations: Optimizations::ALL,
             new_inner_marker: PhantomData,
         }
     }
+
+    /// Limits the memory used by each evaluation to approximately `limit`
+    /// bytes, failing with [`Error::OutOfMemory`] if it uses more.
+    pub fn with_memory_limit(self, limit: usize) -> Self {
+        Self {
+            memory_limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Limits each evaluation to entering `limit` expressions, failing with
+    /// [`Error::StepLimitExceeded`] if it enters more.
+    ///
+    /// Inner evaluators which cannot be observed are not limited.
+    pub fn with_step_limit(self, limit: usize) -> Self {
+        Self {
+            step_limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Decides how arithmetic on small integers handles overflow. By default,
+    /// the result is promoted to an arbitrary-precision integer.
+    pub fn with_integer_overflow(self, integer_overflow: IntegerOverflow) -> Self {
+        Self {
+            integer_overflow,
+            ..self
+        }
+    }
+
+    /// Chooses the optimizations applied to each expression before it is
+    /// pooled. By default, all of them are applied.
+    pub fn with_optimizations(self, optimizations: Optimizations) -> Self {
+        Self {
+            optimizations,
+            ..self
+        }
+    }
+}
+
+impl<NewInner: for<'pool> NewInnerEvaluator<'pool>> PoolingEvaluationContext<NewInner> {
+    /// The number of expressions in the pool.
+    pub fn pool_size(&self) -> usize {
+        self.pool_builder.added()
+    }
+
+    /// Discards every pooled expression that can no longer be reached from the
+    /// bindings, so that binding names over and over again, as a long-running
+    /// session does, does not grow the pool without bound.
+    ///
+    /// Each binding only keeps the bindings it captured that it refers to, so a
+    /// binding which has been shadowed, and is not used by any other, is
+    /// discarded along with its expressions.
+    pub fn compact(&mut self) {
+        let pool = std::mem::take(&mut self.pool_builder).build();
+        let mut compactor = Compactor::new(&pool);
+        let mut rebound = HashMap::new();
+        let bindings =
+            self.bindings
+                .iter()
+                .fold(Bindings::new(), |bindings, (identifier, binding)| {
+                    let binding = rebind(&pool, &mut compactor, &mut rebound, binding);
+                    bindings.with_binding(identifier.clone(), binding)
+                });
+        let (pool_builder, _) = compactor.finish();
+        self.pool_builder = pool_builder;
+        self.bindings = bindings;
+    }
+}
+
+/// Copies a binding, along with the bindings it captured, into a compacted
+/// pool, dropping the captured bindings that its expression does not refer to.
+///
+/// Bindings shared between several others are only copied once, so that they
+/// are still only evaluated once.
+fn rebind(
+    pool: &ast::ExprPool,
+    compactor: &mut Compactor,
+    rebound: &mut HashMap<*const (), Binding<ast::Expr>>,
+    binding: &Binding<ast::Expr>,
+) -> Binding<ast::Expr> {
+    if let Some(existing) = rebound.get(&binding.as_ptr()) {
+        return existing.clone();
+    }
+    let (expr, captured) = binding
+        .unresolved_value()
+        .expect("The bindings in a context are never evaluated.");
+    let referenced: Vec<_> = match free_variables(pool, expr) {
+        Some(names) => names
+            .into_iter()
+            .filter_map(|name| captured.get(&name).map(|b| (name, b.clone())))
+            .collect(),
+        // natives can refer to anything, so everything is kept
+        None => captured
+            .iter()
+            .map(|(name, b)| (name.clone(), b.clone()))
+            .collect(),
+    };
+    let captured =
+        referenced
+            .into_iter()
+            .fold(Bindings::new(), |bindings, (name, captured_binding)| {
+                let captured_binding = rebind(pool, compactor, rebound, &captured_binding);
+                bindings.with_binding(name, captured_binding)
+            });
+    let result = Thunk::unresolved((compactor.copy(expr), captured));
+    rebound.insert(binding.as_ptr(), result.clone());
+    result
 }
 
 impl<NewInner: for<'pool> NewInnerEvaluator<'pool>> Default for PoolingEvaluationContext<NewInner> {
@@ -40,15 +167,25 @@ impl<NewInner: for<'pool> NewInnerEvaluator<'pool>> EvaluationContext
     type Eval = PoolingEvaluator<NewInner>;
 
     fn bind(&mut self, identifier: Identifier, expr: Expr) -> Result<()> {
-        let pool_ref = add_expr(&mut self.pool_builder, expr);
-        self.bindings = self.bindings.with(identifier, pool_ref, Bindings::new());
+        let pool_ref = add_expr(&mut self.pool_builder, self.optimizations.apply(expr));
+        self.bindings = self
+            .bindings
+            .with(identifier, pool_ref, self.bindings.clone());
         Ok(())
     }
 
+    fn identifiers(&self) -> Vec<Identifier> {
+        self.bindings.identifiers().cloned().collect()
+    }
+
     fn evaluator(self) -> Self::Eval {
         PoolingEvaluator {
             pool: self.pool_builder.build(),
             bindings: self.bindings,
+            memory_limit: self.memory_limit,
+            step_limit: self.step_limit,
+            integer_overflow: self.integer_overflow,
+            optimizations: self.optimizations,
             new_inner_marker: PhantomData,
         }
     }
@@ -59,21 +196,189 @@ impl<NewInner: for<'pool> NewInnerEvaluator<'pool>> EvaluationContext
 pub struct PoolingEvaluator<NewInner: for<'pool> NewInnerEvaluator<'pool>> {
     pool: ast::ExprPool,
     bindings: Bindings<ast::Expr>,
+    memory_limit: Option<usize>,
+    step_limit: Option<usize>,
+    integer_overflow: IntegerOverflow,
+    optimizations: Optimizations,
     new_inner_marker: PhantomData<NewInner>,
 }
 
-impl<NewInner: for<'pool> NewInnerEvaluator<'pool>> Evaluator for PoolingEvaluator<NewInner> {
-    fn evaluate(&self, expr: Expr) -> Result<Evaluated> {
+impl<NewInner: for<'pool> NewInnerEvaluator<'pool>> PoolingEvaluator<NewInner> {
+    /// Evaluates an expression, calling `observe` each time an expression is
+    /// entered.
+    ///
+    /// Evaluation is paused while `observe` runs, so it can be used to inspect
+    /// the state of evaluation before continuing. If it returns
+    /// [`ControlFlow::Break`], evaluation stops with [`Error::Interrupted`].
+    ///
+    /// Inner evaluators which cannot be observed never call `observe`.
+    pub fn evaluate_stepwise(
+        &self,
+        expr: Expr,
+        observe: impl FnMut(Step) -> ControlFlow<()> + Send + 'static,
+    ) -> Result<Evaluated> {
+        self.evaluate_observed(expr, Some(Box::new(observe)))
+    }
+
+    /// Evaluates an expression, counting how many times each expression is
+    /// entered, and how long is spent on it.
+    ///
+    /// Inner evaluators which cannot be observed produce an empty profile.
+    pub fn evaluate_profiled(&self, expr: Expr) -> (Result<Evaluated>, Profile) {
+        let profiler = Arc::new(Mutex::new(Profiler::default()));
+        let result = {
+            let profiler = profiler.clone();
+            self.evaluate_stepwise(expr, move |step| {
+                profiler.lock().unwrap().enter(step.expr(), step.span());
+                ControlFlow::Continue(())
+            })
+        };
+        let profiler = std::mem::take(&mut *profiler.lock().unwrap());
+        (result, profiler.finish())
+    }
+
+    /// Evaluates an expression, keeping track of the expressions whose value
+    /// became the result.
+    ///
+    /// Inner evaluators which cannot be observed produce an empty provenance.
+    pub fn evaluate_with_provenance(&self, expr: Expr) -> (Result<Evaluated>, Provenance) {
+        let provenance = Arc::new(Mutex::new(Provenance::default()));
+        let result = {
+            let provenance = provenance.clone();
+            self.evaluate_stepwise(expr, move |step| {
+                if step.produces_result() {
+                    provenance.lock().unwrap().record(step.span());
+                }
+                ControlFlow::Continue(())
+            })
+        };
+        let provenance = std::mem::take(&mut *provenance.lock().unwrap());
+        (result, provenance)
+    }
+
+    /// Evaluates an expression until it finishes or reaches the breakpoint.
+    ///
+    /// If evaluation is suspended, it can be resumed later with
+    /// [`Suspended::resume`][crate::Suspended::resume]. In the meantime, it
+    /// waits on its own thread.
+    ///
+    /// Inner evaluators which cannot be observed never reach a breakpoint.
+    pub fn evaluate_resumably(&self, expr: Expr, breakpoint: Breakpoint) -> Resumed
+    where
+        NewInner: Send + 'static,
+    {
+        let evaluator = self.clone();
+        suspension::start(
+            move |observe| evaluator.evaluate_observed(expr, Some(observe)),
+            breakpoint,
+        )
+    }
+
+    fn evaluate_observed(&self, expr: Expr, observe: Option<ObserveStep>) -> Result<Evaluated> {
+        let Some(limit) = self.step_limit else {
+            return self.evaluate_within_budget(expr, observe);
+        };
+        // each evaluation starts counting again from zero
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let counting: ObserveStep = {
+            let exceeded = exceeded.clone();
+            let mut observe = observe;
+            let mut steps = 0;
+            Box::new(move |step| {
+                steps += 1;
+                if steps > limit {
+                    exceeded.store(true, Ordering::Relaxed);
+                    return ControlFlow::Break(());
+                }
+                match &mut observe {
+                    Some(observe) => observe(step),
+                    None => ControlFlow::Continue(()),
+                }
+            })
+        };
+        let result = self.evaluate_within_budget(expr, Some(counting));
+        match result {
+            Err(Error::Interrupted) if exceeded.load(Ordering::Relaxed) => {
+                Err(Error::StepLimitExceeded { limit })
+            }
+            result => result,
+        }
+    }
+
+    fn evaluate_within_budget(
+        &self,
+        expr: Expr,
+        observe: Option<ObserveStep>,
+    ) -> Result<Evaluated> {
+        // each evaluation starts with a fresh budget
+        let budget = match self.memory_limit {
+            Some(limit) => MemoryBudget::limited(limit),
+            None => MemoryBudget::unlimited(),
+        };
         let mut builder = self.pool.fork();
-        let root = add_expr(&mut builder, expr);
+        let root = add_expr(&mut builder, self.optimizations.apply(expr));
+        let _pool_entries = budget.allocate(builder.added() * size_of::<ast::Inner>());
         let fork = builder.build();
-        let inner = NewInner::new(&fork, self.bindings.clone());
+        let observer = observe.map(|observe| stepping_observer(fork.clone(), observe));
+        let inner = NewInner::new(
+            &fork,
+            self.bindings.clone(),
+            budget,
+            observer,
+            self.integer_overflow,
+        );
         inner.evaluate(root).map(|result| result.to_core(&fork))
     }
 }
 
+impl<NewInner: for<'pool> NewInnerEvaluator<'pool>> Clone for PoolingEvaluator<NewInner> {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            bindings: self.bindings.clone(),
+            memory_limit: self.memory_limit,
+            step_limit: self.step_limit,
+            integer_overflow: self.integer_overflow,
+            optimizations: self.optimizations,
+            new_inner_marker: PhantomData,
+        }
+    }
+}
+
+impl<NewInner: for<'pool> NewInnerEvaluator<'pool>> Evaluator for PoolingEvaluator<NewInner> {
+    fn evaluate(&self, expr: Expr) -> Result<Evaluated> {
+        self.evaluate_observed(expr, None)
+    }
+}
+
+pub(crate) type ObserveStep = Box<dyn FnMut(Step) -> ControlFlow<()> + Send>;
+
+/// Adapts a function which observes each [`Step`] to an [`Observer`].
+fn stepping_observer(pool: ast::ExprPool, observe: ObserveStep) -> Observer<ast::Expr> {
+    let observe = Mutex::new(observe);
+    Arc::new(
+        move |expr: &ast::Expr, bindings: &Bindings<ast::Expr>, produces_result: bool| {
+            (observe.lock().unwrap())(Step {
+                pool: &pool,
+                expr: *expr,
+                bindings,
+                produces_result,
+            })
+        },
+    )
+}
+
 pub trait NewInnerEvaluator<'pool> {
     type Inner: Evaluator<ast::Expr>;
 
-    fn new(pool: &'pool ast::ExprPool, bindings: Bindings<ast::Expr>) -> Self::Inner;
+    /// Constructs an inner evaluator, which should count the memory it uses
+    /// against the budget, call the observer (if there is one) each time it
+    /// enters an expression, and handle integer overflow as specified.
+    fn new(
+        pool: &'pool ast::ExprPool,
+        bindings: Bindings<ast::Expr>,
+        budget: MemoryBudget,
+        observer: Option<Observer<ast::Expr>>,
+        integer_overflow: IntegerOverflow,
+    ) -> Self::Inner;
 }
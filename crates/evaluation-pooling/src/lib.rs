@@ -3,13 +3,24 @@
 //! This evaluator first pools expressions into a vector, simplifying access.
 
 pub mod ast;
+mod compaction;
 mod evaluator;
 mod pool;
 mod pooler;
+mod profile;
+mod provenance;
+mod step;
+mod suspension;
 
 use boo_core::evaluation::EvaluationContext;
 
+pub use compaction::{compact, Remapping};
 pub use evaluator::{NewInnerEvaluator, PoolingEvaluationContext, PoolingEvaluator};
+pub use pooler::add_expr;
+pub use profile::{Profile, ProfileEntry};
+pub use provenance::Provenance;
+pub use step::Step;
+pub use suspension::{Breakpoint, Resumed, Suspended};
 
 pub fn new<NewInner: for<'pool> evaluator::NewInnerEvaluator<'pool>>() -> impl EvaluationContext {
     evaluator::PoolingEvaluationContext::<NewInner>::new()
@@ -72,6 +72,12 @@ impl<T> PoolBuilder<T> {
         }
     }
 
+    /// The number of values added to this builder, not counting any inherited
+    /// from the pool it was forked from.
+    pub fn added(&self) -> usize {
+        self.owned.len()
+    }
+
     /// Splits a pool into many.
     ///
     /// Values for existing references are shared, but new values are inserted
@@ -99,6 +105,16 @@ pub struct Pool<T> {
     marker: PhantomData<T>,
 }
 
+impl<T> Clone for Pool<T> {
+    fn clone(&self) -> Self {
+        Self {
+            pools: self.pools.clone(),
+            offset: self.offset,
+            marker: PhantomData,
+        }
+    }
+}
+
 impl<T> Pool<T> {
     /// Splits out a new pool from this one.
     ///
@@ -176,6 +192,9 @@ mod tests {
         let mut builder_b = pool.fork();
         let a_three = builder_a.add("three");
         let b_four = builder_b.add("four");
+
+        assert_eq!(builder_a.added(), 1);
+        assert_eq!(builder_b.added(), 1);
         let pool_a = builder_a.build();
         let pool_b = builder_b.build();
 
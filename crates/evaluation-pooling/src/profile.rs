@@ -0,0 +1,92 @@
+//! Profiles evaluation, counting how often each expression is entered and how
+//! long is spent on it.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use boo_core::span::Span;
+
+use crate::ast;
+
+/// How often each expression was evaluated, and for how long, keyed by the
+/// location of the expression in the source.
+///
+/// Expressions without a location, such as the implementations of built-ins,
+/// are grouped together under `None`.
+#[derive(Debug, Clone, Default)]
+pub struct Profile(HashMap<Option<Span>, ProfileEntry>);
+
+/// The measurements for a single location.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProfileEntry {
+    /// The number of times the expression was entered.
+    pub count: usize,
+    /// The time spent on the expression itself, excluding its subexpressions.
+    pub duration: Duration,
+}
+
+impl Profile {
+    /// Looks up the measurements for a location.
+    pub fn get(&self, span: Option<Span>) -> Option<&ProfileEntry> {
+        self.0.get(&span)
+    }
+
+    /// Lists every location, starting with the one that took the most time.
+    pub fn hot_spots(&self) -> Vec<(Option<Span>, ProfileEntry)> {
+        let mut entries = self
+            .0
+            .iter()
+            .map(|(span, entry)| (*span, *entry))
+            .collect::<Vec<_>>();
+        entries.sort_by(|(_, a), (_, b)| {
+            b.duration
+                .cmp(&a.duration)
+                .then_with(|| b.count.cmp(&a.count))
+        });
+        entries
+    }
+}
+
+/// Measures evaluation as each expression in the pool is entered.
+///
+/// The time between entering one expression and the next is attributed to the
+/// first, so each expression is only charged for the work it does itself.
+#[derive(Default)]
+pub(crate) struct Profiler {
+    entries: HashMap<ast::Expr, (Option<Span>, ProfileEntry)>,
+    current: Option<(ast::Expr, Instant)>,
+}
+
+impl Profiler {
+    pub fn enter(&mut self, expr: ast::Expr, span: Option<Span>) {
+        let now = Instant::now();
+        self.stop(now);
+        let (_, entry) = self
+            .entries
+            .entry(expr)
+            .or_insert((span, Default::default()));
+        entry.count += 1;
+        self.current = Some((expr, now));
+    }
+
+    /// Stops measuring, and combines the measurements of the expressions at
+    /// each location.
+    pub fn finish(mut self) -> Profile {
+        self.stop(Instant::now());
+        let mut profile = Profile::default();
+        for (span, entry) in self.entries.into_values() {
+            let total = profile.0.entry(span).or_default();
+            total.count += entry.count;
+            total.duration += entry.duration;
+        }
+        profile
+    }
+
+    fn stop(&mut self, now: Instant) {
+        if let Some((expr, start)) = self.current.take() {
+            if let Some((_, entry)) = self.entries.get_mut(&expr) {
+                entry.duration += now - start;
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Tracks where the result of an evaluation came from.
+
+use std::collections::HashSet;
+
+use boo_core::span::Span;
+
+/// The locations of the expressions whose value became the result of an
+/// evaluation, from the outermost expression to the one that produced it.
+///
+/// For example, the result of `let x = 1 in x + 2` comes from the whole
+/// expression, then the body of the `let`, `x + 2`. Each location is only
+/// listed the first time it is reached, so that loops do not make the chain
+/// grow without bound.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    chain: Vec<Span>,
+    seen: HashSet<Span>,
+}
+
+impl Provenance {
+    /// Every location along the way to the result, starting with the
+    /// outermost.
+    pub fn chain(&self) -> &[Span] {
+        &self.chain
+    }
+
+    /// The innermost location that produced the result, if known.
+    pub fn origin(&self) -> Option<Span> {
+        self.chain.last().copied()
+    }
+
+    pub(crate) fn record(&mut self, span: Option<Span>) {
+        if let Some(span) = span {
+            if self.seen.insert(span) {
+                self.chain.push(span);
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Inspects evaluation of a pooled expression, one step at a time.
+
+use boo_core::ast::Expression;
+use boo_core::evaluation::ExpressionReader;
+use boo_core::expr::Expr;
+use boo_core::span::{Span, Spanned};
+use boo_evaluation_lazy::Bindings;
+
+use crate::ast;
+
+/// The state of evaluation as an expression is entered.
+pub struct Step<'a> {
+    pub(crate) pool: &'a ast::ExprPool,
+    pub(crate) expr: ast::Expr,
+    pub(crate) bindings: &'a Bindings<ast::Expr>,
+    pub(crate) produces_result: bool,
+}
+
+impl<'a> Step<'a> {
+    /// The pool holding the expression and everything it refers to.
+    pub fn pool(&self) -> &'a ast::ExprPool {
+        self.pool
+    }
+
+    /// A reference to the expression in the pool.
+    pub fn expr(&self) -> ast::Expr {
+        self.expr
+    }
+
+    /// The expression, whose children are references to elsewhere in the pool.
+    pub fn expression(&self) -> Spanned<&'a Expression<ast::Expr>> {
+        self.pool.read(self.expr)
+    }
+
+    /// The location of the expression in the source, if known.
+    pub fn span(&self) -> Option<Span> {
+        self.expression().span
+    }
+
+    /// Reconstructs the expression as a core expression.
+    pub fn to_core(&self) -> Expr {
+        self.pool.to_core(self.expr)
+    }
+
+    /// The bindings in scope.
+    ///
+    /// These are persistent, and so can be cloned cheaply to keep a snapshot.
+    pub fn bindings(&self) -> &'a Bindings<ast::Expr> {
+        self.bindings
+    }
+
+    /// Whether the value of the expression will be the result of the whole
+    /// evaluation, e.g. because it is the body of the outermost `let`, or of a
+    /// function applied in the outermost expression.
+    pub fn produces_result(&self) -> bool {
+        self.produces_result
+    }
+}
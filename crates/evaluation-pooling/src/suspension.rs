@@ -0,0 +1,155 @@
+//! Evaluates an expression a little at a time, suspending it and resuming it
+//! later.
+//!
+//! Evaluation runs on its own thread, which blocks whenever it reaches a
+//! [`Breakpoint`], until it is resumed. This means that a suspended evaluation
+//! can be held onto for as long as necessary, e.g. by a debugger waiting for
+//! input, or by a scheduler running many evaluations in turn.
+
+use std::ops::ControlFlow;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use boo_core::error::Result;
+use boo_core::evaluation::Evaluated;
+use boo_core::expr::Expr;
+use boo_core::span::Span;
+
+use crate::evaluator::ObserveStep;
+use crate::step::Step;
+
+/// Evaluation threads get a stack as large as the main thread's usually is, so
+/// that suspendable evaluation can recurse as deeply as normal evaluation.
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Decides when to suspend evaluation.
+pub enum Breakpoint {
+    /// Suspends upon entering the `n`th expression from now.
+    Steps(usize),
+    /// Suspends upon entering any expression whose location matches.
+    At(Box<dyn FnMut(Option<Span>) -> bool + Send>),
+}
+
+impl Breakpoint {
+    /// Suspends upon entering any expression whose location matches the
+    /// predicate.
+    pub fn at(predicate: impl FnMut(Option<Span>) -> bool + Send + 'static) -> Self {
+        Self::At(Box::new(predicate))
+    }
+
+    fn is_reached(&mut self, step: &Step) -> bool {
+        match self {
+            Self::Steps(remaining) => {
+                *remaining = remaining.saturating_sub(1);
+                *remaining == 0
+            }
+            Self::At(predicate) => predicate(step.span()),
+        }
+    }
+}
+
+/// The outcome of running evaluation until the next breakpoint.
+pub enum Resumed {
+    /// Evaluation finished before reaching the breakpoint.
+    Finished(Result<Evaluated>),
+    /// Evaluation reached the breakpoint, and can be resumed later.
+    Suspended(Suspended),
+}
+
+/// An evaluation which has been suspended upon entering an expression.
+///
+/// Dropping a suspended evaluation stops it.
+pub struct Suspended {
+    span: Option<Span>,
+    expression: Expr,
+    resume: Sender<Breakpoint>,
+    events: Receiver<Event>,
+    thread: JoinHandle<()>,
+}
+
+impl Suspended {
+    /// The location of the expression about to be evaluated, if known.
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    /// The expression about to be evaluated.
+    pub fn expression(&self) -> &Expr {
+        &self.expression
+    }
+
+    /// Continues evaluation until it finishes or reaches the given breakpoint.
+    pub fn resume(self, breakpoint: Breakpoint) -> Resumed {
+        // if the evaluation thread has gone away, waiting will find out why
+        let _ = self.resume.send(breakpoint);
+        wait(self.resume, self.events, self.thread)
+    }
+}
+
+enum Event {
+    Suspended {
+        span: Option<Span>,
+        expression: Expr,
+    },
+    Finished(Result<Evaluated>),
+}
+
+/// Starts evaluating on a new thread, using the given function, which must
+/// call the observer each time an expression is entered.
+///
+/// Returns once evaluation finishes or reaches the breakpoint.
+pub(crate) fn start(
+    evaluate: impl FnOnce(ObserveStep) -> Result<Evaluated> + Send + 'static,
+    breakpoint: Breakpoint,
+) -> Resumed {
+    let (resume, resumptions) = mpsc::channel::<Breakpoint>();
+    let (suspensions, events) = mpsc::channel();
+    let finished = suspensions.clone();
+    let thread = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let mut breakpoint = breakpoint;
+            let result = evaluate(Box::new(move |step| {
+                if !breakpoint.is_reached(&step) {
+                    return ControlFlow::Continue(());
+                }
+                let event = Event::Suspended {
+                    span: step.span(),
+                    expression: step.to_core(),
+                };
+                if suspensions.send(event).is_err() {
+                    return ControlFlow::Break(());
+                }
+                // if the suspended evaluation is dropped, there is nothing to
+                // wait for, so evaluation stops
+                match resumptions.recv() {
+                    Ok(next) => {
+                        breakpoint = next;
+                        ControlFlow::Continue(())
+                    }
+                    Err(_) => ControlFlow::Break(()),
+                }
+            }));
+            let _ = finished.send(Event::Finished(result));
+        })
+        .expect("Could not start the evaluation thread.");
+    wait(resume, events, thread)
+}
+
+fn wait(resume: Sender<Breakpoint>, events: Receiver<Event>, thread: JoinHandle<()>) -> Resumed {
+    match events.recv() {
+        Ok(Event::Suspended { span, expression }) => Resumed::Suspended(Suspended {
+            span,
+            expression,
+            resume,
+            events,
+            thread,
+        }),
+        Ok(Event::Finished(result)) => Resumed::Finished(result),
+        // the thread only stops without a result if it panicked
+        Err(_) => match thread.join() {
+            Ok(()) => unreachable!("The evaluation thread stopped without a result."),
+            Err(panic) => std::panic::resume_unwind(panic),
+        },
+    }
+}
@@ -1,48 +1,100 @@
 //! Evaluates an expression recursively.
+//!
+//! Rather than using the Rust call stack, the evaluator keeps track of the
+//! remaining work on an explicit stack, so that deeply-nested expressions do
+//! not overflow. Natives still call back into the evaluator to look up their
+//! arguments.
+//!
+//! The evaluator can be given a [`MemoryBudget`], against which it counts the
+//! bindings it creates and the size of its stack. If the budget is exceeded,
+//! evaluation stops with [`Error::OutOfMemory`].
+//!
+//! It can also be given an [`Observer`], which is called each time the
+//! evaluator enters an expression, and can pause or stop evaluation.
 
-use std::sync::Arc;
+use std::mem::size_of;
 
 use boo_core::ast::*;
 use boo_core::error::*;
 use boo_core::evaluation::*;
 use boo_core::identifier::*;
+use boo_core::matching::{Selected, Selection};
 use boo_core::native::*;
 use boo_core::primitive::*;
 use boo_core::span::Span;
 use boo_core::span::Spanned;
-use boo_evaluation_lazy::{Binding, Bindings, CompletedEvaluation, EvaluatedBinding};
+use boo_evaluation_lazy::{
+    resolved, select, store, Binding, Bindings, CompletedEvaluation, EvaluatedBinding,
+    MemoryBudget, Observer,
+};
 
-pub fn new() -> impl EvaluationContext {
+pub fn new() -> RecursiveEvaluator<boo_core::expr::Expr, boo_core::expr::ExprReader> {
     RecursiveEvaluator::new(boo_core::expr::ExprReader, Bindings::new())
 }
 
-pub struct RecursiveEvaluator<Expr: Clone, Reader: ExpressionReader<Expr = Expr>> {
+pub struct RecursiveEvaluator<Expr: Clone + 'static, Reader: ExpressionReader<Expr = Expr>> {
     reader: Reader,
     bindings: Bindings<Expr>,
+    budget: MemoryBudget,
+    observer: Option<Observer<Expr>>,
+    integer_overflow: IntegerOverflow,
 }
 
-impl<Expr: Clone, Reader: ExpressionReader<Expr = Expr>> RecursiveEvaluator<Expr, Reader> {
+impl<Expr: Clone + 'static, Reader: ExpressionReader<Expr = Expr>>
+    RecursiveEvaluator<Expr, Reader>
+{
     pub fn new(reader: Reader, bindings: Bindings<Expr>) -> Self {
-        Self { reader, bindings }
+        Self {
+            reader,
+            bindings,
+            budget: MemoryBudget::unlimited(),
+            observer: None,
+            integer_overflow: IntegerOverflow::default(),
+        }
+    }
+
+    /// Counts the memory used by evaluation against the given budget.
+    pub fn with_memory_budget(self, budget: MemoryBudget) -> Self {
+        Self { budget, ..self }
+    }
+
+    /// Decides how arithmetic on small integers handles overflow.
+    pub fn with_integer_overflow(self, integer_overflow: IntegerOverflow) -> Self {
+        Self {
+            integer_overflow,
+            ..self
+        }
+    }
+
+    /// Calls the observer each time an expression is entered.
+    pub fn with_observer(self, observer: Observer<Expr>) -> Self {
+        Self {
+            observer: Some(observer),
+            ..self
+        }
     }
 }
 
-impl<Expr: Clone, Reader: ExpressionReader<Expr = Expr>> EvaluationContext<Expr>
+impl<Expr: Clone + 'static, Reader: ExpressionReader<Expr = Expr>> EvaluationContext<Expr>
     for RecursiveEvaluator<Expr, Reader>
 {
     type Eval = Self;
 
     fn bind(&mut self, identifier: Identifier, expr: Expr) -> Result<()> {
-        self.bindings = self.bindings.with(identifier, expr, Bindings::new());
+        self.bindings = self.bindings.with(identifier, expr, self.bindings.clone());
         Ok(())
     }
 
+    fn identifiers(&self) -> Vec<Identifier> {
+        self.bindings.identifiers().cloned().collect()
+    }
+
     fn evaluator(self) -> Self::Eval {
         self
     }
 }
 
-impl<Expr: Clone, Reader: ExpressionReader<Expr = Expr>> Evaluator<Expr>
+impl<Expr: Clone + 'static, Reader: ExpressionReader<Expr = Expr>> Evaluator<Expr>
     for RecursiveEvaluator<Expr, Reader>
 {
     /// Evaluates an expression from a pool in a given scope.
@@ -50,78 +102,330 @@ impl<Expr: Clone, Reader: ExpressionReader<Expr = Expr>> Evaluator<Expr>
     /// The bindings are modified by assignment, accessed when evaluating an
     /// identifier, and captured by closures when a function is evaluated.
     fn evaluate(&self, expr: Expr) -> Result<Evaluated<Expr>> {
-        self.evaluate_inner(expr)
-            .map(|completed| completed.finish())
+        self.evaluate_inner(expr)?
+            .finish(&|binding| self.resolve_binding(binding))
     }
 }
 
-impl<Expr: Clone, Reader: ExpressionReader<Expr = Expr>> RecursiveEvaluator<Expr, Reader> {
+/// The work remaining once the current expression has been evaluated.
+///
+/// Rather than recursing into subexpressions, the evaluator pushes these onto a
+/// stack, so that the depth of an expression is limited only by the available
+/// memory.
+enum Continuation<Expr: Clone + 'static> {
+    /// Applies the result, which must be a function, to the argument.
+    Apply {
+        argument: Expr,
+        bindings: Bindings<Expr>,
+        span: Option<Span>,
+    },
+    /// Discards the result, and continues with the next expression.
+    Sequence {
+        next: Expr,
+        bindings: Bindings<Expr>,
+        call_site: Option<Span>,
+    },
+    /// Stores the result in a binding, so that it is only evaluated once.
+    Resolve(Binding<Expr>),
+    /// Continues matching, now that the part of the value it asked for has
+    /// been resolved.
+    Match {
+        selection: Selection<Binding<Expr>, Expr>,
+        bindings: Bindings<Expr>,
+        span: Option<Span>,
+        call_site: Option<Span>,
+    },
+}
+
+impl<Expr: Clone + 'static, Reader: ExpressionReader<Expr = Expr>>
+    RecursiveEvaluator<Expr, Reader>
+{
     fn evaluate_inner(&self, expr: Expr) -> Result<CompletedEvaluation<Expr>> {
-        let Spanned {
-            span,
-            value: expression,
-        } = self.reader.read(expr);
-        match expression.as_ref() {
-            Expression::Primitive(value) => Ok(CompletedEvaluation::Primitive(value.clone())),
-            Expression::Native(Native { implementation, .. }) => {
-                implementation(self).map(CompletedEvaluation::Primitive)
+        self.run(expr, self.bindings.clone(), vec![], true)
+    }
+
+    /// Evaluates an expression in the given bindings, and then works through
+    /// the stack of continuations until it is empty.
+    ///
+    /// `outermost` is only set when evaluating the expression given to
+    /// [`Evaluator::evaluate`], rather than a binding needed along the way.
+    fn run(
+        &self,
+        mut expr: Expr,
+        mut bindings: Bindings<Expr>,
+        mut continuations: Vec<Continuation<Expr>>,
+        outermost: bool,
+    ) -> EvaluatedBinding<Expr> {
+        let mut stack = self.budget.allocate(0);
+        // the most recent application of a function, blamed for errors in natives
+        let mut call_site = None;
+        loop {
+            stack.resize(continuations.len() * size_of::<Continuation<Expr>>());
+            self.budget.check()?;
+            if let Some(observe) = &self.observer {
+                // if nothing is waiting to use the value of this expression, it
+                // will be the result of the whole evaluation
+                let produces_result = outermost
+                    && continuations
+                        .iter()
+                        .rev()
+                        .all(|continuation| matches!(continuation, Continuation::Resolve(_)));
+                if observe(&expr, &bindings, produces_result).is_break() {
+                    return Err(Error::Interrupted);
+                }
             }
-            Expression::Identifier(name) => self.resolve(name, span),
-            Expression::Function(Function { parameter, body }) => {
-                Ok(CompletedEvaluation::Closure {
+            let Spanned {
+                span,
+                value: expression,
+            } = self.reader.read(expr);
+            let mut result = match expression.as_ref() {
+                Expression::Primitive(value) => Ok(CompletedEvaluation::Primitive(value.clone())),
+                Expression::Native(Native { implementation, .. }) => {
+                    implementation(&self.switch(bindings.clone()))
+                        .map(CompletedEvaluation::Primitive)
+                        .map_err(|error| error.located_at(call_site))
+                }
+                Expression::Identifier(name) => match bindings.read(name).cloned() {
+                    Some(binding) => match binding.unresolved_value() {
+                        Some((value, value_bindings)) => {
+                            continuations.push(Continuation::Resolve(binding));
+                            expr = value;
+                            bindings = value_bindings;
+                            continue;
+                        }
+                        None => resolved(binding.value().expect("Binding was not resolved.")),
+                    },
+                    None => Err(Error::unknown_variable(span, name, bindings.identifiers())),
+                },
+                Expression::Function(Function {
+                    parameter,
+                    parameter_type,
+                    body,
+                    ..
+                }) => Ok(CompletedEvaluation::Closure {
                     parameter: parameter.clone(),
+                    parameter_type: parameter_type.clone(),
                     body: body.clone(),
-                    bindings: self.bindings.clone(),
-                })
-            }
-            Expression::Apply(Apply { function, argument }) => {
-                let function_result = self.evaluate_inner(function.clone())?;
-                match function_result {
-                    CompletedEvaluation::Closure {
-                        parameter,
-                        body,
-                        bindings: function_bindings,
-                    } => self
-                        // the body is executed in the context of the function,
-                        // but the argument must be evaluated in the outer context
-                        .switch(function_bindings.with(
-                            parameter.clone(),
-                            argument.clone(),
-                            self.bindings.clone(),
-                        ))
-                        .evaluate_inner(body),
-                    _ => Err(Error::InvalidFunctionApplication { span }),
+                    bindings: bindings.clone(),
+                }),
+                Expression::Apply(Apply { function, argument }) => {
+                    continuations.push(Continuation::Apply {
+                        argument: argument.clone(),
+                        bindings: bindings.clone(),
+                        span,
+                    });
+                    expr = function.clone();
+                    continue;
                 }
-            }
-            Expression::Assign(Assign { name, value, inner }) => self
-                .switch(
-                    self.bindings
-                        .with(name.clone(), value.clone(), self.bindings.clone()),
-                )
-                .evaluate_inner(inner.clone()),
-            Expression::Match(Match { value, patterns }) => {
-                // Ensure we only evaluate the value once.
-                let mut value = Binding::unresolved((value.clone(), self.bindings.clone()));
-                for PatternMatch { pattern, result } in patterns {
-                    match pattern {
-                        Pattern::Anything => {
-                            return self.evaluate_inner(result.clone());
+                Expression::Assign(Assign { name, value, inner }) => {
+                    let binding = self.budget.binding((value.clone(), bindings.clone()));
+                    bindings = bindings.with_binding(name.clone(), binding);
+                    expr = inner.clone();
+                    continue;
+                }
+                Expression::AssignRecursive(AssignRecursive {
+                    bindings: group,
+                    inner,
+                }) => {
+                    bindings = bindings.with_recursive_using(group.iter().cloned(), |value| {
+                        self.budget.binding(value)
+                    });
+                    expr = inner.clone();
+                    continue;
+                }
+                Expression::Match(Match { value, patterns }) => {
+                    // Ensure we only evaluate the value once.
+                    let value = self.budget.binding((value.clone(), bindings.clone()));
+                    let mut selection = Selection::new(
+                        value,
+                        patterns.iter().map(
+                            |PatternMatch {
+                                 pattern, result, ..
+                             }| (pattern.clone(), result.clone()),
+                        ),
+                    );
+                    let selected = select(&mut selection, None);
+                    match self.continue_selection(
+                        selection,
+                        selected,
+                        bindings.clone(),
+                        span,
+                        call_site,
+                        &mut continuations,
+                    ) {
+                        Ok((next, next_bindings)) => {
+                            expr = next;
+                            bindings = next_bindings;
+                            continue;
+                        }
+                        Err(error) => Err(error),
+                    }
+                }
+                Expression::Typed(Typed { expression, .. }) => {
+                    expr = expression.clone();
+                    continue;
+                }
+                Expression::Sequence(Sequence { first, second }) => {
+                    continuations.push(Continuation::Sequence {
+                        next: second.clone(),
+                        bindings: bindings.clone(),
+                        call_site,
+                    });
+                    expr = first.clone();
+                    continue;
+                }
+                Expression::Tuple(Tuple { elements }) => Ok(CompletedEvaluation::Tuple(
+                    elements
+                        .iter()
+                        .map(|element| self.budget.binding((element.clone(), bindings.clone())))
+                        .collect(),
+                )),
+                Expression::List(List::Empty) => Ok(CompletedEvaluation::List(List::Empty)),
+                Expression::List(List::Cons { head, tail }) => {
+                    Ok(CompletedEvaluation::List(List::Cons {
+                        head: self.budget.binding((head.clone(), bindings.clone())),
+                        tail: self.budget.binding((tail.clone(), bindings.clone())),
+                    }))
+                }
+            };
+            // Feed the result to the continuations, until one of them has more
+            // to evaluate.
+            loop {
+                match continuations.pop() {
+                    None => {
+                        return result;
+                    }
+                    Some(Continuation::Resolve(binding)) => {
+                        result = store(&binding, result);
+                    }
+                    Some(Continuation::Apply {
+                        argument,
+                        bindings: argument_bindings,
+                        span,
+                    }) => match result {
+                        Ok(CompletedEvaluation::Closure {
+                            parameter,
+                            body,
+                            bindings: function_bindings,
+                            ..
+                        }) => {
+                            // the body is executed in the context of the function,
+                            // but the argument must be evaluated in the outer context
+                            call_site = span.or(call_site);
+                            bindings = self.bind_argument(
+                                function_bindings,
+                                parameter,
+                                argument,
+                                argument_bindings,
+                            );
+                            expr = body;
+                            break;
+                        }
+                        Ok(_) => {
+                            result = Err(Error::InvalidFunctionApplication { span });
+                        }
+                        Err(_) => {}
+                    },
+                    Some(Continuation::Sequence {
+                        next,
+                        bindings: next_bindings,
+                        call_site: next_call_site,
+                    }) => {
+                        if result.is_ok() {
+                            // evaluating the first expression may have applied
+                            // other functions, but we are back where we started
+                            call_site = next_call_site;
+                            expr = next;
+                            bindings = next_bindings;
+                            break;
                         }
-                        Pattern::Primitive(expected) => {
-                            let resolved_value = self.resolve_binding(&mut value)?;
-                            match resolved_value {
-                                CompletedEvaluation::Primitive(actual) if actual == *expected => {
-                                    return self.evaluate_inner(result.clone());
+                    }
+                    Some(Continuation::Match {
+                        mut selection,
+                        bindings: match_bindings,
+                        span,
+                        call_site: match_call_site,
+                    }) => {
+                        if let Ok(value) = result {
+                            call_site = match_call_site;
+                            let selected = select(&mut selection, Some(value));
+                            match self.continue_selection(
+                                selection,
+                                selected,
+                                match_bindings,
+                                span,
+                                call_site,
+                                &mut continuations,
+                            ) {
+                                Ok((next, next_bindings)) => {
+                                    expr = next;
+                                    bindings = next_bindings;
+                                    break;
+                                }
+                                Err(error) => {
+                                    result = Err(error);
                                 }
-                                _ => {}
                             }
                         }
                     }
                 }
-                Err(Error::MatchWithoutBaseCase { span })
             }
-            Expression::Typed(Typed { expression, typ: _ }) => {
-                self.evaluate_inner(expression.clone())
+        }
+    }
+
+    /// Binds a function's parameter to its argument.
+    ///
+    /// If the argument is just a variable, the parameter shares its binding.
+    /// This means that a tail-recursive function which passes a value along to
+    /// itself does not build up a chain of thunks, each waiting on the last.
+    fn bind_argument(
+        &self,
+        function_bindings: Bindings<Expr>,
+        parameter: Identifier,
+        argument: Expr,
+        mut argument_bindings: Bindings<Expr>,
+    ) -> Bindings<Expr> {
+        if let Expression::Identifier(name) = self.reader.read(argument.clone()).value.as_ref() {
+            if let Some(binding) = argument_bindings.read(name) {
+                return function_bindings.with_binding(parameter, binding.clone());
+            }
+        }
+        function_bindings.with_binding(
+            parameter,
+            self.budget.binding((argument, argument_bindings)),
+        )
+    }
+
+    /// Finds the expression to evaluate next, and the bindings in which to
+    /// evaluate it: either the result of the pattern that matched, or, if the
+    /// selection needs part of the value, that part, after which the selection
+    /// continues.
+    fn continue_selection(
+        &self,
+        selection: Selection<Binding<Expr>, Expr>,
+        selected: Result<Selected<Binding<Expr>, Expr>>,
+        bindings: Bindings<Expr>,
+        span: Option<Span>,
+        call_site: Option<Span>,
+        continuations: &mut Vec<Continuation<Expr>>,
+    ) -> Result<(Expr, Bindings<Expr>)> {
+        match selected? {
+            Selected::Matched { result, variables } => {
+                Ok((result, bindings.with_bindings(variables)))
+            }
+            Selected::NoMatch => Err(Error::MatchWithoutBaseCase { span }),
+            Selected::Evaluate(binding) => {
+                let unresolved = binding
+                    .unresolved_value()
+                    .expect("Binding was already evaluated.");
+                continuations.push(Continuation::Match {
+                    selection,
+                    bindings,
+                    span,
+                    call_site,
+                });
+                continuations.push(Continuation::Resolve(binding));
+                Ok(unresolved)
             }
         }
     }
@@ -130,37 +434,51 @@ impl<Expr: Clone, Reader: ExpressionReader<Expr = Expr>> RecursiveEvaluator<Expr
     fn resolve(&self, identifier: &Identifier, span: Option<Span>) -> EvaluatedBinding<Expr> {
         match self.bindings.clone().read(identifier) {
             Some(binding) => self.resolve_binding(binding),
-            None => Err(Error::UnknownVariable {
+            None => Err(Error::unknown_variable(
                 span,
-                name: identifier.to_string(),
-            }),
+                identifier,
+                self.bindings.identifiers(),
+            )),
         }
     }
 
-    /// Resolves a given binding in context.
+    /// Resolves a given binding immediately; see [`boo_evaluation_lazy::force`].
     fn resolve_binding(&self, binding: &mut Binding<Expr>) -> EvaluatedBinding<Expr> {
-        let result = binding.resolve_by(move |(value, thunk_bindings)| {
-            self.switch(thunk_bindings.clone())
-                .evaluate_inner(value.clone())
-        });
-        Arc::try_unwrap(result).unwrap_or_else(|arc| (*arc).clone())
+        boo_evaluation_lazy::force(binding, |(value, value_bindings)| {
+            self.run(value, value_bindings, vec![], false)
+        })
     }
 
     fn switch(&self, new_bindings: Bindings<Expr>) -> Self {
         Self {
             reader: self.reader,
             bindings: new_bindings,
+            budget: self.budget.clone(),
+            observer: self.observer.clone(),
+            integer_overflow: self.integer_overflow,
         }
     }
 }
 
-impl<Expr: Clone, Reader: ExpressionReader<Expr = Expr>> NativeContext
+impl<Expr: Clone + 'static, Reader: ExpressionReader<Expr = Expr>> NativeContext
     for RecursiveEvaluator<Expr, Reader>
 {
     fn lookup_value(&self, identifier: &Identifier) -> Result<Primitive> {
-        match self.resolve(identifier, None)?.finish() {
-            Evaluated::Primitive(primitive) => Ok(primitive),
-            Evaluated::Function(_) => Err(Error::InvalidPrimitive { span: None }),
+        match self.resolve(identifier, None)? {
+            CompletedEvaluation::Primitive(primitive) => Ok(primitive),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        }
+    }
+
+    fn apply(&self, function: &Identifier, argument: Primitive) -> Result<Primitive> {
+        let (body, bindings) = self.resolve(function, None)?.apply_to(argument)?;
+        match self.run(body, bindings, vec![], false)? {
+            CompletedEvaluation::Primitive(primitive) => Ok(primitive),
+            _ => Err(Error::InvalidPrimitive { span: None }),
         }
     }
+
+    fn integer_overflow(&self) -> IntegerOverflow {
+        self.integer_overflow
+    }
 }
@@ -1,46 +1,71 @@
-use proptest::prelude::*;
-
 use boo_core::builtins;
 use boo_core::evaluation::*;
-use boo_test_helpers::proptest::*;
+use boo_core::primitive::Primitive;
 
 #[test]
 fn test_evaluation_gets_the_same_result_as_reducing_evaluation() {
-    let reducing_evaluator = {
-        let mut context = boo_evaluation_reduction::new();
-        builtins::prepare(&mut context).unwrap();
-        context.evaluator()
-    };
-    let recursive_evaluator = {
+    let recursive_evaluator = boo_conformance::prepared(boo_evaluation_recursive::new());
+    boo_conformance::check_against_reference(&recursive_evaluator);
+}
+
+#[test]
+fn test_applying_functions_gets_the_same_result_as_reducing_evaluation() {
+    let recursive_evaluator = boo_conformance::prepared(boo_evaluation_recursive::new());
+    boo_conformance::check_applications_against_reference(&recursive_evaluator);
+}
+
+#[test]
+fn test_failing_gets_the_same_error_as_reducing_evaluation() {
+    let recursive_evaluator = boo_conformance::prepared(boo_evaluation_recursive::new());
+    boo_conformance::check_failures_against_reference(&recursive_evaluator);
+}
+
+#[test]
+fn test_integer_overflow_fails_in_the_same_way_as_reducing_evaluation() {
+    let recursive_evaluator = boo_conformance::prepared(
+        boo_evaluation_recursive::new().with_integer_overflow(IntegerOverflow::Fail),
+    );
+    boo_conformance::check_integer_overflow_fails(&recursive_evaluator);
+}
+
+#[test]
+fn test_natives_can_apply_functions() {
+    let recursive_evaluator = boo_conformance::prepared(boo_evaluation_recursive::new());
+    boo_conformance::check_natives_can_apply_functions(&recursive_evaluator);
+}
+
+#[test]
+fn test_lists_bound_identifiers() {
+    boo_conformance::check_bound_identifiers_are_listed(boo_evaluation_recursive::new());
+}
+
+#[test]
+fn test_evaluates_deeply_nested_expressions() {
+    boo_conformance::check_deeply_nested_expressions_in_a_small_stack(
+        boo_evaluation_recursive::new,
+    );
+}
+
+#[test]
+fn test_evaluates_deeply_nested_matches() {
+    boo_conformance::check_deeply_nested_matches_in_a_small_stack(boo_evaluation_recursive::new);
+}
+
+#[test]
+fn test_evaluates_deeply_recursive_functions() -> boo_core::error::Result<()> {
+    let evaluator = {
         let mut context = boo_evaluation_recursive::new();
-        builtins::prepare(&mut context).unwrap();
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
         context.evaluator()
     };
+    let program = "let rec count = fn n -> match n { 0 -> 0; _ -> count (n - 1) } in count 10000";
+    let expr = boo_parser::parse_expr(program)?.to_core()?;
+
+    let result = evaluator.evaluate(expr);
 
-    check(&boo_generator::arbitrary(), |expr| {
-        let core_expr = expr.clone().to_core()?;
-        let expected = reducing_evaluator.evaluate(core_expr.clone());
-        let actual = recursive_evaluator.evaluate(core_expr);
-
-        match (expected, actual) {
-            (Ok(Evaluated::Primitive(expected)), Ok(Evaluated::Primitive(actual))) => {
-                prop_assert_eq!(expected, actual);
-            }
-            (Ok(expected), Ok(actual)) => prop_assert!(
-                false,
-                "did not finish evaluation\n  left:   `{}`,\n  right:  `{}`\n  input:  {}\n",
-                expected,
-                actual,
-                expr
-            ),
-            (expected, actual) => prop_assert!(
-                false,
-                "evaluation failed\n  left:   `{:?}`,\n  right:  `{:?}`\n  input:  {}\n",
-                expected,
-                actual,
-                expr
-            ),
-        }
-        Ok(())
-    })
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(0.into())))
+    );
+    Ok(())
 }
@@ -4,10 +4,14 @@
 //! that is "so simple that there are obviously no deficiencies" (to quote Tony
 //! Hoare). We then use it as a reference implementation to validate that the
 //! real evaluator works correctly when presented with an arbitrary program.
+//!
+//! Values are not copied into each place they are substituted, but shared
+//! through a [`Heap`], so that each is evaluated at most once. Otherwise,
+//! evaluating a program can take exponentially longer than it should.
 
-use std::rc::Rc;
-
-use im::HashSet;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex, Weak};
 
 use boo_core::ast::*;
 use boo_core::error::*;
@@ -16,6 +20,7 @@ use boo_core::expr::Expr;
 use boo_core::identifier::*;
 use boo_core::native::*;
 use boo_core::primitive::*;
+use boo_core::span::Span;
 
 pub fn new() -> impl EvaluationContext {
     ReducingEvaluator::new()
@@ -24,11 +29,23 @@ pub fn new() -> impl EvaluationContext {
 /// Evaluates an AST using beta reduction.
 pub struct ReducingEvaluator {
     bindings: Vec<(Identifier, Expr)>,
+    integer_overflow: IntegerOverflow,
 }
 
 impl ReducingEvaluator {
     pub fn new() -> Self {
-        Self { bindings: vec![] }
+        Self {
+            bindings: vec![],
+            integer_overflow: IntegerOverflow::default(),
+        }
+    }
+
+    /// Decides how arithmetic on small integers handles overflow.
+    pub fn with_integer_overflow(self, integer_overflow: IntegerOverflow) -> Self {
+        Self {
+            integer_overflow,
+            ..self
+        }
     }
 }
 
@@ -46,6 +63,16 @@ impl EvaluationContext for ReducingEvaluator {
         Ok(())
     }
 
+    fn identifiers(&self) -> Vec<Identifier> {
+        let mut identifiers: Vec<Identifier> = Vec::new();
+        for (identifier, _) in self.bindings.iter() {
+            if !identifiers.contains(identifier) {
+                identifiers.push(identifier.clone());
+            }
+        }
+        identifiers
+    }
+
     fn evaluator(self) -> Self::Eval {
         self
     }
@@ -53,18 +80,167 @@ impl EvaluationContext for ReducingEvaluator {
 
 impl Evaluator for ReducingEvaluator {
     fn evaluate(&self, expr: Expr) -> Result<Evaluated> {
-        let mut prepared = expr;
-        for (identifier, value) in self.bindings.iter().rev() {
-            prepared = Expr::new(
-                None,
-                Expression::Assign(Assign {
-                    name: identifier.clone(),
-                    value: value.clone(),
-                    inner: prepared,
-                }),
-            );
+        let heap = Heap::new(self.integer_overflow);
+        let (prepared, _) = self.prepare(&heap, expr);
+        evaluate(&heap, prepared)
+    }
+}
+
+impl ReducingEvaluator {
+    /// Evaluates an expression one step at a time, calling `observe` with the
+    /// expression as it stands before each step.
+    ///
+    /// Only the outermost expression is observed; anything evaluated on its
+    /// behalf, such as a shared value or a value being matched, is reduced
+    /// within a single step. Each expression is shown with shared values in
+    /// place, and with the bindings of the context referred to by name.
+    ///
+    /// Built-in operations are shown as they are written: the arguments are
+    /// each reduced in turn, and then the operation is run in a single step.
+    /// Steps which would show the inner workings of a built-in are skipped.
+    ///
+    /// If `observe` returns [`ControlFlow::Break`], evaluation stops with
+    /// [`Error::Interrupted`].
+    pub fn evaluate_stepwise(
+        &self,
+        expr: Expr,
+        mut observe: impl FnMut(&Expr) -> ControlFlow<()>,
+    ) -> Result<Evaluated> {
+        let heap = Heap::new(self.integer_overflow);
+        let (mut progress, names) = self.prepare(&heap, expr);
+        loop {
+            let shown = unlabel_except(&heap, &names, progress.clone());
+            if !contains_native(&shown) && observe(&shown).is_break() {
+                return Err(Error::Interrupted);
+            }
+            match step_visibly(&heap, progress)? {
+                Progress::Next(next) => {
+                    progress = next;
+                }
+                Progress::Complete(complete) => {
+                    return evaluate(&heap, complete);
+                }
+            }
+        }
+    }
+
+    /// Substitutes each binding into the expression.
+    ///
+    /// Also returns the name of the binding that each label on the heap refers
+    /// to, if it refers to one.
+    fn prepare(&self, heap: &Heap, expr: Expr) -> (Expr, HashMap<usize, Identifier>) {
+        // each binding can refer to those before it, and later bindings
+        // shadow earlier ones, so the latest is substituted first
+        let mut substitutions: Vec<Substitution> = Vec::with_capacity(self.bindings.len());
+        let mut names = HashMap::new();
+        for (identifier, value) in self.bindings.iter() {
+            let value = substitutions
+                .iter()
+                .rev()
+                .fold(value.clone(), |value, substitution| {
+                    substitute(substitution.clone(), value)
+                });
+            let substitution = heap.global_substitution(identifier.clone(), value);
+            if let Expression::Identifier(Identifier::Label(label)) =
+                substitution.value.expression()
+            {
+                names.insert(*label, identifier.clone());
+            }
+            substitutions.push(substitution);
+        }
+        let prepared = substitutions
+            .into_iter()
+            .rev()
+            .fold(expr, |prepared, substitution| {
+                substitute(substitution, prepared)
+            });
+        (prepared, names)
+    }
+}
+
+/// Values which have been substituted into an expression.
+///
+/// Rather than substituting a value for each occurrence of a name, the value
+/// is stored on the heap, and the name is replaced with a label referring to
+/// it. The first time the label is evaluated, the value is replaced with the
+/// result, which is then shared by every occurrence.
+///
+/// As natives can run wherever a value is needed, the heap also records how
+/// they handle integer overflow.
+#[derive(Clone)]
+struct Heap(Arc<HeapValues>);
+
+struct HeapValues {
+    values: Mutex<Vec<Expr>>,
+    integer_overflow: IntegerOverflow,
+}
+
+impl Heap {
+    fn new(integer_overflow: IntegerOverflow) -> Self {
+        Self(Arc::new(HeapValues {
+            values: Mutex::new(vec![]),
+            integer_overflow,
+        }))
+    }
+
+    /// Prepares to substitute the value for the name, storing it on the heap.
+    fn substitution(&self, name: Identifier, value: Expr) -> Substitution {
+        Substitution {
+            name: name.into(),
+            value: self.share(value).into(),
+            heap: Arc::downgrade(&self.0),
+            into_natives: true,
         }
-        evaluate(prepared)
+    }
+
+    /// Prepares to substitute a top-level binding.
+    ///
+    /// Natives only look up the parameters of the functions around them, and
+    /// so never refer to a top-level binding. Skipping them avoids wrapping
+    /// each native in another layer of context for every binding.
+    fn global_substitution(&self, name: Identifier, value: Expr) -> Substitution {
+        Substitution {
+            into_natives: false,
+            ..self.substitution(name, value)
+        }
+    }
+
+    /// Stores the expression on the heap, returning a label that refers to it.
+    ///
+    /// Primitives and labels are returned as they are, as there is nothing to
+    /// be gained by sharing them.
+    fn share(&self, expr: Expr) -> Expr {
+        match expr.expression() {
+            Expression::Primitive(_) | Expression::Identifier(Identifier::Label(_)) => expr,
+            _ => {
+                let span = expr.span();
+                let mut values = self.0.values.lock().unwrap();
+                let label = values.len();
+                values.push(expr);
+                Expr::new(span, Expression::Identifier(Identifier::Label(label)))
+            }
+        }
+    }
+
+    /// Reads the value that a label refers to, without evaluating it.
+    fn read(&self, label: usize) -> Expr {
+        self.0.values.lock().unwrap()[label].clone()
+    }
+
+    /// Shares the arguments of the application that a label refers to, so
+    /// that they can be taken apart without being evaluated more than once.
+    fn share_arguments(&self, label: usize) -> Expr {
+        let shared = share_arguments(self, self.read(label));
+        self.0.values.lock().unwrap()[label] = shared.clone();
+        shared
+    }
+
+    /// Evaluates the value that a label refers to, replacing it with the
+    /// result.
+    fn force(&self, label: usize) -> Result<Expr> {
+        let result = complete(self, self.read(label))?;
+        self.0.values.lock().unwrap()[label] = result.clone();
+        Ok(result)
     }
 }
 
@@ -73,68 +249,153 @@ enum Progress<T> {
     Complete(T),
 }
 
-struct EmptyContext {}
+struct EmptyContext {
+    integer_overflow: IntegerOverflow,
+}
 
 impl NativeContext for EmptyContext {
     fn lookup_value(&self, identifier: &Identifier) -> Result<Primitive> {
         Err(Error::UnknownVariable {
             span: None,
             name: identifier.to_string(),
+            suggestions: None,
+        })
+    }
+
+    fn apply(&self, function: &Identifier, _argument: Primitive) -> Result<Primitive> {
+        Err(Error::UnknownVariable {
+            span: None,
+            name: function.to_string(),
+            suggestions: None,
         })
     }
+
+    fn integer_overflow(&self) -> IntegerOverflow {
+        self.integer_overflow
+    }
 }
 
 struct AdditionalContext<'a> {
-    name: Rc<Identifier>,
-    value: Rc<Expr>,
+    substitution: &'a Substitution,
     rest: &'a dyn NativeContext,
 }
 
+impl<'a> AdditionalContext<'a> {
+    /// Evaluates the expression on the heap of the substitution.
+    fn evaluate(&self, expr: Expr) -> Result<Evaluated> {
+        let heap = Heap(
+            self.substitution
+                .heap
+                .upgrade()
+                .expect("The heap was dropped during evaluation."),
+        );
+        evaluate(&heap, expr)
+    }
+}
+
 impl<'a> NativeContext for AdditionalContext<'a> {
     fn lookup_value(&self, identifier: &Identifier) -> Result<Primitive> {
-        if identifier == self.name.as_ref() {
-            match evaluate((*self.value).clone())? {
+        if identifier == self.substitution.name.as_ref() {
+            match self.evaluate((*self.substitution.value).clone())? {
                 Evaluated::Primitive(primitive) => Ok(primitive),
-                Evaluated::Function(_) => Err(Error::InvalidPrimitive { span: None }),
+                _ => Err(Error::InvalidPrimitive { span: None }),
             }
         } else {
             self.rest.lookup_value(identifier)
         }
     }
+
+    fn apply(&self, function: &Identifier, argument: Primitive) -> Result<Primitive> {
+        if function == self.substitution.name.as_ref() {
+            let application = Expr::new(
+                None,
+                Expression::Apply(Apply {
+                    function: (*self.substitution.value).clone(),
+                    argument: Expr::new(None, Expression::Primitive(argument)),
+                }),
+            );
+            match self.evaluate(application)? {
+                Evaluated::Primitive(primitive) => Ok(primitive),
+                _ => Err(Error::InvalidPrimitive { span: None }),
+            }
+        } else {
+            self.rest.apply(function, argument)
+        }
+    }
+
+    fn integer_overflow(&self) -> IntegerOverflow {
+        self.rest.integer_overflow()
+    }
 }
 
-fn evaluate(expr: Expr) -> Result<Evaluated> {
+fn evaluate(heap: &Heap, expr: Expr) -> Result<Evaluated> {
+    match complete(heap, expr)?.take() {
+        Expression::Primitive(primitive) => Ok(Evaluated::Primitive(primitive)),
+        Expression::Function(function) => Ok(Evaluated::Function(Function {
+            body: unlabel(heap, function.body),
+            ..function
+        })),
+        Expression::Tuple(Tuple { elements }) => elements
+            .into_iter()
+            .map(|element| evaluate(heap, element))
+            .collect::<Result<_>>()
+            .map(Evaluated::Tuple),
+        Expression::List(list) => {
+            let mut elements = vec![];
+            let mut next = list;
+            while let List::Cons { head, tail } = next {
+                elements.push(evaluate(heap, head)?);
+                next = match complete(heap, tail)?.take() {
+                    Expression::List(list) => list,
+                    _ => return Err(Error::InvalidPrimitive { span: None }),
+                };
+            }
+            Ok(Evaluated::List(elements))
+        }
+        _ => unreachable!("Evaluated to a non-final expression."),
+    }
+}
+
+/// Steps through the expression until it cannot be reduced any further.
+///
+/// Note that the elements of a tuple or list are not evaluated.
+fn complete(heap: &Heap, expr: Expr) -> Result<Expr> {
     let mut progress = expr;
     loop {
-        match step(progress)? {
+        match step(heap, progress)? {
             Progress::Next(next) => {
                 progress = next;
             }
             Progress::Complete(complete) => {
-                return match complete.take() {
-                    Expression::Primitive(primitive) => Ok(Evaluated::Primitive(primitive)),
-                    Expression::Function(function) => Ok(Evaluated::Function(function)),
-                    _ => unreachable!("Evaluated to a non-final expression."),
-                };
+                return Ok(complete);
             }
         }
     }
 }
 
-fn step(expr: Expr) -> Result<Progress<Expr>> {
+fn step(heap: &Heap, expr: Expr) -> Result<Progress<Expr>> {
     let span = expr.span();
     match expr.take() {
         expression @ Expression::Primitive(_) | expression @ Expression::Function(_) => {
             Ok(Progress::Complete(Expr::new(span, expression)))
         }
-        Expression::Native(Native { implementation, .. }) => implementation(&EmptyContext {})
-            .map(|x| Progress::Complete(Expr::new(span, Expression::Primitive(x)))),
+        Expression::Native(Native { implementation, .. }) => implementation(&EmptyContext {
+            integer_overflow: heap.0.integer_overflow,
+        })
+        .map(|x| Progress::Complete(Expr::new(span, Expression::Primitive(x))))
+        .map_err(|error| error.located_at(span)),
+        Expression::Identifier(Identifier::Label(label)) => {
+            heap.force(label).map(Progress::Complete)
+        }
+        // Bound variables have already been substituted, so there is nothing
+        // in scope to suggest.
         Expression::Identifier(name) => Err(Error::UnknownVariable {
             span,
             name: name.to_string(),
+            suggestions: None,
         }),
         Expression::Apply(Apply { function, argument }) => {
-            let function_result = step(function)?;
+            let function_result = step(heap, function)?;
             match function_result {
                 Progress::Next(function_next) => Ok(Progress::Next(Expr::new(
                     span,
@@ -144,86 +405,395 @@ fn step(expr: Expr) -> Result<Progress<Expr>> {
                     }),
                 ))),
                 Progress::Complete(function_complete) => match function_complete.take() {
-                    Expression::Function(Function { parameter, body }) => {
-                        let substituted_body = substitute(
-                            Substitution {
-                                name: parameter.into(),
-                                value: argument.into(),
-                            },
-                            body,
-                            HashSet::new(),
-                        );
-                        Ok(Progress::Next(substituted_body))
+                    Expression::Function(Function {
+                        parameter, body, ..
+                    }) => {
+                        let substituted_body =
+                            substitute(heap.substitution(parameter, argument), body);
+                        Ok(Progress::Next(located(substituted_body, span)))
                     }
                     _ => Err(Error::InvalidFunctionApplication { span }),
                 },
             }
         }
         Expression::Assign(Assign { name, value, inner }) => {
-            let substituted_inner = substitute(
-                Substitution {
-                    name: name.into(),
-                    value: value.into(),
-                },
-                inner,
-                HashSet::new(),
-            );
-            Ok(Progress::Next(substituted_inner))
+            let substituted_inner = substitute(heap.substitution(name, value), inner);
+            Ok(Progress::Next(located(substituted_inner, span)))
+        }
+        Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+            // each name is replaced with its value, wrapped in the same group so
+            // that any further references are unfolded on demand
+            let substituted_inner =
+                bindings
+                    .iter()
+                    .fold(inner, |substituted_inner, (name, value)| {
+                        let unfolded = Expr::new(
+                            span,
+                            Expression::AssignRecursive(AssignRecursive {
+                                bindings: bindings.clone(),
+                                inner: value.clone(),
+                            }),
+                        );
+                        substitute(heap.substitution(name.clone(), unfolded), substituted_inner)
+                    });
+            Ok(Progress::Next(located(substituted_inner, span)))
         }
         Expression::Match(Match {
             value,
             mut patterns,
         }) => {
-            let PatternMatch { pattern, result } = patterns
+            let PatternMatch {
+                pattern, result, ..
+            } = patterns
                 .pop_front()
                 .ok_or(Error::MatchWithoutBaseCase { span })?;
-            match pattern {
-                Pattern::Anything => Ok(Progress::Next(result)),
-                _ => match step(value)? {
-                    Progress::Next(value_next) => {
-                        // re-insert the pattern and try again
-                        patterns.push_front(PatternMatch { pattern, result });
-                        Ok(Progress::Next(Expr::new(
-                            span,
-                            Expression::Match(Match {
-                                value: value_next,
-                                patterns,
-                            }),
-                        )))
-                    }
-                    Progress::Complete(value_complete) => match pattern {
-                        Pattern::Anything => unreachable!("Case should be handled already."),
-                        Pattern::Primitive(expected) => match value_complete.expression() {
-                            Expression::Primitive(actual) if actual == &expected => {
-                                Ok(Progress::Next(result))
-                            }
-                            // if not matched, try again, having discarded the first pattern
-                            _ => Ok(Progress::Next(Expr::new(
-                                span,
-                                Expression::Match(Match {
-                                    value: value_complete,
-                                    patterns,
-                                }),
-                            ))),
-                        },
+            let (value_next, matched) = match_pattern(heap, &pattern, value)?;
+            match matched {
+                // if matched, assign each of the bound variables in turn
+                Some(assignments) => Ok(Progress::Next(assignments.into_iter().rev().fold(
+                    located(result, span),
+                    |inner, (name, value)| {
+                        Expr::new(span, Expression::Assign(Assign { name, value, inner }))
                     },
-                },
+                ))),
+                // if not matched, try again, having discarded the first pattern
+                None => Ok(Progress::Next(Expr::new(
+                    span,
+                    Expression::Match(Match {
+                        value: value_next,
+                        patterns,
+                    }),
+                ))),
+            }
+        }
+        Expression::Typed(Typed { expression, .. }) => {
+            Ok(Progress::Next(located(expression, span)))
+        }
+        Expression::Sequence(Sequence { first, second }) => {
+            complete(heap, first)?;
+            Ok(Progress::Next(located(second, span)))
+        }
+        // the elements are shared, so that they are only evaluated once
+        Expression::Tuple(Tuple { elements }) => Ok(Progress::Complete(Expr::new(
+            span,
+            Expression::Tuple(Tuple {
+                elements: elements
+                    .into_iter()
+                    .map(|element| heap.share(element))
+                    .collect(),
+            }),
+        ))),
+        expression @ Expression::List(List::Empty) => {
+            Ok(Progress::Complete(Expr::new(span, expression)))
+        }
+        Expression::List(List::Cons { head, tail }) => Ok(Progress::Complete(Expr::new(
+            span,
+            Expression::List(List::Cons {
+                head: heap.share(head),
+                tail: heap.share(tail),
+            }),
+        ))),
+    }
+}
+
+/// Takes a step, as [`step`] does, except that a native function applied to
+/// all of its arguments is reduced the way it is written.
+///
+/// Each argument that is not yet a value is reduced by a step of its own, and
+/// once they all are, the native function is run to completion in one step.
+fn step_visibly(heap: &Heap, expr: Expr) -> Result<Progress<Expr>> {
+    let Some(arity) = saturated_native(heap, &expr, 0) else {
+        return step(heap, expr);
+    };
+    let (function, mut arguments) = unapply(heap, expr);
+    for index in 0..arity {
+        let argument = &arguments[index].1;
+        if let Some(value) = evaluated(heap, argument) {
+            arguments[index].1 = value;
+            continue;
+        }
+        let (Progress::Next(argument_next) | Progress::Complete(argument_next)) =
+            step_visibly(heap, argument.clone())?;
+        arguments[index].1 = argument_next;
+        return Ok(Progress::Next(reapply(function, arguments)));
+    }
+    let rest = arguments.split_off(arity);
+    let result = complete(heap, reapply(function, arguments))?;
+    if rest.is_empty() {
+        Ok(Progress::Complete(result))
+    } else {
+        Ok(Progress::Next(reapply(result, rest)))
+    }
+}
+
+/// If the expression applies a native function to at least as many arguments
+/// as it takes, gives the number that it takes.
+///
+/// Labels are followed, so that partial applications are seen through.
+fn saturated_native(heap: &Heap, expr: &Expr, applied: usize) -> Option<usize> {
+    match expr.expression() {
+        Expression::Apply(Apply { function, .. }) => saturated_native(heap, function, applied + 1),
+        Expression::Identifier(Identifier::Label(label)) => {
+            saturated_native(heap, &heap.read(*label), applied)
+        }
+        Expression::Function(_) => {
+            let mut arity = 0;
+            let mut body = expr;
+            while let Expression::Function(Function { body: inner, .. }) = body.expression() {
+                arity += 1;
+                body = inner;
+            }
+            match body.expression() {
+                Expression::Native(_) if applied >= arity => Some(arity),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Takes an application apart into the function and its arguments, along with
+/// the location of each application, looking through labels that refer to
+/// partial applications.
+fn unapply(heap: &Heap, expr: Expr) -> (Expr, Vec<(Option<Span>, Expr)>) {
+    let mut arguments = vec![];
+    let mut function = expr;
+    loop {
+        let span = function.span();
+        match function.take() {
+            Expression::Apply(Apply {
+                function: inner,
+                argument,
+            }) => {
+                arguments.push((span, argument));
+                function = inner;
+            }
+            Expression::Identifier(Identifier::Label(label))
+                if matches!(heap.read(label).expression(), Expression::Apply(_)) =>
+            {
+                function = heap.share_arguments(label);
+            }
+            expression => {
+                arguments.reverse();
+                return (Expr::new(span, expression), arguments);
+            }
+        }
+    }
+}
+
+/// Applies the function to each of the arguments in turn.
+fn reapply(function: Expr, arguments: Vec<(Option<Span>, Expr)>) -> Expr {
+    arguments
+        .into_iter()
+        .fold(function, |function, (span, argument)| {
+            Expr::new(span, Expression::Apply(Apply { function, argument }))
+        })
+}
+
+/// Shares each argument of the application.
+fn share_arguments(heap: &Heap, expr: Expr) -> Expr {
+    let span = expr.span();
+    match expr.take() {
+        Expression::Apply(Apply { function, argument }) => Expr::new(
+            span,
+            Expression::Apply(Apply {
+                function: share_arguments(heap, function),
+                argument: heap.share(argument),
+            }),
+        ),
+        expression => Expr::new(span, expression),
+    }
+}
+
+/// The value of the expression, if it has already been evaluated.
+fn evaluated(heap: &Heap, expr: &Expr) -> Option<Expr> {
+    match expr.expression() {
+        Expression::Primitive(_)
+        | Expression::Function(_)
+        | Expression::Tuple(_)
+        | Expression::List(_) => Some(expr.clone()),
+        Expression::Identifier(Identifier::Label(label)) => evaluated(heap, &heap.read(*label)),
+        _ => None,
+    }
+}
+
+/// Whether a native function appears anywhere within the expression.
+///
+/// Built-ins are referred to by name, so natives only appear while a built-in
+/// is in the middle of being evaluated.
+fn contains_native(expr: &Expr) -> bool {
+    match expr.expression() {
+        Expression::Primitive(_) | Expression::Identifier(_) => false,
+        Expression::Native(_) => true,
+        Expression::Function(Function { body, .. }) => contains_native(body),
+        Expression::Apply(Apply { function, argument }) => {
+            contains_native(function) || contains_native(argument)
+        }
+        Expression::Assign(Assign { value, inner, .. }) => {
+            contains_native(value) || contains_native(inner)
+        }
+        Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+            bindings.iter().any(|(_, value)| contains_native(value)) || contains_native(inner)
+        }
+        Expression::Match(Match { value, patterns }) => {
+            contains_native(value)
+                || patterns
+                    .iter()
+                    .any(|PatternMatch { result, .. }| contains_native(result))
+        }
+        Expression::Typed(Typed { expression, .. }) => contains_native(expression),
+        Expression::Sequence(Sequence { first, second }) => {
+            contains_native(first) || contains_native(second)
+        }
+        Expression::Tuple(Tuple { elements }) => elements.iter().any(contains_native),
+        Expression::List(List::Empty) => false,
+        Expression::List(List::Cons { head, tail }) => {
+            contains_native(head) || contains_native(tail)
+        }
+    }
+}
+
+/// Gives an expression without a location the location of the expression it
+/// was reached from.
+///
+/// Built-ins have no location, so this means that natives, which do not know
+/// where they were called from, can report errors at the call site.
+fn located(expr: Expr, span: Option<Span>) -> Expr {
+    if expr.span().is_none() {
+        Expr::new(span, expr.take())
+    } else {
+        expr
+    }
+}
+
+/// The variables bound by a pattern, in order.
+type Assignments = Vec<(Identifier, Expr)>;
+
+/// Matches the value against the pattern, evaluating only as much as required.
+///
+/// Returns the value, evaluated as far as was necessary, along with the
+/// variables bound by the pattern if it matched.
+fn match_pattern(
+    heap: &Heap,
+    pattern: &Pattern,
+    value: Expr,
+) -> Result<(Expr, Option<Assignments>)> {
+    match pattern {
+        Pattern::Anything => Ok((value, Some(vec![]))),
+        Pattern::Variable(name) => Ok((value.clone(), Some(vec![(name.clone(), value)]))),
+        Pattern::Some(_) | Pattern::None | Pattern::Ok(_) | Pattern::Err(_) => {
+            match_pattern(heap, &pattern.encoded(), value)
+        }
+        Pattern::Primitive(expected) => {
+            let value_complete = complete(heap, value)?;
+            let matched = match value_complete.expression() {
+                Expression::Primitive(actual) if actual == expected => Some(vec![]),
+                _ => None,
+            };
+            Ok((value_complete, matched))
+        }
+        Pattern::Tuple(patterns) => {
+            let value_complete = complete(heap, value)?;
+            let span = value_complete.span();
+            match value_complete.take() {
+                Expression::Tuple(Tuple { elements }) if elements.len() == patterns.len() => {
+                    let mut matched = Some(vec![]);
+                    let mut elements_next = Vec::with_capacity(elements.len());
+                    for (pattern, element) in patterns.iter().zip(elements) {
+                        match matched {
+                            // stop evaluating elements once the match has failed
+                            None => {
+                                elements_next.push(element);
+                            }
+                            Some(mut assignments) => {
+                                let (element_next, element_matched) =
+                                    match_pattern(heap, pattern, element)?;
+                                elements_next.push(element_next);
+                                matched = element_matched.map(|element_assignments| {
+                                    assignments.extend(element_assignments);
+                                    assignments
+                                });
+                            }
+                        }
+                    }
+                    let value_next = Expr::new(
+                        span,
+                        Expression::Tuple(Tuple {
+                            elements: elements_next,
+                        }),
+                    );
+                    Ok((value_next, matched))
+                }
+                expression => Ok((Expr::new(span, expression), None)),
+            }
+        }
+        Pattern::EmptyList => {
+            let value_complete = complete(heap, value)?;
+            let matched = match value_complete.expression() {
+                Expression::List(List::Empty) => Some(vec![]),
+                _ => None,
+            };
+            Ok((value_complete, matched))
+        }
+        Pattern::Cons {
+            head: head_pattern,
+            tail: tail_pattern,
+        } => {
+            let value_complete = complete(heap, value)?;
+            let span = value_complete.span();
+            match value_complete.take() {
+                Expression::List(List::Cons { head, tail }) => {
+                    let (head_next, head_matched) = match_pattern(heap, head_pattern, head)?;
+                    let (tail_next, matched) = match head_matched {
+                        // stop evaluating once the match has failed
+                        None => (tail, None),
+                        Some(mut assignments) => {
+                            let (tail_next, tail_matched) =
+                                match_pattern(heap, tail_pattern, tail)?;
+                            let matched = tail_matched.map(|tail_assignments| {
+                                assignments.extend(tail_assignments);
+                                assignments
+                            });
+                            (tail_next, matched)
+                        }
+                    };
+                    let value_next = Expr::new(
+                        span,
+                        Expression::List(List::Cons {
+                            head: head_next,
+                            tail: tail_next,
+                        }),
+                    );
+                    Ok((value_next, matched))
+                }
+                expression => Ok((Expr::new(span, expression), None)),
             }
         }
-        Expression::Typed(Typed { expression, typ: _ }) => Ok(Progress::Next(expression)),
     }
 }
 
 #[derive(Debug, Clone)]
 struct Substitution {
-    name: Rc<Identifier>,
-    value: Rc<Expr>,
+    name: Arc<Identifier>,
+    value: Arc<Expr>,
+    // natives hold on to substitutions, and so must not keep the heap alive
+    heap: Weak<HeapValues>,
+    // whether natives can look up the name
+    into_natives: bool,
 }
 
-fn substitute(substitution: Substitution, expr: Expr, bound: HashSet<Identifier>) -> Expr {
+/// Substitutes the value for each occurrence of the name.
+///
+/// The value is always a label or a primitive, neither of which can be
+/// captured by the names bound within the expression, so there is no need to
+/// rename anything.
+fn substitute(substitution: Substitution, expr: Expr) -> Expr {
     let span = expr.span();
     match expr.take() {
         expression @ Expression::Primitive(_) => Expr::new(span, expression),
+        expression @ Expression::Native(_) if !substitution.into_natives => {
+            Expr::new(span, expression)
+        }
         Expression::Native(Native {
             unique_name,
             implementation,
@@ -231,116 +801,234 @@ fn substitute(substitution: Substitution, expr: Expr, bound: HashSet<Identifier>
             span,
             Expression::Native(Native {
                 unique_name,
-                implementation: Rc::new(move |context| {
+                implementation: Arc::new(move |context| {
                     implementation(&AdditionalContext {
-                        name: substitution.name.clone(),
-                        value: substitution.value.clone(),
+                        substitution: &substitution,
                         rest: context,
                     })
                 }),
             }),
         ),
-        Expression::Identifier(name) if name == *substitution.name => {
-            avoid_alpha_capture((*substitution.value).clone(), bound)
-        }
+        Expression::Identifier(name) if name == *substitution.name => (*substitution.value).clone(),
         expression @ Expression::Identifier(_) => Expr::new(span, expression),
-        Expression::Function(Function { parameter, body }) if parameter != *substitution.name => {
-            Expr::new(
-                span,
-                Expression::Function(Function {
-                    parameter: parameter.clone(),
-                    body: substitute(substitution, body, bound.update(parameter)),
-                }),
-            )
-        }
+        Expression::Function(Function {
+            parameter,
+            parameter_type,
+            parameter_type_span,
+            body,
+        }) if parameter != *substitution.name => Expr::new(
+            span,
+            Expression::Function(Function {
+                parameter,
+                parameter_type,
+                parameter_type_span,
+                body: substitute(substitution, body),
+            }),
+        ),
         expression @ Expression::Function(_) => Expr::new(span, expression),
         Expression::Apply(Apply { function, argument }) => Expr::new(
             span,
             Expression::Apply(Apply {
-                function: substitute(substitution.clone(), function, bound.clone()),
-                argument: substitute(substitution, argument, bound),
+                function: substitute(substitution.clone(), function),
+                argument: substitute(substitution, argument),
             }),
         ),
-        Expression::Assign(Assign { name, value, inner }) if name != *substitution.name => {
+        Expression::Assign(Assign { name, value, inner }) => {
+            // the name is only bound in the inner expression, not in its value
+            let inner = if name == *substitution.name {
+                inner
+            } else {
+                substitute(substitution.clone(), inner)
+            };
             Expr::new(
                 span,
                 Expression::Assign(Assign {
-                    name: name.clone(),
-                    value: substitute(substitution.clone(), value, bound.clone()),
-                    inner: substitute(substitution, inner, bound.update(name)),
+                    name,
+                    value: substitute(substitution, value),
+                    inner,
                 }),
             )
         }
-        expression @ Expression::Assign(_) => Expr::new(span, expression),
+        Expression::AssignRecursive(AssignRecursive { bindings, inner })
+            if bindings
+                .iter()
+                .all(|(name, _)| name != substitution.name.as_ref()) =>
+        {
+            Expr::new(
+                span,
+                Expression::AssignRecursive(AssignRecursive {
+                    bindings: bindings
+                        .into_iter()
+                        .map(|(name, value)| (name, substitute(substitution.clone(), value)))
+                        .collect(),
+                    inner: substitute(substitution, inner),
+                }),
+            )
+        }
+        // the names are bound in the bindings as well as the inner expression
+        expression @ Expression::AssignRecursive(_) => Expr::new(span, expression),
         Expression::Match(Match { value, patterns }) => Expr::new(
             span,
             Expression::Match(Match {
-                value: substitute(substitution.clone(), value, bound.clone()),
+                value: substitute(substitution.clone(), value),
                 patterns: patterns
                     .into_iter()
-                    .map(|PatternMatch { pattern, result }| PatternMatch {
-                        pattern,
-                        result: substitute(substitution.clone(), result, bound.clone()),
-                    })
+                    .map(
+                        |PatternMatch {
+                             pattern,
+                             pattern_span,
+                             result,
+                         }| {
+                            // variables bound by the pattern shadow the substitution
+                            let result =
+                                if pattern.variables().contains(&substitution.name.as_ref()) {
+                                    result
+                                } else {
+                                    substitute(substitution.clone(), result)
+                                };
+                            PatternMatch {
+                                pattern,
+                                pattern_span,
+                                result,
+                            }
+                        },
+                    )
                     .collect(),
             }),
         ),
-        Expression::Typed(Typed { expression, typ }) => Expr::new(
+        Expression::Typed(Typed {
+            expression,
+            typ,
+            typ_span,
+        }) => Expr::new(
             span,
             Expression::Typed(Typed {
-                expression: substitute(substitution, expression, bound),
+                expression: substitute(substitution, expression),
                 typ,
+                typ_span,
             }),
         ),
-    }
-}
-
-fn avoid_alpha_capture(expr: Expr, bound: HashSet<Identifier>) -> Expr {
-    Expr::new(
-        expr.span(),
-        match expr.take() {
-            expression @ Expression::Primitive(_) | expression @ Expression::Native(_) => {
-                expression
-            }
-            Expression::Identifier(identifier) if bound.contains(&identifier) => {
-                let original = Box::new(identifier);
-                let new_identifier = (1u32..)
-                    .map(|suffix| Identifier::AvoidingCapture {
-                        original: original.clone(),
-                        suffix,
-                    })
-                    .find(|i| !bound.contains(i))
-                    .unwrap();
-                Expression::Identifier(new_identifier)
-            }
-            Expression::Identifier(identifier) => Expression::Identifier(identifier),
-            Expression::Function(Function { parameter, body }) => Expression::Function(Function {
-                parameter,
-                body: avoid_alpha_capture(body, bound),
-            }),
-            Expression::Apply(Apply { function, argument }) => Expression::Apply(Apply {
-                function: avoid_alpha_capture(function, bound.clone()),
-                argument: avoid_alpha_capture(argument, bound),
-            }),
-            Expression::Assign(Assign { name, value, inner }) => Expression::Assign(Assign {
-                name,
-                value: avoid_alpha_capture(value, bound.clone()),
-                inner: avoid_alpha_capture(inner, bound),
+        Expression::Sequence(Sequence { first, second }) => Expr::new(
+            span,
+            Expression::Sequence(Sequence {
+                first: substitute(substitution.clone(), first),
+                second: substitute(substitution, second),
             }),
-            Expression::Match(Match { value, patterns }) => Expression::Match(Match {
-                value: avoid_alpha_capture(value, bound.clone()),
-                patterns: patterns
+        ),
+        Expression::Tuple(Tuple { elements }) => Expr::new(
+            span,
+            Expression::Tuple(Tuple {
+                elements: elements
                     .into_iter()
-                    .map(|PatternMatch { pattern, result }| PatternMatch {
-                        pattern,
-                        result: avoid_alpha_capture(result, bound.clone()),
-                    })
+                    .map(|element| substitute(substitution.clone(), element))
                     .collect(),
             }),
-            Expression::Typed(Typed { expression, typ }) => Expression::Typed(Typed {
-                expression: avoid_alpha_capture(expression, bound),
-                typ,
+        ),
+        expression @ Expression::List(List::Empty) => Expr::new(span, expression),
+        Expression::List(List::Cons { head, tail }) => Expr::new(
+            span,
+            Expression::List(List::Cons {
+                head: substitute(substitution.clone(), head),
+                tail: substitute(substitution, tail),
             }),
+        ),
+    }
+}
+
+/// Replaces each label with the value it refers to, so that the expression can
+/// be read without the heap.
+fn unlabel(heap: &Heap, expr: Expr) -> Expr {
+    unlabel_except(heap, &HashMap::new(), expr)
+}
+
+/// Replaces each label with the value it refers to, except for those which
+/// are given a name, which are replaced with the name.
+fn unlabel_except(heap: &Heap, names: &HashMap<usize, Identifier>, expr: Expr) -> Expr {
+    let span = expr.span();
+    match expr.take() {
+        Expression::Identifier(Identifier::Label(label)) => match names.get(&label) {
+            Some(name) => Expr::new(span, Expression::Identifier(name.clone())),
+            None => unlabel_except(heap, names, heap.read(label)),
         },
-    )
+        expression => Expr::new(
+            span,
+            match expression {
+                expression @ Expression::Primitive(_)
+                | expression @ Expression::Native(_)
+                | expression @ Expression::Identifier(_) => expression,
+                Expression::Function(Function {
+                    parameter,
+                    parameter_type,
+                    parameter_type_span,
+                    body,
+                }) => Expression::Function(Function {
+                    parameter,
+                    parameter_type,
+                    parameter_type_span,
+                    body: unlabel_except(heap, names, body),
+                }),
+                Expression::Apply(Apply { function, argument }) => Expression::Apply(Apply {
+                    function: unlabel_except(heap, names, function),
+                    argument: unlabel_except(heap, names, argument),
+                }),
+                Expression::Assign(Assign { name, value, inner }) => Expression::Assign(Assign {
+                    name,
+                    value: unlabel_except(heap, names, value),
+                    inner: unlabel_except(heap, names, inner),
+                }),
+                Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                    Expression::AssignRecursive(AssignRecursive {
+                        bindings: bindings
+                            .into_iter()
+                            .map(|(name, value)| (name, unlabel_except(heap, names, value)))
+                            .collect(),
+                        inner: unlabel_except(heap, names, inner),
+                    })
+                }
+                Expression::Match(Match { value, patterns }) => Expression::Match(Match {
+                    value: unlabel_except(heap, names, value),
+                    patterns: patterns
+                        .into_iter()
+                        .map(
+                            |PatternMatch {
+                                 pattern,
+                                 pattern_span,
+                                 result,
+                             }| PatternMatch {
+                                pattern,
+                                pattern_span,
+                                result: unlabel_except(heap, names, result),
+                            },
+                        )
+                        .collect(),
+                }),
+                Expression::Typed(Typed {
+                    expression,
+                    typ,
+                    typ_span,
+                }) => Expression::Typed(Typed {
+                    expression: unlabel_except(heap, names, expression),
+                    typ,
+                    typ_span,
+                }),
+                Expression::Sequence(Sequence { first, second }) => {
+                    Expression::Sequence(Sequence {
+                        first: unlabel_except(heap, names, first),
+                        second: unlabel_except(heap, names, second),
+                    })
+                }
+                Expression::Tuple(Tuple { elements }) => Expression::Tuple(Tuple {
+                    elements: elements
+                        .into_iter()
+                        .map(|element| unlabel_except(heap, names, element))
+                        .collect(),
+                }),
+                Expression::List(List::Empty) => Expression::List(List::Empty),
+                Expression::List(List::Cons { head, tail }) => Expression::List(List::Cons {
+                    head: unlabel_except(heap, names, head),
+                    tail: unlabel_except(heap, names, tail),
+                }),
+            },
+        ),
+    }
 }
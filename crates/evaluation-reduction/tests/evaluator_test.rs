@@ -0,0 +1,173 @@
+use std::ops::ControlFlow;
+
+use boo_core::builtins;
+use boo_core::error::Error;
+use boo_core::evaluation::*;
+use boo_core::primitive::Primitive;
+use boo_core::span::Span;
+use boo_evaluation_reduction::ReducingEvaluator;
+
+#[test]
+fn test_shares_the_evaluation_of_each_value() {
+    // without sharing, this evaluates `double` over a billion times
+    let result = evaluate(
+        "let rec double = fn n -> match n { 0 -> 1; _ -> let x = double (n - 1) in x + x } in double 30",
+    );
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer((1 << 30).into())))
+    );
+}
+
+#[test]
+fn test_shares_the_evaluation_of_elements() {
+    let result = evaluate(
+        "let rec double = fn n -> match n { 0 -> 1; _ -> match (double (n - 1), 0) { (x, _) -> x + x } } in double 30",
+    );
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer((1 << 30).into())))
+    );
+}
+
+#[test]
+fn test_functions_refer_to_values_rather_than_the_heap() {
+    let result = evaluate("let x = 1 in let y = x in fn z -> y");
+
+    assert_eq!(
+        result.map(|evaluated| evaluated.to_string()),
+        Ok("fn z -> (1)".to_string())
+    );
+}
+
+#[test]
+fn test_assignments_refer_to_the_names_they_shadow() {
+    let result = evaluate("let x = 1 in let x = x + 1 in x");
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(2.into())))
+    );
+}
+
+#[test]
+fn test_recursive_assignments_shadow_names_in_their_bindings() {
+    let result =
+        evaluate("let x = 1 in let rec x = fn n -> match n { 0 -> 0; _ -> x (n - 1) } in x 3");
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(0.into())))
+    );
+}
+
+#[test]
+fn test_observes_each_step_with_bindings_referred_to_by_name() {
+    let mut context = ReducingEvaluator::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+    let expr = boo_parser::parse_expr("let x = 1 + 2 in x * 2")
+        .unwrap()
+        .to_core()
+        .unwrap();
+
+    let mut steps = vec![];
+    let result = context.evaluate_stepwise(expr, |step| {
+        steps.push(step.to_string());
+        ControlFlow::Continue(())
+    });
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(6.into())))
+    );
+    assert_eq!(
+        steps,
+        [
+            "let x = (((+) (1)) (2)) in (((*) (x)) (2))",
+            "((*) (((+) (1)) (2))) (2)",
+            "((*) (3)) (2)",
+        ]
+    );
+}
+
+#[test]
+fn test_observes_partially_applied_built_ins_as_they_are_written() {
+    let mut context = ReducingEvaluator::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+    let expr = boo_parser::parse_expr("let p = pow 2 in p (1 + 2) - p 1")
+        .unwrap()
+        .to_core()
+        .unwrap();
+
+    let mut steps = vec![];
+    let result = context.evaluate_stepwise(expr, |step| {
+        steps.push(step.to_string());
+        ControlFlow::Continue(())
+    });
+
+    assert_eq!(
+        result,
+        Ok(Evaluated::Primitive(Primitive::Integer(6.into())))
+    );
+    assert_eq!(
+        steps,
+        [
+            "let p = ((pow) (2)) in (((-) ((p) (((+) (1)) (2)))) ((p) (1)))",
+            "((-) (((pow) (2)) (((+) (1)) (2)))) (((pow) (2)) (1))",
+            "((-) (((pow) (2)) (3))) (((pow) (2)) (1))",
+            "((-) (8)) (((pow) (2)) (1))",
+            "((-) (8)) (2)",
+        ]
+    );
+}
+
+#[test]
+fn test_stops_stepping_when_asked() {
+    let mut context = ReducingEvaluator::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+    let expr = boo_parser::parse_expr("let rec loop = fn x -> loop x in loop 1")
+        .unwrap()
+        .to_core()
+        .unwrap();
+
+    let mut steps = 0;
+    let result = context.evaluate_stepwise(expr, |_| {
+        steps += 1;
+        if steps == 10 {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+
+    assert_eq!(result, Err(Error::Interrupted));
+    assert_eq!(steps, 10);
+}
+
+#[test]
+fn test_integer_overflow_can_fail_instead() -> boo_core::error::Result<()> {
+    let program = "let x = 65536 in 1 + x * x";
+    let mut context = ReducingEvaluator::new().with_integer_overflow(IntegerOverflow::Fail);
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let evaluator = context.evaluator();
+
+    let result = evaluator.evaluate(boo_parser::parse_expr(program)?.to_core()?);
+
+    let multiplication_start = program.find("x * x").unwrap();
+    assert_eq!(
+        result,
+        Err(Error::IntegerOverflow {
+            span: Some(Span::from(multiplication_start..multiplication_start + 5))
+        })
+    );
+    Ok(())
+}
+
+fn evaluate(program: &str) -> boo_core::error::Result<Evaluated> {
+    let mut context = boo_evaluation_reduction::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+    let evaluator = context.evaluator();
+    evaluator.evaluate(boo_parser::parse_expr(program)?.to_core()?)
+}
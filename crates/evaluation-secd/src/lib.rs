@@ -0,0 +1,411 @@
+//! Evaluates a core AST using a SECD machine.
+//!
+//! The state of the machine consists of a stack of evaluated values, the
+//! bindings in scope (the environment), the instructions still to be run (the
+//! control), and a dump, in which the rest of the state is saved while
+//! evaluating an expression in a different environment, such as the body of a
+//! function.
+//!
+//! As with the other evaluators, values are bound lazily, and each binding is
+//! only evaluated once.
+
+use boo_core::ast::*;
+use boo_core::error::*;
+use boo_core::evaluation::*;
+use boo_core::expr::Expr;
+use boo_core::identifier::*;
+use boo_core::matching::{Selected, Selection};
+use boo_core::native::*;
+use boo_core::primitive::*;
+use boo_core::span::Span;
+use boo_evaluation_lazy::{
+    resolved, select, Binding, Bindings, CompletedEvaluation, EvaluatedBinding,
+};
+
+pub fn new() -> impl EvaluationContext {
+    SecdEvaluator::new()
+}
+
+/// Evaluates an AST using a SECD machine.
+pub struct SecdEvaluator {
+    bindings: Bindings<Expr>,
+    integer_overflow: IntegerOverflow,
+}
+
+impl SecdEvaluator {
+    pub fn new() -> Self {
+        Self {
+            bindings: Bindings::new(),
+            integer_overflow: IntegerOverflow::default(),
+        }
+    }
+
+    /// Decides how arithmetic on small integers handles overflow.
+    pub fn with_integer_overflow(self, integer_overflow: IntegerOverflow) -> Self {
+        Self {
+            integer_overflow,
+            ..self
+        }
+    }
+}
+
+impl Default for SecdEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EvaluationContext for SecdEvaluator {
+    type Eval = Self;
+
+    fn bind(&mut self, identifier: Identifier, expr: Expr) -> Result<()> {
+        self.bindings = self.bindings.with(identifier, expr, self.bindings.clone());
+        Ok(())
+    }
+
+    fn identifiers(&self) -> Vec<Identifier> {
+        self.bindings.identifiers().cloned().collect()
+    }
+
+    fn evaluator(self) -> Self::Eval {
+        self
+    }
+}
+
+impl Evaluator for SecdEvaluator {
+    fn evaluate(&self, expr: Expr) -> Result<Evaluated> {
+        Machine::new(
+            self,
+            self.bindings.clone(),
+            vec![Instruction::Evaluate(expr)],
+        )
+        .run()?
+        .finish(&|binding| self.force(binding))
+    }
+}
+
+impl SecdEvaluator {
+    /// Evaluates a binding immediately; see [`boo_evaluation_lazy::force`].
+    fn force(&self, binding: &mut Binding<Expr>) -> EvaluatedBinding<Expr> {
+        boo_evaluation_lazy::force(binding, |(expr, environment)| {
+            Machine::new(self, environment, vec![Instruction::Evaluate(expr)]).run()
+        })
+    }
+
+    fn switch(&self, new_bindings: Bindings<Expr>) -> Self {
+        Self {
+            bindings: new_bindings,
+            integer_overflow: self.integer_overflow,
+        }
+    }
+}
+
+impl NativeContext for SecdEvaluator {
+    fn lookup_value(&self, identifier: &Identifier) -> Result<Primitive> {
+        let result = match self.bindings.clone().read(identifier) {
+            Some(binding) => self.force(binding)?,
+            None => {
+                return Err(Error::UnknownVariable {
+                    span: None,
+                    name: identifier.to_string(),
+                    suggestions: None,
+                })
+            }
+        };
+        match result {
+            CompletedEvaluation::Primitive(primitive) => Ok(primitive),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        }
+    }
+
+    fn apply(&self, function: &Identifier, argument: Primitive) -> Result<Primitive> {
+        let function = match self.bindings.clone().read(function) {
+            Some(binding) => self.force(binding)?,
+            None => {
+                return Err(Error::UnknownVariable {
+                    span: None,
+                    name: function.to_string(),
+                    suggestions: None,
+                })
+            }
+        };
+        let (body, bindings) = function.apply_to(argument)?;
+        match Machine::new(self, bindings, vec![Instruction::Evaluate(body)]).run()? {
+            CompletedEvaluation::Primitive(primitive) => Ok(primitive),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        }
+    }
+
+    fn integer_overflow(&self) -> IntegerOverflow {
+        self.integer_overflow
+    }
+}
+
+/// A single instruction for the machine.
+enum Instruction {
+    /// Evaluates an expression, pushing the result onto the stack.
+    Evaluate(Expr),
+    /// Pops a function from the stack, and applies it to the argument.
+    Apply { argument: Expr, span: Option<Span> },
+    /// Pops a value from the stack, and discards it.
+    Discard,
+    /// Stores the value at the top of the stack in a binding, so that it is
+    /// only evaluated once.
+    Update(Binding<Expr>),
+    /// Pops the part of a value that the selection asked for from the stack,
+    /// and continues matching.
+    Match {
+        selection: Selection<Binding<Expr>, Expr>,
+        span: Option<Span>,
+    },
+}
+
+/// The state saved while the machine evaluates in a different environment.
+struct Dump {
+    stack: Vec<CompletedEvaluation<Expr>>,
+    environment: Bindings<Expr>,
+    control: Vec<Instruction>,
+    call_site: Option<Span>,
+}
+
+struct Machine<'a> {
+    evaluator: &'a SecdEvaluator,
+    stack: Vec<CompletedEvaluation<Expr>>,
+    environment: Bindings<Expr>,
+    /// The instructions to run, with the next one at the end.
+    control: Vec<Instruction>,
+    dump: Vec<Dump>,
+    /// The most recent application of a function, blamed for errors in natives.
+    call_site: Option<Span>,
+}
+
+impl<'a> Machine<'a> {
+    fn new(
+        evaluator: &'a SecdEvaluator,
+        environment: Bindings<Expr>,
+        control: Vec<Instruction>,
+    ) -> Self {
+        Self {
+            evaluator,
+            stack: vec![],
+            environment,
+            control,
+            dump: vec![],
+            call_site: None,
+        }
+    }
+
+    /// Runs instructions until there are none left, and the dump is empty.
+    fn run(mut self) -> EvaluatedBinding<Expr> {
+        loop {
+            match self.control.pop() {
+                Some(instruction) => self.step(instruction)?,
+                None => {
+                    let value = self.stack.pop().expect("The stack is empty.");
+                    match self.dump.pop() {
+                        Some(Dump {
+                            stack,
+                            environment,
+                            control,
+                            call_site,
+                        }) => {
+                            self.stack = stack;
+                            self.environment = environment;
+                            self.control = control;
+                            self.call_site = call_site;
+                            self.stack.push(value);
+                        }
+                        None => {
+                            return Ok(value);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn step(&mut self, instruction: Instruction) -> Result<()> {
+        match instruction {
+            Instruction::Evaluate(expr) => self.evaluate(expr),
+            Instruction::Apply { argument, span } => match self.stack.pop() {
+                Some(CompletedEvaluation::Closure {
+                    parameter,
+                    body,
+                    bindings,
+                    ..
+                }) => {
+                    // the argument is evaluated in the environment of the call
+                    let environment = bindings.with(parameter, argument, self.environment.clone());
+                    self.enter(environment, vec![Instruction::Evaluate(body)]);
+                    self.call_site = span.or(self.call_site);
+                    Ok(())
+                }
+                _ => Err(Error::InvalidFunctionApplication { span }),
+            },
+            Instruction::Discard => {
+                self.stack.pop();
+                Ok(())
+            }
+            Instruction::Update(binding) => {
+                let value = self.stack.last().expect("The stack is empty.").clone();
+                binding.resolve_with(Ok(value));
+                Ok(())
+            }
+            Instruction::Match {
+                mut selection,
+                span,
+            } => {
+                let value = self.stack.pop().expect("The stack is empty.");
+                let selected = select(&mut selection, Some(value))?;
+                self.continue_selection(selection, selected, span)
+            }
+        }
+    }
+
+    fn evaluate(&mut self, expr: Expr) -> Result<()> {
+        let span = expr.span();
+        match expr.take() {
+            Expression::Primitive(value) => {
+                self.stack.push(CompletedEvaluation::Primitive(value));
+            }
+            Expression::Native(Native { implementation, .. }) => {
+                let value = implementation(&self.evaluator.switch(self.environment.clone()))
+                    .map_err(|error| error.located_at(self.call_site))?;
+                self.stack.push(CompletedEvaluation::Primitive(value));
+            }
+            Expression::Identifier(name) => match self.environment.read(&name).cloned() {
+                Some(binding) => match binding.unresolved_value() {
+                    Some((value, environment)) => self.enter(
+                        environment,
+                        vec![Instruction::Update(binding), Instruction::Evaluate(value)],
+                    ),
+                    None => {
+                        let value = resolved(binding.value().expect("Binding was not resolved."))?;
+                        self.stack.push(value);
+                    }
+                },
+                None => {
+                    return Err(Error::unknown_variable(
+                        span,
+                        &name,
+                        self.environment.identifiers(),
+                    ));
+                }
+            },
+            Expression::Function(Function {
+                parameter,
+                parameter_type,
+                body,
+                ..
+            }) => {
+                self.stack.push(CompletedEvaluation::Closure {
+                    parameter,
+                    parameter_type,
+                    body,
+                    bindings: self.environment.clone(),
+                });
+            }
+            Expression::Apply(Apply { function, argument }) => {
+                self.control.push(Instruction::Apply { argument, span });
+                self.control.push(Instruction::Evaluate(function));
+            }
+            Expression::Assign(Assign { name, value, inner }) => {
+                let environment = self.environment.with(name, value, self.environment.clone());
+                self.enter(environment, vec![Instruction::Evaluate(inner)]);
+            }
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                let environment = self.environment.with_recursive(bindings);
+                self.enter(environment, vec![Instruction::Evaluate(inner)]);
+            }
+            Expression::Match(Match { value, patterns }) => {
+                // Ensure we only evaluate the value once.
+                let value = Binding::unresolved((value, self.environment.clone()));
+                let mut selection = Selection::new(
+                    value,
+                    patterns.into_iter().map(
+                        |PatternMatch {
+                             pattern, result, ..
+                         }| (pattern, result),
+                    ),
+                );
+                let selected = select(&mut selection, None)?;
+                self.continue_selection(selection, selected, span)?;
+            }
+            Expression::Typed(Typed { expression, .. }) => {
+                self.control.push(Instruction::Evaluate(expression));
+            }
+            Expression::Sequence(Sequence { first, second }) => {
+                self.control.push(Instruction::Evaluate(second));
+                self.control.push(Instruction::Discard);
+                self.control.push(Instruction::Evaluate(first));
+            }
+            Expression::Tuple(Tuple { elements }) => {
+                self.stack.push(CompletedEvaluation::Tuple(
+                    elements
+                        .into_iter()
+                        .map(|element| Binding::unresolved((element, self.environment.clone())))
+                        .collect(),
+                ));
+            }
+            Expression::List(List::Empty) => {
+                self.stack.push(CompletedEvaluation::List(List::Empty));
+            }
+            Expression::List(List::Cons { head, tail }) => {
+                self.stack.push(CompletedEvaluation::List(List::Cons {
+                    head: Binding::unresolved((head, self.environment.clone())),
+                    tail: Binding::unresolved((tail, self.environment.clone())),
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates the result of the pattern that matched, with the variables
+    /// bound by the pattern, or, if the selection needs part of the value,
+    /// evaluates that first, and then continues.
+    fn continue_selection(
+        &mut self,
+        selection: Selection<Binding<Expr>, Expr>,
+        selected: Selected<Binding<Expr>, Expr>,
+        span: Option<Span>,
+    ) -> Result<()> {
+        match selected {
+            Selected::Matched { result, variables } => {
+                let environment = self.environment.with_bindings(variables);
+                self.enter(environment, vec![Instruction::Evaluate(result)]);
+                Ok(())
+            }
+            Selected::NoMatch => Err(Error::MatchWithoutBaseCase { span }),
+            Selected::Evaluate(binding) => {
+                let (value, environment) = binding
+                    .unresolved_value()
+                    .expect("Binding was already evaluated.");
+                self.control.push(Instruction::Match { selection, span });
+                self.enter(
+                    environment,
+                    vec![Instruction::Update(binding), Instruction::Evaluate(value)],
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Switches to a new environment to run the given instructions, saving
+    /// the current state in the dump.
+    ///
+    /// If there is nothing left to do in the current environment, there is no
+    /// need to save it, and so calls in tail position do not grow the dump.
+    fn enter(&mut self, environment: Bindings<Expr>, control: Vec<Instruction>) {
+        if self.control.is_empty() && self.stack.is_empty() {
+            self.environment = environment;
+            self.control = control;
+            return;
+        }
+        self.dump.push(Dump {
+            stack: std::mem::take(&mut self.stack),
+            environment: std::mem::replace(&mut self.environment, environment),
+            control: std::mem::replace(&mut self.control, control),
+            call_site: self.call_site,
+        });
+    }
+}
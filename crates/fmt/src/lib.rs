@@ -0,0 +1,266 @@
+//! Formats Boo source code canonically.
+//!
+//! Formatting works on the tokens, rather than the AST, so that the program is
+//! printed exactly as it was written, with the same parentheses and without
+//! any desugaring. Only the whitespace between tokens changes. Comments are
+//! recovered from the text between tokens, and each is printed on its own line.
+//!
+//! The result does not depend on the original whitespace, except that a single
+//! blank line is kept wherever there was at least one before a line, so
+//! formatting the result again changes nothing.
+
+use boo_core::error::Result;
+use boo_core::span::Span;
+use boo_parser::lexer::{lex, AnnotatedToken, Token};
+
+/// The indentation for each level of nesting within brackets.
+const INDENT: &str = "  ";
+
+/// Formats a program.
+///
+/// Returns an error if the program cannot be parsed.
+pub fn format(input: &str) -> Result<String> {
+    let tokens = lex(input)?;
+    boo_parser::parser::parse_program_tokens(&tokens)?;
+    let mut formatter = Formatter::new(input);
+    for (index, token) in tokens.iter().enumerate() {
+        formatter.write_gap(token.annotation.start);
+        let before = index.checked_sub(1).map(|previous| &tokens[previous].token);
+        let before_that = index.checked_sub(2).map(|previous| &tokens[previous].token);
+        formatter.write_token(token, before, before_that);
+    }
+    formatter.write_gap(input.len());
+    Ok(formatter.finish())
+}
+
+struct Formatter<'a> {
+    input: &'a str,
+    output: String,
+    /// The end of the last token written.
+    position: usize,
+    /// The number of brackets which are open.
+    depth: usize,
+    /// Whether the current line is empty.
+    at_line_start: bool,
+    /// Whether the next token should start a new line.
+    break_before_next: bool,
+    /// For each `let` or `try` outside of brackets, whether it started a line.
+    /// If so, its body starts a new line too.
+    bindings: Vec<bool>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            output: String::new(),
+            position: 0,
+            depth: 0,
+            at_line_start: true,
+            break_before_next: false,
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Writes any comments between the last token and the given position,
+    /// each on its own line.
+    fn write_gap(&mut self, end: usize) {
+        let gap = &self.input[self.position..end];
+        let mut rest = gap;
+        while let Some(comment_start) = rest.find('#') {
+            let (whitespace, comment) = rest.split_at(comment_start);
+            let comment_end = comment.find('\n').unwrap_or(comment.len());
+            self.end_line();
+            self.start_line(is_blank(whitespace), self.depth);
+            self.output.push_str(comment[..comment_end].trim_end());
+            self.at_line_start = false;
+            self.end_line();
+            rest = &comment[comment_end..];
+        }
+        if self.break_before_next {
+            self.end_line();
+            self.break_before_next = false;
+        }
+        if self.at_line_start && is_blank(rest) {
+            self.blank_line();
+        }
+        self.position = end;
+    }
+
+    fn write_token(
+        &mut self,
+        token: &AnnotatedToken<Span>,
+        before: Option<&Token>,
+        before_that: Option<&Token>,
+    ) {
+        let started_line = self.at_line_start;
+        if started_line {
+            let closing = matches!(
+                token.token,
+                Token::EndGroup | Token::EndList | Token::BlockEnd
+            );
+            let depth = if closing {
+                self.depth.saturating_sub(1)
+            } else {
+                self.depth
+            };
+            self.start_line(false, depth);
+        } else if needs_space(before, before_that, &token.token) {
+            self.output.push(' ');
+        }
+        self.output.push_str(&self.input[token.annotation.range()]);
+        self.at_line_start = false;
+        self.position = token.annotation.end;
+
+        match token.token {
+            Token::StartGroup | Token::StartList | Token::BlockStart => self.depth += 1,
+            Token::EndGroup | Token::EndList | Token::BlockEnd => {
+                self.depth = self.depth.saturating_sub(1)
+            }
+            Token::Let | Token::Try if self.depth == 0 => self.bindings.push(started_line),
+            Token::In if self.depth == 0 => {
+                if self.bindings.pop().unwrap_or(false) {
+                    self.break_before_next = true;
+                }
+            }
+            // outside of brackets, `;` can only finish a definition
+            Token::Separator if self.depth == 0 => {
+                self.bindings.clear();
+                self.break_before_next = true;
+            }
+            _ => {}
+        }
+    }
+
+    /// Indents a new line, preceded by a blank line if requested, unless this
+    /// is the start of the output.
+    fn start_line(&mut self, blank: bool, depth: usize) {
+        if blank {
+            self.blank_line();
+        }
+        for _ in 0..depth {
+            self.output.push_str(INDENT);
+        }
+    }
+
+    fn blank_line(&mut self) {
+        if !self.output.is_empty() && !self.output.ends_with("\n\n") {
+            self.output.push('\n');
+        }
+    }
+
+    fn end_line(&mut self) {
+        if !self.at_line_start {
+            self.output.push('\n');
+            self.at_line_start = true;
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.end_line();
+        while self.output.ends_with("\n\n") {
+            self.output.pop();
+        }
+        self.output
+    }
+}
+
+/// Whether the whitespace spans a blank line.
+fn is_blank(whitespace: &str) -> bool {
+    whitespace.matches('\n').count() >= 2
+}
+
+/// Tokens are separated by a space, except inside brackets, before
+/// punctuation, and after a unary minus.
+fn needs_space(before: Option<&Token>, before_that: Option<&Token>, token: &Token) -> bool {
+    match (before, token) {
+        (Some(Token::StartGroup | Token::StartList), _) => false,
+        (
+            _,
+            Token::EndGroup
+            | Token::EndList
+            | Token::Comma
+            | Token::Separator
+            | Token::Annotate
+            | Token::Dot,
+        ) => false,
+        (Some(Token::Operator("-" | "-.")), _) if is_unary(before_that) => !matches!(
+            token,
+            Token::Identifier(_)
+                | Token::StartGroup
+                | Token::StartList
+                | Token::Boolean(_)
+                | Token::Char(_)
+                | Token::String(_)
+        ),
+        _ => true,
+    }
+}
+
+/// Whether a minus sign following the given token negates what follows, rather
+/// than subtracting it.
+fn is_unary(before: Option<&Token>) -> bool {
+    matches!(
+        before,
+        None | Some(
+            Token::StartGroup
+                | Token::StartList
+                | Token::BlockStart
+                | Token::Comma
+                | Token::Separator
+                | Token::Operator(_)
+                | Token::Assign
+                | Token::Arrow
+                | Token::In
+                | Token::Match
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formatting_rejects_invalid_programs() {
+        let result = format("let x = 1 in");
+        assert!(matches!(
+            result,
+            Err(boo_core::error::Error::ParseError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_formatting_spacing() -> Result<()> {
+        assert_eq!(
+            format("(  1+2 )*f [ x,y ] (-z)(- 3)")?,
+            "(1 + 2) * f [x, y] (-z) (- 3)\n"
+        );
+        assert_eq!(
+            format("fn (x :Integer)->match x{0->1;_ -> x}")?,
+            "fn (x: Integer) -> match x { 0 -> 1; _ -> x }\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_formatting_definitions_and_bindings() -> Result<()> {
+        assert_eq!(
+            format(
+                "def one = 1; def two =\n 2;  let x = one in let y = let z = two in z in x + y"
+            )?,
+            "def one = 1;\ndef two = 2;\nlet x = one in\nlet y = let z = two in z in\nx + y\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_formatting_keeps_comments_and_blank_lines() -> Result<()> {
+        let input = "# doubles\ndef double = fn x -> x * 2;  # inline\n\n\n\ndef y = (1 + # within\n 2);\n\n# main\ndouble y\n# end\n\n";
+        assert_eq!(
+            format(input)?,
+            "# doubles\ndef double = fn x -> x * 2;\n# inline\n\ndef y = (1 +\n  # within\n  2);\n\n# main\ndouble y\n# end\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use proptest::prelude::*;
+
+use boo_parser::lexer::{lex, Token};
+use boo_test_helpers::proptest::*;
+
+#[test]
+fn test_formatting_keeps_the_tokens_and_is_stable() {
+    let strategy = (
+        proptest::collection::vec(boo_generator::arbitrary(), 0..3),
+        boo_generator::arbitrary(),
+        proptest::collection::vec(any::<prop::sample::Index>(), 0..3),
+    );
+    check(&strategy, |(definitions, main, comment_positions)| {
+        let mut input = String::new();
+        for (i, definition) in definitions.into_iter().enumerate() {
+            input.push_str(&format!("def d{} = {};\n", i, definition));
+        }
+        input.push_str(&main.to_string());
+        // insert comments between tokens, working backwards so the offsets
+        // stay valid
+        let token_starts = lex(&input)?
+            .into_iter()
+            .map(|token| token.annotation.start)
+            .collect::<Vec<_>>();
+        let mut comment_offsets = comment_positions
+            .into_iter()
+            .map(|position| token_starts[position.index(token_starts.len())])
+            .collect::<Vec<_>>();
+        comment_offsets.sort();
+        comment_offsets.dedup();
+        for offset in comment_offsets.into_iter().rev() {
+            input.insert_str(offset, " # a comment\n ");
+        }
+
+        let formatted = boo_fmt::format(&input)?;
+
+        prop_assert_eq!(
+            tokens(&formatted)?,
+            tokens(&input)?,
+            "\ninput:\n{}\nformatted:\n{}\n",
+            input,
+            formatted
+        );
+        prop_assert_eq!(
+            formatted.matches("# a comment").count(),
+            input.matches("# a comment").count()
+        );
+        let reformatted = boo_fmt::format(&formatted)?;
+        prop_assert_eq!(&reformatted, &formatted, "\ninput:\n{}\n", input);
+        Ok(())
+    })
+}
+
+fn tokens(input: &str) -> boo_core::error::Result<Vec<Token>> {
+    Ok(lex(input)?.into_iter().map(|token| token.token).collect())
+}
@@ -6,9 +6,10 @@ use std::sync::Arc;
 
 use im::HashMap;
 use proptest::prelude::*;
+use proptest::test_runner::TestCaseError;
 
 use boo_core::identifier::Identifier;
-use boo_core::primitive::Primitive;
+use boo_core::primitive::{Integer, Primitive};
 use boo_core::types::{Monotype, Type, TypeRef};
 use boo_language::*;
 
@@ -58,6 +59,10 @@ impl TargetType {
             TargetType::Unknown => None,
             TargetType::Known(known) => match known.as_ref() {
                 Type::Integer => Some(Type::Integer.into()),
+                Type::Float => Some(Type::Float.into()),
+                Type::Boolean => Some(Type::Boolean.into()),
+                Type::Char => Some(Type::Char.into()),
+                Type::String => Some(Type::String.into()),
                 Type::Function { parameter, body } => {
                     let mono_parameter = parameter.as_monotype()?;
                     let mono_body = body.as_monotype()?;
@@ -69,7 +74,88 @@ impl TargetType {
                         .into(),
                     )
                 }
+                Type::Tuple(elements) => {
+                    let mono_elements = elements
+                        .iter()
+                        .map(|element| element.as_monotype())
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(Type::Tuple(mono_elements).into())
+                }
+                Type::List(element) => {
+                    let mono_element = element.as_monotype()?;
+                    Some(Type::List(mono_element).into())
+                }
+                Type::Option(element) => {
+                    let mono_element = element.as_monotype()?;
+                    Some(Type::Option(mono_element).into())
+                }
+                Type::Result { ok, error } => {
+                    let mono_ok = ok.as_monotype()?;
+                    let mono_error = error.as_monotype()?;
+                    Some(
+                        Type::Result {
+                            ok: mono_ok,
+                            error: mono_error,
+                        }
+                        .into(),
+                    )
+                }
+                Type::Constructor { name, arguments } => {
+                    let mono_arguments = arguments
+                        .iter()
+                        .map(|argument| argument.as_monotype())
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(
+                        Type::Constructor {
+                            name: name.clone(),
+                            arguments: mono_arguments,
+                        }
+                        .into(),
+                    )
+                }
                 Type::Variable(variable) => Some(Type::Variable(variable.clone()).into()),
+                Type::Forall { quantifiers, body } => {
+                    let mono_body = body.as_monotype()?;
+                    Some(
+                        Type::Forall {
+                            quantifiers: quantifiers.clone(),
+                            body: mono_body,
+                        }
+                        .into(),
+                    )
+                }
+                Type::Recursive { variable, body } => {
+                    let mono_body = body.as_monotype()?;
+                    Some(
+                        Type::Recursive {
+                            variable: variable.clone(),
+                            body: mono_body,
+                        }
+                        .into(),
+                    )
+                }
+            },
+        }
+    }
+
+    /// The number of nodes in the smallest expression of this type, when there
+    /// are no variables in scope.
+    fn smallest_size(&self) -> usize {
+        match self {
+            TargetType::Unknown => 1,
+            TargetType::Known(known) => match known.as_ref() {
+                Type::Function { body, .. } => 1 + body.smallest_size(),
+                Type::Tuple(elements) => {
+                    1 + elements
+                        .iter()
+                        .map(|element| element.smallest_size())
+                        .sum::<usize>()
+                }
+                // an empty list needs to know the type of its elements
+                Type::List(element) if element.as_monotype().is_none() => {
+                    1 + element.smallest_size()
+                }
+                _ => 1,
             },
         }
     }
@@ -82,6 +168,10 @@ impl TargetType {
             TargetType::Unknown => true,
             TargetType::Known(known) => match (known.as_ref(), other.as_ref()) {
                 (Type::Integer, Type::Integer) => true,
+                (Type::Float, Type::Float) => true,
+                (Type::Boolean, Type::Boolean) => true,
+                (Type::Char, Type::Char) => true,
+                (Type::String, Type::String) => true,
                 (
                     Type::Function {
                         parameter: self_parameter,
@@ -95,6 +185,48 @@ impl TargetType {
                     self_parameter.matches_monotype(other_parameter)
                         && self_body.matches_monotype(other_body)
                 }
+                (Type::Tuple(self_elements), Type::Tuple(other_elements)) => {
+                    self_elements.len() == other_elements.len()
+                        && self_elements.iter().zip(other_elements.iter()).all(
+                            |(self_element, other_element)| {
+                                self_element.matches_monotype(other_element)
+                            },
+                        )
+                }
+                (Type::List(self_element), Type::List(other_element)) => {
+                    self_element.matches_monotype(other_element)
+                }
+                (Type::Option(self_element), Type::Option(other_element)) => {
+                    self_element.matches_monotype(other_element)
+                }
+                (
+                    Type::Result {
+                        ok: self_ok,
+                        error: self_error,
+                    },
+                    Type::Result {
+                        ok: other_ok,
+                        error: other_error,
+                    },
+                ) => self_ok.matches_monotype(other_ok) && self_error.matches_monotype(other_error),
+                (
+                    Type::Constructor {
+                        name: self_name,
+                        arguments: self_arguments,
+                    },
+                    Type::Constructor {
+                        name: other_name,
+                        arguments: other_arguments,
+                    },
+                ) => {
+                    self_name == other_name
+                        && self_arguments.len() == other_arguments.len()
+                        && self_arguments.iter().zip(other_arguments.iter()).all(
+                            |(self_argument, other_argument)| {
+                                self_argument.matches_monotype(other_argument)
+                            },
+                        )
+                }
                 _ => false,
             },
         }
@@ -112,8 +244,14 @@ pub struct ExprGenConfig {
     /// The minimum and maximum depth of each branch of the expression.
     ///
     /// Note that the maximum depth may be violated sometimes; consider it
-    /// best-effort.
+    /// best-effort. This is ignored if `nodes` is set.
     pub depth: std::ops::Range<usize>,
+    /// The maximum total number of nodes in the expression, as an alternative
+    /// to limiting the depth.
+    ///
+    /// This is only exceeded if there is no smaller expression of the target
+    /// type (for example, if the target is a tuple with more elements).
+    pub nodes: Option<usize>,
     /// The specific strategy for generating identifiers.
     pub gen_identifier: Rc<BoxedStrategy<Identifier>>,
 }
@@ -122,6 +260,7 @@ impl Default for ExprGenConfig {
     fn default() -> Self {
         Self {
             depth: 0..4,
+            nodes: None,
             gen_identifier: Rc::new(Identifier::arbitrary().boxed()),
         }
     }
@@ -133,32 +272,309 @@ pub fn arbitrary() -> impl Strategy<Value = Expr> {
 }
 
 /// Creates a strategy for generating expresions according to the configuration.
+///
+/// The expressions evaluate to an integer.
 pub fn gen(config: Rc<ExprGenConfig>) -> impl Strategy<Value = Expr> {
-    Just(Type::<TargetType>::Integer.into())
-        .prop_flat_map(move |target_type| {
-            let start_depth = config.depth.clone();
-            gen_nested(config.clone(), start_depth, target_type, HashMap::new())
-        })
-        .prop_map(|(expr, _)| expr)
+    gen_typed(config, Type::Integer.into())
+}
+
+/// Creates a strategy for generating expressions of the target type according
+/// to the configuration.
+///
+/// If the target type is partially or fully unknown, the expressions may be of
+/// any type that matches it.
+pub fn gen_typed(
+    config: Rc<ExprGenConfig>,
+    target_type: TargetType,
+) -> impl Strategy<Value = Expr> {
+    let size = match config.nodes {
+        Some(nodes) => Size::Nodes(nodes),
+        None => Size::Depth(config.depth.clone()),
+    };
+    let strategy = gen_nested(config.clone(), size, target_type.clone(), HashMap::new())
+        .prop_map(|(expr, _)| expr);
+    match config.nodes {
+        None => strategy.boxed(),
+        Some(nodes) => {
+            // The budget is shared out between the children of each node, but
+            // a part of the expression which cannot fit in its share (e.g. a
+            // function, when there is only room for one node) will go over,
+            // so we reject the few expressions which end up too large.
+            let nodes = nodes.max(target_type.smallest_size());
+            strategy
+                .prop_filter("too many nodes", move |expr| count_nodes(expr) <= nodes)
+                .boxed()
+        }
+    }
+}
+
+/// Renders the expression as source code and parses it again, so that each
+/// node has the span of its rendering, rather than `0..0`.
+///
+/// This means that the locations in a failure report point somewhere useful,
+/// as long as the source is reported too.
+///
+/// Fails if the source does not parse back to the same expression (ignoring
+/// spans), as that means the parser and the pretty-printer disagree. This is
+/// meant to be called in the body of a property test, so that such a failure
+/// can be shrunk.
+pub fn locate(expr: &Expr) -> Result<(String, Expr), TestCaseError> {
+    let source = expr.to_string();
+    let located = boo_parser::parse_expr(&source).map_err(|error| {
+        TestCaseError::fail(format!(
+            "Could not parse a rendered expression: {error}\n{source}"
+        ))
+    })?;
+    prop_assert_eq!(
+        located.clone().without_spans(),
+        expr.clone().without_spans(),
+        "The rendered expression was parsed differently:\n{}",
+        source
+    );
+    Ok((source, located))
+}
+
+/// Creates a strategy which renders and re-parses each expression generated by
+/// the given strategy with [`locate`], producing the parsed expression along
+/// with its source.
+///
+/// Expressions which do not survive the round trip are rejected, rather than
+/// failing the test, as a failure here could not be shrunk. To check that they
+/// all survive it, call [`locate`] in the body of a test instead.
+pub fn roundtripped(
+    strategy: impl Strategy<Value = Expr>,
+) -> impl Strategy<Value = (String, Expr)> {
+    strategy.prop_filter_map("the rendered expression does not parse back", |expr| {
+        locate(&expr).ok()
+    })
+}
+
+/// A program which evaluates to a function, along with an argument for each
+/// of its parameters.
+#[derive(Debug, Clone)]
+pub struct FunctionProgram {
+    pub function: Expr,
+    pub arguments: Vec<Expr>,
+}
+
+impl FunctionProgram {
+    /// Applies the function to each of the arguments in turn.
+    pub fn applied(&self) -> Expr {
+        self.arguments
+            .iter()
+            .fold(self.function.clone(), |function, argument| {
+                Expr::new(
+                    0.into(),
+                    Expression::Apply(Apply {
+                        function,
+                        argument: argument.clone(),
+                    }),
+                )
+            })
+    }
+
+    /// Locates the function and its arguments with [`locate`], rendering the
+    /// function applied to each of the arguments.
+    pub fn located(&self) -> Result<(String, Self), TestCaseError> {
+        let (source, mut function) = locate(&self.applied())?;
+        let mut arguments = Vec::with_capacity(self.arguments.len());
+        for _ in &self.arguments {
+            match *function.expression {
+                Expression::Apply(apply) => {
+                    arguments.push(apply.argument);
+                    function = apply.function;
+                }
+                _ => {
+                    return Err(TestCaseError::fail(format!(
+                        "The located program is not an application:\n{source}"
+                    )))
+                }
+            }
+        }
+        arguments.reverse();
+        Ok((
+            source,
+            Self {
+                function,
+                arguments,
+            },
+        ))
+    }
+}
+
+/// Creates a strategy for generating programs which evaluate to a function
+/// with the given parameter and result types, along with arguments of the
+/// parameter types.
+///
+/// The function is curried, so it takes one parameter at a time.
+pub fn gen_function_program(
+    config: Rc<ExprGenConfig>,
+    parameter_types: Vec<Monotype>,
+    result_type: Monotype,
+) -> impl Strategy<Value = FunctionProgram> {
+    let function_type = parameter_types
+        .iter()
+        .rev()
+        .fold(result_type, |body, parameter| {
+            Type::Function {
+                parameter: parameter.clone(),
+                body,
+            }
+            .into()
+        });
+    let arguments = parameter_types
+        .into_iter()
+        .map(|parameter_type| gen_typed(config.clone(), parameter_type.into()))
+        .collect::<Vec<_>>();
+    (gen_typed(config, function_type.into()), arguments).prop_map(|(function, arguments)| {
+        FunctionProgram {
+            function,
+            arguments,
+        }
+    })
+}
+
+/// The size of an expression still to be generated.
+#[derive(Debug, Clone)]
+enum Size {
+    /// The minimum and maximum depth of each branch of the expression.
+    Depth(std::ops::Range<usize>),
+    /// The maximum number of nodes in the expression.
+    Nodes(usize),
+}
+
+impl Size {
+    /// The size shared by the children of a node of this size.
+    fn next(&self) -> Self {
+        match self {
+            Size::Depth(depth) => {
+                Size::Depth(depth.start.saturating_sub(1)..depth.end.saturating_sub(1))
+            }
+            Size::Nodes(nodes) => Size::Nodes(nodes.saturating_sub(1)),
+        }
+    }
+
+    /// The size of each of `count` children sharing this size.
+    ///
+    /// Each branch has the same depth, but the nodes are split evenly.
+    fn split(&self, count: usize) -> Self {
+        match self {
+            Size::Depth(depth) => Size::Depth(depth.clone()),
+            Size::Nodes(nodes) => Size::Nodes(nodes / count.max(1)),
+        }
+    }
+
+    /// The largest number of children, up to `count`, that can share this
+    /// size.
+    fn limit(&self, count: usize) -> usize {
+        match self {
+            Size::Depth(_) => count,
+            Size::Nodes(nodes) => count.min(*nodes),
+        }
+    }
+
+    /// Whether an expression with this many nodes fits in this size.
+    fn fits(&self, count: usize) -> bool {
+        match self {
+            Size::Depth(_) => true,
+            Size::Nodes(nodes) => *nodes >= count,
+        }
+    }
+
+    /// The size left over after generating the given expression from this
+    /// size, for its siblings.
+    fn remaining(&self, expr: &Expr) -> Self {
+        match self {
+            Size::Depth(depth) => Size::Depth(depth.clone()),
+            Size::Nodes(nodes) => Size::Nodes(nodes.saturating_sub(count_nodes(expr))),
+        }
+    }
+
+    /// Whether the expression can be a leaf.
+    fn allows_leaf(&self) -> bool {
+        match self {
+            Size::Depth(depth) => depth.start == 0,
+            Size::Nodes(_) => true,
+        }
+    }
+
+    /// Whether the expression can have children.
+    fn allows_children(&self) -> bool {
+        match self {
+            Size::Depth(depth) => depth.end > 0,
+            Size::Nodes(nodes) => *nodes >= 3,
+        }
+    }
+
+    /// Whether the expression can have children without introducing new
+    /// bindings.
+    ///
+    /// If we continuously generate nodes that do not introduce new bindings,
+    /// we can end up with uncontrollable recursion. By requiring more room for
+    /// these types of nodes than for others (depth (max_depth - 2) or higher,
+    /// or at least four nodes), we try to avoid this (most of the time).
+    fn allows_children_without_bindings(&self) -> bool {
+        match self {
+            Size::Depth(depth) => depth.end > 1,
+            Size::Nodes(nodes) => *nodes >= 4,
+        }
+    }
+
+    /// A larger size, for when nothing fits.
+    fn grow(&self) -> Self {
+        match self {
+            Size::Depth(depth) => Size::Depth(depth.start..(depth.end + 1)),
+            Size::Nodes(nodes) => Size::Nodes(nodes + 1),
+        }
+    }
+}
+
+/// Counts the nodes in an expression.
+fn count_nodes(expr: &Expr) -> usize {
+    1 + expr.children().into_iter().map(count_nodes).sum::<usize>()
 }
 
 /// Generates an expression of the target type (or any type, if it's not
 /// specified).
 fn gen_nested(
     config: Rc<ExprGenConfig>,
-    depth: std::ops::Range<usize>,
+    size: Size,
+    target_type: TargetType,
+    bindings: Bindings,
+) -> ExprStrategy {
+    match size {
+        Size::Depth(_) => gen_choices(config, size, target_type, bindings),
+        // Some choices create the strategies for their children straight
+        // away, and a type annotation's child is only one node smaller, so
+        // creating every choice up front takes exponential time and memory in
+        // the number of nodes. Instead, we wait until we are generating a
+        // value, and only create the choices for the nodes we generate.
+        Size::Nodes(_) => Just(())
+            .prop_flat_map(move |()| {
+                gen_choices(
+                    config.clone(),
+                    size.clone(),
+                    target_type.clone(),
+                    bindings.clone(),
+                )
+            })
+            .boxed(),
+    }
+}
+
+/// Chooses between all the kinds of expression that fit the size and the
+/// target type.
+fn gen_choices(
+    config: Rc<ExprGenConfig>,
+    size: Size,
     target_type: TargetType,
     bindings: Bindings,
 ) -> ExprStrategy {
     let mut choices: Vec<(u32, ExprStrategy)> = Vec::new();
-    let next_depth = {
-        let next_start = if depth.start == 0 { 0 } else { depth.start - 1 };
-        let next_end = if depth.end == 0 { 0 } else { depth.end - 1 };
-        next_start..next_end
-    };
+    let next_size = size.next();
 
     // if we are allowed to generate a leaf:
-    if depth.start == 0 {
+    if size.allows_leaf() {
         // generate primitives
         if let Some(strategy) = gen_primitive(target_type.clone()) {
             choices.push((1, strategy.prop_map(make_primitive_expr).boxed()));
@@ -171,40 +587,78 @@ fn gen_nested(
     }
 
     // if this node can have children:
-    if depth.end > 0 {
+    if size.allows_children() {
         // generate variable assignments
         choices.push((
             2,
             gen_assignment(
                 config.clone(),
-                next_depth.clone(),
+                next_size.clone(),
                 target_type.clone(),
                 bindings.clone(),
             ),
         ));
 
+        // generate recursive assignments
+        choices.push((
+            1,
+            gen_recursive_assignment(
+                config.clone(),
+                next_size.clone(),
+                target_type.clone(),
+                bindings.clone(),
+            ),
+        ));
+
+        // generate calls to already-bound functions
+        if let Some(strategy) = gen_call(
+            config.clone(),
+            next_size.clone(),
+            target_type.clone(),
+            bindings.clone(),
+        ) {
+            choices.push((2, strategy));
+        }
+
         // generate functions
         if let Some(strategy) = gen_function(
             config.clone(),
-            next_depth.clone(),
+            next_size.clone(),
             target_type.clone(),
             bindings.clone(),
         ) {
             choices.push((2, strategy));
         }
+
+        // generate tuples
+        if let Some(strategy) = gen_tuple(
+            config.clone(),
+            next_size.clone(),
+            target_type.clone(),
+            bindings.clone(),
+        ) {
+            choices.push((1, strategy));
+        }
+
+        // generate lists
+        if let Some(strategy) = gen_list(
+            config.clone(),
+            next_size.clone(),
+            target_type.clone(),
+            bindings.clone(),
+        ) {
+            choices.push((1, strategy));
+        }
     }
 
-    // If we continuously generate nodes that do not introduce new bindings,
-    // we can end up with uncontrollable recursion. By limiting these types of
-    // nodes to depth (max_depth - 2) or higher, we try to avoid this (most of
-    // the time).
-    if depth.end > 1 {
+    // if this node can have children that do not introduce new bindings:
+    if size.allows_children_without_bindings() {
         // generate pattern matches
         choices.push((
             2,
             gen_match(
                 config.clone(),
-                next_depth.clone(),
+                next_size.clone(),
                 target_type.clone(),
                 bindings.clone(),
             ),
@@ -215,7 +669,7 @@ fn gen_nested(
             2,
             gen_apply(
                 config.clone(),
-                next_depth.clone(),
+                next_size.clone(),
                 target_type.clone(),
                 bindings.clone(),
             ),
@@ -224,18 +678,39 @@ fn gen_nested(
         // generate infix computations
         if let Some(strategy) = gen_infix(
             config.clone(),
-            next_depth.clone(),
+            next_size.clone(),
             target_type.clone(),
             bindings.clone(),
         ) {
             choices.push((2, strategy));
         }
 
+        // generate calls to string built-ins
+        if let Some(strategy) = gen_string_builtin(
+            config.clone(),
+            next_size.clone(),
+            target_type.clone(),
+            bindings.clone(),
+        ) {
+            choices.push((1, strategy));
+        }
+
+        // generate sequences
         choices.push((
             1,
-            gen_typed(
+            gen_sequence(
                 config.clone(),
-                next_depth,
+                next_size.clone(),
+                target_type.clone(),
+                bindings.clone(),
+            ),
+        ));
+
+        choices.push((
+            1,
+            gen_type_annotation(
+                config.clone(),
+                next_size,
                 target_type.clone(),
                 bindings.clone(),
             ),
@@ -243,13 +718,53 @@ fn gen_nested(
     }
 
     if choices.is_empty() {
-        // increase the depth and try again
-        gen_nested(config, depth.start..(depth.end + 1), target_type, bindings)
+        let smallest = match size {
+            Size::Depth(_) => None,
+            Size::Nodes(_) => gen_smallest(config.clone(), target_type.clone(), bindings.clone()),
+        };
+        // otherwise, increase the size and try again
+        smallest.unwrap_or_else(|| gen_nested(config, size.grow(), target_type, bindings))
     } else {
         prop::strategy::Union::new_weighted(choices).boxed()
     }
 }
 
+/// Generates one of the smallest expressions of the target type, for when
+/// there is no leaf of that type: a function, tuple, or list with as few
+/// children as possible.
+///
+/// If the target type is not one of these, returns `None`.
+fn gen_smallest(
+    config: Rc<ExprGenConfig>,
+    target_type: TargetType,
+    bindings: Bindings,
+) -> Option<ExprStrategy> {
+    let next_size = Size::Nodes(1);
+    let choices = [
+        gen_function(
+            config.clone(),
+            next_size.clone(),
+            target_type.clone(),
+            bindings.clone(),
+        ),
+        gen_tuple(
+            config.clone(),
+            next_size.clone(),
+            target_type.clone(),
+            bindings.clone(),
+        ),
+        gen_list(config, next_size, target_type, bindings),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    if choices.is_empty() {
+        None
+    } else {
+        Some(prop::strategy::Union::new(choices).boxed())
+    }
+}
+
 /// Generates an identifier that has not already been bound.
 fn gen_unused_identifier(
     config: Rc<ExprGenConfig>,
@@ -257,7 +772,7 @@ fn gen_unused_identifier(
 ) -> impl Strategy<Value = Identifier> {
     let conf = config.clone();
     config.gen_identifier.clone().prop_flat_map(move |name| {
-        if bindings.contains_key(&name) {
+        if bindings.contains_key(&name) || BUILTIN_NAMES.contains(&name.name().as_str()) {
             gen_unused_identifier(conf.clone(), bindings.clone()).boxed()
         } else {
             Just(name).boxed()
@@ -312,20 +827,24 @@ fn gen_variable_reference(target_type: TargetType, bindings: Bindings) -> Option
 /// Generates an assignment.
 fn gen_assignment(
     config: Rc<ExprGenConfig>,
-    next_depth: std::ops::Range<usize>,
+    next_size: Size,
     target_type: TargetType,
     bindings: Bindings,
 ) -> ExprStrategy {
-    gen_unused_identifier(config.clone(), bindings.clone())
-        .prop_flat_map(move |name| {
+    // the inner expression gets whatever the value leaves over
+    (
+        gen_unused_identifier(config.clone(), bindings.clone()),
+        gen_assigned_value_type(&next_size.split(2)),
+    )
+        .prop_flat_map(move |(name, value_target_type)| {
             let config_ = config.clone();
-            let next_depth_ = next_depth.clone();
+            let next_size_ = next_size.clone();
             let target_type_ = target_type.clone();
             let bindings_ = bindings.clone();
             gen_nested(
                 config_.clone(),
-                next_depth.clone(),
-                TargetType::Unknown,
+                next_size.split(2),
+                value_target_type,
                 bindings_.clone(),
             )
             .prop_flat_map(move |(value, value_type): ExprStrategyValue| {
@@ -333,7 +852,7 @@ fn gen_assignment(
                 let value_ = value;
                 gen_nested(
                     config_.clone(),
-                    next_depth_.clone(),
+                    next_size_.remaining(&value_),
                     target_type_.clone(),
                     bindings_.update(name.clone(), value_type),
                 )
@@ -353,11 +872,98 @@ fn gen_assignment(
         .boxed()
 }
 
-/// Generates a function of the given type.
+/// Generates the target type of an assigned value.
+///
+/// This is usually unknown, but is sometimes a function, so that there are
+/// named functions to call, as long as the value has room for one.
+fn gen_assigned_value_type(value_size: &Size) -> BoxedStrategy<TargetType> {
+    if !value_size.fits(2) {
+        return Just(TargetType::Unknown).boxed();
+    }
+    prop_oneof![
+        3 => Just(TargetType::Unknown),
+        1 => Just(
+            Type::Function {
+                parameter: Type::Integer.into(),
+                body: Type::Integer.into(),
+            }
+            .into()
+        ),
+    ]
+    .boxed()
+}
+
+/// Generates a group of one or two recursive assignments.
+///
+/// The values never refer to the names in the group, as there is no way to
+/// guarantee that a recursive reference will terminate.
+fn gen_recursive_assignment(
+    config: Rc<ExprGenConfig>,
+    next_size: Size,
+    target_type: TargetType,
+    bindings: Bindings,
+) -> ExprStrategy {
+    let config_ = config.clone();
+    let next_size_ = next_size.clone();
+    let bindings_ = bindings.clone();
+    // the inner expression is always present
+    (1usize..=next_size.limit(3).saturating_sub(1).max(1))
+        .prop_flat_map(move |group_size| {
+            let value_size = next_size_.split(group_size + 1);
+            let initial = Just((Vec::<(Identifier, Expr)>::new(), bindings_.clone())).boxed();
+            (0..group_size).fold(initial, |strategy, _| {
+                let config = config_.clone();
+                let next_size = value_size.clone();
+                let bindings = bindings_.clone();
+                strategy
+                    .prop_flat_map(move |(group, group_bindings)| {
+                        (
+                            gen_unused_identifier(config.clone(), group_bindings.clone()),
+                            gen_nested(
+                                config.clone(),
+                                next_size.clone(),
+                                TargetType::Unknown,
+                                bindings.clone(),
+                            ),
+                        )
+                            .prop_map(
+                                move |(name, (value, value_type))| {
+                                    let mut next_group = group.clone();
+                                    next_group.push((name.clone(), value));
+                                    (next_group, group_bindings.update(name, value_type))
+                                },
+                            )
+                    })
+                    .boxed()
+            })
+        })
+        .prop_flat_map(move |(group, group_bindings)| {
+            gen_nested(
+                config.clone(),
+                next_size.split(group.len() + 1),
+                target_type.clone(),
+                group_bindings,
+            )
+            .prop_map(move |(inner, inner_type)| {
+                let expr = Expr::new(
+                    0.into(),
+                    Expression::AssignRecursive(AssignRecursive {
+                        bindings: group.clone(),
+                        inner,
+                    }),
+                );
+                (expr, inner_type)
+            })
+        })
+        .boxed()
+}
+
+/// Generates a function of the given type, sometimes annotating the parameter
+/// with its type.
 /// If the target type is not a function type, returns `None`.
 fn gen_function(
     config: Rc<ExprGenConfig>,
-    next_depth: std::ops::Range<usize>,
+    next_size: Size,
     target_type: TargetType,
     bindings: Bindings,
 ) -> Option<ExprStrategy> {
@@ -376,13 +982,21 @@ fn gen_function(
                 };
                 let target_body_type_ = target_body_type.clone();
                 Some(
-                    gen_unused_identifier(config.clone(), bindings.clone())
-                        .prop_flat_map(move |parameter| {
-                            let parameter_ = parameter.clone();
+                    (
+                        gen_unused_identifier(config.clone(), bindings.clone()),
+                        proptest::bool::ANY,
+                    )
+                        .prop_flat_map(move |(parameter, annotate)| {
+                            let parameter_ = Parameter {
+                                name: parameter.clone(),
+                                typ: annotate.then(|| mono_parameter_type.clone()),
+                                typ_span: annotate.then(|| 0.into()),
+                                span: 0.into(),
+                            };
                             let mono_parameter_type_ = mono_parameter_type.clone();
                             gen_nested(
                                 config.clone(),
-                                next_depth.clone(),
+                                next_size.clone(),
                                 target_body_type_.clone(),
                                 bindings.update(parameter, mono_parameter_type.clone()),
                             )
@@ -411,113 +1025,406 @@ fn gen_function(
     }
 }
 
+/// Generates a tuple of the given type, or a tuple of two or three elements of
+/// any type if the target type is unknown.
+/// If the target type is not a tuple type, returns `None`.
+fn gen_tuple(
+    config: Rc<ExprGenConfig>,
+    next_size: Size,
+    target_type: TargetType,
+    bindings: Bindings,
+) -> Option<ExprStrategy> {
+    let element_types: BoxedStrategy<Vec<TargetType>> = match target_type {
+        TargetType::Unknown => (2usize..=next_size.limit(3).max(2))
+            .prop_map(|length| vec![TargetType::Unknown; length])
+            .boxed(),
+        TargetType::Known(known) => match known.as_ref() {
+            Type::Tuple(elements) => Just(elements.clone()).boxed(),
+            _ => {
+                return None;
+            }
+        },
+    };
+    Some(
+        element_types
+            .prop_flat_map(move |element_types| {
+                let element_size = next_size.split(element_types.len());
+                element_types
+                    .into_iter()
+                    .map(|element_type| {
+                        gen_nested(
+                            config.clone(),
+                            element_size.clone(),
+                            element_type,
+                            bindings.clone(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .prop_map(|elements_with_types| {
+                let (elements, element_types): (Vec<Expr>, Vec<Monotype>) =
+                    elements_with_types.into_iter().unzip();
+                let expr = Expr::new(0.into(), Expression::Tuple(Tuple { elements }));
+                (expr, Type::Tuple(element_types).into())
+            })
+            .boxed(),
+    )
+}
+
+/// Generates a list literal of the given type, or of any type if the target
+/// type is unknown.
+///
+/// The first element determines the type of the rest. Empty lists are only
+/// generated when the element type is fully known.
+/// If the target type is not a list type, returns `None`.
+fn gen_list(
+    config: Rc<ExprGenConfig>,
+    next_size: Size,
+    target_type: TargetType,
+    bindings: Bindings,
+) -> Option<ExprStrategy> {
+    let element_type = match target_type {
+        TargetType::Unknown => TargetType::Unknown,
+        TargetType::Known(known) => match known.as_ref() {
+            Type::List(element) => element.clone(),
+            _ => {
+                return None;
+            }
+        },
+    };
+    let element_type_ = element_type.clone();
+    let non_empty = (1..=next_size.limit(3).max(1))
+        .prop_flat_map(move |length| {
+            let element_size = next_size.split(length);
+            let config_ = config.clone();
+            let bindings_ = bindings.clone();
+            gen_nested(
+                config.clone(),
+                element_size.clone(),
+                element_type_.clone(),
+                bindings.clone(),
+            )
+            .prop_flat_map(move |(first, first_type)| {
+                proptest::collection::vec(
+                    gen_nested(
+                        config_.clone(),
+                        element_size.clone(),
+                        first_type.clone().into(),
+                        bindings_.clone(),
+                    ),
+                    length - 1,
+                )
+                .prop_map(move |rest| {
+                    let mut elements = vec![first.clone()];
+                    elements.extend(rest.into_iter().map(|(element, _)| element));
+                    let expr = Expr::new(0.into(), Expression::List(List { elements }));
+                    (expr, Type::List(first_type.clone()).into())
+                })
+            })
+        })
+        .boxed();
+    match element_type.as_monotype() {
+        Some(mono_element_type) => {
+            let empty = Just((
+                Expr::new(0.into(), Expression::List(List { elements: vec![] })),
+                Type::List(mono_element_type).into(),
+            ))
+            .boxed();
+            Some(prop_oneof![empty, non_empty].boxed())
+        }
+        None => Some(non_empty),
+    }
+}
+
 /// Generates a pattern match.
 ///
-/// It always has a default case.
+/// It always has a default case. The default case is generated first, so that
+/// all other patterns can be generated with the same result type.
 fn gen_match(
     config: Rc<ExprGenConfig>,
-    next_depth: std::ops::Range<usize>,
+    next_size: Size,
+    target_type: TargetType,
+    bindings: Bindings,
+) -> ExprStrategy {
+    // the value and the default case are always present
+    (0..=next_size.limit(6).saturating_sub(2))
+        .prop_flat_map(move |pattern_count| {
+            gen_match_with_patterns(
+                config.clone(),
+                next_size.split(pattern_count + 2),
+                pattern_count,
+                target_type.clone(),
+                bindings.clone(),
+            )
+        })
+        .boxed()
+}
+
+/// Generates a pattern match with the given number of patterns, plus the
+/// default case.
+fn gen_match_with_patterns(
+    config: Rc<ExprGenConfig>,
+    next_size: Size,
+    pattern_count: usize,
     target_type: TargetType,
     bindings: Bindings,
 ) -> ExprStrategy {
     gen_nested(
         config.clone(),
-        next_depth.clone(),
+        next_size.clone(),
         TargetType::Unknown,
         bindings.clone(),
     )
     .prop_flat_map(move |(value, value_type): ExprStrategyValue| {
         let config_ = config.clone();
-        let next_depth_ = next_depth.clone();
-        let target_type_ = target_type.clone();
+        let next_size_ = next_size.clone();
         let bindings_ = bindings.clone();
-        proptest::collection::vec(
-            gen_pattern(
-                config.clone(),
-                next_depth.clone(),
-                value_type,
-                target_type.clone(),
-                bindings.clone(),
-            ),
-            0..5,
+        gen_nested(
+            config.clone(),
+            next_size.clone(),
+            target_type.clone(),
+            bindings.clone(),
         )
-        .prop_flat_map(move |patterns| {
-            gen_nested(
-                config_.clone(),
-                next_depth_.clone(),
-                target_type_.clone(),
-                bindings_.clone(),
+        .prop_flat_map(move |(anything_result, anything_type)| {
+            let value_ = value.clone();
+            proptest::collection::vec(
+                gen_pattern(
+                    config_.clone(),
+                    next_size_.clone(),
+                    value_type.clone(),
+                    anything_type.clone().into(),
+                    bindings_.clone(),
+                ),
+                pattern_count,
             )
-            .prop_map(move |(anything_result, anything_type)| {
-                let mut patterns_with_base_case = patterns.clone();
-                patterns_with_base_case.push((Pattern::Anything, anything_result, anything_type));
-                patterns_with_base_case
+            .prop_map(move |patterns| {
+                let mut patterns_with_base_case = patterns;
+                patterns_with_base_case.push((
+                    Pattern::Anything,
+                    anything_result.clone(),
+                    anything_type.clone(),
+                ));
+                let expr = Expr::new(
+                    0.into(),
+                    Expression::Match(Match {
+                        value: value_.clone(),
+                        patterns: patterns_with_base_case
+                            .iter()
+                            .map(|(pattern, result, _)| PatternMatch {
+                                pattern: pattern.clone(),
+                                pattern_span: 0.into(),
+                                result: result.clone(),
+                            })
+                            .collect(),
+                    }),
+                );
+                (expr, anything_type.clone())
             })
         })
-        .prop_map(move |patterns| {
-            let expr = Expr::new(
-                0.into(),
-                Expression::Match(Match {
-                    value: value.clone(),
-                    patterns: patterns
-                        .iter()
-                        .map(|(pattern, result, _)| PatternMatch {
-                            pattern: pattern.clone(),
-                            result: result.clone(),
-                        })
-                        .collect(),
-                }),
-            );
-            let expr_type = patterns.first().unwrap().2.clone();
-            (expr, expr_type)
-        })
     })
     .boxed()
 }
 
-/// Generates a single pattern.
+/// Generates a single pattern, along with its result.
 fn gen_pattern(
     config: Rc<ExprGenConfig>,
-    next_depth: std::ops::Range<usize>,
+    next_size: Size,
     pattern_type: Monotype,
     target_type: TargetType,
     bindings: Bindings,
 ) -> impl Strategy<Value = (Pattern, Expr, Monotype)> {
-    let mut choices: Vec<BoxedStrategy<Pattern>> = vec![];
-    if let Some(primitive_strategy) =
-        gen_primitive(pattern_type.into()).map(|strategy| strategy.prop_map(Pattern::Primitive))
+    gen_pattern_of_type(config.clone(), pattern_type, bindings).prop_flat_map(
+        move |(pattern, result_bindings)| {
+            gen_nested(
+                config.clone(),
+                next_size.clone(),
+                target_type.clone(),
+                result_bindings,
+            )
+            .prop_map(move |(expr, expr_type)| (pattern.clone(), expr, expr_type))
+        },
+    )
+}
+
+/// Generates a pattern that matches values of the given type, along with the
+/// bindings extended by any variables in the pattern.
+fn gen_pattern_of_type(
+    config: Rc<ExprGenConfig>,
+    pattern_type: Monotype,
+    bindings: Bindings,
+) -> BoxedStrategy<(Pattern, Bindings)> {
+    let mut choices: Vec<BoxedStrategy<(Pattern, Bindings)>> = vec![];
+    if let Some(primitive_strategy) = gen_primitive(pattern_type.clone().into()) {
+        let bindings_ = bindings.clone();
+        choices.push(
+            primitive_strategy
+                .prop_map(move |primitive| (Pattern::Primitive(primitive), bindings_.clone()))
+                .boxed(),
+        );
+    };
+    match pattern_type.as_ref() {
+        Type::Tuple(element_types) => {
+            choices.push(
+                gen_patterns_of_types(config.clone(), element_types.clone(), bindings.clone())
+                    .prop_map(|(patterns, bindings)| (Pattern::Tuple(patterns), bindings))
+                    .boxed(),
+            );
+        }
+        Type::List(element_type) => {
+            let config_ = config.clone();
+            let element_type_ = element_type.clone();
+            let bindings_ = bindings.clone();
+            choices.push(
+                (0usize..=2)
+                    .prop_flat_map(move |length| {
+                        gen_patterns_of_types(
+                            config_.clone(),
+                            vec![element_type_.clone(); length],
+                            bindings_.clone(),
+                        )
+                    })
+                    .prop_map(|(patterns, bindings)| (Pattern::List(patterns), bindings))
+                    .boxed(),
+            );
+            choices.push(
+                gen_patterns_of_types(
+                    config.clone(),
+                    vec![element_type.clone(), pattern_type.clone()],
+                    bindings.clone(),
+                )
+                .prop_map(|(mut patterns, bindings)| {
+                    let tail = patterns.pop().unwrap();
+                    let head = patterns.pop().unwrap();
+                    (
+                        Pattern::Cons {
+                            head: head.into(),
+                            tail: tail.into(),
+                        },
+                        bindings,
+                    )
+                })
+                .boxed(),
+            );
+        }
+        _ => {}
+    }
     {
-        choices.push(primitive_strategy.boxed());
+        let bindings_ = bindings.clone();
+        choices.push(
+            gen_unused_identifier(config, bindings.clone())
+                .prop_map(move |name| {
+                    (
+                        Pattern::Variable(name.clone()),
+                        bindings_.update(name, pattern_type.clone()),
+                    )
+                })
+                .boxed(),
+        );
+    }
+    choices.push(Just((Pattern::Anything, bindings)).boxed());
+    prop::strategy::Union::new(choices).boxed()
+}
+
+/// The names of the built-ins which may be called by generated expressions.
+///
+/// These must never be shadowed.
+const BUILTIN_NAMES: [&str; 5] = ["concat", "head", "split", "to_lower", "to_upper"];
+
+/// Generates a call to a built-in function on strings, if the target type is
+/// a string.
+fn gen_string_builtin(
+    config: Rc<ExprGenConfig>,
+    next_size: Size,
+    target_type: TargetType,
+    bindings: Bindings,
+) -> Option<ExprStrategy> {
+    if !target_type.matches_monotype(&Type::String.into()) {
+        return None;
+    }
+    // the largest call, `head (split separator string)`, has six nodes below
+    // the outermost application, including the two arguments
+    if !next_size.fits(6) {
+        return None;
+    }
+    let next_size = next_size.split(6);
+    let call = |name: &str, arguments: Vec<Expr>| {
+        let function = Expr::new(
+            0.into(),
+            Expression::Identifier(Identifier::name_from_str(name).unwrap()),
+        );
+        arguments.into_iter().fold(function, |function, argument| {
+            Expr::new(0.into(), Expression::Apply(Apply { function, argument }))
+        })
     };
-    choices.push(Just(Pattern::Anything).boxed());
-    prop::strategy::Union::new(choices).prop_flat_map(move |pattern| {
+    let gen_string = move || {
         gen_nested(
             config.clone(),
-            next_depth.clone(),
-            target_type.clone(),
+            next_size.clone(),
+            Type::String.into(),
             bindings.clone(),
         )
-        .prop_map(move |(expr, expr_type)| (pattern.clone(), expr, expr_type))
-    })
+    };
+    Some(
+        prop_oneof![
+            (prop::bool::ANY, gen_string()).prop_map(move |(upper, (string, _))| {
+                let name = if upper { "to_upper" } else { "to_lower" };
+                call(name, vec![string])
+            }),
+            (gen_string(), gen_string())
+                .prop_map(move |((left, _), (right, _))| call("concat", vec![left, right])),
+            (gen_string(), gen_string()).prop_map(move |((separator, _), (string, _))| {
+                call("head", vec![call("split", vec![separator, string])])
+            }),
+        ]
+        .prop_map(|expr| (expr, Type::String.into()))
+        .boxed(),
+    )
+}
+
+/// Generates a sequence of patterns matching the given types in turn, threading
+/// the bindings through so that variables are not bound twice.
+fn gen_patterns_of_types(
+    config: Rc<ExprGenConfig>,
+    pattern_types: Vec<Monotype>,
+    bindings: Bindings,
+) -> BoxedStrategy<(Vec<Pattern>, Bindings)> {
+    let initial = Just((Vec::<Pattern>::new(), bindings)).boxed();
+    pattern_types
+        .into_iter()
+        .fold(initial, |strategy, pattern_type| {
+            let config_ = config.clone();
+            strategy
+                .prop_flat_map(move |(patterns, bindings)| {
+                    gen_pattern_of_type(config_.clone(), pattern_type.clone(), bindings).prop_map(
+                        move |(pattern, next_bindings)| {
+                            let mut next_patterns = patterns.clone();
+                            next_patterns.push(pattern);
+                            (next_patterns, next_bindings)
+                        },
+                    )
+                })
+                .boxed()
+        })
 }
 
 /// Generates a function application.
 fn gen_apply(
     config: Rc<ExprGenConfig>,
-    next_depth: std::ops::Range<usize>,
+    next_size: Size,
     target_type: TargetType,
     bindings: Bindings,
 ) -> ExprStrategy {
+    // the function gets whatever the argument leaves over
     gen_nested(
         config.clone(),
-        next_depth.clone(),
+        next_size.split(2),
         TargetType::Unknown,
         bindings.clone(),
     )
     .prop_flat_map(move |(argument, argument_type): ExprStrategyValue| {
         gen_nested(
             config.clone(),
-            next_depth.clone(),
+            next_size.remaining(&argument),
             TargetType::Known(
                 Type::Function {
                     parameter: argument_type.into(),
@@ -545,64 +1452,215 @@ fn gen_apply(
     .boxed()
 }
 
+/// Generates a call to an already-bound function (in `bindings`) which returns
+/// the target type.
+///
+/// If there is no such function, returns `None`.
+fn gen_call(
+    config: Rc<ExprGenConfig>,
+    next_size: Size,
+    target_type: TargetType,
+    bindings: Bindings,
+) -> Option<ExprStrategy> {
+    // the name of the function is one of the two children
+    let next_size = next_size.split(2);
+    let functions = bindings
+        .iter()
+        .filter_map(|(name, typ)| match typ.as_ref() {
+            Type::Function { parameter, body } if target_type.matches_monotype(body) => {
+                Some((name.clone(), parameter.clone(), body.clone()))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if functions.is_empty() {
+        return None;
+    }
+    Some(
+        proptest::sample::select(functions)
+            .prop_flat_map(move |(name, parameter_type, body_type)| {
+                gen_nested(
+                    config.clone(),
+                    next_size.clone(),
+                    parameter_type.into(),
+                    bindings.clone(),
+                )
+                .prop_map(move |(argument, _)| {
+                    let expr = Expr::new(
+                        0.into(),
+                        Expression::Apply(Apply {
+                            function: Expr::new(0.into(), Expression::Identifier(name.clone())),
+                            argument,
+                        }),
+                    );
+                    (expr, body_type.clone())
+                })
+            })
+            .boxed(),
+    )
+}
+
 /// Generates an infix operation of the given type.
-/// If the type is not `Integer`, returns `None`.
+///
+/// Arithmetic operations are generated for `Integer` and `Float` targets,
+/// bitwise operations for `Integer` targets, concatenation for `String`
+/// targets, and comparisons (of any primitive type) for `Boolean` targets. For
+/// any other type, returns `None`.
+///
+/// Integer divisors (for both division and modulo) are always non-zero
+/// literals, and shift amounts are always small, non-negative literals, so that
+/// the generated expression can always be evaluated successfully.
 fn gen_infix(
     config: Rc<ExprGenConfig>,
-    next_depth: std::ops::Range<usize>,
+    next_size: Size,
     target_type: TargetType,
     bindings: Bindings,
 ) -> Option<ExprStrategy> {
-    match target_type {
-        TargetType::Known(known) if *known == Type::Integer => Some(
-            proptest::arbitrary::any::<Operation>()
-                .prop_flat_map(move |operation| {
-                    (
-                        gen_nested(
-                            config.clone(),
-                            next_depth.clone(),
-                            TargetType::Known(Type::Integer.into()),
-                            bindings.clone(),
-                        ),
-                        gen_nested(
-                            config.clone(),
-                            next_depth.clone(),
-                            TargetType::Known(Type::Integer.into()),
-                            bindings.clone(),
-                        ),
-                    )
-                        .prop_map(move |((left, _), (right, _))| {
-                            let expr = Expr::new(
-                                0.into(),
-                                Expression::Infix(Infix {
-                                    operation,
-                                    left,
-                                    right,
-                                }),
-                            );
-                            (expr, Type::Integer.into())
-                        })
-                })
-                .boxed(),
+    let next_size = next_size.split(2);
+    let (operations, operand_types, result_type): (
+        Vec<Operation>,
+        Vec<Type<TargetType>>,
+        Monotype,
+    ) = match target_type {
+        TargetType::Known(known) if *known == Type::Integer => (
+            vec![
+                Operation::Add,
+                Operation::Subtract,
+                Operation::Multiply,
+                Operation::Divide,
+                Operation::Modulo,
+                Operation::BitwiseAnd,
+                Operation::BitwiseOr,
+                Operation::BitwiseXor,
+                Operation::ShiftLeft,
+                Operation::ShiftRight,
+            ],
+            vec![Type::Integer],
+            Type::Integer.into(),
         ),
-        _ => None,
-    }
+        TargetType::Known(known) if *known == Type::Float => (
+            vec![
+                Operation::AddFloat,
+                Operation::SubtractFloat,
+                Operation::MultiplyFloat,
+                Operation::DivideFloat,
+            ],
+            vec![Type::Float],
+            Type::Float.into(),
+        ),
+        TargetType::Known(known) if *known == Type::String => (
+            vec![Operation::Concatenate],
+            vec![Type::String],
+            Type::String.into(),
+        ),
+        TargetType::Known(known) if *known == Type::Boolean => (
+            vec![
+                Operation::Equal,
+                Operation::NotEqual,
+                Operation::LessThan,
+                Operation::LessThanOrEqual,
+                Operation::GreaterThan,
+                Operation::GreaterThanOrEqual,
+            ],
+            vec![
+                Type::Integer,
+                Type::Float,
+                Type::Boolean,
+                Type::Char,
+                Type::String,
+            ],
+            Type::Boolean.into(),
+        ),
+        _ => return None,
+    };
+    Some(
+        (
+            proptest::sample::select(operations),
+            proptest::sample::select(operand_types),
+        )
+            .prop_flat_map(move |(operation, operand_type)| {
+                let result_type = result_type.clone();
+                let gen_right = match operation {
+                    Operation::Divide | Operation::Modulo => Integer::arbitrary()
+                        .prop_filter("non-zero", |divisor| *divisor != Integer::from(0))
+                        .prop_map(|divisor| make_primitive_expr(Primitive::Integer(divisor)))
+                        .boxed(),
+                    Operation::ShiftLeft | Operation::ShiftRight => (0u32..64)
+                        .prop_map(|amount| make_primitive_expr(Primitive::Integer(amount.into())))
+                        .boxed(),
+                    _ => gen_nested(
+                        config.clone(),
+                        next_size.clone(),
+                        operand_type.clone().into(),
+                        bindings.clone(),
+                    ),
+                };
+                (
+                    gen_nested(
+                        config.clone(),
+                        next_size.clone(),
+                        operand_type.into(),
+                        bindings.clone(),
+                    ),
+                    gen_right,
+                )
+                    .prop_map(move |((left, _), (right, _))| {
+                        let expr = Expr::new(
+                            0.into(),
+                            Expression::Infix(Infix {
+                                operation,
+                                operator_span: 0.into(),
+                                left,
+                                right,
+                            }),
+                        );
+                        (expr, result_type.clone())
+                    })
+            })
+            .boxed(),
+    )
+}
+
+/// Generates a sequence of two expressions, the first of any type, and the
+/// second of the target type.
+fn gen_sequence(
+    config: Rc<ExprGenConfig>,
+    next_size: Size,
+    target_type: TargetType,
+    bindings: Bindings,
+) -> ExprStrategy {
+    let next_size = next_size.split(2);
+    (
+        gen_nested(
+            config.clone(),
+            next_size.clone(),
+            TargetType::Unknown,
+            bindings.clone(),
+        ),
+        gen_nested(config, next_size, target_type, bindings),
+    )
+        .prop_map(|((first, _), (second, second_type))| {
+            let expr = Expr::new(0.into(), Expression::Sequence(Sequence { first, second }));
+            (expr, second_type)
+        })
+        .boxed()
 }
 
-/// Generates an expression along with its valid type.
-fn gen_typed(
+/// Generates an expression annotated with its valid type.
+fn gen_type_annotation(
     config: Rc<ExprGenConfig>,
-    next_depth: std::ops::Range<usize>,
+    next_size: Size,
     target_type: TargetType,
     bindings: Bindings,
 ) -> ExprStrategy {
-    gen_nested(config, next_depth, target_type, bindings)
+    gen_nested(config, next_size, target_type, bindings)
         .prop_map(|(expr, typ)| {
             let typed_expr = Expr::new(
                 expr.span,
                 Expression::Typed(Typed {
                     expression: expr,
                     typ: typ.clone(),
+                    typ_span: 0.into(),
                 }),
             );
             (typed_expr, typ)
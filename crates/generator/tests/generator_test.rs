@@ -0,0 +1,83 @@
+use std::rc::Rc;
+
+use proptest::prelude::*;
+use proptest::test_runner::TestCaseResult;
+
+use boo_core::types::Type;
+use boo_generator::ExprGenConfig;
+use boo_language::*;
+use boo_test_helpers::proptest::*;
+
+#[test]
+fn test_generating_expressions_within_a_node_budget() {
+    let config = ExprGenConfig {
+        nodes: Some(200),
+        ..Default::default()
+    };
+    check(&boo_generator::gen(Rc::new(config)), |input| {
+        let nodes = count_nodes(&input);
+        prop_assert!(nodes <= 200, "\nnodes = {}\ninput = {}\n", nodes, input);
+        Ok(())
+    })
+}
+
+#[test]
+fn test_generating_functions_within_a_node_budget() {
+    let config = ExprGenConfig {
+        nodes: Some(1),
+        ..Default::default()
+    };
+    let function_type = Type::Function {
+        parameter: Type::Integer.into(),
+        body: Type::Integer.into(),
+    };
+    // there is no function with fewer than two nodes
+    check(
+        &boo_generator::gen_typed(Rc::new(config), function_type.into()),
+        |input| {
+            prop_assert_eq!(count_nodes(&input), 2, "\ninput = {}\n", input);
+            Ok(())
+        },
+    )
+}
+
+#[test]
+fn test_locating_an_expression_in_its_rendered_source() {
+    check(&boo_generator::arbitrary(), |input| {
+        let (source, located) = boo_generator::locate(&input)?;
+        check_spans(&source, &located)
+    })
+}
+
+#[test]
+fn test_generating_expressions_along_with_their_rendered_source() {
+    check(
+        &boo_generator::roundtripped(boo_generator::arbitrary()),
+        |(source, located)| check_spans(&source, &located),
+    )
+}
+
+fn check_spans(source: &str, expr: &Expr) -> TestCaseResult {
+    let range = expr.span.range();
+    prop_assert!(range.end <= source.len(), "\nsource = {}\n", source);
+    match expr.expression.as_ref() {
+        Expression::Primitive(value) => prop_assert_eq!(&source[range], value.to_string()),
+        Expression::Identifier(name) => prop_assert_eq!(&source[range], name.to_string()),
+        _ => {}
+    }
+    for child in expr.children() {
+        prop_assert!(
+            expr.span.start <= child.span.start && child.span.end <= expr.span.end,
+            "\nparent = {:?}\nchild = {:?}\nsource = {}\n",
+            expr.span,
+            child.span,
+            source
+        );
+        check_spans(source, child)?;
+    }
+    Ok(())
+}
+
+fn count_nodes(expr: &Expr) -> usize {
+    1 + expr.children().into_iter().map(count_nodes).sum::<usize>()
+}
@@ -0,0 +1,176 @@
+//! Syntax highlighting for the REPL.
+
+use nu_ansi_term::{Color, Style};
+use reedline::{Highlighter, StyledText};
+
+use boo::error::Error;
+use boo::parser::lexer::{tokens, AnnotatedToken, Token};
+use boo::span::Span;
+
+/// Colors each token in the input as it is typed, according to the lexer.
+///
+/// Anything the lexer rejects is underlined. Whitespace and comments are left
+/// as they are.
+pub struct BooHighlighter;
+
+impl Highlighter for BooHighlighter {
+    fn highlight(&self, line: &str, _cursor: usize) -> StyledText {
+        let mut styled = StyledText::new();
+        // a command, such as `:type`, is not part of the expression
+        let start = if line.starts_with(':') {
+            let end = line.find(' ').unwrap_or(line.len());
+            styled.push((Kind::Command.style(), line[..end].to_string()));
+            end
+        } else {
+            0
+        };
+        let mut position = start;
+        for token in tokens(&line[start..]) {
+            let (span, kind) = match token {
+                Ok(AnnotatedToken { annotation, token }) => (annotation, kind_of(&token)),
+                Err(
+                    Error::UnexpectedToken { span, .. }
+                    | Error::InvalidEscapeSequence { span, .. }
+                    | Error::InvalidDigit { span, .. },
+                ) => (span, Kind::Invalid),
+                // the lexer produces no other errors
+                Err(_) => continue,
+            };
+            let span: Span = (start + span.start..start + span.end).into();
+            styled.push((Kind::Plain.style(), line[position..span.start].to_string()));
+            styled.push((kind.style(), line[span.range()].to_string()));
+            position = span.end;
+        }
+        styled.push((Kind::Plain.style(), line[position..].to_string()));
+        styled
+    }
+}
+
+/// The kinds of text which are highlighted differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Plain,
+    Command,
+    Keyword,
+    Literal,
+    Text,
+    Identifier,
+    Operator,
+    Invalid,
+}
+
+impl Kind {
+    fn style(self) -> Style {
+        match self {
+            Kind::Plain => Style::new(),
+            Kind::Command => Style::new().bold(),
+            Kind::Keyword => Color::Magenta.bold(),
+            Kind::Literal => Color::Cyan.normal(),
+            Kind::Text => Color::Green.normal(),
+            Kind::Identifier => Color::Blue.normal(),
+            Kind::Operator => Color::Yellow.normal(),
+            Kind::Invalid => Color::Red.underline(),
+        }
+    }
+}
+
+fn kind_of(token: &Token) -> Kind {
+    match token {
+        Token::Def
+        | Token::Let
+        | Token::In
+        | Token::Rec
+        | Token::And
+        | Token::Try
+        | Token::Fn
+        | Token::Match => Kind::Keyword,
+        Token::Boolean(_) | Token::Integer(_) | Token::Float(_) => Kind::Literal,
+        Token::Char(_) | Token::String(_) => Kind::Text,
+        Token::Identifier(_) => Kind::Identifier,
+        Token::Operator(_) | Token::Arrow | Token::Assign | Token::Annotate | Token::Dot => {
+            Kind::Operator
+        }
+        Token::StartGroup
+        | Token::EndGroup
+        | Token::StartList
+        | Token::EndList
+        | Token::BlockStart
+        | Token::BlockEnd
+        | Token::Separator
+        | Token::Comma
+        | Token::Anything => Kind::Plain,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlight(line: &str) -> Vec<(Style, String)> {
+        BooHighlighter
+            .highlight(line, 0)
+            .buffer
+            .into_iter()
+            .filter(|(_, text)| !text.is_empty())
+            .collect()
+    }
+
+    fn styled(kind: Kind, text: &str) -> (Style, String) {
+        (kind.style(), text.to_string())
+    }
+
+    #[test]
+    fn test_highlighting_each_kind_of_token() {
+        let result = highlight("let x = 1 + y in (x)");
+
+        assert_eq!(
+            result,
+            vec![
+                styled(Kind::Keyword, "let"),
+                styled(Kind::Plain, " "),
+                styled(Kind::Identifier, "x"),
+                styled(Kind::Plain, " "),
+                styled(Kind::Operator, "="),
+                styled(Kind::Plain, " "),
+                styled(Kind::Literal, "1"),
+                styled(Kind::Plain, " "),
+                styled(Kind::Operator, "+"),
+                styled(Kind::Plain, " "),
+                styled(Kind::Identifier, "y"),
+                styled(Kind::Plain, " "),
+                styled(Kind::Keyword, "in"),
+                styled(Kind::Plain, " "),
+                styled(Kind::Plain, "("),
+                styled(Kind::Identifier, "x"),
+                styled(Kind::Plain, ")"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_underlining_invalid_tokens() {
+        let result = highlight(":t 1 $ 2");
+
+        assert_eq!(
+            result,
+            vec![
+                styled(Kind::Command, ":t"),
+                styled(Kind::Plain, " "),
+                styled(Kind::Literal, "1"),
+                styled(Kind::Plain, " "),
+                styled(Kind::Invalid, "$"),
+                styled(Kind::Plain, " "),
+                styled(Kind::Literal, "2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_preserving_comments_and_unfinished_input() {
+        let line = "\"unfinished # not a comment\n# a comment\n";
+
+        let text = BooHighlighter.highlight(line, 0).raw_string();
+
+        assert_eq!(text, line);
+    }
+}
@@ -0,0 +1,101 @@
+//! Machine-readable output, for `--output json`.
+//!
+//! Each input produces a single line of JSON, describing either its value and
+//! type, or the errors that prevented it from being evaluated, along with any
+//! warnings.
+
+use miette::{Report, Severity};
+use serde_json::{json, Value};
+
+use boo::evaluation::Evaluated;
+use boo::span::SourceIndex;
+use boo::types::Monotype;
+
+use crate::Source;
+
+/// Describes the result of a program that was evaluated successfully. Its
+/// type is left out if it was not type-checked.
+pub fn success(value: &Evaluated, typ: Option<&Monotype>, warnings: Vec<Value>) -> Value {
+    json!({
+        "value": match typ {
+            Some(typ) => value.display_as(typ).to_string(),
+            None => value.to_string(),
+        },
+        "type": typ.map(|typ| typ.to_string()),
+        "warnings": warnings,
+    })
+}
+
+/// Describes the errors that stopped a program from being evaluated.
+pub fn failure(errors: Vec<Value>, warnings: Vec<Value>) -> Value {
+    json!({
+        "errors": errors,
+        "warnings": warnings,
+    })
+}
+
+/// Describes an error or warning, along with each of the locations it refers
+/// to, as byte offsets and as lines and columns.
+pub fn diagnostic(report: &Report, source: Source) -> Value {
+    let index = SourceIndex::new(source.text);
+    let labels = report
+        .labels()
+        .into_iter()
+        .flatten()
+        .map(|label| {
+            let location = index.location(label.offset());
+            json!({
+                "label": label.label(),
+                "start": label.offset(),
+                "end": label.offset() + label.len(),
+                "line": location.line,
+                "column": location.column,
+            })
+        })
+        .collect::<Vec<_>>();
+    let severity = match report.severity().unwrap_or(Severity::Error) {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Advice => "advice",
+    };
+    json!({
+        "file": source.name,
+        "code": report.code().map(|code| code.to_string()),
+        "severity": severity,
+        "message": report.to_string(),
+        "help": report.help().map(|help| help.to_string()),
+        "labels": labels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describing_a_diagnostic() {
+        let text = "let x = 1 in\nx + true";
+        let source = Source { name: None, text };
+        let core = boo::parse_expr(text).unwrap().to_core().unwrap();
+        let error = boo_types_hindley_milner::type_of(&core, boo::builtins::Globals::default())
+            .unwrap_err();
+
+        let described = diagnostic(&Report::new(error), source);
+
+        assert_eq!(
+            described["code"],
+            "boo::type_checker::type_unification_error"
+        );
+        assert_eq!(described["severity"], "error");
+        assert_eq!(
+            described["labels"][1],
+            json!({
+                "label": "but this is Boolean",
+                "start": 17,
+                "end": 21,
+                "line": 2,
+                "column": 5,
+            })
+        );
+    }
+}
@@ -1,69 +1,459 @@
-use std::io::IsTerminal;
+mod highlighter;
+mod json;
+mod prompt;
+mod validator;
 
-use clap::Parser;
+use std::collections::HashMap;
+use std::io::{BufRead, IsTerminal};
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
 use miette::IntoDiagnostic;
 use reedline::*;
 
-use boo::evaluation::{EvaluationContext, Evaluator};
+use boo::builtins::{Globals, IoCapabilities};
+use boo::diagnostics::Diagnostics;
+use boo::evaluation::{Evaluated, EvaluationContext, Evaluator, IntegerOverflow};
+use boo::identifier::Identifier;
+use boo::optimization::Optimizations;
+use boo::span::SourceIndex;
+use boo::types::{Monotype, Polytype, Type, TypeVariable};
+use boo::Program;
+use boo_types_hindley_milner::typed::TypedExpr;
+
+use crate::highlighter::BooHighlighter;
+use crate::prompt::BooPrompt;
+use crate::validator::BooValidator;
 
 #[derive(Debug, Parser)]
 struct Args {
-    /// Use evaluation by reduction instead of optimized evaluation.
+    /// Evaluates the given program and exits, rather than starting a REPL or
+    /// reading the program from stdin.
+    #[arg(short, long, value_name = "PROGRAM")]
+    eval: Option<String>,
+    /// Runs each file in turn, followed by the program given with `--eval`,
+    /// if any, and exits. Each can use the definitions made by the ones
+    /// before it.
+    files: Vec<PathBuf>,
+    /// Reads stdin line by line, interpreting each line as a separate program,
+    /// and printing one result for each. Each line can use the definitions
+    /// made by the ones before it. Blank lines are skipped.
+    #[arg(long, conflicts_with_all = ["eval", "files"])]
+    lines: bool,
+    /// The evaluator used to run programs.
+    #[arg(long, alias = "kernel", value_enum, default_value_t = EvaluatorName::Optimized)]
+    evaluator: EvaluatorName,
+    /// Compile the program to a WebAssembly module, written to the given file,
+    /// instead of evaluating it.
+    #[arg(long, value_name = "FILE")]
+    compile_wasm: Option<PathBuf>,
+    /// Print the program, canonically formatted, instead of evaluating it.
+    #[arg(long, conflicts_with_all = ["compile_wasm", "profile"])]
+    fmt: bool,
+    /// Evaluate with the optimized evaluator, and then print the expressions
+    /// which took the longest to evaluate.
+    #[arg(long, conflicts_with = "evaluator")]
+    profile: bool,
+    /// What evaluation does when arithmetic on integers that fit in 32 bits
+    /// produces one that does not.
+    #[arg(long, value_enum, default_value_t = IntegerOverflowName::Promote)]
+    integer_overflow: IntegerOverflowName,
+    /// The value of `seed`, the first state given to `random`, so that its
+    /// numbers can be reproduced. Without one, the seed is taken from the
+    /// clock.
     #[arg(long)]
-    reduction: bool,
+    seed: Option<u64>,
+    /// How results and errors are printed.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text, conflicts_with_all = ["compile_wasm", "fmt"])]
+    output: OutputFormat,
+    /// The number of steps shown by `:trace` before it gives up.
+    #[arg(long, default_value_t = 100)]
+    trace_limit: usize,
+    /// A file of settings, read when the REPL starts. Each line is an option
+    /// and its value, as given to `:set`. Blank lines, and lines starting
+    /// with `#`, are ignored. Defaults to `boo/config` in `$XDG_CONFIG_HOME`,
+    /// or in `~/.config`, if it exists.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum EvaluatorName {
+    /// Pools expressions, and then evaluates them recursively.
+    Optimized,
+    /// Evaluates the expression recursively, without pooling.
+    Recursive,
+    /// Evaluates by reducing the expression step by step.
+    Reduction,
+    /// Evaluates with a Krivine machine, using call-by-name, which can be
+    /// exponentially slower than the others.
+    Krivine,
+    /// Evaluates with a SECD machine.
+    Secd,
+    /// Evaluates with a CEK machine.
+    Cek,
+    /// Compiles expressions to bytecode, and runs them on a virtual machine.
+    Vm,
+    /// Compiles integer arithmetic to machine code, and evaluates everything
+    /// else with the optimized evaluator.
+    #[cfg(feature = "jit")]
+    Jit,
+}
+
+impl EvaluatorName {
+    fn name(self) -> String {
+        self.to_possible_value()
+            .expect("Every evaluator has a name.")
+            .get_name()
+            .to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Prints results for people to read, with errors and warnings shown
+    /// alongside the source code.
+    Text,
+    /// Prints a line of JSON for each program evaluated, with its value and
+    /// type, or its errors, along with any warnings. Commands, such as
+    /// `:type`, still print text.
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum IntegerOverflowName {
+    /// Promotes the result to an arbitrary-precision integer.
+    Promote,
+    /// Fails with an error.
+    Fail,
+}
+
+impl From<IntegerOverflowName> for IntegerOverflow {
+    fn from(name: IntegerOverflowName) -> Self {
+        match name {
+            IntegerOverflowName::Promote => IntegerOverflow::Promote,
+            IntegerOverflowName::Fail => IntegerOverflow::Fail,
+        }
+    }
 }
 
+/// The number of expressions shown when profiling.
+const HOT_SPOTS: usize = 10;
+
+/// Evaluation stops once it uses approximately this much memory, so that a
+/// program with a space leak fails, rather than bringing down the REPL.
+const MEMORY_LIMIT: usize = 512 * 1024 * 1024;
+
 enum Command<'a> {
-    Evaluate(&'a dyn Evaluator),
+    Evaluate(&'a Args),
     ShowType,
+    ShowTypeAt(usize),
+    Lint,
+    Specialize,
+    Provenance,
+    Bindings,
+    ShowAst {
+        spans: bool,
+    },
+    ShowCore,
+    Trace {
+        limit: usize,
+        integer_overflow: IntegerOverflow,
+    },
+    Set,
+}
+
+/// The input being interpreted, after any command.
+#[derive(Clone, Copy)]
+struct Source<'a> {
+    /// The file the input came from, if it came from one.
+    name: Option<&'a str>,
+    text: &'a str,
+}
+
+/// The definitions made so far, which are visible to each subsequent input,
+/// along with the settings.
+#[derive(Default)]
+struct Session {
+    definitions: Vec<(Identifier, Polytype, boo::expr::Expr)>,
+    /// The type of the last result, shown by the prompt if it is configured
+    /// to do so.
+    last_type: Option<Monotype>,
+    settings: Settings,
+}
+
+/// The options which can be changed with `:set`, or in the configuration
+/// file.
+struct Settings {
+    /// The REPL prompt, as described by [`BooPrompt`].
+    prompt: String,
+    evaluation: EvaluationConfig,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            prompt: prompt::DEFAULT_PROMPT.to_string(),
+            evaluation: EvaluationConfig::default(),
+        }
+    }
+}
+
+/// How programs are checked and evaluated.
+#[derive(Debug, Clone, Copy)]
+struct EvaluationConfig {
+    /// Whether programs are type-checked before they are evaluated.
+    type_check: bool,
+    /// The most steps each evaluation can take, if it is limited.
+    fuel: Option<usize>,
+    /// Whether warnings, including those from the linter, stop programs from
+    /// being evaluated, as errors do.
+    strict: bool,
+    /// The optimizations applied by the optimized evaluator.
+    optimizations: Optimizations,
+}
+
+impl Default for EvaluationConfig {
+    fn default() -> Self {
+        Self {
+            type_check: true,
+            fuel: None,
+            strict: false,
+            optimizations: Optimizations::ALL,
+        }
+    }
+}
+
+impl Settings {
+    /// Sets an option, given as its name followed by its value.
+    ///
+    /// The value can be surrounded by double quotes, so that it can start or
+    /// end with spaces.
+    fn set(&mut self, setting: &str) -> miette::Result<()> {
+        let setting = setting.trim();
+        let (option, value) = setting.split_once(' ').unwrap_or((setting, ""));
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        let evaluation = &mut self.evaluation;
+        match option {
+            "prompt" => self.prompt = value.to_string(),
+            "type-check" => evaluation.type_check = parse_switch(value)?,
+            "fuel" => {
+                evaluation.fuel = match value {
+                    "none" => None,
+                    _ => Some(value.parse().map_err(|_| {
+                        miette::miette!(
+                            "Expected a number of steps, or \"none\", but got {value:?}"
+                        )
+                    })?),
+                }
+            }
+            "strict" => evaluation.strict = parse_switch(value)?,
+            "inline" => evaluation.optimizations.inline = parse_switch(value)?,
+            "dead-code-elimination" => {
+                evaluation.optimizations.eliminate_dead_code = parse_switch(value)?
+            }
+            _ => return Err(miette::miette!("Unknown option: {option:?}")),
+        }
+        Ok(())
+    }
 }
 
-fn main() {
+impl std::fmt::Display for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let switch = |on: bool| if on { "on" } else { "off" };
+        let evaluation = &self.evaluation;
+        writeln!(f, "prompt {:?}", self.prompt)?;
+        writeln!(f, "type-check {}", switch(evaluation.type_check))?;
+        match evaluation.fuel {
+            Some(fuel) => writeln!(f, "fuel {fuel}")?,
+            None => writeln!(f, "fuel none")?,
+        }
+        writeln!(f, "strict {}", switch(evaluation.strict))?;
+        writeln!(f, "inline {}", switch(evaluation.optimizations.inline))?;
+        writeln!(
+            f,
+            "dead-code-elimination {}",
+            switch(evaluation.optimizations.eliminate_dead_code)
+        )
+    }
+}
+
+fn parse_switch(value: &str) -> miette::Result<bool> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(miette::miette!(
+            "Expected \"on\" or \"off\", but got {value:?}"
+        )),
+    }
+}
+
+impl Session {
+    /// The built-ins permitted by the arguments, followed by the definitions.
+    fn globals(&self, args: &Args) -> Globals {
+        self.definitions.iter().fold(
+            Globals::new(capabilities(args)),
+            |globals, (name, typ, value)| {
+                globals.with_definition(name.clone(), typ.clone(), value.clone())
+            },
+        )
+    }
+
+    /// Records each of the program's definitions, in order.
+    ///
+    /// Earlier definitions are kept even if they are shadowed, as the ones
+    /// after them may still refer to them.
+    ///
+    /// The type of each definition is taken from the type-checked program, in
+    /// which the definitions are nested around the main expression. Without
+    /// type checking, a definition which has no type is given the most general
+    /// one, `forall a. a`, so that it can be used anywhere.
+    fn define(
+        &mut self,
+        mut globals: Globals,
+        program: Program,
+        mut typed: Option<&TypedExpr>,
+    ) -> miette::Result<()> {
+        for definition in program.definitions {
+            let value = definition.value.to_core()?.without_spans();
+            let typ = match typed.map(TypedExpr::expression) {
+                Some(boo::ast::Expression::Assign(boo::ast::Assign {
+                    value: typed_value,
+                    inner,
+                    ..
+                })) => {
+                    typed = Some(inner);
+                    boo_types_hindley_milner::generalize(typed_value.typ().clone())
+                }
+                _ => boo_types_hindley_milner::generalized_type_of(&value, globals.clone())
+                    .unwrap_or_else(|_| {
+                        let variable = TypeVariable::new_from_str("a");
+                        Polytype {
+                            quantifiers: vec![variable.clone()],
+                            mono: Type::Variable(variable).into(),
+                        }
+                    }),
+            };
+            globals = globals.with_definition(definition.name.clone(), typ.clone(), value.clone());
+            self.definitions.push((definition.name, typ, value));
+        }
+        Ok(())
+    }
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
-    let evaluator: Box<dyn Evaluator> = if args.reduction {
-        let mut context = boo_evaluation_reduction::new();
-        boo::builtins::prepare(&mut context).unwrap();
-        Box::new(context.evaluator())
-    } else {
-        let mut context = boo::evaluator::new();
-        boo::builtins::prepare(&mut context).unwrap();
-        Box::new(context.evaluator())
-    };
+
+    if !args.files.is_empty() || args.eval.is_some() {
+        return exit_code(run(&args));
+    }
 
     let stdin = std::io::stdin();
-    if stdin.is_terminal() {
-        repl(evaluator.as_ref());
+    if args.lines {
+        exit_code(interpret_lines(&args, stdin.lock()))
+    } else if stdin.is_terminal() {
+        repl(&args);
+        ExitCode::SUCCESS
     } else {
-        match read_and_interpret(evaluator.as_ref(), stdin) {
-            Ok(()) => (),
-            Err(report) => eprintln!("{:?}", report),
+        match read(stdin) {
+            Ok(buffer) => exit_code(interpret(&args, &mut Session::default(), None, &buffer)),
+            Err(report) => {
+                eprintln!("{:?}", report);
+                ExitCode::FAILURE
+            }
         }
     }
 }
 
-fn read_and_interpret(
-    evaluator: &dyn Evaluator,
-    mut input: impl std::io::Read,
-) -> miette::Result<()> {
+/// Runs the files, and then the program given on the command line, stopping
+/// at the first failure.
+///
+/// Returns whether they all succeeded.
+fn run(args: &Args) -> bool {
+    let mut session = Session::default();
+    for path in &args.files {
+        let name = path.display().to_string();
+        let succeeded = match std::fs::read_to_string(path) {
+            Ok(buffer) => interpret(args, &mut session, Some(&name), &buffer),
+            Err(error) => {
+                eprintln!("{name}: {error}");
+                false
+            }
+        };
+        if !succeeded {
+            return false;
+        }
+    }
+    match &args.eval {
+        Some(program) => interpret(args, &mut session, None, program),
+        None => true,
+    }
+}
+
+/// Interprets each line of the input in turn, carrying on after any failures.
+///
+/// Returns whether they all succeeded.
+fn interpret_lines(args: &Args, input: impl BufRead) -> bool {
+    let mut session = Session::default();
+    let mut succeeded = true;
+    for line in input.lines() {
+        match line {
+            Ok(line) if line.trim().is_empty() => {}
+            Ok(line) => {
+                succeeded &= interpret(args, &mut session, None, &line);
+            }
+            Err(error) => {
+                eprintln!("Error: {}", error);
+                return false;
+            }
+        }
+    }
+    succeeded
+}
+
+fn exit_code(succeeded: bool) -> ExitCode {
+    if succeeded {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn read(mut input: impl std::io::Read) -> miette::Result<String> {
     let mut buffer = String::new();
     input.read_to_string(&mut buffer).into_diagnostic()?;
-    interpret(evaluator, &buffer)
+    Ok(buffer)
 }
 
-fn repl(evaluator: &dyn Evaluator) {
-    let mut line_editor = Reedline::create();
-    let prompt = DefaultPrompt {
-        left_prompt: DefaultPromptSegment::Empty,
-        right_prompt: DefaultPromptSegment::Empty,
-    };
+fn repl(args: &Args) {
+    let mut line_editor = Reedline::create()
+        .with_highlighter(Box::new(BooHighlighter))
+        .with_validator(Box::new(BooValidator));
+    let mut session = Session::default();
+    if let Err(report) = configure(args, &mut session.settings) {
+        eprintln!("{:?}", report);
+    }
+
+    let evaluator = args.evaluator.name();
+    println!("Boo, using the {evaluator} evaluator.");
 
     loop {
+        let prompt = BooPrompt::new(
+            &session.settings.prompt,
+            &evaluator,
+            session.last_type.as_ref(),
+        );
         let sig = line_editor.read_line(&prompt);
         match sig {
-            Ok(Signal::Success(buffer)) => match interpret(evaluator, &buffer) {
-                Ok(()) => (),
-                Err(report) => eprintln!("{:?}", report),
-            },
+            Ok(Signal::Success(buffer)) => {
+                interpret(args, &mut session, None, &buffer);
+            }
             Ok(Signal::CtrlD) | Ok(Signal::CtrlC) => {
                 break;
             }
@@ -74,38 +464,538 @@ fn repl(evaluator: &dyn Evaluator) {
     }
 }
 
-fn interpret(evaluator: &dyn Evaluator, buffer: &str) -> miette::Result<()> {
-    let (command, expression) = if buffer.starts_with(':') {
+/// Applies each setting in the configuration file, if there is one.
+fn configure(args: &Args, settings: &mut Settings) -> miette::Result<()> {
+    let path = match &args.config {
+        Some(path) => path.clone(),
+        None => {
+            let Some(directory) = std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                })
+            else {
+                return Ok(());
+            };
+            let path = directory.join("boo").join("config");
+            if !path.exists() {
+                return Ok(());
+            }
+            path
+        }
+    };
+    let name = path.display();
+    let contents =
+        std::fs::read_to_string(&path).map_err(|error| miette::miette!("{name}: {error}"))?;
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        settings
+            .set(line)
+            .map_err(|report| miette::miette!("{name}:{}: {report}", number + 1))?;
+    }
+    Ok(())
+}
+
+/// Interprets the input, printing any error to stderr, against the name of
+/// the file it came from, if any.
+///
+/// Returns whether it succeeded.
+fn interpret(args: &Args, session: &mut Session, name: Option<&str>, buffer: &str) -> bool {
+    match parse_command(args, buffer) {
+        Ok((command, expression)) => {
+            let source = Source {
+                name,
+                text: expression,
+            };
+            let globals = session.globals(args);
+            let result = match command {
+                Command::Evaluate(args) if args.output == OutputFormat::Json => {
+                    return evaluate_as_json(args, session, globals, source);
+                }
+                command => interpret_command(command, session, globals, source),
+            };
+            match result {
+                Ok(()) => true,
+                Err(report) => {
+                    print_report(report, source);
+                    false
+                }
+            }
+        }
+        Err(report) => {
+            eprintln!("{:?}", report);
+            false
+        }
+    }
+}
+
+/// Splits the input into the command and the expression it operates on.
+fn parse_command<'a, 'b>(
+    args: &'a Args,
+    buffer: &'b str,
+) -> miette::Result<(Command<'a>, &'b str)> {
+    if buffer.starts_with(':') {
         let (first, rest) = buffer.split_once(' ').unwrap_or((buffer, ""));
         let command_name = &first[1..];
         match command_name {
-            "evaluate" => Ok((Command::Evaluate(evaluator), rest)),
+            "evaluate" => Ok((Command::Evaluate(args), rest)),
             "type" | "t" => Ok((Command::ShowType, rest)),
+            "type-at" => {
+                let (offset, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+                let offset = offset
+                    .parse()
+                    .map_err(|_| miette::miette!("Invalid offset: {offset:?}"))?;
+                Ok((Command::ShowTypeAt(offset), rest))
+            }
+            "lint" => Ok((Command::Lint, rest)),
+            "specialize" => Ok((Command::Specialize, rest)),
+            "provenance" => Ok((Command::Provenance, rest)),
+            "bindings" => Ok((Command::Bindings, rest)),
+            "ast" => match rest.split_once(' ').unwrap_or((rest, "")) {
+                ("--spans", rest) => Ok((Command::ShowAst { spans: true }, rest)),
+                _ => Ok((Command::ShowAst { spans: false }, rest)),
+            },
+            "core" => Ok((Command::ShowCore, rest)),
+            "trace" => Ok((
+                Command::Trace {
+                    limit: args.trace_limit,
+                    integer_overflow: args.integer_overflow.into(),
+                },
+                rest,
+            )),
+            "set" => Ok((Command::Set, rest)),
             _ => Err(miette::miette!("Unknown command: {command_name:?}")),
         }
     } else {
-        Ok((Command::Evaluate(evaluator), buffer))
-    }?;
+        Ok((Command::Evaluate(args), buffer))
+    }
+}
 
-    interpret_command(command, expression)
-        .map_err(|err| err.with_source_code(expression.to_string()))
+/// The interpreter permits programs to perform input and output, and to use
+/// randomness.
+fn capabilities(args: &Args) -> IoCapabilities {
+    let random_seed = args.seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64)
+    });
+    IoCapabilities {
+        print: true,
+        random_seed: Some(random_seed),
+    }
 }
 
-fn interpret_command(command: Command, expression: &str) -> miette::Result<()> {
+fn interpret_command(
+    command: Command,
+    session: &mut Session,
+    globals: Globals,
+    source: Source,
+) -> miette::Result<()> {
+    let expression = source.text;
+    let config = session.settings.evaluation;
     match command {
-        Command::Evaluate(evaluator) => {
-            let parsed = boo::parse(expression)?;
-            let expression = parsed.to_core()?;
-            boo_types_hindley_milner::validate(&expression)?;
-            let result = evaluator.evaluate(expression)?;
-            println!("{result}");
+        Command::Evaluate(Args {
+            compile_wasm: Some(path),
+            ..
+        }) => {
+            let mut diagnostics = Diagnostics::new();
+            let result = check(source, &globals, config, &mut diagnostics);
+            report_diagnostics(source, diagnostics);
+            let (program, _) = result?;
+            let module = boo_compile_wasm::compile(program.to_core()?)?;
+            std::fs::write(path, module).into_diagnostic()?;
+        }
+        Command::Evaluate(Args { fmt: true, .. }) => {
+            print!("{}", boo_fmt::format(expression)?);
+        }
+        Command::Evaluate(args) => {
+            let mut diagnostics = Diagnostics::new();
+            let result =
+                check(source, &globals, config, &mut diagnostics).and_then(|(program, typed)| {
+                    let result =
+                        evaluate_program(args, config, &globals, expression, program.clone())?;
+                    Ok((program, typed, result))
+                });
+            // warnings are reported even if evaluation fails
+            report_diagnostics(source, diagnostics);
+            let (program, typed, result) = result?;
+            session.last_type = typed.as_ref().map(|typed| typed.typ().clone());
+            match &session.last_type {
+                Some(typ) => println!("{}", result.display_as(typ)),
+                None => println!("{}", result),
+            }
+            session.define(globals, program, typed.as_ref())?;
         }
         Command::ShowType => {
-            let parsed = boo::parse(expression)?;
-            let expression = parsed.to_core()?;
-            let expression_type = boo_types_hindley_milner::type_of(&expression)?;
+            let program = boo::parse_program(expression)?;
+            let expression = program.to_core()?;
+            let expression_type = boo_types_hindley_milner::type_of(&expression, globals)?;
+            println!("{expression_type}");
+        }
+        Command::ShowTypeAt(offset) => {
+            let program = boo::parse_program(expression)?;
+            let expression = program.to_core()?;
+            let expression_type = boo_types_hindley_milner::type_at(&expression, offset, globals)?;
             println!("{expression_type}");
         }
+        Command::Lint => {
+            let program = boo::parse_program(expression)?;
+            let core = program.to_core()?;
+            let mut diagnostics = Diagnostics::new();
+            boo::verification::warnings(&core, &mut diagnostics);
+            boo::lint::lint(&core, &mut diagnostics);
+            if diagnostics.is_empty() {
+                println!("No warnings.");
+            }
+            report_diagnostics(source, diagnostics);
+        }
+        Command::Specialize => {
+            let program = boo::parse_program(expression)?;
+            let expression = program.to_core()?;
+            // natives are run during specialization, so we exclude those with
+            // side effects
+            let builtins = boo::builtins::implementations(IoCapabilities::default())
+                .filter(|(name, _)| name.name() != "trace")
+                .map(|(name, implementation)| (name.clone(), implementation));
+            let residual = boo::specialization::specialize(expression, builtins);
+            println!("{residual}");
+        }
+        Command::Provenance => {
+            let mut diagnostics = Diagnostics::new();
+            let program = check(source, &globals, config, &mut diagnostics);
+            report_diagnostics(source, diagnostics);
+            let mut context = boo::evaluator::with_memory_limit(MEMORY_LIMIT);
+            globals.prepare(&mut context)?;
+            let main = program?.0.bind(&mut context)?;
+            let (result, provenance) = context.evaluator().evaluate_with_provenance(main);
+            println!("{}", result?);
+            for span in provenance.chain() {
+                println!("  from {}", source_at(expression, *span));
+            }
+        }
+        Command::ShowAst { spans } => {
+            let program = boo::parse_program(expression)?;
+            println!("{program}");
+            if spans {
+                for value in program
+                    .definitions
+                    .iter()
+                    .map(|definition| &definition.value)
+                    .chain(program.main.as_ref())
+                {
+                    print_spans(expression, value, 0);
+                }
+            }
+        }
+        Command::ShowCore => {
+            let program = boo::parse_program(expression)?;
+            println!("{}", program.to_core()?);
+        }
+        Command::Trace {
+            limit,
+            integer_overflow,
+        } => {
+            let mut diagnostics = Diagnostics::new();
+            let program = check(source, &globals, config, &mut diagnostics);
+            report_diagnostics(source, diagnostics);
+            let mut context = boo_evaluation_reduction::ReducingEvaluator::new()
+                .with_integer_overflow(integer_overflow);
+            globals.prepare(&mut context)?;
+            let main = program?.0.bind(&mut context)?;
+            let mut steps = 0;
+            let result = context.evaluate_stepwise(main, |expr| {
+                if steps == limit {
+                    return ControlFlow::Break(());
+                }
+                steps += 1;
+                // continuation lines are indented to line up after the step number
+                let shown = format!("{expr:74}").replace('\n', "\n      ");
+                println!("{steps:>4}  {shown}");
+                ControlFlow::Continue(())
+            });
+            match result {
+                Err(boo::error::Error::Interrupted) => {
+                    eprintln!("Stopped after {limit} steps.");
+                }
+                result => println!("{}", result?),
+            }
+        }
+        Command::Set if expression.trim().is_empty() => {
+            print!("{}", session.settings);
+        }
+        Command::Set => {
+            session.settings.set(expression)?;
+        }
+        Command::Bindings => {
+            let mut context = boo::evaluator::new();
+            globals.prepare(&mut context)?;
+            // later globals replace earlier ones with the same name
+            let types = globals.types().collect::<HashMap<_, _>>();
+            let mut identifiers = context.identifiers();
+            identifiers.sort_by_key(Identifier::name);
+            for identifier in identifiers {
+                if let Some(typ) = types.get(&identifier) {
+                    println!("{identifier} : {typ}");
+                }
+            }
+        }
     }
     Ok(())
 }
+
+/// Checks and evaluates a program, printing the result, or every error, as a
+/// line of JSON.
+///
+/// Returns whether it succeeded.
+fn evaluate_as_json(args: &Args, session: &mut Session, globals: Globals, source: Source) -> bool {
+    let config = session.settings.evaluation;
+    let mut diagnostics = Diagnostics::new();
+    let result =
+        check_all(source.text, &globals, config, &mut diagnostics).and_then(|(program, typed)| {
+            let typ = typed.as_ref().map(|typed| typed.typ().clone());
+            let value = evaluate_program(args, config, &globals, source.text, program.clone())
+                .map_err(|report| vec![report])?;
+            session
+                .define(globals, program, typed.as_ref())
+                .map_err(|report| vec![report])?;
+            session.last_type = typ.clone();
+            Ok((value, typ))
+        });
+    let warnings = diagnostics
+        .into_iter()
+        .map(|warning| json::diagnostic(&miette::Report::new(warning), source))
+        .collect();
+    let (output, succeeded) = match result {
+        Ok((value, typ)) => (json::success(&value, typ.as_ref(), warnings), true),
+        Err(errors) => {
+            let errors = errors
+                .iter()
+                .map(|error| json::diagnostic(error, source))
+                .collect();
+            (json::failure(errors, warnings), false)
+        }
+    };
+    println!("{output}");
+    succeeded
+}
+
+/// Evaluates a checked program with the evaluator chosen by the arguments,
+/// configured as the settings describe.
+fn evaluate_program(
+    args: &Args,
+    config: EvaluationConfig,
+    globals: &Globals,
+    expression: &str,
+    program: Program,
+) -> miette::Result<Evaluated> {
+    let integer_overflow = args.integer_overflow.into();
+    let optimized = || {
+        let context = boo::evaluator::with_memory_limit(MEMORY_LIMIT)
+            .with_integer_overflow(integer_overflow)
+            .with_optimizations(config.optimizations);
+        match config.fuel {
+            Some(fuel) => context.with_step_limit(fuel),
+            None => context,
+        }
+    };
+    if args.profile {
+        let mut context = optimized();
+        globals.prepare(&mut context)?;
+        let main = program.bind(&mut context)?;
+        let (result, profile) = context.evaluator().evaluate_profiled(main);
+        report_hot_spots(expression, &profile);
+        return Ok(result?);
+    }
+    let result = match args.evaluator {
+        EvaluatorName::Optimized => evaluate(optimized(), globals, program)?,
+        EvaluatorName::Krivine => {
+            let context = boo_evaluation_krivine::KrivineEvaluator::new()
+                .with_integer_overflow(integer_overflow);
+            match config.fuel {
+                Some(fuel) => evaluate(context.with_step_limit(fuel), globals, program)?,
+                None => evaluate(context, globals, program)?,
+            }
+        }
+        evaluator if config.fuel.is_some() => {
+            return Err(miette::miette!(
+                "The {} evaluator cannot limit the number of steps it takes.",
+                evaluator.name()
+            ));
+        }
+        EvaluatorName::Recursive => evaluate(
+            boo_evaluation_recursive::new().with_integer_overflow(integer_overflow),
+            globals,
+            program,
+        )?,
+        EvaluatorName::Reduction => evaluate(
+            boo_evaluation_reduction::ReducingEvaluator::new()
+                .with_integer_overflow(integer_overflow),
+            globals,
+            program,
+        )?,
+        EvaluatorName::Secd => evaluate(
+            boo_evaluation_secd::SecdEvaluator::new().with_integer_overflow(integer_overflow),
+            globals,
+            program,
+        )?,
+        EvaluatorName::Cek => evaluate(
+            boo_evaluation_cek::CekEvaluator::new().with_integer_overflow(integer_overflow),
+            globals,
+            program,
+        )?,
+        EvaluatorName::Vm => evaluate(
+            boo_vm::new().with_integer_overflow(integer_overflow),
+            globals,
+            program,
+        )?,
+        #[cfg(feature = "jit")]
+        EvaluatorName::Jit => evaluate(
+            boo_jit::JitEvaluationContext::new(
+                boo::evaluator::new().with_integer_overflow(integer_overflow),
+            )
+            .with_integer_overflow(integer_overflow),
+            globals,
+            program,
+        )?,
+    };
+    Ok(result)
+}
+
+/// Parses and type-checks a program, recording any warnings along the way.
+fn check(
+    source: Source,
+    globals: &Globals,
+    config: EvaluationConfig,
+    diagnostics: &mut Diagnostics,
+) -> miette::Result<(Program, Option<TypedExpr>)> {
+    check_all(source.text, globals, config, diagnostics).map_err(|mut errors| {
+        let last = errors.pop().expect("There is always at least one error.");
+        for error in errors {
+            print_report(error, source);
+        }
+        last
+    })
+}
+
+/// Parses and type-checks a program, recording any warnings along the way,
+/// and producing the program along with its type-checked core expression, if
+/// type checking is enabled.
+///
+/// If it fails, produces every type error, rather than just the first. In
+/// strict mode, the program is linted too, and any warnings are produced as
+/// errors instead.
+fn check_all(
+    expression: &str,
+    globals: &Globals,
+    config: EvaluationConfig,
+    diagnostics: &mut Diagnostics,
+) -> std::result::Result<(Program, Option<TypedExpr>), Vec<miette::Report>> {
+    let program = boo::parse_program(expression).map_err(|error| vec![error.into()])?;
+    let core = program
+        .clone()
+        .to_core()
+        .map_err(|error| vec![error.into()])?;
+    boo::verification::warnings(&core, diagnostics);
+    if config.strict {
+        boo::lint::lint(&core, diagnostics);
+        if !diagnostics.is_empty() {
+            let warnings = std::mem::replace(diagnostics, Diagnostics::new());
+            return Err(warnings.into_iter().map(miette::Report::new).collect());
+        }
+    }
+    if !config.type_check {
+        return Ok((program, None));
+    }
+    let typed = boo_types_hindley_milner::annotate_collecting_errors(&core, globals.clone())
+        .map_err(|errors| {
+            errors
+                .into_iter()
+                .map(miette::Report::new)
+                .collect::<Vec<_>>()
+        })?;
+    Ok((program, Some(typed)))
+}
+
+/// Prints each warning to stderr, alongside the source code.
+fn report_diagnostics(source: Source, diagnostics: Diagnostics) {
+    for warning in diagnostics {
+        print_report(miette::Report::new(warning), source);
+    }
+}
+
+/// Prints the report to stderr, alongside the source code, preceded by the
+/// name of the file and the line and column of the first location it refers
+/// to.
+fn print_report(report: miette::Report, source: Source) {
+    let location = report
+        .labels()
+        .and_then(|mut labels| labels.next())
+        .map(|label| SourceIndex::new(source.text).location(label.offset()));
+    let (report, prefix) = match source.name {
+        Some(name) => (
+            report.with_source_code(miette::NamedSource::new(name, source.text.to_string())),
+            format!("{name}:"),
+        ),
+        None => (
+            report.with_source_code(source.text.to_string()),
+            String::new(),
+        ),
+    };
+    match location {
+        Some(location) => eprintln!("{prefix}{location}: {report:?}"),
+        None if source.name.is_some() => eprintln!("{prefix} {report:?}"),
+        None => eprintln!("{report:?}"),
+    }
+}
+
+/// Prints the expressions which took the longest to evaluate to stderr, as a
+/// table.
+fn report_hot_spots(expression: &str, profile: &boo::evaluator::Profile) {
+    eprintln!("{:>10}  {:>12}  expression", "count", "time");
+    for (span, entry) in profile.hot_spots().into_iter().take(HOT_SPOTS) {
+        let source = match span {
+            Some(span) => source_at(expression, span),
+            None => "(built-in)".to_string(),
+        };
+        let time = format!("{:.3?}", entry.duration);
+        eprintln!("{:>10}  {:>12}  {}", entry.count, time, source);
+    }
+}
+
+/// Prints the location and source code of the expression and each of its
+/// subexpressions, indented to show how they are nested.
+fn print_spans(expression: &str, expr: &boo::Expr, depth: usize) {
+    let span = format!("{}..{}", expr.span.start, expr.span.end);
+    println!(
+        "{:indent$}{span}  {}",
+        "",
+        source_at(expression, expr.span),
+        indent = depth * 2
+    );
+    for child in expr.children() {
+        print_spans(expression, child, depth + 1);
+    }
+}
+
+/// The source code at the given location, on a single line.
+fn source_at(expression: &str, span: boo::span::Span) -> String {
+    expression[span.range()]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Binds the globals and the program's definitions in a fresh context, and
+/// then evaluates the main expression.
+fn evaluate(
+    mut context: impl EvaluationContext,
+    globals: &Globals,
+    program: Program,
+) -> boo::error::Result<Evaluated> {
+    globals.prepare(&mut context)?;
+    let main = program.bind(&mut context)?;
+    context.evaluator().evaluate(main)
+}
@@ -0,0 +1,93 @@
+//! The REPL prompt.
+
+use std::borrow::Cow;
+
+use reedline::{Prompt, PromptEditMode, PromptHistorySearch, PromptHistorySearchStatus};
+
+use boo::types::Monotype;
+
+/// The prompt used when none is configured.
+pub const DEFAULT_PROMPT: &str = "{evaluator}";
+
+/// Separates the prompt from the input.
+const INDICATOR: &str = "〉";
+
+/// Marks the lines after the first in an unfinished input.
+const CONTINUATION: &str = "…";
+
+/// Shows the prompt, configured with `:set prompt`, followed by `〉`.
+///
+/// In the prompt, `{evaluator}` is replaced with the name of the evaluator,
+/// and `{type}` with the type of the last result, if there is one. Further
+/// lines of an unfinished input are marked with `…` instead, aligned with the
+/// end of the prompt.
+pub struct BooPrompt {
+    text: String,
+}
+
+impl BooPrompt {
+    pub fn new(template: &str, evaluator: &str, last_type: Option<&Monotype>) -> Self {
+        let last_type = last_type.map(|typ| typ.to_string()).unwrap_or_default();
+        Self {
+            text: template
+                .replace("{evaluator}", evaluator)
+                .replace("{type}", &last_type),
+        }
+    }
+}
+
+impl Prompt for BooPrompt {
+    fn render_prompt_left(&self) -> Cow<str> {
+        Cow::Borrowed(&self.text)
+    }
+
+    fn render_prompt_right(&self) -> Cow<str> {
+        Cow::Borrowed("")
+    }
+
+    fn render_prompt_indicator(&self, _edit_mode: PromptEditMode) -> Cow<str> {
+        Cow::Borrowed(INDICATOR)
+    }
+
+    fn render_prompt_multiline_indicator(&self) -> Cow<str> {
+        // the input is aligned with the end of the last line of the prompt
+        let width = self.text.lines().last().unwrap_or("").chars().count();
+        Cow::Owned(format!(
+            "{CONTINUATION:>width$}{INDICATOR}",
+            width = width.max(1)
+        ))
+    }
+
+    fn render_prompt_history_search_indicator(
+        &self,
+        history_search: PromptHistorySearch,
+    ) -> Cow<str> {
+        let prefix = match history_search.status {
+            PromptHistorySearchStatus::Passing => "",
+            PromptHistorySearchStatus::Failing => "failing ",
+        };
+        Cow::Owned(format!("({prefix}search: {}) ", history_search.term))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boo::types::Type;
+
+    use super::*;
+
+    #[test]
+    fn test_filling_in_the_prompt() {
+        let prompt = BooPrompt::new("{evaluator} {type}", "vm", Some(&Type::Integer.into()));
+
+        assert_eq!(prompt.render_prompt_left(), "vm Integer");
+        assert_eq!(prompt.render_prompt_multiline_indicator(), "         …〉");
+    }
+
+    #[test]
+    fn test_leaving_out_the_type_before_the_first_result() {
+        let prompt = BooPrompt::new("[{type}]", "vm", None);
+
+        assert_eq!(prompt.render_prompt_left(), "[]");
+    }
+}
@@ -0,0 +1,57 @@
+//! Multi-line input in the REPL.
+
+use reedline::{ValidationResult, Validator};
+
+use boo::error::Error;
+
+/// Treats the input as unfinished if the parser runs out of input before the
+/// program is complete, so that pressing Enter starts a new line rather than
+/// running it.
+pub struct BooValidator;
+
+impl Validator for BooValidator {
+    fn validate(&self, line: &str) -> ValidationResult {
+        // a command, such as `:type`, is not part of the expression, and
+        // `:set` is not followed by one at all
+        let expression = match line.split_once(' ') {
+            Some((":set", _)) => return ValidationResult::Complete,
+            Some((command, rest)) if command.starts_with(':') => rest,
+            _ => line,
+        };
+        let end = expression.trim_end().len();
+        if end == 0 {
+            return ValidationResult::Complete;
+        }
+        match boo::parse_program(expression) {
+            Err(Error::ParseError { span, .. }) if span.start >= end => {
+                ValidationResult::Incomplete
+            }
+            _ => ValidationResult::Complete,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_complete(line: &str) -> bool {
+        matches!(BooValidator.validate(line), ValidationResult::Complete)
+    }
+
+    #[test]
+    fn test_continuing_unfinished_input() {
+        assert!(!is_complete("let x = 1 in"));
+        assert!(!is_complete(":t fn x ->\n"));
+        assert!(!is_complete("[1, 2"));
+    }
+
+    #[test]
+    fn test_finishing_complete_or_invalid_input() {
+        assert!(is_complete("let x = 1 in\nx + 1"));
+        assert!(is_complete("1 + )"));
+        assert!(is_complete(":set prompt let"));
+        assert!(is_complete(""));
+        assert!(is_complete("  \n"));
+    }
+}
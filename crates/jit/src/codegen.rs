@@ -0,0 +1,507 @@
+//! Generates machine code for a [`Program`] with Cranelift.
+//!
+//! Every function takes a pointer to a failure flag and the current call
+//! depth, followed by its arguments, and returns an `i64`. Booleans are
+//! represented as 0 or 1.
+//!
+//! Anything that the interpreter would handle differently sets the failure
+//! flag and returns immediately. This includes integer overflow, as Boo
+//! integers are arbitrary-precision; errors such as division by zero; and
+//! recursing too deeply, which would otherwise overflow the native stack. The
+//! interpreter then evaluates the expression instead.
+//!
+//! When overflowing small integers is an error, arithmetic whose result would
+//! not fit in 32 bits is also left to the interpreter, which reports it.
+
+use cranelift_codegen::ir::condcodes::IntCC;
+use cranelift_codegen::ir::{types, AbiParam, Block, InstBuilder, MemFlags, Signature, Value};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext, Variable};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{FuncId, Module};
+
+use boo_core::evaluation::IntegerOverflow;
+use boo_core::primitive::Primitive;
+
+use crate::ir::{FunctionId, Kind, LocalId, Operation, Pattern, Program, Term};
+
+/// The deepest that calls can be nested before giving up.
+const MAX_DEPTH: i64 = 10_000;
+
+/// The largest number of terms that will be compiled in a single function.
+///
+/// Lazily-evaluated locals are compiled wherever they are used, so this stops
+/// the code from growing too large.
+const MAX_SIZE: usize = 10_000;
+
+/// A compiled program, ready to run.
+pub struct Compiled {
+    module: Option<JITModule>,
+    main: *const u8,
+    result: Kind,
+}
+
+type MainFunction = unsafe extern "C" fn(*mut u8, i64) -> i64;
+
+impl Compiled {
+    /// Runs the program, returning `None` if it could not finish.
+    pub fn run(&self) -> Option<Primitive> {
+        let mut failed: u8 = 0;
+        // Safety: the code was compiled with this signature, and the module
+        // that owns it lives as long as `self`.
+        let result = unsafe {
+            let main = std::mem::transmute::<*const u8, MainFunction>(self.main);
+            main(&mut failed, 0)
+        };
+        if failed != 0 {
+            return None;
+        }
+        match self.result {
+            Kind::Integer => Some(Primitive::Integer(result.into())),
+            Kind::Boolean => Some(Primitive::Boolean(result != 0)),
+        }
+    }
+}
+
+impl Drop for Compiled {
+    fn drop(&mut self) {
+        if let Some(module) = self.module.take() {
+            // Safety: the code is only reachable through `self`.
+            unsafe { module.free_memory() };
+        }
+    }
+}
+
+/// Compiles a program, returning `None` if the code would be too large.
+pub fn compile(program: &Program, integer_overflow: IntegerOverflow) -> Option<Compiled> {
+    let mut flags = settings::builder();
+    flags.set("opt_level", "speed").ok()?;
+    let isa = cranelift_native::builder()
+        .ok()?
+        .finish(settings::Flags::new(flags))
+        .ok()?;
+    let mut module = JITModule::new(JITBuilder::with_isa(
+        isa,
+        cranelift_module::default_libcall_names(),
+    ));
+    let pointer_type = module.target_config().pointer_type();
+
+    let signatures = program
+        .functions
+        .iter()
+        .map(|function| {
+            let mut signature = module.make_signature();
+            signature.params.push(AbiParam::new(pointer_type));
+            signature.params.push(AbiParam::new(types::I64));
+            for _ in 0..function.arity {
+                signature.params.push(AbiParam::new(types::I64));
+            }
+            signature.returns.push(AbiParam::new(types::I64));
+            signature
+        })
+        .collect::<Vec<Signature>>();
+    let ids = signatures
+        .iter()
+        .map(|signature| module.declare_anonymous_function(signature).ok())
+        .collect::<Option<Vec<FuncId>>>()?;
+
+    let mut context = module.make_context();
+    let mut builder_context = FunctionBuilderContext::new();
+    for (function, (id, signature)) in ids.iter().zip(signatures).enumerate() {
+        context.func.signature = signature;
+        let builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+        let translator = Translator::new(
+            builder,
+            &mut module,
+            &ids,
+            program,
+            function,
+            integer_overflow,
+        );
+        if !translator.translate_function() {
+            return None;
+        }
+        module.define_function(*id, &mut context).ok()?;
+        module.clear_context(&mut context);
+    }
+    module.finalize_definitions().ok()?;
+
+    let main = module.get_finalized_function(ids[program.main]);
+    Some(Compiled {
+        module: Some(module),
+        main,
+        result: program.functions[program.main].result,
+    })
+}
+
+/// A lazily-evaluated local, which is evaluated the first time it is used.
+struct Lazy<'a> {
+    term: &'a Term,
+    value: Variable,
+    evaluated: Variable,
+}
+
+struct Translator<'a, 'b> {
+    builder: FunctionBuilder<'b>,
+    module: &'b mut JITModule,
+    ids: &'b [FuncId],
+    program: &'a Program,
+    function: FunctionId,
+    integer_overflow: IntegerOverflow,
+    state: Value,
+    depth: Value,
+    /// Self-calls in tail position jump back here, rather than calling.
+    start: Block,
+    /// Sets the failure flag and returns.
+    failure: Block,
+    parameters: Vec<Variable>,
+    locals: Vec<Option<Lazy<'a>>>,
+    next_variable: usize,
+    size: usize,
+}
+
+impl<'a, 'b> Translator<'a, 'b> {
+    fn new(
+        mut builder: FunctionBuilder<'b>,
+        module: &'b mut JITModule,
+        ids: &'b [FuncId],
+        program: &'a Program,
+        function: FunctionId,
+        integer_overflow: IntegerOverflow,
+    ) -> Self {
+        let arity = program.functions[function].arity;
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        let state = builder.block_params(entry)[0];
+        let depth = builder.block_params(entry)[1];
+        let arguments = builder.block_params(entry)[2..].to_vec();
+
+        let parameters = (0..arity)
+            .map(|i| Variable::from_u32(i as u32))
+            .collect::<Vec<_>>();
+        for parameter in parameters.iter() {
+            builder.declare_var(*parameter, types::I64);
+        }
+        let start = builder.create_block();
+        for _ in 0..arity {
+            builder.append_block_param(start, types::I64);
+        }
+        let failure = builder.create_block();
+
+        let too_deep = builder
+            .ins()
+            .icmp_imm(IntCC::SignedGreaterThan, depth, MAX_DEPTH);
+        builder
+            .ins()
+            .brif(too_deep, failure, &[], start, &arguments);
+        builder.switch_to_block(start);
+        for (parameter, value) in parameters.iter().zip(builder.block_params(start).to_vec()) {
+            builder.def_var(*parameter, value);
+        }
+
+        Self {
+            builder,
+            module,
+            ids,
+            program,
+            function,
+            integer_overflow,
+            state,
+            depth,
+            start,
+            failure,
+            parameters,
+            locals: (0..program.functions[function].locals)
+                .map(|_| None)
+                .collect(),
+            next_variable: arity,
+            size: 0,
+        }
+    }
+
+    /// Translates the body of the function, returning `false` if it is too
+    /// large.
+    fn translate_function(mut self) -> bool {
+        let body = &self.program.functions[self.function].body;
+        if let Some(result) = self.translate(body, true) {
+            self.builder.ins().return_(&[result]);
+        }
+
+        self.builder.switch_to_block(self.failure);
+        let one = self.builder.ins().iconst(types::I8, 1);
+        self.builder
+            .ins()
+            .store(MemFlags::trusted(), one, self.state, 0);
+        let zero = self.builder.ins().iconst(types::I64, 0);
+        self.builder.ins().return_(&[zero]);
+
+        self.builder.seal_all_blocks();
+        self.builder.finalize();
+        self.size <= MAX_SIZE
+    }
+
+    /// Translates a term, returning its value.
+    ///
+    /// If the term is in tail position and ends in a call to the same
+    /// function, this jumps back to the start of the function instead, and
+    /// there is no value.
+    fn translate(&mut self, term: &'a Term, tail: bool) -> Option<Value> {
+        self.size += 1;
+        match term {
+            Term::Integer(value) => Some(self.builder.ins().iconst(types::I64, *value)),
+            Term::Boolean(value) => Some(self.builder.ins().iconst(types::I64, i64::from(*value))),
+            Term::Local(local) => Some(self.local(*local)),
+            Term::Operation(operation, left, right) => {
+                let left = self.value(left);
+                let right = self.value(right);
+                Some(self.operation(*operation, left, right))
+            }
+            Term::Call(function, arguments) => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| self.value(argument))
+                    .collect::<Vec<_>>();
+                if tail && *function == self.function {
+                    self.builder.ins().jump(self.start, &arguments);
+                    return None;
+                }
+                let callee = self
+                    .module
+                    .declare_func_in_func(self.ids[*function], self.builder.func);
+                let depth = self.builder.ins().iadd_imm(self.depth, 1);
+                let mut call_arguments = vec![self.state, depth];
+                call_arguments.extend(arguments);
+                let call = self.builder.ins().call(callee, &call_arguments);
+                let result = self.builder.inst_results(call)[0];
+                let failed = self
+                    .builder
+                    .ins()
+                    .load(types::I8, MemFlags::trusted(), self.state, 0);
+                self.fail_if(failed);
+                Some(result)
+            }
+            Term::Let(local, value, inner) => {
+                self.bind(*local, value);
+                self.translate(inner, tail)
+            }
+            Term::Match(local, value, branches) => {
+                self.bind(*local, value);
+                let done = self.builder.create_block();
+                self.builder.append_block_param(done, types::I64);
+                for (pattern, result) in branches {
+                    let expected = match pattern {
+                        Pattern::Anything => {
+                            if let Some(result) = self.translate(result, tail) {
+                                self.builder.ins().jump(done, &[result]);
+                            }
+                            return self.finish_match(done);
+                        }
+                        Pattern::Integer(expected) => *expected,
+                        Pattern::Boolean(expected) => i64::from(*expected),
+                    };
+                    let actual = self.local(*local);
+                    let matches = self.builder.ins().icmp_imm(IntCC::Equal, actual, expected);
+                    let then_block = self.builder.create_block();
+                    let else_block = self.builder.create_block();
+                    self.builder
+                        .ins()
+                        .brif(matches, then_block, &[], else_block, &[]);
+                    self.builder.switch_to_block(then_block);
+                    if let Some(result) = self.translate(result, tail) {
+                        self.builder.ins().jump(done, &[result]);
+                    }
+                    self.builder.switch_to_block(else_block);
+                }
+                // no pattern matched
+                self.builder.ins().jump(self.failure, &[]);
+                self.finish_match(done)
+            }
+            Term::Sequence(first, second) => {
+                self.value(first);
+                self.translate(second, tail)
+            }
+        }
+    }
+
+    /// Translates a term that is not in tail position.
+    fn value(&mut self, term: &'a Term) -> Value {
+        self.translate(term, false)
+            .expect("A term that is not in tail position must have a value.")
+    }
+
+    fn finish_match(&mut self, done: Block) -> Option<Value> {
+        self.builder.switch_to_block(done);
+        Some(self.builder.block_params(done)[0])
+    }
+
+    /// Binds a local lazily, marking it as not yet evaluated.
+    fn bind(&mut self, local: LocalId, term: &'a Term) {
+        let value = self.variable();
+        let evaluated = self.variable();
+        let zero = self.builder.ins().iconst(types::I64, 0);
+        self.builder.def_var(value, zero);
+        self.builder.def_var(evaluated, zero);
+        self.locals[local] = Some(Lazy {
+            term,
+            value,
+            evaluated,
+        });
+    }
+
+    fn variable(&mut self) -> Variable {
+        let variable = Variable::from_u32(self.next_variable as u32);
+        self.next_variable += 1;
+        self.builder.declare_var(variable, types::I64);
+        variable
+    }
+
+    /// Reads a local, evaluating it first if necessary.
+    fn local(&mut self, local: LocalId) -> Value {
+        if local < self.parameters.len() {
+            return self.builder.use_var(self.parameters[local]);
+        }
+        let Lazy {
+            term,
+            value,
+            evaluated,
+        } = *self.locals[local]
+            .as_ref()
+            .expect("A local was used before it was bound.");
+        if self.size > MAX_SIZE {
+            // give up; the function will be discarded
+            return self.builder.ins().iconst(types::I64, 0);
+        }
+        let is_evaluated = self.builder.use_var(evaluated);
+        let evaluate = self.builder.create_block();
+        let done = self.builder.create_block();
+        self.builder
+            .ins()
+            .brif(is_evaluated, done, &[], evaluate, &[]);
+        self.builder.switch_to_block(evaluate);
+        let result = self.value(term);
+        self.builder.def_var(value, result);
+        let one = self.builder.ins().iconst(types::I64, 1);
+        self.builder.def_var(evaluated, one);
+        self.builder.ins().jump(done, &[]);
+        self.builder.switch_to_block(done);
+        self.builder.use_var(value)
+    }
+
+    /// Jumps to the failure block if the condition is non-zero.
+    fn fail_if(&mut self, condition: Value) {
+        let ok = self.builder.create_block();
+        self.builder
+            .ins()
+            .brif(condition, self.failure, &[], ok, &[]);
+        self.builder.switch_to_block(ok);
+    }
+
+    /// Jumps to the failure block if the result is not a small integer, when
+    /// overflowing small integers is an error.
+    fn fail_unless_small(&mut self, result: Value) -> Value {
+        if self.integer_overflow == IntegerOverflow::Fail {
+            let small = self.builder.ins().ireduce(types::I32, result);
+            let extended = self.builder.ins().sextend(types::I64, small);
+            let overflowed = self.builder.ins().icmp(IntCC::NotEqual, extended, result);
+            self.fail_if(overflowed);
+        }
+        result
+    }
+
+    fn operation(&mut self, operation: Operation, left: Value, right: Value) -> Value {
+        match operation {
+            Operation::Add => {
+                let (result, overflowed) = self.builder.ins().sadd_overflow(left, right);
+                self.fail_if(overflowed);
+                self.fail_unless_small(result)
+            }
+            Operation::Subtract => {
+                let (result, overflowed) = self.builder.ins().ssub_overflow(left, right);
+                self.fail_if(overflowed);
+                self.fail_unless_small(result)
+            }
+            Operation::Multiply => {
+                let (result, overflowed) = self.builder.ins().smul_overflow(left, right);
+                self.fail_if(overflowed);
+                self.fail_unless_small(result)
+            }
+            Operation::Divide => {
+                let by_zero = self.builder.ins().icmp_imm(IntCC::Equal, right, 0);
+                self.fail_if(by_zero);
+                // dividing the smallest integer by -1 overflows
+                let smallest = self.builder.ins().icmp_imm(IntCC::Equal, left, i64::MIN);
+                let minus_one = self.builder.ins().icmp_imm(IntCC::Equal, right, -1);
+                let overflowed = self.builder.ins().band(smallest, minus_one);
+                self.fail_if(overflowed);
+                let result = self.builder.ins().sdiv(left, right);
+                self.fail_unless_small(result)
+            }
+            Operation::Modulo => {
+                let by_zero = self.builder.ins().icmp_imm(IntCC::Equal, right, 0);
+                self.fail_if(by_zero);
+                // the remainder of dividing by -1 is always 0, as it is for 1,
+                // but dividing the smallest integer by -1 overflows
+                let minus_one = self.builder.ins().icmp_imm(IntCC::Equal, right, -1);
+                let one = self.builder.ins().iconst(types::I64, 1);
+                let divisor = self.builder.ins().select(minus_one, one, right);
+                self.builder.ins().srem(left, divisor)
+            }
+            Operation::BitwiseAnd => self.builder.ins().band(left, right),
+            Operation::BitwiseOr => self.builder.ins().bor(left, right),
+            Operation::BitwiseXor => self.builder.ins().bxor(left, right),
+            Operation::ShiftLeft => {
+                self.check_shift_amount(right);
+                let large = self
+                    .builder
+                    .ins()
+                    .icmp_imm(IntCC::SignedGreaterThan, right, 63);
+                let largest = self.builder.ins().iconst(types::I64, 63);
+                let amount = self.builder.ins().select(large, largest, right);
+                let result = self.builder.ins().ishl(left, amount);
+                // the shift overflowed if shifting back loses information
+                let shifted_back = self.builder.ins().sshr(result, amount);
+                let lossy = self.builder.ins().icmp(IntCC::NotEqual, shifted_back, left);
+                let non_zero = self.builder.ins().icmp_imm(IntCC::NotEqual, left, 0);
+                let too_large = self.builder.ins().band(large, non_zero);
+                let overflowed = self.builder.ins().bor(lossy, too_large);
+                self.fail_if(overflowed);
+                self.fail_unless_small(result)
+            }
+            Operation::ShiftRight => {
+                self.check_shift_amount(right);
+                // shifting by 63 or more leaves only the sign
+                let large = self
+                    .builder
+                    .ins()
+                    .icmp_imm(IntCC::SignedGreaterThan, right, 63);
+                let largest = self.builder.ins().iconst(types::I64, 63);
+                let amount = self.builder.ins().select(large, largest, right);
+                self.builder.ins().sshr(left, amount)
+            }
+            Operation::Equal => self.compare(IntCC::Equal, left, right),
+            Operation::NotEqual => self.compare(IntCC::NotEqual, left, right),
+            Operation::LessThan => self.compare(IntCC::SignedLessThan, left, right),
+            Operation::LessThanOrEqual => self.compare(IntCC::SignedLessThanOrEqual, left, right),
+            Operation::GreaterThan => self.compare(IntCC::SignedGreaterThan, left, right),
+            Operation::GreaterThanOrEqual => {
+                self.compare(IntCC::SignedGreaterThanOrEqual, left, right)
+            }
+        }
+    }
+
+    /// Fails unless the shift amount fits in 32 bits, as in the interpreter,
+    /// which rejects negative amounts as well as larger ones.
+    fn check_shift_amount(&mut self, amount: Value) {
+        let invalid =
+            self.builder
+                .ins()
+                .icmp_imm(IntCC::UnsignedGreaterThan, amount, i64::from(u32::MAX));
+        self.fail_if(invalid);
+    }
+
+    fn compare(&mut self, condition: IntCC, left: Value, right: Value) -> Value {
+        let result = self.builder.ins().icmp(condition, left, right);
+        self.builder.ins().uextend(types::I64, result)
+    }
+}
@@ -0,0 +1,129 @@
+//! The intermediate representation of programs that can be compiled.
+//!
+//! This covers a small subset of the core language: integers and booleans,
+//! built-in operations upon them, and first-order functions on integers, with
+//! all names resolved.
+
+/// The kind of value produced by a term.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Integer,
+    Boolean,
+}
+
+/// Refers to a local within a function.
+///
+/// The parameters of the function are the first locals.
+pub type LocalId = usize;
+
+/// Refers to a function within a [`Program`].
+pub type FunctionId = usize;
+
+/// A built-in binary operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight,
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+impl Operation {
+    /// Looks up an operation by the unique name of its native implementation.
+    pub fn from_native_name(name: &str) -> Option<Self> {
+        match name {
+            "+" => Some(Operation::Add),
+            "-" => Some(Operation::Subtract),
+            "*" => Some(Operation::Multiply),
+            "/" => Some(Operation::Divide),
+            "%" => Some(Operation::Modulo),
+            "&" => Some(Operation::BitwiseAnd),
+            "|" => Some(Operation::BitwiseOr),
+            "xor" => Some(Operation::BitwiseXor),
+            "<<" => Some(Operation::ShiftLeft),
+            ">>" => Some(Operation::ShiftRight),
+            "==" => Some(Operation::Equal),
+            "!=" => Some(Operation::NotEqual),
+            "<" => Some(Operation::LessThan),
+            "<=" => Some(Operation::LessThanOrEqual),
+            ">" => Some(Operation::GreaterThan),
+            ">=" => Some(Operation::GreaterThanOrEqual),
+            _ => None,
+        }
+    }
+
+    /// Computes the kind of the result, given the kinds of the operands, if
+    /// they are valid.
+    pub fn kind(self, left: Kind, right: Kind) -> Option<Kind> {
+        match self {
+            Operation::Equal
+            | Operation::NotEqual
+            | Operation::LessThan
+            | Operation::LessThanOrEqual
+            | Operation::GreaterThan
+            | Operation::GreaterThanOrEqual => (left == right).then_some(Kind::Boolean),
+            _ => (left == Kind::Integer && right == Kind::Integer).then_some(Kind::Integer),
+        }
+    }
+}
+
+/// A pattern that can be compiled.
+///
+/// Variable patterns are resolved to the local holding the matched value, and
+/// so are equivalent to [`Pattern::Anything`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pattern {
+    Anything,
+    Integer(i64),
+    Boolean(bool),
+}
+
+/// A term with resolved names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Integer(i64),
+    Boolean(bool),
+    /// Refers to a parameter or a lazily-evaluated local.
+    Local(LocalId),
+    Operation(Operation, Box<Term>, Box<Term>),
+    /// Calls a function with exactly as many arguments as it has parameters.
+    Call(FunctionId, Vec<Term>),
+    /// Binds a local lazily.
+    Let(LocalId, Box<Term>, Box<Term>),
+    /// Binds the value to a local lazily, and then matches it against each
+    /// pattern in turn.
+    Match(LocalId, Box<Term>, Vec<(Pattern, Term)>),
+    /// Evaluates the first term, discarding its result, and then the second.
+    Sequence(Box<Term>, Box<Term>),
+}
+
+/// A function on integers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// The number of parameters, all of which are integers.
+    pub arity: usize,
+    /// The number of locals, including the parameters.
+    pub locals: usize,
+    pub body: Term,
+    pub result: Kind,
+}
+
+/// A whole program, along with the functions it calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub functions: Vec<Function>,
+    /// The function which computes the result, with no parameters.
+    pub main: FunctionId,
+}
@@ -0,0 +1,121 @@
+//! A just-in-time compiler for Boo, built on Cranelift.
+//!
+//! Expressions that only work with integers and booleans, and first-order
+//! functions upon them, are compiled to machine code. Everything else is
+//! evaluated by the optimized interpreter, as is any compiled expression that
+//! cannot finish, e.g. because an integer outgrows 64 bits.
+
+mod codegen;
+mod ir;
+mod lowering;
+mod strictness;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use boo_core::error::Result;
+use boo_core::evaluation::*;
+use boo_core::expr::Expr;
+use boo_core::identifier::Identifier;
+use boo_core::primitive::Primitive;
+
+use crate::codegen::Compiled;
+
+pub fn new() -> JitEvaluationContext<impl EvaluationContext> {
+    JitEvaluationContext::new(boo_evaluation_optimized::new())
+}
+
+/// Wraps another evaluation context, which is used to evaluate anything that
+/// cannot be compiled.
+pub struct JitEvaluationContext<Inner> {
+    globals: Vec<(Identifier, Expr)>,
+    inner: Inner,
+    integer_overflow: IntegerOverflow,
+}
+
+impl<Inner: EvaluationContext> JitEvaluationContext<Inner> {
+    pub fn new(inner: Inner) -> Self {
+        Self {
+            globals: vec![],
+            inner,
+            integer_overflow: IntegerOverflow::default(),
+        }
+    }
+
+    /// Decides how compiled arithmetic on small integers handles overflow.
+    ///
+    /// This should match the inner context, which evaluates anything that the
+    /// compiled code leaves to it.
+    pub fn with_integer_overflow(self, integer_overflow: IntegerOverflow) -> Self {
+        Self {
+            integer_overflow,
+            ..self
+        }
+    }
+}
+
+impl<Inner: EvaluationContext> EvaluationContext for JitEvaluationContext<Inner> {
+    type Eval = JitEvaluator<Inner::Eval>;
+
+    fn bind(&mut self, identifier: Identifier, expr: Expr) -> Result<()> {
+        self.globals.push((identifier.clone(), expr.clone()));
+        self.inner.bind(identifier, expr)
+    }
+
+    fn identifiers(&self) -> Vec<Identifier> {
+        self.inner.identifiers()
+    }
+
+    fn evaluator(self) -> Self::Eval {
+        JitEvaluator {
+            globals: self.globals,
+            inner: self.inner.evaluator(),
+            integer_overflow: self.integer_overflow,
+            compiled: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+/// Compiles each expression the first time it is evaluated, if possible.
+pub struct JitEvaluator<Inner> {
+    globals: Vec<(Identifier, Expr)>,
+    inner: Inner,
+    integer_overflow: IntegerOverflow,
+    /// The compiled code for each expression, or `None` if the expression
+    /// cannot be compiled.
+    compiled: RefCell<HashMap<Expr, Option<Rc<Compiled>>>>,
+}
+
+impl<Inner: Evaluator> JitEvaluator<Inner> {
+    /// Evaluates the expression with compiled code only.
+    ///
+    /// Returns `None` if the expression cannot be compiled, or if the compiled
+    /// code cannot finish evaluating it.
+    pub fn evaluate_compiled(&self, expr: &Expr) -> Option<Primitive> {
+        self.compiled_for(expr)?.run()
+    }
+
+    fn compiled_for(&self, expr: &Expr) -> Option<Rc<Compiled>> {
+        self.compiled
+            .borrow_mut()
+            .entry(expr.clone())
+            .or_insert_with(|| {
+                let program = lowering::lower(&self.globals, expr)?;
+                if !strictness::is_strict(&program) {
+                    return None;
+                }
+                codegen::compile(&program, self.integer_overflow).map(Rc::new)
+            })
+            .clone()
+    }
+}
+
+impl<Inner: Evaluator> Evaluator for JitEvaluator<Inner> {
+    fn evaluate(&self, expr: Expr) -> Result<Evaluated> {
+        match self.evaluate_compiled(&expr) {
+            Some(value) => Ok(Evaluated::Primitive(value)),
+            None => self.inner.evaluate(expr),
+        }
+    }
+}
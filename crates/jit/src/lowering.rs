@@ -0,0 +1,356 @@
+//! Lowers core expressions to the [intermediate representation][crate::ir].
+//!
+//! Anything outside the supported subset results in `None`, in which case the
+//! expression is evaluated by the interpreter instead.
+
+use std::collections::HashMap;
+
+use boo_core::ast::*;
+use boo_core::expr::Expr;
+use boo_core::identifier::Identifier;
+use boo_core::primitive::Primitive;
+
+use crate::ir::{self, FunctionId, Kind, LocalId, Operation, Program, Term};
+
+/// Lowers an expression, which may refer to the given top-level bindings.
+///
+/// Each binding may only refer to the bindings before it.
+pub fn lower(globals: &[(Identifier, Expr)], expr: &Expr) -> Option<Program> {
+    let mut lowering = Lowering {
+        globals,
+        lowered_globals: HashMap::new(),
+        results: vec![],
+        functions: vec![],
+    };
+    let scope = Scope {
+        entries: HashMap::new(),
+        visible_globals: globals.len(),
+    };
+    let main = lowering.function(&[], expr, &scope, None)?;
+    Some(Program {
+        functions: lowering.functions.into_iter().collect::<Option<Vec<_>>>()?,
+        main,
+    })
+}
+
+/// What a name refers to.
+#[derive(Debug, Clone, Copy)]
+enum Entry {
+    Local(LocalId, Kind),
+    /// A function, and the number of parameters it takes.
+    Function(FunctionId, usize),
+    Operation(Operation),
+    /// A local of an enclosing function, which cannot be captured.
+    Unavailable,
+}
+
+#[derive(Clone)]
+struct Scope {
+    entries: HashMap<Identifier, Entry>,
+    /// Names not in scope are looked up in this many top-level bindings.
+    visible_globals: usize,
+}
+
+impl Scope {
+    fn with(&self, name: &Identifier, entry: Entry) -> Self {
+        let mut scope = self.clone();
+        scope.entries.insert(name.clone(), entry);
+        scope
+    }
+}
+
+struct Lowering<'a> {
+    globals: &'a [(Identifier, Expr)],
+    /// Each top-level binding is lowered at most once, when it is first used.
+    lowered_globals: HashMap<usize, Option<Entry>>,
+    /// The kind of the result of each function, known as soon as the function
+    /// is reserved.
+    results: Vec<Kind>,
+    functions: Vec<Option<ir::Function>>,
+}
+
+impl<'a> Lowering<'a> {
+    fn term(&mut self, expr: &Expr, scope: &Scope, locals: &mut usize) -> Option<(Term, Kind)> {
+        match expr.expression() {
+            Expression::Primitive(Primitive::Integer(value)) => {
+                Some((Term::Integer(i64::try_from(value).ok()?), Kind::Integer))
+            }
+            Expression::Primitive(Primitive::Boolean(value)) => {
+                Some((Term::Boolean(*value), Kind::Boolean))
+            }
+            Expression::Identifier(name) => match self.resolve(name, scope)? {
+                Entry::Local(local, kind) => Some((Term::Local(local), kind)),
+                Entry::Function(function, 0) => {
+                    Some((Term::Call(function, vec![]), self.results[function]))
+                }
+                Entry::Function(_, _) | Entry::Operation(_) | Entry::Unavailable => None,
+            },
+            Expression::Apply(_) => {
+                let mut arguments = vec![];
+                let mut head = expr;
+                while let Expression::Apply(Apply { function, argument }) = head.expression() {
+                    arguments.push(argument);
+                    head = function;
+                }
+                arguments.reverse();
+                let Expression::Identifier(name) = head.expression() else {
+                    return None;
+                };
+                match (self.resolve(name, scope)?, arguments.as_slice()) {
+                    (Entry::Function(function, arity), _) if arguments.len() == arity => {
+                        let arguments = arguments
+                            .into_iter()
+                            .map(|argument| match self.term(argument, scope, locals)? {
+                                (term, Kind::Integer) => Some(term),
+                                (_, Kind::Boolean) => None,
+                            })
+                            .collect::<Option<Vec<_>>>()?;
+                        Some((Term::Call(function, arguments), self.results[function]))
+                    }
+                    (Entry::Operation(operation), [left, right]) => {
+                        let (left, left_kind) = self.term(left, scope, locals)?;
+                        let (right, right_kind) = self.term(right, scope, locals)?;
+                        let kind = operation.kind(left_kind, right_kind)?;
+                        Some((
+                            Term::Operation(operation, Box::new(left), Box::new(right)),
+                            kind,
+                        ))
+                    }
+                    _ => None,
+                }
+            }
+            Expression::Assign(Assign { name, value, inner }) => {
+                if let Some((parameters, body)) = function_literal(value) {
+                    let arity = parameters.len();
+                    let function = self.function(&parameters, body, scope, None)?;
+                    let inner_scope = scope.with(name, Entry::Function(function, arity));
+                    return self.term(inner, &inner_scope, locals);
+                }
+                let (value, value_kind) = self.term(value, scope, locals)?;
+                let local = new_local(locals);
+                let inner_scope = scope.with(name, Entry::Local(local, value_kind));
+                let (inner, kind) = self.term(inner, &inner_scope, locals)?;
+                Some((Term::Let(local, Box::new(value), Box::new(inner)), kind))
+            }
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                let literals = bindings
+                    .iter()
+                    .map(|(name, value)| Some((name, function_literal(value)?)))
+                    .collect::<Option<Vec<_>>>()?;
+                // the functions must all produce the same kind of result, which
+                // is not known until they have been lowered
+                let inner_scope =
+                    [Kind::Integer, Kind::Boolean]
+                        .into_iter()
+                        .find_map(|result| {
+                            let reserved = self.functions.len();
+                            let inner_scope = self.recursive_functions(&literals, scope, result);
+                            if inner_scope.is_none() {
+                                self.forget_functions_from(reserved);
+                            }
+                            inner_scope
+                        })?;
+                self.term(inner, &inner_scope, locals)
+            }
+            Expression::Match(Match { value, patterns }) => {
+                let (value, value_kind) = self.term(value, scope, locals)?;
+                let local = new_local(locals);
+                let mut kind = None;
+                let mut branches = Vec::with_capacity(patterns.len());
+                for PatternMatch {
+                    pattern, result, ..
+                } in patterns
+                {
+                    let (pattern, branch_scope) = match (pattern, value_kind) {
+                        (Pattern::Anything, _) => (ir::Pattern::Anything, scope.clone()),
+                        (Pattern::Variable(name), _) => (
+                            ir::Pattern::Anything,
+                            scope.with(name, Entry::Local(local, value_kind)),
+                        ),
+                        (Pattern::Primitive(Primitive::Integer(expected)), Kind::Integer) => (
+                            ir::Pattern::Integer(i64::try_from(expected).ok()?),
+                            scope.clone(),
+                        ),
+                        (Pattern::Primitive(Primitive::Boolean(expected)), Kind::Boolean) => {
+                            (ir::Pattern::Boolean(*expected), scope.clone())
+                        }
+                        _ => return None,
+                    };
+                    let (result, result_kind) = self.term(result, &branch_scope, locals)?;
+                    if *kind.get_or_insert(result_kind) != result_kind {
+                        return None;
+                    }
+                    branches.push((pattern, result));
+                }
+                Some((Term::Match(local, Box::new(value), branches), kind?))
+            }
+            Expression::Typed(Typed { expression, .. }) => self.term(expression, scope, locals),
+            Expression::Sequence(Sequence { first, second }) => {
+                let (first, _) = self.term(first, scope, locals)?;
+                let (second, kind) = self.term(second, scope, locals)?;
+                Some((Term::Sequence(Box::new(first), Box::new(second)), kind))
+            }
+            Expression::Primitive(_)
+            | Expression::Native(_)
+            | Expression::Function(_)
+            | Expression::Tuple(_)
+            | Expression::List(_) => None,
+        }
+    }
+
+    /// Lowers a function, which cannot refer to any locals from the
+    /// surrounding scope.
+    ///
+    /// If the function was already reserved, its result must match the
+    /// reserved kind.
+    fn function(
+        &mut self,
+        parameters: &[&Identifier],
+        body: &Expr,
+        outer: &Scope,
+        reserved: Option<FunctionId>,
+    ) -> Option<FunctionId> {
+        let mut scope = Scope {
+            entries: outer
+                .entries
+                .iter()
+                .map(|(name, entry)| match entry {
+                    Entry::Local(_, _) => (name.clone(), Entry::Unavailable),
+                    _ => (name.clone(), *entry),
+                })
+                .collect(),
+            visible_globals: outer.visible_globals,
+        };
+        for (local, parameter) in parameters.iter().enumerate() {
+            scope = scope.with(parameter, Entry::Local(local, Kind::Integer));
+        }
+        let mut locals = parameters.len();
+        let (body, result) = self.term(body, &scope, &mut locals)?;
+        let function = match reserved {
+            Some(function) if self.results[function] == result => function,
+            Some(_) => return None,
+            None => self.reserve(result),
+        };
+        self.functions[function] = Some(ir::Function {
+            arity: parameters.len(),
+            locals,
+            body,
+            result,
+        });
+        Some(function)
+    }
+
+    /// Lowers a group of mutually-recursive functions, assuming they all
+    /// produce the given kind of result, and returns the scope in which they
+    /// are visible.
+    fn recursive_functions(
+        &mut self,
+        literals: &[(&Identifier, (Vec<&Identifier>, &Expr))],
+        scope: &Scope,
+        result: Kind,
+    ) -> Option<Scope> {
+        let mut inner_scope = scope.clone();
+        let mut reserved = Vec::with_capacity(literals.len());
+        for (name, (parameters, _)) in literals {
+            let function = self.reserve(result);
+            inner_scope = inner_scope.with(name, Entry::Function(function, parameters.len()));
+            reserved.push(function);
+        }
+        for ((_, (parameters, body)), function) in literals.iter().zip(reserved) {
+            self.function(parameters, body, &inner_scope, Some(function))?;
+        }
+        Some(inner_scope)
+    }
+
+    /// Discards the functions from the given one onwards, after they failed
+    /// to lower.
+    fn forget_functions_from(&mut self, first: FunctionId) {
+        self.results.truncate(first);
+        self.functions.truncate(first);
+        self.lowered_globals.retain(
+            |_, entry| !matches!(entry, Some(Entry::Function(function, _)) if *function >= first),
+        );
+    }
+
+    fn reserve(&mut self, result: Kind) -> FunctionId {
+        self.results.push(result);
+        self.functions.push(None);
+        self.functions.len() - 1
+    }
+
+    fn resolve(&mut self, name: &Identifier, scope: &Scope) -> Option<Entry> {
+        if let Some(entry) = scope.entries.get(name) {
+            return Some(*entry);
+        }
+        let index = self.globals[..scope.visible_globals]
+            .iter()
+            .rposition(|(global, _)| global == name)?;
+        self.global(index)
+    }
+
+    fn global(&mut self, index: usize) -> Option<Entry> {
+        if let Some(entry) = self.lowered_globals.get(&index) {
+            return *entry;
+        }
+        let expr = &self.globals[index].1;
+        let scope = Scope {
+            entries: HashMap::new(),
+            visible_globals: index,
+        };
+        let entry = if let Some(operation) = operation(expr) {
+            Some(Entry::Operation(operation))
+        } else {
+            let (parameters, body) = function_literal(expr).unwrap_or((vec![], expr));
+            self.function(&parameters, body, &scope, None)
+                .map(|function| Entry::Function(function, parameters.len()))
+        };
+        self.lowered_globals.insert(index, entry);
+        entry
+    }
+}
+
+fn new_local(locals: &mut usize) -> LocalId {
+    *locals += 1;
+    *locals - 1
+}
+
+/// Splits a function literal, which may take several parameters, into its
+/// parameters and body.
+fn function_literal(expr: &Expr) -> Option<(Vec<&Identifier>, &Expr)> {
+    let mut parameters = vec![];
+    let mut body = expr;
+    loop {
+        match body.expression() {
+            Expression::Function(Function {
+                parameter,
+                body: inner,
+                ..
+            }) => {
+                parameters.push(parameter);
+                body = inner;
+            }
+            Expression::Typed(Typed { expression, .. }) if parameters.is_empty() => {
+                body = expression;
+            }
+            _ => break,
+        }
+    }
+    (!parameters.is_empty()).then_some((parameters, body))
+}
+
+/// Recognizes a built-in binary operation, in the form
+/// `fn left -> fn right -> <native>`.
+fn operation(expr: &Expr) -> Option<Operation> {
+    let (parameters, body) = function_literal(expr)?;
+    let [left, right] = parameters.as_slice() else {
+        return None;
+    };
+    match body.expression() {
+        Expression::Native(native)
+            if left.to_string() == "left" && right.to_string() == "right" =>
+        {
+            Operation::from_native_name(&native.unique_name.to_string())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,94 @@
+//! Determines which function parameters are always evaluated.
+//!
+//! Boo is lazy, but compiled functions take their arguments as values, and so
+//! the arguments must be evaluated before the call. This is only safe if the
+//! function would evaluate the parameter anyway; otherwise, evaluating an
+//! argument that is never used could fail, or never finish.
+
+use std::collections::HashMap;
+
+use crate::ir::{LocalId, Pattern, Program, Term};
+
+/// Returns `true` if every function in the program is strict in all of its
+/// parameters.
+pub fn is_strict(program: &Program) -> bool {
+    strictness(program)
+        .iter()
+        .all(|parameters| parameters.iter().all(|strict| *strict))
+}
+
+/// Computes, for each function, whether it is strict in each parameter.
+///
+/// This starts by assuming every function is strict in everything, and then
+/// refines the assumption until it no longer changes, so that recursive calls
+/// do not prevent a parameter from being considered strict.
+fn strictness(program: &Program) -> Vec<Vec<bool>> {
+    let mut strictness = program
+        .functions
+        .iter()
+        .map(|function| vec![true; function.arity])
+        .collect::<Vec<_>>();
+    loop {
+        let next = program
+            .functions
+            .iter()
+            .map(|function| {
+                (0..function.arity)
+                    .map(|parameter| {
+                        Analysis {
+                            strictness: &strictness,
+                            values: HashMap::new(),
+                        }
+                        .forces(&function.body, parameter)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        if next == strictness {
+            return strictness;
+        }
+        strictness = next;
+    }
+}
+
+struct Analysis<'a> {
+    strictness: &'a [Vec<bool>],
+    /// The values of the lazily-evaluated locals in scope.
+    values: HashMap<LocalId, &'a Term>,
+}
+
+impl<'a> Analysis<'a> {
+    /// Returns `true` if evaluating the term always evaluates the local.
+    fn forces(&mut self, term: &'a Term, local: LocalId) -> bool {
+        match term {
+            Term::Integer(_) | Term::Boolean(_) => false,
+            Term::Local(other) => {
+                *other == local
+                    || match self.values.get(other) {
+                        Some(value) => self.forces(value, local),
+                        None => false,
+                    }
+            }
+            Term::Operation(_, left, right) | Term::Sequence(left, right) => {
+                self.forces(left, local) || self.forces(right, local)
+            }
+            Term::Call(function, arguments) => self.strictness[*function]
+                .iter()
+                .zip(arguments)
+                .any(|(strict, argument)| *strict && self.forces(argument, local)),
+            Term::Let(bound, value, inner) => {
+                self.values.insert(*bound, value);
+                self.forces(inner, local)
+            }
+            Term::Match(bound, value, branches) => {
+                self.values.insert(*bound, value);
+                // the value is evaluated if the first pattern needs to inspect it
+                let inspected = !matches!(branches.first(), Some((Pattern::Anything, _)) | None);
+                (inspected && self.forces(value, local))
+                    || branches
+                        .iter()
+                        .all(|(_, result)| self.forces(result, local))
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use proptest::prelude::*;
+
+use boo_core::builtins;
+use boo_core::evaluation::*;
+use boo_core::primitive::Primitive;
+use boo_test_helpers::proptest::*;
+
+#[test]
+fn test_evaluation_gets_the_same_result_as_reducing_evaluation() {
+    let jit_evaluator = boo_conformance::prepared(boo_jit::new());
+    boo_conformance::check_against_reference(&jit_evaluator);
+}
+
+#[test]
+fn test_applying_functions_gets_the_same_result_as_reducing_evaluation() {
+    let jit_evaluator = boo_conformance::prepared(boo_jit::new());
+    boo_conformance::check_applications_against_reference(&jit_evaluator);
+}
+
+#[test]
+fn test_failing_gets_the_same_error_as_reducing_evaluation() {
+    let jit_evaluator = boo_conformance::prepared(boo_jit::new());
+    boo_conformance::check_failures_against_reference(&jit_evaluator);
+}
+
+#[test]
+fn test_integer_overflow_fails_in_the_same_way_as_reducing_evaluation() {
+    let jit_evaluator = boo_conformance::prepared(
+        boo_jit::JitEvaluationContext::new(
+            boo_evaluation_optimized::new().with_integer_overflow(IntegerOverflow::Fail),
+        )
+        .with_integer_overflow(IntegerOverflow::Fail),
+    );
+    boo_conformance::check_integer_overflow_fails(&jit_evaluator);
+}
+
+#[test]
+fn test_natives_can_apply_functions() {
+    let jit_evaluator = boo_conformance::prepared(boo_jit::new());
+    boo_conformance::check_natives_can_apply_functions(&jit_evaluator);
+}
+
+#[test]
+fn test_lists_bound_identifiers() {
+    boo_conformance::check_bound_identifiers_are_listed(boo_jit::new());
+}
+
+#[test]
+fn test_compiled_arithmetic_gets_the_same_result_as_interpreted_arithmetic() {
+    let interpreter = {
+        let mut context = boo_evaluation_optimized::new();
+        builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+        context.evaluator()
+    };
+    let jit_evaluator = prepared();
+
+    check(&arithmetic(), |program| {
+        let expr = boo_parser::parse_expr(&program)?.to_core()?;
+        let expected = interpreter.evaluate(expr.clone());
+        // if the compiled code gives up, the interpreter is used instead
+        if let Some(actual) = jit_evaluator.evaluate_compiled(&expr) {
+            prop_assert_eq!(
+                expected,
+                Ok(Evaluated::Primitive(actual)),
+                "input: {}",
+                program
+            );
+        }
+        Ok(())
+    })
+}
+
+/// Generates integer expressions, biased towards values that overflow.
+fn arithmetic() -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        (-100i64..100).prop_map(|value| format!("({value})")),
+        prop_oneof![Just(i64::MIN), Just(i64::MAX), Just(64), Just(-1)]
+            .prop_map(|value| format!("({value})")),
+        Just("x".to_owned()),
+    ];
+    leaf.prop_recursive(4, 32, 2, |inner| {
+        prop_oneof![
+            (
+                inner.clone(),
+                prop_oneof![
+                    Just("+"),
+                    Just("-"),
+                    Just("*"),
+                    Just("/"),
+                    Just("%"),
+                    Just("&"),
+                    Just("|"),
+                    Just("xor"),
+                    Just("<<"),
+                    Just(">>"),
+                ],
+                inner.clone()
+            )
+                .prop_map(|(left, operator, right)| format!("({left} {operator} {right})")),
+            (inner.clone(), inner.clone(), inner.clone(), inner.clone()).prop_map(
+                |(left, right, then, otherwise)| {
+                    format!("(match {left} < {right} {{ false -> {otherwise}; _ -> {then} }})")
+                }
+            ),
+            (inner.clone(), inner.clone())
+                .prop_map(|(value, body)| format!("(let x = {value} in {body})")),
+        ]
+    })
+    .prop_map(|body| format!("let x = 3 in {body}"))
+}
+
+#[test]
+fn test_compiles_integer_arithmetic() {
+    check_compiled("(1 + 2) * 3 - 7 / 2 % 2 + (1 << 4 | 3)", integer(27));
+}
+
+#[test]
+fn test_compiles_comparisons() {
+    check_compiled("3 * 3 > 8", Primitive::Boolean(true));
+}
+
+#[test]
+fn test_compiles_recursive_functions() {
+    check_compiled(
+        "let rec factorial = fn n -> match n { 0 -> 1; _ -> n * factorial (n - 1) } in factorial 20",
+        integer(2_432_902_008_176_640_000),
+    );
+}
+
+#[test]
+fn test_compiles_functions_with_accumulators() {
+    check_compiled(
+        "let rec sum = fn total n -> match n { 0 -> total; _ -> sum (total + n) (n - 1) } in sum 0 1_000_000",
+        integer(500_000_500_000),
+    );
+}
+
+#[test]
+fn test_compiles_mutually_recursive_functions() {
+    check_compiled(
+        "let rec is_even = fn n -> match n { 0 -> true; _ -> is_odd (n - 1) } and is_odd = fn n -> match n { 0 -> false; _ -> is_even (n - 1) } in is_even 100",
+        Primitive::Boolean(true),
+    );
+}
+
+#[test]
+fn test_compiles_top_level_definitions() {
+    let mut context = boo_jit::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+    let program = boo_parser::parse_program(
+        "def double = fn x -> x * 2; def quadruple = fn x -> double (double x); quadruple 3",
+    )
+    .unwrap();
+    let main = program.bind(&mut context).unwrap();
+    let evaluator = context.evaluator();
+
+    assert_eq!(evaluator.evaluate_compiled(&main), Some(integer(12)));
+}
+
+#[test]
+fn test_falls_back_when_integers_overflow() {
+    check_interpreted(
+        "let rec factorial = fn n -> match n { 0 -> 1; _ -> n * factorial (n - 1) } in factorial 25",
+        Primitive::Integer("15511210043330985984000000".parse().unwrap()),
+    );
+}
+
+#[test]
+fn test_falls_back_for_unused_values_that_cannot_be_computed() {
+    check_interpreted("let const = fn x y -> x in const 1 (1 / 0)", integer(1));
+}
+
+#[test]
+fn test_evaluates_unused_locals_lazily() {
+    check_compiled("let x = 1 / 0 in 5", integer(5));
+}
+
+#[test]
+fn test_falls_back_for_unsupported_expressions() {
+    check_interpreted("match (1, 2) { (a, b) -> a + b }", integer(3));
+}
+
+#[test]
+fn test_gives_up_on_deep_recursion() {
+    let evaluator = prepared();
+    let expr = boo_parser::parse_expr(
+        "let rec count = fn n -> match n { 0 -> 0; _ -> 1 + count (n - 1) } in count 20_000",
+    )
+    .unwrap()
+    .to_core()
+    .unwrap();
+
+    // the interpreter would need a larger stack to evaluate this
+    assert_eq!(evaluator.evaluate_compiled(&expr), None);
+}
+
+fn integer(value: i64) -> Primitive {
+    Primitive::Integer(value.into())
+}
+
+/// Checks that the program is compiled, and gets the expected result.
+fn check_compiled(program: &str, expected: Primitive) {
+    let evaluator = prepared();
+    let expr = boo_parser::parse_expr(program).unwrap().to_core().unwrap();
+
+    assert_eq!(evaluator.evaluate_compiled(&expr), Some(expected.clone()));
+    assert_eq!(evaluator.evaluate(expr), Ok(Evaluated::Primitive(expected)));
+}
+
+/// Checks that the program is not evaluated by compiled code, but still gets
+/// the expected result.
+fn check_interpreted(program: &str, expected: Primitive) {
+    let evaluator = prepared();
+    let expr = boo_parser::parse_expr(program).unwrap().to_core().unwrap();
+
+    assert_eq!(evaluator.evaluate_compiled(&expr), None);
+    assert_eq!(evaluator.evaluate(expr), Ok(Evaluated::Primitive(expected)));
+}
+
+fn prepared() -> impl std::ops::Deref<Target = boo_jit::JitEvaluator<impl Evaluator>> {
+    let mut context = boo_jit::new();
+    builtins::prepare(&mut context, builtins::IoCapabilities::default()).unwrap();
+    Box::new(context.evaluator())
+}
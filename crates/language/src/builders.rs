@@ -1,5 +1,7 @@
 //! Builders for a valid [`Expr`][super::Expr].
 
+use std::sync::Arc;
+
 use boo_core::primitive::*;
 
 use super::*;
@@ -12,15 +14,43 @@ pub fn primitive_integer(span: impl Into<Span>, value: Integer) -> Expr {
     primitive(span, Primitive::Integer(value))
 }
 
+pub fn primitive_float(span: impl Into<Span>, value: Float) -> Expr {
+    primitive(span, Primitive::Float(value))
+}
+
+pub fn primitive_boolean(span: impl Into<Span>, value: bool) -> Expr {
+    primitive(span, Primitive::Boolean(value))
+}
+
+pub fn primitive_char(span: impl Into<Span>, value: char) -> Expr {
+    primitive(span, Primitive::Char(value))
+}
+
+pub fn primitive_string(span: impl Into<Span>, value: impl Into<String>) -> Expr {
+    primitive(span, Primitive::String(Arc::new(value.into())))
+}
+
 pub fn identifier(span: impl Into<Span>, name: Identifier) -> Expr {
     Expr::new(span.into(), Expression::Identifier(name))
 }
 
 pub fn function(span: impl Into<Span>, parameters: Vec<Identifier>, body: Expr) -> Expr {
     assert!(!parameters.is_empty(), "parameters must not be empty");
+    let span = span.into();
     Expr::new(
-        span.into(),
-        Expression::Function(Function { parameters, body }),
+        span,
+        Expression::Function(Function {
+            parameters: parameters
+                .into_iter()
+                .map(|name| Parameter {
+                    name,
+                    typ: None,
+                    typ_span: None,
+                    span,
+                })
+                .collect(),
+            body,
+        }),
     )
 }
 
@@ -35,11 +65,37 @@ pub fn assign(span: impl Into<Span>, name: Identifier, value: Expr, inner: Expr)
     )
 }
 
-pub fn infix(span: impl Into<Span>, operation: Operation, left: Expr, right: Expr) -> Expr {
+pub fn assign_recursive(
+    span: impl Into<Span>,
+    bindings: Vec<(Identifier, Expr)>,
+    inner: Expr,
+) -> Expr {
+    assert!(!bindings.is_empty(), "bindings must not be empty");
     Expr::new(
         span.into(),
+        Expression::AssignRecursive(AssignRecursive { bindings, inner }),
+    )
+}
+
+pub fn tuple(span: impl Into<Span>, elements: Vec<Expr>) -> Expr {
+    assert!(
+        elements.len() >= 2,
+        "tuples must have at least two elements"
+    );
+    Expr::new(span.into(), Expression::Tuple(Tuple { elements }))
+}
+
+pub fn list(span: impl Into<Span>, elements: Vec<Expr>) -> Expr {
+    Expr::new(span.into(), Expression::List(List { elements }))
+}
+
+pub fn infix(span: impl Into<Span>, operation: Operation, left: Expr, right: Expr) -> Expr {
+    let span = span.into();
+    Expr::new(
+        span,
         Expression::Infix(Infix {
             operation,
+            operator_span: span,
             left,
             right,
         }),
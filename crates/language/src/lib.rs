@@ -5,7 +5,9 @@ pub mod operation;
 mod rewriter;
 
 use boo_core::error::Result;
+use boo_core::evaluation::EvaluationContext;
 use boo_core::identifier::Identifier;
+use boo_core::pretty::{self, Doc, Pretty};
 use boo_core::primitive::Primitive;
 use boo_core::span::Span;
 use boo_core::types::Monotype;
@@ -35,6 +37,196 @@ impl Expr {
         verification::verify(&result)?;
         Ok(result)
     }
+
+    /// Replaces the location of the expression and all of its subexpressions
+    /// with `0..0`.
+    ///
+    /// This is useful for comparing expressions parsed from different sources,
+    /// or with generated expressions, which have no location.
+    pub fn without_spans(self) -> Self {
+        Expr::new(
+            0.into(),
+            match *self.expression {
+                Expression::Primitive(x) => Expression::Primitive(x),
+                Expression::Identifier(x) => Expression::Identifier(x),
+                Expression::Function(Function { parameters, body }) => {
+                    Expression::Function(Function {
+                        parameters: parameters
+                            .into_iter()
+                            .map(|parameter| Parameter {
+                                typ_span: parameter.typ_span.map(|_| 0.into()),
+                                span: 0.into(),
+                                ..parameter
+                            })
+                            .collect(),
+                        body: body.without_spans(),
+                    })
+                }
+                Expression::Apply(Apply { function, argument }) => Expression::Apply(Apply {
+                    function: function.without_spans(),
+                    argument: argument.without_spans(),
+                }),
+                Expression::Assign(Assign { name, value, inner }) => Expression::Assign(Assign {
+                    name,
+                    value: value.without_spans(),
+                    inner: inner.without_spans(),
+                }),
+                Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                    Expression::AssignRecursive(AssignRecursive {
+                        bindings: bindings
+                            .into_iter()
+                            .map(|(name, value)| (name, value.without_spans()))
+                            .collect(),
+                        inner: inner.without_spans(),
+                    })
+                }
+                Expression::Match(Match { value, patterns }) => Expression::Match(Match {
+                    value: value.without_spans(),
+                    patterns: patterns
+                        .into_iter()
+                        .map(
+                            |PatternMatch {
+                                 pattern, result, ..
+                             }| PatternMatch {
+                                pattern,
+                                pattern_span: 0.into(),
+                                result: result.without_spans(),
+                            },
+                        )
+                        .collect(),
+                }),
+                Expression::Infix(Infix {
+                    operation,
+                    left,
+                    right,
+                    ..
+                }) => Expression::Infix(Infix {
+                    operation,
+                    operator_span: 0.into(),
+                    left: left.without_spans(),
+                    right: right.without_spans(),
+                }),
+                Expression::Typed(Typed {
+                    expression,
+                    typ,
+                    typ_span: _,
+                }) => Expression::Typed(Typed {
+                    expression: expression.without_spans(),
+                    typ,
+                    typ_span: 0.into(),
+                }),
+                Expression::Sequence(Sequence { first, second }) => {
+                    Expression::Sequence(Sequence {
+                        first: first.without_spans(),
+                        second: second.without_spans(),
+                    })
+                }
+                Expression::Tuple(Tuple { elements }) => Expression::Tuple(Tuple {
+                    elements: elements.into_iter().map(Self::without_spans).collect(),
+                }),
+                Expression::List(List { elements }) => Expression::List(List {
+                    elements: elements.into_iter().map(Self::without_spans).collect(),
+                }),
+            },
+        )
+    }
+
+    /// The immediate subexpressions, in the order they appear in the source.
+    pub fn children(&self) -> Vec<&Expr> {
+        match self.expression.as_ref() {
+            Expression::Primitive(_) | Expression::Identifier(_) => vec![],
+            Expression::Function(Function { body, .. }) => vec![body],
+            Expression::Apply(Apply { function, argument }) => vec![function, argument],
+            Expression::Assign(Assign { value, inner, .. }) => vec![value, inner],
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => bindings
+                .iter()
+                .map(|(_, value)| value)
+                .chain([inner])
+                .collect(),
+            Expression::Match(Match { value, patterns }) => [value]
+                .into_iter()
+                .chain(patterns.iter().map(|PatternMatch { result, .. }| result))
+                .collect(),
+            Expression::Infix(Infix { left, right, .. }) => vec![left, right],
+            Expression::Typed(Typed { expression, .. }) => vec![expression],
+            Expression::Sequence(Sequence { first, second }) => vec![first, second],
+            Expression::Tuple(Tuple { elements }) | Expression::List(List { elements }) => {
+                elements.iter().collect()
+            }
+        }
+    }
+}
+
+/// A whole program: a sequence of top-level definitions, optionally followed
+/// by the main expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Program {
+    /// The top-level definitions, in order.
+    pub definitions: Vec<Definition>,
+    /// The expression evaluated to produce the result of the program.
+    ///
+    /// If there is none, the program evaluates to the value of the last
+    /// definition. A program always has a main expression, a definition, or
+    /// both.
+    pub main: Option<Expr>,
+}
+
+/// A top-level definition, visible to all subsequent definitions and to the
+/// main expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Definition {
+    /// The name of the definition.
+    pub name: Identifier,
+    /// The defined value.
+    pub value: Expr,
+}
+
+impl Program {
+    /// Convert the whole program to a single core expression, by nesting each
+    /// definition around the main expression.
+    ///
+    /// This is suitable for type-checking the program.
+    pub fn to_core(self) -> Result<boo_core::expr::Expr> {
+        self.into_expr().to_core()
+    }
+
+    /// Bind each definition into the given context, returning the core main
+    /// expression, ready to be evaluated.
+    pub fn bind(
+        self,
+        context: &mut impl EvaluationContext<boo_core::expr::Expr>,
+    ) -> Result<boo_core::expr::Expr> {
+        let main = self.main();
+        for Definition { name, value } in self.definitions {
+            context.bind(name, value.to_core()?)?;
+        }
+        main.to_core()
+    }
+
+    /// The main expression, or a reference to the last definition if there is
+    /// none.
+    fn main(&self) -> Expr {
+        match (&self.main, self.definitions.last()) {
+            (Some(main), _) => main.clone(),
+            (None, Some(Definition { name, value })) => {
+                Expr::new(value.span, Expression::Identifier(name.clone()))
+            }
+            (None, None) => panic!("A program must have a main expression or a definition."),
+        }
+    }
+
+    fn into_expr(self) -> Expr {
+        let main = self.main();
+        self.definitions
+            .into_iter()
+            .rev()
+            .fold(main, |inner, Definition { name, value }| {
+                Expr::new(
+                    value.span | inner.span,
+                    Expression::Assign(Assign { name, value, inner }),
+                )
+            })
+    }
 }
 
 /// An inner Boo language expression node.
@@ -45,9 +237,13 @@ pub enum Expression {
     Function(Function),
     Apply(Apply),
     Assign(Assign),
+    AssignRecursive(AssignRecursive),
     Match(Match),
     Infix(Infix),
     Typed(Typed),
+    Sequence(Sequence),
+    Tuple(Tuple),
+    List(List),
 }
 
 /// Represents assignment.
@@ -61,15 +257,37 @@ pub struct Assign {
     pub inner: Expr,
 }
 
+/// Represents a group of mutually-recursive assignments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AssignRecursive {
+    /// The names and values of the assigned variables.
+    pub bindings: Vec<(Identifier, Expr)>,
+    /// The rest of the expression.
+    pub inner: Expr,
+}
+
 /// Represents a function definition.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Function {
-    /// The names of the function parameters.
-    pub parameters: Vec<Identifier>,
+    /// The function parameters.
+    pub parameters: Vec<Parameter>,
     /// The body of the function.
     pub body: Expr,
 }
 
+/// A function parameter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Parameter {
+    /// The name of the parameter.
+    pub name: Identifier,
+    /// The stated type of the parameter, if any.
+    pub typ: Option<Monotype>,
+    /// The location of the stated type in the source, if there is one.
+    pub typ_span: Option<Span>,
+    /// The location of the parameter in the source, including any type.
+    pub span: Span,
+}
+
 /// A set of patterns matched against a value.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Match {
@@ -84,6 +302,8 @@ pub struct Match {
 pub struct PatternMatch {
     /// The pattern to be matched.
     pub pattern: Pattern,
+    /// The location of the pattern in the source.
+    pub pattern_span: Span,
     /// The result of matching against the pattern.
     pub result: Expr,
 }
@@ -93,6 +313,17 @@ pub struct PatternMatch {
 pub enum Pattern {
     Anything,
     Primitive(Primitive),
+    Variable(Identifier),
+    Tuple(Vec<Pattern>),
+    List(Vec<Pattern>),
+    Cons {
+        head: Box<Pattern>,
+        tail: Box<Pattern>,
+    },
+    Some(Box<Pattern>),
+    None,
+    Ok(Box<Pattern>),
+    Err(Box<Pattern>),
 }
 
 /// Applies an argument to a function.
@@ -109,6 +340,8 @@ pub struct Apply {
 pub struct Infix {
     /// The operation.
     pub operation: Operation,
+    /// The location of the operator in the source.
+    pub operator_span: Span,
     /// The left operand.
     pub left: Expr,
     /// The right operand.
@@ -122,64 +355,245 @@ pub struct Typed {
     pub expression: Expr,
     /// The stated type of the expression.
     pub typ: Monotype,
+    /// The location of the stated type in the source.
+    pub typ_span: Span,
 }
 
-impl std::fmt::Display for Expr {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.expression.fmt(f)
+/// Evaluates one expression for its effects, and then another for the result.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sequence {
+    /// The expression evaluated first, whose result is discarded.
+    pub first: Expr,
+    /// The expression providing the result.
+    pub second: Expr,
+}
+
+/// A fixed-size collection of values, which may be of different types.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tuple {
+    /// The elements of the tuple.
+    pub elements: Vec<Expr>,
+}
+
+/// A list of values, all of the same type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct List {
+    /// The elements of the list.
+    pub elements: Vec<Expr>,
+}
+
+impl Pretty for Program {
+    fn to_doc(&self) -> Doc {
+        // The top level is never flattened, so each definition is on its own
+        // line.
+        Doc::join(
+            self.definitions
+                .iter()
+                .map(|definition| definition.to_doc())
+                .chain(self.main.iter().map(|main| main.to_doc())),
+            Doc::line(),
+        )
     }
 }
 
-impl std::fmt::Display for Expression {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Pretty for Definition {
+    fn to_doc(&self) -> Doc {
+        Doc::concat([
+            Doc::text(format!("def {} = ", self.name)),
+            Doc::parenthesize(self.value.to_doc()),
+            Doc::text(";"),
+        ])
+    }
+}
+
+impl Pretty for Expr {
+    fn to_doc(&self) -> Doc {
+        self.expression.to_doc()
+    }
+}
+
+impl Pretty for Expression {
+    fn to_doc(&self) -> Doc {
         match self {
-            Expression::Primitive(x) => x.fmt(f),
-            Expression::Identifier(x) => x.fmt(f),
-            Expression::Function(x) => x.fmt(f),
-            Expression::Apply(x) => x.fmt(f),
-            Expression::Assign(x) => x.fmt(f),
-            Expression::Match(x) => x.fmt(f),
-            Expression::Infix(x) => x.fmt(f),
-            Expression::Typed(x) => x.fmt(f),
+            Expression::Primitive(x) => Doc::text(x.to_string()),
+            Expression::Identifier(x) => Doc::text(x.to_string()),
+            Expression::Function(x) => x.to_doc(),
+            Expression::Apply(x) => x.to_doc(),
+            Expression::Assign(x) => x.to_doc(),
+            Expression::AssignRecursive(x) => x.to_doc(),
+            Expression::Match(x) => x.to_doc(),
+            Expression::Infix(x) => x.to_doc(),
+            Expression::Typed(x) => x.to_doc(),
+            Expression::Sequence(x) => x.to_doc(),
+            Expression::Tuple(x) => x.to_doc(),
+            Expression::List(x) => x.to_doc(),
         }
     }
 }
 
-impl std::fmt::Display for Assign {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "let {} = ({}) in ({})",
-            self.name, self.value, self.inner
-        )
+impl Pretty for Assign {
+    fn to_doc(&self) -> Doc {
+        Doc::concat([
+            Doc::text(format!("let {} = ", self.name)),
+            Doc::parenthesize(self.value.to_doc()),
+            Doc::text(" in"),
+            Doc::line(),
+            Doc::parenthesize(self.inner.to_doc()),
+        ])
+        .group()
     }
 }
 
-impl std::fmt::Display for Function {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "fn ")?;
+impl Pretty for AssignRecursive {
+    fn to_doc(&self) -> Doc {
+        let bindings = self.bindings.iter().map(|(name, value)| {
+            Doc::concat([
+                Doc::text(format!("{} = ", name)),
+                Doc::parenthesize(value.to_doc()),
+            ])
+        });
+        Doc::concat([
+            Doc::text("let rec "),
+            Doc::join(bindings, Doc::concat([Doc::line(), Doc::text("and ")])),
+            Doc::text(" in"),
+            Doc::line(),
+            Doc::parenthesize(self.inner.to_doc()),
+        ])
+        .group()
+    }
+}
+
+impl Pretty for Function {
+    fn to_doc(&self) -> Doc {
+        let mut header = String::from("fn ");
         for parameter in &self.parameters {
-            write!(f, "{} ", parameter)?;
+            header.push_str(&format!("{} ", parameter));
         }
-        write!(f, "-> ({})", self.body)
+        header.push_str("-> ");
+        Doc::concat([Doc::text(header), Doc::parenthesize(self.body.to_doc())])
     }
 }
 
-impl std::fmt::Display for Match {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "match {} {{", self.value)?;
-        let mut pattern_iter = self.patterns.iter();
-        if let Some(PatternMatch {
-            pattern: first_pattern,
-            result: first_result,
-        }) = pattern_iter.next()
+impl Pretty for Match {
+    fn to_doc(&self) -> Doc {
+        let patterns = self.patterns.iter().map(
+            |PatternMatch {
+                 pattern, result, ..
+             }| {
+                Doc::concat([
+                    Doc::text(format!("{} -> ", pattern)),
+                    Doc::parenthesize(result.to_doc()),
+                ])
+            },
+        );
+        Doc::concat([
+            Doc::text("match "),
+            self.value.to_doc(),
+            Doc::text(" "),
+            Doc::bracket(
+                "{",
+                Doc::join(patterns, Doc::concat([Doc::text(";"), Doc::line()])),
+                "}",
+            ),
+        ])
+    }
+}
+
+// Application is left-associative, so a chain of applications is printed as
+// the function followed by each argument.
+impl Pretty for Apply {
+    fn to_doc(&self) -> Doc {
+        let mut arguments = vec![&self.argument];
+        let mut function = &self.function;
+        while let Expression::Apply(Apply {
+            function: inner_function,
+            argument,
+        }) = function.expression.as_ref()
         {
-            write!(f, "{} -> ({})", first_pattern, first_result)?;
-            for PatternMatch { pattern, result } in pattern_iter {
-                write!(f, "; {} -> ({})", pattern, result)?;
-            }
+            arguments.push(argument);
+            function = inner_function;
+        }
+        Doc::concat([
+            Doc::parenthesize(function.to_doc()),
+            Doc::concat(
+                arguments
+                    .into_iter()
+                    .rev()
+                    .flat_map(|argument| [Doc::line(), Doc::parenthesize(argument.to_doc())]),
+            )
+            .nest(),
+        ])
+        .group()
+    }
+}
+
+impl Pretty for Infix {
+    fn to_doc(&self) -> Doc {
+        Doc::concat([
+            Doc::parenthesize(self.left.to_doc()),
+            Doc::concat([
+                Doc::line(),
+                Doc::text(format!("{} ", self.operation)),
+                Doc::parenthesize(self.right.to_doc()),
+            ])
+            .nest(),
+        ])
+        .group()
+    }
+}
+
+impl Pretty for Typed {
+    fn to_doc(&self) -> Doc {
+        Doc::concat([
+            Doc::parenthesize(self.expression.to_doc()),
+            Doc::text(format!(": {}", self.typ)),
+        ])
+    }
+}
+
+impl Pretty for Sequence {
+    fn to_doc(&self) -> Doc {
+        Doc::parenthesize(Doc::concat([
+            Doc::parenthesize(self.first.to_doc()),
+            Doc::text(";"),
+            Doc::line(),
+            Doc::parenthesize(self.second.to_doc()),
+        ]))
+    }
+}
+
+impl Pretty for Tuple {
+    fn to_doc(&self) -> Doc {
+        Doc::parenthesize(Doc::join(
+            self.elements
+                .iter()
+                .map(|element| Doc::parenthesize(element.to_doc())),
+            Doc::concat([Doc::text(","), Doc::line()]),
+        ))
+    }
+}
+
+impl Pretty for List {
+    fn to_doc(&self) -> Doc {
+        Doc::bracket(
+            "[",
+            Doc::join(
+                self.elements
+                    .iter()
+                    .map(|element| Doc::parenthesize(element.to_doc())),
+                Doc::concat([Doc::text(","), Doc::line()]),
+            ),
+            "]",
+        )
+    }
+}
+
+impl std::fmt::Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.typ {
+            None => self.name.fmt(f),
+            Some(typ) => write!(f, "({}: {})", self.name, typ),
         }
-        write!(f, "}}")
     }
 }
 
@@ -188,24 +602,118 @@ impl std::fmt::Display for Pattern {
         match self {
             Pattern::Anything => write!(f, "_"),
             Pattern::Primitive(x) => x.fmt(f),
+            Pattern::Variable(x) => x.fmt(f),
+            Pattern::Tuple(elements) => {
+                write!(f, "(")?;
+                let mut element_iter = elements.iter();
+                if let Some(first) = element_iter.next() {
+                    write!(f, "{}", first)?;
+                    for element in element_iter {
+                        write!(f, ", {}", element)?;
+                    }
+                }
+                write!(f, ")")
+            }
+            Pattern::List(elements) => {
+                write!(f, "[")?;
+                let mut element_iter = elements.iter();
+                if let Some(first) = element_iter.next() {
+                    write!(f, "{}", first)?;
+                    for element in element_iter {
+                        write!(f, ", {}", element)?;
+                    }
+                }
+                write!(f, "]")
+            }
+            Pattern::Cons { head, tail } => write!(f, "({}) :: ({})", head, tail),
+            Pattern::Some(inner) => write!(f, "some ({})", inner),
+            Pattern::None => write!(f, "none"),
+            Pattern::Ok(inner) => write!(f, "ok ({})", inner),
+            Pattern::Err(inner) => write!(f, "err ({})", inner),
         }
     }
 }
 
+impl std::fmt::Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for Definition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for Assign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for AssignRecursive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for Match {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
 impl std::fmt::Display for Apply {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}) ({})", self.function, self.argument)
+        pretty::display(self, f)
     }
 }
 
 impl std::fmt::Display for Infix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}) {} ({})", self.left, self.operation, self.right)
+        pretty::display(self, f)
     }
 }
 
 impl std::fmt::Display for Typed {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}): {}", self.expression, self.typ)
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for Sequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for Tuple {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+impl std::fmt::Display for List {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
     }
 }
@@ -1,8 +1,8 @@
-//! Infix operations on integers.
+//! Infix operations.
 
 use boo_core::identifier::Identifier;
 
-/// The set of valid infix operations on integers.
+/// The set of valid infix operations.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, proptest_derive::Arbitrary)]
 pub enum Operation {
     /// Adds two integers.
@@ -11,6 +11,44 @@ pub enum Operation {
     Subtract,
     /// Multiplies two integers.
     Multiply,
+    /// Divides an integer by another, rounding towards zero.
+    Divide,
+    /// Computes the remainder of dividing an integer by another.
+    Modulo,
+    /// Computes the bitwise "and" of two integers.
+    BitwiseAnd,
+    /// Computes the bitwise "or" of two integers.
+    BitwiseOr,
+    /// Computes the bitwise "exclusive or" of two integers.
+    BitwiseXor,
+    /// Shifts the bits of an integer to the left.
+    ShiftLeft,
+    /// Shifts the bits of an integer to the right, preserving the sign.
+    ShiftRight,
+    /// Adds two floats.
+    AddFloat,
+    /// Subtracts a float from another.
+    SubtractFloat,
+    /// Multiplies two floats.
+    MultiplyFloat,
+    /// Divides a float by another.
+    DivideFloat,
+    /// Concatenates two strings.
+    Concatenate,
+    /// Constructs a list from an element and another list.
+    Cons,
+    /// Checks whether two values are equal.
+    Equal,
+    /// Checks whether two values are not equal.
+    NotEqual,
+    /// Checks whether a value is less than another.
+    LessThan,
+    /// Checks whether a value is less than or equal to another.
+    LessThanOrEqual,
+    /// Checks whether a value is greater than another.
+    GreaterThan,
+    /// Checks whether a value is greater than or equal to another.
+    GreaterThanOrEqual,
 }
 
 impl Operation {
@@ -20,6 +58,25 @@ impl Operation {
             Operation::Add => Identifier::operator_from_str("+"),
             Operation::Subtract => Identifier::operator_from_str("-"),
             Operation::Multiply => Identifier::operator_from_str("*"),
+            Operation::Divide => Identifier::operator_from_str("/"),
+            Operation::Modulo => Identifier::operator_from_str("%"),
+            Operation::BitwiseAnd => Identifier::operator_from_str("&"),
+            Operation::BitwiseOr => Identifier::operator_from_str("|"),
+            Operation::BitwiseXor => Identifier::operator_from_str("xor"),
+            Operation::ShiftLeft => Identifier::operator_from_str("<<"),
+            Operation::ShiftRight => Identifier::operator_from_str(">>"),
+            Operation::AddFloat => Identifier::operator_from_str("+."),
+            Operation::SubtractFloat => Identifier::operator_from_str("-."),
+            Operation::MultiplyFloat => Identifier::operator_from_str("*."),
+            Operation::DivideFloat => Identifier::operator_from_str("/."),
+            Operation::Concatenate => Identifier::operator_from_str("++"),
+            Operation::Cons => Identifier::operator_from_str("::"),
+            Operation::Equal => Identifier::operator_from_str("=="),
+            Operation::NotEqual => Identifier::operator_from_str("!="),
+            Operation::LessThan => Identifier::operator_from_str("<"),
+            Operation::LessThanOrEqual => Identifier::operator_from_str("<="),
+            Operation::GreaterThan => Identifier::operator_from_str(">"),
+            Operation::GreaterThanOrEqual => Identifier::operator_from_str(">="),
         }
         .unwrap()
     }
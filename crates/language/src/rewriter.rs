@@ -1,9 +1,17 @@
 //! Rewrites the expression tree to as a core AST.
 //!
-//! For now, this just rewrites infix operations as normal function application.
+//! For now, this just rewrites infix operations as normal function application,
+//! functions of multiple parameters as nested functions, and list literals as a
+//! sequence of list constructions.
+//!
+//! Each node synthesized along the way is given a span derived from the surface
+//! node it came from, covering the part of the source it represents. These
+//! spans always lie within the span of the originating node, so the surface
+//! node can be found again from any core node.
 
 use boo_core::error::Result;
 use boo_core::expr as core;
+use boo_core::span::Span;
 
 pub fn rewrite(expr: crate::Expr) -> Result<core::Expr> {
     let wrap = { |expression| core::Expr::new(Some(expr.span), expression) };
@@ -11,14 +19,30 @@ pub fn rewrite(expr: crate::Expr) -> Result<core::Expr> {
         crate::Expression::Primitive(x) => wrap(core::Expression::Primitive(x)),
         crate::Expression::Identifier(x) => wrap(core::Expression::Identifier(x)),
         crate::Expression::Function(crate::Function { parameters, body }) => {
-            let mut expr = rewrite(body)?;
-            for parameter in parameters.into_iter().rev() {
-                expr = wrap(core::Expression::Function(core::Function {
-                    parameter,
-                    body: expr,
-                }));
+            // `fn x y -> body` becomes `fn x -> fn y -> body`, where the inner
+            // function starts at its parameter
+            let end = expr.span.end;
+            let mut result = rewrite(body)?;
+            for (index, parameter) in parameters.into_iter().enumerate().rev() {
+                let span = if index == 0 {
+                    expr.span
+                } else {
+                    Span {
+                        start: parameter.span.start,
+                        end,
+                    }
+                };
+                result = core::Expr::new(
+                    Some(span),
+                    core::Expression::Function(core::Function {
+                        parameter: parameter.name,
+                        parameter_type: parameter.typ,
+                        parameter_type_span: parameter.typ_span,
+                        body: result,
+                    }),
+                );
             }
-            expr
+            result
         }
         crate::Expression::Apply(crate::Apply { function, argument }) => {
             wrap(core::Expression::Apply(core::Apply {
@@ -33,19 +57,30 @@ pub fn rewrite(expr: crate::Expr) -> Result<core::Expr> {
                 inner: rewrite(inner)?,
             }))
         }
+        crate::Expression::AssignRecursive(crate::AssignRecursive { bindings, inner }) => {
+            wrap(core::Expression::AssignRecursive(core::AssignRecursive {
+                bindings: bindings
+                    .into_iter()
+                    .map(|(name, value)| Ok((name, rewrite(value)?)))
+                    .collect::<Result<_>>()?,
+                inner: rewrite(inner)?,
+            }))
+        }
         crate::Expression::Match(crate::Match { value, patterns }) => {
             wrap(core::Expression::Match(core::Match {
                 value: rewrite(value)?,
                 patterns: patterns
                     .into_iter()
                     .map(
-                        |crate::PatternMatch { pattern, result }| -> Result<core::PatternMatch<_>> {
-                            let rewritten_pattern = match pattern {
-                                crate::Pattern::Anything => core::Pattern::Anything,
-                                crate::Pattern::Primitive(x) => core::Pattern::Primitive(x),
-                            };
+                        |crate::PatternMatch {
+                             pattern,
+                             pattern_span,
+                             result,
+                         }|
+                         -> Result<core::PatternMatch<_>> {
                             Ok(core::PatternMatch {
-                                pattern: rewritten_pattern,
+                                pattern: rewrite_pattern(pattern),
+                                pattern_span: Some(pattern_span),
                                 result: rewrite(result)?,
                             })
                         },
@@ -55,24 +90,113 @@ pub fn rewrite(expr: crate::Expr) -> Result<core::Expr> {
         }
         crate::Expression::Infix(crate::Infix {
             operation,
+            operator_span,
             left,
             right,
-        }) => wrap(core::Expression::Apply(core::Apply {
-            function: wrap(core::Expression::Apply(core::Apply {
-                function: wrap(core::Expression::Identifier(operation.identifier())),
-                argument: rewrite(left)?,
-            })),
-            argument: rewrite(right)?,
+        }) => {
+            // `a + b` becomes `(+) a b`, where the partial application runs
+            // from the left operand to the operator
+            let partial_span = left.span | operator_span;
+            wrap(core::Expression::Apply(core::Apply {
+                function: core::Expr::new(
+                    Some(partial_span),
+                    core::Expression::Apply(core::Apply {
+                        function: core::Expr::new(
+                            Some(operator_span),
+                            core::Expression::Identifier(operation.identifier()),
+                        ),
+                        argument: rewrite(left)?,
+                    }),
+                ),
+                argument: rewrite(right)?,
+            }))
+        }
+        crate::Expression::Typed(crate::Typed {
+            expression,
+            typ,
+            typ_span,
+        }) => wrap(core::Expression::Typed(core::Typed {
+            expression: rewrite(expression)?,
+            typ,
+            typ_span: Some(typ_span),
         })),
-        crate::Expression::Typed(crate::Typed { expression, typ }) => {
-            wrap(core::Expression::Typed(core::Typed {
-                expression: rewrite(expression)?,
-                typ,
+        crate::Expression::Sequence(crate::Sequence { first, second }) => {
+            wrap(core::Expression::Sequence(core::Sequence {
+                first: rewrite(first)?,
+                second: rewrite(second)?,
             }))
         }
+        crate::Expression::Tuple(crate::Tuple { elements }) => {
+            wrap(core::Expression::Tuple(core::Tuple {
+                elements: elements.into_iter().map(rewrite).collect::<Result<_>>()?,
+            }))
+        }
+        crate::Expression::List(crate::List { elements }) => {
+            // `[a, b]` becomes `a :: b :: []`, where each tail runs from its
+            // first element to the end of the list, and the empty list is
+            // located at the closing bracket
+            let end = expr.span.end;
+            let mut list = core::Expr::new(
+                Some(if elements.is_empty() {
+                    expr.span
+                } else {
+                    Span {
+                        start: end.saturating_sub(1).max(expr.span.start),
+                        end,
+                    }
+                }),
+                core::Expression::List(core::List::Empty),
+            );
+            for (index, element) in elements.into_iter().enumerate().rev() {
+                let span = if index == 0 {
+                    expr.span
+                } else {
+                    Span {
+                        start: element.span.start,
+                        end,
+                    }
+                };
+                list = core::Expr::new(
+                    Some(span),
+                    core::Expression::List(core::List::Cons {
+                        head: rewrite(element)?,
+                        tail: list,
+                    }),
+                );
+            }
+            list
+        }
     })
 }
 
+fn rewrite_pattern(pattern: crate::Pattern) -> core::Pattern {
+    match pattern {
+        crate::Pattern::Anything => core::Pattern::Anything,
+        crate::Pattern::Primitive(x) => core::Pattern::Primitive(x),
+        crate::Pattern::Variable(x) => core::Pattern::Variable(x),
+        crate::Pattern::Tuple(elements) => {
+            core::Pattern::Tuple(elements.into_iter().map(rewrite_pattern).collect())
+        }
+        crate::Pattern::List(elements) => {
+            elements
+                .into_iter()
+                .rev()
+                .fold(core::Pattern::EmptyList, |tail, head| core::Pattern::Cons {
+                    head: rewrite_pattern(head).into(),
+                    tail: tail.into(),
+                })
+        }
+        crate::Pattern::Cons { head, tail } => core::Pattern::Cons {
+            head: rewrite_pattern(*head).into(),
+            tail: rewrite_pattern(*tail).into(),
+        },
+        crate::Pattern::Some(inner) => core::Pattern::Some(rewrite_pattern(*inner).into()),
+        crate::Pattern::None => core::Pattern::None,
+        crate::Pattern::Ok(inner) => core::Pattern::Ok(rewrite_pattern(*inner).into()),
+        crate::Pattern::Err(inner) => core::Pattern::Err(rewrite_pattern(*inner).into()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use boo_core::identifier::Identifier;
@@ -89,6 +213,7 @@ mod tests {
             (0..5).into(),
             crate::Expression::Infix(crate::Infix {
                 operation: crate::Operation::Add,
+                operator_span: (2..3).into(),
                 left: crate::Expr::new((0..1).into(), crate::Expression::Primitive(a.clone())),
                 right: crate::Expr::new((4..5).into(), crate::Expression::Primitive(b.clone())),
             }),
@@ -98,10 +223,10 @@ mod tests {
             Some((0..5).into()),
             core::Expression::Apply(core::Apply {
                 function: core::Expr::new(
-                    Some((0..5).into()),
+                    Some((0..3).into()),
                     core::Expression::Apply(core::Apply {
                         function: core::Expr::new(
-                            Some((0..5).into()),
+                            Some((2..3).into()),
                             core::Expression::Identifier(Identifier::operator_from_str("+")?),
                         ),
                         argument: core::Expr::new(
@@ -119,4 +244,89 @@ mod tests {
         assert_eq!(actual, expected);
         Ok(())
     }
+
+    #[test]
+    fn test_functions_with_multiple_parameters_are_nested_from_each_parameter() -> anyhow::Result<()>
+    {
+        // fn x y -> x
+        let x = Identifier::name_from_str("x")?;
+        let y = Identifier::name_from_str("y")?;
+        let parameter = |name: &Identifier, span: std::ops::Range<usize>| crate::Parameter {
+            name: name.clone(),
+            typ: None,
+            typ_span: None,
+            span: span.into(),
+        };
+        let expression = crate::Expr::new(
+            (0..11).into(),
+            crate::Expression::Function(crate::Function {
+                parameters: vec![parameter(&x, 3..4), parameter(&y, 5..6)],
+                body: crate::Expr::new((10..11).into(), crate::Expression::Identifier(x.clone())),
+            }),
+        );
+
+        let expected = core::Expr::new(
+            Some((0..11).into()),
+            core::Expression::Function(core::Function {
+                parameter: x.clone(),
+                parameter_type: None,
+                parameter_type_span: None,
+                body: core::Expr::new(
+                    Some((5..11).into()),
+                    core::Expression::Function(core::Function {
+                        parameter: y,
+                        parameter_type: None,
+                        parameter_type_span: None,
+                        body: core::Expr::new(
+                            Some((10..11).into()),
+                            core::Expression::Identifier(x),
+                        ),
+                    }),
+                ),
+            }),
+        );
+
+        let actual = rewrite(expression)?;
+
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_elements_are_located_up_to_the_end_of_the_list() -> anyhow::Result<()> {
+        // [1, 2]
+        let a = Primitive::Integer(1.into());
+        let b = Primitive::Integer(2.into());
+        let expression = crate::Expr::new(
+            (0..6).into(),
+            crate::Expression::List(crate::List {
+                elements: vec![
+                    crate::Expr::new((1..2).into(), crate::Expression::Primitive(a.clone())),
+                    crate::Expr::new((4..5).into(), crate::Expression::Primitive(b.clone())),
+                ],
+            }),
+        );
+
+        let expected = core::Expr::new(
+            Some((0..6).into()),
+            core::Expression::List(core::List::Cons {
+                head: core::Expr::new(Some((1..2).into()), core::Expression::Primitive(a)),
+                tail: core::Expr::new(
+                    Some((4..6).into()),
+                    core::Expression::List(core::List::Cons {
+                        head: core::Expr::new(Some((4..5).into()), core::Expression::Primitive(b)),
+                        tail: core::Expr::new(
+                            Some((5..6).into()),
+                            core::Expression::List(core::List::Empty),
+                        ),
+                    }),
+                ),
+            }),
+        );
+
+        let actual = rewrite(expression)?;
+
+        assert_eq!(actual, expected);
+        Ok(())
+    }
 }
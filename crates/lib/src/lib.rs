@@ -1,15 +1,23 @@
 pub use boo_core::ast;
 pub use boo_core::builtins;
+pub use boo_core::diagnostics;
 pub use boo_core::error;
 pub use boo_core::evaluation;
+pub use boo_core::expr;
 pub use boo_core::identifier;
+pub use boo_core::lint;
 pub use boo_core::native;
+pub use boo_core::optimization;
 pub use boo_core::primitive;
+pub use boo_core::span;
+pub use boo_core::specialization;
 pub use boo_core::types;
+pub use boo_core::verification;
+pub use boo_core::warning;
 
-pub use boo_language::Expr;
+pub use boo_language::{Expr, Program};
 
 pub use boo_evaluation_optimized as evaluator;
 
 pub use boo_parser as parser;
-pub use boo_parser::parse;
+pub use boo_parser::{parse_expr, parse_program};
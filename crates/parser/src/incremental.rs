@@ -0,0 +1,331 @@
+//! Re-parses a program after an edit, re-using as much of the previous parse
+//! as possible.
+//!
+//! Only the tokens around the edit are re-lexed; lexing stops as soon as it
+//! reaches a token which starts at the same place as before the edit, as the
+//! rest of the input is unchanged. Only the top-level definitions containing
+//! changed tokens are re-parsed, along with the main expression if it contains
+//! any. Everything else is re-used, with its spans shifted if it comes after
+//! the edit.
+
+use std::ops::Range;
+
+use boo_core::error::*;
+use boo_core::span::*;
+use boo_language::*;
+
+use crate::lexer::*;
+use crate::parser::*;
+
+/// A change to the input: the text in the range is replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// The range of the replaced text, in the input before the edit.
+    pub range: Span,
+    /// The text which replaces it.
+    pub replacement: String,
+}
+
+impl TextEdit {
+    /// Applies the edit to the input it was made against.
+    pub fn apply(&self, input: &str) -> String {
+        let mut result =
+            String::with_capacity(input.len() - self.range.range().len() + self.replacement.len());
+        result.push_str(&input[..self.range.start]);
+        result.push_str(&self.replacement);
+        result.push_str(&input[self.range.end..]);
+        result
+    }
+
+    /// The end of the replacement, in the input after the edit.
+    fn replacement_end(&self) -> usize {
+        self.range.start + self.replacement.len()
+    }
+
+    /// Moves a position at or after the end of the replaced text to where it
+    /// is after the edit.
+    fn shift(&self, position: usize) -> usize {
+        position - self.range.end + self.replacement_end()
+    }
+}
+
+/// A parsed program, along with the tokens it was parsed from, so that it can
+/// be re-parsed incrementally.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedProgram<'a> {
+    /// The length of the input.
+    length: usize,
+    tokens: Vec<AnnotatedToken<'a, Span>>,
+    /// The range of tokens making up each top-level definition. The main
+    /// expression is made up of the rest.
+    definitions: Vec<Range<usize>>,
+    program: Program,
+}
+
+impl<'a> ParsedProgram<'a> {
+    /// Lexes and parses the whole input.
+    pub fn parse(input: &'a str) -> Result<Self> {
+        let tokens = lex(input)?;
+        Self::from_tokens(input.len(), tokens)
+    }
+
+    fn from_tokens(length: usize, tokens: Vec<AnnotatedToken<'a, Span>>) -> Result<Self> {
+        let (definitions, main) = parse_located_program_tokens(&tokens)?;
+        let (definitions, values) = definitions.into_iter().unzip();
+        Ok(Self {
+            length,
+            tokens,
+            definitions,
+            program: Program {
+                definitions: values,
+                main,
+            },
+        })
+    }
+
+    /// The tokens lexed from the input.
+    pub fn tokens(&self) -> &[AnnotatedToken<'a, Span>] {
+        &self.tokens
+    }
+
+    /// The parsed program.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    /// Discards the tokens, returning the parsed program.
+    pub fn into_program(self) -> Program {
+        self.program
+    }
+
+    /// Re-parses the program after the edit. The input must be the result of
+    /// applying the edit to the input which was previously parsed.
+    ///
+    /// The result is the same as parsing the new input from scratch, including
+    /// any error.
+    pub fn reparse<'b>(&self, input: &'b str, edit: &TextEdit) -> Result<ParsedProgram<'b>> {
+        if edit.range.start > edit.range.end
+            || edit.range.end > self.length
+            || input.len() != self.length - edit.range.range().len() + edit.replacement.len()
+        {
+            return ParsedProgram::parse(input);
+        }
+
+        let (tokens, changed) = self.relex(input, edit)?;
+        let changed_count = tokens.len() + changed.len() - self.tokens.len();
+        let shift_index = |index: usize| index - changed.end + changed.start + changed_count;
+
+        // Definitions entirely before or after the changed tokens are re-used.
+        // Without a main expression, the last definition may be unterminated,
+        // in which case anything appended to it could extend it.
+        let reusable = if self.program.main.is_some() {
+            self.definitions.len()
+        } else {
+            self.definitions.len().saturating_sub(1)
+        };
+        let before = self.definitions[..reusable]
+            .partition_point(|definition| definition.end <= changed.start);
+        let after = self
+            .definitions
+            .partition_point(|definition| definition.start < changed.end);
+        let main_start = self
+            .definitions
+            .last()
+            .map_or(0, |definition| definition.end);
+        let main_changed = changed.start >= main_start || changed.end > main_start;
+        let region_start = self
+            .definitions
+            .get(before)
+            .map_or(main_start, |definition| definition.start)
+            .min(changed.start);
+
+        let reparsed = if main_changed {
+            parse_located_program_tokens(&tokens[region_start..])
+                .map(|(definitions, main)| (definitions, Some(main)))
+        } else {
+            let region_end = self.definitions[..after]
+                .last()
+                .map_or(changed.end, |definition| definition.end.max(changed.end));
+            parse_located_definition_tokens(&tokens[region_start..shift_index(region_end)])
+                .map(|definitions| (definitions, None))
+        };
+        let Ok((reparsed_definitions, reparsed_main)) = reparsed else {
+            // The error may depend on what follows the changed region, so
+            // parse everything to report it accurately.
+            return ParsedProgram::from_tokens(input.len(), tokens);
+        };
+
+        let mut definitions = Vec::with_capacity(self.definitions.len());
+        let mut values = Vec::with_capacity(self.definitions.len());
+        for (range, value) in self.definitions[..before]
+            .iter()
+            .zip(&self.program.definitions)
+        {
+            definitions.push(range.clone());
+            values.push(value.clone());
+        }
+        for (range, value) in reparsed_definitions {
+            definitions.push(range.start + region_start..range.end + region_start);
+            values.push(value);
+        }
+        let shift_span = |span: Span| Span {
+            start: edit.shift(span.start),
+            end: edit.shift(span.end),
+        };
+        if !main_changed {
+            for (range, value) in self.definitions[after..]
+                .iter()
+                .zip(&self.program.definitions[after..])
+            {
+                definitions.push(shift_index(range.start)..shift_index(range.end));
+                let mut value = value.clone();
+                shift_spans(&mut value.value, &shift_span);
+                values.push(value);
+            }
+        }
+        let main = reparsed_main.unwrap_or_else(|| {
+            let mut main = self.program.main.clone();
+            if let Some(main) = &mut main {
+                shift_spans(main, &shift_span);
+            }
+            main
+        });
+
+        Ok(ParsedProgram {
+            length: input.len(),
+            tokens,
+            definitions,
+            program: Program {
+                definitions: values,
+                main,
+            },
+        })
+    }
+
+    /// Re-lexes the tokens affected by the edit, returning all the tokens for
+    /// the new input, along with the range of previous tokens which were
+    /// replaced. The replacements start at the same index.
+    fn relex<'b>(
+        &self,
+        input: &'b str,
+        edit: &TextEdit,
+    ) -> Result<(Vec<AnnotatedToken<'b, Span>>, Range<usize>)> {
+        // Any token ending at or after the start of the edit may be extended
+        // or replaced by it.
+        let mut start = self
+            .tokens
+            .partition_point(|token| token.annotation.end < edit.range.start);
+        // Adjacent tokens may merge, so lexing must resume after whitespace
+        // or a comment, which cannot be part of the previous token.
+        while start > 0
+            && start < self.tokens.len()
+            && self.tokens[start - 1].annotation.end == self.tokens[start].annotation.start
+        {
+            start -= 1;
+        }
+        let resume_position = start
+            .checked_sub(1)
+            .map_or(0, |previous| self.tokens[previous].annotation.end);
+
+        let mut tokens: Vec<AnnotatedToken<'b, Span>> = self.tokens[..start]
+            .iter()
+            .map(|token| AnnotatedToken {
+                annotation: token.annotation,
+                token: token.token.rebase(input, token.annotation),
+            })
+            .collect();
+
+        // Once a token starts where a token started before the edit, the rest
+        // of the input is unchanged, and so are the rest of the tokens.
+        let mut end = self.tokens.len();
+        for token in lex_from(input, resume_position) {
+            let token = token?;
+            let token_start = token.annotation.start;
+            if token_start >= edit.replacement_end() {
+                let previous_start = token_start - edit.replacement_end() + edit.range.end;
+                if let Ok(index) = self.tokens[start..]
+                    .binary_search_by_key(&previous_start, |token| token.annotation.start)
+                {
+                    end = start + index;
+                    break;
+                }
+            }
+            tokens.push(token);
+        }
+
+        tokens.extend(self.tokens[end..].iter().map(|token| {
+            let annotation = Span {
+                start: edit.shift(token.annotation.start),
+                end: edit.shift(token.annotation.end),
+            };
+            AnnotatedToken {
+                annotation,
+                token: token.token.rebase(input, annotation),
+            }
+        }));
+        Ok((tokens, start..end))
+    }
+}
+
+/// Updates every span in the expression.
+fn shift_spans(expr: &mut Expr, shift: &impl Fn(Span) -> Span) {
+    expr.span = shift(expr.span);
+    match expr.expression.as_mut() {
+        Expression::Primitive(_) | Expression::Identifier(_) => {}
+        Expression::Function(Function { parameters, body }) => {
+            for parameter in parameters {
+                parameter.span = shift(parameter.span);
+                parameter.typ_span = parameter.typ_span.map(shift);
+            }
+            shift_spans(body, shift);
+        }
+        Expression::Apply(Apply { function, argument }) => {
+            shift_spans(function, shift);
+            shift_spans(argument, shift);
+        }
+        Expression::Assign(Assign { value, inner, .. }) => {
+            shift_spans(value, shift);
+            shift_spans(inner, shift);
+        }
+        Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+            for (_, value) in bindings {
+                shift_spans(value, shift);
+            }
+            shift_spans(inner, shift);
+        }
+        Expression::Match(Match { value, patterns }) => {
+            shift_spans(value, shift);
+            for pattern in patterns {
+                pattern.pattern_span = shift(pattern.pattern_span);
+                shift_spans(&mut pattern.result, shift);
+            }
+        }
+        Expression::Infix(Infix {
+            operator_span,
+            left,
+            right,
+            ..
+        }) => {
+            *operator_span = shift(*operator_span);
+            shift_spans(left, shift);
+            shift_spans(right, shift);
+        }
+        Expression::Typed(Typed {
+            expression,
+            typ_span,
+            ..
+        }) => {
+            shift_spans(expression, shift);
+            *typ_span = shift(*typ_span);
+        }
+        Expression::Sequence(Sequence { first, second }) => {
+            shift_spans(first, shift);
+            shift_spans(second, shift);
+        }
+        Expression::Tuple(Tuple { elements }) | Expression::List(List { elements }) => {
+            for element in elements {
+                shift_spans(element, shift);
+            }
+        }
+    }
+}
@@ -7,49 +7,198 @@ use boo_core::identifier::*;
 use boo_core::primitive::*;
 use boo_core::span::*;
 
+/// The reasons the lexer can fail to produce a token.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum LexError {
+    /// The input does not form a valid token.
+    #[default]
+    UnexpectedToken,
+    /// A string or character literal contains an invalid escape sequence at
+    /// the given location.
+    InvalidEscapeSequence(Span),
+    /// An integer literal contains a character at the given location that is
+    /// not a digit in the given radix.
+    InvalidDigit(Span, u32),
+}
+
 /// The set of tokens generated by the lexer.
 #[derive(Debug, Clone, PartialEq, Eq, Logos)]
+#[logos(error = LexError)]
 #[logos(skip r"[ \t\n\f]+")]
+#[logos(skip r"#[^\n]*")]
 pub enum Token<'a> {
     #[token(r"(")]
     StartGroup,
     #[token(r")")]
     EndGroup,
+    #[token(r"[")]
+    StartList,
+    #[token(r"]")]
+    EndList,
     #[token(r"{")]
     BlockStart,
     #[token(r"}")]
     BlockEnd,
     #[token(r";")]
     Separator,
+    #[token(r",")]
+    Comma,
     #[token(r"_")]
     Anything,
+    #[token(r"def")]
+    Def,
     #[token(r"let")]
     Let,
     #[token(r"in")]
     In,
+    #[token(r"rec")]
+    Rec,
+    #[token(r"and")]
+    And,
+    #[token(r"try")]
+    Try,
     #[token(r"fn")]
     Fn,
     #[token(r"match")]
     Match,
+    #[token(r"true", |_| true)]
+    #[token(r"false", |_| false)]
+    Boolean(bool),
     #[token(r"->")]
     Arrow,
     #[token(r"=")]
     Assign,
     #[token(r":")]
     Annotate,
-    #[regex(r"-?[0-9](_?[0-9])*", |token|
+    #[token(r".")]
+    Dot,
+    #[regex(r"[0-9](_?[0-9])*", |token|
         str::replace(token.slice(), "_", "").parse::<Integer>().ok()
     )]
+    // these match any trailing letters or digits; the callback rejects invalid ones
+    #[regex(r"0x[_\p{Number}\p{Letter}]+", |token| lex_integer_with_radix(token, 16))]
+    #[regex(r"0o[_\p{Number}\p{Letter}]+", |token| lex_integer_with_radix(token, 8))]
+    #[regex(r"0b[_\p{Number}\p{Letter}]+", |token| lex_integer_with_radix(token, 2))]
     Integer(Integer),
-    #[regex(r"\+|\-|\*")]
+    #[regex(r"[0-9](_?[0-9])*(\.[0-9](_?[0-9])*([eE][+-]?[0-9]+)?|[eE][+-]?[0-9]+)", |token|
+        str::replace(token.slice(), "_", "").parse::<Float>().ok()
+    )]
+    Float(Float),
+    // this matches any number of characters; the callback rejects anything but one
+    #[regex(r"'([^'\\]|\\.)+'", |token| {
+        let unescaped = unescape_literal(token)?;
+        let mut chars = unescaped.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(LexError::UnexpectedToken),
+        }
+    })]
+    Char(char),
+    #[regex(r#""([^"\\]|\\.)*""#, unescape_literal)]
+    String(String),
+    #[regex(r"\+\+|\+\.?|\-\.?|\*\.?|/\.?|%|&|\||<<|>>|==|!=|<=?|>=?|::")]
+    #[token(r"xor")]
     Operator(&'a str),
     // note that the following regex is duplicated from identifier.rs
     #[regex(r"[_\p{Letter}][_\p{Number}\p{Letter}]*", |token|
-        Identifier::name_from_str(token.slice()).map_err(|_| ())
+        Identifier::name_from_str(token.slice()).ok()
     )]
     Identifier(Identifier),
 }
 
+/// Parses an integer literal with a two-character radix prefix, such as
+/// `0xff`.
+///
+/// Fails at the first character that is not a digit in the given radix, or an
+/// underscore between two digits.
+fn lex_integer_with_radix<'a>(
+    token: &mut logos::Lexer<'a, Token<'a>>,
+    radix: u32,
+) -> std::result::Result<Integer, LexError> {
+    let digits = &token.slice()[2..];
+    let start = token.span().start + 2;
+    let mut chars = digits.char_indices().peekable();
+    let mut after_digit = false;
+    while let Some((index, c)) = chars.next() {
+        let valid = if c == '_' {
+            after_digit && chars.peek().map_or(false, |(_, next)| next.is_digit(radix))
+        } else {
+            c.is_digit(radix)
+        };
+        if !valid {
+            let span = (start + index..start + index + c.len_utf8()).into();
+            return Err(LexError::InvalidDigit(span, radix));
+        }
+        after_digit = c != '_';
+    }
+    Integer::from_str_radix(&digits.replace('_', ""), radix).ok_or(LexError::UnexpectedToken)
+}
+
+/// Replaces escape sequences in the contents of a string or character literal
+/// (i.e. without the surrounding quotes) with the characters they represent.
+fn unescape_literal<'a>(
+    token: &mut logos::Lexer<'a, Token<'a>>,
+) -> std::result::Result<String, LexError> {
+    let slice = token.slice();
+    unescape(&slice[1..slice.len() - 1], token.span().start + 1)
+}
+
+/// Replaces escape sequences in the input with the characters they represent.
+///
+/// The supported escape sequences are `\"`, `\'`, `\\`, `\n`, `\r`, `\t`, and
+/// `\u{...}`, which contains between one and six hexadecimal digits
+/// representing a Unicode scalar value.
+///
+/// If there is an invalid escape sequence, returns its span, offset by the
+/// given start position.
+fn unescape(input: &str, start: usize) -> std::result::Result<String, LexError> {
+    let mut result = String::with_capacity(input.len());
+    let mut chars = input.char_indices().peekable();
+    while let Some((escape_start, c)) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        let invalid = |escape_end: usize| {
+            LexError::InvalidEscapeSequence((start + escape_start..start + escape_end).into())
+        };
+        match chars.next() {
+            Some((_, '"')) => result.push('"'),
+            Some((_, '\'')) => result.push('\''),
+            Some((_, '\\')) => result.push('\\'),
+            Some((_, 'n')) => result.push('\n'),
+            Some((_, 'r')) => result.push('\r'),
+            Some((_, 't')) => result.push('\t'),
+            Some((index, 'u')) => {
+                let mut escape_end = index + 1;
+                if chars.next_if(|(_, c)| *c == '{').is_none() {
+                    return Err(invalid(escape_end));
+                }
+                escape_end += 1;
+                let mut digits = String::new();
+                while let Some((index, digit)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+                    digits.push(digit);
+                    escape_end = index + 1;
+                }
+                let Some((index, _)) = chars.next_if(|(_, c)| *c == '}') else {
+                    return Err(invalid(escape_end));
+                };
+                escape_end = index + 1;
+                let value = (1..=6)
+                    .contains(&digits.len())
+                    .then(|| u32::from_str_radix(&digits, 16).ok())
+                    .flatten()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| invalid(escape_end))?;
+                result.push(value);
+            }
+            Some((index, other)) => return Err(invalid(index + other.len_utf8())),
+            None => return Err(invalid(input.len())),
+        }
+    }
+    Ok(result)
+}
+
 /// A wrapper around a token that provides a specific annotation.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AnnotatedToken<'a, Annotation> {
@@ -57,22 +206,89 @@ pub struct AnnotatedToken<'a, Annotation> {
     pub token: Token<'a>,
 }
 
+impl<'a> Token<'a> {
+    /// Re-creates the token as if it were lexed from the given input, at the
+    /// given span. The input must contain the same text at that span.
+    pub fn rebase<'b>(&self, input: &'b str, span: Span) -> Token<'b> {
+        match self {
+            Token::StartGroup => Token::StartGroup,
+            Token::EndGroup => Token::EndGroup,
+            Token::StartList => Token::StartList,
+            Token::EndList => Token::EndList,
+            Token::BlockStart => Token::BlockStart,
+            Token::BlockEnd => Token::BlockEnd,
+            Token::Separator => Token::Separator,
+            Token::Comma => Token::Comma,
+            Token::Anything => Token::Anything,
+            Token::Def => Token::Def,
+            Token::Let => Token::Let,
+            Token::In => Token::In,
+            Token::Rec => Token::Rec,
+            Token::And => Token::And,
+            Token::Try => Token::Try,
+            Token::Fn => Token::Fn,
+            Token::Match => Token::Match,
+            Token::Boolean(value) => Token::Boolean(*value),
+            Token::Arrow => Token::Arrow,
+            Token::Assign => Token::Assign,
+            Token::Annotate => Token::Annotate,
+            Token::Dot => Token::Dot,
+            Token::Integer(value) => Token::Integer(value.clone()),
+            Token::Float(value) => Token::Float(*value),
+            Token::Char(value) => Token::Char(*value),
+            Token::String(value) => Token::String(value.clone()),
+            Token::Operator(_) => Token::Operator(&input[span.range()]),
+            Token::Identifier(value) => Token::Identifier(value.clone()),
+        }
+    }
+}
+
 /// Lexes the input and produces a vector of tokens, annotated with their spans,
 /// or an error.
 pub fn lex(input: &str) -> Result<Vec<AnnotatedToken<Span>>> {
-    Token::lexer(input)
-        .spanned()
-        .map(move |(token, span)| {
-            let span: Span = span.into();
-            token
-                .map(|value| AnnotatedToken {
-                    annotation: span,
-                    token: value,
-                })
-                .map_err(|_| Error::UnexpectedToken {
+    tokens(input).collect()
+}
+
+/// Lexes the input lazily, producing each token, annotated with its span, or
+/// an error, as it is reached.
+///
+/// Unlike [`lex`], this does not need to hold every token at once. Lexing can
+/// continue after an error, though the error may have split a token in two.
+pub fn tokens(input: &str) -> impl Iterator<Item = Result<AnnotatedToken<Span>>> + '_ {
+    lex_from(input, 0)
+}
+
+/// Lexes the input, starting at the given position, and produces each token,
+/// annotated with its span, or an error.
+///
+/// Spans are relative to the start of the whole input, not the given position.
+pub fn lex_from(
+    input: &str,
+    start: usize,
+) -> impl Iterator<Item = Result<AnnotatedToken<Span>>> + '_ {
+    let mut lexer = Token::lexer(input);
+    lexer.bump(start);
+    lexer.spanned().map(move |(token, span)| {
+        let span: Span = span.into();
+        token
+            .map(|value| AnnotatedToken {
+                annotation: span,
+                token: value,
+            })
+            .map_err(|error| match error {
+                LexError::UnexpectedToken => Error::UnexpectedToken {
                     span,
                     token: input[span.range()].to_string(),
-                })
-        })
-        .collect()
+                },
+                LexError::InvalidEscapeSequence(span) => Error::InvalidEscapeSequence {
+                    span,
+                    sequence: input[span.range()].to_string(),
+                },
+                LexError::InvalidDigit(span, radix) => Error::InvalidDigit {
+                    span,
+                    digit: input[span.range()].to_string(),
+                    radix,
+                },
+            })
+    })
 }
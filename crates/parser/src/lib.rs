@@ -1,24 +1,43 @@
 //! Transforms an input string into an evaluatable program.
 
+pub mod incremental;
 pub mod lexer;
 pub mod parser;
+pub mod precedence;
 
 use boo_core::error::Result;
-use boo_language::Expr;
+use boo_language::{Expr, Program};
 
-pub fn parse(input: &str) -> Result<Expr> {
+/// Parses a single expression.
+pub fn parse_expr(input: &str) -> Result<Expr> {
     let tokens = lexer::lex(input)?;
-    parser::parse_tokens(&tokens)
+    parser::parse_expr_tokens(&tokens)
+}
+
+/// Parses a whole program: a sequence of top-level definitions, each
+/// terminated by `;`, followed by the main expression.
+///
+/// The main expression may be omitted, in which case the program evaluates to
+/// its last definition, and that definition need not be terminated. This means
+/// that `let x = 3` is a valid program.
+pub fn parse_program(input: &str) -> Result<Program> {
+    let tokens = lexer::lex(input)?;
+    parser::parse_program_tokens(&tokens)
 }
 
 #[cfg(test)]
 mod tests {
+    use boo_core::error::{Error, ParseHint};
+    use boo_core::identifier::Identifier;
+    use boo_core::primitive::Primitive;
+    use boo_language::{Apply, Expression, Infix, Operation};
+
     use super::*;
 
     #[test]
     fn test_parsing_nothing() {
         let input = "";
-        let parsed = parse(input);
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Err(
@@ -29,12 +48,23 @@ mod tests {
                 },
                 expected_tokens: [
                     "'('",
+                    "'-'",
+                    "'-.'",
+                    "'['",
+                    "a boolean",
+                    "a character",
+                    "a float",
+                    "a string",
                     "an identifier",
                     "an integer",
                     "fn",
                     "let",
                     "match",
+                    "try",
                 ],
+                context: None,
+                context_span: None,
+                hint: None,
             },
         )
         "###);
@@ -43,7 +73,7 @@ mod tests {
     #[test]
     fn test_parsing_an_integer() {
         let input = "123";
-        let parsed = parse(input);
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
@@ -67,7 +97,7 @@ mod tests {
     #[test]
     fn test_parsing_a_negative_integer() {
         let input = "-456";
-        let parsed = parse(input);
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
@@ -91,7 +121,7 @@ mod tests {
     #[test]
     fn test_parsing_an_integer_with_underscores() {
         let input = "987_654_321";
-        let parsed = parse(input);
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
@@ -112,10 +142,271 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_parsing_integers_in_other_bases() {
+        let input = "[0xFF, 0o17, -0b1010_1010]";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 26,
+                },
+                expression: List(
+                    List {
+                        elements: [
+                            Expr {
+                                span: Span {
+                                    start: 1,
+                                    end: 5,
+                                },
+                                expression: Primitive(
+                                    Integer(
+                                        Small(
+                                            255,
+                                        ),
+                                    ),
+                                ),
+                            },
+                            Expr {
+                                span: Span {
+                                    start: 7,
+                                    end: 11,
+                                },
+                                expression: Primitive(
+                                    Integer(
+                                        Small(
+                                            15,
+                                        ),
+                                    ),
+                                ),
+                            },
+                            Expr {
+                                span: Span {
+                                    start: 13,
+                                    end: 25,
+                                },
+                                expression: Primitive(
+                                    Integer(
+                                        Small(
+                                            -170,
+                                        ),
+                                    ),
+                                ),
+                            },
+                        ],
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_rejects_invalid_digits_in_other_bases() {
+        for (input, start, radix) in [
+            ("0b102", 4, 2),
+            ("0o9", 2, 8),
+            ("0xg", 2, 16),
+            ("0b1__0", 3, 2),
+        ] {
+            let parsed = parse_expr(input);
+
+            assert_eq!(
+                parsed,
+                Err(Error::InvalidDigit {
+                    span: (start..start + 1).into(),
+                    digit: input[start..start + 1].to_string(),
+                    radix,
+                }),
+                "input: {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_parsing_floats() {
+        let input = "1.5 +. -2e10";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 12,
+                },
+                expression: Infix(
+                    Infix {
+                        operation: AddFloat,
+                        operator_span: Span {
+                            start: 4,
+                            end: 6,
+                        },
+                        left: Expr {
+                            span: Span {
+                                start: 0,
+                                end: 3,
+                            },
+                            expression: Primitive(
+                                Float(
+                                    Float(
+                                        1.5,
+                                    ),
+                                ),
+                            ),
+                        },
+                        right: Expr {
+                            span: Span {
+                                start: 7,
+                                end: 12,
+                            },
+                            expression: Primitive(
+                                Float(
+                                    Float(
+                                        -20000000000.0,
+                                    ),
+                                ),
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_booleans() {
+        let input = "true";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 4,
+                },
+                expression: Primitive(
+                    Boolean(
+                        true,
+                    ),
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_characters() {
+        let input = r"'\''";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 4,
+                },
+                expression: Primitive(
+                    Char(
+                        '\'',
+                    ),
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_strings() {
+        let input = r#""Hello, \"world\"!\n""#;
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 21,
+                },
+                expression: Primitive(
+                    String(
+                        "Hello, \"world\"!\n",
+                    ),
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_unicode_escape_sequences() {
+        let input = r#""\u{48}\u{e9}\u{1F600}""#;
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 23,
+                },
+                expression: Primitive(
+                    String(
+                        "Hé😀",
+                    ),
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_rejects_invalid_escape_sequences() {
+        let input = r#""\q""#;
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Err(
+            InvalidEscapeSequence {
+                span: Span {
+                    start: 1,
+                    end: 3,
+                },
+                sequence: "\\q",
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_rejects_invalid_unicode_escape_sequences() {
+        let input = r#""abc\u{110000}def""#;
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Err(
+            InvalidEscapeSequence {
+                span: Span {
+                    start: 4,
+                    end: 14,
+                },
+                sequence: "\\u{110000}",
+            },
+        )
+        "###);
+    }
+
     #[test]
     fn test_parsing_operators() {
         let input = "1 + 2 - 3 * 4";
-        let parsed = parse(input);
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
@@ -127,6 +418,10 @@ mod tests {
                 expression: Infix(
                     Infix {
                         operation: Subtract,
+                        operator_span: Span {
+                            start: 6,
+                            end: 7,
+                        },
                         left: Expr {
                             span: Span {
                                 start: 0,
@@ -135,6 +430,10 @@ mod tests {
                             expression: Infix(
                                 Infix {
                                     operation: Add,
+                                    operator_span: Span {
+                                        start: 2,
+                                        end: 3,
+                                    },
                                     left: Expr {
                                         span: Span {
                                             start: 0,
@@ -172,6 +471,10 @@ mod tests {
                             expression: Infix(
                                 Infix {
                                     operation: Multiply,
+                                    operator_span: Span {
+                                        start: 10,
+                                        end: 11,
+                                    },
                                     left: Expr {
                                         span: Span {
                                             start: 8,
@@ -209,73 +512,126 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_parentheses() {
-        let input = "1 * (2 + 3) - 4";
-        let parsed = parse(input);
+    fn test_parsing_bitwise_operators() {
+        let input = "1 | 2 xor 3 & 4 << 5";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
                     start: 0,
-                    end: 15,
+                    end: 20,
                 },
                 expression: Infix(
                     Infix {
-                        operation: Subtract,
+                        operation: BitwiseOr,
+                        operator_span: Span {
+                            start: 2,
+                            end: 3,
+                        },
                         left: Expr {
                             span: Span {
                                 start: 0,
-                                end: 10,
+                                end: 1,
+                            },
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        1,
+                                    ),
+                                ),
+                            ),
+                        },
+                        right: Expr {
+                            span: Span {
+                                start: 4,
+                                end: 20,
                             },
                             expression: Infix(
                                 Infix {
-                                    operation: Multiply,
+                                    operation: BitwiseXor,
+                                    operator_span: Span {
+                                        start: 6,
+                                        end: 9,
+                                    },
                                     left: Expr {
                                         span: Span {
-                                            start: 0,
-                                            end: 1,
+                                            start: 4,
+                                            end: 5,
                                         },
                                         expression: Primitive(
                                             Integer(
                                                 Small(
-                                                    1,
+                                                    2,
                                                 ),
                                             ),
                                         ),
                                     },
                                     right: Expr {
                                         span: Span {
-                                            start: 5,
-                                            end: 10,
+                                            start: 10,
+                                            end: 20,
                                         },
                                         expression: Infix(
                                             Infix {
-                                                operation: Add,
+                                                operation: BitwiseAnd,
+                                                operator_span: Span {
+                                                    start: 12,
+                                                    end: 13,
+                                                },
                                                 left: Expr {
                                                     span: Span {
-                                                        start: 5,
-                                                        end: 6,
+                                                        start: 10,
+                                                        end: 11,
                                                     },
                                                     expression: Primitive(
                                                         Integer(
                                                             Small(
-                                                                2,
+                                                                3,
                                                             ),
                                                         ),
                                                     ),
                                                 },
                                                 right: Expr {
                                                     span: Span {
-                                                        start: 9,
-                                                        end: 10,
+                                                        start: 14,
+                                                        end: 20,
                                                     },
-                                                    expression: Primitive(
-                                                        Integer(
-                                                            Small(
-                                                                3,
-                                                            ),
-                                                        ),
+                                                    expression: Infix(
+                                                        Infix {
+                                                            operation: ShiftLeft,
+                                                            operator_span: Span {
+                                                                start: 16,
+                                                                end: 18,
+                                                            },
+                                                            left: Expr {
+                                                                span: Span {
+                                                                    start: 14,
+                                                                    end: 15,
+                                                                },
+                                                                expression: Primitive(
+                                                                    Integer(
+                                                                        Small(
+                                                                            4,
+                                                                        ),
+                                                                    ),
+                                                                ),
+                                                            },
+                                                            right: Expr {
+                                                                span: Span {
+                                                                    start: 19,
+                                                                    end: 20,
+                                                                },
+                                                                expression: Primitive(
+                                                                    Integer(
+                                                                        Small(
+                                                                            5,
+                                                                        ),
+                                                                    ),
+                                                                ),
+                                                            },
+                                                        },
                                                     ),
                                                 },
                                             },
@@ -284,19 +640,6 @@ mod tests {
                                 },
                             ),
                         },
-                        right: Expr {
-                            span: Span {
-                                start: 14,
-                                end: 15,
-                            },
-                            expression: Primitive(
-                                Integer(
-                                    Small(
-                                        4,
-                                    ),
-                                ),
-                            ),
-                        },
                     },
                 ),
             },
@@ -305,66 +648,75 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_variable_assignment() {
-        let input = "let thing = 9";
-        let parsed = parse(input);
-
-        insta::assert_debug_snapshot!(parsed, @r###"
-        Err(
-            ParseError {
-                span: Span {
-                    start: 13,
-                    end: 13,
-                },
-                expected_tokens: [
-                    "'('",
-                    "'*'",
-                    "'+'",
-                    "'-'",
-                    "':'",
-                    "an identifier",
-                    "an integer",
-                    "in",
-                ],
-            },
-        )
-        "###);
-    }
-
-    #[test]
-    fn test_parsing_variable_use() {
-        let input = "foo + bar";
-        let parsed = parse(input);
+    fn test_parsing_comparisons() {
+        let input = "1 + 1 == 2";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
                     start: 0,
-                    end: 9,
+                    end: 10,
                 },
                 expression: Infix(
                     Infix {
-                        operation: Add,
+                        operation: Equal,
+                        operator_span: Span {
+                            start: 6,
+                            end: 8,
+                        },
                         left: Expr {
                             span: Span {
                                 start: 0,
-                                end: 3,
+                                end: 5,
                             },
-                            expression: Identifier(
-                                Name(
-                                    "foo",
-                                ),
+                            expression: Infix(
+                                Infix {
+                                    operation: Add,
+                                    operator_span: Span {
+                                        start: 2,
+                                        end: 3,
+                                    },
+                                    left: Expr {
+                                        span: Span {
+                                            start: 0,
+                                            end: 1,
+                                        },
+                                        expression: Primitive(
+                                            Integer(
+                                                Small(
+                                                    1,
+                                                ),
+                                            ),
+                                        ),
+                                    },
+                                    right: Expr {
+                                        span: Span {
+                                            start: 4,
+                                            end: 5,
+                                        },
+                                        expression: Primitive(
+                                            Integer(
+                                                Small(
+                                                    1,
+                                                ),
+                                            ),
+                                        ),
+                                    },
+                                },
                             ),
                         },
                         right: Expr {
                             span: Span {
-                                start: 6,
-                                end: 9,
+                                start: 9,
+                                end: 10,
                             },
-                            expression: Identifier(
-                                Name(
-                                    "bar",
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        2,
+                                    ),
                                 ),
                             ),
                         },
@@ -376,85 +728,84 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_variable_assignment_and_use() {
-        let input = "let price = 3 in let quantity = 5 in price * quantity";
-        let parsed = parse(input);
+    fn test_parsing_parentheses() {
+        let input = "1 * (2 + 3) - 4";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
                     start: 0,
-                    end: 53,
+                    end: 15,
                 },
-                expression: Assign(
-                    Assign {
-                        name: Name(
-                            "price",
-                        ),
-                        value: Expr {
-                            span: Span {
-                                start: 12,
-                                end: 13,
-                            },
-                            expression: Primitive(
-                                Integer(
-                                    Small(
-                                        3,
-                                    ),
-                                ),
-                            ),
+                expression: Infix(
+                    Infix {
+                        operation: Subtract,
+                        operator_span: Span {
+                            start: 12,
+                            end: 13,
                         },
-                        inner: Expr {
+                        left: Expr {
                             span: Span {
-                                start: 17,
-                                end: 53,
+                                start: 0,
+                                end: 10,
                             },
-                            expression: Assign(
-                                Assign {
-                                    name: Name(
-                                        "quantity",
-                                    ),
-                                    value: Expr {
+                            expression: Infix(
+                                Infix {
+                                    operation: Multiply,
+                                    operator_span: Span {
+                                        start: 2,
+                                        end: 3,
+                                    },
+                                    left: Expr {
                                         span: Span {
-                                            start: 32,
-                                            end: 33,
+                                            start: 0,
+                                            end: 1,
                                         },
                                         expression: Primitive(
                                             Integer(
                                                 Small(
-                                                    5,
+                                                    1,
                                                 ),
                                             ),
                                         ),
                                     },
-                                    inner: Expr {
+                                    right: Expr {
                                         span: Span {
-                                            start: 37,
-                                            end: 53,
+                                            start: 5,
+                                            end: 10,
                                         },
                                         expression: Infix(
                                             Infix {
-                                                operation: Multiply,
+                                                operation: Add,
+                                                operator_span: Span {
+                                                    start: 7,
+                                                    end: 8,
+                                                },
                                                 left: Expr {
                                                     span: Span {
-                                                        start: 37,
-                                                        end: 42,
+                                                        start: 5,
+                                                        end: 6,
                                                     },
-                                                    expression: Identifier(
-                                                        Name(
-                                                            "price",
+                                                    expression: Primitive(
+                                                        Integer(
+                                                            Small(
+                                                                2,
+                                                            ),
                                                         ),
                                                     ),
                                                 },
                                                 right: Expr {
                                                     span: Span {
-                                                        start: 45,
-                                                        end: 53,
+                                                        start: 9,
+                                                        end: 10,
                                                     },
-                                                    expression: Identifier(
-                                                        Name(
-                                                            "quantity",
+                                                    expression: Primitive(
+                                                        Integer(
+                                                            Small(
+                                                                3,
+                                                            ),
                                                         ),
                                                     ),
                                                 },
@@ -464,6 +815,19 @@ mod tests {
                                 },
                             ),
                         },
+                        right: Expr {
+                            span: Span {
+                                start: 14,
+                                end: 15,
+                            },
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        4,
+                                    ),
+                                ),
+                            ),
+                        },
                     },
                 ),
             },
@@ -472,53 +836,70 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_a_function() {
-        let input = "fn x -> x + 1";
-        let parsed = parse(input);
+    fn test_parsing_negation() {
+        let input = "-(1 + x)";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
                     start: 0,
-                    end: 13,
+                    end: 7,
                 },
-                expression: Function(
-                    Function {
-                        parameters: [
-                            Name(
-                                "x",
+                expression: Infix(
+                    Infix {
+                        operation: Subtract,
+                        operator_span: Span {
+                            start: 0,
+                            end: 1,
+                        },
+                        left: Expr {
+                            span: Span {
+                                start: 0,
+                                end: 1,
+                            },
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        0,
+                                    ),
+                                ),
                             ),
-                        ],
-                        body: Expr {
+                        },
+                        right: Expr {
                             span: Span {
-                                start: 8,
-                                end: 13,
+                                start: 2,
+                                end: 7,
                             },
                             expression: Infix(
                                 Infix {
                                     operation: Add,
+                                    operator_span: Span {
+                                        start: 4,
+                                        end: 5,
+                                    },
                                     left: Expr {
                                         span: Span {
-                                            start: 8,
-                                            end: 9,
+                                            start: 2,
+                                            end: 3,
                                         },
-                                        expression: Identifier(
-                                            Name(
-                                                "x",
+                                        expression: Primitive(
+                                            Integer(
+                                                Small(
+                                                    1,
+                                                ),
                                             ),
                                         ),
                                     },
                                     right: Expr {
                                         span: Span {
-                                            start: 12,
-                                            end: 13,
+                                            start: 6,
+                                            end: 7,
                                         },
-                                        expression: Primitive(
-                                            Integer(
-                                                Small(
-                                                    1,
-                                                ),
+                                        expression: Identifier(
+                                            Name(
+                                                "x",
                                             ),
                                         ),
                                     },
@@ -533,60 +914,1806 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_a_function_with_multiple_arguments() {
-        let input = "fn x y -> x * y";
-        let parsed = parse(input);
+    fn test_parsing_subtraction_of_a_literal() {
+        let expected = Expr::new(
+            0.into(),
+            Expression::Infix(Infix {
+                operation: Operation::Subtract,
+                operator_span: 0.into(),
+                left: Expr::new(
+                    0.into(),
+                    Expression::Identifier(Identifier::name_from_str("x").unwrap()),
+                ),
+                right: Expr::new(
+                    0.into(),
+                    Expression::Primitive(Primitive::Integer(2.into())),
+                ),
+            }),
+        );
+        for input in ["x-2", "x - 2", "x -2"] {
+            let parsed = parse_expr(input).unwrap().without_spans();
+            assert_eq!(parsed, expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parsing_a_negative_argument_requires_parentheses() {
+        let function = Expr::new(
+            0.into(),
+            Expression::Identifier(Identifier::name_from_str("f").unwrap()),
+        );
+        let integer = |n: i64| {
+            Expr::new(
+                0.into(),
+                Expression::Primitive(Primitive::Integer(n.into())),
+            )
+        };
+
+        let subtraction = parse_expr("f -5").unwrap().without_spans();
+        let application = parse_expr("f (-5)").unwrap().without_spans();
+
+        assert_eq!(
+            subtraction,
+            Expr::new(
+                0.into(),
+                Expression::Infix(Infix {
+                    operation: Operation::Subtract,
+                    operator_span: 0.into(),
+                    left: function.clone(),
+                    right: integer(5),
+                }),
+            )
+        );
+        assert_eq!(
+            application,
+            Expr::new(
+                0.into(),
+                Expression::Apply(Apply {
+                    function,
+                    argument: integer(-5),
+                }),
+            )
+        );
+    }
+
+    #[test]
+    fn test_parsing_tuples() {
+        let input = "(1, x)";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
                     start: 0,
-                    end: 15,
+                    end: 6,
                 },
-                expression: Function(
-                    Function {
-                        parameters: [
-                            Name(
-                                "x",
-                            ),
-                            Name(
-                                "y",
-                            ),
-                        ],
-                        body: Expr {
-                            span: Span {
-                                start: 10,
-                                end: 15,
+                expression: Tuple(
+                    Tuple {
+                        elements: [
+                            Expr {
+                                span: Span {
+                                    start: 1,
+                                    end: 2,
+                                },
+                                expression: Primitive(
+                                    Integer(
+                                        Small(
+                                            1,
+                                        ),
+                                    ),
+                                ),
                             },
-                            expression: Infix(
-                                Infix {
-                                    operation: Multiply,
-                                    left: Expr {
-                                        span: Span {
+                            Expr {
+                                span: Span {
+                                    start: 4,
+                                    end: 5,
+                                },
+                                expression: Identifier(
+                                    Name(
+                                        "x",
+                                    ),
+                                ),
+                            },
+                        ],
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_a_sequence() {
+        let input = "(x; y; z)";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 9,
+                },
+                expression: Sequence(
+                    Sequence {
+                        first: Expr {
+                            span: Span {
+                                start: 1,
+                                end: 2,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "x",
+                                ),
+                            ),
+                        },
+                        second: Expr {
+                            span: Span {
+                                start: 4,
+                                end: 8,
+                            },
+                            expression: Sequence(
+                                Sequence {
+                                    first: Expr {
+                                        span: Span {
+                                            start: 4,
+                                            end: 5,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "y",
+                                            ),
+                                        ),
+                                    },
+                                    second: Expr {
+                                        span: Span {
+                                            start: 7,
+                                            end: 8,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "z",
+                                            ),
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_rejects_a_mix_of_tuple_and_sequence() {
+        let input = "(x, y; z)";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Err(
+            ParseError {
+                span: Span {
+                    start: 9,
+                    end: 9,
+                },
+                expected_tokens: [
+                    "a tuple or a sequence, but not both",
+                ],
+                context: None,
+                context_span: None,
+                hint: None,
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_lists() {
+        let input = "[1, x]";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 6,
+                },
+                expression: List(
+                    List {
+                        elements: [
+                            Expr {
+                                span: Span {
+                                    start: 1,
+                                    end: 2,
+                                },
+                                expression: Primitive(
+                                    Integer(
+                                        Small(
+                                            1,
+                                        ),
+                                    ),
+                                ),
+                            },
+                            Expr {
+                                span: Span {
+                                    start: 4,
+                                    end: 5,
+                                },
+                                expression: Identifier(
+                                    Name(
+                                        "x",
+                                    ),
+                                ),
+                            },
+                        ],
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_cons_is_right_associative() {
+        let input = "1 :: 2 :: []";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 12,
+                },
+                expression: Infix(
+                    Infix {
+                        operation: Cons,
+                        operator_span: Span {
+                            start: 2,
+                            end: 4,
+                        },
+                        left: Expr {
+                            span: Span {
+                                start: 0,
+                                end: 1,
+                            },
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        1,
+                                    ),
+                                ),
+                            ),
+                        },
+                        right: Expr {
+                            span: Span {
+                                start: 5,
+                                end: 12,
+                            },
+                            expression: Infix(
+                                Infix {
+                                    operation: Cons,
+                                    operator_span: Span {
+                                        start: 7,
+                                        end: 9,
+                                    },
+                                    left: Expr {
+                                        span: Span {
+                                            start: 5,
+                                            end: 6,
+                                        },
+                                        expression: Primitive(
+                                            Integer(
+                                                Small(
+                                                    2,
+                                                ),
+                                            ),
+                                        ),
+                                    },
+                                    right: Expr {
+                                        span: Span {
                                             start: 10,
-                                            end: 11,
+                                            end: 12,
+                                        },
+                                        expression: List(
+                                            List {
+                                                elements: [],
+                                            },
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_variable_assignment() {
+        let input = "let thing = 9";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Err(
+            ParseError {
+                span: Span {
+                    start: 13,
+                    end: 13,
+                },
+                expected_tokens: [
+                    "'!='",
+                    "'%'",
+                    "'&'",
+                    "'('",
+                    "'*'",
+                    "'*.'",
+                    "'+'",
+                    "'++'",
+                    "'+.'",
+                    "'-'",
+                    "'-.'",
+                    "'/'",
+                    "'/.'",
+                    "':'",
+                    "'::'",
+                    "'<'",
+                    "'<<'",
+                    "'<='",
+                    "'=='",
+                    "'>'",
+                    "'>='",
+                    "'>>'",
+                    "'['",
+                    "'|'",
+                    "a boolean",
+                    "a character",
+                    "a float",
+                    "a string",
+                    "an identifier",
+                    "an integer",
+                    "in",
+                    "xor",
+                ],
+                context: Some(
+                    ParseContext {
+                        description: "the body of `let thing = ...`",
+                        closing_token: "in",
+                    },
+                ),
+                context_span: Some(
+                    Span {
+                        start: 0,
+                        end: 3,
+                    },
+                ),
+                hint: None,
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parse_errors_describe_the_unfinished_construct() {
+        let cases = [
+            (
+                "(let price = 9)",
+                "Parse error: while parsing the body of `let price = ...`, expected `in`",
+            ),
+            (
+                "def double = fn x -> x * 2\ndef triple = fn x -> x * 3;\ndouble 3",
+                "Parse error: while parsing the definition of `double`, expected `;`",
+            ),
+            (
+                "(1 + 2",
+                "Parse error: while parsing a parenthesized expression, expected `)`",
+            ),
+            (
+                "match x { 1 -> 2",
+                "Parse error: while parsing the patterns of a `match` expression, expected `}`",
+            ),
+            (
+                "(let x = let y = 1 in y)",
+                "Parse error: while parsing the body of `let x = ...`, expected `in`",
+            ),
+        ];
+        for (input, expected_message) in cases {
+            let message = parse_program(input).unwrap_err().to_string();
+            assert_eq!(message, expected_message, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_explain_application_before_an_operator() {
+        let hint = |input: &str| match parse_expr(input) {
+            Err(Error::ParseError { hint, .. }) => hint,
+            result => panic!("expected a parse error for {:?}, got {:?}", input, result),
+        };
+
+        for input in ["f x -", "f x * * 2", "f (g x) + )", "f [1, 2] :: in"] {
+            assert!(
+                matches!(
+                    hint(input),
+                    Some(ParseHint::ApplicationBeforeOperator { .. })
+                ),
+                "input: {:?}",
+                input
+            );
+        }
+        // no application precedes the operator
+        for input in ["x -", "(f x) * * 2", "fn x -> x +"] {
+            assert_eq!(hint(input), None, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_for_keywords_used_as_names() {
+        let cases = [
+            ("let in = 1 in 2", 4..6, "in"),
+            ("def match = 1;\nmatch", 4..9, "match"),
+            ("fn x let -> x", 5..8, "let"),
+            ("let rec f = 1 and fn = 2 in f", 18..20, "fn"),
+        ];
+        for (input, span, keyword) in cases {
+            assert_eq!(
+                parse_program(input),
+                Err(Error::KeywordAsIdentifier {
+                    span: span.into(),
+                    keyword: keyword.to_string(),
+                }),
+                "input: {:?}",
+                input
+            );
+        }
+
+        // a keyword where an expression is expected is not necessarily a name
+        assert!(matches!(
+            parse_program("let x = in 3"),
+            Err(Error::ParseError { .. })
+        ));
+    }
+    #[test]
+    fn test_parsing_variable_use() {
+        let input = "foo + bar";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 9,
+                },
+                expression: Infix(
+                    Infix {
+                        operation: Add,
+                        operator_span: Span {
+                            start: 4,
+                            end: 5,
+                        },
+                        left: Expr {
+                            span: Span {
+                                start: 0,
+                                end: 3,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "foo",
+                                ),
+                            ),
+                        },
+                        right: Expr {
+                            span: Span {
+                                start: 6,
+                                end: 9,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "bar",
+                                ),
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_variable_assignment_and_use() {
+        let input = "let price = 3 in let quantity = 5 in price * quantity";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 53,
+                },
+                expression: Assign(
+                    Assign {
+                        name: Name(
+                            "price",
+                        ),
+                        value: Expr {
+                            span: Span {
+                                start: 12,
+                                end: 13,
+                            },
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        3,
+                                    ),
+                                ),
+                            ),
+                        },
+                        inner: Expr {
+                            span: Span {
+                                start: 17,
+                                end: 53,
+                            },
+                            expression: Assign(
+                                Assign {
+                                    name: Name(
+                                        "quantity",
+                                    ),
+                                    value: Expr {
+                                        span: Span {
+                                            start: 32,
+                                            end: 33,
+                                        },
+                                        expression: Primitive(
+                                            Integer(
+                                                Small(
+                                                    5,
+                                                ),
+                                            ),
+                                        ),
+                                    },
+                                    inner: Expr {
+                                        span: Span {
+                                            start: 37,
+                                            end: 53,
+                                        },
+                                        expression: Infix(
+                                            Infix {
+                                                operation: Multiply,
+                                                operator_span: Span {
+                                                    start: 43,
+                                                    end: 44,
+                                                },
+                                                left: Expr {
+                                                    span: Span {
+                                                        start: 37,
+                                                        end: 42,
+                                                    },
+                                                    expression: Identifier(
+                                                        Name(
+                                                            "price",
+                                                        ),
+                                                    ),
+                                                },
+                                                right: Expr {
+                                                    span: Span {
+                                                        start: 45,
+                                                        end: 53,
+                                                    },
+                                                    expression: Identifier(
+                                                        Name(
+                                                            "quantity",
+                                                        ),
+                                                    ),
+                                                },
+                                            },
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_mutually_recursive_assignment() {
+        let input = "let rec a = b and b = a in a";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 28,
+                },
+                expression: AssignRecursive(
+                    AssignRecursive {
+                        bindings: [
+                            (
+                                Name(
+                                    "a",
+                                ),
+                                Expr {
+                                    span: Span {
+                                        start: 12,
+                                        end: 13,
+                                    },
+                                    expression: Identifier(
+                                        Name(
+                                            "b",
+                                        ),
+                                    ),
+                                },
+                            ),
+                            (
+                                Name(
+                                    "b",
+                                ),
+                                Expr {
+                                    span: Span {
+                                        start: 22,
+                                        end: 23,
+                                    },
+                                    expression: Identifier(
+                                        Name(
+                                            "a",
+                                        ),
+                                    ),
+                                },
+                            ),
+                        ],
+                        inner: Expr {
+                            span: Span {
+                                start: 27,
+                                end: 28,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "a",
+                                ),
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_an_annotated_variable_assignment() {
+        let input = "let x: Integer = 1 in x";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 23,
+                },
+                expression: Assign(
+                    Assign {
+                        name: Name(
+                            "x",
+                        ),
+                        value: Expr {
+                            span: Span {
+                                start: 17,
+                                end: 18,
+                            },
+                            expression: Typed(
+                                Typed {
+                                    expression: Expr {
+                                        span: Span {
+                                            start: 17,
+                                            end: 18,
+                                        },
+                                        expression: Primitive(
+                                            Integer(
+                                                Small(
+                                                    1,
+                                                ),
+                                            ),
+                                        ),
+                                    },
+                                    typ: Monotype(
+                                        Integer,
+                                    ),
+                                    typ_span: Span {
+                                        start: 7,
+                                        end: 14,
+                                    },
+                                },
+                            ),
+                        },
+                        inner: Expr {
+                            span: Span {
+                                start: 22,
+                                end: 23,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "x",
+                                ),
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_a_function() {
+        let input = "fn x -> x + 1";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 13,
+                },
+                expression: Function(
+                    Function {
+                        parameters: [
+                            Parameter {
+                                name: Name(
+                                    "x",
+                                ),
+                                typ: None,
+                                typ_span: None,
+                                span: Span {
+                                    start: 3,
+                                    end: 4,
+                                },
+                            },
+                        ],
+                        body: Expr {
+                            span: Span {
+                                start: 8,
+                                end: 13,
+                            },
+                            expression: Infix(
+                                Infix {
+                                    operation: Add,
+                                    operator_span: Span {
+                                        start: 10,
+                                        end: 11,
+                                    },
+                                    left: Expr {
+                                        span: Span {
+                                            start: 8,
+                                            end: 9,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "x",
+                                            ),
+                                        ),
+                                    },
+                                    right: Expr {
+                                        span: Span {
+                                            start: 12,
+                                            end: 13,
+                                        },
+                                        expression: Primitive(
+                                            Integer(
+                                                Small(
+                                                    1,
+                                                ),
+                                            ),
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_a_function_with_multiple_arguments() {
+        let input = "fn x y -> x * y";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 15,
+                },
+                expression: Function(
+                    Function {
+                        parameters: [
+                            Parameter {
+                                name: Name(
+                                    "x",
+                                ),
+                                typ: None,
+                                typ_span: None,
+                                span: Span {
+                                    start: 3,
+                                    end: 4,
+                                },
+                            },
+                            Parameter {
+                                name: Name(
+                                    "y",
+                                ),
+                                typ: None,
+                                typ_span: None,
+                                span: Span {
+                                    start: 5,
+                                    end: 6,
+                                },
+                            },
+                        ],
+                        body: Expr {
+                            span: Span {
+                                start: 10,
+                                end: 15,
+                            },
+                            expression: Infix(
+                                Infix {
+                                    operation: Multiply,
+                                    operator_span: Span {
+                                        start: 12,
+                                        end: 13,
+                                    },
+                                    left: Expr {
+                                        span: Span {
+                                            start: 10,
+                                            end: 11,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "x",
+                                            ),
+                                        ),
+                                    },
+                                    right: Expr {
+                                        span: Span {
+                                            start: 14,
+                                            end: 15,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "y",
+                                            ),
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_a_function_with_annotated_parameters() {
+        let input = "fn (x: Integer) y -> x";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 22,
+                },
+                expression: Function(
+                    Function {
+                        parameters: [
+                            Parameter {
+                                name: Name(
+                                    "x",
+                                ),
+                                typ: Some(
+                                    Monotype(
+                                        Integer,
+                                    ),
+                                ),
+                                typ_span: Some(
+                                    Span {
+                                        start: 7,
+                                        end: 14,
+                                    },
+                                ),
+                                span: Span {
+                                    start: 3,
+                                    end: 15,
+                                },
+                            },
+                            Parameter {
+                                name: Name(
+                                    "y",
+                                ),
+                                typ: None,
+                                typ_span: None,
+                                span: Span {
+                                    start: 16,
+                                    end: 17,
+                                },
+                            },
+                        ],
+                        body: Expr {
+                            span: Span {
+                                start: 21,
+                                end: 22,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "x",
+                                ),
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_function_application() {
+        let input = "func one two three";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 18,
+                },
+                expression: Apply(
+                    Apply {
+                        function: Expr {
+                            span: Span {
+                                start: 0,
+                                end: 12,
+                            },
+                            expression: Apply(
+                                Apply {
+                                    function: Expr {
+                                        span: Span {
+                                            start: 0,
+                                            end: 8,
+                                        },
+                                        expression: Apply(
+                                            Apply {
+                                                function: Expr {
+                                                    span: Span {
+                                                        start: 0,
+                                                        end: 4,
+                                                    },
+                                                    expression: Identifier(
+                                                        Name(
+                                                            "func",
+                                                        ),
+                                                    ),
+                                                },
+                                                argument: Expr {
+                                                    span: Span {
+                                                        start: 5,
+                                                        end: 8,
+                                                    },
+                                                    expression: Identifier(
+                                                        Name(
+                                                            "one",
+                                                        ),
+                                                    ),
+                                                },
+                                            },
+                                        ),
+                                    },
+                                    argument: Expr {
+                                        span: Span {
+                                            start: 9,
+                                            end: 12,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "two",
+                                            ),
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                        argument: Expr {
+                            span: Span {
+                                start: 13,
+                                end: 18,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "three",
+                                ),
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_inline_function_application() {
+        let input = "(fn argument -> argument + argument) input";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 1,
+                    end: 42,
+                },
+                expression: Apply(
+                    Apply {
+                        function: Expr {
+                            span: Span {
+                                start: 1,
+                                end: 35,
+                            },
+                            expression: Function(
+                                Function {
+                                    parameters: [
+                                        Parameter {
+                                            name: Name(
+                                                "argument",
+                                            ),
+                                            typ: None,
+                                            typ_span: None,
+                                            span: Span {
+                                                start: 4,
+                                                end: 12,
+                                            },
+                                        },
+                                    ],
+                                    body: Expr {
+                                        span: Span {
+                                            start: 16,
+                                            end: 35,
+                                        },
+                                        expression: Infix(
+                                            Infix {
+                                                operation: Add,
+                                                operator_span: Span {
+                                                    start: 25,
+                                                    end: 26,
+                                                },
+                                                left: Expr {
+                                                    span: Span {
+                                                        start: 16,
+                                                        end: 24,
+                                                    },
+                                                    expression: Identifier(
+                                                        Name(
+                                                            "argument",
+                                                        ),
+                                                    ),
+                                                },
+                                                right: Expr {
+                                                    span: Span {
+                                                        start: 27,
+                                                        end: 35,
+                                                    },
+                                                    expression: Identifier(
+                                                        Name(
+                                                            "argument",
+                                                        ),
+                                                    ),
+                                                },
+                                            },
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                        argument: Expr {
+                            span: Span {
+                                start: 37,
+                                end: 42,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "input",
+                                ),
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_assigned_function_application() {
+        let input =
+            "let important_function = fn thing -> (thing + thing) in important_function input";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 80,
+                },
+                expression: Assign(
+                    Assign {
+                        name: Name(
+                            "important_function",
+                        ),
+                        value: Expr {
+                            span: Span {
+                                start: 25,
+                                end: 51,
+                            },
+                            expression: Function(
+                                Function {
+                                    parameters: [
+                                        Parameter {
+                                            name: Name(
+                                                "thing",
+                                            ),
+                                            typ: None,
+                                            typ_span: None,
+                                            span: Span {
+                                                start: 28,
+                                                end: 33,
+                                            },
+                                        },
+                                    ],
+                                    body: Expr {
+                                        span: Span {
+                                            start: 38,
+                                            end: 51,
+                                        },
+                                        expression: Infix(
+                                            Infix {
+                                                operation: Add,
+                                                operator_span: Span {
+                                                    start: 44,
+                                                    end: 45,
+                                                },
+                                                left: Expr {
+                                                    span: Span {
+                                                        start: 38,
+                                                        end: 43,
+                                                    },
+                                                    expression: Identifier(
+                                                        Name(
+                                                            "thing",
+                                                        ),
+                                                    ),
+                                                },
+                                                right: Expr {
+                                                    span: Span {
+                                                        start: 46,
+                                                        end: 51,
+                                                    },
+                                                    expression: Identifier(
+                                                        Name(
+                                                            "thing",
+                                                        ),
+                                                    ),
+                                                },
+                                            },
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                        inner: Expr {
+                            span: Span {
+                                start: 56,
+                                end: 80,
+                            },
+                            expression: Apply(
+                                Apply {
+                                    function: Expr {
+                                        span: Span {
+                                            start: 56,
+                                            end: 74,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "important_function",
+                                            ),
+                                        ),
+                                    },
+                                    argument: Expr {
+                                        span: Span {
+                                            start: 75,
+                                            end: 80,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "input",
+                                            ),
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_function_application_within_infix_operations() {
+        let input = "f left + g right";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 16,
+                },
+                expression: Infix(
+                    Infix {
+                        operation: Add,
+                        operator_span: Span {
+                            start: 7,
+                            end: 8,
+                        },
+                        left: Expr {
+                            span: Span {
+                                start: 0,
+                                end: 6,
+                            },
+                            expression: Apply(
+                                Apply {
+                                    function: Expr {
+                                        span: Span {
+                                            start: 0,
+                                            end: 1,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "f",
+                                            ),
+                                        ),
+                                    },
+                                    argument: Expr {
+                                        span: Span {
+                                            start: 2,
+                                            end: 6,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "left",
+                                            ),
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                        right: Expr {
+                            span: Span {
+                                start: 9,
+                                end: 16,
+                            },
+                            expression: Apply(
+                                Apply {
+                                    function: Expr {
+                                        span: Span {
+                                            start: 9,
+                                            end: 10,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "g",
+                                            ),
+                                        ),
+                                    },
+                                    argument: Expr {
+                                        span: Span {
+                                            start: 11,
+                                            end: 16,
                                         },
                                         expression: Identifier(
+                                            Name(
+                                                "right",
+                                            ),
+                                        ),
+                                    },
+                                },
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_a_match_expression() {
+        let input = "match 2 { 1 -> 2; 2 -> 3; 3 -> 4; _ -> 0 }";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 42,
+                },
+                expression: Match(
+                    Match {
+                        value: Expr {
+                            span: Span {
+                                start: 6,
+                                end: 7,
+                            },
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        2,
+                                    ),
+                                ),
+                            ),
+                        },
+                        patterns: [
+                            PatternMatch {
+                                pattern: Primitive(
+                                    Integer(
+                                        Small(
+                                            1,
+                                        ),
+                                    ),
+                                ),
+                                pattern_span: Span {
+                                    start: 10,
+                                    end: 11,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 15,
+                                        end: 16,
+                                    },
+                                    expression: Primitive(
+                                        Integer(
+                                            Small(
+                                                2,
+                                            ),
+                                        ),
+                                    ),
+                                },
+                            },
+                            PatternMatch {
+                                pattern: Primitive(
+                                    Integer(
+                                        Small(
+                                            2,
+                                        ),
+                                    ),
+                                ),
+                                pattern_span: Span {
+                                    start: 18,
+                                    end: 19,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 23,
+                                        end: 24,
+                                    },
+                                    expression: Primitive(
+                                        Integer(
+                                            Small(
+                                                3,
+                                            ),
+                                        ),
+                                    ),
+                                },
+                            },
+                            PatternMatch {
+                                pattern: Primitive(
+                                    Integer(
+                                        Small(
+                                            3,
+                                        ),
+                                    ),
+                                ),
+                                pattern_span: Span {
+                                    start: 26,
+                                    end: 27,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 31,
+                                        end: 32,
+                                    },
+                                    expression: Primitive(
+                                        Integer(
+                                            Small(
+                                                4,
+                                            ),
+                                        ),
+                                    ),
+                                },
+                            },
+                            PatternMatch {
+                                pattern: Anything,
+                                pattern_span: Span {
+                                    start: 34,
+                                    end: 35,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 39,
+                                        end: 40,
+                                    },
+                                    expression: Primitive(
+                                        Integer(
+                                            Small(
+                                                0,
+                                            ),
+                                        ),
+                                    ),
+                                },
+                            },
+                        ],
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_tuple_patterns() {
+        let input = "match p { (x, _) -> x }";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 23,
+                },
+                expression: Match(
+                    Match {
+                        value: Expr {
+                            span: Span {
+                                start: 6,
+                                end: 7,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "p",
+                                ),
+                            ),
+                        },
+                        patterns: [
+                            PatternMatch {
+                                pattern: Tuple(
+                                    [
+                                        Variable(
+                                            Name(
+                                                "x",
+                                            ),
+                                        ),
+                                        Anything,
+                                    ],
+                                ),
+                                pattern_span: Span {
+                                    start: 10,
+                                    end: 16,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 20,
+                                        end: 21,
+                                    },
+                                    expression: Identifier(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
+                                },
+                            },
+                        ],
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_list_patterns() {
+        let input = "match l { [] -> 0; [x] -> x; x :: _ -> x }";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 42,
+                },
+                expression: Match(
+                    Match {
+                        value: Expr {
+                            span: Span {
+                                start: 6,
+                                end: 7,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "l",
+                                ),
+                            ),
+                        },
+                        patterns: [
+                            PatternMatch {
+                                pattern: List(
+                                    [],
+                                ),
+                                pattern_span: Span {
+                                    start: 10,
+                                    end: 12,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 16,
+                                        end: 17,
+                                    },
+                                    expression: Primitive(
+                                        Integer(
+                                            Small(
+                                                0,
+                                            ),
+                                        ),
+                                    ),
+                                },
+                            },
+                            PatternMatch {
+                                pattern: List(
+                                    [
+                                        Variable(
                                             Name(
                                                 "x",
                                             ),
                                         ),
+                                    ],
+                                ),
+                                pattern_span: Span {
+                                    start: 19,
+                                    end: 22,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 26,
+                                        end: 27,
                                     },
-                                    right: Expr {
-                                        span: Span {
-                                            start: 14,
-                                            end: 15,
-                                        },
-                                        expression: Identifier(
-                                            Name(
-                                                "y",
+                                    expression: Identifier(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
+                                },
+                            },
+                            PatternMatch {
+                                pattern: Cons {
+                                    head: Variable(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
+                                    tail: Anything,
+                                },
+                                pattern_span: Span {
+                                    start: 29,
+                                    end: 35,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 39,
+                                        end: 40,
+                                    },
+                                    expression: Identifier(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
+                                },
+                            },
+                        ],
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_option_patterns() {
+        let input = "match o { none -> 0; some x -> x }";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 34,
+                },
+                expression: Match(
+                    Match {
+                        value: Expr {
+                            span: Span {
+                                start: 6,
+                                end: 7,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "o",
+                                ),
+                            ),
+                        },
+                        patterns: [
+                            PatternMatch {
+                                pattern: None,
+                                pattern_span: Span {
+                                    start: 10,
+                                    end: 14,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 18,
+                                        end: 19,
+                                    },
+                                    expression: Primitive(
+                                        Integer(
+                                            Small(
+                                                0,
                                             ),
                                         ),
+                                    ),
+                                },
+                            },
+                            PatternMatch {
+                                pattern: Some(
+                                    Variable(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
+                                ),
+                                pattern_span: Span {
+                                    start: 21,
+                                    end: 27,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 31,
+                                        end: 32,
                                     },
+                                    expression: Identifier(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
                                 },
+                            },
+                        ],
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_result_patterns() {
+        let input = "match r { ok x -> x; err _ -> 0 }";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 33,
+                },
+                expression: Match(
+                    Match {
+                        value: Expr {
+                            span: Span {
+                                start: 6,
+                                end: 7,
+                            },
+                            expression: Identifier(
+                                Name(
+                                    "r",
+                                ),
                             ),
                         },
+                        patterns: [
+                            PatternMatch {
+                                pattern: Ok(
+                                    Variable(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
+                                ),
+                                pattern_span: Span {
+                                    start: 10,
+                                    end: 14,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 18,
+                                        end: 19,
+                                    },
+                                    expression: Identifier(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
+                                },
+                            },
+                            PatternMatch {
+                                pattern: Err(
+                                    Anything,
+                                ),
+                                pattern_span: Span {
+                                    start: 21,
+                                    end: 26,
+                                },
+                                result: Expr {
+                                    span: Span {
+                                        start: 30,
+                                        end: 31,
+                                    },
+                                    expression: Primitive(
+                                        Integer(
+                                            Small(
+                                                0,
+                                            ),
+                                        ),
+                                    ),
+                                },
+                            },
+                        ],
                     },
                 ),
             },
@@ -595,66 +2722,45 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_function_application() {
-        let input = "func one two three";
-        let parsed = parse(input);
+    fn test_parsing_try() {
+        let input = "try x: Integer = y in z";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
                     start: 0,
-                    end: 18,
+                    end: 23,
                 },
                 expression: Apply(
                     Apply {
                         function: Expr {
                             span: Span {
                                 start: 0,
-                                end: 12,
+                                end: 18,
                             },
                             expression: Apply(
                                 Apply {
                                     function: Expr {
                                         span: Span {
                                             start: 0,
-                                            end: 8,
+                                            end: 3,
                                         },
-                                        expression: Apply(
-                                            Apply {
-                                                function: Expr {
-                                                    span: Span {
-                                                        start: 0,
-                                                        end: 4,
-                                                    },
-                                                    expression: Identifier(
-                                                        Name(
-                                                            "func",
-                                                        ),
-                                                    ),
-                                                },
-                                                argument: Expr {
-                                                    span: Span {
-                                                        start: 5,
-                                                        end: 8,
-                                                    },
-                                                    expression: Identifier(
-                                                        Name(
-                                                            "one",
-                                                        ),
-                                                    ),
-                                                },
-                                            },
+                                        expression: Identifier(
+                                            Name(
+                                                "and_then",
+                                            ),
                                         ),
                                     },
                                     argument: Expr {
                                         span: Span {
-                                            start: 9,
-                                            end: 12,
+                                            start: 17,
+                                            end: 18,
                                         },
                                         expression: Identifier(
                                             Name(
-                                                "two",
+                                                "y",
                                             ),
                                         ),
                                     },
@@ -663,13 +2769,45 @@ mod tests {
                         },
                         argument: Expr {
                             span: Span {
-                                start: 13,
-                                end: 18,
+                                start: 22,
+                                end: 23,
                             },
-                            expression: Identifier(
-                                Name(
-                                    "three",
-                                ),
+                            expression: Function(
+                                Function {
+                                    parameters: [
+                                        Parameter {
+                                            name: Name(
+                                                "x",
+                                            ),
+                                            typ: Some(
+                                                Monotype(
+                                                    Integer,
+                                                ),
+                                            ),
+                                            typ_span: Some(
+                                                Span {
+                                                    start: 7,
+                                                    end: 14,
+                                                },
+                                            ),
+                                            span: Span {
+                                                start: 4,
+                                                end: 14,
+                                            },
+                                        },
+                                    ],
+                                    body: Expr {
+                                        span: Span {
+                                            start: 22,
+                                            end: 23,
+                                        },
+                                        expression: Identifier(
+                                            Name(
+                                                "z",
+                                            ),
+                                        ),
+                                    },
+                                },
                             ),
                         },
                     },
@@ -680,78 +2818,109 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_inline_function_application() {
-        let input = "(fn argument -> argument + argument) input";
-        let parsed = parse(input);
+    fn test_parsing_a_recursive_type_annotation() {
+        let input = "x: mu t. (Integer, Option t)";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
-                    start: 1,
-                    end: 42,
+                    start: 0,
+                    end: 1,
                 },
-                expression: Apply(
-                    Apply {
-                        function: Expr {
+                expression: Typed(
+                    Typed {
+                        expression: Expr {
                             span: Span {
-                                start: 1,
-                                end: 35,
+                                start: 0,
+                                end: 1,
                             },
-                            expression: Function(
-                                Function {
-                                    parameters: [
-                                        Name(
-                                            "argument",
-                                        ),
-                                    ],
-                                    body: Expr {
-                                        span: Span {
-                                            start: 16,
-                                            end: 35,
-                                        },
-                                        expression: Infix(
-                                            Infix {
-                                                operation: Add,
-                                                left: Expr {
-                                                    span: Span {
-                                                        start: 16,
-                                                        end: 24,
-                                                    },
-                                                    expression: Identifier(
-                                                        Name(
-                                                            "argument",
-                                                        ),
-                                                    ),
-                                                },
-                                                right: Expr {
-                                                    span: Span {
-                                                        start: 27,
-                                                        end: 35,
-                                                    },
-                                                    expression: Identifier(
-                                                        Name(
-                                                            "argument",
+                            expression: Identifier(
+                                Name(
+                                    "x",
+                                ),
+                            ),
+                        },
+                        typ: Monotype(
+                            Recursive {
+                                variable: TypeVariable(
+                                    "t",
+                                ),
+                                body: Monotype(
+                                    Tuple(
+                                        [
+                                            Monotype(
+                                                Integer,
+                                            ),
+                                            Monotype(
+                                                Option(
+                                                    Monotype(
+                                                        Variable(
+                                                            TypeVariable(
+                                                                "t",
+                                                            ),
                                                         ),
                                                     ),
-                                                },
-                                            },
-                                        ),
-                                    },
-                                },
-                            ),
+                                                ),
+                                            ),
+                                        ],
+                                    ),
+                                ),
+                            },
+                        ),
+                        typ_span: Span {
+                            start: 3,
+                            end: 28,
                         },
-                        argument: Expr {
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_an_applied_type_constructor() {
+        let input = "x: Integer Integer";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 0,
+                    end: 1,
+                },
+                expression: Typed(
+                    Typed {
+                        expression: Expr {
                             span: Span {
-                                start: 37,
-                                end: 42,
+                                start: 0,
+                                end: 1,
                             },
                             expression: Identifier(
                                 Name(
-                                    "input",
+                                    "x",
                                 ),
                             ),
                         },
+                        typ: Monotype(
+                            Constructor {
+                                name: TypeName(
+                                    "Integer",
+                                ),
+                                arguments: [
+                                    Monotype(
+                                        Integer,
+                                    ),
+                                ],
+                            },
+                        ),
+                        typ_span: Span {
+                            start: 3,
+                            end: 18,
+                        },
                     },
                 ),
             },
@@ -760,102 +2929,67 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_assigned_function_application() {
-        let input =
-            "let important_function = fn thing -> (thing + thing) in important_function input";
-        let parsed = parse(input);
+    fn test_parsing_a_higher_rank_type_annotation() {
+        let input = "x: (forall a. a -> a) -> Integer";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
                     start: 0,
-                    end: 80,
+                    end: 1,
                 },
-                expression: Assign(
-                    Assign {
-                        name: Name(
-                            "important_function",
-                        ),
-                        value: Expr {
+                expression: Typed(
+                    Typed {
+                        expression: Expr {
                             span: Span {
-                                start: 25,
-                                end: 51,
+                                start: 0,
+                                end: 1,
                             },
-                            expression: Function(
-                                Function {
-                                    parameters: [
-                                        Name(
-                                            "thing",
-                                        ),
-                                    ],
-                                    body: Expr {
-                                        span: Span {
-                                            start: 38,
-                                            end: 51,
-                                        },
-                                        expression: Infix(
-                                            Infix {
-                                                operation: Add,
-                                                left: Expr {
-                                                    span: Span {
-                                                        start: 38,
-                                                        end: 43,
-                                                    },
-                                                    expression: Identifier(
-                                                        Name(
-                                                            "thing",
+                            expression: Identifier(
+                                Name(
+                                    "x",
+                                ),
+                            ),
+                        },
+                        typ: Monotype(
+                            Function {
+                                parameter: Monotype(
+                                    Forall {
+                                        quantifiers: [
+                                            TypeVariable(
+                                                "a",
+                                            ),
+                                        ],
+                                        body: Monotype(
+                                            Function {
+                                                parameter: Monotype(
+                                                    Variable(
+                                                        TypeVariable(
+                                                            "a",
                                                         ),
                                                     ),
-                                                },
-                                                right: Expr {
-                                                    span: Span {
-                                                        start: 46,
-                                                        end: 51,
-                                                    },
-                                                    expression: Identifier(
-                                                        Name(
-                                                            "thing",
+                                                ),
+                                                body: Monotype(
+                                                    Variable(
+                                                        TypeVariable(
+                                                            "a",
                                                         ),
                                                     ),
-                                                },
+                                                ),
                                             },
                                         ),
                                     },
-                                },
-                            ),
-                        },
-                        inner: Expr {
-                            span: Span {
-                                start: 56,
-                                end: 80,
+                                ),
+                                body: Monotype(
+                                    Integer,
+                                ),
                             },
-                            expression: Apply(
-                                Apply {
-                                    function: Expr {
-                                        span: Span {
-                                            start: 56,
-                                            end: 74,
-                                        },
-                                        expression: Identifier(
-                                            Name(
-                                                "important_function",
-                                            ),
-                                        ),
-                                    },
-                                    argument: Expr {
-                                        span: Span {
-                                            start: 75,
-                                            end: 80,
-                                        },
-                                        expression: Identifier(
-                                            Name(
-                                                "input",
-                                            ),
-                                        ),
-                                    },
-                                },
-                            ),
+                        ),
+                        typ_span: Span {
+                            start: 4,
+                            end: 32,
                         },
                     },
                 ),
@@ -865,83 +2999,74 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_function_application_within_infix_operations() {
-        let input = "f left + g right";
-        let parsed = parse(input);
+    fn test_parsing_a_result_type_annotation() {
+        let input = "f: Result [Integer] String -> Result (Option Char) (Integer, Float)";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
                     start: 0,
-                    end: 16,
+                    end: 1,
                 },
-                expression: Infix(
-                    Infix {
-                        operation: Add,
-                        left: Expr {
+                expression: Typed(
+                    Typed {
+                        expression: Expr {
                             span: Span {
                                 start: 0,
-                                end: 6,
+                                end: 1,
                             },
-                            expression: Apply(
-                                Apply {
-                                    function: Expr {
-                                        span: Span {
-                                            start: 0,
-                                            end: 1,
-                                        },
-                                        expression: Identifier(
-                                            Name(
-                                                "f",
+                            expression: Identifier(
+                                Name(
+                                    "f",
+                                ),
+                            ),
+                        },
+                        typ: Monotype(
+                            Function {
+                                parameter: Monotype(
+                                    Result {
+                                        ok: Monotype(
+                                            List(
+                                                Monotype(
+                                                    Integer,
+                                                ),
                                             ),
                                         ),
-                                    },
-                                    argument: Expr {
-                                        span: Span {
-                                            start: 2,
-                                            end: 6,
-                                        },
-                                        expression: Identifier(
-                                            Name(
-                                                "left",
-                                            ),
+                                        error: Monotype(
+                                            String,
                                         ),
                                     },
-                                },
-                            ),
-                        },
-                        right: Expr {
-                            span: Span {
-                                start: 9,
-                                end: 16,
-                            },
-                            expression: Apply(
-                                Apply {
-                                    function: Expr {
-                                        span: Span {
-                                            start: 9,
-                                            end: 10,
-                                        },
-                                        expression: Identifier(
-                                            Name(
-                                                "g",
+                                ),
+                                body: Monotype(
+                                    Result {
+                                        ok: Monotype(
+                                            Option(
+                                                Monotype(
+                                                    Char,
+                                                ),
                                             ),
                                         ),
-                                    },
-                                    argument: Expr {
-                                        span: Span {
-                                            start: 11,
-                                            end: 16,
-                                        },
-                                        expression: Identifier(
-                                            Name(
-                                                "right",
+                                        error: Monotype(
+                                            Tuple(
+                                                [
+                                                    Monotype(
+                                                        Integer,
+                                                    ),
+                                                    Monotype(
+                                                        Float,
+                                                    ),
+                                                ],
                                             ),
                                         ),
                                     },
-                                },
-                            ),
+                                ),
+                            },
+                        ),
+                        typ_span: Span {
+                            start: 3,
+                            end: 67,
                         },
                     },
                 ),
@@ -951,116 +3076,56 @@ mod tests {
     }
 
     #[test]
-    fn test_parsing_a_match_expression() {
-        let input = "match 2 { 1 -> 2; 2 -> 3; 3 -> 4; _ -> 0 }";
-        let parsed = parse(input);
+    fn test_parsing_an_option_type_annotation() {
+        let input = "none: Option [Integer] -> Option Integer";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
             Expr {
                 span: Span {
                     start: 0,
-                    end: 42,
+                    end: 4,
                 },
-                expression: Match(
-                    Match {
-                        value: Expr {
+                expression: Typed(
+                    Typed {
+                        expression: Expr {
                             span: Span {
-                                start: 6,
-                                end: 7,
+                                start: 0,
+                                end: 4,
                             },
-                            expression: Primitive(
-                                Integer(
-                                    Small(
-                                        2,
-                                    ),
+                            expression: Identifier(
+                                Name(
+                                    "none",
                                 ),
                             ),
                         },
-                        patterns: [
-                            PatternMatch {
-                                pattern: Primitive(
-                                    Integer(
-                                        Small(
-                                            1,
-                                        ),
-                                    ),
-                                ),
-                                result: Expr {
-                                    span: Span {
-                                        start: 15,
-                                        end: 16,
-                                    },
-                                    expression: Primitive(
-                                        Integer(
-                                            Small(
-                                                2,
+                        typ: Monotype(
+                            Function {
+                                parameter: Monotype(
+                                    Option(
+                                        Monotype(
+                                            List(
+                                                Monotype(
+                                                    Integer,
+                                                ),
                                             ),
                                         ),
                                     ),
-                                },
-                            },
-                            PatternMatch {
-                                pattern: Primitive(
-                                    Integer(
-                                        Small(
-                                            2,
-                                        ),
-                                    ),
                                 ),
-                                result: Expr {
-                                    span: Span {
-                                        start: 23,
-                                        end: 24,
-                                    },
-                                    expression: Primitive(
-                                        Integer(
-                                            Small(
-                                                3,
-                                            ),
-                                        ),
-                                    ),
-                                },
-                            },
-                            PatternMatch {
-                                pattern: Primitive(
-                                    Integer(
-                                        Small(
-                                            3,
+                                body: Monotype(
+                                    Option(
+                                        Monotype(
+                                            Integer,
                                         ),
                                     ),
                                 ),
-                                result: Expr {
-                                    span: Span {
-                                        start: 31,
-                                        end: 32,
-                                    },
-                                    expression: Primitive(
-                                        Integer(
-                                            Small(
-                                                4,
-                                            ),
-                                        ),
-                                    ),
-                                },
-                            },
-                            PatternMatch {
-                                pattern: Anything,
-                                result: Expr {
-                                    span: Span {
-                                        start: 39,
-                                        end: 40,
-                                    },
-                                    expression: Primitive(
-                                        Integer(
-                                            Small(
-                                                0,
-                                            ),
-                                        ),
-                                    ),
-                                },
                             },
-                        ],
+                        ),
+                        typ_span: Span {
+                            start: 6,
+                            end: 40,
+                        },
                     },
                 ),
             },
@@ -1072,7 +3137,7 @@ mod tests {
     fn test_parsing_an_expression_type_annotation() {
         let input =
             "let id = fn x -> x: (Integer -> Integer) in id (1: Integer) + (2 + 3: Integer)";
-        let parsed = parse(input);
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Ok(
@@ -1101,9 +3166,17 @@ mod tests {
                                         expression: Function(
                                             Function {
                                                 parameters: [
-                                                    Name(
-                                                        "x",
-                                                    ),
+                                                    Parameter {
+                                                        name: Name(
+                                                            "x",
+                                                        ),
+                                                        typ: None,
+                                                        typ_span: None,
+                                                        span: Span {
+                                                            start: 12,
+                                                            end: 13,
+                                                        },
+                                                    },
                                                 ],
                                                 body: Expr {
                                                     span: Span {
@@ -1129,6 +3202,10 @@ mod tests {
                                             ),
                                         },
                                     ),
+                                    typ_span: Span {
+                                        start: 21,
+                                        end: 39,
+                                    },
                                 },
                             ),
                         },
@@ -1140,6 +3217,10 @@ mod tests {
                             expression: Infix(
                                 Infix {
                                     operation: Add,
+                                    operator_span: Span {
+                                        start: 60,
+                                        end: 61,
+                                    },
                                     left: Expr {
                                         span: Span {
                                             start: 44,
@@ -1181,6 +3262,10 @@ mod tests {
                                                             typ: Monotype(
                                                                 Integer,
                                                             ),
+                                                            typ_span: Span {
+                                                                start: 51,
+                                                                end: 58,
+                                                            },
                                                         },
                                                     ),
                                                 },
@@ -1202,6 +3287,10 @@ mod tests {
                                                     expression: Infix(
                                                         Infix {
                                                             operation: Add,
+                                                            operator_span: Span {
+                                                                start: 65,
+                                                                end: 66,
+                                                            },
                                                             left: Expr {
                                                                 span: Span {
                                                                     start: 63,
@@ -1234,6 +3323,10 @@ mod tests {
                                                 typ: Monotype(
                                                     Integer,
                                                 ),
+                                                typ_span: Span {
+                                                    start: 70,
+                                                    end: 77,
+                                                },
                                             },
                                         ),
                                     },
@@ -1247,10 +3340,239 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_parsing_ignores_comments() {
+        let input = "# the first line\n1 + # the second line\n2 # the end";
+        let parsed = parse_expr(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Expr {
+                span: Span {
+                    start: 17,
+                    end: 40,
+                },
+                expression: Infix(
+                    Infix {
+                        operation: Add,
+                        operator_span: Span {
+                            start: 19,
+                            end: 20,
+                        },
+                        left: Expr {
+                            span: Span {
+                                start: 17,
+                                end: 18,
+                            },
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        1,
+                                    ),
+                                ),
+                            ),
+                        },
+                        right: Expr {
+                            span: Span {
+                                start: 39,
+                                end: 40,
+                            },
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        2,
+                                    ),
+                                ),
+                            ),
+                        },
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_a_program_with_definitions() {
+        let input = "def x = 1; let y: Integer = 2; x + y";
+        let parsed = parse_program(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Program {
+                definitions: [
+                    Definition {
+                        name: Name(
+                            "x",
+                        ),
+                        value: Expr {
+                            span: Span {
+                                start: 8,
+                                end: 9,
+                            },
+                            expression: Primitive(
+                                Integer(
+                                    Small(
+                                        1,
+                                    ),
+                                ),
+                            ),
+                        },
+                    },
+                    Definition {
+                        name: Name(
+                            "y",
+                        ),
+                        value: Expr {
+                            span: Span {
+                                start: 28,
+                                end: 29,
+                            },
+                            expression: Typed(
+                                Typed {
+                                    expression: Expr {
+                                        span: Span {
+                                            start: 28,
+                                            end: 29,
+                                        },
+                                        expression: Primitive(
+                                            Integer(
+                                                Small(
+                                                    2,
+                                                ),
+                                            ),
+                                        ),
+                                    },
+                                    typ: Monotype(
+                                        Integer,
+                                    ),
+                                    typ_span: Span {
+                                        start: 18,
+                                        end: 25,
+                                    },
+                                },
+                            ),
+                        },
+                    },
+                ],
+                main: Some(
+                    Expr {
+                        span: Span {
+                            start: 31,
+                            end: 36,
+                        },
+                        expression: Infix(
+                            Infix {
+                                operation: Add,
+                                operator_span: Span {
+                                    start: 33,
+                                    end: 34,
+                                },
+                                left: Expr {
+                                    span: Span {
+                                        start: 31,
+                                        end: 32,
+                                    },
+                                    expression: Identifier(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
+                                },
+                                right: Expr {
+                                    span: Span {
+                                        start: 35,
+                                        end: 36,
+                                    },
+                                    expression: Identifier(
+                                        Name(
+                                            "y",
+                                        ),
+                                    ),
+                                },
+                            },
+                        ),
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_parsing_a_program_without_a_main_expression() {
+        for input in ["let x = 3", "def x = 3;", "def y = 1;\nlet x = 3"] {
+            let program = parse_program(input).unwrap();
+
+            assert_eq!(program.main, None, "input: {:?}", input);
+            let last = program.definitions.last().unwrap();
+            assert_eq!(last.name, Identifier::name_from_str("x").unwrap());
+            let value_start = input.rfind('3').unwrap();
+            assert_eq!(last.value.span, (value_start..value_start + 1).into());
+        }
+
+        // a definition must be terminated if anything follows it
+        assert!(parse_program("let x = 3 let y = 4").is_err());
+        assert!(parse_expr("let x = 3").is_err());
+    }
+
+    #[test]
+    fn test_parsing_a_program_without_definitions() {
+        let input = "let x = 1 in x";
+        let parsed = parse_program(input);
+
+        insta::assert_debug_snapshot!(parsed, @r###"
+        Ok(
+            Program {
+                definitions: [],
+                main: Some(
+                    Expr {
+                        span: Span {
+                            start: 0,
+                            end: 14,
+                        },
+                        expression: Assign(
+                            Assign {
+                                name: Name(
+                                    "x",
+                                ),
+                                value: Expr {
+                                    span: Span {
+                                        start: 8,
+                                        end: 9,
+                                    },
+                                    expression: Primitive(
+                                        Integer(
+                                            Small(
+                                                1,
+                                            ),
+                                        ),
+                                    ),
+                                },
+                                inner: Expr {
+                                    span: Span {
+                                        start: 13,
+                                        end: 14,
+                                    },
+                                    expression: Identifier(
+                                        Name(
+                                            "x",
+                                        ),
+                                    ),
+                                },
+                            },
+                        ),
+                    },
+                ),
+            },
+        )
+        "###);
+    }
+
     #[test]
     fn test_parsing_rejects_anything_else() {
-        let input = "1 / 2";
-        let parsed = parse(input);
+        let input = "1 ^ 2";
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Err(
@@ -1259,7 +3581,7 @@ mod tests {
                     start: 2,
                     end: 3,
                 },
-                token: "/",
+                token: "^",
             },
         )
         "###);
@@ -1268,7 +3590,7 @@ mod tests {
     #[test]
     fn test_parsing_rejects_an_unfinished_expression() {
         let input = "3 +";
-        let parsed = parse(input);
+        let parsed = parse_expr(input);
 
         insta::assert_debug_snapshot!(parsed, @r###"
         Err(
@@ -1279,14 +3601,60 @@ mod tests {
                 },
                 expected_tokens: [
                     "'('",
+                    "'-'",
+                    "'-.'",
+                    "'['",
+                    "a boolean",
+                    "a character",
+                    "a float",
+                    "a string",
                     "an identifier",
                     "an integer",
                     "fn",
                     "let",
                     "match",
+                    "try",
                 ],
+                context: None,
+                context_span: None,
+                hint: None,
             },
         )
         "###);
     }
+
+    #[test]
+    fn test_lexing_tokens_lazily() {
+        let mut tokens = lexer::tokens("1 + ^ 2");
+
+        assert_eq!(
+            tokens.next(),
+            Some(Ok(lexer::AnnotatedToken {
+                annotation: (0..1).into(),
+                token: lexer::Token::Integer(1.into()),
+            }))
+        );
+        assert_eq!(
+            tokens.next(),
+            Some(Ok(lexer::AnnotatedToken {
+                annotation: (2..3).into(),
+                token: lexer::Token::Operator("+"),
+            }))
+        );
+        assert_eq!(
+            tokens.next(),
+            Some(Err(boo_core::error::Error::UnexpectedToken {
+                span: (4..5).into(),
+                token: "^".to_string(),
+            }))
+        );
+        assert_eq!(
+            tokens.next(),
+            Some(Ok(lexer::AnnotatedToken {
+                annotation: (6..7).into(),
+                token: lexer::Token::Integer(2.into()),
+            }))
+        );
+        assert_eq!(tokens.next(), None);
+    }
 }
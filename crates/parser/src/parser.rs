@@ -1,5 +1,8 @@
 //! Parses tokens into an AST.
 
+use std::ops::Range;
+use std::sync::Arc;
+
 use boo_core::error::*;
 use boo_core::identifier::*;
 use boo_core::primitive::*;
@@ -8,80 +11,218 @@ use boo_core::types::*;
 use boo_language::*;
 
 use crate::lexer::*;
+use crate::precedence::{self, InfixOperator, Precedence};
 
 peg::parser! {
     grammar parser<'a>() for [&'a AnnotatedToken<'a, Span>] {
         pub rule root() -> Expr = e:expr() { e }
 
-        pub rule expr() -> Expr = precedence! {
+        pub rule program() -> Program =
+            program:located_program() {
+                Program {
+                    definitions: program.0.into_iter().map(|(_, definition)| definition).collect(),
+                    main: program.1,
+                }
+            }
+
+        pub rule located_program() -> (Vec<LocatedDefinition>, Option<Expr>) =
+            definitions:located_definition()*
+            main:expr() {
+                (definitions, Some(main))
+            }
+            / definitions:located_definition()*
+              last:located_final_definition() {
+                let mut definitions = definitions;
+                definitions.push(last);
+                (definitions, None)
+            }
+            / definitions:located_definition()+ {
+                (definitions, None)
+            }
+
+        pub rule located_definitions() -> Vec<LocatedDefinition> =
+            located_definition()*
+
+        rule located_definition() -> LocatedDefinition =
+            start:position!() definition:definition() end:position!() {
+                (start..end, definition)
+            }
+
+        // The last definition in a program may omit its terminating `;`.
+        rule located_final_definition() -> LocatedDefinition =
+            start:position!() definition:unterminated_definition() end:position!() ![_] {
+                (start..end, definition)
+            }
+
+        rule definition() -> Definition =
+            definition:unterminated_definition()
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Separator }] } / expected!(";")) {
+                definition
+            }
+
+        rule unterminated_definition() -> Definition =
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Def | Token::Let }] } / expected!("def"))
+            name:(quiet! { [AnnotatedToken { annotation: _, token: Token::Identifier(name) }] { name } } / expected!("an identifier"))
+            typ:binding_annotation()?
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Assign }] } / expected!("="))
+            value:expr() {
+                Definition {
+                    name: name.clone(),
+                    value: construct_annotated_binding(value, typ),
+                }
+            }
+
+        pub rule expr() -> Expr = expr_at(precedence::BINDING)
+
+        // Parses an expression made up only of operators which bind at least
+        // as tightly as the given precedence. Each operator parses its right
+        // operand at its own precedence, so folding the operators from left to
+        // right respects both precedence and associativity.
+        rule expr_at(min_precedence: Precedence) -> Expr =
+            first:prefix_expr()
+            operators:postfix(min_precedence)* {
+                operators.into_iter().fold(first, |left, operator| operator.apply_to(left))
+            }
+
+        rule prefix_expr() -> Expr =
             let_:(quiet! { [AnnotatedToken { annotation: _, token: Token::Let }] } / expected!("let"))
             name:(quiet! { [AnnotatedToken { annotation: _, token: Token::Identifier(name) }] { name } } / expected!("an identifier"))
+            typ:binding_annotation()?
             (quiet! { [AnnotatedToken { annotation: _, token: Token::Assign }] } / expected!("="))
             value:expr()
             (quiet! { [AnnotatedToken { annotation: _, token: Token::In }] } / expected!("in"))
-            inner:@ {
+            inner:expr_at(precedence::BINDING) {
                 Expr::new(
                     let_.annotation | inner.span,
                     Expression::Assign(Assign {
                         name: name.clone(),
-                        value,
+                        value: construct_annotated_binding(value, typ),
                         inner,
                     }),
                 )
             }
-            --
-            expression:@ (quiet! { [AnnotatedToken { annotation: _, token: Token::Annotate }] } / expected!("':'")) typ:typ() {
-                Expr::new(expression.span, Expression::Typed(Typed {
-                    expression,
-                    typ,
-                }))
+            / try_:(quiet! { [AnnotatedToken { annotation: _, token: Token::Try }] } / expected!("try"))
+            name:identifier()
+            typ:binding_annotation()?
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Assign }] } / expected!("="))
+            value:expr()
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::In }] } / expected!("in"))
+            inner:expr_at(precedence::BINDING) {
+                construct_try(
+                    try_.annotation,
+                    Parameter {
+                        name: name.1,
+                        span: typ.as_ref().map_or(name.0, |(typ_span, _)| name.0 | *typ_span),
+                        typ_span: typ.as_ref().map(|(typ_span, _)| *typ_span),
+                        typ: typ.map(|(_, typ)| typ),
+                    },
+                    value,
+                    inner,
+                )
             }
-            --
-            fn_:(quiet! { [AnnotatedToken { annotation: _, token: Token::Fn }] } / expected!("fn"))
-            parameters:(quiet! { [AnnotatedToken { annotation: _, token: Token::Identifier(name) }] { name } } / expected!("an identifier"))+
+            / let_:(quiet! { [AnnotatedToken { annotation: _, token: Token::Let }] } / expected!("let"))
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Rec }] } / expected!("rec"))
+            bindings:(recursive_binding() ++ (quiet! { [AnnotatedToken { annotation: _, token: Token::And }] } / expected!("and")))
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::In }] } / expected!("in"))
+            inner:expr_at(precedence::BINDING) {
+                Expr::new(
+                    let_.annotation | inner.span,
+                    Expression::AssignRecursive(AssignRecursive {
+                        bindings,
+                        inner,
+                    }),
+                )
+            }
+            / fn_:(quiet! { [AnnotatedToken { annotation: _, token: Token::Fn }] } / expected!("fn"))
+            parameters:parameter()+
             (quiet! { [AnnotatedToken { annotation: _, token: Token::Arrow }] } / expected!("->"))
-            body:@ {
+            body:expr_at(precedence::FUNCTION) {
                 let span = fn_.annotation | body.span;
                 Expr::new(span, Expression::Function(Function {
-                    parameters: parameters.into_iter().cloned().collect(),
+                    parameters,
                     body,
                 }))
             }
-            --
-            x:match_() { x }
-            --
-            left:(@) (quiet! { [AnnotatedToken { annotation: _, token: Token::Operator("+") }] } / expected!("'+'")) right:@ {
-                construct_infix(left, Operation::Add, right)
+            / match_()
+            / literal:negative_literal() {
+                Expr::new(literal.0, Expression::Primitive(literal.1))
             }
-            left:(@) (quiet! { [AnnotatedToken { annotation: _, token: Token::Operator("-") }] } / expected!("'-'")) right:@ {
-                construct_infix(left, Operation::Subtract, right)
+            / minus:(quiet! { [AnnotatedToken { annotation: _, token: Token::Operator("-") }] } / expected!("'-'"))
+            value:expr_at(precedence::NEGATION) {
+                construct_negation(minus.annotation, Primitive::Integer(0.into()), Operation::Subtract, value)
             }
-            --
-            left:(@) (quiet! { [AnnotatedToken { annotation: _, token: Token::Operator("*") }] } / expected!("'*'")) right:@ {
-                construct_infix(left, Operation::Multiply, right)
+            / minus:(quiet! { [AnnotatedToken { annotation: _, token: Token::Operator("-.") }] } / expected!("'-.'"))
+            value:expr_at(precedence::NEGATION) {
+                construct_negation(minus.annotation, Primitive::Float(0.0.into()), Operation::SubtractFloat, value)
             }
-            --
-            function:(@) argument:atomic_expr() {
-                Expr::new(
-                    function.span | argument.span,
-                    Expression::Apply(Apply {
-                        function,
-                        argument,
-                    }),
-                )
+            / atomic_expr()
+
+        rule postfix(min_precedence: Precedence) -> Postfix =
+            operator:infix_operator(min_precedence)
+            right:expr_at(operator.1.right_precedence()) {
+                Postfix::Infix(operator.1.operation, operator.0, right)
+            }
+            / binds(min_precedence, precedence::TYPE_ANNOTATION)
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Annotate }] } / expected!("':'"))
+            typ:typ() {
+                Postfix::Typed(typ)
+            }
+            / binds(min_precedence, precedence::APPLICATION)
+            argument:atomic_expr() {
+                Postfix::Apply(argument)
             }
-            --
-            x:atomic_expr() { x }
-        }
+
+        rule infix_operator(min_precedence: Precedence) -> (Span, &'static InfixOperator) =
+            operator:(
+                quiet! { [AnnotatedToken { annotation, token: Token::Operator(symbol) }] {?
+                    precedence::infix_operator(symbol)
+                        .filter(|operator| operator.precedence >= min_precedence)
+                        .map(|operator| (*annotation, operator))
+                        .ok_or("")
+                } }
+                / expected!(AN_INFIX_OPERATOR)
+            ) {
+                operator
+            }
+
+        // Succeeds, without consuming anything, if an operator of the given
+        // precedence can be parsed here.
+        rule binds(min_precedence: Precedence, precedence: Precedence) =
+            quiet! { {? if precedence >= min_precedence { Ok(()) } else { Err("") } } }
 
         rule atomic_expr() -> Expr =
-            e:(primitive_expr() / identifier_expr() / group()) { e }
+            e:(primitive_expr() / identifier_expr() / group() / list()) { e }
 
         rule group() -> Expr =
-            (quiet! { [AnnotatedToken { annotation: _, token: Token::StartGroup }] } / expected!("'('"))
+            start:(quiet! { [AnnotatedToken { annotation: _, token: Token::StartGroup }] } / expected!("'('"))
             e:expr()
-            (quiet! { [AnnotatedToken { annotation: _, token: Token::EndGroup }] } / expected!(")'")) {
-                e
+            rest:(
+                (quiet! { [AnnotatedToken { annotation: _, token: Token::Comma }] } / expected!("','"))
+                element:expr() { element }
+            )*
+            sequence:(
+                (quiet! { [AnnotatedToken { annotation: _, token: Token::Separator }] } / expected!(";"))
+                element:expr() { element }
+            )*
+            end:(quiet! { [AnnotatedToken { annotation: _, token: Token::EndGroup }] } / expected!(")'")) {?
+                let span = start.annotation | end.annotation;
+                match (rest.is_empty(), sequence.is_empty()) {
+                    (true, true) => Ok(e),
+                    (false, true) => {
+                        let mut elements = rest;
+                        elements.insert(0, e);
+                        Ok(Expr::new(span, Expression::Tuple(Tuple { elements })))
+                    }
+                    (true, false) => Ok(construct_sequence(span, e, sequence)),
+                    (false, false) => Err("a tuple or a sequence, but not both"),
+                }
+            }
+
+        rule list() -> Expr =
+            start:(quiet! { [AnnotatedToken { annotation: _, token: Token::StartList }] } / expected!("'['"))
+            elements:(expr() ** (quiet! { [AnnotatedToken { annotation: _, token: Token::Comma }] } / expected!("','")))
+            end:(quiet! { [AnnotatedToken { annotation: _, token: Token::EndList }] } / expected!("']'")) {
+                Expr::new(start.annotation | end.annotation, Expression::List(List { elements }))
             }
 
         rule primitive_expr() -> Expr =
@@ -93,6 +234,33 @@ peg::parser! {
             quiet! { [AnnotatedToken { annotation, token: Token::Integer(n) }] {
                 (*annotation, Primitive::Integer(n.clone()))
             } } / expected!("an integer")
+            / quiet! { [AnnotatedToken { annotation, token: Token::Float(x) }] {
+                (*annotation, Primitive::Float(*x))
+            } } / expected!("a float")
+            / quiet! { [AnnotatedToken { annotation, token: Token::Boolean(b) }] {
+                (*annotation, Primitive::Boolean(*b))
+            } } / expected!("a boolean")
+            / quiet! { [AnnotatedToken { annotation, token: Token::Char(c) }] {
+                (*annotation, Primitive::Char(*c))
+            } } / expected!("a character")
+            / quiet! { [AnnotatedToken { annotation, token: Token::String(s) }] {
+                (*annotation, Primitive::String(Arc::new(s.clone())))
+            } } / expected!("a string")
+
+        // Numeric literals are lexed without a sign, so that `x -2` is a
+        // subtraction. A minus directly before one makes a negative literal.
+        rule negative_literal() -> (Span, Primitive) =
+            minus:(quiet! { [AnnotatedToken { annotation: _, token: Token::Operator("-") }] } / expected!("'-'"))
+            literal:(
+                quiet! { [AnnotatedToken { annotation, token: Token::Integer(n) }] {
+                    (*annotation, Primitive::Integer(Integer::from(0) - n.clone()))
+                } } / expected!("an integer")
+                / quiet! { [AnnotatedToken { annotation, token: Token::Float(x) }] {
+                    (*annotation, Primitive::Float(Float::from(-f64::from(*x))))
+                } } / expected!("a float")
+            ) {
+                (minus.annotation | literal.0, literal.1)
+            }
 
         rule identifier_expr() -> Expr =
             identifier:identifier() {
@@ -104,6 +272,40 @@ peg::parser! {
                 (*annotation, name.clone())
             } } / expected!("an identifier")
 
+        rule parameter() -> Parameter =
+            name:identifier() {
+                Parameter {
+                    name: name.1,
+                    typ: None,
+                    typ_span: None,
+                    span: name.0,
+                }
+            }
+            / start:(quiet! { [AnnotatedToken { annotation: _, token: Token::StartGroup }] } / expected!("'('"))
+            name:identifier()
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Annotate }] } / expected!("':'"))
+            typ:typ()
+            end:(quiet! { [AnnotatedToken { annotation: _, token: Token::EndGroup }] } / expected!(")'")) {
+                Parameter {
+                    name: name.1,
+                    typ: Some(typ.1),
+                    typ_span: Some(typ.0),
+                    span: start.annotation | end.annotation,
+                }
+            }
+
+        rule recursive_binding() -> (Identifier, Expr) =
+            name:(quiet! { [AnnotatedToken { annotation: _, token: Token::Identifier(name) }] { name } } / expected!("an identifier"))
+            typ:binding_annotation()?
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Assign }] } / expected!("="))
+            value:expr() {
+                (name.clone(), construct_annotated_binding(value, typ))
+            }
+
+        rule binding_annotation() -> (Span, Monotype) =
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Annotate }] } / expected!("':'"))
+            typ:typ() { typ }
+
         rule match_() -> Expr =
             match_:(quiet! { [AnnotatedToken { annotation: _, token: Token::Match }] } / expected!("match"))
             value:expr()
@@ -120,45 +322,216 @@ peg::parser! {
             }
 
         rule pattern_match() -> PatternMatch =
-            pattern:(pattern_primitive() / pattern_anything())
+            pattern:pattern()
             (quiet! { [AnnotatedToken { annotation: _, token: Token::Arrow }] } / expected!("->"))
             result:expr() {
                 PatternMatch {
-                    pattern,
+                    pattern: pattern.1,
+                    pattern_span: pattern.0,
                     result,
                 }
             }
 
-        rule pattern_primitive() -> Pattern =
-            primitive:primitive() {
-                Pattern::Primitive(primitive.1)
+        rule pattern() -> (Span, Pattern) =
+            head:pattern_atom()
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Operator("::") }] } / expected!("'::'"))
+            tail:pattern() {
+                (
+                    head.0 | tail.0,
+                    Pattern::Cons {
+                        head: head.1.into(),
+                        tail: tail.1.into(),
+                    },
+                )
+            }
+            / p:pattern_atom() { p }
+
+        rule pattern_atom() -> (Span, Pattern) =
+            p:(pattern_primitive() / pattern_anything() / pattern_some() / pattern_none() / pattern_ok() / pattern_err() / pattern_variable() / pattern_group() / pattern_list()) { p }
+
+        rule pattern_primitive() -> (Span, Pattern) =
+            primitive:(negative_literal() / primitive()) {
+                (primitive.0, Pattern::Primitive(primitive.1))
+            }
+
+        rule pattern_anything() -> (Span, Pattern) =
+            anything:(quiet! { [AnnotatedToken { annotation: _, token: Token::Anything }] } / expected!("_")) {
+                (anything.annotation, Pattern::Anything)
             }
 
-        rule pattern_anything() -> Pattern =
-            (quiet! { [AnnotatedToken { annotation: _, token: Token::Anything }] } / expected!("_")) {
-                Pattern::Anything
+        rule pattern_some() -> (Span, Pattern) =
+            start:named("some") inner:pattern_atom() {
+                (start | inner.0, Pattern::Some(inner.1.into()))
             }
 
-        rule typ() -> Monotype = precedence! {
-            typ:typ_name() { typ }
+        rule pattern_none() -> (Span, Pattern) =
+            start:named("none") {
+                (start, Pattern::None)
+            }
+
+        rule pattern_ok() -> (Span, Pattern) =
+            start:named("ok") inner:pattern_atom() {
+                (start | inner.0, Pattern::Ok(inner.1.into()))
+            }
+
+        rule pattern_err() -> (Span, Pattern) =
+            start:named("err") inner:pattern_atom() {
+                (start | inner.0, Pattern::Err(inner.1.into()))
+            }
+
+        rule pattern_variable() -> (Span, Pattern) =
+            identifier:identifier() {
+                (identifier.0, Pattern::Variable(identifier.1))
+            }
+
+        rule pattern_group() -> (Span, Pattern) =
+            start:(quiet! { [AnnotatedToken { annotation: _, token: Token::StartGroup }] } / expected!("'('"))
+            first:pattern()
+            rest:(
+                (quiet! { [AnnotatedToken { annotation: _, token: Token::Comma }] } / expected!("','"))
+                element:pattern() { element.1 }
+            )*
+            end:(quiet! { [AnnotatedToken { annotation: _, token: Token::EndGroup }] } / expected!(")'")) {
+                if rest.is_empty() {
+                    first
+                } else {
+                    let mut elements = rest;
+                    elements.insert(0, first.1);
+                    (start.annotation | end.annotation, Pattern::Tuple(elements))
+                }
+            }
+
+        rule pattern_list() -> (Span, Pattern) =
+            start:(quiet! { [AnnotatedToken { annotation: _, token: Token::StartList }] } / expected!("'['"))
+            elements:(pattern() ** (quiet! { [AnnotatedToken { annotation: _, token: Token::Comma }] } / expected!("','")))
+            end:(quiet! { [AnnotatedToken { annotation: _, token: Token::EndList }] } / expected!("']'")) {
+                (
+                    start.annotation | end.annotation,
+                    Pattern::List(elements.into_iter().map(|element| element.1).collect()),
+                )
+            }
+
+        rule typ() -> (Span, Monotype) = precedence! {
+            start:named("forall")
+            quantifiers:typ_variable()+
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Dot }] } / expected!("'.'"))
+            body:@ {
+                (
+                    start | body.0,
+                    Type::Forall {
+                        quantifiers: quantifiers.into_iter().map(|quantifier| quantifier.1).collect(),
+                        body: body.1,
+                    }
+                    .into(),
+                )
+            }
+            start:named("mu")
+            variable:typ_variable()
+            (quiet! { [AnnotatedToken { annotation: _, token: Token::Dot }] } / expected!("'.'"))
+            body:@ {
+                (
+                    start | body.0,
+                    Type::Recursive {
+                        variable: variable.1,
+                        body: body.1,
+                    }
+                    .into(),
+                )
+            }
             --
             parameter:@
             (quiet! { [AnnotatedToken { annotation: _, token: Token::Arrow }] } / expected!("->"))
             body:(@) {
-                Type::Function { parameter, body }.into()
+                (parameter.0 | body.0, Type::Function { parameter: parameter.1, body: body.1 }.into())
             }
             --
-            (quiet! { [AnnotatedToken { annotation: _, token: Token::StartGroup }] } / expected!("'('"))
-            typ:typ()
-            (quiet! { [AnnotatedToken { annotation: _, token: Token::EndGroup }] } / expected!(")'")) {
-                typ
+            start:named("Option") element:@ {
+                (start | element.0, Type::Option(element.1).into())
             }
+            start:named("Result") ok:typ_atom() error:@ {
+                (start | error.0, Type::Result { ok: ok.1, error: error.1 }.into())
+            }
+            --
+            name:typ_constructor_name() arguments:typ_atom()+ {
+                (
+                    name.0 | arguments.last().unwrap().0,
+                    Type::Constructor {
+                        name: name.1,
+                        arguments: arguments.into_iter().map(|argument| argument.1).collect(),
+                    }
+                    .into(),
+                )
+            }
+            typ:typ_atom() { typ }
         }
 
-        rule typ_name() -> Monotype =
+        rule typ_atom() -> (Span, Monotype) =
+            typ_name()
+            /
+            name:typ_constructor_name() {
+                (name.0, Type::Constructor { name: name.1, arguments: vec![] }.into())
+            }
+            /
+            start:(quiet! { [AnnotatedToken { annotation: _, token: Token::StartList }] } / expected!("'['"))
+            element:typ()
+            end:(quiet! { [AnnotatedToken { annotation: _, token: Token::EndList }] } / expected!("']'")) {
+                (start.annotation | end.annotation, Type::List(element.1).into())
+            }
+            /
+            start:(quiet! { [AnnotatedToken { annotation: _, token: Token::StartGroup }] } / expected!("'('"))
+            typ:typ()
+            rest:(
+                (quiet! { [AnnotatedToken { annotation: _, token: Token::Comma }] } / expected!("','"))
+                element:typ() { element.1 }
+            )*
+            end:(quiet! { [AnnotatedToken { annotation: _, token: Token::EndGroup }] } / expected!(")'")) {
+                if rest.is_empty() {
+                    typ
+                } else {
+                    let mut elements = rest;
+                    elements.insert(0, typ.1);
+                    (start.annotation | end.annotation, Type::Tuple(elements).into())
+                }
+            }
+
+        rule named(name: &'static str) -> Span =
+            i:identifier() { ?
+                match i.1 {
+                    Identifier::Name(actual) if actual.as_ref() == name => Ok(i.0),
+                    _ => Err(name),
+                }
+            }
+
+        rule typ_variable() -> (Span, TypeVariable) =
+            i:identifier() { ?
+                match i.1 {
+                    Identifier::Name(name) if name.starts_with(|c: char| !c.is_uppercase()) => {
+                        Ok((i.0, TypeVariable::new(name.to_string())))
+                    }
+                    _ => Err("a type variable"),
+                }
+            }
+
+        rule typ_constructor_name() -> (Span, TypeName) =
+            i:identifier() { ?
+                match i.1 {
+                    Identifier::Name(name) if name.starts_with(char::is_uppercase) => {
+                        Ok((i.0, TypeName::new(name.to_string())))
+                    }
+                    _ => Err("a type name"),
+                }
+            }
+
+        rule typ_name() -> (Span, Monotype) =
+            v:typ_variable() { (v.0, Type::Variable(v.1).into()) }
+            /
             i:identifier() { ?
                  match i.1 {
-                    Identifier::Name(name) if name.as_ref() == "Integer" => Ok(Type::Integer.into()),
+                    Identifier::Name(name) if name.as_ref() == "Integer" => Ok((i.0, Type::Integer.into())),
+                    Identifier::Name(name) if name.as_ref() == "Float" => Ok((i.0, Type::Float.into())),
+                    Identifier::Name(name) if name.as_ref() == "Boolean" => Ok((i.0, Type::Boolean.into())),
+                    Identifier::Name(name) if name.as_ref() == "Char" => Ok((i.0, Type::Char.into())),
+                    Identifier::Name(name) if name.as_ref() == "String" => Ok((i.0, Type::String.into())),
                     _ => Err("unknown type"),
                 }
             }
@@ -169,32 +542,459 @@ peg::parser! {
 /// expression.
 ///
 /// Returns an error if an unexpected token is found.
-pub fn parse_tokens(input: &[AnnotatedToken<Span>]) -> Result<Expr> {
-    parser::root(&(input.iter().collect::<Vec<_>>())).map_err(|inner| {
-        let span: Span = if inner.location < input.len() {
-            input[inner.location].annotation
-        } else {
-            input
-                .last()
-                .map(|s| s.annotation.end.into())
-                .unwrap_or(0.into())
-        };
-        let mut expected_tokens: Vec<&str> = inner.expected.tokens().collect();
-        expected_tokens.sort();
-        Error::ParseError {
-            span,
-            expected_tokens,
+pub fn parse_expr_tokens(input: &[AnnotatedToken<Span>]) -> Result<Expr> {
+    parser::root(&(input.iter().collect::<Vec<_>>())).map_err(|inner| convert_error(input, inner))
+}
+
+/// Parses a slice of [`Token`] values, annotated with a [`Span`], into a
+/// program consisting of top-level definitions and an optional main
+/// expression.
+///
+/// Returns an error if an unexpected token is found.
+pub fn parse_program_tokens(input: &[AnnotatedToken<Span>]) -> Result<Program> {
+    parser::program(&(input.iter().collect::<Vec<_>>()))
+        .map_err(|inner| convert_error(input, inner))
+}
+
+/// A top-level definition, along with the range of tokens it was parsed from.
+pub(crate) type LocatedDefinition = (Range<usize>, Definition);
+
+/// Parses a slice of tokens into a program, along with the range of tokens
+/// making up each top-level definition.
+pub(crate) fn parse_located_program_tokens(
+    input: &[AnnotatedToken<Span>],
+) -> Result<(Vec<LocatedDefinition>, Option<Expr>)> {
+    parser::located_program(&(input.iter().collect::<Vec<_>>()))
+        .map_err(|inner| convert_error(input, inner))
+}
+
+/// Parses a slice of tokens into a sequence of top-level definitions, along
+/// with the range of tokens making up each one.
+pub(crate) fn parse_located_definition_tokens(
+    input: &[AnnotatedToken<Span>],
+) -> Result<Vec<LocatedDefinition>> {
+    parser::located_definitions(&(input.iter().collect::<Vec<_>>()))
+        .map_err(|inner| convert_error(input, inner))
+}
+
+fn convert_error(input: &[AnnotatedToken<Span>], inner: peg::error::ParseError<usize>) -> Error {
+    let span: Span = if inner.location < input.len() {
+        input[inner.location].annotation
+    } else {
+        input
+            .last()
+            .map(|s| s.annotation.end.into())
+            .unwrap_or(0.into())
+    };
+    let mut expected_tokens: Vec<&str> = inner
+        .expected
+        .tokens()
+        .flat_map(|token| {
+            if token == AN_INFIX_OPERATOR {
+                precedence::expected_infix_operators()
+                    .iter()
+                    .map(String::as_str)
+                    .collect()
+            } else {
+                vec![token]
+            }
+        })
+        .collect();
+    expected_tokens.sort();
+    expected_tokens.dedup();
+    if let Some(keyword) = input
+        .get(inner.location)
+        .and_then(|token| keyword(&token.token))
+    {
+        // Only a name can go here, not an expression, so the keyword was
+        // probably meant to be one.
+        if expected_tokens.contains(&"an identifier") && !expected_tokens.contains(&"an integer") {
+            return Error::KeywordAsIdentifier {
+                span,
+                keyword: keyword.to_string(),
+            };
+        }
+    }
+    let construct = innermost_construct(&input[..inner.location.min(input.len())]);
+    Error::ParseError {
+        span,
+        expected_tokens,
+        context: construct.as_ref().map(|construct| construct.context()),
+        context_span: construct.map(|construct| construct.start),
+        hint: application_hint(&input[..inner.location.min(input.len())]),
+    }
+}
+
+/// Marks the position where any infix operator could be parsed. This is
+/// replaced by each of the operators when reporting an error.
+const AN_INFIX_OPERATOR: &str = "an infix operator";
+
+/// An operator which follows its left operand.
+enum Postfix {
+    Infix(Operation, Span, Expr),
+    Typed((Span, Monotype)),
+    Apply(Expr),
+}
+
+impl Postfix {
+    fn apply_to(self, left: Expr) -> Expr {
+        match self {
+            Postfix::Infix(operation, operator_span, right) => {
+                construct_infix(left, operation, operator_span, right)
+            }
+            Postfix::Typed((typ_span, typ)) => Expr::new(
+                left.span,
+                Expression::Typed(Typed {
+                    expression: left,
+                    typ,
+                    typ_span,
+                }),
+            ),
+            Postfix::Apply(argument) => Expr::new(
+                left.span | argument.span,
+                Expression::Apply(Apply {
+                    function: left,
+                    argument,
+                }),
+            ),
+        }
+    }
+}
+
+/// The keyword, if the token is one.
+fn keyword(token: &Token) -> Option<&'static str> {
+    match token {
+        Token::Def => Some("def"),
+        Token::Let => Some("let"),
+        Token::In => Some("in"),
+        Token::Rec => Some("rec"),
+        Token::And => Some("and"),
+        Token::Try => Some("try"),
+        Token::Fn => Some("fn"),
+        Token::Match => Some("match"),
+        _ => None,
+    }
+}
+
+/// Explains how application and infix operators combine, if the tokens end with
+/// an operator directly after a function application, as in `f x -`.
+///
+/// Application binds more tightly than any operator, which is a common source
+/// of surprise when an operator was meant to be part of an argument.
+fn application_hint(tokens: &[AnnotatedToken<Span>]) -> Option<ParseHint> {
+    let [before @ .., AnnotatedToken {
+        token: Token::Operator(operator),
+        ..
+    }] = tokens
+    else {
+        return None;
+    };
+    // find the start of the operand before the operator
+    let argument_start = match before.last().map(|token| &token.token) {
+        Some(Token::EndGroup | Token::EndList) => {
+            let mut depth = 0;
+            before.iter().rposition(|token| {
+                match token.token {
+                    Token::EndGroup | Token::EndList => depth += 1,
+                    Token::StartGroup | Token::StartList => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })?
         }
+        Some(token) if is_atom(token) => before.len() - 1,
+        _ => return None,
+    };
+    // the operand must itself be an argument to something before it
+    let function = before[..argument_start].last()?;
+    if !(is_atom(&function.token) || matches!(function.token, Token::EndGroup | Token::EndList)) {
+        return None;
+    }
+    Some(ParseHint::ApplicationBeforeOperator {
+        operator: precedence::infix_operator(operator)?.symbol,
     })
 }
 
-fn construct_infix(left: Expr, operation: Operation, right: Expr) -> Expr {
+/// Whether the token is an expression on its own.
+fn is_atom(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Identifier(_)
+            | Token::Boolean(_)
+            | Token::Integer(_)
+            | Token::Float(_)
+            | Token::Char(_)
+            | Token::String(_)
+    )
+}
+
+/// A construct which has been started, but not yet finished.
+#[derive(Debug)]
+struct OpenConstruct {
+    kind: ConstructKind,
+    /// The location of the token which started the construct.
+    start: Span,
+}
+
+#[derive(Debug)]
+enum ConstructKind {
+    /// `let`, `try`, or `def`, along with the name, once it is known, and
+    /// whether the `=` has been reached.
+    Binding {
+        keyword: &'static str,
+        name: Option<Identifier>,
+        assigned: bool,
+    },
+    LetRec,
+    Function,
+    MatchValue,
+    MatchPatterns,
+    Group,
+    List,
+}
+
+impl OpenConstruct {
+    fn context(&self) -> ParseContext {
+        let (description, closing_token) = match &self.kind {
+            ConstructKind::Binding {
+                keyword: "def",
+                name: Some(name),
+                assigned: true,
+            } => (format!("the definition of `{}`", name), ";"),
+            ConstructKind::Binding { keyword: "def", .. } => ("a definition".to_string(), ";"),
+            ConstructKind::Binding {
+                keyword,
+                name: Some(name),
+                assigned: true,
+            } => (format!("the body of `{} {} = ...`", keyword, name), "in"),
+            ConstructKind::Binding { keyword, .. } => (format!("a `{}` expression", keyword), "in"),
+            ConstructKind::LetRec => ("a `let rec` expression".to_string(), "in"),
+            ConstructKind::Function => ("the parameters of a function".to_string(), "->"),
+            ConstructKind::MatchValue => ("the value of a `match` expression".to_string(), "{"),
+            ConstructKind::MatchPatterns => {
+                ("the patterns of a `match` expression".to_string(), "}")
+            }
+            ConstructKind::Group => ("a parenthesized expression".to_string(), ")'"),
+            ConstructKind::List => ("a list".to_string(), "']'"),
+        };
+        ParseContext {
+            description,
+            closing_token,
+        }
+    }
+}
+
+/// Finds the innermost construct left unfinished by the tokens.
+///
+/// This only tracks the keywords and brackets which start and finish each
+/// construct, so it can be fooled by malformed input, but it is good enough to
+/// explain what the parser was doing when it failed.
+fn innermost_construct(tokens: &[AnnotatedToken<Span>]) -> Option<OpenConstruct> {
+    let mut stack: Vec<OpenConstruct> = Vec::new();
+    let close = |stack: &mut Vec<OpenConstruct>, matches: fn(&ConstructKind) -> bool| {
+        if let Some(index) = stack.iter().rposition(|construct| matches(&construct.kind)) {
+            stack.truncate(index);
+        }
+    };
+    let mut tokens = tokens.iter().peekable();
+    while let Some(AnnotatedToken { annotation, token }) = tokens.next() {
+        let start = *annotation;
+        match token {
+            Token::Let
+                if matches!(
+                    tokens.peek(),
+                    Some(AnnotatedToken {
+                        token: Token::Rec,
+                        ..
+                    })
+                ) =>
+            {
+                tokens.next();
+                stack.push(OpenConstruct {
+                    kind: ConstructKind::LetRec,
+                    start,
+                });
+            }
+            Token::Let | Token::Try | Token::Def => {
+                let keyword = match token {
+                    Token::Let => "let",
+                    Token::Try => "try",
+                    _ => "def",
+                };
+                let name = match tokens.peek() {
+                    Some(AnnotatedToken {
+                        token: Token::Identifier(name),
+                        ..
+                    }) => Some(name.clone()),
+                    _ => None,
+                };
+                stack.push(OpenConstruct {
+                    kind: ConstructKind::Binding {
+                        keyword,
+                        name,
+                        assigned: false,
+                    },
+                    start,
+                });
+            }
+            Token::Assign => {
+                if let Some(OpenConstruct {
+                    kind: ConstructKind::Binding { assigned, .. },
+                    ..
+                }) = stack.last_mut()
+                {
+                    *assigned = true;
+                }
+            }
+            Token::In => close(&mut stack, |kind| {
+                matches!(
+                    kind,
+                    ConstructKind::Binding {
+                        keyword: "let" | "try",
+                        ..
+                    } | ConstructKind::LetRec
+                )
+            }),
+            // `let` can also start a top-level definition.
+            Token::Separator => {
+                if let Some(OpenConstruct {
+                    kind: ConstructKind::Binding { .. },
+                    ..
+                }) = stack.last()
+                {
+                    stack.pop();
+                }
+            }
+            Token::Fn => stack.push(OpenConstruct {
+                kind: ConstructKind::Function,
+                start,
+            }),
+            Token::Arrow => {
+                if let Some(OpenConstruct {
+                    kind: ConstructKind::Function,
+                    ..
+                }) = stack.last()
+                {
+                    stack.pop();
+                }
+            }
+            Token::Match => stack.push(OpenConstruct {
+                kind: ConstructKind::MatchValue,
+                start,
+            }),
+            Token::BlockStart => {
+                if let Some(OpenConstruct {
+                    kind: kind @ ConstructKind::MatchValue,
+                    ..
+                }) = stack.last_mut()
+                {
+                    *kind = ConstructKind::MatchPatterns;
+                }
+            }
+            Token::BlockEnd => close(&mut stack, |kind| {
+                matches!(kind, ConstructKind::MatchPatterns)
+            }),
+            Token::StartGroup => stack.push(OpenConstruct {
+                kind: ConstructKind::Group,
+                start,
+            }),
+            Token::EndGroup => close(&mut stack, |kind| matches!(kind, ConstructKind::Group)),
+            Token::StartList => stack.push(OpenConstruct {
+                kind: ConstructKind::List,
+                start,
+            }),
+            Token::EndList => close(&mut stack, |kind| matches!(kind, ConstructKind::List)),
+            _ => {}
+        }
+    }
+    stack.pop()
+}
+
+fn construct_infix(left: Expr, operation: Operation, operator_span: Span, right: Expr) -> Expr {
     Expr::new(
         left.span | right.span,
         Expression::Infix(Infix {
             operation,
+            operator_span,
             left,
             right,
         }),
     )
 }
+
+/// Negation is desugared to subtracting the value from zero.
+fn construct_negation(span: Span, zero: Primitive, operation: Operation, value: Expr) -> Expr {
+    Expr::new(
+        span | value.span,
+        Expression::Infix(Infix {
+            operation,
+            operator_span: span,
+            left: Expr::new(span, Expression::Primitive(zero)),
+            right: value,
+        }),
+    )
+}
+
+/// A sequence of several expressions is nested, so that each is evaluated in
+/// turn, and the last provides the result.
+fn construct_sequence(span: Span, first: Expr, rest: Vec<Expr>) -> Expr {
+    let mut expressions = rest;
+    expressions.insert(0, first);
+    let last = expressions.pop().unwrap();
+    let nested = expressions.into_iter().rev().fold(last, |second, first| {
+        Expr::new(
+            first.span | second.span,
+            Expression::Sequence(Sequence { first, second }),
+        )
+    });
+    Expr::new(span, *nested.expression)
+}
+
+/// `try x = value in inner` is desugared to `and_then value (fn x -> inner)`,
+/// so that a failed result short-circuits the rest of the expression.
+///
+/// `and_then` is located at the `try` keyword, and the continuation at the
+/// inner expression.
+fn construct_try(try_span: Span, parameter: Parameter, value: Expr, inner: Expr) -> Expr {
+    let span = try_span | inner.span;
+    let and_then_span = try_span | value.span;
+    let and_then = Expr::new(
+        try_span,
+        Expression::Identifier(Identifier::name_from_str("and_then").unwrap()),
+    );
+    let continuation = Expr::new(
+        inner.span,
+        Expression::Function(Function {
+            parameters: vec![parameter],
+            body: inner,
+        }),
+    );
+    Expr::new(
+        span,
+        Expression::Apply(Apply {
+            function: Expr::new(
+                and_then_span,
+                Expression::Apply(Apply {
+                    function: and_then,
+                    argument: value,
+                }),
+            ),
+            argument: continuation,
+        }),
+    )
+}
+
+/// A type annotation on a binding is equivalent to annotating the value.
+fn construct_annotated_binding(value: Expr, typ: Option<(Span, Monotype)>) -> Expr {
+    match typ {
+        None => value,
+        Some((typ_span, typ)) => Expr::new(
+            value.span,
+            Expression::Typed(Typed {
+                expression: value,
+                typ,
+                typ_span,
+            }),
+        ),
+    }
+}
@@ -0,0 +1,153 @@
+//! The precedence and associativity of each operator.
+//!
+//! Expressions are parsed by precedence climbing: each kind of expression has
+//! a precedence, and an operator only takes an operand which binds at least as
+//! tightly as itself. Higher precedences bind more tightly.
+//!
+//! Infix operators are described entirely by [`INFIX_OPERATORS`], so adding one
+//! only requires a new entry, along with a token for it in the lexer.
+
+use std::sync::OnceLock;
+
+use boo_language::Operation;
+
+/// How tightly an expression binds to its neighbours.
+pub type Precedence = u8;
+
+/// The body of a `let`, `let rec`, or `try` expression, which extends as far
+/// as possible.
+pub const BINDING: Precedence = 0;
+
+/// A type annotation, `expression: Type`.
+pub const TYPE_ANNOTATION: Precedence = 10;
+
+/// The body of a function, `fn x -> body`.
+pub const FUNCTION: Precedence = 20;
+
+/// Negation, `-x` or `-.x`.
+pub const NEGATION: Precedence = 120;
+
+/// Function application, `f x`.
+pub const APPLICATION: Precedence = 130;
+
+/// Whether a chain of operators of the same precedence groups to the left or
+/// to the right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    /// `a - b - c` is parsed as `(a - b) - c`.
+    Left,
+    /// `a :: b :: c` is parsed as `a :: (b :: c)`.
+    Right,
+}
+
+/// An infix operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InfixOperator {
+    /// The operator as it is written.
+    pub symbol: &'static str,
+    /// The operation it represents.
+    pub operation: Operation,
+    pub precedence: Precedence,
+    pub associativity: Associativity,
+}
+
+impl InfixOperator {
+    const fn new(
+        symbol: &'static str,
+        operation: Operation,
+        precedence: Precedence,
+        associativity: Associativity,
+    ) -> Self {
+        Self {
+            symbol,
+            operation,
+            precedence,
+            associativity,
+        }
+    }
+
+    /// The minimum precedence of the right operand.
+    pub fn right_precedence(&self) -> Precedence {
+        match self.associativity {
+            Associativity::Left => self.precedence + 1,
+            Associativity::Right => self.precedence,
+        }
+    }
+}
+
+/// Every infix operator, from the most loosely-binding to the most tightly.
+pub const INFIX_OPERATORS: &[InfixOperator] = {
+    use Associativity::*;
+    use Operation::*;
+    &[
+        InfixOperator::new("==", Equal, 40, Left),
+        InfixOperator::new("!=", NotEqual, 40, Left),
+        InfixOperator::new("<", LessThan, 40, Left),
+        InfixOperator::new("<=", LessThanOrEqual, 40, Left),
+        InfixOperator::new(">", GreaterThan, 40, Left),
+        InfixOperator::new(">=", GreaterThanOrEqual, 40, Left),
+        InfixOperator::new("::", Cons, 50, Right),
+        InfixOperator::new("|", BitwiseOr, 60, Left),
+        InfixOperator::new("xor", BitwiseXor, 70, Left),
+        InfixOperator::new("&", BitwiseAnd, 80, Left),
+        InfixOperator::new("<<", ShiftLeft, 90, Left),
+        InfixOperator::new(">>", ShiftRight, 90, Left),
+        InfixOperator::new("+", Add, 100, Left),
+        InfixOperator::new("-", Subtract, 100, Left),
+        InfixOperator::new("++", Concatenate, 100, Left),
+        InfixOperator::new("+.", AddFloat, 100, Left),
+        InfixOperator::new("-.", SubtractFloat, 100, Left),
+        InfixOperator::new("*", Multiply, 110, Left),
+        InfixOperator::new("/", Divide, 110, Left),
+        InfixOperator::new("%", Modulo, 110, Left),
+        InfixOperator::new("*.", MultiplyFloat, 110, Left),
+        InfixOperator::new("/.", DivideFloat, 110, Left),
+    ]
+};
+
+/// Looks up an infix operator by its symbol.
+pub fn infix_operator(symbol: &str) -> Option<&'static InfixOperator> {
+    INFIX_OPERATORS
+        .iter()
+        .find(|operator| operator.symbol == symbol)
+}
+
+/// How each infix operator is described when it is expected by the parser.
+/// As with keywords, words are written as they are, and symbols are quoted.
+pub(crate) fn expected_infix_operators() -> &'static [String] {
+    static EXPECTED: OnceLock<Vec<String>> = OnceLock::new();
+    EXPECTED.get_or_init(|| {
+        INFIX_OPERATORS
+            .iter()
+            .map(|operator| {
+                if operator.symbol.chars().all(char::is_alphabetic) {
+                    operator.symbol.to_string()
+                } else {
+                    format!("'{}'", operator.symbol)
+                }
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operators_are_ordered_by_precedence() {
+        assert!(INFIX_OPERATORS
+            .windows(2)
+            .all(|pair| pair[0].precedence <= pair[1].precedence));
+        assert!(INFIX_OPERATORS
+            .iter()
+            .all(|operator| FUNCTION < operator.precedence && operator.precedence < NEGATION));
+    }
+
+    #[test]
+    fn test_each_operator_symbol_is_unique() {
+        for operator in INFIX_OPERATORS {
+            assert_eq!(infix_operator(operator.symbol), Some(operator));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use proptest::prelude::*;
+
+use boo_core::span::Span;
+use boo_parser::incremental::*;
+use boo_test_helpers::proptest::*;
+
+const REPLACEMENTS: &[&str] = &[
+    "",
+    " ",
+    "\n",
+    "1",
+    "x",
+    "+ 2",
+    "-",
+    ".5",
+    ";",
+    "(",
+    ")",
+    "in",
+    "let y = 3 in",
+    "def y = 4;\n",
+    "\"",
+    "# comment",
+];
+
+#[test]
+fn test_reparsing_matches_parsing_from_scratch() {
+    let strategy = (
+        proptest::collection::vec(boo_generator::arbitrary(), 0..4),
+        boo_generator::arbitrary(),
+        any::<prop::sample::Index>(),
+        0usize..8,
+        prop::sample::select(REPLACEMENTS),
+    );
+    check(
+        &strategy,
+        |(definitions, main, position, length, replacement)| {
+            let mut input = String::new();
+            for (i, definition) in definitions.into_iter().enumerate() {
+                input.push_str(&format!("def d{} = {};\n", i, definition));
+            }
+            input.push_str(&main.to_string());
+
+            let start = floor_char_boundary(&input, position.index(input.len() + 1));
+            let end = floor_char_boundary(&input, (start + length).min(input.len()));
+            let edit = TextEdit {
+                range: (start..end).into(),
+                replacement: replacement.to_string(),
+            };
+            let edited = edit.apply(&input);
+
+            let previous = ParsedProgram::parse(&input)?;
+            let reparsed = previous.reparse(&edited, &edit);
+            let expected = ParsedProgram::parse(&edited);
+            prop_assert_eq!(
+                reparsed,
+                expected,
+                "\ninput = {:?}\nedited = {:?}\n",
+                input,
+                edited
+            );
+            Ok(())
+        },
+    )
+}
+
+#[test]
+fn test_reparsing_an_edited_definition() -> boo_core::error::Result<()> {
+    let input = "def double = fn x -> x * 2;\ndef triple = fn x -> x * 3;\ndouble (triple 7)";
+    let previous = ParsedProgram::parse(input)?;
+
+    let edit = TextEdit {
+        range: Span { start: 25, end: 26 },
+        replacement: "20".to_string(),
+    };
+    let edited = edit.apply(input);
+    let reparsed = previous.reparse(&edited, &edit)?;
+
+    assert_eq!(
+        edited,
+        "def double = fn x -> x * 20;\ndef triple = fn x -> x * 3;\ndouble (triple 7)"
+    );
+    assert_eq!(reparsed, ParsedProgram::parse(&edited)?);
+    assert_eq!(
+        reparsed.program().definitions[1].value.span,
+        Span { start: 42, end: 55 }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_reparsing_into_an_error() -> boo_core::error::Result<()> {
+    let input = "def one = 1;\ndef two = 2;\none + two";
+    let previous = ParsedProgram::parse(input)?;
+
+    let edit = TextEdit {
+        range: Span { start: 11, end: 12 },
+        replacement: "".to_string(),
+    };
+    let edited = edit.apply(input);
+    let reparsed = previous.reparse(&edited, &edit);
+
+    assert!(reparsed.is_err());
+    assert_eq!(reparsed, ParsedProgram::parse(&edited));
+    Ok(())
+}
+
+#[test]
+fn test_reparsing_an_extended_final_definition() -> boo_core::error::Result<()> {
+    let input = "def one = 1;\nlet two = 2";
+    let previous = ParsedProgram::parse(input)?;
+
+    let edit = TextEdit {
+        range: Span { start: 24, end: 24 },
+        replacement: " + one".to_string(),
+    };
+    let edited = edit.apply(input);
+    let reparsed = previous.reparse(&edited, &edit)?;
+
+    assert_eq!(edited, "def one = 1;\nlet two = 2 + one");
+    assert_eq!(reparsed, ParsedProgram::parse(&edited)?);
+    assert_eq!(reparsed.program().main, None);
+    Ok(())
+}
+
+fn floor_char_boundary(input: &str, mut index: usize) -> usize {
+    while !input.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
@@ -29,7 +29,7 @@ fn main() -> anyhow::Result<()> {
 
     let evaluator = {
         let mut context = boo::evaluator::new();
-        builtins::prepare(&mut context)?;
+        builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
         context.evaluator()
     };
 
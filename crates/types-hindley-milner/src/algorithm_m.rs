@@ -1,81 +1,107 @@
-#![cfg(test)] // not finished yet; see the broken tests below
+//! Algorithm M, which pushes the expected type of each expression downwards,
+//! so that mismatches are reported at the smallest expression that causes them.
 
 use boo_core::builtins;
 use boo_core::error::{Error, Result};
 use boo_core::expr::{self, Expr, Expression};
-use boo_core::primitive::Primitive;
-use boo_core::types::{Monotype, Polytype, Type};
+use boo_core::types::{Monotype, Polytype, Type, TypeName};
 
+use crate::algorithm_w::{float_operator_instead, infer_pattern, instantiate, polytype_of};
 use crate::env::Env;
 use crate::fresh::FreshVariables;
 use crate::subst::Subst;
 use crate::types::{FreeVariables, Monomorphic, Polymorphic};
 use crate::unification::unify;
 
-pub fn type_of(expr: &Expr) -> Result<Monotype> {
-    let base_context = builtins::types()
-        .map(|(name, typ)| (name.clone(), typ))
-        .collect::<Env>();
+pub fn type_of(expr: &Expr, globals: &builtins::Globals) -> Result<Monotype> {
+    let base_context = globals.types().collect::<Env>();
     let mut fresh = FreshVariables::new();
     let target = Monotype::from(Type::Variable(fresh.next()));
-    let subst = infer(base_context, &mut fresh, expr, target.clone())?;
-    let mut result = target;
-    loop {
-        let next = result.substitute(&subst);
-        if result == next {
-            break;
-        }
-        result = next;
-    }
-    Ok(result)
+    let subst = infer(base_context, &mut fresh, expr, &target)?;
+    Ok(target.substitute(&subst))
 }
 
+/// Infers the substitution required for the expression to have the target
+/// type.
+///
+/// The environment and the target type are expected to have had all previous
+/// substitutions applied already.
 fn infer(
     env: Env,
     fresh: &mut FreshVariables,
     expr: &Expr,
-    target_type: Monotype,
+    target_type: &Monotype,
 ) -> Result<Subst> {
+    if let Type::Forall { quantifiers, body } = target_type.as_ref() {
+        // the quantified variables must remain abstract, so each is replaced
+        // with a fresh type that only unifies with itself
+        let skolems = quantifiers
+            .iter()
+            .map(|quantifier| {
+                let skolem = Type::Constructor {
+                    name: TypeName::new(fresh.next().to_string()),
+                    arguments: vec![],
+                };
+                (quantifier.clone(), skolem.into())
+            })
+            .collect::<Subst>();
+        return infer(env, fresh, expr, &body.substitute(&skolems));
+    }
     match expr.expression() {
-        Expression::Primitive(Primitive::Integer(_)) => unify(&target_type, &Type::Integer.into())
-            .ok_or_else(|| Error::TypeMismatch {
-                span: expr.span(),
-                expected_type: target_type,
-                actual_type: Type::Integer.into(),
-            }),
-        Expression::Native(_) => unreachable!("Native expression without a type."),
-        Expression::Identifier(identifier) => env
-            .get(identifier)
-            .ok_or_else(|| Error::UnknownVariable {
-                span: expr.span(),
-                name: identifier.to_string(),
+        Expression::Primitive(primitive) => {
+            let primitive_type = primitive.get_type();
+            unify(target_type, &primitive_type).map_err(|failure| {
+                failure.into_error(Error::TypeMismatch {
+                    span: expr.span(),
+                    expected_type: target_type.clone(),
+                    actual_type: primitive_type,
+                })
             })
-            .and_then(|typ| {
-                let source_type = typ.substitute(&Subst::empty(), fresh).mono;
-                unify(&target_type, &source_type).ok_or(Error::TypeMismatch {
+        }
+        Expression::Native(_) => unreachable!("Native expression without a type."),
+        Expression::Identifier(identifier) => {
+            let typ = env.get(identifier).ok_or_else(|| {
+                Error::unknown_variable(expr.span(), identifier, env.identifiers())
+            })?;
+            let source_type = instantiate(typ, fresh);
+            unify(target_type, &source_type).map_err(|failure| {
+                failure.into_error(Error::TypeMismatch {
                     span: expr.span(),
-                    expected_type: target_type,
+                    expected_type: target_type.clone(),
                     actual_type: source_type,
                 })
-            }),
-        Expression::Function(expr::Function { parameter, body }) => {
-            let parameter_type = Monotype::from(Type::Variable(fresh.next()));
+            })
+        }
+        Expression::Function(expr::Function {
+            parameter,
+            parameter_type,
+            body,
+            ..
+        }) => {
+            let parameter_type = parameter_type
+                .clone()
+                .unwrap_or_else(|| Type::Variable(fresh.next()).into());
             let body_type = Monotype::from(Type::Variable(fresh.next()));
             let source_type = Monotype::from(Type::Function {
                 parameter: parameter_type.clone(),
                 body: body_type.clone(),
             });
-            let function_subst = unify(&target_type, &source_type).ok_or(Error::TypeMismatch {
-                span: expr.span(),
-                expected_type: target_type,
-                actual_type: source_type,
-            })?;
-            let substituted_body_type = body_type.substitute(&function_subst);
+            let function_subst = match unify(target_type, &source_type) {
+                Ok(subst) => subst,
+                Err(failure) => {
+                    return Err(failure.into_error(mismatch(env, fresh, expr, target_type)));
+                }
+            };
             let body_env = env.substitute(&function_subst, fresh).update(
                 parameter.clone(),
-                Polytype::unquantified(parameter_type.substitute(&function_subst)),
+                polytype_of(&parameter_type.substitute(&function_subst)),
             );
-            let body_subst = infer(body_env, fresh, body, substituted_body_type)?;
+            let body_subst = infer(
+                body_env,
+                fresh,
+                body,
+                &body_type.substitute(&function_subst),
+            )?;
             Ok(function_subst.then(&body_subst))
         }
         Expression::Apply(expr::Apply { function, argument }) => {
@@ -84,79 +110,270 @@ fn infer(
                 parameter: parameter_type.clone(),
                 body: target_type.clone(),
             });
-            let function_subst = infer(env.clone(), fresh, function, function_type.clone())?;
-            let argument_type = parameter_type.substitute(&function_subst);
-            let argument_env = env.substitute(&function_subst, fresh);
-            let argument_subst = infer(argument_env, fresh, argument, argument_type.clone())?;
-            function_subst
-                .merge(&argument_subst)
-                .ok_or_else(|| Error::TypeMismatch {
-                    span: argument.span(),
-                    expected_type: target_type.substitute(&function_subst),
-                    actual_type: argument_type.substitute(&function_subst.then(&argument_subst)),
-                })
+            let function_subst = infer(env.clone(), fresh, function, &function_type)?;
+            let argument_subst = infer(
+                env.substitute(&function_subst, fresh),
+                fresh,
+                argument,
+                &parameter_type.substitute(&function_subst),
+            )
+            .map_err(|error| match error {
+                // the argument is at fault, but the operator may be mistaken
+                Error::TypeMismatch {
+                    span,
+                    expected_type,
+                    actual_type,
+                } if span == argument.span() => {
+                    let function_type = function_type.substitute(&function_subst);
+                    match float_operator_instead(function, &function_type, &actual_type) {
+                        Some((operator, float_operator)) => Error::FloatInIntegerOperation {
+                            left_span: function.span(),
+                            left_type: function_type,
+                            right_span: span,
+                            right_type: actual_type,
+                            operator,
+                            float_operator,
+                        },
+                        None => Error::TypeMismatch {
+                            span,
+                            expected_type,
+                            actual_type,
+                        },
+                    }
+                }
+                error => error,
+            })?;
+            Ok(function_subst.then(&argument_subst))
         }
         Expression::Assign(expr::Assign { name, value, inner }) => {
             let value_type = Monotype::from(Type::Variable(fresh.next()));
-            let value_subst = infer(env.clone(), fresh, value, value_type.clone())?;
+            let value_subst = infer(env.clone(), fresh, value, &value_type)?;
             let substituted_value_type = value_type.substitute(&value_subst);
-            let inner_type = target_type.substitute(&value_subst);
-            let inner_env = env.substitute(&value_subst, fresh).update(
+            let substituted_env = env.substitute(&value_subst, fresh);
+            let inner_env = substituted_env.update(
                 name.clone(),
                 Polytype {
                     quantifiers: substituted_value_type
                         .free()
-                        .relative_complement(env.free())
+                        .relative_complement(substituted_env.free())
                         .into_iter()
                         .collect(),
-                    mono: substituted_value_type.substitute(&value_subst),
+                    mono: substituted_value_type,
+                },
+            );
+            let inner_subst = infer(
+                inner_env,
+                fresh,
+                inner,
+                &target_type.substitute(&value_subst),
+            )?;
+            Ok(value_subst.then(&inner_subst))
+        }
+        Expression::AssignRecursive(expr::AssignRecursive { bindings, inner }) => {
+            // infer the group monomorphically, then generalize each binding
+            let value_types = bindings
+                .iter()
+                .map(|_| Monotype::from(Type::Variable(fresh.next())))
+                .collect::<Vec<_>>();
+            let group_env = bindings.iter().zip(value_types.iter()).fold(
+                env.clone(),
+                |group_env, ((name, _), value_type)| {
+                    group_env.update(name.clone(), Polytype::unquantified(value_type.clone()))
+                },
+            );
+            let mut value_subst = Subst::empty();
+            for ((_, value), value_type) in bindings.iter().zip(value_types.iter()) {
+                let subst = infer(
+                    group_env.substitute(&value_subst, fresh),
+                    fresh,
+                    value,
+                    &value_type.substitute(&value_subst),
+                )?;
+                value_subst = value_subst.then(&subst);
+            }
+            let generalizing_env = env.substitute(&value_subst, fresh);
+            let env_free = generalizing_env.free();
+            let inner_env = bindings.iter().zip(value_types.iter()).fold(
+                generalizing_env,
+                |inner_env, ((name, _), value_type)| {
+                    let substituted_value_type = value_type.substitute(&value_subst);
+                    inner_env.update(
+                        name.clone(),
+                        Polytype {
+                            quantifiers: substituted_value_type
+                                .free()
+                                .relative_complement(env_free.clone())
+                                .into_iter()
+                                .collect(),
+                            mono: substituted_value_type,
+                        },
+                    )
                 },
             );
-            let inner_subst = infer(inner_env, fresh, inner, inner_type)?;
+            let inner_subst = infer(
+                inner_env,
+                fresh,
+                inner,
+                &target_type.substitute(&value_subst),
+            )?;
             Ok(value_subst.then(&inner_subst))
         }
         Expression::Match(expr::Match { value, patterns }) => {
+            if patterns.is_empty() {
+                return Err(Error::MatchWithoutBaseCase { span: expr.span() });
+            }
             let value_type = Monotype::from(Type::Variable(fresh.next()));
-            let _ = infer(env.clone(), fresh, value, value_type)?;
-            patterns.iter().try_fold(
-                Subst::empty(),
-                |subst, expr::PatternMatch { pattern: _, result }| {
-                    let result_subst = infer(env.clone(), fresh, result, target_type.clone())?;
-                    subst
-                        .merge(&result_subst)
-                        .ok_or_else(|| Error::TypeMismatch {
-                            span: expr.span(),
-                            expected_type: target_type.substitute(&subst),
-                            actual_type: target_type.substitute(&result_subst),
-                        })
+            let mut subst = infer(env.clone(), fresh, value, &value_type)?;
+            for expr::PatternMatch {
+                pattern,
+                pattern_span,
+                result,
+            } in patterns
+            {
+                let (pattern_subst, pattern_bindings) = infer_pattern(
+                    fresh,
+                    pattern,
+                    *pattern_span,
+                    &value_type.substitute(&subst),
+                    value.span(),
+                )?;
+                subst = subst.then(&pattern_subst);
+                let result_env = pattern_bindings.into_iter().fold(
+                    env.substitute(&subst, fresh),
+                    |result_env, (name, typ)| {
+                        result_env.update(name, Polytype::unquantified(typ.substitute(&subst)))
+                    },
+                );
+                // each result is checked against the type of the ones before
+                let result_subst =
+                    infer(result_env, fresh, result, &target_type.substitute(&subst))?;
+                subst = subst.then(&result_subst);
+            }
+            Ok(subst)
+        }
+        Expression::Typed(expr::Typed {
+            expression,
+            typ,
+            typ_span,
+        }) => {
+            let typ_subst = unify(target_type, typ).map_err(|failure| {
+                failure.into_error(Error::TypeUnificationError {
+                    left_span: expr.span(),
+                    left_type: target_type.clone(),
+                    right_span: *typ_span,
+                    right_type: typ.clone(),
+                })
+            })?;
+            let expression_subst = infer(
+                env.substitute(&typ_subst, fresh),
+                fresh,
+                expression,
+                &typ.substitute(&typ_subst),
+            )?;
+            Ok(typ_subst.then(&expression_subst))
+        }
+        Expression::Sequence(expr::Sequence { first, second }) => {
+            let first_type = Monotype::from(Type::Variable(fresh.next()));
+            let first_subst = infer(env.clone(), fresh, first, &first_type)?;
+            let second_subst = infer(
+                env.substitute(&first_subst, fresh),
+                fresh,
+                second,
+                &target_type.substitute(&first_subst),
+            )?;
+            Ok(first_subst.then(&second_subst))
+        }
+        Expression::Tuple(expr::Tuple { elements }) => {
+            let element_types = elements
+                .iter()
+                .map(|_| Monotype::from(Type::Variable(fresh.next())))
+                .collect::<Vec<_>>();
+            let source_type = Monotype::from(Type::Tuple(element_types.clone()));
+            let tuple_subst = match unify(target_type, &source_type) {
+                Ok(subst) => subst,
+                Err(failure) => {
+                    return Err(failure.into_error(mismatch(env, fresh, expr, target_type)));
+                }
+            };
+            elements.iter().zip(element_types).try_fold(
+                tuple_subst,
+                |subst, (element, element_type)| {
+                    let element_subst = infer(
+                        env.substitute(&subst, fresh),
+                        fresh,
+                        element,
+                        &element_type.substitute(&subst),
+                    )?;
+                    Ok(subst.then(&element_subst))
                 },
             )
         }
-        Expression::Typed(expr::Typed { expression, typ }) => {
-            let expression_subst = infer(env.clone(), fresh, expression, target_type.clone())?;
-            unify(&target_type, typ)
-                .and_then(|typ_subst| expression_subst.merge(&typ_subst))
-                .ok_or_else(|| Error::TypeMismatch {
-                    span: expression.span(),
-                    expected_type: typ.clone(),
-                    actual_type: target_type.substitute(&expression_subst),
+        Expression::List(expr::List::Empty) => {
+            let source_type = Monotype::from(Type::List(Type::Variable(fresh.next()).into()));
+            unify(target_type, &source_type).map_err(|failure| {
+                failure.into_error(Error::TypeMismatch {
+                    span: expr.span(),
+                    expected_type: target_type.clone(),
+                    actual_type: source_type,
                 })
+            })
         }
+        Expression::List(expr::List::Cons { head, tail }) => {
+            let element_type = Monotype::from(Type::Variable(fresh.next()));
+            let source_type = Monotype::from(Type::List(element_type.clone()));
+            let list_subst = match unify(target_type, &source_type) {
+                Ok(subst) => subst,
+                Err(failure) => {
+                    return Err(failure.into_error(mismatch(env, fresh, expr, target_type)));
+                }
+            };
+            let head_subst = infer(
+                env.substitute(&list_subst, fresh),
+                fresh,
+                head,
+                &element_type.substitute(&list_subst),
+            )?;
+            let subst = list_subst.then(&head_subst);
+            let tail_subst = infer(
+                env.substitute(&subst, fresh),
+                fresh,
+                tail,
+                &source_type.substitute(&subst),
+            )?;
+            Ok(subst.then(&tail_subst))
+        }
+    }
+}
+
+/// Constructs the error for an expression that cannot have the expected type.
+///
+/// The actual type is inferred from scratch, so that the error can describe
+/// the whole expression rather than just its outermost shape.
+fn mismatch(env: Env, fresh: &mut FreshVariables, expr: &Expr, expected_type: &Monotype) -> Error {
+    let actual_type = Monotype::from(Type::Variable(fresh.next()));
+    match infer(env, fresh, expr, &actual_type) {
+        Ok(subst) => Error::TypeMismatch {
+            span: expr.span(),
+            expected_type: expected_type.clone(),
+            actual_type: actual_type.substitute(&subst),
+        },
+        Err(error) => error,
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::rc::Rc;
+
     use proptest::prelude::*;
 
     use boo_core::identifier::Identifier;
     use boo_core::types::TypeVariable;
-    use boo_parser::parse;
+    use boo_parser::parse_expr;
     use boo_test_helpers::proptest::check;
 
     use super::*;
 
-    #[ignore]
     #[test]
     fn test_arbitrary_expressions() {
         let generator = boo_generator::gen(
@@ -171,19 +388,72 @@ mod tests {
             eprintln!("rendered: {rendered}");
             let expr = input.clone().to_core()?;
 
-            let actual_type = type_of(&expr)?;
+            let actual_type = type_of(&expr, &builtins::Globals::default())?;
 
             prop_assert_eq!(actual_type, Type::Integer.into());
             Ok(())
         })
     }
 
+    #[test]
+    fn test_arbitrary_expressions_within_a_node_budget() {
+        let generator = boo_generator::gen(
+            boo_generator::ExprGenConfig {
+                gen_identifier: Identifier::gen_ascii(1..=16).boxed().into(),
+                nodes: Some(30),
+                ..Default::default()
+            }
+            .into(),
+        );
+        check(&generator, |input| {
+            let expr = input.clone().to_core()?;
+
+            let actual_type = type_of(&expr, &builtins::Globals::default())?;
+
+            prop_assert_eq!(actual_type, Type::Integer.into());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_arbitrary_expressions_of_other_types() {
+        let function_type: Monotype = Type::Function {
+            parameter: Type::Integer.into(),
+            body: Type::Tuple(vec![Type::Boolean.into(), Type::String.into()]).into(),
+        }
+        .into();
+        let generator = boo_generator::gen_typed(
+            Rc::new(boo_generator::ExprGenConfig {
+                gen_identifier: Identifier::gen_ascii(1..=16).boxed().into(),
+                ..Default::default()
+            }),
+            function_type.clone().into(),
+        );
+        check(&generator, |input| {
+            // the function may be more general than the target type, e.g. if
+            // it does not use its parameter
+            let expr = Expr::new(
+                None,
+                Expression::Typed(expr::Typed {
+                    expression: input.clone().to_core()?,
+                    typ: function_type.clone(),
+                    typ_span: None,
+                }),
+            );
+
+            let actual_type = type_of(&expr, &builtins::Globals::default())?;
+
+            prop_assert_eq!(actual_type, function_type.clone());
+            Ok(())
+        })
+    }
+
     #[test]
     fn test_rejects_incorrect_types() -> Result<()> {
         let program = "1 + (fn x -> 3)";
-        let ast = parse(program)?.to_core()?;
+        let ast = parse_expr(program)?.to_core()?;
 
-        let result = type_of(&ast);
+        let result = type_of(&ast, &builtins::Globals::default());
 
         assert_eq!(
             result,
@@ -191,8 +461,8 @@ mod tests {
                 span: Some((5..14).into()),
                 expected_type: Type::Integer.into(),
                 actual_type: Type::Function {
-                    parameter: Type::Variable(TypeVariable::new_from_str("_5")).into(),
-                    body: Type::Variable(TypeVariable::new_from_str("_6")).into(), // TODO: should be `Type::Integer`
+                    parameter: Type::Variable(TypeVariable::new_from_str("_74")).into(),
+                    body: Type::Integer.into(),
                 }
                 .into(),
             }),
@@ -200,23 +470,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_suggests_float_operators_for_floats_given_to_integer_operators() -> Result<()> {
+        let program = "1.5 + 2.5";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::FloatInIntegerOperation {
+                left_span: Some((4..5).into()),
+                left_type: Type::Function {
+                    parameter: Type::Integer.into(),
+                    body: Type::Function {
+                        parameter: Type::Integer.into(),
+                        body: Type::Integer.into(),
+                    }
+                    .into(),
+                }
+                .into(),
+                right_span: Some((0..3).into()),
+                right_type: Type::Float.into(),
+                operator: "+".to_string(),
+                float_operator: "+.".to_string(),
+            }),
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_parameters_are_monomorphic() -> Result<()> {
         let program = "fn x -> x x";
-        let ast = parse(program)?.to_core()?;
+        let ast = parse_expr(program)?.to_core()?;
+        let function: Monotype = Type::Function {
+            parameter: Type::Variable(TypeVariable::new_from_str("_37")).into(),
+            body: Type::Variable(TypeVariable::new_from_str("_2")).into(),
+        }
+        .into();
 
-        let result = type_of(&ast);
+        let result = type_of(&ast, &builtins::Globals::default());
 
         assert_eq!(
             result,
-            Err(Error::TypeMismatch {
-                span: Some((10..11).into()),
-                expected_type: Type::Variable(TypeVariable::new_from_str("_4")).into(),
-                actual_type: Type::Function {
-                    parameter: Type::Variable(TypeVariable::new_from_str("_4")).into(),
-                    body: Type::Variable(TypeVariable::new_from_str("_2")).into(),
-                }
-                .into()
+            Err(Error::InfiniteType {
+                left_span: Some((10..11).into()),
+                left_type: function.clone(),
+                right_span: None,
+                right_type: Type::Variable(TypeVariable::new_from_str("_37")).into(),
+                variable: TypeVariable::new_from_str("_37"),
+                typ: function,
             }),
         );
         Ok(())
@@ -225,18 +528,18 @@ mod tests {
     #[test]
     fn test_match_expressions_must_be_of_the_same_type() -> Result<()> {
         let program = "match 0 { 1 -> 2; _ -> fn x -> x }";
-        let ast = parse(program)?.to_core()?;
+        let ast = parse_expr(program)?.to_core()?;
 
-        let result = type_of(&ast);
+        let result = type_of(&ast, &builtins::Globals::default());
 
         assert_eq!(
             result,
             Err(Error::TypeMismatch {
-                span: Some((0..34).into()), // TODO: should be `(23..32)`
+                span: Some((23..32).into()),
                 expected_type: Type::Integer.into(),
                 actual_type: Type::Function {
-                    parameter: Type::Variable(TypeVariable::new_from_str("_2")).into(),
-                    body: Type::Variable(TypeVariable::new_from_str("_3")).into(), // TOOD: should be `"_2"`
+                    parameter: Type::Variable(TypeVariable::new_from_str("_73")).into(),
+                    body: Type::Variable(TypeVariable::new_from_str("_73")).into(),
                 }
                 .into(),
             }),
@@ -244,12 +547,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_higher_rank_parameters_can_be_used_at_different_types() -> Result<()> {
+        let program = "let both: (forall a. a -> a) -> (Integer, Boolean) = fn f -> (f 1, f true) in both (fn x -> x)";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Ok(Type::Tuple(vec![Type::Integer.into(), Type::Boolean.into()]).into()),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuple_patterns_bind_variables() -> Result<()> {
+        let program = "fn pair -> match pair { (x, true) -> x + 1; _ -> 0 }";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Ok(Type::Function {
+                parameter: Type::Tuple(vec![Type::Integer.into(), Type::Boolean.into()]).into(),
+                body: Type::Integer.into(),
+            }
+            .into()),
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_type_annotations_are_respected() -> Result<()> {
         let program = "(fn x -> x + 1): Integer";
-        let ast = parse(program)?.to_core()?;
+        let ast = parse_expr(program)?.to_core()?;
 
-        let result = type_of(&ast);
+        let result = type_of(&ast, &builtins::Globals::default());
 
         assert_eq!(
             result,
@@ -257,8 +592,8 @@ mod tests {
                 span: Some((1..14).into()),
                 expected_type: Type::Integer.into(),
                 actual_type: Type::Function {
-                    parameter: Type::Variable(TypeVariable::new_from_str("_1")).into(), // TOOD: should be `Type::Integer`
-                    body: Type::Variable(TypeVariable::new_from_str("_2")).into(), // TOOD: should be `Type::Integer`
+                    parameter: Type::Integer.into(),
+                    body: Type::Integer.into(),
                 }
                 .into(),
             }),
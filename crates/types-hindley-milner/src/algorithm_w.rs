@@ -1,152 +1,916 @@
 use boo_core::builtins;
 use boo_core::error::{Error, Result};
 use boo_core::expr::{self, Expr, Expression};
-use boo_core::primitive::Primitive;
-use boo_core::types::{Monotype, Polytype, Type};
+use boo_core::identifier::Identifier;
+use boo_core::span::Span;
+use boo_core::types::{Monotype, Polytype, Type, TypeName};
 
 use crate::env::Env;
 use crate::fresh::FreshVariables;
 use crate::subst::Subst;
+use crate::typed::{Annotations, TypedExpr};
 use crate::types::{FreeVariables, Monomorphic, Polymorphic};
 use crate::unification::unify;
 
-pub fn type_of(expr: &Expr) -> Result<Monotype> {
-    let base_context = builtins::types()
-        .map(|(name, typ)| (name.clone(), typ))
-        .collect::<Env>();
+pub fn type_of(expr: &Expr, globals: &builtins::Globals) -> Result<Monotype> {
+    let base_context = globals.types().collect::<Env>();
     let mut fresh = FreshVariables::new();
-    let (_, typ) = infer(base_context, &mut fresh, expr)?;
+    let (_, typ) = infer(base_context, &mut fresh, &mut Recorder::default(), expr)?;
     Ok(typ)
 }
 
-fn infer(env: Env, fresh: &mut FreshVariables, expr: &Expr) -> Result<(Subst, Monotype)> {
+pub fn annotate(expr: &Expr, globals: &builtins::Globals) -> Result<TypedExpr> {
+    let base_context = globals.types().collect::<Env>();
+    let mut fresh = FreshVariables::new();
+    let mut recorder = Recorder::default();
+    let (subst, _) = infer(base_context, &mut fresh, &mut recorder, expr)?;
+    Ok(recorder.annotations.annotate(expr, &subst))
+}
+
+pub fn type_errors(expr: &Expr, globals: &builtins::Globals) -> Vec<Error> {
+    annotate_collecting_errors(expr, globals)
+        .err()
+        .unwrap_or_default()
+}
+
+pub fn annotate_collecting_errors(
+    expr: &Expr,
+    globals: &builtins::Globals,
+) -> std::result::Result<TypedExpr, Vec<Error>> {
+    let base_context = globals.types().collect::<Env>();
+    let mut fresh = FreshVariables::new();
+    let mut recorder = Recorder {
+        errors: Some(vec![]),
+        ..Default::default()
+    };
+    // errors are recorded rather than returned, so the result only says whether
+    // there were any
+    let result = infer(base_context, &mut fresh, &mut recorder, expr);
+    match (result, recorder.errors) {
+        (Ok((subst, _)), Some(errors)) if errors.is_empty() => {
+            Ok(recorder.annotations.annotate(expr, &subst))
+        }
+        (_, errors) => Err(errors.unwrap_or_default()),
+    }
+}
+
+/// Information recorded during inference, alongside the resulting type.
+#[derive(Default)]
+struct Recorder {
+    /// The type of each subexpression.
+    annotations: Annotations,
+    /// If present, type errors are recorded here, and inference continues.
+    errors: Option<Vec<Error>>,
+}
+
+impl Recorder {
+    /// Records the error if we are collecting errors, and replaces the failed
+    /// expression with a fresh type variable, which will not cause any
+    /// further errors. Otherwise, returns the error.
+    fn recover(&mut self, error: Error, fresh: &mut FreshVariables) -> Result<(Subst, Monotype)> {
+        match &mut self.errors {
+            Some(errors) => {
+                errors.push(error);
+                Ok((Subst::empty(), Type::Variable(fresh.next()).into()))
+            }
+            None => Err(error),
+        }
+    }
+}
+
+pub fn check_type(expr: &Expr, expected: &Monotype, globals: &builtins::Globals) -> Result<()> {
+    let base_context = globals.types().collect::<Env>();
+    let mut fresh = FreshVariables::new();
+    // type variables in the expected type can stand for any type, so the
+    // expression must work for all of them
+    let free = expected.free();
+    let expected = if free.is_empty() {
+        expected.clone()
+    } else {
+        Type::Forall {
+            quantifiers: free.into_iter().collect(),
+            body: expected.clone(),
+        }
+        .into()
+    };
+    check(
+        base_context,
+        &mut fresh,
+        &mut Recorder::default(),
+        expr,
+        &expected,
+    )
+    .map(|_| ())
+}
+
+fn infer(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr: &Expr,
+) -> Result<(Subst, Monotype)> {
+    let (subst, typ) = match infer_expression(env, fresh, recorder, expr) {
+        Ok(result) => result,
+        Err(error) => recorder.recover(error, fresh)?,
+    };
+    recorder.annotations.record(expr, typ.clone());
+    Ok((subst, typ))
+}
+
+/// Each kind of expression is inferred by its own function, so that deeply
+/// nested expressions only need stack space for the kinds they contain.
+fn infer_expression(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr: &Expr,
+) -> Result<(Subst, Monotype)> {
     match expr.expression() {
-        Expression::Primitive(Primitive::Integer(_)) => Ok((Subst::empty(), Type::Integer.into())),
+        Expression::Primitive(primitive) => Ok((Subst::empty(), primitive.get_type())),
         Expression::Native(_) => unreachable!("Native expression without a type."),
         Expression::Identifier(identifier) => env
             .get(identifier)
-            .ok_or_else(|| Error::UnknownVariable {
-                span: expr.span(),
-                name: identifier.to_string(),
+            .ok_or_else(|| Error::unknown_variable(expr.span(), identifier, env.identifiers()))
+            .map(|typ| (Subst::empty(), instantiate(typ, fresh))),
+        Expression::Function(function) => infer_function(env, fresh, recorder, function),
+        Expression::Apply(apply) => infer_apply(env, fresh, recorder, apply),
+        Expression::Assign(assign) => infer_assign(env, fresh, recorder, assign),
+        Expression::AssignRecursive(assign) => infer_assign_recursive(env, fresh, recorder, assign),
+        Expression::Match(match_) => infer_match(env, fresh, recorder, expr, match_),
+        Expression::Typed(typed) => infer_typed(env, fresh, recorder, typed),
+        Expression::Sequence(sequence) => infer_sequence(env, fresh, recorder, sequence),
+        Expression::Tuple(tuple) => infer_tuple(env, fresh, recorder, tuple),
+        Expression::List(expr::List::Empty) => {
+            let element_type = Type::Variable(fresh.next()).into();
+            Ok((Subst::empty(), Type::List(element_type).into()))
+        }
+        Expression::List(expr::List::Cons { head, tail }) => {
+            infer_cons(env, fresh, recorder, head, tail)
+        }
+    }
+}
+
+fn infer_function(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr::Function {
+        parameter,
+        parameter_type,
+        body,
+        ..
+    }: &expr::Function<Expr>,
+) -> Result<(Subst, Monotype)> {
+    // a stated parameter type is used in place of a fresh type variable
+    let parameter_type = parameter_type
+        .clone()
+        .unwrap_or_else(|| Type::Variable(fresh.next()).into());
+    let (subst, body_type) = infer(
+        env.update(parameter.clone(), polytype_of(&parameter_type)),
+        fresh,
+        recorder,
+        body,
+    )?;
+    let result = Type::Function {
+        parameter: parameter_type,
+        body: body_type,
+    }
+    .substitute(&subst)
+    .into();
+    Ok((subst, result))
+}
+
+fn infer_apply(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr::Apply { function, argument }: &expr::Apply<Expr>,
+) -> Result<(Subst, Monotype)> {
+    let (function_subst, function_type) = infer(env.clone(), fresh, recorder, function)?;
+    // a polymorphic parameter cannot be inferred, so the argument is
+    // checked against it instead
+    if let Type::Function { parameter, body } = function_type.as_ref() {
+        if is_higher_rank(parameter) {
+            let argument_subst = check(
+                env.substitute(&function_subst, fresh),
+                fresh,
+                recorder,
+                argument,
+                parameter,
+            )?;
+            let result = body.substitute(&argument_subst);
+            return Ok((function_subst.then(&argument_subst), result));
+        }
+    }
+    let (argument_subst, argument_type) = infer(
+        env.substitute(&function_subst, fresh),
+        fresh,
+        recorder,
+        argument,
+    )?;
+    let body_type: Monotype = Type::Variable(fresh.next()).into();
+    let expected_function_type: Monotype = Type::Function {
+        parameter: argument_type.clone(),
+        body: body_type.clone(),
+    }
+    .into();
+    let applied_type = function_type.substitute(&argument_subst);
+    // a type that can never unify with a function, such as `Integer`,
+    // is reported on its own, as the argument is not at fault
+    if !matches!(
+        applied_type.as_ref(),
+        Type::Function { .. } | Type::Variable(_) | Type::Forall { .. } | Type::Recursive { .. }
+    ) {
+        return Err(Error::TypeMismatch {
+            span: function.span(),
+            expected_type: expected_function_type,
+            actual_type: applied_type,
+        });
+    }
+    let body_subst = unify(&applied_type, &expected_function_type).map_err(|failure| {
+        failure.into_error(
+            match float_operator_instead(function, &applied_type, &argument_type) {
+                Some((operator, float_operator)) => Error::FloatInIntegerOperation {
+                    left_span: function.span(),
+                    left_type: function_type,
+                    right_span: argument.span(),
+                    right_type: argument_type,
+                    operator,
+                    float_operator,
+                },
+                None => Error::TypeUnificationError {
+                    left_span: function.span(),
+                    left_type: function_type,
+                    right_span: argument.span(),
+                    right_type: argument_type,
+                },
+            },
+        )
+    })?;
+    let result = body_type.substitute(&body_subst);
+    let subst = function_subst.then(&argument_subst).then(&body_subst);
+    Ok((subst, result))
+}
+
+fn infer_assign(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr::Assign { name, value, inner }: &expr::Assign<Expr>,
+) -> Result<(Subst, Monotype)> {
+    let (value_subst, value_type) = infer(env.clone(), fresh, recorder, value)?;
+    let (inner_subst, inner_type) = infer(
+        env.substitute(&value_subst, fresh).update(
+            name.clone(),
+            Polytype {
+                quantifiers: value_type
+                    .free()
+                    .relative_complement(env.free())
+                    .into_iter()
+                    .collect(),
+                mono: value_type,
+            },
+        ),
+        fresh,
+        recorder,
+        inner,
+    )?;
+    let subst = value_subst.then(&inner_subst);
+    Ok((subst, inner_type))
+}
+
+fn infer_assign_recursive(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr::AssignRecursive { bindings, inner }: &expr::AssignRecursive<Expr>,
+) -> Result<(Subst, Monotype)> {
+    // infer the group monomorphically, then generalize each binding
+    let value_placeholders = bindings
+        .iter()
+        .map(|_| Monotype::from(Type::Variable(fresh.next())))
+        .collect::<Vec<_>>();
+    let group_env = bindings.iter().zip(value_placeholders.iter()).fold(
+        env.clone(),
+        |group_env, ((name, _), placeholder)| {
+            group_env.update(name.clone(), Polytype::unquantified(placeholder.clone()))
+        },
+    );
+    let mut subst = Subst::empty();
+    for ((_, value), placeholder) in bindings.iter().zip(value_placeholders.iter()) {
+        let (value_subst, value_type) =
+            infer(group_env.substitute(&subst, fresh), fresh, recorder, value)?;
+        subst = subst.then(&value_subst);
+        let expected_type = placeholder.substitute(&subst);
+        let unified = unify(&expected_type, &value_type).map_err(|failure| {
+            failure.into_error(Error::TypeMismatch {
+                span: value.span(),
+                expected_type: expected_type.clone(),
+                actual_type: value_type.clone(),
+            })
+        })?;
+        subst = subst.then(&unified);
+    }
+    let generalizing_env = env.substitute(&subst, fresh);
+    let env_free = generalizing_env.free();
+    let inner_env = bindings.iter().zip(value_placeholders.iter()).fold(
+        generalizing_env,
+        |inner_env, ((name, _), placeholder)| {
+            let value_type = placeholder.substitute(&subst);
+            inner_env.update(
+                name.clone(),
+                Polytype {
+                    quantifiers: value_type
+                        .free()
+                        .relative_complement(env_free.clone())
+                        .into_iter()
+                        .collect(),
+                    mono: value_type,
+                },
+            )
+        },
+    );
+    let (inner_subst, inner_type) = infer(inner_env, fresh, recorder, inner)?;
+    let subst = subst.then(&inner_subst);
+    Ok((subst, inner_type))
+}
+
+fn infer_match(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr: &Expr,
+    expr::Match { value, patterns }: &expr::Match<Expr>,
+) -> Result<(Subst, Monotype)> {
+    let (mut subst, value_type) = infer(env.clone(), fresh, recorder, value)?;
+    let result_placeholder: Monotype = Type::Variable(fresh.next()).into();
+    let first_result = &patterns
+        .front()
+        .ok_or(Error::MatchWithoutBaseCase { span: expr.span() })?
+        .result;
+    for expr::PatternMatch {
+        pattern,
+        pattern_span,
+        result,
+    } in patterns
+    {
+        let (pattern_subst, pattern_bindings) = infer_pattern(
+            fresh,
+            pattern,
+            *pattern_span,
+            &value_type.substitute(&subst),
+            value.span(),
+        )?;
+        subst = subst.then(&pattern_subst);
+        let result_env = pattern_bindings.into_iter().fold(
+            env.substitute(&subst, fresh),
+            |result_env, (name, typ)| {
+                result_env.update(name, Polytype::unquantified(typ.substitute(&subst)))
+            },
+        );
+        let (result_subst, result_type) = infer(result_env, fresh, recorder, result)?;
+        subst = subst.then(&result_subst);
+        let expected_type = result_placeholder.substitute(&subst);
+        let unified = unify(&expected_type, &result_type).map_err(|failure| {
+            failure.into_error(Error::TypeUnificationError {
+                left_span: first_result.span(),
+                left_type: expected_type.clone(),
+                right_span: result.span(),
+                right_type: result_type.clone(),
             })
-            .map(|typ| (Subst::empty(), typ.substitute(&Subst::empty(), fresh).mono)),
-        Expression::Function(expr::Function { parameter, body }) => {
-            let parameter_type = Type::Variable(fresh.next());
-            let (subst, body_type) = infer(
-                env.update(
+        })?;
+        subst = subst.then(&unified);
+    }
+    let result = result_placeholder.substitute(&subst);
+    Ok((subst, result))
+}
+
+fn infer_typed(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr::Typed {
+        expression,
+        typ,
+        typ_span,
+    }: &expr::Typed<Expr>,
+) -> Result<(Subst, Monotype)> {
+    if is_higher_rank(typ) {
+        let subst = check(env, fresh, recorder, expression, typ)?;
+        return Ok((subst.clone(), typ.substitute(&subst)));
+    }
+    let (expression_subst, expression_type) = infer(env.clone(), fresh, recorder, expression)?;
+    let mismatch = || Error::TypeUnificationError {
+        left_span: expression.span(),
+        left_type: expression_type.clone(),
+        right_span: *typ_span,
+        right_type: typ.clone(),
+    };
+    let typ_subst =
+        unify(&expression_type, typ).map_err(|failure| failure.into_error(mismatch()))?;
+    let subst = expression_subst.merge(&typ_subst).ok_or_else(mismatch)?;
+    let result_type = expression_type.substitute(&subst);
+    Ok((subst, result_type))
+}
+
+fn infer_sequence(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr::Sequence { first, second }: &expr::Sequence<Expr>,
+) -> Result<(Subst, Monotype)> {
+    let (first_subst, _) = infer(env.clone(), fresh, recorder, first)?;
+    let (second_subst, second_type) =
+        infer(env.substitute(&first_subst, fresh), fresh, recorder, second)?;
+    Ok((first_subst.then(&second_subst), second_type))
+}
+
+fn infer_tuple(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr::Tuple { elements }: &expr::Tuple<Expr>,
+) -> Result<(Subst, Monotype)> {
+    let mut subst = Subst::empty();
+    let mut element_types = Vec::with_capacity(elements.len());
+    for element in elements {
+        let (element_subst, element_type) =
+            infer(env.substitute(&subst, fresh), fresh, recorder, element)?;
+        subst = subst.then(&element_subst);
+        element_types.push(element_type);
+    }
+    let result = Monotype::from(Type::Tuple(element_types)).substitute(&subst);
+    Ok((subst, result))
+}
+
+fn infer_cons(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    head: &Expr,
+    tail: &Expr,
+) -> Result<(Subst, Monotype)> {
+    let (head_subst, head_type) = infer(env.clone(), fresh, recorder, head)?;
+    let (tail_subst, tail_type) = infer(env.substitute(&head_subst, fresh), fresh, recorder, tail)?;
+    let expected_type: Monotype = Type::List(head_type.substitute(&tail_subst)).into();
+    let unified = unify(&expected_type, &tail_type).map_err(|failure| {
+        failure.into_error(Error::TypeUnificationError {
+            left_span: head.span(),
+            left_type: expected_type.clone(),
+            right_span: tail.span(),
+            right_type: tail_type,
+        })
+    })?;
+    let result = expected_type.substitute(&unified);
+    let subst = head_subst.then(&tail_subst).then(&unified);
+    Ok((subst, result))
+}
+
+/// Checks an expression against a stated type which may contain nested
+/// `forall` quantifiers, pushing the stated type inwards where inference alone
+/// would not find it.
+///
+/// Mismatches are reported at the innermost expression that does not fit the
+/// stated type, rather than where two inferred types meet.
+fn check(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr: &Expr,
+    expected: &Monotype,
+) -> Result<Subst> {
+    let subst = match check_expression(env, fresh, recorder, expr, expected) {
+        Ok(subst) => subst,
+        Err(error) => recorder.recover(error, fresh)?.0,
+    };
+    recorder
+        .annotations
+        .record(expr, expected.substitute(&subst));
+    Ok(subst)
+}
+
+fn check_expression(
+    env: Env,
+    fresh: &mut FreshVariables,
+    recorder: &mut Recorder,
+    expr: &Expr,
+    expected: &Monotype,
+) -> Result<Subst> {
+    match (expr.expression(), expected.as_ref()) {
+        (_, Type::Forall { quantifiers, body }) => {
+            // the quantified variables must remain abstract, so each is
+            // replaced with a fresh type that only unifies with itself
+            let skolems = quantifiers
+                .iter()
+                .map(|quantifier| {
+                    let skolem = Type::Constructor {
+                        name: TypeName::new(fresh.next().to_string()),
+                        arguments: vec![],
+                    };
+                    (quantifier.clone(), skolem.into())
+                })
+                .collect::<Subst>();
+            check(env, fresh, recorder, expr, &body.substitute(&skolems))
+        }
+        (
+            Expression::Function(expr::Function {
+                parameter,
+                parameter_type,
+                parameter_type_span,
+                body,
+            }),
+            Type::Function {
+                parameter: expected_parameter,
+                body: expected_body,
+            },
+        ) => {
+            let subst = match parameter_type {
+                None => Subst::empty(),
+                Some(parameter_type) => {
+                    unify(parameter_type, expected_parameter).map_err(|failure| {
+                        failure.into_error(Error::TypeMismatch {
+                            span: parameter_type_span.or(expr.span()),
+                            expected_type: expected_parameter.clone(),
+                            actual_type: parameter_type.clone(),
+                        })
+                    })?
+                }
+            };
+            let body_subst = check(
+                env.substitute(&subst, fresh).update(
                     parameter.clone(),
-                    Polytype::unquantified(parameter_type.clone().into()),
+                    polytype_of(&expected_parameter.substitute(&subst)),
                 ),
                 fresh,
+                recorder,
                 body,
+                &expected_body.substitute(&subst),
             )?;
-            let result = Type::Function {
-                parameter: parameter_type.into(),
-                body: body_type,
-            }
-            .substitute(&subst)
-            .into();
-            Ok((subst, result))
+            Ok(subst.then(&body_subst))
         }
-        Expression::Apply(expr::Apply { function, argument }) => {
-            let (function_subst, function_type) = infer(env.clone(), fresh, function)?;
-            let (argument_subst, argument_type) =
-                infer(env.substitute(&function_subst, fresh), fresh, argument)?;
-            let body_type: Monotype = Type::Variable(fresh.next()).into();
-            let expected_function_type: Monotype = Type::Function {
-                parameter: argument_type.clone(),
-                body: body_type.clone(),
-            }
-            .into();
-            let body_subst = unify(
-                &function_type.substitute(&argument_subst),
-                &expected_function_type,
+        (Expression::Tuple(expr::Tuple { elements }), Type::Tuple(expected_elements))
+            if elements.len() == expected_elements.len() =>
+        {
+            elements.iter().zip(expected_elements.iter()).try_fold(
+                Subst::empty(),
+                |subst, (element, expected_element)| {
+                    let element_subst = check(
+                        env.substitute(&subst, fresh),
+                        fresh,
+                        recorder,
+                        element,
+                        &expected_element.substitute(&subst),
+                    )?;
+                    Ok(subst.then(&element_subst))
+                },
             )
-            .ok_or(Error::TypeUnificationError {
-                left_span: function.span(),
-                left_type: function_type,
-                right_span: argument.span(),
-                right_type: argument_type,
-            })?;
-            let result = body_type.substitute(&body_subst);
-            let subst = function_subst.then(&argument_subst).then(&body_subst);
-            Ok((subst, result))
         }
-        Expression::Assign(expr::Assign { name, value, inner }) => {
-            let (value_subst, value_type) = infer(env.clone(), fresh, value)?;
-            let (inner_subst, inner_type) = infer(
-                env.substitute(&value_subst, fresh).update(
+        (Expression::List(expr::List::Cons { head, tail }), Type::List(expected_element)) => {
+            let head_subst = check(env.clone(), fresh, recorder, head, expected_element)?;
+            let tail_subst = check(
+                env.substitute(&head_subst, fresh),
+                fresh,
+                recorder,
+                tail,
+                &expected.substitute(&head_subst),
+            )?;
+            Ok(head_subst.then(&tail_subst))
+        }
+        (Expression::Assign(expr::Assign { name, value, inner }), _) => {
+            let (value_subst, value_type) = infer(env.clone(), fresh, recorder, value)?;
+            let inner_env = env.substitute(&value_subst, fresh);
+            let quantifiers = value_type
+                .free()
+                .relative_complement(inner_env.free())
+                .into_iter()
+                .collect();
+            let inner_subst = check(
+                inner_env.update(
                     name.clone(),
                     Polytype {
-                        quantifiers: value_type
-                            .free()
-                            .relative_complement(env.free())
-                            .into_iter()
-                            .collect(),
+                        quantifiers,
                         mono: value_type,
                     },
                 ),
                 fresh,
+                recorder,
                 inner,
+                &expected.substitute(&value_subst),
             )?;
-            let subst = value_subst.then(&inner_subst);
-            Ok((subst, inner_type))
+            Ok(value_subst.then(&inner_subst))
         }
-        Expression::Match(expr::Match { value, patterns }) => {
-            let _ = infer(env.clone(), fresh, value)?;
-            let result_placeholder = Type::Variable(fresh.next()).into();
-            let mut pattern_iter = patterns.iter();
-            let expr::PatternMatch {
-                pattern: _,
-                result: first_result,
-            } = pattern_iter
-                .next()
-                .ok_or(Error::MatchWithoutBaseCase { span: expr.span() })?;
-            let (first_result_subst, first_result_type) = infer(env.clone(), fresh, first_result)?;
-            let first_unified =
-                unify(&first_result_type, &result_placeholder).ok_or_else(|| {
-                    Error::TypeUnificationError {
-                        left_span: expr.span(),
-                        left_type: result_placeholder.clone(),
-                        right_span: first_result.span(),
-                        right_type: first_result_type.clone(),
-                    }
-                })?;
-            let mut subst = first_result_subst.then(&first_unified);
-            for expr::PatternMatch { pattern: _, result } in pattern_iter {
-                let (result_subst, result_type) = infer(env.clone(), fresh, result)?;
-                let unified = unify(&result_type, &result_placeholder).ok_or_else(|| {
-                    Error::TypeUnificationError {
-                        left_span: expr.span(),
-                        left_type: result_placeholder.clone(),
-                        right_span: result.span(),
-                        right_type: result_type.clone(),
-                    }
-                })?;
-                subst = subst.merge(&result_subst.then(&unified)).ok_or_else(|| {
-                    Error::TypeUnificationError {
-                        left_span: first_result.span(),
-                        left_type: first_result_type.clone(),
-                        right_span: result.span(),
-                        right_type: result_type,
-                    }
-                })?;
+        (Expression::Match(expr::Match { value, patterns }), _) => {
+            let (mut subst, value_type) = infer(env.clone(), fresh, recorder, value)?;
+            for expr::PatternMatch {
+                pattern,
+                pattern_span,
+                result,
+            } in patterns
+            {
+                let (pattern_subst, pattern_bindings) = infer_pattern(
+                    fresh,
+                    pattern,
+                    *pattern_span,
+                    &value_type.substitute(&subst),
+                    value.span(),
+                )?;
+                subst = subst.then(&pattern_subst);
+                let result_env = pattern_bindings.into_iter().fold(
+                    env.substitute(&subst, fresh),
+                    |result_env, (name, typ)| {
+                        result_env.update(name, Polytype::unquantified(typ.substitute(&subst)))
+                    },
+                );
+                let result_subst = check(
+                    result_env,
+                    fresh,
+                    recorder,
+                    result,
+                    &expected.substitute(&subst),
+                )?;
+                subst = subst.then(&result_subst);
             }
-            let result = result_placeholder.substitute(&subst);
-            Ok((subst, result))
+            Ok(subst)
+        }
+        (
+            Expression::Typed(expr::Typed {
+                expression,
+                typ,
+                typ_span,
+            }),
+            _,
+        ) => {
+            let subst = unify(typ, expected).map_err(|failure| {
+                failure.into_error(Error::TypeMismatch {
+                    span: *typ_span,
+                    expected_type: expected.clone(),
+                    actual_type: typ.clone(),
+                })
+            })?;
+            let expression_subst = check(
+                env.substitute(&subst, fresh),
+                fresh,
+                recorder,
+                expression,
+                &typ.substitute(&subst),
+            )?;
+            Ok(subst.then(&expression_subst))
         }
-        Expression::Typed(expr::Typed { expression, typ }) => {
-            let (expression_subst, expression_type) = infer(env.clone(), fresh, expression)?;
-            let subst = unify(&expression_type, typ)
-                .and_then(|typ_subst| expression_subst.merge(&typ_subst))
-                .ok_or_else(|| Error::TypeUnificationError {
-                    left_span: expression.span(),
-                    left_type: expression_type.clone(),
-                    right_span: None,
-                    right_type: typ.clone(),
-                })?;
-            let result_type = expression_type.substitute(&subst);
-            Ok((subst, result_type))
+        (Expression::Sequence(expr::Sequence { first, second }), _) => {
+            let (first_subst, _) = infer(env.clone(), fresh, recorder, first)?;
+            let second_subst = check(
+                env.substitute(&first_subst, fresh),
+                fresh,
+                recorder,
+                second,
+                &expected.substitute(&first_subst),
+            )?;
+            Ok(first_subst.then(&second_subst))
+        }
+        _ => {
+            let (subst, actual_type) = infer(env, fresh, recorder, expr)?;
+            let expected_type = expected.substitute(&subst);
+            let unified = unify(&expected_type, &actual_type).map_err(|failure| {
+                failure.into_error(Error::TypeMismatch {
+                    span: expr.span(),
+                    expected_type: expected_type.clone(),
+                    actual_type: actual_type.clone(),
+                })
+            })?;
+            Ok(subst.then(&unified))
+        }
+    }
+}
+
+/// Returns `true` if the type contains a nested `forall`.
+pub(crate) fn is_higher_rank(typ: &Monotype) -> bool {
+    match typ.as_ref() {
+        Type::Integer | Type::Float | Type::Boolean | Type::Char | Type::String => false,
+        Type::Function { parameter, body } => is_higher_rank(parameter) || is_higher_rank(body),
+        Type::Tuple(elements) => elements.iter().any(is_higher_rank),
+        Type::List(element) | Type::Option(element) => is_higher_rank(element),
+        Type::Result { ok, error } => is_higher_rank(ok) || is_higher_rank(error),
+        Type::Constructor { arguments, .. } => arguments.iter().any(is_higher_rank),
+        Type::Variable(_) => false,
+        Type::Forall { .. } => true,
+        Type::Recursive { body, .. } => is_higher_rank(body),
+    }
+}
+
+/// Finds the float operator to suggest when a float is passed to an integer
+/// arithmetic operator, as in `1.5 + 2.5`, whether the operator is applied to
+/// its first operand or its second.
+///
+/// Returns the integer operator along with its float counterpart.
+pub(crate) fn float_operator_instead(
+    function: &Expr,
+    function_type: &Monotype,
+    argument_type: &Monotype,
+) -> Option<(String, String)> {
+    let Type::Function { parameter, .. } = function_type.as_ref() else {
+        return None;
+    };
+    if *parameter.as_ref() != Type::Integer || *argument_type.as_ref() != Type::Float {
+        return None;
+    }
+    let operator = match function.expression() {
+        Expression::Apply(expr::Apply { function, .. }) => function,
+        _ => function,
+    };
+    let Expression::Identifier(Identifier::Operator(operator)) = operator.expression() else {
+        return None;
+    };
+    match operator.as_str() {
+        "+" | "-" | "*" | "/" => Some((operator.to_string(), format!("{operator}."))),
+        _ => None,
+    }
+}
+
+/// Converts a type with an outermost `forall` into the equivalent polytype.
+pub(crate) fn polytype_of(typ: &Monotype) -> Polytype {
+    match typ.as_ref() {
+        Type::Forall { quantifiers, body } => Polytype {
+            quantifiers: quantifiers.clone(),
+            mono: body.clone(),
+        },
+        _ => Polytype::unquantified(typ.clone()),
+    }
+}
+
+/// Instantiates a polytype with fresh variables, including any outermost
+/// `forall` in the resulting type.
+pub(crate) fn instantiate(typ: &Polytype, fresh: &mut FreshVariables) -> Monotype {
+    let mut result = typ.substitute(&Subst::empty(), fresh).mono;
+    while let Type::Forall { quantifiers, body } = result.as_ref() {
+        let replacements = quantifiers
+            .iter()
+            .map(|quantifier| {
+                (
+                    quantifier.clone(),
+                    Type::Variable(fresh.next_like(quantifier)).into(),
+                )
+            })
+            .collect::<Subst>();
+        result = body.substitute(&replacements);
+    }
+    result
+}
+
+/// Infers the variables bound by a pattern, given the type of the value it
+/// will be matched against.
+pub(crate) fn infer_pattern(
+    fresh: &mut FreshVariables,
+    pattern: &expr::Pattern,
+    pattern_span: Option<Span>,
+    value_type: &Monotype,
+    value_span: Option<Span>,
+) -> Result<(Subst, Vec<(Identifier, Monotype)>)> {
+    match pattern {
+        expr::Pattern::Anything => Ok((Subst::empty(), vec![])),
+        expr::Pattern::Variable(name) => {
+            Ok((Subst::empty(), vec![(name.clone(), value_type.clone())]))
+        }
+        expr::Pattern::Primitive(primitive) => {
+            let primitive_type = primitive.get_type();
+            let subst = unify(value_type, &primitive_type).map_err(|failure| {
+                failure.into_error(Error::TypeUnificationError {
+                    left_span: value_span,
+                    left_type: value_type.clone(),
+                    right_span: pattern_span,
+                    right_type: primitive_type,
+                })
+            })?;
+            Ok((subst, vec![]))
+        }
+        expr::Pattern::Tuple(patterns) => {
+            let element_types = patterns
+                .iter()
+                .map(|_| Monotype::from(Type::Variable(fresh.next())))
+                .collect::<Vec<_>>();
+            let tuple_type: Monotype = Type::Tuple(element_types.clone()).into();
+            let mut subst = unify(value_type, &tuple_type).map_err(|failure| {
+                failure.into_error(Error::TypeUnificationError {
+                    left_span: value_span,
+                    left_type: value_type.clone(),
+                    right_span: pattern_span,
+                    right_type: tuple_type,
+                })
+            })?;
+            let mut bindings = vec![];
+            for (pattern, element_type) in patterns.iter().zip(element_types) {
+                let (element_subst, element_bindings) = infer_pattern(
+                    fresh,
+                    pattern,
+                    pattern_span,
+                    &element_type.substitute(&subst),
+                    value_span,
+                )?;
+                subst = subst.then(&element_subst);
+                bindings.extend(element_bindings);
+            }
+            Ok((subst, bindings))
+        }
+        expr::Pattern::EmptyList => {
+            let list_type: Monotype = Type::List(Type::Variable(fresh.next()).into()).into();
+            let subst = unify(value_type, &list_type).map_err(|failure| {
+                failure.into_error(Error::TypeUnificationError {
+                    left_span: value_span,
+                    left_type: value_type.clone(),
+                    right_span: pattern_span,
+                    right_type: list_type,
+                })
+            })?;
+            Ok((subst, vec![]))
+        }
+        expr::Pattern::Cons { head, tail } => {
+            let element_type: Monotype = Type::Variable(fresh.next()).into();
+            let list_type: Monotype = Type::List(element_type.clone()).into();
+            let list_subst = unify(value_type, &list_type).map_err(|failure| {
+                failure.into_error(Error::TypeUnificationError {
+                    left_span: value_span,
+                    left_type: value_type.clone(),
+                    right_span: pattern_span,
+                    right_type: list_type.clone(),
+                })
+            })?;
+            let (head_subst, mut bindings) = infer_pattern(
+                fresh,
+                head,
+                pattern_span,
+                &element_type.substitute(&list_subst),
+                value_span,
+            )?;
+            let subst = list_subst.then(&head_subst);
+            let (tail_subst, tail_bindings) = infer_pattern(
+                fresh,
+                tail,
+                pattern_span,
+                &list_type.substitute(&subst),
+                value_span,
+            )?;
+            bindings.extend(tail_bindings);
+            Ok((subst.then(&tail_subst), bindings))
+        }
+        expr::Pattern::Some(inner) => {
+            let element_type: Monotype = Type::Variable(fresh.next()).into();
+            let option_type: Monotype = Type::Option(element_type.clone()).into();
+            let subst = unify(value_type, &option_type).map_err(|failure| {
+                failure.into_error(Error::TypeUnificationError {
+                    left_span: value_span,
+                    left_type: value_type.clone(),
+                    right_span: pattern_span,
+                    right_type: option_type,
+                })
+            })?;
+            let (inner_subst, bindings) = infer_pattern(
+                fresh,
+                inner,
+                pattern_span,
+                &element_type.substitute(&subst),
+                value_span,
+            )?;
+            Ok((subst.then(&inner_subst), bindings))
+        }
+        expr::Pattern::Ok(inner) | expr::Pattern::Err(inner) => {
+            let ok_type: Monotype = Type::Variable(fresh.next()).into();
+            let error_type: Monotype = Type::Variable(fresh.next()).into();
+            let result_type: Monotype = Type::Result {
+                ok: ok_type.clone(),
+                error: error_type.clone(),
+            }
+            .into();
+            let subst = unify(value_type, &result_type).map_err(|failure| {
+                failure.into_error(Error::TypeUnificationError {
+                    left_span: value_span,
+                    left_type: value_type.clone(),
+                    right_span: pattern_span,
+                    right_type: result_type,
+                })
+            })?;
+            let inner_type = match pattern {
+                expr::Pattern::Ok(_) => ok_type,
+                _ => error_type,
+            };
+            let (inner_subst, bindings) = infer_pattern(
+                fresh,
+                inner,
+                pattern_span,
+                &inner_type.substitute(&subst),
+                value_span,
+            )?;
+            Ok((subst.then(&inner_subst), bindings))
+        }
+        expr::Pattern::None => {
+            let option_type: Monotype = Type::Option(Type::Variable(fresh.next()).into()).into();
+            let subst = unify(value_type, &option_type).map_err(|failure| {
+                failure.into_error(Error::TypeUnificationError {
+                    left_span: value_span,
+                    left_type: value_type.clone(),
+                    right_span: pattern_span,
+                    right_type: option_type,
+                })
+            })?;
+            Ok((subst, vec![]))
         }
     }
 }
@@ -155,9 +919,8 @@ fn infer(env: Env, fresh: &mut FreshVariables, expr: &Expr) -> Result<(Subst, Mo
 mod tests {
     use proptest::prelude::*;
 
-    use boo_core::identifier::Identifier;
     use boo_core::types::TypeVariable;
-    use boo_parser::parse;
+    use boo_parser::parse_expr;
     use boo_test_helpers::proptest::check;
 
     use super::*;
@@ -176,7 +939,7 @@ mod tests {
             eprintln!("rendered: {rendered}");
             let expr = input.clone().to_core()?;
 
-            let actual_type = type_of(&expr)?;
+            let actual_type = type_of(&expr, &builtins::Globals::default())?;
 
             prop_assert_eq!(actual_type, Type::Integer.into());
             Ok(())
@@ -186,14 +949,14 @@ mod tests {
     #[test]
     fn test_rejects_incorrect_types() -> Result<()> {
         let program = "1 + (fn x -> 3)";
-        let ast = parse(program)?.to_core()?;
+        let ast = parse_expr(program)?.to_core()?;
 
-        let result = type_of(&ast);
+        let result = type_of(&ast, &builtins::Globals::default());
 
         assert_eq!(
             result,
             Err(Error::TypeUnificationError {
-                left_span: Some((0..14).into()),
+                left_span: Some((0..3).into()),
                 left_type: Type::Function {
                     parameter: Type::Integer.into(),
                     body: Type::Integer.into(),
@@ -201,7 +964,7 @@ mod tests {
                 .into(),
                 right_span: Some((5..14).into()),
                 right_type: Type::Function {
-                    parameter: Type::Variable(TypeVariable::new_from_str("_3")).into(),
+                    parameter: Type::Variable(TypeVariable::new_from_str("_69")).into(),
                     body: Type::Integer.into(),
                 }
                 .into(),
@@ -210,20 +973,139 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_rejects_mixing_integers_and_floats() -> Result<()> {
+        let program = "1 + 1.5";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::FloatInIntegerOperation {
+                left_span: Some((0..3).into()),
+                left_type: Type::Function {
+                    parameter: Type::Integer.into(),
+                    body: Type::Integer.into(),
+                }
+                .into(),
+                right_span: Some((4..7).into()),
+                right_type: Type::Float.into(),
+                operator: "+".to_string(),
+                float_operator: "+.".to_string(),
+            }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_applying_a_non_function() -> Result<()> {
+        let program = "5 (-2)";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::TypeMismatch {
+                span: Some((0..1).into()),
+                expected_type: Type::Function {
+                    parameter: Type::Integer.into(),
+                    body: Type::Variable(TypeVariable::new_from_str("_34")).into(),
+                }
+                .into(),
+                actual_type: Type::Integer.into(),
+            }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compares_any_primitive_type() -> Result<()> {
+        let program = r#"let less = fn x y -> x < y in (less 1 2, less "a" "b")"#;
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Ok(Type::Tuple(vec![Type::Boolean.into(), Type::Boolean.into()]).into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_comparing_values_that_cannot_be_compared() -> Result<()> {
+        let programs = [
+            "(fn x -> x) == (fn x -> x)",
+            "[1, 2] < [1, 3]",
+            r#"(1, "a") < (1, "b")"#,
+            "some 1 == some 1",
+        ];
+        for program in programs {
+            let ast = parse_expr(program)?.to_core()?;
+
+            let result = type_of(&ast, &builtins::Globals::default());
+
+            assert!(
+                matches!(result, Err(Error::NotComparable { .. })),
+                "program: {:?}\nresult: {:?}",
+                program,
+                result
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_suggests_float_operators_for_floats_given_to_integer_operators() -> Result<()> {
+        let program = "1.5 + 2.5";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::FloatInIntegerOperation {
+                left_span: Some((4..5).into()),
+                left_type: Type::Function {
+                    parameter: Type::Integer.into(),
+                    body: Type::Function {
+                        parameter: Type::Integer.into(),
+                        body: Type::Integer.into(),
+                    }
+                    .into(),
+                }
+                .into(),
+                right_span: Some((0..3).into()),
+                right_type: Type::Float.into(),
+                operator: "+".to_string(),
+                float_operator: "+.".to_string(),
+            }),
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_parameters_are_monomorphic() -> Result<()> {
         let program = "fn x -> x x";
-        let ast = parse(program)?.to_core()?;
+        let ast = parse_expr(program)?.to_core()?;
 
-        let result = type_of(&ast);
+        let result = type_of(&ast, &builtins::Globals::default());
 
         assert_eq!(
             result,
-            Err(Error::TypeUnificationError {
+            Err(Error::InfiniteType {
                 left_span: Some((8..9).into()),
                 left_type: Type::Variable(TypeVariable::new_from_str("_0")).into(),
                 right_span: Some((10..11).into()),
                 right_type: Type::Variable(TypeVariable::new_from_str("_0")).into(),
+                variable: TypeVariable::new_from_str("_0"),
+                typ: Type::Function {
+                    parameter: Type::Variable(TypeVariable::new_from_str("_0")).into(),
+                    body: Type::Variable(TypeVariable::new_from_str("_35")).into(),
+                }
+                .into(),
             }),
         );
         Ok(())
@@ -232,9 +1114,9 @@ mod tests {
     #[test]
     fn test_match_expressions_must_be_of_the_same_type() -> Result<()> {
         let program = "match 0 { 1 -> 2; _ -> fn x -> x }";
-        let ast = parse(program)?.to_core()?;
+        let ast = parse_expr(program)?.to_core()?;
 
-        let result = type_of(&ast);
+        let result = type_of(&ast, &builtins::Globals::default());
 
         assert_eq!(
             result,
@@ -243,8 +1125,8 @@ mod tests {
                 left_type: Type::Integer.into(),
                 right_span: Some((23..32).into()),
                 right_type: Type::Function {
-                    parameter: Type::Variable(TypeVariable::new_from_str("_1")).into(),
-                    body: Type::Variable(TypeVariable::new_from_str("_1")).into(),
+                    parameter: Type::Variable(TypeVariable::new_from_str("_69")).into(),
+                    body: Type::Variable(TypeVariable::new_from_str("_69")).into(),
                 }
                 .into(),
             }),
@@ -252,12 +1134,141 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_rejects_values_that_do_not_match_their_annotation() -> Result<()> {
+        let program = "let x: Integer = true in x";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::TypeUnificationError {
+                left_span: Some((17..21).into()),
+                left_type: Type::Boolean.into(),
+                right_span: Some((7..14).into()),
+                right_type: Type::Integer.into(),
+            }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameters_are_constrained_by_their_annotation() -> Result<()> {
+        let program = "fn (x: Boolean) -> x + 1";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::TypeUnificationError {
+                left_span: Some((21..22).into()),
+                left_type: Type::Function {
+                    parameter: Type::Integer.into(),
+                    body: Type::Function {
+                        parameter: Type::Integer.into(),
+                        body: Type::Integer.into(),
+                    }
+                    .into(),
+                }
+                .into(),
+                right_span: Some((19..20).into()),
+                right_type: Type::Boolean.into(),
+            }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_higher_rank_parameters_can_be_used_at_different_types() -> Result<()> {
+        let program = "let both: (forall a. a -> a) -> (Integer, Boolean) = fn f -> (f 1, f true) in both (fn x -> x)";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Ok(Type::Tuple(vec![Type::Integer.into(), Type::Boolean.into()]).into()),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rejects_monomorphic_arguments_to_higher_rank_parameters() -> Result<()> {
+        let program =
+            "let apply: (forall a. a -> a) -> Integer = fn f -> f 1 in apply (fn x -> x + 1)";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::TypeUnificationError {
+                left_span: Some((75..76).into()),
+                left_type: Type::Function {
+                    parameter: Type::Integer.into(),
+                    body: Type::Function {
+                        parameter: Type::Integer.into(),
+                        body: Type::Integer.into(),
+                    }
+                    .into(),
+                }
+                .into(),
+                right_span: Some((73..74).into()),
+                // the quantified variable is replaced by an opaque type
+                right_type: Type::Constructor {
+                    name: TypeName::new_from_str("_139"),
+                    arguments: vec![],
+                }
+                .into(),
+            }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tuple_patterns_bind_variables() -> Result<()> {
+        let program = "fn pair -> match pair { (x, true) -> x + 1; _ -> 0 }";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Ok(Type::Function {
+                parameter: Type::Tuple(vec![Type::Integer.into(), Type::Boolean.into()]).into(),
+                body: Type::Integer.into(),
+            }
+            .into()),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_patterns_bind_variables() -> Result<()> {
+        let program = "fn list -> match list { x :: _ -> x + 1; _ -> 0 }";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Ok(Type::Function {
+                parameter: Type::List(Type::Integer.into()).into(),
+                body: Type::Integer.into(),
+            }
+            .into()),
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_type_annotations_are_respected() -> Result<()> {
         let program = "(fn x -> x + 1): Integer";
-        let ast = parse(program)?.to_core()?;
+        let ast = parse_expr(program)?.to_core()?;
 
-        let result = type_of(&ast);
+        let result = type_of(&ast, &builtins::Globals::default());
 
         assert_eq!(
             result,
@@ -268,10 +1279,167 @@ mod tests {
                     body: Type::Integer.into()
                 }
                 .into(),
-                right_span: None, // TODO: should be `Some((17..24).into())`
+                right_span: Some((17..24).into()),
                 right_type: Type::Integer.into(),
             }),
         );
         Ok(())
     }
+
+    #[test]
+    fn test_checking_reports_the_innermost_mismatch() -> Result<()> {
+        let program = "let x = 1 in (x, [true, 2])";
+        let ast = parse_expr(program)?.to_core()?;
+        let expected = Type::Tuple(vec![
+            Type::Integer.into(),
+            Type::List(Type::Boolean.into()).into(),
+        ])
+        .into();
+
+        let result = check_type(&ast, &expected, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::TypeMismatch {
+                span: Some((24..25).into()),
+                expected_type: Type::Boolean.into(),
+                actual_type: Type::Integer.into(),
+            }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_checking_against_a_polymorphic_type() -> Result<()> {
+        let a: Monotype = Type::Variable(TypeVariable::new_from_str("a")).into();
+        let expected = Type::Function {
+            parameter: a.clone(),
+            body: a,
+        }
+        .into();
+
+        let identity = parse_expr("fn x -> x")?.to_core()?;
+        let increment = parse_expr("fn x -> x + 1")?.to_core()?;
+
+        assert_eq!(
+            check_type(&identity, &expected, &builtins::Globals::default()),
+            Ok(())
+        );
+        assert!(check_type(&increment, &expected, &builtins::Globals::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_annotates_every_subexpression() -> Result<()> {
+        let program = "fn x -> (x + 1, [x])";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let typed = annotate(&ast, &builtins::Globals::default())?;
+
+        let Expression::Function(expr::Function { body, .. }) = typed.expression() else {
+            panic!("Expected a function, but got:\n{typed:?}");
+        };
+        let Expression::Tuple(expr::Tuple { elements }) = body.expression() else {
+            panic!("Expected a tuple, but got:\n{body:?}");
+        };
+        assert_eq!(
+            elements
+                .iter()
+                .map(|element| (element.span(), element.typ().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some((9..14).into()), Type::Integer.into()),
+                (
+                    Some((16..19).into()),
+                    Type::List(Type::Integer.into()).into()
+                ),
+            ],
+        );
+        assert_eq!(
+            typed.typ(),
+            &Type::Function {
+                parameter: Type::Integer.into(),
+                body: Type::Tuple(vec![
+                    Type::Integer.into(),
+                    Type::List(Type::Integer.into()).into()
+                ])
+                .into(),
+            }
+            .into(),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_collects_independent_type_errors() -> Result<()> {
+        let program = "(1 + true, 2, 3 + false)";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let errors = type_errors(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| match error {
+                    Error::TypeUnificationError { right_span, .. } => *right_span,
+                    _ => panic!("Unexpected error: {error:?}"),
+                })
+                .collect::<Vec<_>>(),
+            vec![Some((5..9).into()), Some((18..23).into())],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_collects_no_errors_for_well_typed_expressions() -> Result<()> {
+        let program = "let f = fn x -> x + 1 in (f 1, f 2)";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let errors = type_errors(&ast, &builtins::Globals::default());
+
+        assert_eq!(errors, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pattern_errors_point_at_the_pattern() -> Result<()> {
+        let program = "match 1 { true -> 2; _ -> 3 }";
+        let ast = parse_expr(program)?.to_core()?;
+
+        let result = type_of(&ast, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::TypeUnificationError {
+                left_span: Some((6..7).into()),
+                left_type: Type::Integer.into(),
+                right_span: Some((10..14).into()),
+                right_type: Type::Boolean.into(),
+            }),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameter_annotation_errors_point_at_the_annotation() -> Result<()> {
+        let program = "fn (x: Boolean) -> x";
+        let ast = parse_expr(program)?.to_core()?;
+        let expected = Type::Function {
+            parameter: Type::Integer.into(),
+            body: Type::Integer.into(),
+        }
+        .into();
+
+        let result = check_type(&ast, &expected, &builtins::Globals::default());
+
+        assert_eq!(
+            result,
+            Err(Error::TypeMismatch {
+                span: Some((7..14).into()),
+                expected_type: Type::Integer.into(),
+                actual_type: Type::Boolean.into(),
+            }),
+        );
+        Ok(())
+    }
 }
@@ -15,6 +15,11 @@ impl Env {
         self.0.get(key)
     }
 
+    /// Lists the identifiers in scope, in no particular order.
+    pub fn identifiers(&self) -> impl Iterator<Item = &Identifier> {
+        self.0.keys()
+    }
+
     pub fn update(&self, key: Identifier, value: Polytype) -> Self {
         Self(self.0.update(key, value))
     }
@@ -19,4 +19,14 @@ impl FreshVariables {
     pub fn next(&mut self) -> TypeVariable {
         self.values.next().unwrap()
     }
+
+    /// A fresh variable which can stand for the same types as the given one.
+    pub fn next_like(&mut self, variable: &TypeVariable) -> TypeVariable {
+        let next = self.next();
+        if variable.is_comparable() {
+            TypeVariable::new_comparable(&next.0)
+        } else {
+            next
+        }
+    }
 }
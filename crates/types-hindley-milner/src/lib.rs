@@ -3,17 +3,256 @@ mod algorithm_w;
 mod env;
 mod fresh;
 mod subst;
+pub mod typed;
 mod types;
 mod unification;
 
-use boo_core::error::Result;
+use boo_core::builtins::Globals;
+use boo_core::error::{Error, Result};
 use boo_core::expr::Expr;
-use boo_core::types::Monotype;
+use boo_core::types::{Monotype, Polytype};
 
-pub fn type_of(expr: &Expr) -> Result<Monotype> {
-    algorithm_w::type_of(expr)
+use crate::typed::TypedExpr;
+use crate::types::FreeVariables;
+
+/// The algorithm used to infer types.
+///
+/// Both algorithms accept the same programs, but report errors in different
+/// places.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Algorithm W infers the type of each expression from its children, and
+    /// so reports errors where two inferred types meet.
+    #[default]
+    W,
+    /// Algorithm M pushes the expected type of each expression down to its
+    /// children, and so reports errors at the expression which does not fit.
+    M,
+}
+
+pub fn type_of(expr: &Expr, globals: impl Into<Globals>) -> Result<Monotype> {
+    type_of_with(Algorithm::default(), expr, globals)
+}
+
+pub fn type_of_with(
+    algorithm: Algorithm,
+    expr: &Expr,
+    globals: impl Into<Globals>,
+) -> Result<Monotype> {
+    match algorithm {
+        Algorithm::W => algorithm_w::type_of(expr, &globals.into()),
+        Algorithm::M => algorithm_m::type_of(expr, &globals.into()),
+    }
+}
+
+/// Infers the type of an expression, quantified over each of its type
+/// variables, as the type of a top-level definition would be.
+///
+/// The quantifiers are sorted, so that the type is always displayed the same
+/// way.
+pub fn generalized_type_of(expr: &Expr, globals: impl Into<Globals>) -> Result<Polytype> {
+    type_of(expr, globals).map(generalize)
+}
+
+/// Quantifies a type over each of its type variables, as [`generalized_type_of`]
+/// does.
+pub fn generalize(mono: Monotype) -> Polytype {
+    let mut quantifiers = mono.free().into_iter().collect::<Vec<_>>();
+    quantifiers.sort();
+    Polytype { quantifiers, mono }
+}
+
+/// Checks that an expression has the expected type, pushing the expected type
+/// inwards so that errors are reported at the subexpression which does not
+/// fit.
+///
+/// Type variables in the expected type may stand for any type, so `fn x -> x`
+/// checks against `a -> a`, but `fn x -> x + 1` does not.
+pub fn check(expr: &Expr, expected: &Monotype, globals: impl Into<Globals>) -> Result<()> {
+    algorithm_w::check_type(expr, expected, &globals.into())
+}
+
+/// Infers the type of an expression and each of its subexpressions.
+pub fn annotate(expr: &Expr, globals: impl Into<Globals>) -> Result<TypedExpr> {
+    algorithm_w::annotate(expr, &globals.into())
+}
+
+/// Infers the type of the innermost subexpression containing the given offset
+/// into the source.
+pub fn type_at(expr: &Expr, offset: usize, globals: impl Into<Globals>) -> Result<Monotype> {
+    let typed = annotate(expr, globals)?;
+    typed
+        .at(offset)
+        .map(|subexpression| subexpression.typ().clone())
+        .ok_or(Error::NoExpressionAtOffset { offset })
+}
+
+/// Finds all the independent type errors in an expression, rather than
+/// stopping at the first.
+///
+/// Each subexpression that fails to type-check is treated as if it could have
+/// any type, so that it does not cause further errors in the expressions
+/// around it.
+pub fn type_errors(expr: &Expr, globals: impl Into<Globals>) -> Vec<Error> {
+    algorithm_w::type_errors(expr, &globals.into())
+}
+
+/// Infers the type of an expression and each of its subexpressions, as
+/// [`annotate`] does, or, if it fails to type-check, finds all the independent
+/// type errors, as [`type_errors`] does.
+///
+/// This type-checks the expression once, for callers which need either.
+pub fn annotate_collecting_errors(
+    expr: &Expr,
+    globals: impl Into<Globals>,
+) -> std::result::Result<TypedExpr, Vec<Error>> {
+    algorithm_w::annotate_collecting_errors(expr, &globals.into())
+}
+
+pub fn validate(expr: &Expr, globals: impl Into<Globals>) -> Result<()> {
+    type_of(expr, globals).map(|_| ())
 }
 
-pub fn validate(expr: &Expr) -> Result<()> {
-    type_of(expr).map(|_| ())
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use boo_core::builtins::IoCapabilities;
+    use boo_core::expr::{Assign, Expression};
+    use boo_core::identifier::Identifier;
+    use boo_core::types::Type;
+    use boo_parser::parse_expr;
+    use boo_test_helpers::proptest::check;
+
+    use super::*;
+
+    #[test]
+    fn test_algorithms_agree_on_arbitrary_expressions() {
+        let generator = boo_generator::gen(
+            boo_generator::ExprGenConfig {
+                gen_identifier: Identifier::gen_ascii(1..=16).boxed().into(),
+                ..Default::default()
+            }
+            .into(),
+        );
+        check(&generator, |input| {
+            let expr = input.clone().to_core()?;
+
+            let w_type = type_of_with(Algorithm::W, &expr, IoCapabilities::default())?;
+            let m_type = type_of_with(Algorithm::M, &expr, IoCapabilities::default())?;
+
+            prop_assert_eq!(w_type, m_type);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_infers_the_types_of_deeply_nested_expressions_in_a_small_stack() {
+        let result = std::thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(|| {
+                // `let x_0 = 0 in let x_1 = x_0 + 1 in ... x_500`, assembled
+                // here, as parsing it would need a larger stack
+                let innermost = parse_expr("x_500")?.to_core()?;
+                let expr = (0..=500).rev().try_fold(innermost, |inner, i| {
+                    let value = if i == 0 {
+                        "0".to_string()
+                    } else {
+                        format!("x_{} + 1", i - 1)
+                    };
+                    Ok::<_, Error>(Expr::new(
+                        None,
+                        Expression::Assign(Assign {
+                            name: Identifier::name_from_str(&format!("x_{}", i)).unwrap(),
+                            value: parse_expr(&value)?.to_core()?,
+                            inner,
+                        }),
+                    ))
+                })?;
+                type_of(&expr, IoCapabilities::default())
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(result, Ok(Type::Integer.into()));
+    }
+
+    #[test]
+    fn test_type_at_finds_the_innermost_expression() -> anyhow::Result<()> {
+        let program = "fn x -> (x, [x == 1])";
+        let expr = parse_expr(program)?.to_core()?;
+
+        let types = [8, 9, 15, 18]
+            .into_iter()
+            .map(|offset| type_at(&expr, offset, IoCapabilities::default()))
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(
+            types,
+            vec![
+                Type::Tuple(vec![
+                    Type::Integer.into(),
+                    Type::List(Type::Boolean.into()).into()
+                ])
+                .into(),
+                Type::Integer.into(),
+                // the operator itself
+                Type::Function {
+                    parameter: Type::Integer.into(),
+                    body: Type::Function {
+                        parameter: Type::Integer.into(),
+                        body: Type::Boolean.into(),
+                    }
+                    .into(),
+                }
+                .into(),
+                Type::Integer.into(),
+            ],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_generalized_types_are_quantified_over_their_variables() -> anyhow::Result<()> {
+        let program = "fn x -> fn y -> (x, y)";
+        let expr = parse_expr(program)?.to_core()?;
+
+        let typ = generalized_type_of(&expr, IoCapabilities::default())?;
+
+        assert_eq!(typ.quantifiers.len(), 2);
+        assert_eq!(typ.free(), im::HashSet::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_annotating_infers_the_type_or_collects_every_error() -> anyhow::Result<()> {
+        let valid = parse_expr("let id = fn x -> x in (id 1, id true)")?.to_core()?;
+        let invalid = parse_expr("(1 + true, 2 + false)")?.to_core()?;
+
+        let annotated = annotate_collecting_errors(&valid, IoCapabilities::default());
+        let errors = annotate_collecting_errors(&invalid, IoCapabilities::default());
+
+        assert_eq!(
+            annotated.map(|typed| typed.typ().clone()),
+            Ok(type_of(&valid, IoCapabilities::default())?)
+        );
+        assert_eq!(
+            errors.map(|typed| typed.typ().clone()),
+            Err(type_errors(&invalid, IoCapabilities::default()))
+        );
+        assert_eq!(type_errors(&invalid, IoCapabilities::default()).len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_at_rejects_offsets_outside_the_expression() -> anyhow::Result<()> {
+        let program = "1 + 2";
+        let expr = parse_expr(program)?.to_core()?;
+
+        let result = type_at(&expr, 5, IoCapabilities::default());
+
+        assert_eq!(result, Err(Error::NoExpressionAtOffset { offset: 5 }));
+        Ok(())
+    }
 }
@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use boo_core::types::{Monotype, Type, TypeVariable};
 
-use crate::types::Monomorphic;
+use crate::types::{FreeVariables, Monomorphic};
 
 #[derive(Debug, Clone)]
 pub struct Subst(im::HashMap<TypeVariable, Monotype>);
@@ -20,11 +20,32 @@ impl Subst {
         self.0.get(key)
     }
 
+    /// Removes the given variables from the substitution.
+    pub fn without(&self, variables: &[TypeVariable]) -> Self {
+        let mut result = self.0.clone();
+        for variable in variables {
+            result.remove(variable);
+        }
+        Self(result)
+    }
+
+    /// Returns `true` if the substitution replaces the given variable, or
+    /// replaces anything with a type that refers to it.
+    pub fn mentions(&self, variable: &TypeVariable) -> bool {
+        self.0
+            .iter()
+            .any(|(key, value)| key == variable || value.free().contains(variable))
+    }
+
+    /// Composes two substitutions, so that applying the result is equivalent
+    /// to applying `self`, followed by `other`.
     pub fn then(&self, other: &Self) -> Self {
         Self(
             self.0
-                .clone()
-                .union_with(other.0.clone(), |_, later_type| later_type.substitute(self)),
+                .iter()
+                .map(|(variable, typ)| (variable.clone(), typ.substitute(other)))
+                .collect::<im::HashMap<_, _>>()
+                .union(other.0.clone()),
         )
     }
 
@@ -71,6 +92,10 @@ impl FromIterator<(TypeVariable, Monotype)> for Subst {
 fn match_types(left: &Monotype, right: &Monotype) -> Option<Subst> {
     match (left.as_ref(), right.as_ref()) {
         (Type::Integer, Type::Integer) => Some(Subst::empty()),
+        (Type::Float, Type::Float) => Some(Subst::empty()),
+        (Type::Boolean, Type::Boolean) => Some(Subst::empty()),
+        (Type::Char, Type::Char) => Some(Subst::empty()),
+        (Type::String, Type::String) => Some(Subst::empty()),
         (
             Type::Function {
                 parameter: left_parameter,
@@ -85,8 +110,106 @@ fn match_types(left: &Monotype, right: &Monotype) -> Option<Subst> {
             let body_subst = match_types(left_body, right_body)?;
             parameter_subst.merge(&body_subst)
         }
+        (Type::Tuple(left_elements), Type::Tuple(right_elements))
+            if left_elements.len() == right_elements.len() =>
+        {
+            left_elements.iter().zip(right_elements.iter()).try_fold(
+                Subst::empty(),
+                |subst, (left_element, right_element)| {
+                    subst.merge(&match_types(left_element, right_element)?)
+                },
+            )
+        }
+        (Type::List(left_element), Type::List(right_element)) => {
+            match_types(left_element, right_element)
+        }
+        (Type::Option(left_element), Type::Option(right_element)) => {
+            match_types(left_element, right_element)
+        }
+        (
+            Type::Result {
+                ok: left_ok,
+                error: left_error,
+            },
+            Type::Result {
+                ok: right_ok,
+                error: right_error,
+            },
+        ) => {
+            let ok_subst = match_types(left_ok, right_ok)?;
+            let error_subst = match_types(left_error, right_error)?;
+            ok_subst.merge(&error_subst)
+        }
+        (
+            Type::Constructor {
+                name: left_name,
+                arguments: left_arguments,
+            },
+            Type::Constructor {
+                name: right_name,
+                arguments: right_arguments,
+            },
+        ) if left_name == right_name && left_arguments.len() == right_arguments.len() => {
+            left_arguments.iter().zip(right_arguments.iter()).try_fold(
+                Subst::empty(),
+                |subst, (left_argument, right_argument)| {
+                    subst.merge(&match_types(left_argument, right_argument)?)
+                },
+            )
+        }
+        (
+            Type::Forall {
+                quantifiers: left_quantifiers,
+                body: left_body,
+            },
+            Type::Forall {
+                quantifiers: right_quantifiers,
+                body: right_body,
+            },
+        ) if left_quantifiers.len() == right_quantifiers.len() => {
+            let renaming = rename(right_quantifiers, left_quantifiers);
+            let subst = match_types(left_body, &right_body.substitute(&renaming))?;
+            if left_quantifiers.iter().any(|q| subst.mentions(q)) {
+                None
+            } else {
+                Some(subst)
+            }
+        }
+        (
+            Type::Recursive {
+                variable: left_variable,
+                body: left_body,
+            },
+            Type::Recursive {
+                variable: right_variable,
+                body: right_body,
+            },
+        ) => {
+            let renaming = rename(
+                std::slice::from_ref(right_variable),
+                std::slice::from_ref(left_variable),
+            );
+            let subst = match_types(left_body, &right_body.substitute(&renaming))?;
+            if subst.mentions(left_variable) {
+                None
+            } else {
+                Some(subst)
+            }
+        }
         (left, Type::Variable(right)) => Some(Subst::of(right.clone(), left.clone().into())),
         (Type::Variable(left), right) => Some(Subst::of(left.clone(), right.clone().into())),
         _ => None,
     }
 }
+
+/// A substitution which renames each of the variables in `from` to the
+/// corresponding variable in `to`.
+pub fn rename(from: &[TypeVariable], to: &[TypeVariable]) -> Subst {
+    from.iter()
+        .cloned()
+        .zip(
+            to.iter()
+                .map(|variable| Type::Variable(variable.clone()).into()),
+        )
+        .collect()
+}
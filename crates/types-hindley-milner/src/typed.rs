@@ -0,0 +1,208 @@
+//! An AST annotated with the inferred type of every subexpression.
+
+use std::collections::HashMap;
+
+use boo_core::ast::*;
+use boo_core::expr::Expr;
+use boo_core::pretty::{self, Doc, Pretty};
+use boo_core::span::Span;
+use boo_core::types::Monotype;
+
+use crate::subst::Subst;
+use crate::types::Monomorphic;
+
+/// Wraps an expression with a span and its inferred type.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TypedExpr {
+    span: Option<Span>,
+    typ: Monotype,
+    expression: Box<Expression<TypedExpr>>,
+}
+
+impl TypedExpr {
+    pub fn new(span: Option<Span>, typ: Monotype, expression: Expression<Self>) -> Self {
+        Self {
+            span,
+            typ,
+            expression: expression.into(),
+        }
+    }
+
+    pub fn expression(&self) -> &Expression<TypedExpr> {
+        self.expression.as_ref()
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn typ(&self) -> &Monotype {
+        &self.typ
+    }
+
+    /// Finds the smallest subexpression whose span contains the offset.
+    ///
+    /// Desugared expressions, such as infix operators, share the span of the
+    /// original expression, so if several match, the outermost is chosen.
+    pub fn at(&self, offset: usize) -> Option<&TypedExpr> {
+        let span = self.span?;
+        if !span.range().contains(&offset) {
+            return None;
+        }
+        let length = |span: Option<Span>| span.map_or(usize::MAX, |span| span.range().len());
+        let innermost = self
+            .children()
+            .into_iter()
+            .filter_map(|child| child.at(offset))
+            .min_by_key(|child| length(child.span));
+        match innermost {
+            Some(child) if length(child.span) < length(self.span) => Some(child),
+            _ => Some(self),
+        }
+    }
+
+    fn children(&self) -> Vec<&TypedExpr> {
+        match self.expression() {
+            Expression::Primitive(_) | Expression::Native(_) | Expression::Identifier(_) => {
+                vec![]
+            }
+            Expression::Function(Function { body, .. }) => vec![body],
+            Expression::Apply(Apply { function, argument }) => vec![function, argument],
+            Expression::Assign(Assign { value, inner, .. }) => vec![value, inner],
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => bindings
+                .iter()
+                .map(|(_, value)| value)
+                .chain([inner])
+                .collect(),
+            Expression::Match(Match { value, patterns }) => [value]
+                .into_iter()
+                .chain(patterns.iter().map(|PatternMatch { result, .. }| result))
+                .collect(),
+            Expression::Typed(Typed { expression, .. }) => vec![expression],
+            Expression::Sequence(Sequence { first, second }) => vec![first, second],
+            Expression::Tuple(Tuple { elements }) => elements.iter().collect(),
+            Expression::List(List::Empty) => vec![],
+            Expression::List(List::Cons { head, tail }) => vec![head, tail],
+        }
+    }
+}
+
+// We use this for testing, and the default implementation is a bit ugly.
+impl std::fmt::Debug for TypedExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypedExpr")
+            .field("span", &self.span)
+            .field("typ", &self.typ)
+            .field("expression", self.expression.as_ref())
+            .finish()
+    }
+}
+
+impl Pretty for TypedExpr {
+    fn to_doc(&self) -> Doc {
+        self.expression.to_doc()
+    }
+}
+
+impl std::fmt::Display for TypedExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        pretty::display(self, f)
+    }
+}
+
+/// The types inferred for each subexpression during type inference.
+///
+/// Subexpressions are identified by their address, as the same expression may
+/// appear many times in a tree. This means the expression must not move
+/// between inferring and annotating it.
+#[derive(Default)]
+pub(crate) struct Annotations(HashMap<*const Expr, Monotype>);
+
+impl Annotations {
+    pub fn record(&mut self, expr: &Expr, typ: Monotype) {
+        self.0.insert(expr, typ);
+    }
+
+    /// Builds the typed tree, applying the final substitution to each type.
+    pub fn annotate(&self, expr: &Expr, subst: &Subst) -> TypedExpr {
+        let typ = self
+            .0
+            .get(&(expr as *const Expr))
+            .expect("Subexpression was not inferred.")
+            .substitute(subst);
+        let annotate = |expr: &Expr| self.annotate(expr, subst);
+        let expression = match expr.expression() {
+            Expression::Primitive(primitive) => Expression::Primitive(primitive.clone()),
+            Expression::Native(native) => Expression::Native(native.clone()),
+            Expression::Identifier(identifier) => Expression::Identifier(identifier.clone()),
+            Expression::Function(Function {
+                parameter,
+                parameter_type,
+                parameter_type_span,
+                body,
+            }) => Expression::Function(Function {
+                parameter: parameter.clone(),
+                parameter_type: parameter_type.clone(),
+                parameter_type_span: *parameter_type_span,
+                body: annotate(body),
+            }),
+            Expression::Apply(Apply { function, argument }) => Expression::Apply(Apply {
+                function: annotate(function),
+                argument: annotate(argument),
+            }),
+            Expression::Assign(Assign { name, value, inner }) => Expression::Assign(Assign {
+                name: name.clone(),
+                value: annotate(value),
+                inner: annotate(inner),
+            }),
+            Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+                Expression::AssignRecursive(AssignRecursive {
+                    bindings: bindings
+                        .iter()
+                        .map(|(name, value)| (name.clone(), annotate(value)))
+                        .collect(),
+                    inner: annotate(inner),
+                })
+            }
+            Expression::Match(Match { value, patterns }) => Expression::Match(Match {
+                value: annotate(value),
+                patterns: patterns
+                    .iter()
+                    .map(
+                        |PatternMatch {
+                             pattern,
+                             pattern_span,
+                             result,
+                         }| PatternMatch {
+                            pattern: pattern.clone(),
+                            pattern_span: *pattern_span,
+                            result: annotate(result),
+                        },
+                    )
+                    .collect(),
+            }),
+            Expression::Typed(Typed {
+                expression,
+                typ,
+                typ_span,
+            }) => Expression::Typed(Typed {
+                expression: annotate(expression),
+                typ: typ.clone(),
+                typ_span: *typ_span,
+            }),
+            Expression::Sequence(Sequence { first, second }) => Expression::Sequence(Sequence {
+                first: annotate(first),
+                second: annotate(second),
+            }),
+            Expression::Tuple(Tuple { elements }) => Expression::Tuple(Tuple {
+                elements: elements.iter().map(annotate).collect(),
+            }),
+            Expression::List(List::Empty) => Expression::List(List::Empty),
+            Expression::List(List::Cons { head, tail }) => Expression::List(List::Cons {
+                head: annotate(head),
+                tail: annotate(tail),
+            }),
+        };
+        TypedExpr::new(expr.span(), typ, expression)
+    }
+}
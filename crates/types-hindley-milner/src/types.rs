@@ -18,9 +18,22 @@ pub trait Polymorphic: FreeVariables {
 impl FreeVariables for Type<Monotype> {
     fn free(&self) -> im::HashSet<TypeVariable> {
         match self {
-            Type::Integer => im::HashSet::new(),
+            Type::Integer | Type::Float | Type::Boolean | Type::Char | Type::String => {
+                im::HashSet::new()
+            }
             Type::Function { parameter, body } => parameter.free().union(body.free()),
+            Type::Tuple(elements) => im::HashSet::unions(elements.iter().map(|e| e.free())),
+            Type::List(element) => element.free(),
+            Type::Option(element) => element.free(),
+            Type::Result { ok, error } => ok.free().union(error.free()),
+            Type::Constructor { arguments, .. } => {
+                im::HashSet::unions(arguments.iter().map(|a| a.free()))
+            }
             Type::Variable(variable) => im::hashset![variable.clone()],
+            Type::Forall { quantifiers, body } => body
+                .free()
+                .relative_complement(quantifiers.iter().cloned().collect()),
+            Type::Recursive { variable, body } => body.free().without(variable),
         }
     }
 }
@@ -29,14 +42,46 @@ impl Monomorphic for Type<Monotype> {
     fn substitute(&self, substitutions: &Subst) -> Self {
         match self {
             Type::Integer => Type::Integer,
+            Type::Float => Type::Float,
+            Type::Boolean => Type::Boolean,
+            Type::Char => Type::Char,
+            Type::String => Type::String,
             Type::Function { parameter, body } => Type::Function {
                 parameter: parameter.substitute(substitutions),
                 body: body.substitute(substitutions),
             },
+            Type::Tuple(elements) => Type::Tuple(
+                elements
+                    .iter()
+                    .map(|element| element.substitute(substitutions))
+                    .collect(),
+            ),
+            Type::List(element) => Type::List(element.substitute(substitutions)),
+            Type::Option(element) => Type::Option(element.substitute(substitutions)),
+            Type::Result { ok, error } => Type::Result {
+                ok: ok.substitute(substitutions),
+                error: error.substitute(substitutions),
+            },
+            Type::Constructor { name, arguments } => Type::Constructor {
+                name: name.clone(),
+                arguments: arguments
+                    .iter()
+                    .map(|argument| argument.substitute(substitutions))
+                    .collect(),
+            },
             Type::Variable(variable) => match substitutions.get(variable) {
                 None => Type::Variable(variable.clone()),
                 Some(t) => (*t.0).clone(),
             },
+            // quantified variables shadow the substitution
+            Type::Forall { quantifiers, body } => Type::Forall {
+                quantifiers: quantifiers.clone(),
+                body: body.substitute(&substitutions.without(quantifiers)),
+            },
+            Type::Recursive { variable, body } => Type::Recursive {
+                variable: variable.clone(),
+                body: body.substitute(&substitutions.without(std::slice::from_ref(variable))),
+            },
         }
     }
 }
@@ -65,7 +110,7 @@ impl Polymorphic for Polytype {
         let replacements = self
             .quantifiers
             .iter()
-            .map(|q| (q.clone(), fresh.next()))
+            .map(|q| (q.clone(), fresh.next_like(q)))
             .collect::<Vec<_>>();
         let new_quantifiers = replacements
             .iter()
@@ -1,11 +1,92 @@
+use boo_core::error::Error;
 use boo_core::types::{Monotype, Type, TypeVariable};
 
-use crate::subst::Subst;
+use crate::subst::{rename, Subst};
 use crate::types::{FreeVariables, Monomorphic};
 
-pub fn unify(left: &Monotype, right: &Monotype) -> Option<Subst> {
+/// The reason two types could not be unified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    /// The types have different structures.
+    Mismatch,
+    /// A type variable would have to contain itself.
+    InfiniteType {
+        variable: TypeVariable,
+        typ: Monotype,
+    },
+    /// A comparable type variable would have to stand for a type whose values
+    /// cannot be compared.
+    NotComparable { typ: Monotype },
+}
+
+impl Failure {
+    /// Explains the failure, given the error to report for a plain mismatch.
+    ///
+    /// Infinite types are reported with their own error, keeping the spans and
+    /// types of the mismatch.
+    pub fn into_error(self, mismatch: Error) -> Error {
+        match self {
+            Failure::Mismatch => mismatch,
+            // the argument is the part that cannot be compared
+            Failure::NotComparable { typ } => match mismatch {
+                Error::TypeMismatch { span, .. }
+                | Error::TypeUnificationError {
+                    right_span: span, ..
+                } => Error::NotComparable { span, typ },
+                error => error,
+            },
+            Failure::InfiniteType { variable, typ } => match mismatch {
+                Error::TypeMismatch {
+                    span,
+                    expected_type,
+                    actual_type,
+                } => Error::InfiniteType {
+                    left_span: span,
+                    left_type: actual_type,
+                    right_span: None,
+                    right_type: expected_type,
+                    variable,
+                    typ,
+                },
+                Error::TypeUnificationError {
+                    left_span,
+                    left_type,
+                    right_span,
+                    right_type,
+                } => Error::InfiniteType {
+                    left_span,
+                    left_type,
+                    right_span,
+                    right_type,
+                    variable,
+                    typ,
+                },
+                error => error,
+            },
+        }
+    }
+}
+
+pub fn unify(left: &Monotype, right: &Monotype) -> Result<Subst, Failure> {
+    unify_assuming(left, right, &[])
+}
+
+/// Unifies two types, treating recursive types as equal to their unfoldings.
+///
+/// Unfolding recursive types can go on forever, so we keep track of the pairs
+/// of types we have already started unifying, and assume they unify if we come
+/// across them again.
+fn unify_assuming(
+    left: &Monotype,
+    right: &Monotype,
+    assumed: &[(Monotype, Monotype)],
+) -> Result<Subst, Failure> {
     match (left.as_ref(), right.as_ref()) {
-        (Type::Integer, Type::Integer) => Some(Subst::empty()),
+        (Type::Integer, Type::Integer) => Ok(Subst::empty()),
+        (Type::Float, Type::Float) => Ok(Subst::empty()),
+        (Type::Boolean, Type::Boolean) => Ok(Subst::empty()),
+        (Type::Char, Type::Char) => Ok(Subst::empty()),
+        (Type::String, Type::String) => Ok(Subst::empty()),
         (
             Type::Function {
                 parameter: left_parameter,
@@ -16,25 +97,331 @@ pub fn unify(left: &Monotype, right: &Monotype) -> Option<Subst> {
                 body: right_body,
             },
         ) => {
-            let parameter_subst = unify(left_parameter, right_parameter)?;
-            let body_subst = unify(
+            let parameter_subst = unify_assuming(left_parameter, right_parameter, assumed)?;
+            let body_subst = unify_assuming(
                 &left_body.substitute(&parameter_subst),
                 &right_body.substitute(&parameter_subst),
+                assumed,
             )?;
             let subst = parameter_subst.then(&body_subst);
-            Some(subst)
+            Ok(subst)
+        }
+        (Type::Tuple(left_elements), Type::Tuple(right_elements))
+            if left_elements.len() == right_elements.len() =>
+        {
+            left_elements.iter().zip(right_elements.iter()).try_fold(
+                Subst::empty(),
+                |subst, (left_element, right_element)| {
+                    let element_subst = unify_assuming(
+                        &left_element.substitute(&subst),
+                        &right_element.substitute(&subst),
+                        assumed,
+                    )?;
+                    Ok(subst.then(&element_subst))
+                },
+            )
+        }
+        (Type::List(left_element), Type::List(right_element)) => {
+            unify_assuming(left_element, right_element, assumed)
         }
-        (Type::Variable(l), Type::Variable(r)) if l == r => Some(Subst::empty()),
+        (Type::Option(left_element), Type::Option(right_element)) => {
+            unify_assuming(left_element, right_element, assumed)
+        }
+        (
+            Type::Result {
+                ok: left_ok,
+                error: left_error,
+            },
+            Type::Result {
+                ok: right_ok,
+                error: right_error,
+            },
+        ) => {
+            let ok_subst = unify_assuming(left_ok, right_ok, assumed)?;
+            let error_subst = unify_assuming(
+                &left_error.substitute(&ok_subst),
+                &right_error.substitute(&ok_subst),
+                assumed,
+            )?;
+            Ok(ok_subst.then(&error_subst))
+        }
+        (
+            Type::Constructor {
+                name: left_name,
+                arguments: left_arguments,
+            },
+            Type::Constructor {
+                name: right_name,
+                arguments: right_arguments,
+            },
+        ) if left_name == right_name && left_arguments.len() == right_arguments.len() => {
+            left_arguments.iter().zip(right_arguments.iter()).try_fold(
+                Subst::empty(),
+                |subst, (left_argument, right_argument)| {
+                    let argument_subst = unify_assuming(
+                        &left_argument.substitute(&subst),
+                        &right_argument.substitute(&subst),
+                        assumed,
+                    )?;
+                    Ok(subst.then(&argument_subst))
+                },
+            )
+        }
+        (
+            Type::Forall {
+                quantifiers: left_quantifiers,
+                body: left_body,
+            },
+            Type::Forall {
+                quantifiers: right_quantifiers,
+                body: right_body,
+            },
+        ) if left_quantifiers.len() == right_quantifiers.len() => {
+            // the quantified variables must stay abstract
+            let renaming = rename(right_quantifiers, left_quantifiers);
+            let subst = unify_assuming(left_body, &right_body.substitute(&renaming), assumed)?;
+            if left_quantifiers.iter().any(|q| subst.mentions(q)) {
+                Err(Failure::Mismatch)
+            } else {
+                Ok(subst)
+            }
+        }
+        (Type::Variable(l), Type::Variable(r)) if l == r => Ok(Subst::empty()),
         (Type::Variable(var), _) => var_bind(var, right),
         (_, Type::Variable(var)) => var_bind(var, left),
-        _ => None,
+        (Type::Recursive { .. }, _) | (_, Type::Recursive { .. }) => {
+            let pair = (left.clone(), right.clone());
+            if assumed.contains(&pair) {
+                return Ok(Subst::empty());
+            }
+            let assumed = [assumed, &[pair]].concat();
+            let left = unfold(left).ok_or(Failure::Mismatch)?;
+            let right = unfold(right).ok_or(Failure::Mismatch)?;
+            unify_assuming(&left, &right, &assumed)
+        }
+        _ => Err(Failure::Mismatch),
     }
 }
 
-fn var_bind(var: &TypeVariable, typ: &Monotype) -> Option<Subst> {
+/// Replaces the variable in a recursive type with the type itself, exposing
+/// one layer of its structure. Other types are returned unchanged.
+///
+/// Types such as `mu a. a` have no structure to expose, and are rejected.
+fn unfold(typ: &Monotype) -> Option<Monotype> {
+    match typ.as_ref() {
+        Type::Recursive { variable, body } => {
+            if !is_contractive(variable, body) {
+                return None;
+            }
+            Some(body.substitute(&Subst::of(variable.clone(), typ.clone())))
+        }
+        _ => Some(typ.clone()),
+    }
+}
+
+fn is_contractive(variable: &TypeVariable, body: &Monotype) -> bool {
+    match body.as_ref() {
+        Type::Variable(other) => other != variable,
+        Type::Recursive {
+            variable: inner,
+            body: inner_body,
+        } => is_contractive(variable, inner_body) && is_contractive(inner, inner_body),
+        _ => true,
+    }
+}
+
+fn var_bind(var: &TypeVariable, typ: &Monotype) -> Result<Subst, Failure> {
     if typ.free().contains(var) {
-        None
-    } else {
-        Some(Subst::of(var.clone(), typ.clone()))
+        return Err(Failure::InfiniteType {
+            variable: var.clone(),
+            typ: typ.clone(),
+        });
+    }
+    if var.is_comparable() {
+        match typ.as_ref() {
+            Type::Integer | Type::Float | Type::Boolean | Type::Char | Type::String => {}
+            // the other variable takes on the restriction instead
+            Type::Variable(other) if !other.is_comparable() => {
+                return Ok(Subst::of(other.clone(), Type::Variable(var.clone()).into()));
+            }
+            Type::Variable(_) => {}
+            _ => return Err(Failure::NotComparable { typ: typ.clone() }),
+        }
+    }
+    Ok(Subst::of(var.clone(), typ.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use boo_core::types::TypeName;
+
+    use super::*;
+
+    #[test]
+    fn test_unifies_type_constructors_by_unifying_their_arguments() {
+        let a: Monotype = Type::Variable(TypeVariable::new_from_str("a")).into();
+        let b: Monotype = Type::Variable(TypeVariable::new_from_str("b")).into();
+        let left = constructor("Pair", vec![a, Type::Boolean.into()]);
+        let right = constructor("Pair", vec![Type::Integer.into(), b]);
+
+        let subst = unify(&left, &right);
+
+        assert_eq!(
+            subst.map(|subst| (left.substitute(&subst), right.substitute(&subst))),
+            Ok((
+                constructor("Pair", vec![Type::Integer.into(), Type::Boolean.into()]),
+                constructor("Pair", vec![Type::Integer.into(), Type::Boolean.into()]),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_does_not_unify_type_constructors_with_different_names() {
+        let left = constructor("Maybe", vec![Type::Integer.into()]);
+        let right = constructor("List", vec![Type::Integer.into()]);
+
+        let subst = unify(&left, &right);
+
+        assert!(subst.is_err());
+    }
+
+    #[test]
+    fn test_does_not_unify_type_constructors_with_different_numbers_of_arguments() {
+        let left = constructor("Maybe", vec![Type::Integer.into()]);
+        let right = constructor("Maybe", vec![]);
+
+        let subst = unify(&left, &right);
+
+        assert!(subst.is_err());
+    }
+
+    #[test]
+    fn test_reports_a_variable_that_occurs_in_the_other_type() {
+        let a = TypeVariable::new_from_str("a");
+        let list: Monotype = Type::List(Type::Variable(a.clone()).into()).into();
+
+        let subst = unify(&Type::Variable(a.clone()).into(), &list);
+
+        assert_eq!(
+            subst.err(),
+            Some(Failure::InfiniteType {
+                variable: a,
+                typ: list
+            })
+        );
+    }
+
+    #[test]
+    fn test_unifies_a_recursive_type_with_its_unfolding() {
+        let list = integer_list();
+        let unfolded: Monotype = Type::Tuple(vec![
+            Type::Integer.into(),
+            Type::Option(list.clone()).into(),
+        ])
+        .into();
+
+        let subst = unify(&list, &unfolded);
+
+        assert!(subst.is_ok());
+    }
+
+    #[test]
+    fn test_unifies_variables_inside_an_unfolded_recursive_type() {
+        let a: Monotype = Type::Variable(TypeVariable::new_from_str("a")).into();
+        let b: Monotype = Type::Variable(TypeVariable::new_from_str("b")).into();
+        let list = integer_list();
+        let partial: Monotype = Type::Tuple(vec![a.clone(), Type::Option(b.clone()).into()]).into();
+
+        let subst = unify(&partial, &list);
+
+        assert_eq!(
+            subst.map(|subst| (a.substitute(&subst), b.substitute(&subst))),
+            Ok((Type::Integer.into(), list))
+        );
+    }
+
+    #[test]
+    fn test_does_not_unify_a_recursive_type_with_a_different_structure() {
+        let list = integer_list();
+        let unfolded: Monotype = Type::Tuple(vec![
+            Type::Boolean.into(),
+            Type::Option(list.clone()).into(),
+        ])
+        .into();
+
+        let subst = unify(&list, &unfolded);
+
+        assert!(subst.is_err());
+    }
+
+    #[test]
+    fn test_does_not_unify_recursive_types_without_structure() {
+        let t = TypeVariable::new_from_str("t");
+        let empty: Monotype = Type::Recursive {
+            variable: t.clone(),
+            body: Type::Variable(t).into(),
+        }
+        .into();
+
+        let subst = unify(&empty, &Type::Integer.into());
+
+        assert!(subst.is_err());
+    }
+
+    /// `mu t. (Integer, Option t)`
+    fn integer_list() -> Monotype {
+        let t = TypeVariable::new_from_str("t");
+        Type::Recursive {
+            variable: t.clone(),
+            body: Type::Tuple(vec![
+                Type::Integer.into(),
+                Type::Option(Type::Variable(t).into()).into(),
+            ])
+            .into(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_unifies_comparable_variables_with_primitives() {
+        let a: Monotype = Type::Variable(TypeVariable::new_comparable("a")).into();
+
+        let subst = unify(&a, &Type::String.into());
+
+        assert_eq!(
+            subst.map(|subst| a.substitute(&subst)),
+            Ok(Type::String.into())
+        );
+    }
+
+    #[test]
+    fn test_restricts_variables_unified_with_comparable_variables() {
+        let a: Monotype = Type::Variable(TypeVariable::new_comparable("a")).into();
+        let b: Monotype = Type::Variable(TypeVariable::new_from_str("b")).into();
+
+        let subst = unify(&a, &b);
+
+        assert_eq!(
+            subst.map(|subst| (a.substitute(&subst), b.substitute(&subst))),
+            Ok((a.clone(), a.clone()))
+        );
+    }
+
+    #[test]
+    fn test_does_not_unify_comparable_variables_with_compound_types() {
+        let a: Monotype = Type::Variable(TypeVariable::new_comparable("a")).into();
+        let list: Monotype = Type::List(Type::Integer.into()).into();
+
+        let subst = unify(&list, &a);
+
+        assert_eq!(subst.err(), Some(Failure::NotComparable { typ: list }));
+    }
+
+    fn constructor(name: &str, arguments: Vec<Monotype>) -> Monotype {
+        Type::Constructor {
+            name: TypeName::new_from_str(name),
+            arguments,
+        }
+        .into()
     }
 }
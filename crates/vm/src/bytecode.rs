@@ -0,0 +1,118 @@
+//! The instructions executed by the virtual machine.
+
+use boo_core::ast::Pattern;
+use boo_core::identifier::Identifier;
+use boo_core::native::Native;
+use boo_core::primitive::Primitive;
+use boo_core::span::Span;
+use boo_core::types::Monotype;
+use boo_evaluation_pooling::ast::Expr;
+
+/// The compiled form of a single expression.
+///
+/// Executing the code leaves the value of the expression on top of the stack,
+/// and then returns to the caller.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Code(pub Vec<Instruction>);
+
+/// A single instruction.
+///
+/// Expressions which are evaluated lazily, such as arguments and the bodies of
+/// functions, are referred to by their location in the pool. They are compiled
+/// separately, the first time they are evaluated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    /// Pushes a primitive value.
+    Push(Primitive),
+    /// Computes a primitive value natively, and pushes it.
+    Native(Native),
+    /// Pushes the value bound to the variable, evaluating it if necessary.
+    Load {
+        name: Identifier,
+        span: Option<Span>,
+    },
+    /// Pushes a function, closing over the current environment.
+    Closure {
+        parameter: Identifier,
+        parameter_type: Option<Monotype>,
+        body: Expr,
+    },
+    /// Pushes the expression, unevaluated, closing over the current
+    /// environment.
+    Suspend(Expr),
+    /// Evaluates the expression in the current environment, and pushes its
+    /// value.
+    Call(Expr),
+    /// Pops a function and then its argument, and applies one to the other.
+    Apply { span: Option<Span> },
+    /// Binds the expression, unevaluated, in the current environment.
+    Bind { name: Identifier, value: Expr },
+    /// Binds a group of mutually-recursive expressions in the current
+    /// environment.
+    BindRecursive(Vec<(Identifier, Expr)>),
+    /// Discards the value on top of the stack.
+    Pop,
+    /// Pushes a tuple of unevaluated elements.
+    Tuple(Vec<Expr>),
+    /// Pushes the empty list.
+    Nil,
+    /// Pushes a list with an unevaluated head and tail.
+    Cons { head: Expr, tail: Expr },
+    /// Matches the pattern against the value on top of the stack.
+    ///
+    /// If it matches, pops the value, and binds the pattern's variables in the
+    /// current environment. Otherwise, jumps to the target.
+    JumpUnlessMatches { pattern: Pattern, target: usize },
+    /// Fails, as no pattern matched the value.
+    NoMatch { span: Option<Span> },
+    /// Returns the value on top of the stack to the caller.
+    Return,
+}
+
+impl std::fmt::Display for Code {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, instruction) in self.0.iter().enumerate() {
+            writeln!(f, "{index:>4}  {instruction}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instruction::Push(value) => write!(f, "push {value}"),
+            Instruction::Native(native) => write!(f, "native {native}"),
+            Instruction::Load { name, .. } => write!(f, "load {name}"),
+            Instruction::Closure {
+                parameter, body, ..
+            } => write!(f, "closure {parameter} @{body}"),
+            Instruction::Suspend(expr) => write!(f, "suspend @{expr}"),
+            Instruction::Call(expr) => write!(f, "call @{expr}"),
+            Instruction::Apply { .. } => write!(f, "apply"),
+            Instruction::Bind { name, value } => write!(f, "bind {name} @{value}"),
+            Instruction::BindRecursive(bindings) => {
+                write!(f, "bind-recursive")?;
+                for (name, value) in bindings {
+                    write!(f, " {name} @{value}")?;
+                }
+                Ok(())
+            }
+            Instruction::Pop => write!(f, "pop"),
+            Instruction::Tuple(elements) => {
+                write!(f, "tuple")?;
+                for element in elements {
+                    write!(f, " @{element}")?;
+                }
+                Ok(())
+            }
+            Instruction::Nil => write!(f, "nil"),
+            Instruction::Cons { head, tail } => write!(f, "cons @{head} @{tail}"),
+            Instruction::JumpUnlessMatches { pattern, target } => {
+                write!(f, "jump-unless-matches {pattern} {target}")
+            }
+            Instruction::NoMatch { .. } => write!(f, "no-match"),
+            Instruction::Return => write!(f, "return"),
+        }
+    }
+}
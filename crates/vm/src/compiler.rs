@@ -0,0 +1,239 @@
+//! Compiles pooled expressions into [`Code`].
+
+use boo_core::ast::*;
+use boo_core::evaluation::ExpressionReader;
+use boo_core::span::Spanned;
+use boo_evaluation_pooling::ast::{Expr, ExprPool};
+
+use crate::bytecode::*;
+
+/// Compiles an expression, so that executing the code returns its value.
+pub fn compile(pool: &ExprPool, expr: Expr) -> Code {
+    let mut code = vec![];
+    compile_tail(pool, expr, &mut code);
+    Code(code)
+}
+
+/// Compiles an expression in tail position, returning its value.
+///
+/// Expressions in tail position may modify the environment, as nothing else
+/// will be evaluated in it afterwards.
+fn compile_tail(pool: &ExprPool, expr: Expr, code: &mut Vec<Instruction>) {
+    let Spanned {
+        span,
+        value: expression,
+    } = pool.read(expr);
+    match expression {
+        Expression::Assign(Assign { name, value, inner }) => {
+            code.push(Instruction::Bind {
+                name: name.clone(),
+                value: *value,
+            });
+            compile_tail(pool, *inner, code);
+        }
+        Expression::AssignRecursive(AssignRecursive { bindings, inner }) => {
+            code.push(Instruction::BindRecursive(bindings.clone()));
+            compile_tail(pool, *inner, code);
+        }
+        Expression::Match(Match { value, patterns }) => {
+            let inspects_value =
+                patterns
+                    .front()
+                    .is_some_and(|PatternMatch { pattern, .. }| {
+                        !matches!(pattern, Pattern::Anything | Pattern::Variable(_))
+                    });
+            if inspects_value {
+                compile_value(pool, *value, code);
+            } else {
+                code.push(Instruction::Suspend(*value));
+            }
+            for PatternMatch {
+                pattern, result, ..
+            } in patterns
+            {
+                let jump = code.len();
+                code.push(Instruction::JumpUnlessMatches {
+                    pattern: pattern.clone(),
+                    target: 0,
+                });
+                compile_tail(pool, *result, code);
+                let next = code.len();
+                if let Instruction::JumpUnlessMatches { target, .. } = &mut code[jump] {
+                    *target = next;
+                }
+            }
+            code.push(Instruction::NoMatch { span });
+        }
+        Expression::Sequence(Sequence { first, second }) => {
+            compile_value(pool, *first, code);
+            code.push(Instruction::Pop);
+            compile_tail(pool, *second, code);
+        }
+        Expression::Typed(Typed { expression, .. }) => {
+            compile_tail(pool, *expression, code);
+        }
+        _ => {
+            compile_value(pool, expr, code);
+            code.push(Instruction::Return);
+        }
+    }
+}
+
+/// Compiles an expression so that its value is pushed onto the stack, leaving
+/// the environment as it was.
+fn compile_value(pool: &ExprPool, expr: Expr, code: &mut Vec<Instruction>) {
+    let Spanned {
+        span,
+        value: expression,
+    } = pool.read(expr);
+    match expression {
+        Expression::Primitive(value) => code.push(Instruction::Push(value.clone())),
+        Expression::Native(native) => code.push(Instruction::Native(native.clone())),
+        Expression::Identifier(name) => code.push(Instruction::Load {
+            name: name.clone(),
+            span,
+        }),
+        Expression::Function(Function {
+            parameter,
+            parameter_type,
+            body,
+            ..
+        }) => code.push(Instruction::Closure {
+            parameter: parameter.clone(),
+            parameter_type: parameter_type.clone(),
+            body: *body,
+        }),
+        Expression::Apply(Apply { function, argument }) => {
+            code.push(Instruction::Suspend(*argument));
+            compile_value(pool, *function, code);
+            code.push(Instruction::Apply { span });
+        }
+        Expression::Typed(Typed { expression, .. }) => {
+            compile_value(pool, *expression, code);
+        }
+        Expression::Tuple(Tuple { elements }) => code.push(Instruction::Tuple(elements.clone())),
+        Expression::List(List::Empty) => code.push(Instruction::Nil),
+        Expression::List(List::Cons { head, tail }) => code.push(Instruction::Cons {
+            head: *head,
+            tail: *tail,
+        }),
+        // these modify the environment, and so are evaluated separately
+        Expression::Assign(_)
+        | Expression::AssignRecursive(_)
+        | Expression::Match(_)
+        | Expression::Sequence(_) => code.push(Instruction::Call(expr)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use boo_core::expr::Expr as CoreExpr;
+    use boo_core::identifier::Identifier;
+    use boo_core::primitive::Primitive;
+    use boo_evaluation_pooling::ast::ExprPoolBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_compiles_nested_applications_into_a_single_sequence_of_instructions() {
+        let f = Identifier::name_from_str("f").unwrap();
+        let mut builder = ExprPoolBuilder::new();
+        let root = boo_evaluation_pooling::add_expr(
+            &mut builder,
+            CoreExpr::new(
+                None,
+                Expression::Apply(Apply {
+                    function: CoreExpr::new(
+                        None,
+                        Expression::Apply(Apply {
+                            function: CoreExpr::new(None, Expression::Identifier(f.clone())),
+                            argument: CoreExpr::new(
+                                None,
+                                Expression::Primitive(Primitive::Integer(1.into())),
+                            ),
+                        }),
+                    ),
+                    argument: CoreExpr::new(
+                        None,
+                        Expression::Primitive(Primitive::Integer(2.into())),
+                    ),
+                }),
+            ),
+        );
+        let pool = builder.build();
+
+        let code = compile(&pool, root);
+
+        assert!(matches!(
+            code.0.as_slice(),
+            [
+                Instruction::Suspend(_),
+                Instruction::Suspend(_),
+                Instruction::Load { name, .. },
+                Instruction::Apply { .. },
+                Instruction::Apply { .. },
+                Instruction::Return,
+            ] if *name == f
+        ));
+    }
+
+    #[test]
+    fn test_jumps_over_patterns_that_do_not_match() {
+        let n = Identifier::name_from_str("n").unwrap();
+        let mut builder = ExprPoolBuilder::new();
+        let root = boo_evaluation_pooling::add_expr(
+            &mut builder,
+            CoreExpr::new(
+                None,
+                Expression::Match(Match {
+                    value: CoreExpr::new(None, Expression::Identifier(n.clone())),
+                    patterns: [
+                        PatternMatch {
+                            pattern: Pattern::Primitive(Primitive::Integer(0.into())),
+                            pattern_span: None,
+                            result: CoreExpr::new(
+                                None,
+                                Expression::Primitive(Primitive::Boolean(true)),
+                            ),
+                        },
+                        PatternMatch {
+                            pattern: Pattern::Anything,
+                            pattern_span: None,
+                            result: CoreExpr::new(
+                                None,
+                                Expression::Primitive(Primitive::Boolean(false)),
+                            ),
+                        },
+                    ]
+                    .into(),
+                }),
+            ),
+        );
+        let pool = builder.build();
+
+        let code = compile(&pool, root);
+
+        assert_eq!(
+            code.0,
+            vec![
+                Instruction::Load {
+                    name: n,
+                    span: None
+                },
+                Instruction::JumpUnlessMatches {
+                    pattern: Pattern::Primitive(Primitive::Integer(0.into())),
+                    target: 4,
+                },
+                Instruction::Push(Primitive::Boolean(true)),
+                Instruction::Return,
+                Instruction::JumpUnlessMatches {
+                    pattern: Pattern::Anything,
+                    target: 7,
+                },
+                Instruction::Push(Primitive::Boolean(false)),
+                Instruction::Return,
+                Instruction::NoMatch { span: None },
+            ]
+        );
+    }
+}
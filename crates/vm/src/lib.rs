@@ -0,0 +1,38 @@
+//! Evaluates a core AST by compiling it to bytecode, which is then executed by
+//! a stack-based virtual machine.
+//!
+//! Expressions are pooled first, and each pooled expression is compiled the
+//! first time it is evaluated. As with the other evaluators, values are bound
+//! lazily, and each binding is only evaluated once.
+
+pub mod bytecode;
+pub mod compiler;
+pub mod machine;
+
+use boo_core::evaluation::IntegerOverflow;
+use boo_evaluation_lazy::{Bindings, MemoryBudget, Observer};
+use boo_evaluation_pooling::{ast, NewInnerEvaluator, PoolingEvaluationContext};
+
+pub use machine::VirtualMachine;
+
+pub fn new() -> PoolingEvaluationContext<NewVirtualMachine> {
+    PoolingEvaluationContext::<NewVirtualMachine>::new()
+}
+
+pub struct NewVirtualMachine {}
+
+impl<'pool> NewInnerEvaluator<'pool> for NewVirtualMachine {
+    type Inner = VirtualMachine<'pool>;
+
+    // the virtual machine does not track its memory usage or support
+    // observation yet
+    fn new(
+        pool: &'pool ast::ExprPool,
+        bindings: Bindings<ast::Expr>,
+        _budget: MemoryBudget,
+        _observer: Option<Observer<ast::Expr>>,
+        integer_overflow: IntegerOverflow,
+    ) -> Self::Inner {
+        VirtualMachine::new(pool, bindings).with_integer_overflow(integer_overflow)
+    }
+}
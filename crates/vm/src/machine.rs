@@ -0,0 +1,376 @@
+//! Executes compiled [`Code`].
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use boo_core::ast::*;
+use boo_core::error::*;
+use boo_core::evaluation::*;
+use boo_core::identifier::Identifier;
+use boo_core::matching::{Selected, Selection};
+use boo_core::native::*;
+use boo_core::primitive::Primitive;
+use boo_core::span::{Span, Spanned};
+use boo_evaluation_lazy::{
+    resolved, select, Binding, Bindings, CompletedEvaluation, EvaluatedBinding,
+};
+use boo_evaluation_pooling::ast::{Expr, ExprPool};
+
+use crate::bytecode::*;
+use crate::compiler::compile;
+
+/// A stack-based virtual machine.
+///
+/// Each expression is compiled the first time it is evaluated, and the code is
+/// kept for subsequent evaluations.
+pub struct VirtualMachine<'pool> {
+    pool: &'pool ExprPool,
+    bindings: Bindings<Expr>,
+    compiled: RefCell<HashMap<Expr, Rc<Code>>>,
+    integer_overflow: IntegerOverflow,
+}
+
+impl<'pool> VirtualMachine<'pool> {
+    pub fn new(pool: &'pool ExprPool, bindings: Bindings<Expr>) -> Self {
+        Self {
+            pool,
+            bindings,
+            compiled: RefCell::new(HashMap::new()),
+            integer_overflow: IntegerOverflow::default(),
+        }
+    }
+
+    /// Decides how arithmetic on small integers handles overflow.
+    pub fn with_integer_overflow(self, integer_overflow: IntegerOverflow) -> Self {
+        Self {
+            integer_overflow,
+            ..self
+        }
+    }
+}
+
+impl<'pool> Evaluator<Expr> for VirtualMachine<'pool> {
+    fn evaluate(&self, expr: Expr) -> Result<Evaluated<Expr>> {
+        self.run(expr, self.bindings.clone(), None)?
+            .finish(&|binding| self.force(binding))
+    }
+}
+
+/// An entry on the stack.
+enum Operand {
+    /// An evaluated value.
+    Value(CompletedEvaluation<Expr>),
+    /// A value which may not have been evaluated yet.
+    Binding(Binding<Expr>),
+}
+
+/// The code being executed, along with the environment in which it executes.
+struct Frame {
+    code: Rc<Code>,
+    pc: usize,
+    environment: Bindings<Expr>,
+    /// The binding to update with the result, so it is only evaluated once.
+    update: Option<Binding<Expr>>,
+    /// The most recent application of a function, blamed for errors in natives.
+    call_site: Option<Span>,
+    /// A match waiting for part of its value, which is evaluated in a new
+    /// frame, after which the match is run again.
+    selection: Option<Selection<Binding<Expr>, ()>>,
+}
+
+impl<'pool> VirtualMachine<'pool> {
+    /// Evaluates an expression, running until the outermost frame returns.
+    fn run(
+        &self,
+        expr: Expr,
+        environment: Bindings<Expr>,
+        update: Option<Binding<Expr>>,
+    ) -> EvaluatedBinding<Expr> {
+        let mut stack: Vec<Operand> = vec![];
+        let mut frames: Vec<Frame> = vec![];
+        self.enter(&mut frames, expr, environment, update, None);
+
+        loop {
+            let frame = frames.last_mut().unwrap();
+            let code = Rc::clone(&frame.code);
+            let instruction = &code.0[frame.pc];
+            frame.pc += 1;
+            match instruction {
+                Instruction::Push(value) => {
+                    stack.push(Operand::Value(CompletedEvaluation::Primitive(
+                        value.clone(),
+                    )));
+                }
+                Instruction::Native(Native { implementation, .. }) => {
+                    let value = implementation(&self.switch(frame.environment.clone()))
+                        .map_err(|error| error.located_at(frame.call_site))?;
+                    stack.push(Operand::Value(CompletedEvaluation::Primitive(value)));
+                }
+                Instruction::Load { name, span } => {
+                    let binding = frame.environment.read(name).cloned().ok_or_else(|| {
+                        Error::unknown_variable(*span, name, frame.environment.identifiers())
+                    })?;
+                    match binding.unresolved_value() {
+                        Some((expr, environment)) => {
+                            let call_site = frame.call_site;
+                            self.enter(&mut frames, expr, environment, Some(binding), call_site);
+                        }
+                        None => {
+                            let value =
+                                resolved(binding.value().expect("Binding was not resolved."))?;
+                            stack.push(Operand::Value(value));
+                        }
+                    }
+                }
+                Instruction::Closure {
+                    parameter,
+                    parameter_type,
+                    body,
+                } => {
+                    stack.push(Operand::Value(CompletedEvaluation::Closure {
+                        parameter: parameter.clone(),
+                        parameter_type: parameter_type.clone(),
+                        body: *body,
+                        bindings: frame.environment.clone(),
+                    }));
+                }
+                Instruction::Suspend(expr) => {
+                    let binding = self.suspend(*expr, &mut frame.environment);
+                    stack.push(Operand::Binding(binding));
+                }
+                Instruction::Call(expr) => {
+                    let environment = frame.environment.clone();
+                    let call_site = frame.call_site;
+                    self.enter(&mut frames, *expr, environment, None, call_site);
+                }
+                Instruction::Apply { span } => {
+                    let function = stack.pop();
+                    let Some(Operand::Binding(argument)) = stack.pop() else {
+                        unreachable!("Applied a function without an argument.");
+                    };
+                    match function {
+                        Some(Operand::Value(CompletedEvaluation::Closure {
+                            parameter,
+                            body,
+                            bindings,
+                            ..
+                        })) => {
+                            let environment = bindings.with_binding(parameter, argument);
+                            let call_site = span.or(frame.call_site);
+                            self.enter(&mut frames, body, environment, None, call_site);
+                        }
+                        _ => {
+                            return Err(Error::InvalidFunctionApplication { span: *span });
+                        }
+                    }
+                }
+                Instruction::Bind { name, value } => {
+                    frame.environment =
+                        frame
+                            .environment
+                            .with(name.clone(), *value, frame.environment.clone());
+                }
+                Instruction::BindRecursive(bindings) => {
+                    frame.environment = frame.environment.with_recursive(bindings.clone());
+                }
+                Instruction::Pop => {
+                    stack.pop();
+                }
+                Instruction::Tuple(elements) => {
+                    stack.push(Operand::Value(CompletedEvaluation::Tuple(
+                        elements
+                            .iter()
+                            .map(|element| {
+                                Binding::unresolved((*element, frame.environment.clone()))
+                            })
+                            .collect(),
+                    )));
+                }
+                Instruction::Nil => {
+                    stack.push(Operand::Value(CompletedEvaluation::List(List::Empty)));
+                }
+                Instruction::Cons { head, tail } => {
+                    stack.push(Operand::Value(CompletedEvaluation::List(List::Cons {
+                        head: Binding::unresolved((*head, frame.environment.clone())),
+                        tail: Binding::unresolved((*tail, frame.environment.clone())),
+                    })));
+                }
+                Instruction::JumpUnlessMatches { pattern, target } => {
+                    let (mut selection, evaluated) = match frame.selection.take() {
+                        // the part of the value asked for has been evaluated
+                        Some(selection) => match stack.pop() {
+                            Some(Operand::Value(value)) => (selection, Some(value)),
+                            _ => unreachable!("Returned without a value."),
+                        },
+                        None => {
+                            let value = match stack.pop() {
+                                Some(Operand::Binding(binding)) => binding,
+                                // wrap the evaluated value so that variables can share it
+                                Some(Operand::Value(value)) => {
+                                    let binding = Binding::unresolved((expr, Bindings::new()));
+                                    binding.resolve_with(Ok(value));
+                                    binding
+                                }
+                                None => unreachable!("Matched against an empty stack."),
+                            };
+                            (Selection::new(value, [(pattern.clone(), ())]), None)
+                        }
+                    };
+                    match select(&mut selection, evaluated)? {
+                        Selected::Matched { variables, .. } => {
+                            frame.environment = frame.environment.with_bindings(variables);
+                        }
+                        Selected::NoMatch => {
+                            frame.pc = *target;
+                            stack.push(Operand::Binding(selection.value().clone()));
+                        }
+                        Selected::Evaluate(binding) => {
+                            let (expr, environment) = binding
+                                .unresolved_value()
+                                .expect("Binding was already evaluated.");
+                            frame.pc -= 1;
+                            frame.selection = Some(selection);
+                            let call_site = frame.call_site;
+                            self.enter(&mut frames, expr, environment, Some(binding), call_site);
+                        }
+                    }
+                }
+                Instruction::NoMatch { span } => {
+                    return Err(Error::MatchWithoutBaseCase { span: *span });
+                }
+                Instruction::Return => {
+                    let Some(Frame { update, .. }) = frames.pop() else {
+                        unreachable!();
+                    };
+                    if let Some(binding) = update {
+                        let Some(Operand::Value(value)) = stack.pop() else {
+                            unreachable!("Returned without a value.");
+                        };
+                        binding.resolve_with(Ok(value.clone()));
+                        stack.push(Operand::Value(value));
+                    }
+                    if frames.is_empty() {
+                        return match stack.pop() {
+                            Some(Operand::Value(value)) => Ok(value),
+                            _ => unreachable!("Returned without a value."),
+                        };
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pushes a new frame to evaluate the expression.
+    ///
+    /// If the current frame would return immediately afterwards, and does not
+    /// need to update a binding, it is replaced, so that tail calls run in
+    /// constant space.
+    fn enter(
+        &self,
+        frames: &mut Vec<Frame>,
+        expr: Expr,
+        environment: Bindings<Expr>,
+        update: Option<Binding<Expr>>,
+        call_site: Option<Span>,
+    ) {
+        if let Some(current) = frames.last() {
+            if current.update.is_none() && current.code.0[current.pc] == Instruction::Return {
+                frames.pop();
+            }
+        }
+        frames.push(Frame {
+            code: self.code_for(expr),
+            pc: 0,
+            environment,
+            update,
+            call_site,
+            selection: None,
+        });
+    }
+
+    /// Retrieves the compiled code for the expression, compiling it if
+    /// necessary.
+    fn code_for(&self, expr: Expr) -> Rc<Code> {
+        Rc::clone(
+            self.compiled
+                .borrow_mut()
+                .entry(expr)
+                .or_insert_with(|| Rc::new(compile(self.pool, expr))),
+        )
+    }
+
+    /// Suspends an expression in the given environment.
+    ///
+    /// If the expression is a variable, its binding is shared instead.
+    fn suspend(&self, expr: Expr, environment: &mut Bindings<Expr>) -> Binding<Expr> {
+        let Spanned { value, .. } = self.pool.read(expr);
+        if let Expression::Identifier(name) = value {
+            if let Some(binding) = environment.read(name) {
+                return binding.clone();
+            }
+        }
+        Binding::unresolved((expr, environment.clone()))
+    }
+
+    /// Evaluates a binding immediately; see [`boo_evaluation_lazy::force`].
+    fn force(&self, binding: &mut Binding<Expr>) -> EvaluatedBinding<Expr> {
+        boo_evaluation_lazy::force(binding, |(expr, environment)| {
+            self.run(expr, environment, None)
+        })
+    }
+
+    fn switch(&self, bindings: Bindings<Expr>) -> NativeEnvironment<'_, 'pool> {
+        NativeEnvironment {
+            machine: self,
+            bindings,
+        }
+    }
+}
+
+/// The environment in which a native function runs.
+struct NativeEnvironment<'machine, 'pool> {
+    machine: &'machine VirtualMachine<'pool>,
+    bindings: Bindings<Expr>,
+}
+
+impl<'machine, 'pool> NativeContext for NativeEnvironment<'machine, 'pool> {
+    fn lookup_value(&self, identifier: &Identifier) -> Result<Primitive> {
+        let result = match self.bindings.clone().read(identifier) {
+            Some(binding) => self.machine.force(binding)?,
+            None => {
+                return Err(Error::UnknownVariable {
+                    span: None,
+                    name: identifier.to_string(),
+                    suggestions: None,
+                })
+            }
+        };
+        match result {
+            CompletedEvaluation::Primitive(primitive) => Ok(primitive),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        }
+    }
+
+    fn apply(&self, function: &Identifier, argument: Primitive) -> Result<Primitive> {
+        let function = match self.bindings.clone().read(function) {
+            Some(binding) => self.machine.force(binding)?,
+            None => {
+                return Err(Error::UnknownVariable {
+                    span: None,
+                    name: function.to_string(),
+                    suggestions: None,
+                })
+            }
+        };
+        let (body, bindings) = function.apply_to(argument)?;
+        match self.machine.run(body, bindings, None)? {
+            CompletedEvaluation::Primitive(primitive) => Ok(primitive),
+            _ => Err(Error::InvalidPrimitive { span: None }),
+        }
+    }
+
+    fn integer_overflow(&self) -> IntegerOverflow {
+        self.machine.integer_overflow
+    }
+}
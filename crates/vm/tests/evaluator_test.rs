@@ -0,0 +1,97 @@
+use boo_core::builtins;
+use boo_core::error::Error;
+use boo_core::evaluation::*;
+use boo_core::primitive::Primitive;
+
+#[test]
+fn test_evaluation_gets_the_same_result_as_reducing_evaluation() {
+    let vm_evaluator = boo_conformance::prepared(boo_vm::new());
+    boo_conformance::check_against_reference(&vm_evaluator);
+}
+
+#[test]
+fn test_applying_functions_gets_the_same_result_as_reducing_evaluation() {
+    let vm_evaluator = boo_conformance::prepared(boo_vm::new());
+    boo_conformance::check_applications_against_reference(&vm_evaluator);
+}
+
+#[test]
+fn test_failing_gets_the_same_error_as_reducing_evaluation() {
+    let vm_evaluator = boo_conformance::prepared(boo_vm::new());
+    boo_conformance::check_failures_against_reference(&vm_evaluator);
+}
+
+#[test]
+fn test_integer_overflow_fails_in_the_same_way_as_reducing_evaluation() {
+    let vm_evaluator =
+        boo_conformance::prepared(boo_vm::new().with_integer_overflow(IntegerOverflow::Fail));
+    boo_conformance::check_integer_overflow_fails(&vm_evaluator);
+}
+
+#[test]
+fn test_natives_can_apply_functions() {
+    let vm_evaluator = boo_conformance::prepared(boo_vm::new());
+    boo_conformance::check_natives_can_apply_functions(&vm_evaluator);
+}
+
+#[test]
+fn test_lists_bound_identifiers() {
+    boo_conformance::check_bound_identifiers_are_listed(boo_vm::new());
+}
+
+#[test]
+fn test_evaluates_deeply_nested_expressions() {
+    boo_conformance::check_deeply_nested_expressions_in_a_small_stack(boo_vm::new);
+}
+
+#[test]
+fn test_evaluates_deeply_nested_matches() {
+    boo_conformance::check_deeply_nested_matches_in_a_small_stack(boo_vm::new);
+}
+
+#[test]
+fn test_tail_calls_run_in_constant_stack_space() {
+    check_in_a_small_stack(
+        "let rec loop = fn n -> match n { 0 -> 0; _ -> loop (n - 1) } in loop 10000",
+        Primitive::Integer(0.into()),
+    );
+}
+
+#[test]
+fn test_mutually_recursive_tail_calls_run_in_constant_stack_space() {
+    check_in_a_small_stack(
+        "let rec is_even = fn n -> match n { 0 -> true; _ -> is_odd (n - 1) } and is_odd = fn n -> match n { 0 -> false; _ -> is_even (n - 1) } in is_even 10000",
+        Primitive::Boolean(true),
+    );
+}
+
+#[test]
+fn test_tail_calls_can_pass_values_along() {
+    check_in_a_small_stack(
+        "let rec loop = fn n -> fn result -> match n { 0 -> result; _ -> loop (n - 1) result } in loop 10000 7",
+        Primitive::Integer(7.into()),
+    );
+}
+
+/// Evaluates the program on a thread with a stack far too small to hold one
+/// frame per call.
+fn check_in_a_small_stack(program: &'static str, expected: Primitive) {
+    let result = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || {
+            let mut context = boo_vm::new();
+            builtins::prepare(&mut context, builtins::IoCapabilities::default())?;
+            let evaluator = context.evaluator();
+            let expr = boo_parser::parse_expr(program)?.to_core()?;
+            let result = evaluator.evaluate(expr)?;
+            match result {
+                Evaluated::Primitive(primitive) => Ok(primitive),
+                _ => Err(Error::InvalidPrimitive { span: None }),
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(result, Ok(expected));
+}